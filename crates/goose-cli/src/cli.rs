@@ -2,12 +2,16 @@ use anyhow::Result;
 use clap::{Args, Parser, Subcommand};
 
 use goose::config::{Config, ExtensionConfig};
+use goose::offline_queue::global_offline_queue;
+use goose::recipe::cache::RecipeResultCache;
+use goose::recipe::{Clarifications, Recipe};
 
 use crate::commands::bench::agent_generator;
 use crate::commands::configure::handle_configure;
 use crate::commands::info::handle_info;
 use crate::commands::mcp::run_server;
 use crate::commands::project::{handle_project_default, handle_projects_interactive};
+use crate::commands::queue::{handle_queue_list, handle_queue_remove, handle_queue_run};
 use crate::commands::recipe::{handle_deeplink, handle_validate};
 // Import the new handlers from commands::schedule
 use crate::commands::schedule::{
@@ -18,7 +22,9 @@ use crate::commands::schedule::{
 use crate::commands::session::{handle_session_list, handle_session_remove};
 use crate::logging::setup_logging;
 use crate::recipes::extract_from_cli::extract_recipe_info_from_cli;
-use crate::recipes::recipe::{explain_recipe_with_parameters, load_recipe_content_as_template};
+use crate::recipes::recipe::{
+    explain_recipe_with_parameters, load_recipe_as_template, load_recipe_content_as_template,
+};
 use crate::session;
 use crate::session::{build_session, SessionBuilderConfig, SessionSettings};
 use goose_bench::bench_config::BenchRunConfig;
@@ -28,6 +34,7 @@ use goose_bench::runners::metric_aggregator::MetricAggregator;
 use goose_bench::runners::model_runner::ModelRunner;
 use std::io::Read;
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(author, version, display_name = "", about, long_about = None)]
@@ -173,6 +180,19 @@ enum SchedulerCommand {
     CronHelp {},
 }
 
+#[derive(Subcommand, Debug)]
+enum QueueCommand {
+    #[command(about = "List recipe runs queued while offline")]
+    List {},
+    #[command(about = "Run the queued recipe runs now if connectivity is back")]
+    Run {},
+    #[command(about = "Remove a queued run by ID without running it")]
+    Remove {
+        #[arg(long, help = "ID of the queued run to remove")]
+        id: String,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum BenchCommand {
     #[command(name = "init-config", about = "Create a new starter-config")]
@@ -546,6 +566,26 @@ enum Command {
             action = clap::ArgAction::Append
         )]
         additional_sub_recipes: Vec<String>,
+
+        /// Reuse a cached recipe result if one is younger than this many seconds
+        #[arg(
+            long = "cache-ttl",
+            value_name = "SECONDS",
+            help = "Reuse the structured result of an identical recipe run within this many seconds",
+            long_help = "Opt in to result caching for recipes that define a response schema. Runs with the same recipe version and parameters return the stored result if it is younger than the given number of seconds.",
+            requires = "recipe",
+            conflicts_with = "interactive"
+        )]
+        cache_ttl: Option<u64>,
+
+        /// Skip the recipe result cache lookup
+        #[arg(
+            long = "no-cache",
+            help = "Ignore any cached recipe result and run the recipe again",
+            long_help = "Bypass the recipe result cache lookup. The fresh result still replaces the cached entry when --cache-ttl is set.",
+            requires = "cache_ttl"
+        )]
+        no_cache: bool,
     },
 
     /// Recipe utilities for validation and deeplinking
@@ -562,6 +602,13 @@ enum Command {
         command: SchedulerCommand,
     },
 
+    /// Manage recipe runs queued while offline
+    #[command(about = "Manage recipe runs queued while offline")]
+    Queue {
+        #[command(subcommand)]
+        command: QueueCommand,
+    },
+
     /// Update the Goose CLI version
     #[command(about = "Update the goose CLI version")]
     Update {
@@ -624,6 +671,8 @@ pub struct InputConfig {
     pub contents: Option<String>,
     pub extensions_override: Option<Vec<ExtensionConfig>>,
     pub additional_system_prompt: Option<String>,
+    pub require_citations: bool,
+    pub clarifications: Option<Clarifications>,
 }
 
 pub async fn cli() -> Result<()> {
@@ -708,6 +757,8 @@ pub async fn cli() -> Result<()> {
                         quiet: false,
                         sub_recipes: None,
                         final_output_response: None,
+                        require_citations: false,
+                        clarifications: None,
                     })
                     .await;
                     setup_logging(
@@ -761,7 +812,10 @@ pub async fn cli() -> Result<()> {
             scheduled_job_id,
             quiet,
             additional_sub_recipes,
+            cache_ttl,
+            no_cache,
         }) => {
+            let mut recipe_cache = None;
             let (input_config, session_settings, sub_recipes, final_output_response) = match (
                 instructions,
                 input_text,
@@ -778,6 +832,8 @@ pub async fn cli() -> Result<()> {
                             contents: Some(input),
                             extensions_override: None,
                             additional_system_prompt: system,
+                            require_citations: false,
+                            clarifications: None,
                         },
                         None,
                         None,
@@ -797,6 +853,8 @@ pub async fn cli() -> Result<()> {
                             contents: Some(contents),
                             extensions_override: None,
                             additional_system_prompt: None,
+                            require_citations: false,
+                            clarifications: None,
                         },
                         None,
                         None,
@@ -808,6 +866,8 @@ pub async fn cli() -> Result<()> {
                         contents: Some(text),
                         extensions_override: None,
                         additional_system_prompt: system,
+                        require_citations: false,
+                        clarifications: None,
                     },
                     None,
                     None,
@@ -827,6 +887,42 @@ pub async fn cli() -> Result<()> {
                         println!("{}", recipe);
                         return Ok(());
                     }
+                    if !interactive
+                        && goose::offline_queue::is_enabled()
+                        && !global_offline_queue().is_online().await
+                    {
+                        let recipe = load_recipe_as_template(&recipe_name, params)?;
+                        let run = global_offline_queue()
+                            .enqueue(&recipe_name, None, recipe, None)
+                            .await?;
+                        eprintln!(
+                            "No network connection. Queued '{}' as run {}; run it with `goose queue run` once connectivity returns. A running goose scheduler, such as the desktop app's, also runs it then.",
+                            recipe_name, run.id
+                        );
+                        return Ok(());
+                    }
+                    if let Some(ttl) = cache_ttl {
+                        let recipe = Recipe::from_content(&load_recipe_content_as_template(
+                            &recipe_name,
+                            params.clone(),
+                        )?)?;
+                        let cache = RecipeResultCache::new();
+                        let key = RecipeResultCache::cache_key(&recipe, &params);
+                        if !no_cache {
+                            if let Some(cached) = cache.get(&key, Duration::from_secs(ttl)).await {
+                                if !quiet {
+                                    eprintln!(
+                                        "Using cached result for '{}' from {}",
+                                        cached.recipe_title,
+                                        cached.created_at.to_rfc3339()
+                                    );
+                                }
+                                println!("{}", cached.output);
+                                return Ok(());
+                            }
+                        }
+                        recipe_cache = Some((cache, key, recipe));
+                    }
                     extract_recipe_info_from_cli(recipe_name, params, additional_sub_recipes)?
                 }
                 (None, None, None) => {
@@ -853,6 +949,8 @@ pub async fn cli() -> Result<()> {
                 quiet,
                 sub_recipes,
                 final_output_response,
+                require_citations: input_config.require_citations,
+                clarifications: input_config.clarifications,
             })
             .await;
 
@@ -869,6 +967,13 @@ pub async fn cli() -> Result<()> {
                 let _ = session.interactive(input_config.contents).await;
             } else if let Some(contents) = input_config.contents {
                 let _ = session.headless(contents).await;
+                if let Some((cache, key, recipe)) = recipe_cache {
+                    if let Some(output) = session.final_output().await {
+                        if let Err(e) = cache.put(&key, &recipe, output).await {
+                            tracing::warn!("Failed to cache recipe result: {}", e);
+                        }
+                    }
+                }
             } else {
                 eprintln!("Error: no text provided for prompt in headless mode");
                 std::process::exit(1);
@@ -911,6 +1016,14 @@ pub async fn cli() -> Result<()> {
             }
             return Ok(());
         }
+        Some(Command::Queue { command }) => {
+            match command {
+                QueueCommand::List {} => handle_queue_list().await?,
+                QueueCommand::Run {} => handle_queue_run().await?,
+                QueueCommand::Remove { id } => handle_queue_remove(id).await?,
+            }
+            return Ok(());
+        }
         Some(Command::Update {
             canary,
             reconfigure,
@@ -978,6 +1091,8 @@ pub async fn cli() -> Result<()> {
                     quiet: false,
                     sub_recipes: None,
                     final_output_response: None,
+                    require_citations: false,
+                    clarifications: None,
                 })
                 .await;
                 setup_logging(
@@ -49,6 +49,8 @@ pub async fn agent_generator(
         quiet: false,
         sub_recipes: None,
         final_output_response: None,
+        require_citations: false,
+        clarifications: None,
     })
     .await;
 
@@ -14,7 +14,7 @@ use goose::config::{
     PermissionManager,
 };
 use goose::message::Message;
-use goose::providers::{create, providers};
+use goose::providers::{create, providers, validate_model};
 use mcp_core::tool::ToolAnnotations;
 use mcp_core::Tool;
 use serde_json::{json, Value};
@@ -32,7 +32,9 @@ fn get_display_name(extension_id: &str) -> String {
         "developer" => "Developer Tools".to_string(),
         "computercontroller" => "Computer Controller".to_string(),
         "googledrive" => "Google Drive".to_string(),
+        "jupyter" => "Jupyter".to_string(),
         "memory" => "Memory".to_string(),
+        "sql" => "SQL".to_string(),
         "tutorial" => "Tutorial".to_string(),
         "jetbrains" => "JetBrains".to_string(),
         // Add other extensions as needed
@@ -377,6 +379,15 @@ pub async fn configure_provider_dialog() -> Result<bool, Box<dyn Error>> {
         .with_toolshim(toolshim_enabled)
         .with_toolshim_model(std::env::var("GOOSE_TOOLSHIM_OLLAMA_MODEL").ok());
 
+    if let Err(e) = validate_model(provider_name, &model_config).await {
+        spin.stop(style(e.to_string()).red());
+        cliclack::outro(
+            style("Failed to configure provider: the model is not available.")
+                .on_red()
+                .white(),
+        )?;
+        return Ok(false);
+    }
     let provider = create(provider_name, model_config)?;
 
     let messages =
@@ -528,11 +539,21 @@ pub fn configure_extensions_dialog() -> Result<(), Box<dyn Error>> {
                     "Search and read content from google drive - additional config required",
                 )
                 .item("jetbrains", "JetBrains", "Connect to jetbrains IDEs")
+                .item(
+                    "jupyter",
+                    "Jupyter",
+                    "Stateful Python execution in Jupyter kernels - needs jupyter_client and ipykernel",
+                )
                 .item(
                     "memory",
                     "Memory",
                     "Tools to save and retrieve durable memories",
                 )
+                .item(
+                    "sql",
+                    "SQL",
+                    "Read-only queries against Postgres, MySQL and SQLite databases - set GOOSE_SQL_CONNECTIONS",
+                )
                 .item(
                     "tutorial",
                     "Tutorial",
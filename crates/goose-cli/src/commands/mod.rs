@@ -3,6 +3,7 @@ pub mod configure;
 pub mod info;
 pub mod mcp;
 pub mod project;
+pub mod queue;
 pub mod recipe;
 pub mod schedule;
 pub mod session;
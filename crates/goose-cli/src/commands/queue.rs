@@ -0,0 +1,56 @@
+use anyhow::{bail, Result};
+use goose::offline_queue::global_offline_queue;
+use goose::scheduler::QueuedJobExecutor;
+
+pub async fn handle_queue_list() -> Result<()> {
+    let runs = global_offline_queue().pending().await?;
+    if runs.is_empty() {
+        println!("No queued runs.");
+        return Ok(());
+    }
+    println!("Queued runs (oldest first):");
+    for run in runs {
+        println!(
+            "- ID: {}\n  Recipe: {} ({})\n  Schedule: {}\n  Queued At: {}\n  Attempts: {}",
+            run.id,
+            run.recipe.title,
+            run.source,
+            run.schedule_id.as_deref().unwrap_or("-"),
+            run.queued_at.to_rfc3339(),
+            run.attempts
+        );
+        if let Some(error) = run.last_error {
+            println!("  Last Error: {}", error);
+        }
+    }
+    Ok(())
+}
+
+pub async fn handle_queue_run() -> Result<()> {
+    let queue = global_offline_queue();
+    let pending = queue.pending().await?.len();
+    if pending == 0 {
+        println!("No queued runs.");
+        return Ok(());
+    }
+    if !queue.is_online().await {
+        bail!("Still offline; {} run(s) remain queued.", pending);
+    }
+    let completed = queue.drain(&QueuedJobExecutor::default()).await?;
+    let remaining = queue.pending().await?.len();
+    println!(
+        "Ran {} queued run(s), {} remain queued.",
+        completed, remaining
+    );
+    Ok(())
+}
+
+pub async fn handle_queue_remove(id: String) -> Result<()> {
+    let queue = global_offline_queue();
+    if !queue.pending().await?.iter().any(|run| run.id == id) {
+        bail!("Error: Queued run with ID '{}' not found.", id);
+    }
+    queue.remove(&id).await?;
+    println!("Removed queued run '{}'.", id);
+    Ok(())
+}
@@ -619,6 +619,9 @@ async fn process_message_streaming(
                         // For now, we'll just log them
                         tracing::info!("Received MCP notification in web interface");
                     }
+                    Ok(AgentEvent::Usage(_)) => {
+                        // Running usage isn't shown in the web interface
+                    }
                     Ok(AgentEvent::ModelChange { model, mode }) => {
                         // Log model change
                         tracing::info!("Model changed to {} in {} mode", model, mode);
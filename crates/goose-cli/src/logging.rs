@@ -11,7 +11,7 @@ use tracing_subscriber::{
     Registry,
 };
 
-use goose::tracing::langfuse_layer;
+use goose::tracing::{alerting_layer, langfuse_layer};
 use goose_bench::bench_session::BenchAgentError;
 use goose_bench::error_capture::ErrorCaptureLayer;
 
@@ -145,6 +145,11 @@ fn setup_logging_internal(
                 layers.push(langfuse.with_filter(LevelFilter::DEBUG).boxed());
             }
 
+            // Forward fleet problems to PagerDuty/OpsGenie if configured
+            if let Some(alerting) = alerting_layer::create_alerting_layer() {
+                layers.push(alerting.boxed());
+            }
+
             // Build the subscriber
             let subscriber = Registry::default().with(layers);
 
@@ -45,11 +45,14 @@ pub fn extract_recipe_info_from_cli(
             }
         }
     }
+    let additional_system_prompt = recipe.instructions_with_knowledge();
     Ok((
         InputConfig {
             contents: recipe.prompt,
             extensions_override: recipe.extensions,
-            additional_system_prompt: recipe.instructions,
+            additional_system_prompt,
+            require_citations: recipe.require_citations.unwrap_or(false),
+            clarifications: recipe.clarifications,
         },
         recipe.settings.map(|s| SessionSettings {
             goose_provider: s.goose_provider,
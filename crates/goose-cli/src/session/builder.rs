@@ -3,7 +3,7 @@ use goose::agents::extension::ExtensionError;
 use goose::agents::Agent;
 use goose::config::{Config, ExtensionConfig, ExtensionConfigManager};
 use goose::providers::create;
-use goose::recipe::{Response, SubRecipe};
+use goose::recipe::{Clarifications, Response, SubRecipe};
 use goose::session;
 use goose::session::Identifier;
 use mcp_client::transport::Error as McpClientError;
@@ -53,6 +53,10 @@ pub struct SessionBuilderConfig {
     pub sub_recipes: Option<Vec<SubRecipe>>,
     /// Final output expected response
     pub final_output_response: Option<Response>,
+    /// Whether the final answer must cite the tool results it relies on
+    pub require_citations: bool,
+    /// Whether the agent states its assumptions and may ask clarifying questions
+    pub clarifications: Option<Clarifications>,
 }
 
 /// Offers to help debug an extension failure by creating a minimal debugging session
@@ -195,6 +199,14 @@ pub async fn build_session(session_config: SessionBuilderConfig) -> Session {
         agent.add_final_output_tool(final_output_response).await;
     }
 
+    if session_config.require_citations {
+        agent.set_require_citations(true).await;
+    }
+
+    if let Some(clarifications) = session_config.clarifications {
+        agent.set_clarifications(clarifications).await;
+    }
+
     let new_provider = match create(&provider_name, model_config) {
         Ok(provider) => provider,
         Err(e) => {
@@ -531,6 +543,8 @@ mod tests {
             quiet: false,
             sub_recipes: None,
             final_output_response: None,
+            require_citations: false,
+            clarifications: None,
         };
 
         assert_eq!(config.extensions.len(), 1);
@@ -563,6 +577,7 @@ mod tests {
         assert!(!config.interactive);
         assert!(!config.quiet);
         assert!(config.final_output_response.is_none());
+        assert!(!config.require_citations);
     }
 
     #[tokio::test]
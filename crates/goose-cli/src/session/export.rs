@@ -345,6 +345,15 @@ pub fn message_to_markdown(message: &Message, export_all_content: bool) -> Strin
                 md.push_str("**Thinking:**\n");
                 md.push_str("> *Thinking was redacted*\n\n");
             }
+            MessageContent::Assumption(assumption) => {
+                md.push_str(&format!("**Assumption:** {}\n\n", assumption.text));
+            }
+            MessageContent::Question(question) => {
+                md.push_str(&format!("**Question:** {}\n\n", question.question));
+                if let Some(answer) = &question.answer {
+                    md.push_str(&format!("**Answer:** {}\n\n", answer));
+                }
+            }
             _ => {
                 md.push_str(
                     "`WARNING: Message content type could not be rendered to Markdown`\n\n",
@@ -22,7 +22,7 @@ use etcetera::{choose_app_strategy, AppStrategy};
 use goose::agents::extension::{Envs, ExtensionConfig};
 use goose::agents::{Agent, SessionConfig};
 use goose::config::Config;
-use goose::message::{Message, MessageContent};
+use goose::message::{FinishReason, Message, MessageContent};
 use goose::session;
 use input::InputResult;
 use mcp_core::handler::ToolError;
@@ -707,6 +707,10 @@ impl Session {
 
         match planner_response_type {
             PlannerResponseType::Plan => {
+                // Share the plan with extensions and subagents as goose://session/<id>/plan
+                self.agent
+                    .set_session_plan(Some(plan_response.as_concat_text()))
+                    .await;
                 println!();
                 let should_act = match cliclack::confirm(
                     "Do you want to clear message history & act on this plan?",
@@ -773,6 +777,11 @@ impl Session {
         self.process_message(message).await
     }
 
+    /// The structured result produced by the recipe's final output tool, if any
+    pub async fn final_output(&self) -> Option<String> {
+        self.agent.final_output().await
+    }
+
     async fn process_agent_response(&mut self, interactive: bool) -> Result<()> {
         let session_config = self.session_file.as_ref().map(|s| {
             let session_id = session::Identifier::Path(s.clone());
@@ -1061,6 +1070,14 @@ impl Session {
                                 }
                             }
                         }
+                        Some(Ok(AgentEvent::Usage(usage))) => {
+                            if self.debug {
+                                eprintln!(
+                                    "Reply usage so far: {} tokens, ~${:.4}",
+                                    usage.total_tokens, usage.cost_usd
+                                );
+                            }
+                        }
                         Some(Ok(AgentEvent::ModelChange { model, mode })) => {
                             // Log model change if in debug mode
                             if self.debug {
@@ -1154,7 +1171,11 @@ impl Session {
                 "The existing call to {} was interrupted. How would you like to proceed?",
                 last_tool_name
             );
-            self.messages.push(Message::assistant().with_text(&prompt));
+            let mut message = Message::assistant().with_text(&prompt);
+            if interrupt {
+                message = message.with_finish_reason(FinishReason::Cancelled);
+            }
+            self.messages.push(message);
 
             // No need for description update here
             if let Some(session_file) = &self.session_file {
@@ -1176,7 +1197,11 @@ impl Session {
                         Some(MessageContent::ToolResponse(_)) => {
                             // Interruption occurred after a tool had completed but not assistant reply
                             let prompt = "The tool calling loop was interrupted. How would you like to proceed?";
-                            self.messages.push(Message::assistant().with_text(prompt));
+                            self.messages.push(
+                                Message::assistant()
+                                    .with_text(prompt)
+                                    .with_finish_reason(FinishReason::Cancelled),
+                            );
 
                             // No need for description update here
                             if let Some(session_file) = &self.session_file {
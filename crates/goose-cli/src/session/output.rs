@@ -159,6 +159,19 @@ pub fn render_message(message: &Message, debug: bool) {
                 println!("\n{}", style("Thinking:").dim().italic());
                 print_markdown("Thinking was redacted", theme);
             }
+            MessageContent::Assumption(assumption) => {
+                println!("{} {}", style("Assumption:").yellow().dim(), assumption.text);
+            }
+            MessageContent::Question(question) => match &question.answer {
+                Some(answer) => println!(
+                    "{} {}\n{} {}",
+                    style("Question:").cyan(),
+                    question.question,
+                    style("Answer:").cyan().dim(),
+                    answer
+                ),
+                None => println!("{} {}", style("Question:").cyan(), question.question),
+            },
             _ => {
                 println!("WARNING: Message content type could not be rendered");
             }
@@ -266,8 +266,8 @@ pub unsafe extern "C" fn goose_agent_send_message(
                 Ok(AgentEvent::McpNotification(_)) => {
                     // TODO: Handle MCP notifications.
                 }
-                Ok(AgentEvent::ModelChange { .. }) => {
-                    // Model change events are informational, just continue
+                Ok(AgentEvent::ModelChange { .. }) | Ok(AgentEvent::Usage(_)) => {
+                    // Model change and usage events are informational, just continue
                 }
 
                 Err(e) => {
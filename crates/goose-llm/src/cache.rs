@@ -0,0 +1,243 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+};
+
+use once_cell::sync::Lazy;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    message::Message, providers::ProviderCompleteResponse, types::core::Tool, ModelConfig,
+};
+
+/// Caches provider completions by a fingerprint of the request, so identical requests such as
+/// deterministic recipe re-runs or repeated evals don't pay for the same completion twice.
+///
+/// The most recently used entries are kept in memory. When a directory is given, entries are
+/// also written there as `<fingerprint>.json`, so they survive restarts.
+pub struct CompletionCache {
+    capacity: usize,
+    dir: Option<PathBuf>,
+    entries: Mutex<Lru>,
+}
+
+#[derive(Default)]
+struct Lru {
+    responses: HashMap<String, ProviderCompleteResponse>,
+    /// Fingerprints from least to most recently used
+    order: VecDeque<String>,
+}
+
+impl Lru {
+    fn touch(&mut self, fingerprint: &str) {
+        if let Some(position) = self.order.iter().position(|f| f == fingerprint) {
+            self.order.remove(position);
+        }
+        self.order.push_back(fingerprint.to_string());
+    }
+}
+
+impl CompletionCache {
+    pub fn new(capacity: usize, dir: Option<PathBuf>) -> Self {
+        Self {
+            capacity,
+            dir,
+            entries: Mutex::new(Lru::default()),
+        }
+    }
+
+    /// Hash everything that affects the completion. Message timestamps and response metadata
+    /// are left out, since they differ between otherwise identical requests.
+    pub fn fingerprint(
+        provider_name: &str,
+        model_config: &ModelConfig,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> String {
+        let messages: Vec<Value> = messages
+            .iter()
+            .map(|message| {
+                let mut value = serde_json::to_value(message).unwrap_or_default();
+                if let Some(object) = value.as_object_mut() {
+                    object.remove("created");
+                    object.remove("metadata");
+                }
+                value
+            })
+            .collect();
+        let request = json!({
+            "provider": provider_name,
+            "model": model_config,
+            "system": system,
+            "messages": messages,
+            "tools": tools,
+        });
+        Sha256::digest(request.to_string().as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn get(&self, fingerprint: &str) -> Option<ProviderCompleteResponse> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(response) = entries.responses.get(fingerprint).cloned() {
+            entries.touch(fingerprint);
+            return Some(response);
+        }
+        drop(entries);
+
+        let response = self.read_from_disk(fingerprint)?;
+        self.remember(fingerprint, response.clone());
+        Some(response)
+    }
+
+    pub fn insert(&self, fingerprint: &str, response: ProviderCompleteResponse) {
+        self.write_to_disk(fingerprint, &response);
+        self.remember(fingerprint, response);
+    }
+
+    /// Drop every entry, including those on disk
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        *entries = Lru::default();
+        if let Some(dir) = &self.dir {
+            if let Ok(files) = std::fs::read_dir(dir) {
+                for file in files.flatten() {
+                    let path = file.path();
+                    if path.extension().is_some_and(|ext| ext == "json") {
+                        let _ = std::fs::remove_file(path);
+                    }
+                }
+            }
+        }
+    }
+
+    fn remember(&self, fingerprint: &str, response: ProviderCompleteResponse) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.responses.insert(fingerprint.to_string(), response);
+        entries.touch(fingerprint);
+        while entries.order.len() > self.capacity {
+            if let Some(evicted) = entries.order.pop_front() {
+                entries.responses.remove(&evicted);
+            }
+        }
+    }
+
+    fn path(&self, fingerprint: &str) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.json", fingerprint)))
+    }
+
+    fn read_from_disk(&self, fingerprint: &str) -> Option<ProviderCompleteResponse> {
+        let contents = std::fs::read_to_string(self.path(fingerprint)?).ok()?;
+        serde_json::from_str(&contents)
+            .map_err(|e| tracing::warn!("Ignoring unreadable cached completion: {}", e))
+            .ok()
+    }
+
+    fn write_to_disk(&self, fingerprint: &str, response: &ProviderCompleteResponse) {
+        let Some(path) = self.path(fingerprint) else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| {
+                let contents = serde_json::to_string(response)?;
+                std::fs::write(&path, contents)
+            });
+        if let Err(e) = result {
+            tracing::warn!("Failed to write cached completion to {:?}: {}", path, e);
+        }
+    }
+}
+
+static CACHE: Lazy<RwLock<Option<Arc<CompletionCache>>>> = Lazy::new(|| RwLock::new(None));
+
+/// Cache completions returned by [`crate::completion`], keeping up to `capacity` in memory and,
+/// when `dir` is set, every one on disk. Replaces any cache configured before.
+#[uniffi::export(default(dir = None))]
+pub fn enable_completion_cache(capacity: u32, dir: Option<String>) {
+    *CACHE.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(CompletionCache::new(
+        capacity as usize,
+        dir.map(PathBuf::from),
+    )));
+}
+
+/// Stop caching completions. Entries already on disk are kept.
+#[uniffi::export]
+pub fn disable_completion_cache() {
+    *CACHE.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Drop every cached completion, including those on disk
+#[uniffi::export]
+pub fn clear_completion_cache() {
+    if let Some(cache) = completion_cache() {
+        cache.clear();
+    }
+}
+
+pub(crate) fn completion_cache() -> Option<Arc<CompletionCache>> {
+    CACHE.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::Usage;
+
+    fn response(text: &str) -> ProviderCompleteResponse {
+        ProviderCompleteResponse::new(
+            Message::assistant().with_text(text),
+            "gpt-4o".to_string(),
+            Usage::default(),
+        )
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_timestamps() {
+        let model = ModelConfig::new("gpt-4o".to_string());
+        let mut first = Message::user().with_text("hello");
+        first.created = 1;
+        let mut second = first.clone();
+        second.created = 2;
+
+        assert_eq!(
+            CompletionCache::fingerprint("openai", &model, "system", &[first.clone()], &[]),
+            CompletionCache::fingerprint("openai", &model, "system", &[second], &[])
+        );
+        assert_ne!(
+            CompletionCache::fingerprint("openai", &model, "system", &[first.clone()], &[]),
+            CompletionCache::fingerprint("openai", &model, "other system", &[first], &[])
+        );
+    }
+
+    #[test]
+    fn test_lru_eviction_and_disk_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = CompletionCache::new(1, Some(dir.path().to_path_buf()));
+        cache.insert("a", response("first"));
+        cache.insert("b", response("second"));
+        assert!(!cache.entries.lock().unwrap().responses.contains_key("a"));
+
+        // Evicted from memory, but still on disk
+        let restored = cache.get("a").unwrap();
+        assert_eq!(
+            restored.message.content,
+            Message::assistant().with_text("first").content
+        );
+
+        let reopened = CompletionCache::new(8, Some(dir.path().to_path_buf()));
+        assert_eq!(reopened.get("b").unwrap().model, "gpt-4o");
+        reopened.clear();
+        assert!(reopened.get("b").is_none());
+    }
+}
@@ -5,6 +5,7 @@ use chrono::Utc;
 use serde_json::Value;
 
 use crate::{
+    cache::{completion_cache, CompletionCache},
     message::{Message, MessageContent},
     prompt_template,
     providers::create,
@@ -43,23 +44,57 @@ pub async fn completion(req: CompletionRequest) -> Result<CompletionResponse, Co
     )?;
     let tools = collect_prefixed_tools(&req.extensions);
 
-    // Call the LLM provider
+    // Call the LLM provider, unless an identical request has been answered before
+    let cache = completion_cache().map(|cache| {
+        let fingerprint = CompletionCache::fingerprint(
+            &req.provider_name,
+            &req.model_config,
+            &system_prompt,
+            &req.messages,
+            &tools,
+        );
+        (cache, fingerprint)
+    });
     let start_provider = Instant::now();
-    let mut response = provider
-        .complete(&system_prompt, &req.messages, &tools)
-        .await?;
+    let cached = cache
+        .as_ref()
+        .and_then(|(cache, fingerprint)| cache.get(fingerprint));
+    let mut response = match cached {
+        Some(mut response) => {
+            response.message.created = Utc::now().timestamp_millis();
+            response
+        }
+        None => {
+            let response = provider
+                .complete(&system_prompt, &req.messages, &tools)
+                .await?;
+            if let Some((cache, fingerprint)) = &cache {
+                cache.insert(fingerprint, response.clone());
+            }
+            response
+        }
+    };
     let provider_elapsed_sec = start_provider.elapsed().as_secs_f32();
     let usage_tokens = response.usage.total_tokens;
 
     let tool_configs = collect_prefixed_tool_configs(&req.extensions);
     update_needs_approval_for_tool_calls(&mut response.message, &tool_configs)?;
 
+    // Keep the metadata on the message too, so it is persisted with the transcript
+    let metadata = response.metadata;
+    let message = if metadata.is_empty() {
+        response.message
+    } else {
+        response.message.with_metadata(metadata.clone())
+    };
+
     Ok(CompletionResponse::new(
-        response.message,
+        message,
         response.model,
         response.usage,
         calculate_runtime_metrics(start_total, provider_elapsed_sec, usage_tokens),
-    ))
+    )
+    .with_metadata(metadata))
 }
 
 /// Render the global `system.md` template with the provided context.
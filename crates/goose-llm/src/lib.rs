@@ -1,5 +1,6 @@
 uniffi::setup_scaffolding!();
 
+mod cache;
 mod completion;
 pub mod extractors;
 pub mod message;
@@ -9,6 +10,7 @@ pub mod providers;
 mod structured_outputs;
 pub mod types;
 
+pub use cache::{clear_completion_cache, disable_completion_cache, enable_completion_cache};
 pub use completion::completion;
 pub use message::Message;
 pub use model::ModelConfig;
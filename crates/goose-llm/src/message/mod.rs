@@ -20,7 +20,7 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-use crate::types::core::Role;
+use crate::{providers::ResponseMetadata, types::core::Role};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, uniffi::Record)]
 /// A message to or from an LLM
@@ -29,6 +29,9 @@ pub struct Message {
     pub role: Role,
     pub created: i64,
     pub content: Contents,
+    /// How the provider served this message, recorded on assistant responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<ResponseMetadata>,
 }
 
 impl Message {
@@ -37,6 +40,7 @@ impl Message {
             role,
             created: Utc::now().timestamp_millis(),
             content: Contents::default(),
+            metadata: None,
         }
     }
 
@@ -56,6 +60,12 @@ impl Message {
         self
     }
 
+    /// Record how the provider served this message
+    pub fn with_metadata(mut self, metadata: ResponseMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
     /// Add text content to the message
     pub fn with_text<S: Into<String>>(self, text: S) -> Self {
         self.with_content(MessageContent::text(text))
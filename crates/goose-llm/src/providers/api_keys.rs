@@ -0,0 +1,274 @@
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use super::errors::ProviderError;
+
+/// How long a key that hit a rate limit is skipped while other keys are available
+pub const DEFAULT_RATE_LIMIT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// How requests are spread across the configured API keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeySelection {
+    /// Take each key in turn
+    #[default]
+    RoundRobin,
+    /// Take the key with the fewest requests in flight
+    LeastLoaded,
+}
+
+/// Split a comma-separated list of keys from an environment variable
+pub fn parse_key_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[derive(Debug)]
+struct ApiKeyState {
+    key: String,
+    in_flight: AtomicUsize,
+    requests: AtomicU64,
+    rate_limited: AtomicU64,
+    limited_until: Mutex<Option<Instant>>,
+}
+
+impl ApiKeyState {
+    fn limited_for(&self) -> Option<Duration> {
+        let limited_until = *self.limited_until.lock().unwrap_or_else(|e| e.into_inner());
+        limited_until
+            .map(|until| until.saturating_duration_since(Instant::now()))
+            .filter(|remaining| !remaining.is_zero())
+    }
+}
+
+/// Usage of one key, with the key itself shortened to its last characters
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ApiKeyStats {
+    pub key_hint: String,
+    pub in_flight: usize,
+    pub requests: u64,
+    pub rate_limited: u64,
+    /// Time left before the key is used again after hitting a rate limit
+    pub limited_for: Option<Duration>,
+}
+
+/// The API keys of one provider, handed out per request so parallel workloads
+/// spread across them and a key that hit its rate limit is rested
+#[derive(Debug)]
+pub struct ApiKeyPool {
+    keys: Vec<ApiKeyState>,
+    selection: KeySelection,
+    next: AtomicUsize,
+    rate_limit_cooldown: Duration,
+}
+
+impl ApiKeyPool {
+    /// Create a pool over the given keys, ignoring duplicates. An empty list still yields a
+    /// single empty key so that providers behave as before when no key is configured.
+    pub fn new(keys: impl IntoIterator<Item = String>, selection: KeySelection) -> Self {
+        let mut unique: Vec<String> = Vec::new();
+        for key in keys {
+            if !key.is_empty() && !unique.contains(&key) {
+                unique.push(key);
+            }
+        }
+        if unique.is_empty() {
+            unique.push(String::new());
+        }
+
+        Self {
+            keys: unique
+                .into_iter()
+                .map(|key| ApiKeyState {
+                    key,
+                    in_flight: AtomicUsize::new(0),
+                    requests: AtomicU64::new(0),
+                    rate_limited: AtomicU64::new(0),
+                    limited_until: Mutex::new(None),
+                })
+                .collect(),
+            selection,
+            next: AtomicUsize::new(0),
+            rate_limit_cooldown: DEFAULT_RATE_LIMIT_COOLDOWN,
+        }
+    }
+
+    pub fn with_rate_limit_cooldown(mut self, cooldown: Duration) -> Self {
+        self.rate_limit_cooldown = cooldown;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Whether any key is not resting after a rate limit
+    pub fn has_available(&self) -> bool {
+        self.keys.iter().any(|key| key.limited_for().is_none())
+    }
+
+    /// Pick a key for the next request. When every key is resting, the one that
+    /// becomes available soonest is used rather than failing outright.
+    pub fn acquire(&self) -> ApiKeyLease<'_> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let order = (0..self.keys.len()).map(|offset| (start + offset) % self.keys.len());
+        let available: Vec<usize> = order
+            .clone()
+            .filter(|index| self.keys[*index].limited_for().is_none())
+            .collect();
+
+        let index = match (available.first(), self.selection) {
+            (None, _) => order
+                .min_by_key(|index| self.keys[*index].limited_for())
+                .unwrap_or_default(),
+            (Some(first), KeySelection::RoundRobin) => *first,
+            (Some(first), KeySelection::LeastLoaded) => available
+                .iter()
+                .copied()
+                .min_by_key(|index| self.keys[*index].in_flight.load(Ordering::SeqCst))
+                .unwrap_or(*first),
+        };
+
+        let key = &self.keys[index];
+        key.in_flight.fetch_add(1, Ordering::SeqCst);
+        key.requests.fetch_add(1, Ordering::Relaxed);
+        ApiKeyLease { pool: self, index }
+    }
+
+    pub fn stats(&self) -> Vec<ApiKeyStats> {
+        self.keys
+            .iter()
+            .map(|key| {
+                let chars: Vec<char> = key.key.chars().collect();
+                let hint: String = chars[chars.len().saturating_sub(4)..].iter().collect();
+                ApiKeyStats {
+                    key_hint: format!("...{}", hint),
+                    in_flight: key.in_flight.load(Ordering::SeqCst),
+                    requests: key.requests.load(Ordering::Relaxed),
+                    rate_limited: key.rate_limited.load(Ordering::Relaxed),
+                    limited_for: key.limited_for(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// A key in use by one request; released when dropped
+pub struct ApiKeyLease<'a> {
+    pool: &'a ApiKeyPool,
+    index: usize,
+}
+
+impl ApiKeyLease<'_> {
+    pub fn key(&self) -> &str {
+        &self.pool.keys[self.index].key
+    }
+
+    /// Rest the key if the request made with it was rate limited, for as long as the provider
+    /// asked or the pool's cool-down otherwise
+    pub fn record<T>(&self, result: &Result<T, ProviderError>) {
+        if let Err(error @ ProviderError::RateLimitExceeded { .. }) = result {
+            let key = &self.pool.keys[self.index];
+            let rest_for = error.retry_after().unwrap_or(self.pool.rate_limit_cooldown);
+            key.rate_limited.fetch_add(1, Ordering::Relaxed);
+            *key.limited_until.lock().unwrap_or_else(|e| e.into_inner()) =
+                Some(Instant::now() + rest_for);
+            tracing::debug!(
+                "API key {} hit a rate limit, resting it for {:?}",
+                self.index,
+                rest_for
+            );
+        }
+    }
+}
+
+impl Drop for ApiKeyLease<'_> {
+    fn drop(&mut self) {
+        self.pool.keys[self.index]
+            .in_flight
+            .fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::errors::ErrorDetails;
+
+    fn pool(selection: KeySelection) -> ApiKeyPool {
+        ApiKeyPool::new(
+            ["key-a", "key-b", "key-a", "key-c"].map(String::from),
+            selection,
+        )
+    }
+
+    #[test]
+    fn test_round_robin_skips_rate_limited_keys() {
+        let keys = pool(KeySelection::RoundRobin);
+        assert_eq!(keys.len(), 3);
+
+        let picked: Vec<String> = (0..3).map(|_| keys.acquire().key().to_string()).collect();
+        assert_eq!(picked, vec!["key-a", "key-b", "key-c"]);
+
+        let lease = keys.acquire();
+        assert_eq!(lease.key(), "key-a");
+        lease.record::<()>(&Err(ProviderError::rate_limited("slow down")));
+        drop(lease);
+
+        let picked: Vec<String> = (0..3).map(|_| keys.acquire().key().to_string()).collect();
+        assert_eq!(picked, vec!["key-b", "key-c", "key-b"]);
+        assert_eq!(keys.stats()[0].rate_limited, 1);
+        assert!(keys.stats()[0].limited_for.is_some());
+    }
+
+    #[test]
+    fn test_least_loaded_prefers_idle_keys() {
+        let keys = pool(KeySelection::LeastLoaded);
+        let first = keys.acquire();
+        let second = keys.acquire();
+        assert_ne!(first.key(), second.key());
+        drop(first);
+
+        let third = keys.acquire();
+        assert_ne!(third.key(), second.key());
+        assert_eq!(keys.stats().iter().map(|s| s.in_flight).sum::<usize>(), 2);
+    }
+
+    #[test]
+    fn test_all_keys_limited_uses_soonest_available() {
+        let keys = ApiKeyPool::new(vec!["only".to_string()], KeySelection::RoundRobin);
+        let lease = keys.acquire();
+        lease.record::<()>(&Err(ProviderError::rate_limited("slow down")));
+        drop(lease);
+        assert!(!keys.has_available());
+        assert_eq!(keys.acquire().key(), "only");
+    }
+
+    #[test]
+    fn test_rate_limited_key_rests_for_retry_after() {
+        let keys = ApiKeyPool::new(vec!["only".to_string()], KeySelection::RoundRobin);
+        let lease = keys.acquire();
+        lease.record::<()>(&Err(ProviderError::rate_limited("slow down").with_details(
+            ErrorDetails {
+                status: Some(429),
+                retryable: true,
+                retry_after_ms: Some(120_000),
+                ..Default::default()
+            },
+        )));
+        drop(lease);
+        let limited_for = keys.stats()[0].limited_for.unwrap();
+        assert!(limited_for > DEFAULT_RATE_LIMIT_COOLDOWN);
+    }
+}
@@ -26,11 +26,37 @@ impl Usage {
     }
 }
 
-#[derive(Debug, Clone, uniffi::Record)]
+/// Details of how a response was produced, kept for reproducibility and for quoting in
+/// support escalations to the provider
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize, uniffi::Record)]
+pub struct ResponseMetadata {
+    /// The exact model version that served the request, which may differ from the requested model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_version: Option<String>,
+    /// Identifies the backend configuration the request ran with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+    /// The request ID from the response headers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// The ID of the completion in the response body
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_id: Option<String>,
+}
+
+impl ResponseMetadata {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 pub struct ProviderCompleteResponse {
     pub message: Message,
     pub model: String,
     pub usage: Usage,
+    #[serde(default)]
+    pub metadata: ResponseMetadata,
 }
 
 impl ProviderCompleteResponse {
@@ -39,8 +65,14 @@ impl ProviderCompleteResponse {
             message,
             model,
             usage,
+            metadata: ResponseMetadata::default(),
         }
     }
+
+    pub fn with_metadata(mut self, metadata: ResponseMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
 
 /// Response from a structured‐extraction call
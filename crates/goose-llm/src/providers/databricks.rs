@@ -8,9 +8,11 @@ use serde_json::{json, Value};
 use url::Url;
 
 use super::{
-    errors::ProviderError,
+    api_keys::{parse_key_list, ApiKeyPool, ApiKeyStats, KeySelection},
+    errors::{ErrorDetails, ProviderError},
     formats::databricks::{create_request, get_usage, response_to_message},
-    utils::{get_env, get_model, ImageFormat},
+    http::NetworkConfig,
+    utils::{get_env, get_model, get_request_id, get_response_metadata, ImageFormat},
 };
 use crate::{
     message::Message,
@@ -34,10 +36,17 @@ fn default_timeout() -> u64 {
 pub struct DatabricksProviderConfig {
     pub host: String,
     pub token: String,
+    /// Additional tokens to spread requests across along with `token`
+    #[serde(default)]
+    pub tokens: Vec<String>,
+    #[serde(default)]
+    pub key_selection: KeySelection,
     #[serde(default)]
     pub image_format: ImageFormat,
     #[serde(default = "default_timeout")]
     pub timeout: u64, // timeout in seconds
+    #[serde(flatten)]
+    pub network: NetworkConfig,
 }
 
 impl DatabricksProviderConfig {
@@ -45,15 +54,30 @@ impl DatabricksProviderConfig {
         Self {
             host,
             token,
+            tokens: Vec::new(),
+            key_selection: KeySelection::default(),
             image_format: ImageFormat::OpenAi,
             timeout: default_timeout(),
+            network: NetworkConfig::default(),
         }
     }
 
     pub fn from_env() -> Self {
         let host = get_env("DATABRICKS_HOST").expect("Missing DATABRICKS_HOST");
         let token = get_env("DATABRICKS_TOKEN").expect("Missing DATABRICKS_TOKEN");
-        Self::new(host, token)
+        let mut config = Self::new(host, token);
+        config.network = NetworkConfig::from_env();
+        if let Ok(tokens) = get_env("DATABRICKS_TOKENS") {
+            config.tokens = parse_key_list(&tokens);
+        }
+        config
+    }
+
+    /// Every configured token, starting with `token`
+    pub fn all_tokens(&self) -> Vec<String> {
+        std::iter::once(self.token.clone())
+            .chain(self.tokens.iter().cloned())
+            .collect()
     }
 }
 
@@ -62,6 +86,7 @@ pub struct DatabricksProvider {
     config: DatabricksProviderConfig,
     model: ModelConfig,
     client: Client,
+    tokens: ApiKeyPool,
 }
 
 impl DatabricksProvider {
@@ -83,47 +108,81 @@ impl Default for DatabricksProvider {
 
 impl DatabricksProvider {
     pub fn from_config(config: DatabricksProviderConfig, model: ModelConfig) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout))
-            .build()?;
+        let client = config
+            .network
+            .build_client(Duration::from_secs(config.timeout))?;
 
+        let tokens = ApiKeyPool::new(config.all_tokens(), config.key_selection);
         Ok(Self {
             config,
             model,
             client,
+            tokens,
         })
     }
 
-    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+    /// Usage of each configured token
+    pub fn api_key_stats(&self) -> Vec<ApiKeyStats> {
+        self.tokens.stats()
+    }
+
+    /// Send the request with the next token, moving on to another token when one is rate limited
+    /// Returns the response body together with the request ID from its headers
+    async fn post(&self, payload: Value) -> Result<(Value, Option<String>), ProviderError> {
+        let mut attempts = self.tokens.len();
+        loop {
+            let lease = self.tokens.acquire();
+            let result = self.post_with_token(lease.key(), &payload).await;
+            lease.record(&result);
+            attempts -= 1;
+            match result {
+                Err(ProviderError::RateLimitExceeded { .. })
+                    if attempts > 0 && self.tokens.has_available() =>
+                {
+                    continue
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn post_with_token(
+        &self,
+        token: &str,
+        payload: &Value,
+    ) -> Result<(Value, Option<String>), ProviderError> {
         let base_url = Url::parse(&self.config.host)
-            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+            .map_err(|e| ProviderError::request_failed(format!("Invalid base URL: {e}")))?;
         let path = format!("serving-endpoints/{}/invocations", self.model.model_name);
         let url = base_url.join(&path).map_err(|e| {
-            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+            ProviderError::request_failed(format!("Failed to construct endpoint URL: {e}"))
         })?;
 
-        let auth_header = format!("Bearer {}", &self.config.token);
+        let auth_header = format!("Bearer {}", token);
         let response = self
             .client
             .post(url)
             .header("Authorization", auth_header)
-            .json(&payload)
+            .json(payload)
             .send()
             .await?;
 
         let status = response.status();
+        let request_id = get_request_id(response.headers());
+        let headers = response.headers().clone();
         let payload: Option<Value> = response.json().await.ok();
+        let details = ErrorDetails::from_response(status, &headers, payload.as_ref());
 
         match status {
-            StatusCode::OK => payload.ok_or_else(|| {
-                ProviderError::RequestFailed("Response body is not valid JSON".to_string())
+            StatusCode::OK => payload.map(|payload| (payload, request_id)).ok_or_else(|| {
+                ProviderError::request_failed("Response body is not valid JSON")
             }),
             StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-                Err(ProviderError::Authentication(format!(
+                Err(ProviderError::authentication(format!(
                     "Authentication failed. Please ensure your API keys are valid and have the required permissions. \
                     Status: {}. Response: {:?}",
                     status, payload
-                )))
+                )).with_details(details))
             }
             StatusCode::BAD_REQUEST => {
                 // Databricks provides a generic 'error' but also includes 'external_model_message' which is provider specific
@@ -171,16 +230,17 @@ impl DatabricksProvider {
                         status, payload
                     )
                 );
-                Err(ProviderError::RequestFailed(format!(
+                Err(ProviderError::request_failed(format!(
                     "Request failed with status: {}. Message: {}",
                     status, error_msg
-                )))
+                ))
+                .with_details(details))
             }
             StatusCode::TOO_MANY_REQUESTS => {
-                Err(ProviderError::RateLimitExceeded(format!("{:?}", payload)))
+                Err(ProviderError::rate_limited(format!("{:?}", payload)).with_details(details))
             }
             StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-                Err(ProviderError::ServerError(format!("{:?}", payload)))
+                Err(ProviderError::server_error(format!("{:?}", payload)).with_details(details))
             }
             _ => {
                 tracing::debug!(
@@ -190,10 +250,11 @@ impl DatabricksProvider {
                         status, payload
                     )
                 );
-                Err(ProviderError::RequestFailed(format!(
+                Err(ProviderError::request_failed(format!(
                     "Request failed with status: {}",
                     status
-                )))
+                ))
+                .with_details(details))
             }
         }
     }
@@ -224,7 +285,7 @@ impl Provider for DatabricksProvider {
             .expect("payload should have model key")
             .remove("model");
 
-        let response = self.post(payload.clone()).await?;
+        let (response, request_id) = self.post(payload.clone()).await?;
 
         // Parse response
         let message = response_to_message(response.clone())?;
@@ -239,7 +300,8 @@ impl Provider for DatabricksProvider {
         let model = get_model(&response);
         super::utils::emit_debug_trace(&self.model, &payload, &response, &usage);
 
-        Ok(ProviderCompleteResponse::new(message, model, usage))
+        let metadata = get_response_metadata(&response, request_id);
+        Ok(ProviderCompleteResponse::new(message, model, usage).with_metadata(metadata))
     }
 
     async fn extract(
@@ -268,7 +330,7 @@ impl Provider for DatabricksProvider {
             );
 
         // 3. Call OpenAI
-        let response = self.post(payload.clone()).await?;
+        let (response, _) = self.post(payload.clone()).await?;
 
         // 4. Extract the assistant’s `content` and parse it into JSON
         let msg = &response["choices"][0]["message"];
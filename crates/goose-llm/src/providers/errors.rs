@@ -1,21 +1,104 @@
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde_json::Value;
 use thiserror::Error;
 
+/// What is known about a failed request beyond its message, so callers can decide whether
+/// and when to try again without matching on error text
+#[derive(Debug, Clone, Default, PartialEq, uniffi::Record)]
+pub struct ErrorDetails {
+    /// HTTP status of the response, if the provider answered
+    pub status: Option<u16>,
+    /// Error code reported by the provider, e.g. `insufficient_quota`
+    pub code: Option<String>,
+    /// Whether the same request may succeed if it is sent again
+    pub retryable: bool,
+    /// How long to wait before retrying, from the provider's `Retry-After` header
+    pub retry_after_ms: Option<u64>,
+}
+
+impl ErrorDetails {
+    /// Details of an error response. Timeouts, conflicts, rate limits and server errors are
+    /// retryable, unless the provider says the account is out of quota.
+    pub fn from_response(status: StatusCode, headers: &HeaderMap, payload: Option<&Value>) -> Self {
+        let code = payload.and_then(error_code);
+        let out_of_quota = code.as_deref() == Some("insufficient_quota");
+        Self {
+            status: Some(status.as_u16()),
+            retryable: !out_of_quota
+                && (status.is_server_error()
+                    || matches!(
+                        status,
+                        StatusCode::REQUEST_TIMEOUT
+                            | StatusCode::CONFLICT
+                            | StatusCode::TOO_MANY_REQUESTS
+                    )),
+            retry_after_ms: retry_after(headers).map(|delay| delay.as_millis() as u64),
+            code,
+        }
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.retry_after_ms.map(Duration::from_millis)
+    }
+}
+
+/// The provider's error code, from OpenAI style `{"error": {"code": ..}}` bodies or
+/// Databricks style `{"error_code": ..}` ones
+fn error_code(payload: &Value) -> Option<String> {
+    let code = payload
+        .get("error")
+        .and_then(|error| error.get("code").or_else(|| error.get("type")))
+        .or_else(|| payload.get("error_code"))?;
+    match code {
+        Value::String(code) => Some(code.clone()),
+        Value::Number(code) => Some(code.to_string()),
+        _ => None,
+    }
+}
+
+/// The delay asked for in `retry-after-ms` or `Retry-After`. Only the delay-seconds form of
+/// `Retry-After` is understood; an HTTP date is ignored.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(ms) = header("retry-after-ms").and_then(|ms| ms.trim().parse::<f64>().ok()) {
+        return Some(Duration::from_millis(ms.max(0.0) as u64));
+    }
+    header("retry-after")
+        .and_then(|secs| secs.trim().parse::<f64>().ok())
+        .map(|secs| Duration::from_millis((secs.max(0.0) * 1000.0) as u64))
+}
+
 #[derive(Error, Debug, uniffi::Error)]
 pub enum ProviderError {
-    #[error("Authentication error: {0}")]
-    Authentication(String),
+    #[error("Authentication error: {message}")]
+    Authentication {
+        message: String,
+        details: ErrorDetails,
+    },
 
     #[error("Context length exceeded: {0}")]
     ContextLengthExceeded(String),
 
-    #[error("Rate limit exceeded: {0}")]
-    RateLimitExceeded(String),
+    #[error("Rate limit exceeded: {message}")]
+    RateLimitExceeded {
+        message: String,
+        details: ErrorDetails,
+    },
 
-    #[error("Server error: {0}")]
-    ServerError(String),
+    #[error("Server error: {message}")]
+    ServerError {
+        message: String,
+        details: ErrorDetails,
+    },
 
-    #[error("Request failed: {0}")]
-    RequestFailed(String),
+    #[error("Request failed: {message}")]
+    RequestFailed {
+        message: String,
+        details: ErrorDetails,
+    },
 
     #[error("Execution error: {0}")]
     ExecutionError(String),
@@ -27,6 +110,80 @@ pub enum ProviderError {
     ResponseParseError(String),
 }
 
+impl ProviderError {
+    pub fn authentication(message: impl Into<String>) -> Self {
+        Self::Authentication {
+            message: message.into(),
+            details: ErrorDetails::default(),
+        }
+    }
+
+    pub fn rate_limited(message: impl Into<String>) -> Self {
+        Self::RateLimitExceeded {
+            message: message.into(),
+            details: ErrorDetails {
+                retryable: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn server_error(message: impl Into<String>) -> Self {
+        Self::ServerError {
+            message: message.into(),
+            details: ErrorDetails {
+                retryable: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// A request that could not be made or was rejected; not retryable unless details say so
+    pub fn request_failed(message: impl Into<String>) -> Self {
+        Self::RequestFailed {
+            message: message.into(),
+            details: ErrorDetails::default(),
+        }
+    }
+
+    /// Replace the details of an error that carries them
+    pub fn with_details(mut self, new_details: ErrorDetails) -> Self {
+        if let Self::Authentication { details, .. }
+        | Self::RateLimitExceeded { details, .. }
+        | Self::ServerError { details, .. }
+        | Self::RequestFailed { details, .. } = &mut self
+        {
+            *details = new_details;
+        }
+        self
+    }
+
+    pub fn details(&self) -> Option<&ErrorDetails> {
+        match self {
+            Self::Authentication { details, .. }
+            | Self::RateLimitExceeded { details, .. }
+            | Self::ServerError { details, .. }
+            | Self::RequestFailed { details, .. } => Some(details),
+            _ => None,
+        }
+    }
+
+    /// HTTP status of the response that failed, if the provider answered
+    pub fn status(&self) -> Option<u16> {
+        self.details().and_then(|details| details.status)
+    }
+
+    /// Whether sending the same request again may succeed
+    pub fn is_retryable(&self) -> bool {
+        self.details().is_some_and(|details| details.retryable)
+    }
+
+    /// How long the provider asked callers to wait before trying again
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.details().and_then(ErrorDetails::retry_after)
+    }
+}
+
 impl From<anyhow::Error> for ProviderError {
     fn from(error: anyhow::Error) -> Self {
         ProviderError::ExecutionError(error.to_string())
@@ -35,6 +192,17 @@ impl From<anyhow::Error> for ProviderError {
 
 impl From<reqwest::Error> for ProviderError {
     fn from(error: reqwest::Error) -> Self {
+        // Connection failures and timeouts never reached the model, so they can be sent again
+        if error.is_connect() || error.is_timeout() {
+            return ProviderError::RequestFailed {
+                message: error.to_string(),
+                details: ErrorDetails {
+                    status: error.status().map(|status| status.as_u16()),
+                    retryable: true,
+                    ..Default::default()
+                },
+            };
+        }
         ProviderError::ExecutionError(error.to_string())
     }
 }
@@ -142,3 +310,57 @@ impl std::fmt::Display for OpenAIError {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use serde_json::json;
+
+    #[test]
+    fn test_details_from_response() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("7"));
+        let details = ErrorDetails::from_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            &headers,
+            Some(&json!({"error": {"code": "rate_limit_exceeded", "message": "slow down"}})),
+        );
+        assert_eq!(details.status, Some(429));
+        assert_eq!(details.code.as_deref(), Some("rate_limit_exceeded"));
+        assert!(details.retryable);
+        assert_eq!(details.retry_after(), Some(Duration::from_secs(7)));
+
+        let quota = ErrorDetails::from_response(
+            StatusCode::TOO_MANY_REQUESTS,
+            &HeaderMap::new(),
+            Some(&json!({"error": {"code": "insufficient_quota"}})),
+        );
+        assert!(!quota.retryable);
+
+        let bad_request = ErrorDetails::from_response(
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            Some(&json!({"error_code": "INVALID_PARAMETER_VALUE"})),
+        );
+        assert!(!bad_request.retryable);
+        assert_eq!(bad_request.code.as_deref(), Some("INVALID_PARAMETER_VALUE"));
+    }
+
+    #[test]
+    fn test_retryability_follows_details() {
+        let error = ProviderError::server_error("overloaded").with_details(ErrorDetails {
+            status: Some(503),
+            retryable: true,
+            retry_after_ms: Some(1500),
+            ..Default::default()
+        });
+        assert!(error.is_retryable());
+        assert_eq!(error.status(), Some(503));
+        assert_eq!(error.retry_after(), Some(Duration::from_millis(1500)));
+
+        assert!(!ProviderError::authentication("bad key").is_retryable());
+        assert!(!ProviderError::ContextLengthExceeded("too long".into()).is_retryable());
+        assert!(ProviderError::rate_limited("slow down").is_retryable());
+    }
+}
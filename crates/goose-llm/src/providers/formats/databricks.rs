@@ -326,6 +326,7 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
         role: Role::Assistant,
         created: chrono::Utc::now().timestamp_millis(),
         content: content.into(),
+        metadata: None,
     })
 }
 
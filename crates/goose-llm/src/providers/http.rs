@@ -0,0 +1,82 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+
+use super::utils::get_env;
+
+/// How a provider reaches its endpoint from networks that require an egress proxy
+/// or terminate TLS with a private certificate authority
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Proxy for all requests, e.g. `http://proxy.corp.example:3128`
+    #[serde(default)]
+    pub proxy_url: Option<String>,
+    /// Comma-separated hosts or domains that bypass the proxy
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+    /// Path to a PEM file with certificates to trust in addition to the system roots
+    #[serde(default)]
+    pub extra_ca_bundle: Option<String>,
+}
+
+impl NetworkConfig {
+    /// Read `GOOSE_PROXY_URL`, `GOOSE_NO_PROXY` and `GOOSE_EXTRA_CA_BUNDLE`
+    pub fn from_env() -> Self {
+        Self {
+            proxy_url: get_env("GOOSE_PROXY_URL").ok(),
+            no_proxy: get_env("GOOSE_NO_PROXY").ok(),
+            extra_ca_bundle: get_env("GOOSE_EXTRA_CA_BUNDLE").ok(),
+        }
+    }
+
+    /// Build the HTTP client used for provider requests
+    pub fn build_client(&self, timeout: Duration) -> Result<Client> {
+        let mut builder = Client::builder().timeout(timeout);
+
+        if let Some(proxy_url) = &self.proxy_url {
+            let proxy = Proxy::all(proxy_url)
+                .with_context(|| format!("Invalid proxy URL: {proxy_url}"))?
+                .no_proxy(self.no_proxy.as_deref().and_then(NoProxy::from_string));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(path) = &self.extra_ca_bundle {
+            let pem =
+                std::fs::read(path).with_context(|| format!("Failed to read CA bundle {path}"))?;
+            let certificates = Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid CA bundle {path}"))?;
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        Ok(builder.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_client_with_proxy() {
+        let config = NetworkConfig {
+            proxy_url: Some("http://proxy.example.com:3128".to_string()),
+            no_proxy: Some("localhost,.internal.example.com".to_string()),
+            extra_ca_bundle: None,
+        };
+        assert!(config.build_client(Duration::from_secs(5)).is_ok());
+    }
+
+    #[test]
+    fn test_build_client_rejects_missing_ca_bundle() {
+        let config = NetworkConfig {
+            extra_ca_bundle: Some("/nonexistent/ca-bundle.pem".to_string()),
+            ..Default::default()
+        };
+        let err = config.build_client(Duration::from_secs(5)).unwrap_err();
+        assert!(err.to_string().contains("Failed to read CA bundle"));
+    }
+}
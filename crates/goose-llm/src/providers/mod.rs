@@ -1,10 +1,14 @@
+pub mod api_keys;
 pub mod base;
 pub mod databricks;
 pub mod errors;
 mod factory;
 pub mod formats;
+pub mod http;
 pub mod openai;
 pub mod utils;
 
-pub use base::{Provider, ProviderCompleteResponse, ProviderExtractResponse, Usage};
+pub use base::{
+    Provider, ProviderCompleteResponse, ProviderExtractResponse, ResponseMetadata, Usage,
+};
 pub use factory::create;
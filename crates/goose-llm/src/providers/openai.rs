@@ -7,9 +7,14 @@ use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use super::{
+    api_keys::{parse_key_list, ApiKeyPool, ApiKeyStats, KeySelection},
     errors::ProviderError,
     formats::openai::{create_request, get_usage, response_to_message},
-    utils::{emit_debug_trace, get_env, get_model, handle_response_openai_compat, ImageFormat},
+    http::NetworkConfig,
+    utils::{
+        emit_debug_trace, get_env, get_model, get_request_id, get_response_metadata,
+        handle_response_openai_compat, ImageFormat,
+    },
 };
 use crate::{
     message::Message,
@@ -36,6 +41,11 @@ fn default_host() -> String {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenAiProviderConfig {
     pub api_key: String,
+    /// Additional keys to spread requests across along with `api_key`
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    #[serde(default)]
+    pub key_selection: KeySelection,
     #[serde(default = "default_host")]
     pub host: String,
     #[serde(default)]
@@ -48,24 +58,41 @@ pub struct OpenAiProviderConfig {
     pub custom_headers: Option<HashMap<String, String>>,
     #[serde(default = "default_timeout")]
     pub timeout: u64, // timeout in seconds
+    #[serde(flatten)]
+    pub network: NetworkConfig,
 }
 
 impl OpenAiProviderConfig {
     pub fn new(api_key: String) -> Self {
         Self {
             api_key,
+            api_keys: Vec::new(),
+            key_selection: KeySelection::default(),
             host: default_host(),
             organization: None,
             base_path: default_base_path(),
             project: None,
             custom_headers: None,
             timeout: 600,
+            network: NetworkConfig::default(),
         }
     }
 
     pub fn from_env() -> Self {
         let api_key = get_env("OPENAI_API_KEY").expect("Missing OPENAI_API_KEY");
-        Self::new(api_key)
+        let mut config = Self::new(api_key);
+        config.network = NetworkConfig::from_env();
+        if let Ok(keys) = get_env("OPENAI_API_KEYS") {
+            config.api_keys = parse_key_list(&keys);
+        }
+        config
+    }
+
+    /// Every configured key, starting with `api_key`
+    pub fn all_api_keys(&self) -> Vec<String> {
+        std::iter::once(self.api_key.clone())
+            .chain(self.api_keys.iter().cloned())
+            .collect()
     }
 }
 
@@ -74,6 +101,7 @@ pub struct OpenAiProvider {
     config: OpenAiProviderConfig,
     model: ModelConfig,
     client: Client,
+    keys: ApiKeyPool,
 }
 
 impl OpenAiProvider {
@@ -93,28 +121,59 @@ impl Default for OpenAiProvider {
 
 impl OpenAiProvider {
     pub fn from_config(config: OpenAiProviderConfig, model: ModelConfig) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(config.timeout))
-            .build()?;
+        let client = config
+            .network
+            .build_client(Duration::from_secs(config.timeout))?;
 
+        let keys = ApiKeyPool::new(config.all_api_keys(), config.key_selection);
         Ok(Self {
             config,
             model,
             client,
+            keys,
         })
     }
 
-    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+    /// Usage of each configured API key
+    pub fn api_key_stats(&self) -> Vec<ApiKeyStats> {
+        self.keys.stats()
+    }
+
+    /// Send the request with the next API key, moving on to another key when one is rate limited
+    /// Returns the response body together with the request ID from its headers
+    async fn post(&self, payload: Value) -> Result<(Value, Option<String>), ProviderError> {
+        let mut attempts = self.keys.len();
+        loop {
+            let lease = self.keys.acquire();
+            let result = self.post_with_key(lease.key(), &payload).await;
+            lease.record(&result);
+            attempts -= 1;
+            match result {
+                Err(ProviderError::RateLimitExceeded { .. })
+                    if attempts > 0 && self.keys.has_available() =>
+                {
+                    continue
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn post_with_key(
+        &self,
+        api_key: &str,
+        payload: &Value,
+    ) -> Result<(Value, Option<String>), ProviderError> {
         let base_url = url::Url::parse(&self.config.host)
-            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+            .map_err(|e| ProviderError::request_failed(format!("Invalid base URL: {e}")))?;
         let url = base_url.join(&self.config.base_path).map_err(|e| {
-            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+            ProviderError::request_failed(format!("Failed to construct endpoint URL: {e}"))
         })?;
 
         let mut request = self
             .client
             .post(url)
-            .header("Authorization", format!("Bearer {}", self.config.api_key));
+            .header("Authorization", format!("Bearer {}", api_key));
 
         // Add organization header if present
         if let Some(org) = &self.config.organization {
@@ -132,9 +191,10 @@ impl OpenAiProvider {
             }
         }
 
-        let response = request.json(&payload).send().await?;
+        let response = request.json(payload).send().await?;
+        let request_id = get_request_id(response.headers());
 
-        handle_response_openai_compat(response).await
+        Ok((handle_response_openai_compat(response).await?, request_id))
     }
 }
 
@@ -153,7 +213,7 @@ impl Provider for OpenAiProvider {
         let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
 
         // Make request
-        let response = self.post(payload.clone()).await?;
+        let (response, request_id) = self.post(payload.clone()).await?;
 
         // Parse response
         let message = response_to_message(response.clone())?;
@@ -167,7 +227,8 @@ impl Provider for OpenAiProvider {
         };
         let model = get_model(&response);
         emit_debug_trace(&self.model, &payload, &response, &usage);
-        Ok(ProviderCompleteResponse::new(message, model, usage))
+        let metadata = get_response_metadata(&response, request_id);
+        Ok(ProviderCompleteResponse::new(message, model, usage).with_metadata(metadata))
     }
 
     async fn extract(
@@ -196,7 +257,7 @@ impl Provider for OpenAiProvider {
             );
 
         // 3. Call OpenAI
-        let response = self.post(payload.clone()).await?;
+        let (response, _) = self.post(payload.clone()).await?;
 
         // 4. Extract the assistant’s `content` and parse it into JSON
         let msg = &response["choices"][0]["message"];
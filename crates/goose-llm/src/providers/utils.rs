@@ -3,14 +3,14 @@ use std::{env, io::Read, path::Path};
 use anyhow::Result;
 use base64::Engine;
 use regex::Regex;
-use reqwest::{Response, StatusCode};
+use reqwest::{header::HeaderMap, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::{from_value, json, Value};
 
-use super::base::Usage;
+use super::base::{ResponseMetadata, Usage};
 use crate::{
     model::ModelConfig,
-    providers::errors::{OpenAIError, ProviderError},
+    providers::errors::{ErrorDetails, OpenAIError, ProviderError},
     types::core::ImageContent,
 };
 
@@ -60,20 +60,25 @@ pub fn convert_image(image: &ImageContent, image_format: &ImageFormat) -> Value
 /// Context window exceeded: https://community.openai.com/t/help-needed-tackling-context-length-limits-in-openai-models/617543
 pub async fn handle_response_openai_compat(response: Response) -> Result<Value, ProviderError> {
     let status = response.status();
+    let headers = response.headers().clone();
     // Try to parse the response body as JSON (if applicable)
     let payload = match response.json::<Value>().await {
         Ok(json) => json,
-        Err(e) => return Err(ProviderError::RequestFailed(e.to_string())),
+        Err(e) => {
+            return Err(ProviderError::request_failed(e.to_string())
+                .with_details(ErrorDetails::from_response(status, &headers, None)))
+        }
     };
+    let details = ErrorDetails::from_response(status, &headers, Some(&payload));
 
     match status {
         StatusCode::OK => Ok(payload),
         StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-            Err(ProviderError::Authentication(format!(
+            Err(ProviderError::authentication(format!(
                 "Authentication failed. Please ensure your API keys are valid and have the required permissions. \
                 Status: {}. Response: {:?}",
                 status, payload
-            )))
+            )).with_details(details))
         }
         StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND => {
             tracing::debug!(
@@ -90,22 +95,23 @@ pub async fn handle_response_openai_compat(response: Response) -> Result<Value,
                         err.message.unwrap_or("Unknown error".to_string()),
                     ));
                 }
-                return Err(ProviderError::RequestFailed(format!(
+                return Err(ProviderError::request_failed(format!(
                     "{} (status {})",
                     err,
                     status.as_u16()
-                )));
+                ))
+                .with_details(details));
             }
-            Err(ProviderError::RequestFailed(format!(
-                "Unknown error (status {})",
-                status
-            )))
+            Err(
+                ProviderError::request_failed(format!("Unknown error (status {})", status))
+                    .with_details(details),
+            )
         }
         StatusCode::TOO_MANY_REQUESTS => {
-            Err(ProviderError::RateLimitExceeded(format!("{:?}", payload)))
+            Err(ProviderError::rate_limited(format!("{:?}", payload)).with_details(details))
         }
         StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-            Err(ProviderError::ServerError(format!("{:?}", payload)))
+            Err(ProviderError::server_error(format!("{:?}", payload)).with_details(details))
         }
         _ => {
             tracing::debug!(
@@ -115,10 +121,10 @@ pub async fn handle_response_openai_compat(response: Response) -> Result<Value,
                     status, payload
                 )
             );
-            Err(ProviderError::RequestFailed(format!(
-                "Request failed with status: {}",
-                status
-            )))
+            Err(
+                ProviderError::request_failed(format!("Request failed with status: {}", status))
+                    .with_details(details),
+            )
         }
     }
 }
@@ -161,6 +167,26 @@ pub fn get_model(data: &Value) -> String {
     }
 }
 
+/// The request ID a provider returned in the response headers, if any
+pub fn get_request_id(headers: &HeaderMap) -> Option<String> {
+    ["x-request-id", "request-id", "x-databricks-request-id"]
+        .iter()
+        .find_map(|name| headers.get(*name)?.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Collect the metadata describing how a response was served. Most OpenAI compatible
+/// providers return the served model, a `system_fingerprint` and a completion `id`.
+pub fn get_response_metadata(data: &Value, request_id: Option<String>) -> ResponseMetadata {
+    let field = |name: &str| data.get(name).and_then(Value::as_str).map(str::to_string);
+    ResponseMetadata {
+        model_version: field("model"),
+        system_fingerprint: field("system_fingerprint"),
+        request_id,
+        response_id: field("id"),
+    }
+}
+
 /// Check if a file is actually an image by examining its magic bytes
 fn is_image_file(path: &Path) -> bool {
     if let Ok(mut file) = std::fs::File::open(path) {
@@ -211,14 +237,14 @@ pub fn load_image_file(path: &str) -> Result<ImageContent, ProviderError> {
 
     // Verify it's an image before proceeding
     if !is_image_file(path) {
-        return Err(ProviderError::RequestFailed(
+        return Err(ProviderError::request_failed(
             "File is not a valid image".to_string(),
         ));
     }
 
     // Read the file
     let bytes = std::fs::read(path)
-        .map_err(|e| ProviderError::RequestFailed(format!("Failed to read image file: {}", e)))?;
+        .map_err(|e| ProviderError::request_failed(format!("Failed to read image file: {}", e)))?;
 
     // Detect mime type from extension
     let mime_type = match path.extension().and_then(|e| e.to_str()) {
@@ -226,13 +252,13 @@ pub fn load_image_file(path: &str) -> Result<ImageContent, ProviderError> {
             "png" => "image/png",
             "jpg" | "jpeg" => "image/jpeg",
             _ => {
-                return Err(ProviderError::RequestFailed(
+                return Err(ProviderError::request_failed(
                     "Unsupported image format".to_string(),
                 ));
             }
         },
         None => {
-            return Err(ProviderError::RequestFailed(
+            return Err(ProviderError::request_failed(
                 "Unknown image format".to_string(),
             ));
         }
@@ -342,6 +368,29 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_get_response_metadata() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req_123".parse().unwrap());
+        let data = json!({
+            "id": "chatcmpl-abc",
+            "model": "gpt-4o-2024-08-06",
+            "system_fingerprint": "fp_50cad350e4",
+        });
+
+        let metadata = get_response_metadata(&data, get_request_id(&headers));
+        assert_eq!(metadata.model_version.as_deref(), Some("gpt-4o-2024-08-06"));
+        assert_eq!(
+            metadata.system_fingerprint.as_deref(),
+            Some("fp_50cad350e4")
+        );
+        assert_eq!(metadata.request_id.as_deref(), Some("req_123"));
+        assert_eq!(metadata.response_id.as_deref(), Some("chatcmpl-abc"));
+
+        let empty = get_response_metadata(&json!({}), get_request_id(&HeaderMap::new()));
+        assert!(empty.is_empty());
+    }
+
     #[test]
     fn test_sanitize_function_name() {
         assert_eq!(sanitize_function_name("hello-world"), "hello-world");
@@ -8,7 +8,10 @@ use thiserror::Error;
 use serde::{Deserialize, Serialize};
 
 use crate::types::json_value_ffi::JsonValueFfi;
-use crate::{message::Message, providers::Usage};
+use crate::{
+    message::Message,
+    providers::{ResponseMetadata, Usage},
+};
 use crate::{model::ModelConfig, providers::errors::ProviderError};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +103,8 @@ pub struct CompletionResponse {
     pub model: String,
     pub usage: Usage,
     pub runtime_metrics: RuntimeMetrics,
+    #[serde(default)]
+    pub metadata: ResponseMetadata,
 }
 
 impl CompletionResponse {
@@ -114,8 +119,14 @@ impl CompletionResponse {
             model,
             usage,
             runtime_metrics,
+            metadata: ResponseMetadata::default(),
         }
     }
+
+    pub fn with_metadata(mut self, metadata: ResponseMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
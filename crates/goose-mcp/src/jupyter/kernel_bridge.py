@@ -0,0 +1,95 @@
+"""Runs one Jupyter kernel and executes code in it for the goose jupyter extension.
+
+Requests and responses are JSON lines: requests arrive on stdin, responses leave on the
+original stdout. Everything else, including whatever the kernel prints, goes to stderr.
+"""
+import json
+import os
+import queue
+import re
+import sys
+
+from jupyter_client.manager import KernelManager
+
+ANSI_ESCAPE = re.compile(r"\x1b\[[0-9;]*[A-Za-z]")
+
+responses = os.fdopen(os.dup(1), "w", encoding="utf-8")
+os.dup2(2, 1)
+
+
+def respond(**fields):
+    responses.write(json.dumps(fields) + "\n")
+    responses.flush()
+
+
+def start(kernel_name):
+    manager = KernelManager(kernel_name=kernel_name)
+    manager.start_kernel()
+    client = manager.client()
+    client.start_channels()
+    client.wait_for_ready(timeout=60)
+    return manager, client
+
+
+def execute(manager, client, code, timeout):
+    text = []
+    figures = []
+    status = "ok"
+
+    def on_output(msg):
+        nonlocal status
+        kind = msg["header"]["msg_type"]
+        content = msg["content"]
+        if kind == "stream":
+            text.append(content["text"])
+        elif kind in ("execute_result", "display_data"):
+            data = content.get("data", {})
+            if "image/png" in data:
+                figures.append(data["image/png"].replace("\n", ""))
+            elif "text/plain" in data:
+                text.append(data["text/plain"] + "\n")
+        elif kind == "error":
+            status = "error"
+            text.append(ANSI_ESCAPE.sub("", "\n".join(content["traceback"])) + "\n")
+
+    try:
+        reply = client.execute_interactive(
+            code, timeout=timeout, output_hook=on_output, allow_stdin=False
+        )
+        execution_count = reply["content"].get("execution_count")
+    except (TimeoutError, queue.Empty):
+        manager.interrupt_kernel()
+        status = "timeout"
+        execution_count = None
+        text.append("Execution timed out after %ss and was interrupted\n" % timeout)
+
+    respond(
+        status=status,
+        text="".join(text),
+        figures=figures,
+        execution_count=execution_count,
+    )
+
+
+def main():
+    manager, client = start(sys.argv[1] if len(sys.argv) > 1 else "python3")
+    respond(status="ready")
+    try:
+        for line in sys.stdin:
+            request = json.loads(line)
+            command = request.get("command", "execute")
+            if command == "execute":
+                execute(manager, client, request["code"], request.get("timeout", 60))
+            elif command == "restart":
+                manager.restart_kernel(now=True)
+                client.wait_for_ready(timeout=60)
+                respond(status="ok", text="Kernel restarted", figures=[])
+            else:
+                respond(status="error", text="Unknown command %s" % command, figures=[])
+    finally:
+        client.stop_channels()
+        manager.shutdown_kernel(now=True)
+
+
+if __name__ == "__main__":
+    main()
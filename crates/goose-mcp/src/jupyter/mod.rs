@@ -0,0 +1,481 @@
+use base64::Engine;
+use etcetera::{choose_app_strategy, AppStrategy};
+use indoc::{formatdoc, indoc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    process::Stdio,
+    sync::atomic::{AtomicUsize, Ordering},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{mpsc, Mutex};
+
+use mcp_core::{
+    handler::{PromptError, ResourceError, ToolError},
+    prompt::Prompt,
+    protocol::{JsonRpcMessage, ServerCapabilities},
+    resource::Resource,
+    role::Role,
+    tool::{Tool, ToolAnnotations},
+    Content,
+};
+use mcp_server::router::CapabilitiesBuilder;
+use mcp_server::Router;
+
+/// Starts a kernel with `jupyter_client` and relays execution requests to it as JSON lines
+const KERNEL_BRIDGE: &str = include_str!("kernel_bridge.py");
+
+const DEFAULT_KERNEL: &str = "default";
+const DEFAULT_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_MAX_OUTPUT_CHARS: usize = 20_000;
+
+/// What the bridge reports for each request
+#[derive(Debug, Deserialize)]
+struct BridgeResponse {
+    status: String,
+    #[serde(default)]
+    text: String,
+    /// Base64-encoded PNGs
+    #[serde(default)]
+    figures: Vec<String>,
+    #[serde(default)]
+    execution_count: Option<u64>,
+}
+
+/// A running kernel and the bridge process talking to it
+struct Kernel {
+    _child: Child,
+    stdin: ChildStdin,
+    responses: Lines<BufReader<ChildStdout>>,
+}
+
+impl Kernel {
+    async fn start(python: &str, kernel_name: &str) -> Result<Self, ToolError> {
+        let mut child = Command::new(python)
+            .arg("-c")
+            .arg(KERNEL_BRIDGE)
+            .arg(kernel_name)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to start {}: {}", python, e)))?;
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| ToolError::ExecutionError("Kernel bridge has no stdin".to_string()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| ToolError::ExecutionError("Kernel bridge has no stdout".to_string()))?;
+
+        let mut kernel = Self {
+            _child: child,
+            stdin,
+            responses: BufReader::new(stdout).lines(),
+        };
+        let ready = kernel.read_response().await.map_err(|_| {
+            ToolError::ExecutionError(
+                "The Jupyter kernel failed to start; check that jupyter_client and ipykernel \
+                 are installed for GOOSE_JUPYTER_PYTHON"
+                    .to_string(),
+            )
+        })?;
+        if ready.status != "ready" {
+            return Err(ToolError::ExecutionError(format!(
+                "Unexpected kernel status {}",
+                ready.status
+            )));
+        }
+        Ok(kernel)
+    }
+
+    async fn request(&mut self, request: Value) -> Result<BridgeResponse, ToolError> {
+        let line = format!("{}\n", request);
+        self.stdin
+            .write_all(line.as_bytes())
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Kernel is not running: {}", e)))?;
+        self.stdin
+            .flush()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Kernel is not running: {}", e)))?;
+        self.read_response().await
+    }
+
+    async fn read_response(&mut self) -> Result<BridgeResponse, ToolError> {
+        let line = self
+            .responses
+            .next_line()
+            .await
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?
+            .ok_or_else(|| ToolError::ExecutionError("Kernel exited".to_string()))?;
+        serde_json::from_str(&line)
+            .map_err(|e| ToolError::ExecutionError(format!("Unreadable kernel response: {}", e)))
+    }
+}
+
+/// Keep the start and end of long output, where tracebacks and final results are
+fn truncate_output(text: &str, max_chars: usize) -> String {
+    let total = text.chars().count();
+    if total <= max_chars {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(max_chars / 2).collect();
+    let tail: String = text.chars().skip(total - max_chars / 2).collect();
+    format!(
+        "{}\n... {} characters truncated ...\n{}",
+        head,
+        total - head.chars().count() - tail.chars().count(),
+        tail
+    )
+}
+
+/// A kernel name made safe to use in a file name
+fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The process id and start time, which no other session saving figures shares
+fn figure_prefix() -> String {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    format!("{}-{:x}", std::process::id(), started)
+}
+
+/// Stateful Python execution in Jupyter kernels. Each goose session runs its own copy of the
+/// extension, so kernels are never shared between sessions.
+#[derive(Clone)]
+pub struct JupyterRouter {
+    tools: Vec<Tool>,
+    instructions: String,
+    python: String,
+    kernel_name: String,
+    /// Where captured figures are saved
+    artifact_dir: PathBuf,
+    /// Sets this process's figure file names apart from other sessions saving to the same
+    /// directory
+    figure_prefix: String,
+    /// Figures saved so far, numbering them across kernel restarts
+    figures_saved: Arc<AtomicUsize>,
+    max_output_chars: usize,
+    kernels: Arc<Mutex<HashMap<String, Arc<Mutex<Kernel>>>>>,
+}
+
+impl Default for JupyterRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JupyterRouter {
+    pub fn new() -> Self {
+        let execute = Tool::new(
+            "jupyter_execute".to_string(),
+            indoc! {r#"
+                Execute Python code in a Jupyter kernel. The kernel keeps its state between
+                calls: variables, imports and loaded data stay available, so build an analysis
+                up step by step instead of rerunning everything.
+
+                Returns printed output, the value of the last expression and any error with its
+                traceback; long output is truncated in the middle. Figures displayed by the code,
+                e.g. with matplotlib's plt.show(), are saved as PNG files and returned as images.
+
+                Use separate `kernel` names for independent pieces of work.
+            "#}
+            .to_string(),
+            json!({
+                "type": "object",
+                "required": ["code"],
+                "properties": {
+                    "code": {
+                        "type": "string",
+                        "description": "Python code to execute"
+                    },
+                    "kernel": {
+                        "type": "string",
+                        "description": "Name of the kernel to run in, started on first use (default: 'default')"
+                    },
+                    "timeout_secs": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Interrupt the execution after this many seconds (default: 120)"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Execute Python".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: false,
+                open_world_hint: true,
+            }),
+        );
+
+        let restart = Tool::new(
+            "jupyter_restart".to_string(),
+            "Restart a Jupyter kernel, clearing all of its variables and imports.".to_string(),
+            json!({
+                "type": "object",
+                "properties": {
+                    "kernel": {
+                        "type": "string",
+                        "description": "Name of the kernel to restart (default: 'default')"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Restart Jupyter kernel".to_string()),
+                read_only_hint: false,
+                destructive_hint: true,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let artifact_dir = std::env::var("GOOSE_JUPYTER_ARTIFACT_DIR")
+            .map(PathBuf::from)
+            .or_else(|_| {
+                choose_app_strategy(crate::APP_STRATEGY.clone())
+                    .map(|strategy| strategy.in_cache_dir("jupyter"))
+            })
+            .unwrap_or_else(|_| std::env::temp_dir().join("goose-jupyter"));
+
+        let instructions = formatdoc! {r#"
+            The jupyter extension runs Python in persistent Jupyter kernels. Prefer it over
+            one-off scripts for data analysis: load data once, then explore it across calls.
+            Figures are saved under {dir}.
+            "#,
+            dir = artifact_dir.display(),
+        };
+
+        Self {
+            tools: vec![execute, restart],
+            instructions,
+            python: std::env::var("GOOSE_JUPYTER_PYTHON").unwrap_or_else(|_| "python3".to_string()),
+            kernel_name: std::env::var("GOOSE_JUPYTER_KERNEL")
+                .unwrap_or_else(|_| "python3".to_string()),
+            artifact_dir,
+            figure_prefix: figure_prefix(),
+            figures_saved: Arc::new(AtomicUsize::new(0)),
+            max_output_chars: std::env::var("GOOSE_JUPYTER_MAX_OUTPUT_CHARS")
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .unwrap_or(DEFAULT_MAX_OUTPUT_CHARS),
+            kernels: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn kernel_argument(arguments: &Value) -> String {
+        arguments
+            .get("kernel")
+            .and_then(|v| v.as_str())
+            .unwrap_or(DEFAULT_KERNEL)
+            .to_string()
+    }
+
+    /// The named kernel, started if it isn't running yet
+    async fn kernel(&self, name: &str) -> Result<Arc<Mutex<Kernel>>, ToolError> {
+        let mut kernels = self.kernels.lock().await;
+        if let Some(kernel) = kernels.get(name) {
+            return Ok(Arc::clone(kernel));
+        }
+        let kernel = Arc::new(Mutex::new(
+            Kernel::start(&self.python, &self.kernel_name).await?,
+        ));
+        kernels.insert(name.to_string(), Arc::clone(&kernel));
+        Ok(kernel)
+    }
+
+    async fn execute(&self, arguments: &Value) -> Result<Vec<Content>, ToolError> {
+        let code = arguments
+            .get("code")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'code' parameter".to_string()))?;
+        let timeout = arguments
+            .get("timeout_secs")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TIMEOUT_SECS);
+        let name = Self::kernel_argument(arguments);
+
+        let kernel = self.kernel(&name).await?;
+        let response = {
+            let mut kernel = kernel.lock().await;
+            kernel
+                .request(json!({"command": "execute", "code": code, "timeout": timeout}))
+                .await
+        };
+        let response = match response {
+            Ok(response) => response,
+            Err(e) => {
+                // A kernel that stopped answering is started again on the next call
+                self.kernels.lock().await.remove(&name);
+                return Err(e);
+            }
+        };
+
+        let mut text = truncate_output(&response.text, self.max_output_chars);
+        let mut contents = Vec::new();
+        for figure in &response.figures {
+            let path = self.artifact_dir.join(format!(
+                "{}-{}-{}-{}.png",
+                file_stem(&name),
+                self.figure_prefix,
+                response.execution_count.unwrap_or_default(),
+                self.figures_saved.fetch_add(1, Ordering::Relaxed) + 1
+            ));
+            let saved = base64::engine::general_purpose::STANDARD
+                .decode(figure)
+                .map_err(|e| e.to_string())
+                .and_then(|bytes| {
+                    std::fs::create_dir_all(&self.artifact_dir)
+                        .and_then(|_| std::fs::write(&path, bytes))
+                        .map_err(|e| e.to_string())
+                });
+            match saved {
+                Ok(()) => text.push_str(&format!("\nSaved figure to {}", path.display())),
+                Err(e) => text.push_str(&format!("\nFailed to save figure: {}", e)),
+            }
+            contents.push(Content::image(figure.clone(), "image/png").with_priority(0.0));
+        }
+        if text.is_empty() {
+            text = "(no output)".to_string();
+        }
+
+        if response.status == "ok" {
+            contents.insert(0, Content::text(text).with_audience(vec![Role::Assistant]));
+            Ok(contents)
+        } else {
+            Err(ToolError::ExecutionError(text))
+        }
+    }
+
+    async fn restart(&self, arguments: &Value) -> Result<Vec<Content>, ToolError> {
+        let name = Self::kernel_argument(arguments);
+        let Some(kernel) = self.kernels.lock().await.get(&name).cloned() else {
+            return Ok(vec![Content::text(format!(
+                "Kernel {} is not running; it starts fresh on the next execution",
+                name
+            ))]);
+        };
+        let response = kernel
+            .lock()
+            .await
+            .request(json!({"command": "restart"}))
+            .await?;
+        Ok(vec![Content::text(format!("{}: {}", name, response.text))])
+    }
+}
+
+impl Router for JupyterRouter {
+    fn name(&self) -> String {
+        "jupyter".to_string()
+    }
+
+    fn instructions(&self) -> String {
+        self.instructions.clone()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        CapabilitiesBuilder::new().with_tools(false).build()
+    }
+
+    fn list_tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        _notifier: mpsc::Sender<JsonRpcMessage>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+        let this = self.clone();
+        let tool_name = tool_name.to_string();
+
+        Box::pin(async move {
+            match tool_name.as_str() {
+                "jupyter_execute" => this.execute(&arguments).await,
+                "jupyter_restart" => this.restart(&arguments).await,
+                _ => Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
+            }
+        })
+    }
+
+    fn list_resources(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    fn read_resource(
+        &self,
+        _uri: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        Box::pin(async move { Ok("".to_string()) })
+    }
+
+    fn list_prompts(&self) -> Vec<Prompt> {
+        vec![]
+    }
+
+    fn get_prompt(
+        &self,
+        prompt_name: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+        let prompt_name = prompt_name.to_string();
+        Box::pin(async move {
+            Err(PromptError::NotFound(format!(
+                "Prompt {} not found",
+                prompt_name
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_output_keeps_head_and_tail() {
+        assert_eq!(truncate_output("short", 10), "short");
+
+        let long = format!("{}{}", "a".repeat(50), "b".repeat(50));
+        let truncated = truncate_output(&long, 20);
+        assert!(truncated.starts_with("aaaaaaaaaa\n"));
+        assert!(truncated.ends_with("\nbbbbbbbbbb"));
+        assert!(truncated.contains("80 characters truncated"));
+    }
+
+    #[test]
+    fn test_file_stem_stays_in_artifact_dir() {
+        assert_eq!(file_stem("sales-2024"), "sales-2024");
+        assert_eq!(file_stem("../etc/passwd"), "___etc_passwd");
+    }
+
+    #[test]
+    fn test_bridge_response_defaults() {
+        let response: BridgeResponse = serde_json::from_str(r#"{"status": "ready"}"#).unwrap();
+        assert_eq!(response.status, "ready");
+        assert!(response.text.is_empty());
+        assert!(response.figures.is_empty());
+        assert_eq!(response.execution_count, None);
+    }
+}
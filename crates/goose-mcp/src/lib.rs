@@ -11,12 +11,16 @@ pub mod computercontroller;
 mod developer;
 pub mod google_drive;
 mod jetbrains;
+mod jupyter;
 mod memory;
+mod sql;
 mod tutorial;
 
 pub use computercontroller::ComputerControllerRouter;
 pub use developer::DeveloperRouter;
 pub use google_drive::GoogleDriveRouter;
 pub use jetbrains::JetBrainsRouter;
+pub use jupyter::JupyterRouter;
 pub use memory::MemoryRouter;
+pub use sql::SqlRouter;
 pub use tutorial::TutorialRouter;
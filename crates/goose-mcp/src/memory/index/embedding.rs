@@ -0,0 +1,75 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+/// Embeds memories with an OpenAI compatible `/embeddings` endpoint. Every agent writing to
+/// a shared index has to use the same model, or their vectors can't be compared.
+#[derive(Clone)]
+pub struct Embedder {
+    client: reqwest::Client,
+    host: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl Embedder {
+    pub fn new(host: &str, model: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            host: host.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+        }
+    }
+
+    /// Configured by `GOOSE_MEMORY_EMBEDDING_HOST`, `GOOSE_MEMORY_EMBEDDING_MODEL` and
+    /// `GOOSE_MEMORY_EMBEDDING_API_KEY`. Memories are sent to the host to be embedded, so there
+    /// is no default one: it has to be configured before anything leaves the machine.
+    pub fn from_env() -> Result<Self> {
+        let host = std::env::var("GOOSE_MEMORY_EMBEDDING_HOST").map_err(|_| {
+            anyhow!("GOOSE_MEMORY_EMBEDDING_HOST is required to embed memories for search")
+        })?;
+        let model = std::env::var("GOOSE_MEMORY_EMBEDDING_MODEL")
+            .unwrap_or_else(|_| DEFAULT_EMBEDDING_MODEL.to_string());
+        let api_key = std::env::var("GOOSE_MEMORY_EMBEDDING_API_KEY").ok();
+        Ok(Self::new(&host, &model, api_key))
+    }
+
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut request = self
+            .client
+            .post(format!("{}/embeddings", self.host))
+            .json(&json!({ "input": [text], "model": self.model }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .context("Failed to reach the embedding endpoint")?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Embedding request failed ({}): {}", status, body));
+        }
+        let response: EmbeddingResponse = response.json().await?;
+        response
+            .data
+            .into_iter()
+            .next()
+            .map(|data| data.embedding)
+            .ok_or_else(|| anyhow!("Embedding response had no embeddings"))
+    }
+}
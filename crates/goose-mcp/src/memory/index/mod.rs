@@ -0,0 +1,255 @@
+//! Semantic search over memories. Every remembered memory is embedded and stored in a vector
+//! index next to the category files, so memories can be found by meaning rather than by
+//! category. The index is a trait so memory can stay on the machine or live in a shared
+//! vector database that a whole organization's agents use.
+
+mod embedding;
+mod pgvector;
+mod qdrant;
+mod sqlite;
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+pub use embedding::Embedder;
+pub use pgvector::PgVectorIndex;
+pub use qdrant::QdrantIndex;
+pub use sqlite::SqliteIndex;
+
+/// Scope of memories shared across projects; local memories are scoped to their directory
+pub const GLOBAL_SCOPE: &str = "global";
+
+/// Scope of the local memories of `dir`. The directory is hashed rather than stored, so a
+/// shared index doesn't learn the paths of the projects on every machine using it.
+pub fn local_scope(dir: &Path) -> String {
+    format!("local:{:016x}", fingerprint(&[&dir.to_string_lossy()]))
+}
+
+/// A memory as the index stores it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryRecord {
+    pub scope: String,
+    pub category: String,
+    pub text: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl MemoryRecord {
+    /// Stable ID of the memory, so remembering the same thing twice replaces it and a memory
+    /// removed from its category file can be found in the index
+    pub fn id(&self) -> u64 {
+        record_id(&self.scope, &self.category, &self.text)
+    }
+}
+
+pub fn record_id(scope: &str, category: &str, text: &str) -> u64 {
+    fingerprint(&[scope, category, text])
+}
+
+/// FNV-1a over `parts`; stable across builds and platforms, unlike `DefaultHasher`
+pub fn fingerprint(parts: &[&str]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for byte in part.bytes().chain(std::iter::once(0)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// A memory found by a search, with its cosine similarity to the query
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredMemory {
+    #[serde(flatten)]
+    pub record: MemoryRecord,
+    pub score: f32,
+}
+
+#[async_trait]
+pub trait MemoryIndex: Send + Sync {
+    /// Add a memory, replacing the one with the same ID
+    async fn upsert(&self, record: &MemoryRecord, vector: &[f32]) -> Result<()>;
+
+    /// The memories closest to `vector` within `scopes`, most similar first
+    async fn search(
+        &self,
+        vector: &[f32],
+        scopes: &[String],
+        limit: usize,
+    ) -> Result<Vec<ScoredMemory>>;
+
+    /// Remove memories by ID; IDs that aren't indexed are ignored
+    async fn remove(&self, ids: &[u64]) -> Result<()>;
+
+    /// Remove every memory of a scope, or of one category in it
+    async fn clear(&self, scope: &str, category: Option<&str>) -> Result<()>;
+}
+
+/// IDs of the memories this machine has added to the index, so memories that were remembered
+/// before the index was configured, or while it was unreachable, are added at startup without
+/// embedding every memory again
+pub struct IndexedIds {
+    path: PathBuf,
+    ids: Mutex<BTreeSet<u64>>,
+}
+
+impl IndexedIds {
+    /// Load the IDs kept at `path`; a missing or unreadable file means nothing is indexed yet
+    pub fn load(path: PathBuf) -> Self {
+        let ids = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        Self {
+            path,
+            ids: Mutex::new(ids),
+        }
+    }
+
+    pub fn contains(&self, id: u64) -> bool {
+        self.ids.lock().unwrap().contains(&id)
+    }
+
+    pub fn insert(&self, id: u64) -> Result<()> {
+        let mut ids = self.ids.lock().unwrap();
+        if ids.insert(id) {
+            self.save(&ids)?;
+        }
+        Ok(())
+    }
+
+    pub fn remove(&self, removed: &[u64]) -> Result<()> {
+        let mut ids = self.ids.lock().unwrap();
+        let before = ids.len();
+        ids.retain(|id| !removed.contains(id));
+        if ids.len() != before {
+            self.save(&ids)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self, ids: &BTreeSet<u64>) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(ids)?)?;
+        Ok(())
+    }
+}
+
+/// Which index backs memory search, from `GOOSE_MEMORY_INDEX`:
+/// - `sqlite`: a local database at `GOOSE_MEMORY_INDEX_URL`, by default at `sqlite_path`
+/// - `qdrant`: a Qdrant server at `GOOSE_MEMORY_INDEX_URL`, with `GOOSE_MEMORY_INDEX_API_KEY`
+/// - `pgvector`: a Postgres database with the pgvector extension at `GOOSE_MEMORY_INDEX_URL`
+///
+/// Without it memories are only kept in their category files.
+pub fn index_from_env(sqlite_path: &Path) -> Result<Option<Arc<dyn MemoryIndex>>> {
+    let Ok(backend) = std::env::var("GOOSE_MEMORY_INDEX") else {
+        return Ok(None);
+    };
+    let url = std::env::var("GOOSE_MEMORY_INDEX_URL").ok();
+    let collection =
+        std::env::var("GOOSE_MEMORY_INDEX_COLLECTION").unwrap_or_else(|_| "goose_memory".into());
+
+    let index: Arc<dyn MemoryIndex> = match backend.to_lowercase().as_str() {
+        "sqlite" => {
+            let url = url.unwrap_or_else(|| format!("sqlite://{}?mode=rwc", sqlite_path.display()));
+            Arc::new(SqliteIndex::connect_lazy(&url)?)
+        }
+        "qdrant" => Arc::new(QdrantIndex::new(
+            url.as_deref().unwrap_or("http://localhost:6333"),
+            &collection,
+            std::env::var("GOOSE_MEMORY_INDEX_API_KEY").ok(),
+        )),
+        "pgvector" | "postgres" => {
+            let url = url.ok_or_else(|| {
+                anyhow!("GOOSE_MEMORY_INDEX_URL is required for the pgvector memory index")
+            })?;
+            Arc::new(PgVectorIndex::connect_lazy(&url, &collection)?)
+        }
+        other => return Err(anyhow!("Unknown memory index backend: {}", other)),
+    };
+    Ok(Some(index))
+}
+
+/// Cosine similarity of two vectors; 0 when either is all zeros or their lengths differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        dot += x * y;
+        norm_a += x * x;
+        norm_b += y * y;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_id_is_stable_and_field_separated() {
+        let record = MemoryRecord {
+            scope: GLOBAL_SCOPE.to_string(),
+            category: "editor".to_string(),
+            text: "Prefers vim keybindings".to_string(),
+            tags: vec![],
+        };
+        assert_eq!(
+            record.id(),
+            record_id("global", "editor", "Prefers vim keybindings")
+        );
+        assert_ne!(record_id("ab", "c", "x"), record_id("a", "bc", "x"));
+    }
+
+    #[test]
+    fn test_local_scope_hides_the_directory() {
+        let dir = Path::new("/home/alice/projects/secret-launch/.goose/memory");
+        let scope = local_scope(dir);
+        assert!(scope.starts_with("local:"));
+        assert!(!scope.contains("secret-launch"));
+        assert_eq!(scope, local_scope(dir));
+        assert_ne!(
+            scope,
+            local_scope(Path::new("/home/alice/other/.goose/memory"))
+        );
+    }
+
+    #[test]
+    fn test_indexed_ids_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("memory").join("indexed.json");
+
+        let indexed = IndexedIds::load(path.clone());
+        assert!(!indexed.contains(1));
+        indexed.insert(1).unwrap();
+        indexed.insert(2).unwrap();
+        indexed.remove(&[1]).unwrap();
+
+        let reloaded = IndexedIds::load(path);
+        assert!(!reloaded.contains(1));
+        assert!(reloaded.contains(2));
+    }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 0.0]), 0.0);
+    }
+}
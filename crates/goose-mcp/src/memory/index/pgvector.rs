@@ -0,0 +1,150 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use sqlx::postgres::{PgPool, PgPoolOptions};
+use sqlx::Row;
+use tokio::sync::OnceCell;
+
+use super::{MemoryIndex, MemoryRecord, ScoredMemory};
+
+/// A memory index in a Postgres table using the pgvector extension, for organizations that
+/// already run Postgres. The vector column has no fixed dimension, so switching embedding
+/// models needs the table cleared rather than migrated.
+pub struct PgVectorIndex {
+    pool: PgPool,
+    table: String,
+    schema: OnceCell<()>,
+}
+
+impl PgVectorIndex {
+    pub fn connect_lazy(url: &str, table: &str) -> Result<Self> {
+        if table.is_empty() || !table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(anyhow!("Invalid memory index table name: {}", table));
+        }
+        Ok(Self {
+            pool: PgPoolOptions::new().connect_lazy(url)?,
+            table: table.to_string(),
+            schema: OnceCell::new(),
+        })
+    }
+
+    async fn pool(&self) -> Result<&PgPool> {
+        self.schema
+            .get_or_try_init(|| async {
+                sqlx::query("CREATE EXTENSION IF NOT EXISTS vector")
+                    .execute(&self.pool)
+                    .await?;
+                sqlx::query(&format!(
+                    "CREATE TABLE IF NOT EXISTS {} (
+                        id BIGINT PRIMARY KEY,
+                        scope TEXT NOT NULL,
+                        category TEXT NOT NULL,
+                        text TEXT NOT NULL,
+                        tags TEXT[] NOT NULL,
+                        embedding vector NOT NULL
+                    )",
+                    self.table
+                ))
+                .execute(&self.pool)
+                .await?;
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+        Ok(&self.pool)
+    }
+}
+
+/// pgvector's text form of a vector, cast with `::vector` in the queries
+fn vector_literal(vector: &[f32]) -> String {
+    let values: Vec<String> = vector.iter().map(f32::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+#[async_trait]
+impl MemoryIndex for PgVectorIndex {
+    async fn upsert(&self, record: &MemoryRecord, vector: &[f32]) -> Result<()> {
+        // Postgres has no unsigned integers; the ID keeps its bits as a BIGINT
+        sqlx::query(&format!(
+            "INSERT INTO {} (id, scope, category, text, tags, embedding)
+             VALUES ($1, $2, $3, $4, $5, $6::vector)
+             ON CONFLICT (id) DO UPDATE SET tags = EXCLUDED.tags, embedding = EXCLUDED.embedding",
+            self.table
+        ))
+        .bind(record.id() as i64)
+        .bind(&record.scope)
+        .bind(&record.category)
+        .bind(&record.text)
+        .bind(&record.tags)
+        .bind(vector_literal(vector))
+        .execute(self.pool().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        vector: &[f32],
+        scopes: &[String],
+        limit: usize,
+    ) -> Result<Vec<ScoredMemory>> {
+        let rows = sqlx::query(&format!(
+            "SELECT scope, category, text, tags,
+                    (1 - (embedding <=> $1::vector))::REAL AS score
+             FROM {}
+             WHERE scope = ANY($2)
+             ORDER BY embedding <=> $1::vector
+             LIMIT $3",
+            self.table
+        ))
+        .bind(vector_literal(vector))
+        .bind(scopes)
+        .bind(limit as i64)
+        .fetch_all(self.pool().await?)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(ScoredMemory {
+                    record: MemoryRecord {
+                        scope: row.try_get("scope")?,
+                        category: row.try_get("category")?,
+                        text: row.try_get("text")?,
+                        tags: row.try_get("tags")?,
+                    },
+                    score: row.try_get("score")?,
+                })
+            })
+            .collect()
+    }
+
+    async fn remove(&self, ids: &[u64]) -> Result<()> {
+        let ids: Vec<i64> = ids.iter().map(|id| *id as i64).collect();
+        sqlx::query(&format!("DELETE FROM {} WHERE id = ANY($1)", self.table))
+            .bind(&ids)
+            .execute(self.pool().await?)
+            .await?;
+        Ok(())
+    }
+
+    async fn clear(&self, scope: &str, category: Option<&str>) -> Result<()> {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE scope = $1 AND ($2::TEXT IS NULL OR category = $2)",
+            self.table
+        ))
+        .bind(scope)
+        .bind(category)
+        .execute(self.pool().await?)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_vector_literal_and_table_name() {
+        assert_eq!(vector_literal(&[1.0, -0.5, 0.25]), "[1,-0.5,0.25]");
+        assert!(PgVectorIndex::connect_lazy("postgres://localhost/memory", "mem; DROP").is_err());
+    }
+}
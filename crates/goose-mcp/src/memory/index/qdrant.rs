@@ -0,0 +1,161 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{Method, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::OnceCell;
+
+use super::{MemoryIndex, MemoryRecord, ScoredMemory};
+
+#[derive(Deserialize)]
+struct SearchResponse {
+    result: Vec<SearchHit>,
+}
+
+#[derive(Deserialize)]
+struct SearchHit {
+    score: f32,
+    payload: MemoryRecord,
+}
+
+/// A memory index in a Qdrant collection, for memory shared by many agents. The collection
+/// is created with cosine distance on the first write, sized to the embedding model.
+pub struct QdrantIndex {
+    client: reqwest::Client,
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+    created: OnceCell<()>,
+}
+
+impl QdrantIndex {
+    pub fn new(url: &str, collection: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            collection: collection.to_string(),
+            api_key,
+            created: OnceCell::new(),
+        }
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self.client.request(
+            method,
+            format!("{}/collections/{}{}", self.url, self.collection, path),
+        );
+        match &self.api_key {
+            Some(api_key) => request.header("api-key", api_key),
+            None => request,
+        }
+    }
+
+    /// The response if it was successful, otherwise an error with Qdrant's explanation
+    async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Qdrant request failed ({}): {}", status, body));
+        }
+        Ok(response)
+    }
+
+    async fn ensure_collection(&self, dimensions: usize) -> Result<()> {
+        self.created
+            .get_or_try_init(|| async {
+                let existing = self.request(Method::GET, "").send().await?;
+                if existing.status() == StatusCode::NOT_FOUND {
+                    let created = self
+                        .request(Method::PUT, "")
+                        .json(&json!({
+                            "vectors": { "size": dimensions, "distance": "Cosine" }
+                        }))
+                        .send()
+                        .await?;
+                    Self::check(created).await?;
+                }
+                Ok::<_, anyhow::Error>(())
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, selector: Value) -> Result<()> {
+        let response = self
+            .request(Method::POST, "/points/delete?wait=true")
+            .json(&selector)
+            .send()
+            .await?;
+        // Nothing was ever remembered, so there's nothing to delete
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        Self::check(response).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl MemoryIndex for QdrantIndex {
+    async fn upsert(&self, record: &MemoryRecord, vector: &[f32]) -> Result<()> {
+        self.ensure_collection(vector.len()).await?;
+        let response = self
+            .request(Method::PUT, "/points?wait=true")
+            .json(&json!({
+                "points": [{
+                    "id": record.id(),
+                    "vector": vector,
+                    "payload": record,
+                }]
+            }))
+            .send()
+            .await?;
+        Self::check(response).await?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        vector: &[f32],
+        scopes: &[String],
+        limit: usize,
+    ) -> Result<Vec<ScoredMemory>> {
+        let response = self
+            .request(Method::POST, "/points/search")
+            .json(&json!({
+                "vector": vector,
+                "limit": limit,
+                "with_payload": true,
+                "filter": { "must": [{ "key": "scope", "match": { "any": scopes } }] }
+            }))
+            .send()
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(Vec::new());
+        }
+        let response: SearchResponse = Self::check(response).await?.json().await?;
+        Ok(response
+            .result
+            .into_iter()
+            .map(|hit| ScoredMemory {
+                record: hit.payload,
+                score: hit.score,
+            })
+            .collect())
+    }
+
+    async fn remove(&self, ids: &[u64]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        self.delete(json!({ "points": ids })).await
+    }
+
+    async fn clear(&self, scope: &str, category: Option<&str>) -> Result<()> {
+        let mut must = vec![json!({ "key": "scope", "match": { "value": scope } })];
+        if let Some(category) = category {
+            must.push(json!({ "key": "category", "match": { "value": category } }));
+        }
+        self.delete(json!({ "filter": { "must": must } })).await
+    }
+}
@@ -0,0 +1,180 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use sqlx::sqlite::{SqlitePool, SqlitePoolOptions};
+use sqlx::Row;
+use tokio::sync::OnceCell;
+
+use super::{cosine_similarity, MemoryIndex, MemoryRecord, ScoredMemory};
+
+/// A memory index in a local SQLite database. Vectors are stored as little endian `f32`
+/// blobs and compared in process, which is plenty fast for one person's memories.
+pub struct SqliteIndex {
+    pool: SqlitePool,
+    schema: OnceCell<()>,
+}
+
+impl SqliteIndex {
+    pub fn connect_lazy(url: &str) -> Result<Self> {
+        // SQLite serializes writes anyway, and one connection keeps `:memory:` databases whole
+        Ok(Self {
+            pool: SqlitePoolOptions::new()
+                .max_connections(1)
+                .connect_lazy(url)?,
+            schema: OnceCell::new(),
+        })
+    }
+
+    async fn pool(&self) -> Result<&SqlitePool> {
+        self.schema
+            .get_or_try_init(|| async {
+                sqlx::query(
+                    "CREATE TABLE IF NOT EXISTS memories (
+                        id TEXT PRIMARY KEY,
+                        scope TEXT NOT NULL,
+                        category TEXT NOT NULL,
+                        text TEXT NOT NULL,
+                        tags TEXT NOT NULL,
+                        vector BLOB NOT NULL
+                    )",
+                )
+                .execute(&self.pool)
+                .await
+                .map(|_| ())
+            })
+            .await?;
+        Ok(&self.pool)
+    }
+}
+
+fn encode(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+fn decode(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[async_trait]
+impl MemoryIndex for SqliteIndex {
+    async fn upsert(&self, record: &MemoryRecord, vector: &[f32]) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO memories (id, scope, category, text, tags, vector)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(format!("{:016x}", record.id()))
+        .bind(&record.scope)
+        .bind(&record.category)
+        .bind(&record.text)
+        .bind(serde_json::to_string(&record.tags)?)
+        .bind(encode(vector))
+        .execute(self.pool().await?)
+        .await?;
+        Ok(())
+    }
+
+    async fn search(
+        &self,
+        vector: &[f32],
+        scopes: &[String],
+        limit: usize,
+    ) -> Result<Vec<ScoredMemory>> {
+        let pool = self.pool().await?;
+        let mut found = Vec::new();
+        for scope in scopes {
+            let rows =
+                sqlx::query("SELECT category, text, tags, vector FROM memories WHERE scope = ?")
+                    .bind(scope)
+                    .fetch_all(pool)
+                    .await?;
+            for row in rows {
+                let stored: Vec<u8> = row.try_get("vector")?;
+                let tags: String = row.try_get("tags")?;
+                found.push(ScoredMemory {
+                    score: cosine_similarity(vector, &decode(&stored)),
+                    record: MemoryRecord {
+                        scope: scope.clone(),
+                        category: row.try_get("category")?,
+                        text: row.try_get("text")?,
+                        tags: serde_json::from_str(&tags).unwrap_or_default(),
+                    },
+                });
+            }
+        }
+        found.sort_by(|a, b| b.score.total_cmp(&a.score));
+        found.truncate(limit);
+        Ok(found)
+    }
+
+    async fn remove(&self, ids: &[u64]) -> Result<()> {
+        let pool = self.pool().await?;
+        for id in ids {
+            sqlx::query("DELETE FROM memories WHERE id = ?")
+                .bind(format!("{:016x}", id))
+                .execute(pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn clear(&self, scope: &str, category: Option<&str>) -> Result<()> {
+        let pool = self.pool().await?;
+        match category {
+            Some(category) => {
+                sqlx::query("DELETE FROM memories WHERE scope = ? AND category = ?")
+                    .bind(scope)
+                    .bind(category)
+                    .execute(pool)
+                    .await?
+            }
+            None => {
+                sqlx::query("DELETE FROM memories WHERE scope = ?")
+                    .bind(scope)
+                    .execute(pool)
+                    .await?
+            }
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(category: &str, text: &str) -> MemoryRecord {
+        MemoryRecord {
+            scope: "global".to_string(),
+            category: category.to_string(),
+            text: text.to_string(),
+            tags: vec!["pref".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_by_similarity_and_clear_removes() {
+        let index = SqliteIndex::connect_lazy("sqlite::memory:").unwrap();
+        let vim = record("editor", "Prefers vim keybindings");
+        let tabs = record("style", "Indents with tabs");
+        index.upsert(&vim, &[1.0, 0.0, 0.0]).await.unwrap();
+        index.upsert(&tabs, &[0.0, 1.0, 0.0]).await.unwrap();
+        // Remembering the same memory again replaces it
+        index.upsert(&vim, &[0.9, 0.1, 0.0]).await.unwrap();
+
+        let scopes = vec!["global".to_string()];
+        let found = index.search(&[1.0, 0.0, 0.0], &scopes, 5).await.unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].record, vim);
+        assert!(found[0].score > found[1].score);
+
+        index.remove(&[tabs.id()]).await.unwrap();
+        index.clear("global", Some("editor")).await.unwrap();
+        assert!(index
+            .search(&[1.0, 0.0, 0.0], &scopes, 5)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
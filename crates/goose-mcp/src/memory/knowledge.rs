@@ -0,0 +1,512 @@
+//! Background jobs that keep knowledge sources searchable. Each source is synced into the
+//! memory index on its own schedule under a `knowledge:<name>` scope, so recipes can pick the
+//! sources they search. Only documents whose content changed since the last sync are
+//! embedded again, and documents that disappeared are removed from the index. Every goose
+//! process on a machine starts these jobs, so a source is synced under a file lock and
+//! processes that find it held skip that round.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use fs2::FileExt;
+use ignore::WalkBuilder;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::index::{fingerprint, Embedder, MemoryIndex, MemoryRecord};
+
+/// How often a source is synced unless it says otherwise
+const DEFAULT_SYNC_SECS: u64 = 3600;
+/// Largest chunk of a document embedded as one record
+const CHUNK_CHARS: usize = 1500;
+/// Files larger than this are skipped rather than indexed
+const MAX_DOCUMENT_BYTES: u64 = 1_000_000;
+const DEFAULT_EXTENSIONS: &[&str] = &["md", "markdown", "txt", "rst", "adoc", "html", "htm"];
+
+static SCRIPT_OR_STYLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?is)<(script|style)[^>]*>.*?</(script|style)>").unwrap());
+static BLOCK_END: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<br\s*/?>|</(p|div|h[1-6]|li|tr|pre|table)>").unwrap());
+static TAG: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").unwrap());
+static BLANK_LINES: Lazy<Regex> = Lazy::new(|| Regex::new(r"\n\s*\n(\s*\n)+").unwrap());
+
+/// Where a source's documents come from
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SourceKind {
+    /// Text files in a local directory, honoring `.gitignore`
+    Directory { path: PathBuf },
+    /// The HTML pages of a Confluence space export
+    Confluence { path: PathBuf },
+    /// A git repository, shallow cloned and pulled before every sync
+    Git {
+        url: String,
+        #[serde(default)]
+        branch: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct KnowledgeSource {
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: SourceKind,
+    /// Seconds between syncs
+    #[serde(default = "default_sync_secs")]
+    pub every_secs: u64,
+    /// File extensions to index from directories and repositories
+    #[serde(default)]
+    pub extensions: Option<Vec<String>>,
+}
+
+fn default_sync_secs() -> u64 {
+    DEFAULT_SYNC_SECS
+}
+
+impl KnowledgeSource {
+    /// The index scope the source's documents are stored under
+    pub fn scope(&self) -> String {
+        scope_of(&self.name)
+    }
+}
+
+pub fn scope_of(name: &str) -> String {
+    format!("knowledge:{}", name)
+}
+
+/// Parse the JSON list of sources; names double as file names, so they are checked here
+pub fn parse_sources(json: &str) -> Result<Vec<KnowledgeSource>> {
+    let sources: Vec<KnowledgeSource> =
+        serde_json::from_str(json).context("Invalid knowledge sources")?;
+    for source in &sources {
+        if source.name.is_empty()
+            || !source
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow!(
+                "Knowledge source names may only use letters, digits, '-' and '_': {:?}",
+                source.name
+            ));
+        }
+    }
+    Ok(sources)
+}
+
+/// Sources configured by `GOOSE_KNOWLEDGE_SOURCES`, e.g.
+/// `[{"name": "handbook", "type": "git", "url": "https://github.com/org/handbook"}]`
+pub fn sources_from_env() -> Result<Vec<KnowledgeSource>> {
+    match std::env::var("GOOSE_KNOWLEDGE_SOURCES") {
+        Ok(json) => parse_sources(&json),
+        Err(_) => Ok(Vec::new()),
+    }
+}
+
+/// What a source contained at its last sync, to tell which documents changed since
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SyncState {
+    documents: BTreeMap<String, DocumentState>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DocumentState {
+    hash: u64,
+    chunks: Vec<u64>,
+}
+
+/// Documents touched by one sync
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub updated: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+}
+
+pub struct KnowledgeSync {
+    index: Arc<dyn MemoryIndex>,
+    embedder: Embedder,
+    /// Sync state and git checkouts of every source
+    state_dir: PathBuf,
+}
+
+impl KnowledgeSync {
+    pub fn new(index: Arc<dyn MemoryIndex>, embedder: Embedder, state_dir: PathBuf) -> Self {
+        Self {
+            index,
+            embedder,
+            state_dir,
+        }
+    }
+
+    /// Sync every source now and then on its schedule, each on its own task. A failed sync is
+    /// logged and tried again at the next interval.
+    pub fn spawn(self: Arc<Self>, sources: Vec<KnowledgeSource>) {
+        for source in sources {
+            let sync = Arc::clone(&self);
+            tokio::spawn(async move {
+                let every = Duration::from_secs(source.every_secs.max(60));
+                loop {
+                    match sync.sync(&source).await {
+                        Ok(None) => tracing::debug!(
+                            "Knowledge source {} is being synced by another process",
+                            source.name
+                        ),
+                        Ok(Some(report)) => tracing::info!(
+                            "Synced knowledge source {}: {} updated, {} unchanged, {} removed",
+                            source.name,
+                            report.updated,
+                            report.unchanged,
+                            report.removed
+                        ),
+                        Err(e) => {
+                            tracing::warn!("Failed to sync knowledge source {}: {}", source.name, e)
+                        }
+                    }
+                    tokio::time::sleep(every).await;
+                }
+            });
+        }
+    }
+
+    /// Sync a source, or return `None` without doing anything when another process is
+    /// syncing it
+    pub async fn sync(&self, source: &KnowledgeSource) -> Result<Option<SyncReport>> {
+        tokio::fs::create_dir_all(&self.state_dir).await?;
+        let lock = std::fs::File::create(self.state_dir.join(format!("{}.lock", source.name)))?;
+        if lock.try_lock_exclusive().is_err() {
+            return Ok(None);
+        }
+        let result = self.sync_locked(source).await;
+        let _ = FileExt::unlock(&lock);
+        result.map(Some)
+    }
+
+    async fn sync_locked(&self, source: &KnowledgeSource) -> Result<SyncReport> {
+        let root = self.checkout(source).await?;
+        let documents = {
+            let source = source.clone();
+            tokio::task::spawn_blocking(move || read_documents(&source, &root)).await??
+        };
+
+        let state_path = self.state_dir.join(format!("{}.json", source.name));
+        let mut state: SyncState = match tokio::fs::read(&state_path).await {
+            Ok(content) => serde_json::from_slice(&content).unwrap_or_default(),
+            Err(_) => SyncState::default(),
+        };
+        // Keep whatever was synced before a failure, so the next sync picks up from there
+        let result = self.apply(source, &documents, &mut state).await;
+        tokio::fs::write(&state_path, serde_json::to_vec(&state)?).await?;
+        result
+    }
+
+    async fn apply(
+        &self,
+        source: &KnowledgeSource,
+        documents: &BTreeMap<String, String>,
+        state: &mut SyncState,
+    ) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        let gone: Vec<String> = state
+            .documents
+            .keys()
+            .filter(|path| !documents.contains_key(*path))
+            .cloned()
+            .collect();
+        for path in gone {
+            if let Some(document) = state.documents.get(&path) {
+                self.index.remove(&document.chunks).await?;
+            }
+            state.documents.remove(&path);
+            report.removed += 1;
+        }
+
+        for (path, text) in documents {
+            let hash = fingerprint(&[text]);
+            if let Some(document) = state.documents.get(path) {
+                if document.hash == hash {
+                    report.unchanged += 1;
+                    continue;
+                }
+                self.index.remove(&document.chunks).await?;
+                state.documents.remove(path);
+            }
+
+            let mut chunks = Vec::new();
+            for record in chunk_records(source, path, text) {
+                let vector = self.embedder.embed(&record.text).await?;
+                self.index.upsert(&record, &vector).await?;
+                chunks.push(record.id());
+            }
+            state
+                .documents
+                .insert(path.clone(), DocumentState { hash, chunks });
+            report.updated += 1;
+        }
+        Ok(report)
+    }
+
+    /// The directory holding the source's documents, cloning or pulling git sources first
+    async fn checkout(&self, source: &KnowledgeSource) -> Result<PathBuf> {
+        let (url, branch) = match &source.kind {
+            SourceKind::Directory { path } | SourceKind::Confluence { path } => {
+                if !path.is_dir() {
+                    return Err(anyhow!("{} is not a directory", path.display()));
+                }
+                return Ok(path.clone());
+            }
+            SourceKind::Git { url, branch } => (url, branch),
+        };
+
+        let checkout = self.state_dir.join("repos").join(&source.name);
+        if checkout.join(".git").is_dir() {
+            let refspec = branch.as_deref().unwrap_or("HEAD");
+            git(&checkout, &["fetch", "--depth", "1", "origin", refspec]).await?;
+            git(&checkout, &["reset", "--hard", "FETCH_HEAD"]).await?;
+        } else {
+            tokio::fs::create_dir_all(&checkout).await?;
+            let mut args = vec!["clone", "--depth", "1"];
+            if let Some(branch) = branch {
+                args.extend(["--branch", branch.as_str()]);
+            }
+            args.extend([url.as_str(), "."]);
+            git(&checkout, &args).await?;
+        }
+        Ok(checkout)
+    }
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Result<()> {
+    let output = tokio::process::Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// The records a document is indexed as. Each chunk's category is the document's path with the
+/// chunk's position, so its ID differs from that of the same text in another document or
+/// elsewhere in the same one.
+fn chunk_records(source: &KnowledgeSource, path: &str, text: &str) -> Vec<MemoryRecord> {
+    chunk_text(text, CHUNK_CHARS)
+        .into_iter()
+        .enumerate()
+        .map(|(position, chunk)| MemoryRecord {
+            scope: source.scope(),
+            category: format!("{}#{}", path, position + 1),
+            text: chunk,
+            tags: vec![source.name.clone()],
+        })
+        .collect()
+}
+
+/// The text of every document in a source, keyed by path relative to its root
+fn read_documents(source: &KnowledgeSource, root: &Path) -> Result<BTreeMap<String, String>> {
+    let extensions: Vec<String> = match (&source.kind, &source.extensions) {
+        (SourceKind::Confluence { .. }, _) => vec!["html".into(), "htm".into()],
+        (_, Some(extensions)) => extensions.clone(),
+        (_, None) => DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+    };
+
+    let mut documents = BTreeMap::new();
+    for entry in WalkBuilder::new(root).hidden(true).build() {
+        let entry = entry?;
+        let path = entry.path();
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+        if !entry.file_type().is_some_and(|t| t.is_file())
+            || !extensions.contains(&extension)
+            || entry.metadata().map(|m| m.len()).unwrap_or(0) > MAX_DOCUMENT_BYTES
+        {
+            continue;
+        }
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let text = if extension.starts_with("htm") {
+            html_to_text(&content)
+        } else {
+            content
+        };
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        documents.insert(relative.to_string_lossy().replace('\\', "/"), text);
+    }
+    Ok(documents)
+}
+
+/// The readable text of an HTML page, with block elements on their own lines
+pub fn html_to_text(html: &str) -> String {
+    let text = SCRIPT_OR_STYLE.replace_all(html, "");
+    let text = BLOCK_END.replace_all(&text, "\n\n");
+    let text = TAG.replace_all(&text, "");
+    let text = text
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&");
+    BLANK_LINES.replace_all(&text, "\n\n").trim().to_string()
+}
+
+/// Split text into chunks of whole paragraphs up to `max_chars`, cutting paragraphs that are
+/// longer than that on character boundaries
+pub fn chunk_text(text: &str, max_chars: usize) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for paragraph in text.split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        if !current.is_empty() && current.len() + paragraph.len() + 2 > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if paragraph.len() > max_chars {
+            let chars: Vec<char> = paragraph.chars().collect();
+            for piece in chars.chunks(max_chars) {
+                chunks.push(piece.iter().collect());
+            }
+            continue;
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::index::SqliteIndex;
+
+    #[test]
+    fn test_parse_sources() {
+        let sources = parse_sources(
+            r#"[
+                {"name": "handbook", "type": "git", "url": "https://example.com/handbook.git"},
+                {"name": "wiki", "type": "confluence", "path": "/exports/ENG", "every_secs": 600}
+            ]"#,
+        )
+        .unwrap();
+        assert_eq!(sources.len(), 2);
+        assert!(matches!(
+            sources[0].kind,
+            SourceKind::Git { branch: None, .. }
+        ));
+        assert_eq!(sources[0].every_secs, DEFAULT_SYNC_SECS);
+        assert_eq!(sources[1].scope(), "knowledge:wiki");
+
+        assert!(parse_sources(r#"[{"name": "../up", "type": "directory", "path": "/"}]"#).is_err());
+    }
+
+    #[test]
+    fn test_html_to_text() {
+        let html = "<html><head><style>p { color: red }</style></head><body>\
+            <h1>Deploys</h1><p>Run <code>make deploy</code> &amp; wait.</p>\
+            <script>track()</script><ul><li>One</li><li>Two</li></ul></body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Deploys\n\nRun make deploy & wait.\n\nOne\n\nTwo"
+        );
+    }
+
+    #[test]
+    fn test_chunk_text() {
+        let text = "aaaa\n\nbbbb\n\ncccccccccccc\n\ndd";
+        assert_eq!(
+            chunk_text(text, 10),
+            vec!["aaaa\n\nbbbb", "cccccccccc", "cc", "dd"]
+        );
+    }
+
+    #[test]
+    fn test_chunk_records_are_keyed_by_document_and_position() {
+        let source = KnowledgeSource {
+            name: "docs".to_string(),
+            kind: SourceKind::Directory {
+                path: PathBuf::from("/docs"),
+            },
+            every_secs: DEFAULT_SYNC_SECS,
+            extensions: None,
+        };
+        let text = format!("{}\n\n{}", "a".repeat(CHUNK_CHARS), "a".repeat(CHUNK_CHARS));
+        let records = chunk_records(&source, "guides/setup.md", &text);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].category, "guides/setup.md#1");
+        assert_ne!(records[0].id(), records[1].id());
+
+        let other = chunk_records(&source, "guides/faq.md", &text);
+        assert_ne!(records[0].id(), other[0].id());
+    }
+
+    #[tokio::test]
+    async fn test_sync_is_skipped_while_another_process_holds_the_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let sync = KnowledgeSync::new(
+            Arc::new(SqliteIndex::connect_lazy("sqlite::memory:").unwrap()),
+            Embedder::new("http://127.0.0.1:9", "test-embedding", None),
+            dir.path().join("state"),
+        );
+        let source = KnowledgeSource {
+            name: "docs".to_string(),
+            kind: SourceKind::Directory {
+                path: dir.path().join("missing"),
+            },
+            every_secs: DEFAULT_SYNC_SECS,
+            extensions: None,
+        };
+
+        std::fs::create_dir_all(dir.path().join("state")).unwrap();
+        let held = std::fs::File::create(dir.path().join("state/docs.lock")).unwrap();
+        held.lock_exclusive().unwrap();
+        assert!(sync.sync(&source).await.unwrap().is_none());
+
+        // Once the lock is free the sync runs, and fails on the missing directory
+        FileExt::unlock(&held).unwrap();
+        assert!(sync.sync(&source).await.is_err());
+    }
+
+    #[test]
+    fn test_read_documents_from_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("guides")).unwrap();
+        std::fs::write(dir.path().join("guides/setup.md"), "# Setup").unwrap();
+        std::fs::write(dir.path().join("page.html"), "<p>Hello</p>").unwrap();
+        std::fs::write(dir.path().join("logo.png"), [0u8, 1, 2]).unwrap();
+
+        let source = KnowledgeSource {
+            name: "docs".to_string(),
+            kind: SourceKind::Directory {
+                path: dir.path().to_path_buf(),
+            },
+            every_secs: DEFAULT_SYNC_SECS,
+            extensions: None,
+        };
+        let documents = read_documents(&source, dir.path()).unwrap();
+        assert_eq!(
+            documents.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("guides/setup.md".to_string(), "# Setup".to_string()),
+                ("page.html".to_string(), "Hello".to_string()),
+            ]
+        );
+    }
+}
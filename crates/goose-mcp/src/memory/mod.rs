@@ -2,6 +2,7 @@ use async_trait::async_trait;
 use etcetera::{choose_app_strategy, AppStrategy};
 use indoc::formatdoc;
 use serde_json::{json, Value};
+use std::sync::Arc;
 use std::{
     collections::HashMap,
     fs,
@@ -23,6 +24,11 @@ use mcp_core::{
 use mcp_server::router::CapabilitiesBuilder;
 use mcp_server::Router;
 
+mod index;
+mod knowledge;
+
+use index::{Embedder, IndexedIds, MemoryIndex, MemoryRecord, GLOBAL_SCOPE};
+
 // MemoryRouter implementation
 #[derive(Clone)]
 pub struct MemoryRouter {
@@ -30,6 +36,14 @@ pub struct MemoryRouter {
     instructions: String,
     global_memory_dir: PathBuf,
     local_memory_dir: PathBuf,
+    /// Semantic search over memories, when `GOOSE_MEMORY_INDEX` configures an index
+    index: Option<Arc<dyn MemoryIndex>>,
+    /// Set whenever `index` is
+    embedder: Option<Embedder>,
+    /// Memories this machine has added to the index
+    indexed: Arc<IndexedIds>,
+    /// Names of the knowledge sources synced into the index
+    knowledge_sources: Vec<String>,
 }
 
 impl Default for MemoryRouter {
@@ -244,18 +258,133 @@ impl MemoryRouter {
         fs::create_dir_all(&global_memory_dir).unwrap();
         fs::create_dir_all(&local_memory_dir).unwrap();
 
+        // Kept beside the memory directory, where every file is a category
+        let (index, embedder) =
+            index::index_from_env(&global_memory_dir.with_file_name("memory_index.db"))
+                .and_then(|index| match index {
+                    Some(index) => Ok(Some((index, Embedder::from_env()?))),
+                    None => Ok(None),
+                })
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Memory search is disabled: {}", e);
+                    None
+                })
+                .unzip();
+        let indexed = Arc::new(IndexedIds::load(
+            choose_app_strategy(crate::APP_STRATEGY.clone())
+                .map(|strategy| strategy.in_cache_dir("memory"))
+                .unwrap_or_else(|_| PathBuf::from(".cache/goose/memory"))
+                .join("indexed.json"),
+        ));
+
+        let mut tools = vec![
+            remember_memory,
+            retrieve_memories,
+            remove_memory_category,
+            remove_specific_memory,
+        ];
+        let mut instructions = instructions;
+        if index.is_some() {
+            tools.push(Tool::new(
+                "search_memories",
+                "Finds the memories closest in meaning to a query across all categories",
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {"type": "string"},
+                        "limit": {"type": "integer", "minimum": 1},
+                        "is_global": {"type": "boolean"}
+                    },
+                    "required": ["query"]
+                }),
+                Some(ToolAnnotations {
+                    title: Some("Search Memories".to_string()),
+                    read_only_hint: true,
+                    destructive_hint: false,
+                    idempotent_hint: true,
+                    open_world_hint: false,
+                }),
+            ));
+            instructions.push_str(&formatdoc! {r#"
+                Searching Memories:
+                - When you don't know which category holds what you need, search memories by meaning.
+                - Use: `search_memories(query="how do we format code")`
+                - Note: Leave out is_global to search local and global memories together.
+                "#});
+        }
+
+        let sources = match &index {
+            Some(_) => knowledge::sources_from_env().unwrap_or_else(|e| {
+                tracing::warn!("Knowledge sources are disabled: {}", e);
+                Vec::new()
+            }),
+            None => Vec::new(),
+        };
+        let knowledge_sources: Vec<String> = sources.iter().map(|s| s.name.clone()).collect();
+        if let (Some(index), Some(embedder), false) = (&index, &embedder, sources.is_empty()) {
+            if tokio::runtime::Handle::try_current().is_ok() {
+                let state_dir = choose_app_strategy(crate::APP_STRATEGY.clone())
+                    .map(|strategy| strategy.in_cache_dir("knowledge"))
+                    .unwrap_or_else(|_| PathBuf::from(".cache/goose/knowledge"));
+                Arc::new(knowledge::KnowledgeSync::new(
+                    Arc::clone(index),
+                    embedder.clone(),
+                    state_dir,
+                ))
+                .spawn(sources);
+            }
+            tools.push(Tool::new(
+                "search_knowledge",
+                format!(
+                    "Finds the passages closest in meaning to a query in the synced knowledge \
+                     sources: {}",
+                    knowledge_sources.join(", ")
+                ),
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "query": {"type": "string"},
+                        "sources": {
+                            "type": "array",
+                            "items": {"type": "string", "enum": knowledge_sources},
+                            "description": "Sources to search, all of them when left out"
+                        },
+                        "limit": {"type": "integer", "minimum": 1}
+                    },
+                    "required": ["query"]
+                }),
+                Some(ToolAnnotations {
+                    title: Some("Search Knowledge".to_string()),
+                    read_only_hint: true,
+                    destructive_hint: false,
+                    idempotent_hint: true,
+                    open_world_hint: false,
+                }),
+            ));
+        }
+
         let mut memory_router = Self {
-            tools: vec![
-                remember_memory,
-                retrieve_memories,
-                remove_memory_category,
-                remove_specific_memory,
-            ],
+            tools,
             instructions: instructions.clone(),
             global_memory_dir,
             local_memory_dir,
+            index,
+            embedder,
+            indexed,
+            knowledge_sources,
         };
 
+        if memory_router.index.is_some() && tokio::runtime::Handle::try_current().is_ok() {
+            let router = memory_router.clone();
+            tokio::spawn(async move {
+                match router.backfill_index().await {
+                    Ok(0) => {}
+                    Ok(added) => tracing::info!("Added {} memories to the search index", added),
+                    Err(e) => tracing::warn!("Failed to add memories to the search index: {}", e),
+                }
+            });
+        }
+
         let retrieved_global_memories = memory_router.retrieve_all(true);
         let retrieved_local_memories = memory_router.retrieve_all(false);
 
@@ -310,6 +439,148 @@ impl MemoryRouter {
         &self.instructions
     }
 
+    /// Index scope of global memories, or of this project's local ones
+    fn scope(&self, is_global: bool) -> String {
+        if is_global {
+            GLOBAL_SCOPE.to_string()
+        } else {
+            index::local_scope(&self.local_memory_dir)
+        }
+    }
+
+    /// Every memory in the category files, as the index stores it
+    fn stored_memories(&self, is_global: bool) -> io::Result<Vec<MemoryRecord>> {
+        let base_dir = if is_global {
+            &self.global_memory_dir
+        } else {
+            &self.local_memory_dir
+        };
+        let mut records = Vec::new();
+        if !base_dir.exists() {
+            return Ok(records);
+        }
+        let scope = self.scope(is_global);
+        for entry in fs::read_dir(base_dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_file() {
+                continue;
+            }
+            let category = entry.file_name().to_string_lossy().replace(".txt", "");
+            for memory in fs::read_to_string(entry.path())?.split("\n\n") {
+                let (tags, text) = match memory.split_once('\n') {
+                    Some((tags, text)) if tags.starts_with('#') => (
+                        tags[1..].split_whitespace().map(String::from).collect(),
+                        text,
+                    ),
+                    _ => (Vec::new(), memory),
+                };
+                if text.trim().is_empty() {
+                    continue;
+                }
+                records.push(MemoryRecord {
+                    scope: scope.clone(),
+                    category: category.clone(),
+                    text: text.to_string(),
+                    tags,
+                });
+            }
+        }
+        Ok(records)
+    }
+
+    /// Add the memories that aren't in the index yet, such as those remembered before it was
+    /// configured or while it couldn't be reached
+    async fn backfill_index(&self) -> anyhow::Result<usize> {
+        let (Some(index), Some(embedder)) = (&self.index, &self.embedder) else {
+            return Ok(0);
+        };
+        let mut added = 0;
+        for is_global in [true, false] {
+            for record in self.stored_memories(is_global)? {
+                let id = record.id();
+                if self.indexed.contains(id) {
+                    continue;
+                }
+                let vector = embedder.embed(&record.text).await?;
+                index.upsert(&record, &vector).await?;
+                self.indexed.insert(id)?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    async fn index_memory(
+        &self,
+        category: &str,
+        data: &str,
+        tags: &[&str],
+        is_global: bool,
+    ) -> anyhow::Result<()> {
+        let (Some(index), Some(embedder)) = (&self.index, &self.embedder) else {
+            return Ok(());
+        };
+        let record = MemoryRecord {
+            scope: self.scope(is_global),
+            category: category.to_string(),
+            text: data.to_string(),
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        };
+        let vector = embedder.embed(data).await?;
+        index.upsert(&record, &vector).await?;
+        self.indexed.insert(record.id())
+    }
+
+    /// The memories of a category containing `memory_content`, as they were remembered
+    fn find_memories(
+        &self,
+        category: &str,
+        memory_content: &str,
+        is_global: bool,
+    ) -> io::Result<Vec<String>> {
+        let memory_file_path = self.get_memory_file(category, is_global);
+        if !memory_file_path.exists() {
+            return Ok(Vec::new());
+        }
+        Ok(fs::read_to_string(memory_file_path)?
+            .split("\n\n")
+            .filter(|entry| entry.contains(memory_content))
+            .map(|entry| match entry.split_once('\n') {
+                Some((tags, data)) if tags.starts_with('#') => data.to_string(),
+                _ => entry.to_string(),
+            })
+            .collect())
+    }
+
+    async fn search(
+        &self,
+        query: &str,
+        limit: usize,
+        is_global: Option<bool>,
+    ) -> anyhow::Result<Vec<index::ScoredMemory>> {
+        let scopes = match is_global {
+            Some(is_global) => vec![self.scope(is_global)],
+            None => vec![self.scope(true), self.scope(false)],
+        };
+        self.search_scopes(query, limit, scopes).await
+    }
+
+    async fn search_scopes(
+        &self,
+        query: &str,
+        limit: usize,
+        scopes: Vec<String>,
+    ) -> anyhow::Result<Vec<index::ScoredMemory>> {
+        let (Some(index), Some(embedder)) = (&self.index, &self.embedder) else {
+            return Err(anyhow::anyhow!("No memory index is configured"));
+        };
+        let vector = embedder.embed(query).await?;
+        // Only ever hand back what was asked for, whatever the backend's filtering does
+        let mut found = index.search(&vector, &scopes, limit).await?;
+        found.retain(|memory| scopes.contains(&memory.record.scope));
+        Ok(found)
+    }
+
     fn get_memory_file(&self, category: &str, is_global: bool) -> PathBuf {
         // Defaults to local memory if no is_global flag is provided
         let base_dir = if is_global {
@@ -461,7 +732,15 @@ impl MemoryRouter {
                     )
                 })?;
                 self.remember("context", args.category, data, &args.tags, args.is_global)?;
-                Ok(format!("Stored memory in category: {}", args.category))
+                let mut result = format!("Stored memory in category: {}", args.category);
+                if let Err(e) = self
+                    .index_memory(args.category, data, &args.tags, args.is_global)
+                    .await
+                {
+                    tracing::warn!("Failed to index memory: {}", e);
+                    result.push_str(&format!(" (not added to the search index: {})", e));
+                }
+                Ok(result)
             }
             "retrieve_memories" => {
                 let args = MemoryArgs::from_value(&tool_call.arguments)?;
@@ -474,6 +753,22 @@ impl MemoryRouter {
             }
             "remove_memory_category" => {
                 let args = MemoryArgs::from_value(&tool_call.arguments)?;
+                if let Some(index) = &self.index {
+                    let category = Some(args.category).filter(|category| *category != "*");
+                    index
+                        .clear(&self.scope(args.is_global), category)
+                        .await
+                        .map_err(|e| io::Error::other(e.to_string()))?;
+                    let cleared: Vec<u64> = self
+                        .stored_memories(args.is_global)?
+                        .iter()
+                        .filter(|record| category.is_none_or(|c| record.category == c))
+                        .map(MemoryRecord::id)
+                        .collect();
+                    self.indexed
+                        .remove(&cleared)
+                        .map_err(|e| io::Error::other(e.to_string()))?;
+                }
                 if args.category == "*" {
                     self.clear_all_global_or_local_memories(args.is_global)?;
                     Ok(format!(
@@ -488,12 +783,98 @@ impl MemoryRouter {
             "remove_specific_memory" => {
                 let args = MemoryArgs::from_value(&tool_call.arguments)?;
                 let memory_content = tool_call.arguments["memory_content"].as_str().unwrap();
+                if let Some(index) = &self.index {
+                    let scope = self.scope(args.is_global);
+                    let ids: Vec<u64> = self
+                        .find_memories(args.category, memory_content, args.is_global)?
+                        .iter()
+                        .map(|data| index::record_id(&scope, args.category, data))
+                        .collect();
+                    index
+                        .remove(&ids)
+                        .await
+                        .map_err(|e| io::Error::other(e.to_string()))?;
+                    self.indexed
+                        .remove(&ids)
+                        .map_err(|e| io::Error::other(e.to_string()))?;
+                }
                 self.remove_specific_memory(args.category, memory_content, args.is_global)?;
                 Ok(format!(
                     "Removed specific memory from category: {}",
                     args.category
                 ))
             }
+            "search_memories" => {
+                let query = tool_call.arguments["query"].as_str().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "Query must be a string")
+                })?;
+                let limit = tool_call.arguments["limit"].as_u64().unwrap_or(5) as usize;
+                let is_global = tool_call.arguments["is_global"].as_bool();
+                let found = self
+                    .search(query, limit.max(1), is_global)
+                    .await
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                if found.is_empty() {
+                    return Ok("No matching memories found".to_string());
+                }
+                let lines: Vec<String> = found
+                    .iter()
+                    .map(|memory| {
+                        let scope = if memory.record.scope == GLOBAL_SCOPE {
+                            "global"
+                        } else {
+                            "local"
+                        };
+                        format!(
+                            "- [{} {}] {} (similarity {:.2})",
+                            scope, memory.record.category, memory.record.text, memory.score
+                        )
+                    })
+                    .collect();
+                Ok(format!("Found memories:\n{}", lines.join("\n")))
+            }
+            "search_knowledge" => {
+                let query = tool_call.arguments["query"].as_str().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::InvalidInput, "Query must be a string")
+                })?;
+                let limit = tool_call.arguments["limit"].as_u64().unwrap_or(5) as usize;
+                let sources: Vec<String> = match tool_call.arguments["sources"].as_array() {
+                    Some(sources) => sources
+                        .iter()
+                        .filter_map(|s| s.as_str())
+                        .map(String::from)
+                        .collect(),
+                    None => self.knowledge_sources.clone(),
+                };
+                if let Some(unknown) = sources
+                    .iter()
+                    .find(|source| !self.knowledge_sources.contains(source))
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Unknown knowledge source: {}", unknown),
+                    ));
+                }
+                let scopes = sources.iter().map(|s| knowledge::scope_of(s)).collect();
+                let found = self
+                    .search_scopes(query, limit.max(1), scopes)
+                    .await
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                if found.is_empty() {
+                    return Ok("No matching passages found".to_string());
+                }
+                let passages: Vec<String> = found
+                    .iter()
+                    .map(|passage| {
+                        let source = passage.record.tags.first().cloned().unwrap_or_default();
+                        format!(
+                            "[{}: {}] (similarity {:.2})\n{}",
+                            source, passage.record.category, passage.score, passage.record.text
+                        )
+                    })
+                    .collect();
+                Ok(passages.join("\n\n"))
+            }
             _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "Unknown tool")),
         }
     }
@@ -0,0 +1,659 @@
+use futures::TryStreamExt;
+use indoc::{formatdoc, indoc};
+use serde_json::{json, Value};
+use sqlparser::ast::{Expr, Query, SetExpr, Statement, Visit, Visitor};
+use sqlparser::dialect::{Dialect, MySqlDialect, PostgreSqlDialect, SQLiteDialect};
+use sqlparser::parser::Parser;
+use sqlx::any::{AnyPoolOptions, AnyRow};
+use sqlx::{AnyConnection, AnyPool, Column, Row, ValueRef};
+use std::ops::ControlFlow;
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc, time::Duration};
+use tokio::sync::{mpsc, Mutex};
+
+use mcp_core::{
+    handler::{PromptError, ResourceError, ToolError},
+    prompt::Prompt,
+    protocol::{JsonRpcMessage, ServerCapabilities},
+    resource::Resource,
+    role::Role,
+    tool::{Tool, ToolAnnotations},
+    Content,
+};
+use mcp_server::router::CapabilitiesBuilder;
+use mcp_server::Router;
+
+const DEFAULT_MAX_ROWS: usize = 500;
+const DEFAULT_MAX_BYTES: usize = 1_000_000;
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+/// Functions a query may not call, since they change settings such as the read-only mode of
+/// the session
+const DENIED_FUNCTIONS: &[&str] = &["set_config"];
+
+/// The database behind a connection URL, which decides how queries are parsed and how the
+/// connection is made read-only
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SqlDialect {
+    Postgres,
+    MySql,
+    Sqlite,
+}
+
+impl SqlDialect {
+    fn from_url(url: &str) -> Option<Self> {
+        let (scheme, _) = url.split_once(':')?;
+        match scheme.to_ascii_lowercase().as_str() {
+            "postgres" | "postgresql" => Some(SqlDialect::Postgres),
+            "mysql" | "mariadb" => Some(SqlDialect::MySql),
+            "sqlite" => Some(SqlDialect::Sqlite),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "postgres",
+            SqlDialect::MySql => "mysql",
+            SqlDialect::Sqlite => "sqlite",
+        }
+    }
+
+    fn parser(self) -> Box<dyn Dialect> {
+        match self {
+            SqlDialect::Postgres => Box::new(PostgreSqlDialect {}),
+            SqlDialect::MySql => Box::new(MySqlDialect {}),
+            SqlDialect::Sqlite => Box::new(SQLiteDialect {}),
+        }
+    }
+
+    /// Run on every new connection so the database itself refuses writes, including ones the
+    /// query check can't see such as functions with side effects
+    fn read_only_statement(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "SET SESSION CHARACTERISTICS AS TRANSACTION READ ONLY",
+            SqlDialect::MySql => "SET SESSION TRANSACTION READ ONLY",
+            SqlDialect::Sqlite => "PRAGMA query_only = ON",
+        }
+    }
+
+    /// Starts the transaction every query runs in, which is rolled back afterwards
+    fn begin_read_only(self) -> &'static str {
+        match self {
+            SqlDialect::Postgres => "BEGIN READ ONLY",
+            SqlDialect::MySql => "START TRANSACTION READ ONLY",
+            SqlDialect::Sqlite => "BEGIN",
+        }
+    }
+
+    /// Accept a single query and nothing that changes data or schema
+    fn check_read_only(self, sql: &str) -> Result<(), ToolError> {
+        let statements = Parser::parse_sql(self.parser().as_ref(), sql).map_err(|e| {
+            ToolError::InvalidParameters(format!("Could not parse the query: {}", e))
+        })?;
+        match statements.as_slice() {
+            [statement @ Statement::Query(_)] => match statement.visit(&mut WriteFinder) {
+                ControlFlow::Break(reason) => Err(ToolError::InvalidParameters(reason)),
+                ControlFlow::Continue(()) => Ok(()),
+            },
+            [] => Err(ToolError::InvalidParameters(
+                "The query is empty".to_string(),
+            )),
+            [_] => Err(ToolError::InvalidParameters(
+                "Only read-only queries such as SELECT are allowed".to_string(),
+            )),
+            _ => Err(ToolError::InvalidParameters(
+                "Run one query at a time".to_string(),
+            )),
+        }
+    }
+}
+
+/// Finds what a statement the parser accepts as a query can do besides reading: create a
+/// table with `SELECT ... INTO`, write through a data-modifying CTE, or change settings
+struct WriteFinder;
+
+impl Visitor for WriteFinder {
+    type Break = String;
+
+    fn pre_visit_query(&mut self, query: &Query) -> ControlFlow<String> {
+        match query.body.as_ref() {
+            SetExpr::Select(select) if select.into.is_some() => {
+                ControlFlow::Break("SELECT ... INTO isn't allowed, it creates a table".to_string())
+            }
+            SetExpr::Insert(_) | SetExpr::Update(_) => {
+                ControlFlow::Break("Only read-only queries such as SELECT are allowed".to_string())
+            }
+            _ => ControlFlow::Continue(()),
+        }
+    }
+
+    fn pre_visit_expr(&mut self, expr: &Expr) -> ControlFlow<String> {
+        if let Expr::Function(function) = expr {
+            if let Some(name) = function.name.0.last() {
+                if DENIED_FUNCTIONS.contains(&name.value.to_lowercase().as_str()) {
+                    return ControlFlow::Break(format!(
+                        "{} can't be called in a query",
+                        name.value
+                    ));
+                }
+            }
+        }
+        ControlFlow::Continue(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Connection {
+    url: String,
+    dialect: SqlDialect,
+}
+
+/// Read-only SQL queries against the databases configured in `GOOSE_SQL_CONNECTIONS`, a JSON
+/// object of connection names to `postgres://`, `mysql://` or `sqlite:` URLs.
+///
+/// Queries are checked before they run, and run in a read-only transaction that is rolled
+/// back, on a connection set to read-only. Those checks are a safety net rather than access
+/// control: connect as a database role that is only granted SELECT on what goose should see.
+#[derive(Clone)]
+pub struct SqlRouter {
+    tools: Vec<Tool>,
+    instructions: String,
+    connections: HashMap<String, Connection>,
+    /// Opened on first use
+    pools: Arc<Mutex<HashMap<String, AnyPool>>>,
+    max_rows: usize,
+    max_bytes: usize,
+    timeout: Duration,
+}
+
+impl Default for SqlRouter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+impl SqlRouter {
+    pub fn new() -> Self {
+        let configured: HashMap<String, String> = std::env::var("GOOSE_SQL_CONNECTIONS")
+            .ok()
+            .and_then(|value| {
+                serde_json::from_str(&value)
+                    .map_err(|e| tracing::error!("Invalid GOOSE_SQL_CONNECTIONS: {}", e))
+                    .ok()
+            })
+            .unwrap_or_default();
+        Self::with_connections(
+            configured,
+            env_or("GOOSE_SQL_MAX_ROWS", DEFAULT_MAX_ROWS),
+            env_or("GOOSE_SQL_MAX_BYTES", DEFAULT_MAX_BYTES),
+            Duration::from_secs(env_or("GOOSE_SQL_TIMEOUT_SECS", DEFAULT_TIMEOUT_SECS)),
+        )
+    }
+
+    fn with_connections(
+        configured: HashMap<String, String>,
+        max_rows: usize,
+        max_bytes: usize,
+        timeout: Duration,
+    ) -> Self {
+        sqlx::any::install_default_drivers();
+
+        let mut connections = HashMap::new();
+        for (name, url) in configured {
+            match SqlDialect::from_url(&url) {
+                Some(dialect) => {
+                    connections.insert(name, Connection { url, dialect });
+                }
+                None => tracing::error!(
+                    "Ignoring SQL connection {}: only postgres, mysql and sqlite URLs are supported",
+                    name
+                ),
+            }
+        }
+
+        let list_connections = Tool::new(
+            "sql_list_connections".to_string(),
+            "List the configured database connections and the SQL dialect of each.".to_string(),
+            json!({
+                "type": "object",
+                "properties": {}
+            }),
+            Some(ToolAnnotations {
+                title: Some("List SQL connections".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let query = Tool::new(
+            "sql_query".to_string(),
+            indoc! {r#"
+                Run a read-only SQL query against a configured connection and get the rows back
+                as JSON. Only a single SELECT (including WITH ... SELECT) is accepted, and it
+                runs in a read-only transaction that is rolled back afterwards.
+
+                Pass values through `params` instead of writing them into the query, using the
+                placeholders of the connection's dialect: $1, $2, ... for postgres and ? for
+                mysql and sqlite.
+
+                Results stop at the row and size limits and say when they were truncated; use
+                aggregates, filters and LIMIT to keep them small. Cast columns of types without
+                a plain JSON form, such as timestamps, decimals or UUIDs, to text in the query.
+            "#}
+            .to_string(),
+            json!({
+                "type": "object",
+                "required": ["connection", "query"],
+                "properties": {
+                    "connection": {
+                        "type": "string",
+                        "description": "Name of the connection, from sql_list_connections"
+                    },
+                    "query": {
+                        "type": "string",
+                        "description": "The SELECT query to run"
+                    },
+                    "params": {
+                        "type": "array",
+                        "items": {"type": ["string", "number", "boolean", "null"]},
+                        "description": "Values bound to the query's placeholders, in order"
+                    },
+                    "max_rows": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "description": "Return at most this many rows, bounded by the configured limit"
+                    }
+                }
+            }),
+            Some(ToolAnnotations {
+                title: Some("Run SQL query".to_string()),
+                read_only_hint: true,
+                destructive_hint: false,
+                idempotent_hint: true,
+                open_world_hint: false,
+            }),
+        );
+
+        let mut names: Vec<String> = connections
+            .iter()
+            .map(|(name, connection)| format!("- {} ({})", name, connection.dialect.name()))
+            .collect();
+        names.sort();
+        let instructions = formatdoc! {r#"
+            The sql extension runs read-only queries against these databases:
+            {connections}
+
+            Explore a schema before querying it: information_schema.tables and
+            information_schema.columns on postgres and mysql, or sqlite_master and
+            pragma_table_info('table') on sqlite. Results are limited to {max_rows} rows and
+            {max_bytes} bytes per query.
+            "#,
+            connections = if names.is_empty() {
+                "(none configured; set GOOSE_SQL_CONNECTIONS)".to_string()
+            } else {
+                names.join("\n")
+            },
+            max_rows = max_rows,
+            max_bytes = max_bytes,
+        };
+
+        Self {
+            tools: vec![list_connections, query],
+            instructions,
+            connections,
+            pools: Arc::new(Mutex::new(HashMap::new())),
+            max_rows,
+            max_bytes,
+            timeout,
+        }
+    }
+
+    fn connection(&self, name: &str) -> Result<&Connection, ToolError> {
+        self.connections.get(name).ok_or_else(|| {
+            ToolError::InvalidParameters(format!(
+                "Unknown connection '{}'; use sql_list_connections to see the configured ones",
+                name
+            ))
+        })
+    }
+
+    async fn pool(&self, name: &str, connection: &Connection) -> Result<AnyPool, ToolError> {
+        let mut pools = self.pools.lock().await;
+        if let Some(pool) = pools.get(name) {
+            return Ok(pool.clone());
+        }
+
+        let read_only = connection.dialect.read_only_statement();
+        let pool = AnyPoolOptions::new()
+            .max_connections(4)
+            .acquire_timeout(self.timeout)
+            .after_connect(move |conn, _meta| {
+                Box::pin(async move {
+                    sqlx::query(read_only).execute(&mut *conn).await?;
+                    Ok(())
+                })
+            })
+            .connect(&connection.url)
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to connect to '{}': {}", name, e))
+            })?;
+        pools.insert(name.to_string(), pool.clone());
+        Ok(pool)
+    }
+
+    fn list_connections(&self) -> Value {
+        let mut connections: Vec<Value> = self
+            .connections
+            .iter()
+            .map(|(name, connection)| json!({"name": name, "dialect": connection.dialect.name()}))
+            .collect();
+        connections.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+        Value::Array(connections)
+    }
+
+    async fn query(&self, arguments: &Value) -> Result<Value, ToolError> {
+        let name = arguments
+            .get("connection")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| {
+                ToolError::InvalidParameters("Missing 'connection' parameter".to_string())
+            })?;
+        let sql = arguments
+            .get("query")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'query' parameter".to_string()))?;
+        let params = match arguments.get("params") {
+            None | Some(Value::Null) => Vec::new(),
+            Some(Value::Array(params)) => params.clone(),
+            Some(_) => {
+                return Err(ToolError::InvalidParameters(
+                    "'params' must be an array".to_string(),
+                ))
+            }
+        };
+        let max_rows = arguments
+            .get("max_rows")
+            .and_then(|v| v.as_u64())
+            .map_or(self.max_rows, |n| (n as usize).clamp(1, self.max_rows));
+
+        let connection = self.connection(name)?;
+        connection.dialect.check_read_only(sql)?;
+        let pool = self.pool(name, connection).await?;
+
+        // Run in a read-only transaction that is always rolled back, so nothing the query does
+        // outlives it, even where the connection's read-only setting could be changed
+        let query_error =
+            |e: sqlx::Error| ToolError::ExecutionError(format!("Query failed: {}", e));
+        let mut conn = pool.acquire().await.map_err(query_error)?;
+        sqlx::query(connection.dialect.begin_read_only())
+            .execute(&mut *conn)
+            .await
+            .map_err(query_error)?;
+        match tokio::time::timeout(self.timeout, self.fetch(&mut conn, sql, params, max_rows)).await
+        {
+            Ok(result) => {
+                sqlx::query("ROLLBACK")
+                    .execute(&mut *conn)
+                    .await
+                    .map_err(query_error)?;
+                result
+            }
+            Err(_) => {
+                // Still inside the query and its transaction, so the connection isn't reused
+                drop(conn.detach());
+                Err(ToolError::ExecutionError(format!(
+                    "Query timed out after {}s",
+                    self.timeout.as_secs()
+                )))
+            }
+        }
+    }
+
+    async fn fetch(
+        &self,
+        conn: &mut AnyConnection,
+        sql: &str,
+        params: Vec<Value>,
+        max_rows: usize,
+    ) -> Result<Value, ToolError> {
+        let mut query = sqlx::query(sql);
+        for param in params {
+            query = match param {
+                Value::Null => query.bind(Option::<String>::None),
+                Value::Bool(b) => query.bind(b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => query.bind(i),
+                    None => query.bind(n.as_f64().unwrap_or_default()),
+                },
+                Value::String(s) => query.bind(s),
+                other => query.bind(other.to_string()),
+            };
+        }
+
+        let query_error =
+            |e: sqlx::Error| ToolError::ExecutionError(format!("Query failed: {}", e));
+        let mut stream = query.fetch(&mut *conn);
+        let mut columns: Option<Vec<String>> = None;
+        let mut rows = Vec::new();
+        let mut bytes = 0;
+        let mut truncated = false;
+        while let Some(row) = stream.try_next().await.map_err(query_error)? {
+            if columns.is_none() {
+                columns = Some(row.columns().iter().map(|c| c.name().to_string()).collect());
+            }
+            if rows.len() == max_rows {
+                truncated = true;
+                break;
+            }
+            let values = row_values(&row)?;
+            bytes += serde_json::to_vec(&values).map_or(0, |encoded| encoded.len());
+            if bytes > self.max_bytes {
+                truncated = true;
+                break;
+            }
+            rows.push(Value::Array(values));
+        }
+
+        Ok(json!({
+            "columns": columns.unwrap_or_default(),
+            "row_count": rows.len(),
+            "rows": rows,
+            "truncated": truncated,
+        }))
+    }
+}
+
+/// Column values of a row as JSON, trying the types the database's value can be read as
+fn row_values(row: &AnyRow) -> Result<Vec<Value>, ToolError> {
+    (0..row.columns().len())
+        .map(|i| {
+            let raw = row
+                .try_get_raw(i)
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+            if raw.is_null() {
+                return Ok(Value::Null);
+            }
+            if let Ok(value) = row.try_get::<i64, _>(i) {
+                return Ok(json!(value));
+            }
+            if let Ok(value) = row.try_get::<f64, _>(i) {
+                return Ok(json!(value));
+            }
+            if let Ok(value) = row.try_get::<bool, _>(i) {
+                return Ok(json!(value));
+            }
+            if let Ok(value) = row.try_get::<String, _>(i) {
+                return Ok(json!(value));
+            }
+            if let Ok(value) = row.try_get::<Vec<u8>, _>(i) {
+                return Ok(json!(String::from_utf8_lossy(&value)));
+            }
+            Err(ToolError::ExecutionError(format!(
+                "Column '{}' has a type that can't be read; cast it to text in the query",
+                row.columns()[i].name()
+            )))
+        })
+        .collect()
+}
+
+impl Router for SqlRouter {
+    fn name(&self) -> String {
+        "sql".to_string()
+    }
+
+    fn instructions(&self) -> String {
+        self.instructions.clone()
+    }
+
+    fn capabilities(&self) -> ServerCapabilities {
+        CapabilitiesBuilder::new().with_tools(false).build()
+    }
+
+    fn list_tools(&self) -> Vec<Tool> {
+        self.tools.clone()
+    }
+
+    fn call_tool(
+        &self,
+        tool_name: &str,
+        arguments: Value,
+        _notifier: mpsc::Sender<JsonRpcMessage>,
+    ) -> Pin<Box<dyn Future<Output = Result<Vec<Content>, ToolError>> + Send + 'static>> {
+        let this = self.clone();
+        let tool_name = tool_name.to_string();
+
+        Box::pin(async move {
+            let result = match tool_name.as_str() {
+                "sql_list_connections" => this.list_connections(),
+                "sql_query" => this.query(&arguments).await?,
+                _ => return Err(ToolError::NotFound(format!("Tool {} not found", tool_name))),
+            };
+            Ok(vec![
+                Content::text(result.to_string()).with_audience(vec![Role::Assistant])
+            ])
+        })
+    }
+
+    fn list_resources(&self) -> Vec<Resource> {
+        Vec::new()
+    }
+
+    fn read_resource(
+        &self,
+        _uri: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, ResourceError>> + Send + 'static>> {
+        Box::pin(async move { Ok("".to_string()) })
+    }
+
+    fn list_prompts(&self) -> Vec<Prompt> {
+        vec![]
+    }
+
+    fn get_prompt(
+        &self,
+        prompt_name: &str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, PromptError>> + Send + 'static>> {
+        let prompt_name = prompt_name.to_string();
+        Box::pin(async move {
+            Err(PromptError::NotFound(format!(
+                "Prompt {} not found",
+                prompt_name
+            )))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialect_from_url() {
+        assert_eq!(
+            SqlDialect::from_url("postgresql://localhost/db"),
+            Some(SqlDialect::Postgres)
+        );
+        assert_eq!(
+            SqlDialect::from_url("mysql://root@localhost/db"),
+            Some(SqlDialect::MySql)
+        );
+        assert_eq!(
+            SqlDialect::from_url("sqlite:data.db"),
+            Some(SqlDialect::Sqlite)
+        );
+        assert_eq!(SqlDialect::from_url("mssql://localhost"), None);
+    }
+
+    #[test]
+    fn test_only_single_queries_are_allowed() {
+        let dialect = SqlDialect::Postgres;
+        assert!(dialect
+            .check_read_only("WITH t AS (SELECT 1 AS n) SELECT n FROM t WHERE n = $1")
+            .is_ok());
+        assert!(dialect.check_read_only("DELETE FROM users").is_err());
+        assert!(dialect.check_read_only("DROP TABLE users").is_err());
+        assert!(dialect
+            .check_read_only("SELECT 1; UPDATE users SET admin = true")
+            .is_err());
+        assert!(dialect.check_read_only("").is_err());
+    }
+
+    #[test]
+    fn test_queries_that_write_or_change_settings_are_refused() {
+        let dialect = SqlDialect::Postgres;
+        assert!(dialect
+            .check_read_only("SELECT set_config('default_transaction_read_only', 'off', false)")
+            .is_err());
+        assert!(dialect
+            .check_read_only("SELECT n FROM (SELECT pg_catalog.SET_CONFIG('a', 'b', true) AS n) t")
+            .is_err());
+        assert!(dialect
+            .check_read_only("SELECT * INTO copied FROM users")
+            .is_err());
+        assert!(dialect
+            .check_read_only("SELECT config FROM settings WHERE name = 'set_config'")
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_query_limits_rows() {
+        let router = SqlRouter::with_connections(
+            HashMap::from([("local".to_string(), "sqlite::memory:".to_string())]),
+            2,
+            DEFAULT_MAX_BYTES,
+            Duration::from_secs(5),
+        );
+        let result = router
+            .query(&json!({
+                "connection": "local",
+                "query": "SELECT 1 AS n, 'a' AS s UNION ALL SELECT 2, ? UNION ALL SELECT 3, NULL",
+                "params": ["b"]
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            result,
+            json!({
+                "columns": ["n", "s"],
+                "row_count": 2,
+                "rows": [[1, "a"], [2, "b"]],
+                "truncated": true
+            })
+        );
+
+        let refused = router
+            .query(&json!({"connection": "local", "query": "CREATE TABLE t (n INTEGER)"}))
+            .await;
+        assert!(refused.is_err());
+    }
+}
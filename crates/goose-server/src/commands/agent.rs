@@ -7,10 +7,13 @@ use etcetera::{choose_app_strategy, AppStrategy};
 use goose::agents::Agent;
 use goose::config::APP_STRATEGY;
 use goose::scheduler_factory::SchedulerFactory;
+use goose::watcher::{RecipeWatcher, WatchConfig};
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
 
+use goose::providers::pool::{global_pool_manager, PrewarmTarget};
 use goose::providers::pricing::initialize_pricing_cache;
+use goose::providers::usage_telemetry::spawn_usage_export;
 
 pub async fn run() -> Result<()> {
     // Initialize logging
@@ -27,13 +30,31 @@ pub async fn run() -> Result<()> {
         );
     }
 
+    // Keep pooled providers healthy for the lifetime of the server
+    global_pool_manager().spawn_health_checks();
+    // Issue short-lived provider credentials and renew them ahead of expiry
+    global_pool_manager().spawn_credential_refresh();
+    // Report token usage to the team's metrics store, if one is configured
+    spawn_usage_export();
+
+    // Connect to the configured providers in the background so credential or network
+    // problems show up in the logs before the first user turn
+    tokio::spawn(async {
+        global_pool_manager()
+            .prewarm(&PrewarmTarget::from_config())
+            .await;
+    });
+
     let secret_key =
         std::env::var("GOOSE_SERVER__SECRET_KEY").unwrap_or_else(|_| "test".to_string());
 
     let new_agent = Agent::new();
     let agent_ref = Arc::new(new_agent);
 
-    let app_state = state::AppState::new(agent_ref.clone(), secret_key.clone()).await;
+    // Admin endpoints are only served when an admin key is configured
+    let admin_key = std::env::var("GOOSE_SERVER__ADMIN_KEY").ok();
+    let app_state =
+        state::AppState::new_with_admin_key(agent_ref.clone(), secret_key.clone(), admin_key).await;
 
     let schedule_file_path = choose_app_strategy(APP_STRATEGY.clone())?
         .data_dir()
@@ -42,6 +63,17 @@ pub async fn run() -> Result<()> {
     let scheduler_instance = SchedulerFactory::create(schedule_file_path).await?;
     app_state.set_scheduler(scheduler_instance.clone()).await;
 
+    // Rerun recipes when the files they watch change
+    let watches = WatchConfig::from_config();
+    if !watches.is_empty() {
+        match RecipeWatcher::new(watches, scheduler_instance.clone()) {
+            Ok(watcher) => {
+                watcher.spawn();
+            }
+            Err(e) => tracing::warn!("Recipe watches are not running: {}", e),
+        }
+    }
+
     // NEW: Provide scheduler access to the agent
     agent_ref.set_scheduler(scheduler_instance).await;
 
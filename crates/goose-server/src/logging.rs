@@ -9,7 +9,7 @@ use tracing_subscriber::{
 };
 
 use goose::config::APP_STRATEGY;
-use goose::tracing::langfuse_layer;
+use goose::tracing::{alerting_layer, langfuse_layer};
 
 /// Returns the directory where log files should be stored.
 /// Creates the directory structure if it doesn't exist.
@@ -94,7 +94,8 @@ pub fn setup_logging(name: Option<&str>) -> Result<()> {
     // Build the subscriber with required layers
     let subscriber = Registry::default()
         .with(file_layer.with_filter(env_filter))
-        .with(console_layer.with_filter(LevelFilter::INFO));
+        .with(console_layer.with_filter(LevelFilter::INFO))
+        .with(alerting_layer::create_alerting_layer());
 
     // Initialize with Langfuse if available
     if let Some(langfuse) = langfuse_layer::create_langfuse_observer() {
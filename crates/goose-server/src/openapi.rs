@@ -4,11 +4,13 @@ use goose::agents::ExtensionConfig;
 use goose::config::permission::PermissionLevel;
 use goose::config::ExtensionEntry;
 use goose::message::{
-    ContextLengthExceeded, FrontendToolRequest, Message, MessageContent, RedactedThinkingContent,
-    SummarizationRequested, ThinkingContent, ToolConfirmationRequest, ToolRequest, ToolResponse,
+    Assumption, ContextLengthExceeded, FinishReason, FrontendToolRequest, Message, MessageContent,
+    Question, RedactedThinkingContent, SummarizationRequested, ThinkingContent,
+    ToolConfirmationRequest, ToolRequest, ToolResponse,
 };
 use goose::permission::permission_confirmation::PrincipalType;
 use goose::providers::base::{ConfigKey, ModelInfo, ProviderMetadata};
+use goose::providers::databricks::{ServedModel, ServingEndpoint};
 use goose::session::info::SessionInfo;
 use goose::session::SessionMetadata;
 use mcp_core::content::{Annotations, Content, EmbeddedResource, ImageContent, TextContent};
@@ -29,6 +31,8 @@ use utoipa::OpenApi;
         super::routes::config_management::upsert_config,
         super::routes::config_management::remove_config,
         super::routes::config_management::read_config,
+        super::routes::config_management::reload_providers,
+        super::routes::config_management::databricks_endpoints,
         super::routes::config_management::add_extension,
         super::routes::config_management::remove_extension,
         super::routes::config_management::get_extensions,
@@ -36,10 +40,18 @@ use utoipa::OpenApi;
         super::routes::config_management::providers,
         super::routes::config_management::upsert_permissions,
         super::routes::agent::get_tools,
+        super::routes::agent::get_open_questions,
+        super::routes::agent::answer_question,
         super::routes::reply::confirm_permission,
+        super::routes::reply::subagent_events_handler,
+        super::routes::reply::subagent_events_schema,
         super::routes::context::manage_context,
+        super::routes::context::context_report,
+        super::routes::provenance::list_provenance,
+        super::routes::provenance::trace_claim,
         super::routes::session::list_sessions,
         super::routes::session::get_session_history,
+        super::routes::session::get_session_view,
         super::routes::schedule::create_schedule,
         super::routes::schedule::list_schedules,
         super::routes::schedule::delete_schedule,
@@ -49,13 +61,21 @@ use utoipa::OpenApi;
         super::routes::schedule::unpause_schedule,
         super::routes::schedule::kill_running_job,
         super::routes::schedule::inspect_running_job,
-        super::routes::schedule::sessions_handler
+        super::routes::schedule::sessions_handler,
+        super::routes::trigger::email_trigger,
+        super::routes::admin::list_sessions,
+        super::routes::admin::terminate_session,
+        super::routes::admin::list_subagents,
+        super::routes::admin::terminate_subagent,
+        super::routes::admin::drain,
+        super::routes::admin::health
     ),
     components(schemas(
         super::routes::config_management::UpsertConfigQuery,
         super::routes::config_management::ConfigKeyQuery,
         super::routes::config_management::ConfigResponse,
         super::routes::config_management::ProvidersResponse,
+        super::routes::config_management::ReloadProvidersResponse,
         super::routes::config_management::ProviderDetails,
         super::routes::config_management::ExtensionResponse,
         super::routes::config_management::ExtensionQuery,
@@ -64,10 +84,24 @@ use utoipa::OpenApi;
         super::routes::reply::PermissionConfirmationRequest,
         super::routes::context::ContextManageRequest,
         super::routes::context::ContextManageResponse,
+        super::routes::context::ContextReportRequest,
+        goose::context_mgmt::report::ContextReport,
+        goose::context_mgmt::report::ContextItem,
+        goose::context_mgmt::report::ContextSource,
+        super::routes::provenance::ProvenanceListResponse,
+        super::routes::provenance::ProvenanceTraceRequest,
+        super::routes::provenance::ProvenanceTraceResponse,
+        goose::agents::provenance::ProvenanceRecord,
+        goose::agents::provenance::ProvenanceMatch,
+        goose::agents::provenance::ProvenanceSource,
         super::routes::session::SessionListResponse,
         super::routes::session::SessionHistoryResponse,
+        super::routes::session::SessionViewResponse,
+        goose::session::view::ZoomLevel,
+        goose::session::view::TurnSummary,
         Message,
         MessageContent,
+        FinishReason,
         Content,
         EmbeddedResource,
         ImageContent,
@@ -83,11 +117,16 @@ use utoipa::OpenApi;
         ResourceContents,
         ContextLengthExceeded,
         SummarizationRequested,
+        Assumption,
+        Question,
+        super::routes::agent::AnswerQuestionRequest,
         Role,
         ProviderMetadata,
         ExtensionEntry,
         ExtensionConfig,
         ConfigKey,
+        ServingEndpoint,
+        ServedModel,
         Envs,
         Tool,
         ToolAnnotations,
@@ -102,10 +141,20 @@ use utoipa::OpenApi;
         super::routes::schedule::KillJobResponse,
         super::routes::schedule::InspectJobResponse,
         goose::scheduler::ScheduledJob,
+        goose::schedule_constraints::ScheduleConstraints,
+        goose::schedule_constraints::BusinessHours,
+        goose::schedule_constraints::BlackoutWindow,
         super::routes::schedule::RunNowResponse,
         super::routes::schedule::ListSchedulesResponse,
         super::routes::schedule::SessionsQuery,
         super::routes::schedule::SessionDisplayInfo,
+        super::routes::trigger::EmailTriggerResponse,
+        goose::email_trigger::InboundEmail,
+        super::routes::admin::AdminSession,
+        super::routes::admin::AdminSessionListResponse,
+        super::routes::admin::AdminSubagentListResponse,
+        super::routes::admin::DrainRequest,
+        super::routes::admin::AdminHealthResponse,
     ))
 )]
 pub struct ApiDoc;
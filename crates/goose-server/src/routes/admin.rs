@@ -0,0 +1,394 @@
+use super::utils::verify_admin_key;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::state::AppState;
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use goose::agents::subagent::{SubAgentProgress, SubAgentStatus};
+use goose::providers::pool::global_pool_manager;
+use goose::providers::pool_stats::PoolStats;
+use goose::session::info::{get_valid_sorted_sessions, SessionInfo, SortOrder};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminSession {
+    #[serde(flatten)]
+    pub info: SessionInfo,
+    /// When the reply in progress started, if one is streaming
+    pub active_since: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminSessionListResponse {
+    /// Every session stored on this node, most recent first
+    sessions: Vec<AdminSession>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminSubagentListResponse {
+    /// Progress of every running subagent, by id
+    #[schema(value_type = Object)]
+    subagents: HashMap<String, SubAgentProgress>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DrainRequest {
+    /// Refuse new replies while true; replies in progress run to completion
+    draining: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminHealthResponse {
+    draining: bool,
+    /// Sessions with a reply in progress
+    active_sessions: usize,
+    subagents: usize,
+    /// Number of subagents in each status
+    subagents_by_status: HashMap<String, usize>,
+    /// Pooled provider instances
+    pooled_providers: usize,
+    #[schema(value_type = Object)]
+    pool: PoolStats,
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/sessions",
+    responses(
+        (status = 200, description = "All sessions on this node", body = AdminSessionListResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing admin key"),
+        (status = 403, description = "Forbidden - Admin API not enabled"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("admin_key" = [])
+    ),
+    tag = "Admin"
+)]
+async fn list_sessions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<AdminSessionListResponse>, StatusCode> {
+    verify_admin_key(&headers, &state)?;
+
+    let active = state.active_sessions.list();
+    let sessions = get_valid_sorted_sessions(SortOrder::Descending)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|info| AdminSession {
+            active_since: active.get(&info.id).copied(),
+            info,
+        })
+        .collect();
+
+    Ok(Json(AdminSessionListResponse { sessions }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/sessions/{session_id}/terminate",
+    params(
+        ("session_id" = String, Path, description = "Session whose reply should be stopped")
+    ),
+    responses(
+        (status = 204, description = "Reply stopped"),
+        (status = 401, description = "Unauthorized - Invalid or missing admin key"),
+        (status = 403, description = "Forbidden - Admin API not enabled"),
+        (status = 404, description = "No reply in progress for the session")
+    ),
+    security(
+        ("admin_key" = [])
+    ),
+    tag = "Admin"
+)]
+async fn terminate_session(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    verify_admin_key(&headers, &state)?;
+
+    if state.active_sessions.terminate(&session_id) {
+        tracing::info!("Admin terminated session {}", session_id);
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/subagents",
+    responses(
+        (status = 200, description = "Running subagents", body = AdminSubagentListResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing admin key"),
+        (status = 403, description = "Forbidden - Admin API not enabled"),
+        (status = 412, description = "Precondition failed - Agent not available")
+    ),
+    security(
+        ("admin_key" = [])
+    ),
+    tag = "Admin"
+)]
+async fn list_subagents(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<AdminSubagentListResponse>, StatusCode> {
+    verify_admin_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    Ok(Json(AdminSubagentListResponse {
+        subagents: agent.subagent_progress().await,
+    }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/subagents/{subagent_id}/terminate",
+    params(
+        ("subagent_id" = String, Path, description = "Subagent to terminate")
+    ),
+    responses(
+        (status = 204, description = "Subagent terminated"),
+        (status = 401, description = "Unauthorized - Invalid or missing admin key"),
+        (status = 403, description = "Forbidden - Admin API not enabled"),
+        (status = 404, description = "Subagent not found"),
+        (status = 412, description = "Precondition failed - Agent not available")
+    ),
+    security(
+        ("admin_key" = [])
+    ),
+    tag = "Admin"
+)]
+async fn terminate_subagent(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(subagent_id): Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    verify_admin_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    match agent.terminate_subagent(&subagent_id).await {
+        Ok(()) => {
+            tracing::info!("Admin terminated subagent {}", subagent_id);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        Err(_) => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/drain",
+    request_body = DrainRequest,
+    responses(
+        (status = 200, description = "Drain state updated", body = AdminHealthResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing admin key"),
+        (status = 403, description = "Forbidden - Admin API not enabled")
+    ),
+    security(
+        ("admin_key" = [])
+    ),
+    tag = "Admin"
+)]
+async fn drain(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<DrainRequest>,
+) -> Result<Json<AdminHealthResponse>, StatusCode> {
+    verify_admin_key(&headers, &state)?;
+
+    state.set_draining(request.draining);
+    tracing::info!(
+        "Admin set draining to {} with {} active sessions",
+        request.draining,
+        state.active_sessions.count()
+    );
+    Ok(Json(aggregate_health(&state).await))
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/health",
+    responses(
+        (status = 200, description = "Aggregate health of this node", body = AdminHealthResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing admin key"),
+        (status = 403, description = "Forbidden - Admin API not enabled")
+    ),
+    security(
+        ("admin_key" = [])
+    ),
+    tag = "Admin"
+)]
+async fn health(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<AdminHealthResponse>, StatusCode> {
+    verify_admin_key(&headers, &state)?;
+    Ok(Json(aggregate_health(&state).await))
+}
+
+async fn aggregate_health(state: &AppState) -> AdminHealthResponse {
+    let subagents = match state.get_agent().await {
+        Ok(agent) => agent.subagent_progress().await,
+        Err(_) => HashMap::new(),
+    };
+    let mut subagents_by_status = HashMap::new();
+    for progress in subagents.values() {
+        let status = match progress.status {
+            SubAgentStatus::Ready => "ready",
+            SubAgentStatus::Queued => "queued",
+            SubAgentStatus::Processing => "processing",
+            SubAgentStatus::Completed(_) => "completed",
+            SubAgentStatus::Terminated => "terminated",
+            SubAgentStatus::Suspended => "suspended",
+        };
+        *subagents_by_status.entry(status.to_string()).or_insert(0) += 1;
+    }
+
+    let pool = global_pool_manager();
+    AdminHealthResponse {
+        draining: state.is_draining(),
+        active_sessions: state.active_sessions.count(),
+        subagents: subagents.len(),
+        subagents_by_status,
+        pooled_providers: pool.len(),
+        pool: pool.stats(),
+    }
+}
+
+// Configure routes for this module
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/admin/sessions", get(list_sessions))
+        .route(
+            "/admin/sessions/{session_id}/terminate",
+            post(terminate_session),
+        )
+        .route("/admin/subagents", get(list_subagents))
+        .route(
+            "/admin/subagents/{subagent_id}/terminate",
+            post(terminate_subagent),
+        )
+        .route("/admin/drain", post(drain))
+        .route("/admin/health", get(health))
+        .with_state(state)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::Request};
+    use goose::agents::Agent;
+    use tower::ServiceExt;
+
+    async fn app(admin_key: Option<&str>) -> (Arc<AppState>, Router) {
+        let state = AppState::new_with_admin_key(
+            Arc::new(Agent::new()),
+            "test".to_string(),
+            admin_key.map(str::to_string),
+        )
+        .await;
+        (state.clone(), routes(state))
+    }
+
+    fn request(method: &str, uri: &str, admin_key: Option<&str>, body: Body) -> Request<Body> {
+        let mut request = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("Content-Type", "application/json");
+        if let Some(admin_key) = admin_key {
+            request = request.header("X-Admin-Key", admin_key);
+        }
+        request.body(body).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_require_admin_key() {
+        let (_, disabled) = app(None).await;
+        let response = disabled
+            .oneshot(request("GET", "/admin/health", Some("key"), Body::empty()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let (_, app) = app(Some("admin")).await;
+        let response = app
+            .oneshot(request(
+                "GET",
+                "/admin/health",
+                Some("wrong"),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_drain_and_terminate_session() {
+        let (state, app) = app(Some("admin")).await;
+        let guard = state.active_sessions.start("session-1");
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/drain",
+                Some("admin"),
+                Body::from(r#"{"draining": true}"#),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(state.is_draining());
+
+        let response = app
+            .clone()
+            .oneshot(request(
+                "POST",
+                "/admin/sessions/session-1/terminate",
+                Some("admin"),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        // The stop was stored for the reply to pick up
+        tokio::time::timeout(std::time::Duration::from_secs(1), guard.stop.notified())
+            .await
+            .unwrap();
+
+        drop(guard);
+        let response = app
+            .oneshot(request(
+                "POST",
+                "/admin/sessions/session-1/terminate",
+                Some("admin"),
+                Body::empty(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -6,11 +6,13 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
+use goose::agents::subagent_types::ToolPolicy;
 use goose::config::Config;
 use goose::config::PermissionManager;
+use goose::message::{Message, Question};
 use goose::model::ModelConfig;
-use goose::providers::create;
-use goose::recipe::Response;
+use goose::providers::{create, validate_model};
+use goose::recipe::{Clarifications, Response};
 use goose::{
     agents::{extension::ToolInfo, extension_manager::get_parameter_names},
     config::permission::PermissionLevel,
@@ -66,6 +68,20 @@ struct UpdateProviderRequest {
 #[derive(Deserialize)]
 struct SessionConfigRequest {
     response: Option<Response>,
+    #[serde(default)]
+    require_citations: Option<bool>,
+    #[serde(default)]
+    clarifications: Option<Clarifications>,
+    /// Platform tools to hide from the session, on top of the configured policy
+    #[serde(default)]
+    platform_tool_policy: Option<ToolPolicy>,
+}
+
+#[derive(Deserialize, utoipa::ToSchema)]
+pub struct AnswerQuestionRequest {
+    /// Id of the open question being answered
+    id: String,
+    answer: String,
 }
 
 #[derive(Deserialize)]
@@ -189,6 +205,7 @@ async fn get_tools(
     path = "/agent/update_provider",
     responses(
         (status = 200, description = "Update provider completed", body = String),
+        (status = 400, description = "The model isn't available from the provider"),
         (status = 500, description = "Internal server error")
     )
 )]
@@ -219,6 +236,12 @@ async fn update_agent_provider(
             .expect("Did not find a model on payload or in env to update provider with")
     });
     let model_config = ModelConfig::new(model);
+    validate_model(&payload.provider, &model_config)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Rejected model for {}: {}", payload.provider, e);
+            StatusCode::BAD_REQUEST
+        })?;
     let new_provider = create(&payload.provider, model_config).unwrap();
     agent
         .update_provider(new_provider)
@@ -294,6 +317,21 @@ async fn update_session_config(
         })
     })?;
 
+    if let Some(require_citations) = payload.require_citations {
+        agent.set_require_citations(require_citations).await;
+        tracing::info!("Set citation requirement to {}", require_citations);
+    }
+
+    if let Some(clarifications) = payload.clarifications.clone() {
+        agent.set_clarifications(clarifications).await;
+        tracing::info!("Set clarification contract");
+    }
+
+    if let Some(policy) = payload.platform_tool_policy.clone() {
+        agent.restrict_platform_tools(policy).await;
+        tracing::info!("Restricted platform tools");
+    }
+
     if let Some(response) = payload.response {
         agent.add_final_output_tool(response).await;
 
@@ -301,11 +339,69 @@ async fn update_session_config(
         Ok(Json(
             "Session config updated with final output tool".to_string(),
         ))
+    } else if payload.require_citations.is_some()
+        || payload.clarifications.is_some()
+        || payload.platform_tool_policy.is_some()
+    {
+        Ok(Json("Session config updated".to_string()))
     } else {
         Ok(Json("Nothing provided to update.".to_string()))
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/agent/questions",
+    responses(
+        (status = 200, description = "Open questions retrieved successfully", body = Vec<Question>),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn get_open_questions(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<Question>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    Ok(Json(agent.open_questions().await))
+}
+
+#[utoipa::path(
+    post,
+    path = "/agent/answer",
+    request_body = AnswerQuestionRequest,
+    responses(
+        (status = 200, description = "Question answered; send the returned message to the agent to continue", body = Message),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 404, description = "No open question with that id"),
+        (status = 424, description = "Agent not initialized")
+    )
+)]
+async fn answer_question(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<AnswerQuestionRequest>,
+) -> Result<Json<Message>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    agent
+        .answer_question(&payload.id, payload.answer)
+        .await
+        .map(Json)
+        .map_err(|_| StatusCode::NOT_FOUND)
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/agent/versions", get(get_versions))
@@ -318,5 +414,7 @@ pub fn routes(state: Arc<AppState>) -> Router {
             post(update_router_tool_selector),
         )
         .route("/agent/session_config", post(update_session_config))
+        .route("/agent/questions", get(get_open_questions))
+        .route("/agent/answer", post(answer_question))
         .with_state(state)
 }
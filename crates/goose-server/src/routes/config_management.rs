@@ -13,6 +13,7 @@ use goose::config::{extensions::name_to_key, PermissionManager};
 use goose::config::{ExtensionConfigManager, ExtensionEntry};
 use goose::model::ModelConfig;
 use goose::providers::base::ProviderMetadata;
+use goose::providers::databricks::{DatabricksProvider, ServingEndpoint, DATABRICKS_DEFAULT_MODEL};
 use goose::providers::pricing::{
     get_all_pricing, get_model_pricing, parse_model_id, refresh_pricing,
 };
@@ -136,6 +137,56 @@ pub async fn remove_config(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/config/providers/databricks/endpoints",
+    responses(
+        (status = 200, description = "Serving endpoints in the configured Databricks workspace", body = [ServingEndpoint]),
+        (status = 400, description = "Databricks is not configured"),
+        (status = 502, description = "Databricks could not be reached or rejected the request")
+    )
+)]
+pub async fn databricks_endpoints(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<ServingEndpoint>>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let provider =
+        DatabricksProvider::from_env(ModelConfig::new(DATABRICKS_DEFAULT_MODEL.to_string()))
+            .map_err(|_| StatusCode::BAD_REQUEST)?;
+    match provider.list_serving_endpoints().await {
+        Ok(endpoints) => Ok(Json(endpoints)),
+        Err(e) => {
+            tracing::warn!("Failed to list Databricks serving endpoints: {}", e);
+            Err(StatusCode::BAD_GATEWAY)
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReloadProvidersResponse {
+    /// Number of pooled providers retired; they are rebuilt on next use
+    pub retired: usize,
+}
+
+#[utoipa::path(
+    post,
+    path = "/config/providers/reload",
+    responses(
+        (status = 200, description = "Provider configuration reloaded", body = ReloadProvidersResponse),
+    )
+)]
+pub async fn reload_providers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<ReloadProvidersResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let retired = goose::providers::pool::reload_provider_config();
+    Ok(Json(ReloadProvidersResponse { retired }))
+}
+
 #[utoipa::path(
     post,
     path = "/config/read",
@@ -642,6 +693,11 @@ pub fn routes(state: Arc<AppState>) -> Router {
         .route("/config/extensions", post(add_extension))
         .route("/config/extensions/{name}", delete(remove_extension))
         .route("/config/providers", get(providers))
+        .route("/config/providers/reload", post(reload_providers))
+        .route(
+            "/config/providers/databricks/endpoints",
+            get(databricks_endpoints),
+        )
         .route("/config/pricing", post(get_pricing))
         .route("/config/init", post(init_config))
         .route("/config/backup", post(backup_config))
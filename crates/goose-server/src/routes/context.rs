@@ -6,6 +6,7 @@ use axum::{
     routing::post,
     Json, Router,
 };
+use goose::context_mgmt::report::ContextReport;
 use goose::message::Message;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -79,9 +80,52 @@ async fn manage_context(
     }))
 }
 
+/// Request payload for a context composition report
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextReportRequest {
+    /// The conversation up to the turn to report on
+    pub messages: Vec<Message>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/context/report",
+    request_body = ContextReportRequest,
+    responses(
+        (status = 200, description = "Prompt broken down by source", body = ContextReport),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 412, description = "Precondition failed - Agent not available"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Context Management"
+)]
+async fn context_report(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ContextReportRequest>,
+) -> Result<Json<ContextReport>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    let report = agent
+        .context_report(&request.messages)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    Ok(Json(report))
+}
+
 // Configure routes for this module
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/context/manage", post(manage_context))
+        .route("/context/report", post(context_report))
         .with_state(state)
 }
@@ -1,14 +1,17 @@
 // Export route modules
+pub mod admin;
 pub mod agent;
 pub mod audio;
 pub mod config_management;
 pub mod context;
 pub mod extension;
 pub mod health;
+pub mod provenance;
 pub mod recipe;
 pub mod reply;
 pub mod schedule;
 pub mod session;
+pub mod trigger;
 pub mod utils;
 use std::sync::Arc;
 
@@ -18,13 +21,16 @@ use axum::Router;
 pub fn configure(state: Arc<crate::state::AppState>) -> Router {
     Router::new()
         .merge(health::routes())
+        .merge(admin::routes(state.clone()))
         .merge(reply::routes(state.clone()))
         .merge(agent::routes(state.clone()))
         .merge(audio::routes(state.clone()))
         .merge(context::routes(state.clone()))
+        .merge(provenance::routes(state.clone()))
         .merge(extension::routes(state.clone()))
         .merge(config_management::routes(state.clone()))
         .merge(recipe::routes(state.clone()))
         .merge(session::routes(state.clone()))
         .merge(schedule::routes(state.clone()))
+        .merge(trigger::routes(state.clone()))
 }
@@ -0,0 +1,117 @@
+use super::utils::verify_secret_key;
+use crate::state::AppState;
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+use goose::agents::provenance::{ProvenanceMatch, ProvenanceRecord};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+/// Matches returned for a claim unless the request asks for another number
+const DEFAULT_TRACE_LIMIT: usize = 5;
+
+#[derive(Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenanceQuery {
+    /// Only the records of this session, every session's when absent
+    session_id: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProvenanceListResponse {
+    /// Tool results the model was given, oldest first
+    records: Vec<ProvenanceRecord>,
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenanceTraceRequest {
+    /// The claim from a reply to trace back to its context
+    claim: String,
+    /// Only consider the context of this session
+    session_id: Option<String>,
+    /// Largest number of matches to return
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProvenanceTraceResponse {
+    /// Context the claim most likely came from, best match first
+    matches: Vec<ProvenanceMatch>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/provenance",
+    params(ProvenanceQuery),
+    responses(
+        (status = 200, description = "Where the context given to the model came from", body = ProvenanceListResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 412, description = "Precondition failed - Agent not available")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Provenance"
+)]
+async fn list_provenance(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<ProvenanceQuery>,
+) -> Result<Json<ProvenanceListResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    let records = agent.provenance(query.session_id.as_deref()).await;
+    Ok(Json(ProvenanceListResponse { records }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/provenance/trace",
+    request_body = ProvenanceTraceRequest,
+    responses(
+        (status = 200, description = "Context the claim most likely came from", body = ProvenanceTraceResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 412, description = "Precondition failed - Agent not available")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Provenance"
+)]
+async fn trace_claim(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(request): Json<ProvenanceTraceRequest>,
+) -> Result<Json<ProvenanceTraceResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    let matches = agent
+        .trace_claim(
+            &request.claim,
+            request.session_id.as_deref(),
+            request.limit.unwrap_or(DEFAULT_TRACE_LIMIT),
+        )
+        .await;
+    Ok(Json(ProvenanceTraceResponse { matches }))
+}
+
+// Configure routes for this module
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/provenance", get(list_provenance))
+        .route("/provenance/trace", post(trace_claim))
+        .with_state(state)
+}
@@ -4,13 +4,13 @@ use axum::{
     extract::State,
     http::{self, HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::post,
+    routing::{get, post},
     Json, Router,
 };
 use bytes::Bytes;
 use futures::{stream::StreamExt, Stream};
 use goose::{
-    agents::{AgentEvent, SessionConfig},
+    agents::{subagent_types::SubAgentEventEnvelope, AgentEvent, SessionConfig, TurnUsage},
     message::{Message, MessageContent},
     permission::permission_confirmation::PrincipalType,
 };
@@ -30,7 +30,7 @@ use std::{
     task::{Context, Poll},
     time::Duration,
 };
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use tokio::time::timeout;
 use tokio_stream::wrappers::ReceiverStream;
 use utoipa::ToSchema;
@@ -97,6 +97,9 @@ enum MessageEvent {
         request_id: String,
         message: JsonRpcMessage,
     },
+    Usage {
+        usage: TurnUsage,
+    },
 }
 
 async fn stream_event(
@@ -118,6 +121,9 @@ async fn handler(
     Json(request): Json<ChatRequest>,
 ) -> Result<SseResponse, StatusCode> {
     verify_secret_key(&headers, &state)?;
+    if state.is_draining() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
 
     let (tx, rx) = mpsc::channel(100);
     let stream = ReceiverStream::new(rx);
@@ -128,6 +134,7 @@ async fn handler(
     let session_id = request
         .session_id
         .unwrap_or_else(session::generate_session_id);
+    let active_session = state.active_sessions.start(&session_id);
 
     tokio::spawn(async move {
         let agent = state.get_agent().await;
@@ -226,8 +233,19 @@ async fn handler(
             }
         };
 
+        let mut finish_reason = "stop";
         loop {
             tokio::select! {
+                _ = active_session.stop.notified() => {
+                    let _ = stream_event(
+                        MessageEvent::Error {
+                            error: "Session terminated by an administrator".to_string(),
+                        },
+                        &tx,
+                    ).await;
+                    finish_reason = "terminated";
+                    break;
+                }
                 response = timeout(Duration::from_millis(500), stream.next()) => {
                     match response {
                         Ok(Some(Ok(AgentEvent::Message(message)))) => {
@@ -264,6 +282,11 @@ async fn handler(
                                 ).await;
                             }
                         }
+                        Ok(Some(Ok(AgentEvent::Usage(usage)))) => {
+                            if let Err(e) = stream_event(MessageEvent::Usage { usage }, &tx).await {
+                                tracing::error!("Error sending usage through channel: {}", e);
+                            }
+                        }
                         Ok(Some(Ok(AgentEvent::McpNotification((request_id, n))))) => {
                             if let Err(e) = stream_event(MessageEvent::Notification{
                                 request_id: request_id.clone(),
@@ -305,7 +328,7 @@ async fn handler(
 
         let _ = stream_event(
             MessageEvent::Finish {
-                reason: "stop".to_string(),
+                reason: finish_reason.to_string(),
             },
             &tx,
         )
@@ -334,12 +357,16 @@ async fn ask_handler(
     Json(request): Json<AskRequest>,
 ) -> Result<Json<AskResponse>, StatusCode> {
     verify_secret_key(&headers, &state)?;
+    if state.is_draining() {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
 
     let session_working_dir = request.session_working_dir;
 
     let session_id = request
         .session_id
         .unwrap_or_else(session::generate_session_id);
+    let active_session = state.active_sessions.start(&session_id);
 
     let agent = state
         .get_agent()
@@ -374,7 +401,17 @@ async fn ask_handler(
     let mut all_messages = messages.clone();
     let mut response_message = Message::assistant();
 
-    while let Some(response) = stream.next().await {
+    loop {
+        let response = tokio::select! {
+            _ = active_session.stop.notified() => {
+                tracing::info!("Session {} terminated by an administrator", session_id);
+                return Err(StatusCode::SERVICE_UNAVAILABLE);
+            }
+            response = stream.next() => response,
+        };
+        let Some(response) = response else {
+            break;
+        };
         match response {
             Ok(AgentEvent::Message(message)) => {
                 if message.role == Role::Assistant {
@@ -391,6 +428,9 @@ async fn ask_handler(
                 // Log model change for non-streaming
                 tracing::info!("Model changed to {} in {} mode", model, mode);
             }
+            Ok(AgentEvent::Usage(_)) => {
+                // Usage is recorded in the session metadata
+            }
             Ok(AgentEvent::McpNotification(n)) => {
                 // Handle notifications if needed
                 tracing::info!("Received notification: {:?}", n);
@@ -522,9 +562,91 @@ async fn submit_tool_result(
     Ok(Json(json!({"status": "ok"})))
 }
 
+/// How often an idle subagent event stream sends a comment, so proxies don't close it
+const SUBAGENT_EVENTS_KEEP_ALIVE: Duration = Duration::from_secs(15);
+
+/// Stream what the agent's subagents do as server-sent events, one versioned
+/// `SubAgentEventEnvelope` per event, until the client goes away. Events a slow client missed
+/// are skipped.
+#[utoipa::path(
+    get,
+    path = "/subagent_events",
+    responses(
+        (status = 200, description = "Server-sent events, one subagent event envelope each; see /subagent_events/schema", content_type = "text/event-stream", body = String),
+        (status = 401, description = "Unauthorized - invalid secret key"),
+        (status = 412, description = "Precondition failed - Agent not available")
+    )
+)]
+pub async fn subagent_events_handler(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<SseResponse, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+    let mut events = agent.subscribe_subagent_events();
+
+    let (tx, rx) = mpsc::channel(100);
+    tokio::spawn(async move {
+        let mut keep_alive = tokio::time::interval(SUBAGENT_EVENTS_KEEP_ALIVE);
+        // The first tick completes right away
+        keep_alive.tick().await;
+        loop {
+            let received = tokio::select! {
+                received = events.recv() => received,
+                _ = keep_alive.tick() => {
+                    // Comment lines are ignored by SSE clients
+                    if tx.send(": keep-alive\n\n".to_string()).await.is_err() {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            let event = match received {
+                Ok(event) => event,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Subagent event stream fell behind, skipped {}", skipped);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            let Ok(json) = serde_json::to_string(&SubAgentEventEnvelope::new(event)) else {
+                continue;
+            };
+            if tx.send(format!("data: {}\n\n", json)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Ok(SseResponse::new(ReceiverStream::new(rx)))
+}
+
+/// The JSON Schema of the events sent by `/subagent_events`
+#[utoipa::path(
+    get,
+    path = "/subagent_events/schema",
+    responses(
+        (status = 200, description = "JSON Schema of a subagent event envelope", body = Value),
+        (status = 401, description = "Unauthorized - invalid secret key")
+    )
+)]
+pub async fn subagent_events_schema(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+    Ok(Json(SubAgentEventEnvelope::json_schema()))
+}
+
 pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/reply", post(handler))
+        .route("/subagent_events", get(subagent_events_handler))
+        .route("/subagent_events/schema", get(subagent_events_schema))
         .route("/ask", post(ask_handler))
         .route("/confirm", post(confirm_permission))
         .route("/tool_result", post(submit_tool_result))
@@ -12,6 +12,7 @@ use chrono::NaiveDateTime;
 
 use crate::routes::utils::verify_secret_key;
 use crate::state::AppState;
+use goose::schedule_constraints::ScheduleConstraints;
 use goose::scheduler::ScheduledJob;
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
@@ -21,6 +22,9 @@ pub struct CreateScheduleRequest {
     cron: String,
     #[serde(default)]
     execution_mode: Option<String>, // "foreground" or "background"
+    /// Blackouts and business hours the schedule's runs must respect
+    #[serde(default)]
+    constraints: Option<ScheduleConstraints>,
 }
 
 #[derive(Deserialize, Serialize, utoipa::ToSchema)]
@@ -127,6 +131,7 @@ async fn create_schedule(
         current_session_id: None,
         process_start_time: None,
         execution_mode: req.execution_mode.or(Some("background".to_string())), // Default to background
+        constraints: req.constraints,
     };
     scheduler
         .add_scheduled_job(job.clone())
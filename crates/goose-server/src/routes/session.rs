@@ -3,23 +3,35 @@ use std::sync::Arc;
 
 use crate::state::AppState;
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
     routing::get,
     Json, Router,
 };
 use goose::message::Message;
-use goose::session;
-use goose::session::info::{get_valid_sorted_sessions, SessionInfo, SortOrder};
+use goose::session::history::{list_sessions_page, read_messages_page, InvalidCursor};
+use goose::session::info::SessionInfo;
+use goose::session::view::{session_view, TurnSummary, ZoomLevel};
 use goose::session::SessionMetadata;
-use serde::Serialize;
-use utoipa::ToSchema;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Deserialize, IntoParams)]
+pub struct PageQuery {
+    /// Cursor from a previous response's `nextCursor`
+    cursor: Option<String>,
+    /// Largest number of items to return, everything when absent
+    limit: Option<usize>,
+}
 
 #[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionListResponse {
     /// List of available session information objects
     sessions: Vec<SessionInfo>,
+    /// Cursor for the next page, absent on the last one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
 }
 
 #[derive(Serialize, ToSchema)]
@@ -31,13 +43,40 @@ pub struct SessionHistoryResponse {
     metadata: SessionMetadata,
     /// List of messages in the session conversation
     messages: Vec<Message>,
+    /// Cursor for the next page of messages, absent on the last one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_cursor: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ViewQuery {
+    /// Level of detail, turn summaries when absent
+    #[serde(default)]
+    zoom: ZoomLevel,
+    /// Only show this turn, to expand it
+    turn: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionViewResponse {
+    session_id: String,
+    zoom: ZoomLevel,
+    /// Turn summaries, at the `turns` zoom level
+    #[serde(skip_serializing_if = "Option::is_none")]
+    turns: Option<Vec<TurnSummary>>,
+    /// Messages, at the `messages` and `raw` zoom levels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    messages: Option<Vec<Message>>,
 }
 
 #[utoipa::path(
     get,
     path = "/sessions",
+    params(PageQuery),
     responses(
         (status = 200, description = "List of available sessions retrieved successfully", body = SessionListResponse),
+        (status = 400, description = "Invalid cursor"),
         (status = 401, description = "Unauthorized - Invalid or missing API key"),
         (status = 500, description = "Internal server error")
     ),
@@ -46,27 +85,38 @@ pub struct SessionHistoryResponse {
     ),
     tag = "Session Management"
 )]
-// List all available sessions
+// List available sessions, most recently modified first
 async fn list_sessions(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
+    Query(page): Query<PageQuery>,
 ) -> Result<Json<SessionListResponse>, StatusCode> {
     verify_secret_key(&headers, &state)?;
 
-    let sessions = get_valid_sorted_sessions(SortOrder::Descending)
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let page = list_sessions_page(page.cursor.as_deref(), page.limit).map_err(|e| {
+        if e.is::<InvalidCursor>() {
+            return StatusCode::BAD_REQUEST;
+        }
+        tracing::error!("Failed to list sessions: {:?}", e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
 
-    Ok(Json(SessionListResponse { sessions }))
+    Ok(Json(SessionListResponse {
+        sessions: page.items,
+        next_cursor: page.next_cursor,
+    }))
 }
 
 #[utoipa::path(
     get,
     path = "/sessions/{session_id}",
     params(
-        ("session_id" = String, Path, description = "Unique identifier for the session")
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        PageQuery
     ),
     responses(
         (status = 200, description = "Session history retrieved successfully", body = SessionHistoryResponse),
+        (status = 400, description = "Invalid cursor"),
         (status = 401, description = "Unauthorized - Invalid or missing API key"),
         (status = 404, description = "Session not found"),
         (status = 500, description = "Internal server error")
@@ -81,27 +131,78 @@ async fn get_session_history(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Path(session_id): Path<String>,
+    Query(page): Query<PageQuery>,
 ) -> Result<Json<SessionHistoryResponse>, StatusCode> {
     verify_secret_key(&headers, &state)?;
 
-    let session_path = match session::get_path(session::Identifier::Name(session_id.clone())) {
-        Ok(path) => path,
-        Err(_) => return Err(StatusCode::BAD_REQUEST),
-    };
+    let (metadata, page) = read_messages_page(&session_id, page.cursor.as_deref(), page.limit)
+        .map_err(|e| {
+            if e.is::<InvalidCursor>() {
+                return StatusCode::BAD_REQUEST;
+            }
+            tracing::error!("Failed to read session messages: {:?}", e);
+            StatusCode::NOT_FOUND
+        })?;
 
-    let metadata = session::read_metadata(&session_path).map_err(|_| StatusCode::NOT_FOUND)?;
+    Ok(Json(SessionHistoryResponse {
+        session_id,
+        metadata,
+        messages: page.items,
+        next_cursor: page.next_cursor,
+    }))
+}
 
-    let messages = match session::read_messages(&session_path) {
-        Ok(messages) => messages,
-        Err(e) => {
-            tracing::error!("Failed to read session messages: {:?}", e);
-            return Err(StatusCode::NOT_FOUND);
+#[utoipa::path(
+    get,
+    path = "/sessions/{session_id}/view",
+    params(
+        ("session_id" = String, Path, description = "Unique identifier for the session"),
+        ViewQuery
+    ),
+    responses(
+        (status = 200, description = "Session transcript at the requested zoom level", body = SessionViewResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 404, description = "Session or turn not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Session Management"
+)]
+// Get a session's transcript as turn summaries, condensed messages or raw messages
+async fn get_session_view(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(session_id): Path<String>,
+    Query(query): Query<ViewQuery>,
+) -> Result<Json<SessionViewResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+
+    let view = session_view(&session_id).map_err(|e| {
+        tracing::error!("Failed to read session view: {:?}", e);
+        StatusCode::NOT_FOUND
+    })?;
+
+    let (turns, messages) = match (query.zoom, query.turn) {
+        (ZoomLevel::Turns, None) => (Some(view.turns().to_vec()), None),
+        (ZoomLevel::Turns, Some(turn)) => {
+            let summary = view.turns().get(turn).ok_or(StatusCode::NOT_FOUND)?;
+            (Some(vec![summary.clone()]), None)
+        }
+        (zoom, None) => (None, Some(view.messages(zoom).to_vec())),
+        (zoom, Some(turn)) => {
+            let messages = view
+                .turn_messages(turn, zoom)
+                .ok_or(StatusCode::NOT_FOUND)?;
+            (None, Some(messages.to_vec()))
         }
     };
 
-    Ok(Json(SessionHistoryResponse {
+    Ok(Json(SessionViewResponse {
         session_id,
-        metadata,
+        zoom: query.zoom,
+        turns,
         messages,
     }))
 }
@@ -111,5 +212,6 @@ pub fn routes(state: Arc<AppState>) -> Router {
     Router::new()
         .route("/sessions", get(list_sessions))
         .route("/sessions/{session_id}", get(get_session_history))
+        .route("/sessions/{session_id}/view", get(get_session_view))
         .with_state(state)
 }
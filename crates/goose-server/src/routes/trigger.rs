@@ -0,0 +1,73 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    routing::post,
+    Json, Router,
+};
+use goose::agents::subagent_types::SpawnSubAgentArgs;
+use goose::email_trigger::{InboundEmail, EMAIL_TRIGGER_SESSION_ID};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::routes::utils::verify_secret_key;
+use crate::state::AppState;
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EmailTriggerResponse {
+    /// IDs of the subagents started for the email, one per matching rule
+    subagent_ids: Vec<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/triggers/email",
+    request_body = InboundEmail,
+    responses(
+        (status = 200, description = "Recipes started for the email's matching rules", body = EmailTriggerResponse),
+        (status = 401, description = "Unauthorized - Invalid or missing API key"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("api_key" = [])
+    ),
+    tag = "Triggers"
+)]
+async fn email_trigger(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(email): Json<InboundEmail>,
+) -> Result<Json<EmailTriggerResponse>, StatusCode> {
+    verify_secret_key(&headers, &state)?;
+    let agent = state
+        .get_agent()
+        .await
+        .map_err(|_| StatusCode::PRECONDITION_FAILED)?;
+
+    let mut subagent_ids = Vec::new();
+    for rule in state.email_triggers.matching(&email) {
+        let task = rule.task(&email).map_err(|e| {
+            tracing::error!("Failed to render task for recipe {}: {}", rule.recipe, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        let args = SpawnSubAgentArgs::new_with_recipe(rule.recipe.clone(), task)
+            .with_tool_policy(rule.tool_policy.clone())
+            .with_session_id(EMAIL_TRIGGER_SESSION_ID);
+        let id = agent.run_subagent_in_background(args).await.map_err(|e| {
+            tracing::error!("Failed to run recipe {} for email: {}", rule.recipe, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+        subagent_ids.push(id);
+    }
+    state.email_triggers.mark_handled(&email);
+
+    Ok(Json(EmailTriggerResponse { subagent_ids }))
+}
+
+pub fn routes(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/triggers/email", post(email_trigger))
+        .with_state(state)
+}
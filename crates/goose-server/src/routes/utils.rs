@@ -37,6 +37,22 @@ pub fn verify_secret_key(headers: &HeaderMap, state: &AppState) -> Result<Status
     }
 }
 
+/// Check the `X-Admin-Key` header for the admin role. Admin endpoints are forbidden when
+/// the server has no admin key configured.
+pub fn verify_admin_key(headers: &HeaderMap, state: &AppState) -> Result<StatusCode, StatusCode> {
+    let admin_key = state.admin_key.as_deref().ok_or(StatusCode::FORBIDDEN)?;
+    let provided = headers
+        .get("X-Admin-Key")
+        .and_then(|value| value.to_str().ok())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if provided != admin_key {
+        Err(StatusCode::UNAUTHORIZED)
+    } else {
+        Ok(StatusCode::OK)
+    }
+}
+
 /// Inspects a configuration key to determine if it's set, its location, and value (for non-secret keys)
 #[allow(dead_code)]
 pub fn inspect_key(key_name: &str, is_secret: bool) -> Result<KeyInfo, Box<dyn Error>> {
@@ -1,7 +1,11 @@
+use chrono::{DateTime, Utc};
 use goose::agents::Agent;
+use goose::email_trigger::EmailTriggers;
 use goose::scheduler_trait::SchedulerTrait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, Notify};
 
 pub type AgentRef = Arc<Agent>;
 
@@ -9,15 +13,34 @@ pub type AgentRef = Arc<Agent>;
 pub struct AppState {
     agent: Option<AgentRef>,
     pub secret_key: String,
+    /// Key for the admin endpoints; they are disabled when unset
+    pub admin_key: Option<String>,
     pub scheduler: Arc<Mutex<Option<Arc<dyn SchedulerTrait>>>>,
+    pub active_sessions: Arc<ActiveSessions>,
+    /// Rules for the recipes run by incoming emails
+    pub email_triggers: Arc<EmailTriggers>,
+    draining: Arc<AtomicBool>,
 }
 
 impl AppState {
+    #[allow(dead_code)] // Used by tests; goosed builds its state with new_with_admin_key
     pub async fn new(agent: AgentRef, secret_key: String) -> Arc<AppState> {
+        Self::new_with_admin_key(agent, secret_key, None).await
+    }
+
+    pub async fn new_with_admin_key(
+        agent: AgentRef,
+        secret_key: String,
+        admin_key: Option<String>,
+    ) -> Arc<AppState> {
         Arc::new(Self {
             agent: Some(agent.clone()),
             secret_key,
+            admin_key,
             scheduler: Arc::new(Mutex::new(None)),
+            active_sessions: Arc::new(ActiveSessions::default()),
+            email_triggers: Arc::new(EmailTriggers::from_config()),
+            draining: Arc::new(AtomicBool::new(false)),
         })
     }
 
@@ -39,4 +62,102 @@ impl AppState {
             .clone()
             .ok_or_else(|| anyhow::anyhow!("Scheduler not initialized"))
     }
+
+    /// Whether the node is draining for maintenance and refuses new replies
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn set_draining(&self, draining: bool) {
+        self.draining.store(draining, Ordering::SeqCst);
+    }
+}
+
+/// A reply currently streaming for a session
+struct ActiveSession {
+    started_at: DateTime<Utc>,
+    stop: Arc<Notify>,
+}
+
+/// Sessions with a reply in progress, so they can be listed and stopped
+#[derive(Default)]
+pub struct ActiveSessions {
+    sessions: std::sync::Mutex<HashMap<String, ActiveSession>>,
+}
+
+impl ActiveSessions {
+    /// Track a reply for `session_id` until the returned guard is dropped. The guard's
+    /// `stop` is notified when the session is terminated.
+    pub fn start(self: &Arc<Self>, session_id: &str) -> ActiveSessionGuard {
+        let stop = Arc::new(Notify::new());
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                session_id.to_string(),
+                ActiveSession {
+                    started_at: Utc::now(),
+                    stop: Arc::clone(&stop),
+                },
+            );
+        ActiveSessionGuard {
+            sessions: Arc::clone(self),
+            session_id: session_id.to_string(),
+            stop,
+        }
+    }
+
+    /// Stop the reply in progress for a session. Returns false if there is none.
+    pub fn terminate(&self, session_id: &str) -> bool {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        match sessions.get(session_id) {
+            Some(session) => {
+                session.stop.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Session ids with a reply in progress and when the reply started
+    pub fn list(&self) -> HashMap<String, DateTime<Utc>> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|(id, session)| (id.clone(), session.started_at))
+            .collect()
+    }
+
+    /// Number of sessions with a reply in progress
+    pub fn count(&self) -> usize {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .len()
+    }
+}
+
+/// Keeps a session listed as active while a reply streams
+pub struct ActiveSessionGuard {
+    sessions: Arc<ActiveSessions>,
+    session_id: String,
+    pub stop: Arc<Notify>,
+}
+
+impl Drop for ActiveSessionGuard {
+    fn drop(&mut self) {
+        let mut sessions = self
+            .sessions
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        // A newer reply for the same session may have replaced this one
+        if sessions
+            .get(&self.session_id)
+            .is_some_and(|session| Arc::ptr_eq(&session.stop, &self.stop))
+        {
+            sessions.remove(&self.session_id);
+        }
+    }
 }
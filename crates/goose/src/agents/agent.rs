@@ -14,22 +14,28 @@ use crate::agents::sub_recipe_execution_tool::sub_recipe_execute_task_tool::{
 };
 use crate::agents::sub_recipe_manager::SubRecipeManager;
 use crate::config::{Config, ExtensionConfigManager, PermissionManager};
-use crate::message::Message;
+use crate::message::{FinishReason, Message, MessageContent, Question};
+use crate::model::ModelConfig;
 use crate::permission::permission_judge::check_tool_permissions;
 use crate::permission::PermissionConfirmation;
-use crate::providers::base::Provider;
+use crate::providers::base::{CompletionOptions, Provider};
 use crate::providers::errors::ProviderError;
-use crate::recipe::{Author, Recipe, Response, Settings, SubRecipe};
+use crate::providers::pool::global_pool_manager;
+use crate::providers::rate_limit::{with_priority, Priority};
+use crate::providers::usage_tracker::with_usage_session;
+use crate::recipe::{Author, Clarifications, Recipe, Response, Settings, SubRecipe};
 use crate::scheduler_trait::SchedulerTrait;
+use crate::token_counter::CountTokens;
 use crate::tool_monitor::{ToolCall, ToolMonitor};
 use regex::Regex;
 use serde_json::Value;
-use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
 use tracing::{debug, error, instrument};
 
 use crate::agents::extension::{ExtensionConfig, ExtensionError, ExtensionResult, ToolInfo};
 use crate::agents::extension_manager::{get_parameter_names, ExtensionManager};
 use crate::agents::platform_tools::{
+    PLATFORM_DESCRIBE_TOOL_TOOL_NAME, PLATFORM_GET_SUBAGENT_STATUS_TOOL_NAME,
     PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME,
     PLATFORM_MANAGE_SCHEDULE_TOOL_NAME, PLATFORM_READ_RESOURCE_TOOL_NAME,
     PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
@@ -41,30 +47,49 @@ use crate::agents::router_tool_selector::{
 use crate::agents::router_tools::{ROUTER_LLM_SEARCH_TOOL_NAME, ROUTER_VECTOR_SEARCH_TOOL_NAME};
 use crate::agents::tool_router_index_manager::ToolRouterIndexManager;
 use crate::agents::tool_vectordb::generate_table_id;
-use crate::agents::types::SessionConfig;
-use crate::agents::types::{FrontendTool, ToolResultReceiver};
+use crate::agents::types::{FrontendTool, HostTool, PlatformToolHandler, ToolResultReceiver};
+use crate::agents::types::{SessionConfig, TurnUsage};
 use mcp_core::{
     prompt::Prompt, protocol::GetPromptResult, tool::Tool, Content, ToolError, ToolResult,
 };
 
-use crate::agents::subagent_tools::SUBAGENT_RUN_TASK_TOOL_NAME;
+use crate::agents::subagent_tools::{
+    SUBAGENT_COLLECT_RESULTS_TOOL_NAME, SUBAGENT_HANDOFF_TOOL_NAME,
+    SUBAGENT_RELAY_MESSAGE_TOOL_NAME, SUBAGENT_RUN_TASK_TOOL_NAME, SUBAGENT_SEND_MESSAGE_TOOL_NAME,
+    SUBAGENT_SPAWN_PARALLEL_TOOL_NAME,
+};
 
+use super::citations;
+use super::clarifications;
 use super::final_output_tool::FinalOutputTool;
+use super::platform_tool_access::PlatformToolAccess;
 use super::platform_tools;
+use super::provenance::{ProvenanceLog, ProvenanceMatch, ProvenanceRecord};
 use super::router_tools;
-use super::subagent_manager::SubAgentManager;
+use super::session_resources::{Artifact, SessionResources};
+use super::subagent_manager::{SubAgentManager, EVENT_CAPACITY};
 use super::subagent_tools;
+use super::subagent_types::{SubAgentEvent, ToolPolicy};
+use super::tool_call_limit;
+use super::tool_costs::ToolCosts;
 use super::tool_execution::{ToolCallResult, CHAT_MODE_TOOL_SKIPPED_RESPONSE, DECLINED_RESPONSE};
+use super::tool_output_dedup::ToolOutputDedup;
+use super::tool_output_summary::{ToolOutputSummarizer, ToolOutputSummaryConfig};
 
 const DEFAULT_MAX_TURNS: u32 = 1000;
 
+const CITATIONS_PROMPT_KEY: &str = "citations";
+const CLARIFICATIONS_PROMPT_KEY: &str = "clarifications";
+
 /// The main goose Agent
 pub struct Agent {
     pub(super) provider: Mutex<Option<Arc<dyn Provider>>>,
-    pub(super) extension_manager: RwLock<ExtensionManager>,
+    pub(super) extension_manager: Arc<RwLock<ExtensionManager>>,
     pub(super) sub_recipe_manager: Mutex<SubRecipeManager>,
     pub(super) final_output_tool: Mutex<Option<FinalOutputTool>>,
     pub(super) frontend_tools: Mutex<HashMap<String, FrontendTool>>,
+    /// Tools registered by the embedding application, run in-process
+    pub(super) host_tools: Mutex<HashMap<String, HostTool>>,
     pub(super) frontend_instructions: Mutex<Option<String>>,
     pub(super) prompt_manager: Mutex<PromptManager>,
     pub(super) confirmation_tx: mpsc::Sender<(String, PermissionConfirmation)>,
@@ -74,15 +99,42 @@ pub struct Agent {
     pub(super) tool_monitor: Mutex<Option<ToolMonitor>>,
     pub(super) router_tool_selector: Mutex<Option<Arc<Box<dyn RouterToolSelector>>>>,
     pub(super) scheduler_service: Mutex<Option<Arc<dyn SchedulerTrait>>>,
-    pub(super) subagent_manager: Mutex<Option<SubAgentManager>>,
+    pub(super) subagent_manager: Mutex<Option<Arc<SubAgentManager>>>,
+    /// Event bus of the subagent manager, kept here so subscribers outlive a manager swap
+    pub(super) subagent_events: broadcast::Sender<SubAgentEvent>,
     pub(super) mcp_notification_rx: Arc<Mutex<mpsc::Receiver<JsonRpcMessage>>>,
+    /// Priority of the current session, inherited by the subagents it spawns
+    pub(super) priority: Mutex<Priority>,
+    /// Estimates prompt size before dispatch; created on first use unless set explicitly
+    pub(super) token_counter: Mutex<Option<Arc<dyn CountTokens>>>,
+    /// Transcript, plan and artifacts of the current session, readable as `goose://session/...`
+    pub(super) session_resources: Arc<Mutex<SessionResources>>,
+    /// Configured tool cost weights and the session budget they are checked against
+    pub(super) tool_costs: Mutex<ToolCosts>,
+    /// Outputs already returned in this session, so repeats can be replaced by a reference
+    pub(super) tool_output_dedup: Arc<Mutex<ToolOutputDedup>>,
+    /// Where each tool result the model was given came from, to trace claims back to it
+    pub(super) provenance: Mutex<ProvenanceLog>,
+    /// Whether final answers must cite the tool results they rely on
+    pub(super) require_citations: Mutex<bool>,
+    /// Whether the agent states its assumptions and may ask clarifying questions
+    pub(super) clarifications: Mutex<Option<Clarifications>>,
+    /// Questions the agent asked that haven't been answered yet, by id
+    pub(super) open_questions: Mutex<HashMap<String, Question>>,
+    /// Which platform tools the session sees, by its policy and how deeply it is nested
+    pub(super) platform_tool_access: Mutex<PlatformToolAccess>,
 }
 
 #[derive(Clone, Debug)]
 pub enum AgentEvent {
     Message(Message),
     McpNotification((String, JsonRpcMessage)),
-    ModelChange { model: String, mode: String },
+    ModelChange {
+        model: String,
+        mode: String,
+    },
+    /// Running usage for the reply, sent after each provider call
+    Usage(TurnUsage),
 }
 
 impl Default for Agent {
@@ -132,13 +184,16 @@ impl Agent {
         let (tool_tx, tool_rx) = mpsc::channel(32);
         // Add MCP notification channel
         let (mcp_tx, mcp_rx) = mpsc::channel(100);
+        let session_resources = Arc::new(Mutex::new(SessionResources::default()));
+        let (subagent_events, _) = broadcast::channel(EVENT_CAPACITY);
 
         Self {
             provider: Mutex::new(None),
-            extension_manager: RwLock::new(ExtensionManager::new()),
+            extension_manager: Arc::new(RwLock::new(ExtensionManager::new())),
             sub_recipe_manager: Mutex::new(SubRecipeManager::new()),
             final_output_tool: Mutex::new(None),
             frontend_tools: Mutex::new(HashMap::new()),
+            host_tools: Mutex::new(HashMap::new()),
             frontend_instructions: Mutex::new(None),
             prompt_manager: Mutex::new(PromptManager::new()),
             confirmation_tx: confirm_tx,
@@ -149,8 +204,23 @@ impl Agent {
             router_tool_selector: Mutex::new(None),
             scheduler_service: Mutex::new(None),
             // Initialize with MCP notification support
-            subagent_manager: Mutex::new(Some(SubAgentManager::new(mcp_tx))),
+            subagent_manager: Mutex::new(Some(Arc::new(
+                SubAgentManager::new(mcp_tx)
+                    .with_artifact_store(Arc::clone(&session_resources))
+                    .with_events(subagent_events.clone()),
+            ))),
+            subagent_events,
             mcp_notification_rx: Arc::new(Mutex::new(mcp_rx)),
+            priority: Mutex::new(Priority::default()),
+            token_counter: Mutex::new(None),
+            session_resources,
+            tool_costs: Mutex::new(ToolCosts::from_config()),
+            tool_output_dedup: Arc::new(Mutex::new(ToolOutputDedup::from_config())),
+            provenance: Mutex::new(ProvenanceLog::from_config()),
+            require_citations: Mutex::new(false),
+            clarifications: Mutex::new(None),
+            open_questions: Mutex::new(HashMap::new()),
+            platform_tool_access: Mutex::new(PlatformToolAccess::from_config()),
         }
     }
 
@@ -184,6 +254,20 @@ impl Agent {
         }
     }
 
+    /// Swap the model the agent talks to mid-session, e.g. between a fast and a powerful
+    /// model, keeping the provider's HTTP client and credentials. A provider that can't switch
+    /// in place is recreated as `provider_name`. Unlike [`Agent::update_provider`] this leaves
+    /// subagents and the tool router untouched.
+    pub async fn switch_model(&self, provider_name: &str, model: ModelConfig) -> Result<()> {
+        let mut provider = self.provider.lock().await;
+        let current = provider
+            .as_ref()
+            .ok_or_else(|| anyhow!("Provider not set"))?;
+        let switched = crate::providers::switch_model(provider_name, current, model)?;
+        *provider = Some(switched);
+        Ok(())
+    }
+
     /// Check if a tool is a frontend tool
     pub async fn is_frontend_tool(&self, name: &str) -> bool {
         self.frontend_tools.lock().await.contains_key(name)
@@ -194,6 +278,73 @@ impl Agent {
         self.frontend_tools.lock().await.get(name).cloned()
     }
 
+    /// Register a tool that is handled in-process by the embedding application,
+    /// e.g. `create_ticket`, without running a separate MCP server.
+    ///
+    /// The tool is listed alongside the platform tools under its own name, which must
+    /// not contain the `__` extension separator. Registering the same name again
+    /// replaces the previous handler.
+    pub async fn register_platform_tool(
+        &self,
+        tool: Tool,
+        handler: impl PlatformToolHandler + 'static,
+    ) -> Result<()> {
+        if tool.name.is_empty() || tool.name.contains("__") {
+            return Err(anyhow!(
+                "Invalid platform tool name '{}': must be non-empty and not contain '__'",
+                tool.name
+            ));
+        }
+        if self.is_frontend_tool(&tool.name).await {
+            return Err(anyhow!(
+                "Tool '{}' is already registered as a frontend tool",
+                tool.name
+            ));
+        }
+
+        self.host_tools.lock().await.insert(
+            tool.name.clone(),
+            HostTool {
+                tool,
+                handler: Arc::new(handler),
+            },
+        );
+        Ok(())
+    }
+
+    /// Remove a tool added with `register_platform_tool`, returning whether it existed
+    pub async fn unregister_platform_tool(&self, name: &str) -> bool {
+        self.host_tools.lock().await.remove(name).is_some()
+    }
+
+    /// Replace the tool cost weights and session budget loaded from config
+    pub async fn set_tool_costs(&self, tool_costs: ToolCosts) {
+        *self.tool_costs.lock().await = tool_costs;
+    }
+
+    /// Check a tool call against the session budget and charge its estimated cost
+    async fn charge_tool_cost(&self, tool_name: &str) -> Result<(), ToolError> {
+        let tool_costs = self.tool_costs.lock().await;
+        let Some(cost) = tool_costs.cost_for(tool_name).map(|cost| cost.cost_usd) else {
+            return Ok(());
+        };
+
+        let session_id = self.session_resources.lock().await.session_id().to_string();
+        let usage = global_pool_manager().usage();
+        tool_costs
+            .check_budget(tool_name, usage.spend(&session_id).total_cost_usd())
+            .map_err(ToolError::ExecutionError)?;
+        if cost > 0.0 {
+            usage.record_tool_cost(&session_id, cost);
+        }
+        Ok(())
+    }
+
+    /// Check if a tool was registered by the embedding application
+    pub async fn is_host_tool(&self, name: &str) -> bool {
+        self.host_tools.lock().await.contains_key(name)
+    }
+
     /// Get all tools from all clients with proper prefixing
     pub async fn get_prefixed_tools(&self) -> ExtensionResult<Vec<Tool>> {
         let mut tools = self
@@ -220,6 +371,97 @@ impl Agent {
         self.extend_system_prompt(final_output_system_prompt).await;
     }
 
+    /// Make final answers cite the tool results they rely on. An answer with claims that cite
+    /// nothing, or cite ids that aren't tool results, goes back to the model to fix.
+    pub async fn set_require_citations(&self, require: bool) {
+        let mut require_citations = self.require_citations.lock().await;
+        self.set_system_prompt_extra(
+            CITATIONS_PROMPT_KEY,
+            require.then(|| citations::CITATION_SYSTEM_PROMPT.to_string()),
+        )
+        .await;
+        *require_citations = require;
+    }
+
+    /// Give the agent explicit assumption and question channels. Tagged assumptions and
+    /// questions in its answers become their own message content, a final answer that must
+    /// state its assumptions and doesn't is sent back once, and questions stay open until
+    /// answered with [`Self::answer_question`] or the session changes. A later contract
+    /// replaces the earlier one, prompt included.
+    pub async fn set_clarifications(&self, contract: Clarifications) {
+        let mut clarifications = self.clarifications.lock().await;
+        self.set_system_prompt_extra(
+            CLARIFICATIONS_PROMPT_KEY,
+            clarifications::system_prompt(&contract),
+        )
+        .await;
+        *clarifications = Some(contract);
+    }
+
+    /// Narrow which platform tools the session sees and may call, on top of
+    /// `GOOSE_PLATFORM_TOOL_POLICY`. Restrictions add up; a later call never widens access.
+    pub async fn restrict_platform_tools(&self, policy: ToolPolicy) {
+        self.platform_tool_access.lock().await.restrict(policy);
+    }
+
+    /// Questions the agent asked that haven't been answered yet
+    pub async fn open_questions(&self) -> Vec<Question> {
+        self.open_questions.lock().await.values().cloned().collect()
+    }
+
+    /// Answer an open question. The returned user message carries the answered question and
+    /// is what to send to the agent next to let it go on.
+    pub async fn answer_question(&self, id: &str, answer: String) -> Result<Message> {
+        let mut question = self
+            .open_questions
+            .lock()
+            .await
+            .remove(id)
+            .ok_or_else(|| anyhow!("No open question with id {}", id))?;
+        question.answer = Some(answer);
+        Ok(Message::user().with_content(MessageContent::Question(question)))
+    }
+
+    /// Share a plan with the session, readable as `goose://session/<id>/plan`
+    pub async fn set_session_plan(&self, plan: Option<String>) {
+        self.session_resources.lock().await.set_plan(plan);
+    }
+
+    /// Share a text artifact with the session, readable as
+    /// `goose://session/<id>/artifacts/<name>`
+    pub async fn add_session_artifact(&self, name: impl Into<String>, content: impl Into<String>) {
+        let name = name.into();
+        let artifact = Artifact::text(&name, content);
+        self.session_resources
+            .lock()
+            .await
+            .add_artifact(name, artifact);
+    }
+
+    /// Read a `goose://session/...` resource
+    pub async fn read_session_resource(&self, uri: &str) -> Option<String> {
+        self.session_resources.lock().await.read(uri)
+    }
+
+    /// URIs and names of the current session's resources
+    pub async fn list_session_resources(&self) -> Vec<(String, String)> {
+        self.session_resources.lock().await.list()
+    }
+
+    /// Provider request priority of the session currently being served
+    pub async fn priority(&self) -> Priority {
+        *self.priority.lock().await
+    }
+
+    /// The validated structured output recorded by the final output tool, if any
+    pub async fn final_output(&self) -> Option<String> {
+        self.final_output_tool
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|tool| tool.final_output.clone())
+    }
+
     pub async fn add_sub_recipes(&self, sub_recipes: Vec<SubRecipe>) {
         let mut sub_recipe_manager = self.sub_recipe_manager.lock().await;
         sub_recipe_manager.add_sub_recipe_tools(sub_recipes);
@@ -246,6 +488,26 @@ impl Agent {
             }
         }
 
+        // Hidden tools are refused too, in case the model calls one it saw elsewhere
+        if !self
+            .platform_tool_access
+            .lock()
+            .await
+            .is_visible(&tool_call.name)
+        {
+            return (
+                request_id,
+                Err(ToolError::ExecutionError(format!(
+                    "Tool {} is not available in this session",
+                    tool_call.name
+                ))),
+            );
+        }
+
+        if let Err(e) = self.charge_tool_cost(&tool_call.name).await {
+            return (request_id, Err(e));
+        }
+
         if tool_call.name == PLATFORM_MANAGE_SCHEDULE_TOOL_NAME {
             let result = self
                 .handle_schedule_management(tool_call.arguments, request_id.clone())
@@ -273,6 +535,11 @@ impl Agent {
             return (request_id, Ok(ToolCallResult::from(result)));
         }
 
+        if tool_call.name == PLATFORM_DESCRIBE_TOOL_TOOL_NAME {
+            let result = self.describe_tool(&tool_call.arguments).await;
+            return (request_id, Ok(ToolCallResult::from(result)));
+        }
+
         if tool_call.name == FINAL_OUTPUT_TOOL_NAME {
             if let Some(final_output_tool) = self.final_output_tool.lock().await.as_mut() {
                 let result = final_output_tool.execute_tool_call(tool_call.clone()).await;
@@ -296,25 +563,86 @@ impl Agent {
         } else if tool_call.name == SUB_RECIPE_EXECUTE_TASK_TOOL_NAME {
             sub_recipe_execute_task_tool::run_tasks(tool_call.arguments.clone()).await
         } else if tool_call.name == PLATFORM_READ_RESOURCE_TOOL_NAME {
-            // Check if the tool is read_resource and handle it separately
-            ToolCallResult::from(
-                extension_manager
-                    .read_resource(tool_call.arguments.clone())
-                    .await,
-            )
+            // Session resources are served by the agent itself, everything else by extensions
+            let uri = tool_call.arguments.get("uri").and_then(|v| v.as_str());
+            match uri.filter(|uri| SessionResources::is_session_uri(uri)) {
+                Some(uri) => ToolCallResult::from(
+                    self.session_resources
+                        .lock()
+                        .await
+                        .read_content(uri)
+                        .map(|content| vec![content])
+                        .ok_or_else(|| {
+                            ToolError::InvalidParameters(format!(
+                                "Session resource with uri '{}' not found",
+                                uri
+                            ))
+                        }),
+                ),
+                None => ToolCallResult::from(
+                    extension_manager
+                        .read_resource(tool_call.arguments.clone())
+                        .await,
+                ),
+            }
         } else if tool_call.name == PLATFORM_LIST_RESOURCES_TOOL_NAME {
-            ToolCallResult::from(
-                extension_manager
-                    .list_resources(tool_call.arguments.clone())
-                    .await,
-            )
+            let mut resources = self
+                .list_session_resources()
+                .await
+                .into_iter()
+                .map(|(uri, name)| format!("goose - {}, uri: ({})", name, uri))
+                .collect::<Vec<String>>()
+                .join("\n");
+            match tool_call
+                .arguments
+                .get("extension")
+                .and_then(|v| v.as_str())
+            {
+                Some("goose") => ToolCallResult::from(Ok(vec![Content::text(resources)])),
+                _ => ToolCallResult::from(
+                    extension_manager
+                        .list_resources(tool_call.arguments.clone())
+                        .await
+                        .map(|mut contents| {
+                            if tool_call.arguments.get("extension").is_none() {
+                                resources.push('\n');
+                                contents.insert(0, Content::text(resources));
+                            }
+                            contents
+                        }),
+                ),
+            }
         } else if tool_call.name == PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME {
             ToolCallResult::from(extension_manager.search_available_extensions().await)
         } else if tool_call.name == SUBAGENT_RUN_TASK_TOOL_NAME {
+            self.handle_run_subagent_task(tool_call.arguments.clone())
+                .await
+        } else if tool_call.name == SUBAGENT_SPAWN_PARALLEL_TOOL_NAME {
             ToolCallResult::from(
-                self.handle_run_subagent_task(tool_call.arguments.clone())
+                self.handle_spawn_parallel_subagents(tool_call.arguments.clone())
                     .await,
             )
+        } else if tool_call.name == SUBAGENT_COLLECT_RESULTS_TOOL_NAME {
+            self.handle_collect_subagent_results(tool_call.arguments.clone())
+                .await
+        } else if tool_call.name == SUBAGENT_SEND_MESSAGE_TOOL_NAME {
+            self.handle_send_subagent_message(tool_call.arguments.clone())
+                .await
+        } else if tool_call.name == SUBAGENT_RELAY_MESSAGE_TOOL_NAME {
+            self.handle_relay_subagent_message(tool_call.arguments.clone())
+                .await
+        } else if tool_call.name == SUBAGENT_HANDOFF_TOOL_NAME {
+            self.handle_subagent_handoff(tool_call.arguments.clone())
+                .await
+        } else if tool_call.name == PLATFORM_GET_SUBAGENT_STATUS_TOOL_NAME {
+            self.handle_get_subagent_status(tool_call.arguments.clone())
+                .await
+        } else if let Some(host_tool) = self.host_tools.lock().await.get(&tool_call.name).cloned() {
+            let arguments = tool_call.arguments.clone();
+            ToolCallResult {
+                result: Box::new(async move { host_tool.handler.call(arguments).await }.boxed()),
+                notification_stream: None,
+            }
         } else if self.is_frontend_tool(&tool_call.name).await {
             // For frontend tools, return an error indicating we need frontend execution
             ToolCallResult::from(Err(ToolError::ExecutionError(
@@ -346,6 +674,8 @@ impl Agent {
                     )
                 }
             };
+            // Put cheaper tools first so the model sees them before costly alternatives
+            let selected_tools = self.tool_costs.lock().await.rank_selected(selected_tools);
             ToolCallResult::from(Ok(selected_tools))
         } else {
             // Clone the result to ensure no references to extension_manager are returned
@@ -358,19 +688,61 @@ impl Agent {
             }
         };
 
+        let output = result
+            .result
+            .map(super::large_response_handler::process_tool_response);
+        // Dedupe before summarizing so a repeated large output isn't summarized again
+        let output: Box<dyn Future<Output = ToolResult<Vec<Content>>> + Send + Unpin> =
+            if self.tool_output_dedup.lock().await.is_enabled() {
+                let dedup = Arc::clone(&self.tool_output_dedup);
+                let (tool_name, request_id) = (tool_call.name.clone(), request_id.clone());
+                Box::new(
+                    async move {
+                        let output = output.await;
+                        dedup.lock().await.process(&tool_name, &request_id, output)
+                    }
+                    .boxed(),
+                )
+            } else {
+                Box::new(output)
+            };
+        let output: Box<dyn Future<Output = ToolResult<Vec<Content>>> + Send + Unpin> =
+            match self.tool_output_summarizer().await {
+                Some(summarizer) => {
+                    let (tool_name, request_id) = (tool_call.name.clone(), request_id.clone());
+                    Box::new(
+                        async move {
+                            summarizer
+                                .process(&tool_name, &request_id, output.await)
+                                .await
+                        }
+                        .boxed(),
+                    )
+                }
+                None => Box::new(output),
+            };
+
         (
             request_id,
             Ok(ToolCallResult {
                 notification_stream: result.notification_stream,
-                result: Box::new(
-                    result
-                        .result
-                        .map(super::large_response_handler::process_tool_response),
-                ),
+                result: output,
             }),
         )
     }
 
+    /// Summarizer for large tool outputs, if `GOOSE_TOOL_OUTPUT_SUMMARY_THRESHOLD` is set
+    async fn tool_output_summarizer(&self) -> Option<ToolOutputSummarizer> {
+        let config = ToolOutputSummaryConfig::from_config()?;
+        let provider = self.provider().await.ok()?;
+        Some(ToolOutputSummarizer::new(
+            config.summary_provider(provider),
+            self.token_counter().await?,
+            config.threshold_tokens,
+            Arc::clone(&self.session_resources),
+        ))
+    }
+
     pub(super) async fn manage_extensions(
         &self,
         action: String,
@@ -541,6 +913,36 @@ impl Agent {
         Ok(())
     }
 
+    /// The full, uncompressed definition of a tool, for `platform__describe_tool`
+    async fn describe_tool(&self, arguments: &Value) -> Result<Vec<Content>, ToolError> {
+        let name = arguments
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing 'name' parameter".to_string()))?;
+
+        let mut tools = self.list_tools(None).await;
+        tools.extend(
+            self.frontend_tools
+                .lock()
+                .await
+                .values()
+                .map(|frontend_tool| frontend_tool.tool.clone()),
+        );
+        let tool = tools
+            .into_iter()
+            .find(|tool| tool.name == name)
+            .ok_or_else(|| ToolError::NotFound(format!("Tool '{}' not found", name)))?;
+
+        let definition = serde_json::json!({
+            "name": tool.name,
+            "description": tool.description,
+            "inputSchema": tool.input_schema,
+        });
+        Ok(vec![Content::text(
+            serde_json::to_string_pretty(&definition).unwrap_or_default(),
+        )])
+    }
+
     pub async fn list_tools(&self, extension_name: Option<String>) -> Vec<Tool> {
         let extension_manager = self.extension_manager.read().await;
         let mut prefixed_tools = extension_manager
@@ -556,14 +958,31 @@ impl Agent {
                 platform_tools::manage_schedule_tool(),
             ]);
 
+            // Add tools registered by the embedding application
+            prefixed_tools.extend(
+                self.host_tools
+                    .lock()
+                    .await
+                    .values()
+                    .map(|host_tool| host_tool.tool.clone()),
+            );
+
             // Add subagent tool (only if ALPHA_FEATURES is enabled)
             let config = Config::global();
             if config.get_param::<bool>("ALPHA_FEATURES").unwrap_or(false) {
                 prefixed_tools.push(subagent_tools::run_task_subagent_tool());
+                prefixed_tools.push(subagent_tools::spawn_parallel_subagent_tool());
+                prefixed_tools.push(subagent_tools::collect_results_subagent_tool());
+                prefixed_tools.push(subagent_tools::send_message_subagent_tool());
+                prefixed_tools.push(subagent_tools::relay_message_subagent_tool());
+                prefixed_tools.push(subagent_tools::handoff_subagent_tool());
+                prefixed_tools.push(platform_tools::get_subagent_status_tool());
             }
 
             // Add resource tools if supported
-            if extension_manager.supports_resources() {
+            if extension_manager.supports_resources()
+                || self.session_resources.lock().await.has_shared_content()
+            {
                 prefixed_tools.extend([
                     platform_tools::read_resource_tool(),
                     platform_tools::list_resources_tool(),
@@ -582,6 +1001,8 @@ impl Agent {
                 .push(sub_recipe_execute_task_tool::create_sub_recipe_execute_task_tool());
         }
 
+        let access = self.platform_tool_access.lock().await;
+        prefixed_tools.retain(|tool| access.is_visible(&tool.name));
         prefixed_tools
     }
 
@@ -672,6 +1093,37 @@ impl Agent {
     ) -> anyhow::Result<BoxStream<'_, anyhow::Result<AgentEvent>>> {
         let mut messages = messages.to_vec();
         let reply_span = tracing::Span::current();
+        let priority = session
+            .as_ref()
+            .map(SessionConfig::priority)
+            .unwrap_or_default();
+        *self.priority.lock().await = priority;
+        let session_id = session
+            .as_ref()
+            .and_then(|s| crate::session::storage::get_path(s.id.clone()).ok())
+            .and_then(|path| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            });
+        let usage_session = session_id.clone();
+        // A configured seed makes runs reproducible where the provider supports it
+        let completion_options = CompletionOptions::from_config();
+        // Subagents left running by an earlier process come back suspended
+        if let Some(session_id) = &session_id {
+            if let Err(e) = self.restore_subagents(session_id).await {
+                tracing::warn!("Failed to restore subagents of {}: {}", session_id, e);
+            }
+        }
+        let session_changed = self
+            .session_resources
+            .lock()
+            .await
+            .set_session_id(session_id);
+        // Questions and the outputs deduplicated against belong to the session they came from
+        if session_changed {
+            self.open_questions.lock().await.clear();
+            self.tool_output_dedup.lock().await.clear();
+        }
 
         // Load settings from config
         let config = Config::global();
@@ -714,6 +1166,14 @@ impl Agent {
         Ok(Box::pin(async_stream::try_stream! {
             let _ = reply_span.enter();
             let mut turns_taken = 0u32;
+            let mut content_filter_rewritten = false;
+            let mut citation_retries = 0;
+            let mut assumptions_requested = false;
+            let clarification_contract = self.clarifications.lock().await.clone();
+            let max_citation_retries = citations::max_retries_from_config();
+            let max_tool_calls = tool_call_limit::max_from_config();
+            let mut turn_usage = TurnUsage::default();
+            let turn_budget_usd: Option<f64> = config.get_param("GOOSE_TURN_BUDGET_USD").ok();
             let max_turns = session
                 .as_ref()
                 .and_then(|s| s.max_turns)
@@ -729,6 +1189,16 @@ impl Agent {
                     ));
                     break;
                 }
+                if turn_usage.exceeds(turn_budget_usd) {
+                    yield AgentEvent::Message(Message::assistant().with_text(format!(
+                        "I've stopped because this reply has cost about ${:.4}, over the budget of ${:.4} for a single reply. Would you like me to continue?",
+                        turn_usage.cost_usd,
+                        turn_budget_usd.unwrap_or_default(),
+                    )));
+                    break;
+                }
+
+                self.session_resources.lock().await.set_transcript(&messages);
 
                 // Check for MCP notifications from subagents
                 let mcp_notifications = self.get_mcp_notifications().await;
@@ -752,14 +1222,33 @@ impl Agent {
                     }
                 }
 
-                match Self::generate_response_from_provider(
+                // Hand the conversation back for compaction before paying for a request
+                // the provider is going to reject anyway
+                if self.exceeds_context_limit(&system_prompt, &messages, &tools).await {
+                    yield AgentEvent::Message(Message::assistant().with_context_length_exceeded(
+                        "The conversation is estimated to exceed the context length of the model. Please reduce the number of messages and try again.",
+                    ));
+                    break;
+                }
+
+                let completion = with_priority(priority, Self::generate_response_from_provider(
                     self.provider().await?,
                     &system_prompt,
                     &messages,
                     &tools,
                     &toolshim_tools,
-                ).await {
+                    &completion_options,
+                ));
+                let completion = match &usage_session {
+                    Some(session_id) => with_usage_session(session_id.clone(), completion).await,
+                    None => completion.await,
+                };
+                match completion {
                     Ok((response, usage)) => {
+                        let response = match &clarification_contract {
+                            Some(contract) => clarifications::structure_response(response, contract),
+                            None => response,
+                        };
                         // Emit model change event if provider is lead-worker
                         let provider = self.provider().await?;
                         if let Some(lead_worker) = provider.as_lead_worker() {
@@ -782,15 +1271,60 @@ impl Agent {
 
                         // record usage for the session in the session file
                         if let Some(session_config) = session.clone() {
-                            Self::update_session_metrics(session_config, &usage, messages.len()).await?;
+                            Self::update_session_metrics(session_config, &usage, messages.len(), completion_options.seed).await?;
                         }
 
+                        // Providers return usage once a response is complete, so the running
+                        // total advances per request; a request that takes the reply over
+                        // GOOSE_TURN_BUDGET_USD stops it before the next one
+                        turn_usage.add(&usage.usage);
+                        yield AgentEvent::Usage(turn_usage.clone());
+
                         // categorize the type of requests we need to handle
                         let (frontend_requests,
                             remaining_requests,
                             filtered_response) =
                             self.categorize_tool_requests(&response).await;
 
+                        // Dispatch no more tool calls than one turn may make; the rest are
+                        // answered with an error telling the model to call them again later
+                        let requested = frontend_requests.len() + remaining_requests.len();
+
+                        // An answer that doesn't cite its sources goes back to the model before
+                        // anyone sees it; only the corrected answer is yielded
+                        if requested == 0 && *self.require_citations.lock().await && citation_retries < max_citation_retries {
+                            let sources = citations::source_ids(&messages);
+                            let report = citations::check_citations(&response.as_concat_text(), &sources);
+                            if !sources.is_empty() && !report.is_ok() {
+                                citation_retries += 1;
+                                tracing::warn!("Final answer does not cite its sources. Asking the model to fix it.");
+                                messages.push(response.clone());
+                                messages.push(Message::user().with_text(report.feedback(&sources)));
+                                continue;
+                            }
+                        }
+                        let (frontend_requests, mut over_limit) =
+                            tool_call_limit::split(frontend_requests, max_tool_calls);
+                        let (remaining_requests, rejected) = tool_call_limit::split(
+                            remaining_requests,
+                            max_tool_calls.map(|max| max - frontend_requests.len()),
+                        );
+                        over_limit.extend(rejected);
+                        let mut over_limit_response = Message::user();
+                        if let Some(max) = max_tool_calls.filter(|_| !over_limit.is_empty()) {
+                            tracing::warn!(
+                                "Model asked for {} tool calls in one turn, rejecting all but the first {}",
+                                requested,
+                                max
+                            );
+                            for request in &over_limit {
+                                over_limit_response = over_limit_response.with_tool_response(
+                                    request.id.clone(),
+                                    Err(tool_call_limit::rejection(max, requested)),
+                                );
+                            }
+                        }
+
                         // Record tool calls in the router selector
                         let selector = self.router_tool_selector.lock().await.clone();
                         if let Some(selector) = selector {
@@ -816,8 +1350,42 @@ impl Agent {
 
                         tokio::task::yield_now().await;
 
+                        match response.finish_reason {
+                            Some(FinishReason::Length) => tracing::warn!(
+                                "Model response was cut off by the output token limit"
+                            ),
+                            Some(FinishReason::ContentFilter) => tracing::warn!(
+                                "Model response was withheld or truncated by the provider's content filter"
+                            ),
+                            _ => {}
+                        }
+
                         let num_tool_requests = frontend_requests.len() + remaining_requests.len();
                         if num_tool_requests == 0 {
+                            if let Some(contract) = &clarification_contract {
+                                let questions: Vec<Question> = response
+                                    .content
+                                    .iter()
+                                    .filter_map(|content| content.as_question().cloned())
+                                    .collect();
+                                let states_assumptions = response
+                                    .content
+                                    .iter()
+                                    .any(|content| content.as_assumption().is_some());
+                                if contract.require_assumptions && !states_assumptions && questions.is_empty() && !assumptions_requested {
+                                    assumptions_requested = true;
+                                    tracing::warn!("Final answer does not state its assumptions. Asking the model to list them.");
+                                    let feedback = Message::user().with_text(clarifications::MISSING_ASSUMPTIONS_FEEDBACK);
+                                    messages.push(response.clone());
+                                    messages.push(feedback.clone());
+                                    yield AgentEvent::Message(feedback);
+                                    continue;
+                                }
+                                let mut open_questions = self.open_questions.lock().await;
+                                for question in questions {
+                                    open_questions.insert(question.id.clone(), question);
+                                }
+                            }
                             if let Some(final_output_tool) = self.final_output_tool.lock().await.as_ref() {
                                 if final_output_tool.final_output.is_none() {
                                     tracing::warn!("Final output tool has not been called yet. Continuing agent loop.");
@@ -835,7 +1403,7 @@ impl Agent {
                         }
 
                         // Process tool requests depending on frontend tools and then goose_mode
-                        let message_tool_response = Arc::new(Mutex::new(Message::user()));
+                        let message_tool_response = Arc::new(Mutex::new(over_limit_response));
 
                         // First handle any frontend tool requests
                         let mut frontend_tool_stream = self.handle_frontend_tool_requests(
@@ -962,6 +1530,11 @@ impl Agent {
                         }
 
                         let final_message_tool_resp = message_tool_response.lock().await.clone();
+                        self.provenance.lock().await.record_turn(
+                            usage_session.as_deref(),
+                            &response,
+                            &final_message_tool_resp,
+                        );
                         yield AgentEvent::Message(final_message_tool_resp.clone());
 
                         messages.push(response);
@@ -1001,6 +1574,30 @@ impl Agent {
                         ));
                         break;
                     },
+                    Err(ProviderError::ContentFiltered(reason)) => {
+                        // Optionally retry once with a sanitized rewrite of the prompt
+                        if !content_filter_rewritten
+                            && config.get_param::<bool>("GOOSE_CONTENT_FILTER_REWRITE").unwrap_or(false)
+                        {
+                            content_filter_rewritten = true;
+                            match Self::rewrite_filtered_prompt(self.provider().await?, &mut messages).await {
+                                Ok(true) => {
+                                    tracing::info!("Prompt was blocked by the content filter, retrying with a rewritten prompt");
+                                    continue;
+                                }
+                                Ok(false) => {}
+                                Err(e) => tracing::warn!("Failed to rewrite prompt blocked by the content filter: {}", e),
+                            }
+                        }
+                        yield AgentEvent::Message(
+                            Message::assistant()
+                                .with_text(format!(
+                                    "The model provider's content filter blocked this request ({reason}). Please rephrase it and try again."
+                                ))
+                                .with_finish_reason(FinishReason::ContentFilter),
+                        );
+                        break;
+                    },
                     Err(e) => {
                         // Create an error message & terminate the stream
                         error!("Error: {}", e);
@@ -1015,12 +1612,34 @@ impl Agent {
         }))
     }
 
+    /// Where the tool results of `session_id`, or of every session if `None`, came from
+    pub async fn provenance(&self, session_id: Option<&str>) -> Vec<ProvenanceRecord> {
+        self.provenance.lock().await.records(session_id)
+    }
+
+    /// The context a claim in a reply most likely came from, best match first
+    pub async fn trace_claim(
+        &self,
+        claim: &str,
+        session_id: Option<&str>,
+        limit: usize,
+    ) -> Vec<ProvenanceMatch> {
+        self.provenance.lock().await.trace(claim, session_id, limit)
+    }
+
     /// Extend the system prompt with one line of additional instruction
     pub async fn extend_system_prompt(&self, instruction: String) {
         let mut prompt_manager = self.prompt_manager.lock().await;
         prompt_manager.add_system_prompt_extra(instruction);
     }
 
+    /// Set or remove the system prompt instruction kept under `key`; see
+    /// [`PromptManager::set_system_prompt_extra`]
+    pub async fn set_system_prompt_extra(&self, key: &str, instruction: Option<String>) {
+        let mut prompt_manager = self.prompt_manager.lock().await;
+        prompt_manager.set_system_prompt_extra(key, instruction);
+    }
+
     /// Get MCP notifications from subagents
     pub async fn get_mcp_notifications(&self) -> Vec<JsonRpcMessage> {
         let mut notifications = Vec::new();
@@ -1033,6 +1652,11 @@ impl Agent {
         notifications
     }
 
+    /// Follow what this agent's subagents do as it happens; see [`SubAgentManager::subscribe`]
+    pub fn subscribe_subagent_events(&self) -> broadcast::Receiver<SubAgentEvent> {
+        self.subagent_events.subscribe()
+    }
+
     /// Update the provider
     pub async fn update_provider(&self, provider: Arc<dyn Provider>) -> Result<()> {
         let mut current_provider = self.provider.lock().await;
@@ -1045,7 +1669,11 @@ impl Agent {
             let mut rx_guard = self.mcp_notification_rx.lock().await;
             *rx_guard = mcp_rx;
         }
-        *self.subagent_manager.lock().await = Some(SubAgentManager::new(mcp_tx));
+        *self.subagent_manager.lock().await = Some(Arc::new(
+            SubAgentManager::new(mcp_tx)
+                .with_artifact_store(Arc::clone(&self.session_resources))
+                .with_events(self.subagent_events.clone()),
+        ));
 
         self.update_router_tool_selector(Some(provider), None)
             .await?;
@@ -1329,7 +1957,9 @@ impl Agent {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::providers::mock::MockProvider;
     use crate::recipe::Response;
+    use mcp_core::tool::ToolCall;
 
     #[tokio::test]
     async fn test_add_final_output_tool() -> Result<()> {
@@ -1366,4 +1996,118 @@ mod tests {
         assert!(system_prompt.contains(&final_output_tool_system_prompt));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_citation_prompt_follows_the_setting() {
+        let agent = Agent::new();
+        let system_prompt = || async {
+            agent.prompt_manager.lock().await.build_system_prompt(
+                vec![],
+                None,
+                serde_json::Value::Null,
+                None,
+                None,
+            )
+        };
+
+        agent.set_require_citations(true).await;
+        agent.set_require_citations(false).await;
+        agent.set_require_citations(true).await;
+        agent.set_require_citations(true).await;
+        assert_eq!(
+            system_prompt()
+                .await
+                .matches(citations::CITATION_SYSTEM_PROMPT)
+                .count(),
+            1
+        );
+
+        agent.set_require_citations(false).await;
+        assert!(!system_prompt()
+            .await
+            .contains(citations::CITATION_SYSTEM_PROMPT));
+    }
+
+    #[tokio::test]
+    async fn test_clarifications_prompt_is_replaced() {
+        let agent = Agent::new();
+        let contract = |require_assumptions, allow_questions| Clarifications {
+            require_assumptions,
+            allow_questions,
+        };
+        let extras = || async {
+            agent.prompt_manager.lock().await.build_system_prompt(
+                vec![],
+                None,
+                serde_json::Value::Null,
+                None,
+                None,
+            )
+        };
+
+        agent.set_clarifications(contract(true, true)).await;
+        let both = clarifications::system_prompt(&contract(true, true)).unwrap();
+        assert!(extras().await.contains(&both));
+
+        agent.set_clarifications(contract(false, true)).await;
+        let questions = clarifications::system_prompt(&contract(false, true)).unwrap();
+        let system_prompt = extras().await;
+        assert!(!system_prompt.contains(&both));
+        assert_eq!(system_prompt.matches(&questions).count(), 1);
+
+        agent.set_clarifications(contract(false, false)).await;
+        assert!(!extras().await.contains(&questions));
+    }
+
+    #[tokio::test]
+    async fn test_uncited_answer_is_not_yielded() -> Result<()> {
+        let uncited = "The build failed because the linker ran out of memory on the runner.";
+        let cited = "The build failed because the linker ran out of memory [cite: call_1].";
+        let agent = Agent::new();
+        agent
+            .update_provider(Arc::new(
+                MockProvider::default().with_text(uncited).with_text(cited),
+            ))
+            .await?;
+        agent.set_require_citations(true).await;
+
+        let messages = vec![
+            Message::user().with_text("Why did the build fail?"),
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(ToolCall::new("developer__shell", serde_json::json!({}))),
+            ),
+            Message::user()
+                .with_tool_response("call_1", Ok(vec![Content::text("ld: out of memory")])),
+        ];
+        let mut stream = agent.reply(&messages, None).await?;
+        let mut answers = Vec::new();
+        while let Some(event) = stream.next().await {
+            if let AgentEvent::Message(message) = event? {
+                answers.push(message.as_concat_text());
+            }
+        }
+
+        assert!(!answers.iter().any(|answer| answer == uncited));
+        assert_eq!(answers.last().map(String::as_str), Some(cited));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_switch_model_recreates_providers_that_cannot_switch() -> Result<()> {
+        let agent = Agent::new();
+        agent
+            .update_provider(Arc::new(MockProvider::default()))
+            .await?;
+
+        // The mock provider can't switch in place, so a new one is created for the model
+        agent
+            .switch_model("mock", ModelConfig::new("powerful".to_string()))
+            .await?;
+        assert_eq!(
+            agent.provider().await?.get_model_config().model_name,
+            "powerful"
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,223 @@
+use std::collections::BTreeMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::config::Config;
+use crate::message::{Message, MessageContent};
+
+/// Times the model is asked to fix its citations unless `GOOSE_CITATION_MAX_RETRIES` says otherwise
+const DEFAULT_MAX_RETRIES: usize = 2;
+/// Words a passage needs before it counts as a claim that must be cited
+const MIN_CLAIM_WORDS: usize = 6;
+/// Characters of a passage quoted back to the model when it lacks a citation
+const QUOTE_CHARS: usize = 80;
+
+static CITATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[cite:\s*([^\]\s]+)\s*\]").unwrap());
+
+pub const CITATION_SYSTEM_PROMPT: &str = "Every claim in your final answer must cite the tool \
+result it comes from. Put [cite:<id>] after the claim, where <id> is the id of the tool call \
+whose result supports it; cite several with [cite:<id1>][cite:<id2>]. Don't cite ids you were \
+not given and don't make claims you can't support with a tool result.";
+
+pub fn max_retries_from_config() -> usize {
+    Config::global()
+        .get_param::<usize>("GOOSE_CITATION_MAX_RETRIES")
+        .unwrap_or(DEFAULT_MAX_RETRIES)
+}
+
+/// The sources an answer can cite: the id of every tool call that returned text, with the tool
+/// that made it
+pub fn source_ids(messages: &[Message]) -> BTreeMap<String, String> {
+    let tools: BTreeMap<&str, &str> = messages
+        .iter()
+        .flat_map(|message| &message.content)
+        .filter_map(|content| match content {
+            MessageContent::ToolRequest(request) => request
+                .tool_call
+                .as_ref()
+                .ok()
+                .map(|call| (request.id.as_str(), call.name.as_str())),
+            _ => None,
+        })
+        .collect();
+
+    messages
+        .iter()
+        .flat_map(|message| &message.content)
+        .filter_map(|content| match content {
+            MessageContent::ToolResponse(response) => match &response.tool_result {
+                Ok(result) if result.iter().any(|c| c.as_text().is_some()) => Some((
+                    response.id.clone(),
+                    tools
+                        .get(response.id.as_str())
+                        .map(|tool| tool.to_string())
+                        .unwrap_or_default(),
+                )),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// What is wrong with the citations of an answer
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CitationReport {
+    /// Passages making claims without citing anything
+    pub uncited: Vec<String>,
+    /// Cited ids that aren't among the sources
+    pub unknown: Vec<String>,
+}
+
+impl CitationReport {
+    pub fn is_ok(&self) -> bool {
+        self.uncited.is_empty() && self.unknown.is_empty()
+    }
+
+    /// Ask the model to fix the answer, listing the problems and the ids it may cite
+    pub fn feedback(&self, sources: &BTreeMap<String, String>) -> String {
+        let mut feedback = String::from(
+            "Your answer doesn't cite its sources properly. Rewrite the whole answer so every claim cites the tool result it comes from with [cite:<id>].\n",
+        );
+        if !self.uncited.is_empty() {
+            feedback.push_str("\nThese passages have no citation:\n");
+            for passage in &self.uncited {
+                feedback.push_str(&format!("- \"{}\"\n", passage));
+            }
+        }
+        if !self.unknown.is_empty() {
+            feedback.push_str(&format!(
+                "\nThese cited ids are not sources: {}\n",
+                self.unknown.join(", ")
+            ));
+        }
+        feedback.push_str("\nYou can cite:\n");
+        for (id, tool) in sources {
+            feedback.push_str(&format!("- {} ({})\n", id, tool));
+        }
+        feedback
+    }
+}
+
+/// Check that every claim of an answer cites a known source. A claim is a paragraph or list
+/// item of some length; headings, code blocks and short lines such as greetings are left alone.
+pub fn check_citations(answer: &str, sources: &BTreeMap<String, String>) -> CitationReport {
+    let mut report = CitationReport::default();
+    for passage in passages(answer) {
+        let cited: Vec<&str> = CITATION
+            .captures_iter(&passage)
+            .filter_map(|captures| captures.get(1).map(|id| id.as_str()))
+            .collect();
+        for id in &cited {
+            if !sources.contains_key(*id) && !report.unknown.iter().any(|known| known == id) {
+                report.unknown.push(id.to_string());
+            }
+        }
+        let words = CITATION
+            .replace_all(&passage, "")
+            .split_whitespace()
+            .count();
+        if cited.is_empty() && words >= MIN_CLAIM_WORDS {
+            report
+                .uncited
+                .push(passage.chars().take(QUOTE_CHARS).collect());
+        }
+    }
+    report
+}
+
+fn passages(answer: &str) -> Vec<String> {
+    let mut passages = Vec::new();
+    let mut current = String::new();
+    let mut in_code = false;
+    let mut flush = |current: &mut String| {
+        if !current.trim().is_empty() {
+            passages.push(current.trim().to_string());
+        }
+        current.clear();
+    };
+
+    for line in answer.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") {
+            flush(&mut current);
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            continue;
+        }
+        let list_item = trimmed.starts_with("- ")
+            || trimmed.starts_with("* ")
+            || trimmed
+                .split_once(". ")
+                .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        if trimmed.is_empty() || trimmed.starts_with('#') || list_item {
+            flush(&mut current);
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        current.push_str(trimmed);
+        current.push(' ');
+    }
+    flush(&mut current);
+    passages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::{handler::ToolError, tool::ToolCall, Content};
+    use serde_json::json;
+
+    fn sources() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("call_1".to_string(), "developer__shell".to_string()),
+            ("call_2".to_string(), "memory__search_knowledge".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_source_ids_only_include_text_results() {
+        let messages = vec![
+            Message::assistant()
+                .with_tool_request("call_1", Ok(ToolCall::new("developer__shell", json!({}))))
+                .with_tool_request("call_2", Ok(ToolCall::new("developer__shell", json!({})))),
+            Message::user()
+                .with_tool_response("call_1", Ok(vec![Content::text("3 files")]))
+                .with_tool_response("call_2", Err(ToolError::ExecutionError("boom".into()))),
+        ];
+        assert_eq!(
+            source_ids(&messages),
+            BTreeMap::from([("call_1".to_string(), "developer__shell".to_string())])
+        );
+    }
+
+    #[test]
+    fn test_check_citations() {
+        let answer = "# Summary\n\
+            The release ships three new providers and drops Python 3.8 [cite:call_1].\n\n\
+            - The memory index now supports pgvector as a backend [cite:call_2]\n\
+            - Knowledge sources are synced on a schedule every hour\n\
+            - Qdrant collections are created lazily on first use [cite:call_9]\n\n\
+            ```\nlet x = some_code_that_is_long_enough_to_count();\n```\n\
+            Hope that helps!";
+
+        let report = check_citations(answer, &sources());
+        assert_eq!(
+            report.uncited,
+            vec!["- Knowledge sources are synced on a schedule every hour".to_string()]
+        );
+        assert_eq!(report.unknown, vec!["call_9".to_string()]);
+        assert!(!report.is_ok());
+
+        let feedback = report.feedback(&sources());
+        assert!(feedback.contains("call_9"));
+        assert!(feedback.contains("- call_2 (memory__search_knowledge)"));
+
+        let fixed = "The release ships three new providers and drops Python 3.8 [cite:call_1][cite:call_2].";
+        assert!(check_citations(fixed, &sources()).is_ok());
+    }
+}
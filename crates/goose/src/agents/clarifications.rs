@@ -0,0 +1,172 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::message::{Message, MessageContent};
+use crate::recipe::Clarifications;
+
+static CLARIFICATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?s)<(assumption|question)>\s*(.*?)\s*</(?:assumption|question)>").unwrap()
+});
+
+const ASSUMPTION_PROMPT: &str = "State each assumption you make about the task instead of \
+asking about it, as <assumption>what you assumed</assumption>, so it can be checked.";
+
+const QUESTION_PROMPT: &str = "When you can't go on without more information, ask a clarifying \
+question as <question>your question</question> and end your turn. The answer comes back in \
+the next message.";
+
+pub const MISSING_ASSUMPTIONS_FEEDBACK: &str = "Your answer doesn't state its assumptions. Repeat \
+the answer and list every assumption you made as <assumption>what you assumed</assumption>, \
+or <assumption>none</assumption> if you made none.";
+
+/// What the system prompt has to ask of the model for a recipe's clarification contract
+pub fn system_prompt(clarifications: &Clarifications) -> Option<String> {
+    let parts: Vec<&str> = [
+        (clarifications.require_assumptions, ASSUMPTION_PROMPT),
+        (clarifications.allow_questions, QUESTION_PROMPT),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, prompt)| enabled.then_some(prompt))
+    .collect();
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Turn the `<assumption>` and `<question>` tags in a response's text into assumption and
+/// question content, each with an id of its own. Questions are only taken when the contract
+/// allows them; the text around the tags stays where it was.
+pub fn structure_response(response: Message, clarifications: &Clarifications) -> Message {
+    let mut structured = Message {
+        content: Vec::with_capacity(response.content.len()),
+        ..response.clone()
+    };
+    for content in response.content {
+        let MessageContent::Text(text) = &content else {
+            structured.content.push(content);
+            continue;
+        };
+        let mut rest = String::new();
+        let mut last = 0;
+        let mut found = Vec::new();
+        for captures in CLARIFICATION.captures_iter(&text.text) {
+            let whole = captures.get(0).unwrap();
+            let kind = &captures[1];
+            let body = captures[2].to_string();
+            if kind == "question" && !clarifications.allow_questions {
+                continue;
+            }
+            rest.push_str(&text.text[last..whole.start()]);
+            last = whole.end();
+            let id = format!(
+                "{}_{}",
+                kind,
+                &uuid::Uuid::new_v4().simple().to_string()[..8]
+            );
+            found.push(match kind {
+                "assumption" => MessageContent::assumption(id, body),
+                _ => MessageContent::question(id, body),
+            });
+        }
+        if found.is_empty() {
+            structured.content.push(content);
+            continue;
+        }
+        rest.push_str(&text.text[last..]);
+        if !rest.trim().is_empty() {
+            structured.content.push(MessageContent::text(rest.trim()));
+        }
+        structured.content.extend(found);
+    }
+    structured
+}
+
+/// Write assumptions and questions back as text, the only form providers understand.
+/// Answered questions read as the answer to the question.
+pub fn flatten(messages: &[Message]) -> Vec<Message> {
+    messages
+        .iter()
+        .map(|message| {
+            if !message.content.iter().any(|content| {
+                matches!(
+                    content,
+                    MessageContent::Assumption(_) | MessageContent::Question(_)
+                )
+            }) {
+                return message.clone();
+            }
+            let content = message
+                .content
+                .iter()
+                .map(|content| match content {
+                    MessageContent::Assumption(assumption) => MessageContent::text(format!(
+                        "<assumption>{}</assumption>",
+                        assumption.text
+                    )),
+                    MessageContent::Question(question) => match &question.answer {
+                        Some(answer) => MessageContent::text(format!(
+                            "Answer to your question \"{}\": {}",
+                            question.question, answer
+                        )),
+                        None => MessageContent::text(format!(
+                            "<question>{}</question>",
+                            question.question
+                        )),
+                    },
+                    other => other.clone(),
+                })
+                .collect();
+            Message {
+                content,
+                ..message.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structure_response_and_flatten() {
+        let contract = Clarifications {
+            require_assumptions: true,
+            allow_questions: true,
+        };
+        let response = Message::assistant().with_text(
+            "I'll add the endpoint.\n<assumption>The API is versioned under /v1</assumption>\n\
+             <question>Should it require auth?</question>",
+        );
+
+        let structured = structure_response(response, &contract);
+        assert_eq!(structured.as_concat_text(), "I'll add the endpoint.");
+        let assumption = structured.content[1].as_assumption().unwrap();
+        assert_eq!(assumption.text, "The API is versioned under /v1");
+        let question = structured.content[2].as_question().unwrap();
+        assert_eq!(question.question, "Should it require auth?");
+        assert!(question.id.starts_with("question_"));
+
+        let mut answered = question.clone();
+        answered.answer = Some("Yes".to_string());
+        let flattened = flatten(&[
+            structured.clone(),
+            Message::user().with_content(MessageContent::Question(answered)),
+        ]);
+        assert!(flattened[0]
+            .as_concat_text()
+            .contains("<assumption>The API is versioned under /v1</assumption>"));
+        assert_eq!(
+            flattened[1].as_concat_text(),
+            "Answer to your question \"Should it require auth?\": Yes"
+        );
+
+        let no_questions = Clarifications {
+            require_assumptions: true,
+            allow_questions: false,
+        };
+        let structured = structure_response(
+            Message::assistant().with_text("<question>Really?</question>"),
+            &no_questions,
+        );
+        assert_eq!(structured.as_concat_text(), "<question>Really?</question>");
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::message::Message;
+use crate::providers::base::Provider;
+use crate::recipe::{ConfidenceCheck, Recipe};
+
+/// Scores below this count as low confidence unless the recipe sets its own threshold
+const DEFAULT_THRESHOLD: f64 = 0.7;
+
+const CONFIDENCE_PROMPT: &str = "You review an answer to a task against its success criteria. \
+Judge how likely it is that the answer meets every criterion, from 0 (certainly not) to 1 \
+(certainly). Reply with JSON only, like {\"score\": 0.8, \"rationale\": \"one or two \
+sentences on what is missing or uncertain\"}.";
+
+/// How confident the model is that a final answer meets the recipe's success criteria
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceScore {
+    /// Between 0 and 1
+    pub score: f64,
+    pub rationale: String,
+    /// Whether the score is under the recipe's threshold
+    pub low: bool,
+}
+
+/// Ask the model to score an answer against the criteria of `check`, or against the recipe's
+/// instructions when it lists none
+pub async fn assess(
+    provider: &dyn Provider,
+    recipe: &Recipe,
+    check: &ConfidenceCheck,
+    task: &str,
+    answer: &str,
+) -> Result<ConfidenceScore> {
+    let criteria = if check.criteria.is_empty() {
+        recipe
+            .instructions
+            .clone()
+            .unwrap_or_else(|| recipe.description.clone())
+    } else {
+        check
+            .criteria
+            .iter()
+            .map(|criterion| format!("- {}", criterion))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let message = Message::user().with_text(format!(
+        "Success criteria:\n{}\n\nTask:\n{}\n\nAnswer:\n{}",
+        criteria, task, answer
+    ));
+    let (response, _) = provider
+        .complete(CONFIDENCE_PROMPT, &[message], &[])
+        .await?;
+    parse_score(
+        &response.as_concat_text(),
+        check.threshold.unwrap_or(DEFAULT_THRESHOLD),
+    )
+}
+
+/// Read the score out of the model's reply, tolerating text or code fences around the JSON
+fn parse_score(reply: &str, threshold: f64) -> Result<ConfidenceScore> {
+    #[derive(Deserialize)]
+    struct Reply {
+        score: f64,
+        #[serde(default)]
+        rationale: String,
+    }
+
+    let start = reply.find('{');
+    let end = reply.rfind('}');
+    let json = match (start, end) {
+        (Some(start), Some(end)) if start < end => &reply[start..=end],
+        _ => return Err(anyhow!("No confidence score in reply: {}", reply)),
+    };
+    let parsed: Reply = serde_json::from_str(json)?;
+    if !parsed.score.is_finite() {
+        return Err(anyhow!("Invalid confidence score {}", parsed.score));
+    }
+    let score = parsed.score.clamp(0.0, 1.0);
+    Ok(ConfidenceScore {
+        score,
+        rationale: parsed.rationale,
+        low: score < threshold,
+    })
+}
+
+/// The task given to a verification recipe for an answer scored with low confidence
+pub fn verification_task(task: &str, answer: &str, score: &ConfidenceScore) -> String {
+    format!(
+        "Verify this answer and correct it where it falls short. It was scored {:.2} for \
+        confidence: {}\n\nTask:\n{}\n\nAnswer:\n{}",
+        score.score, score.rationale, task, answer
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_score() {
+        let reply = "```json\n{\"score\": 0.55, \"rationale\": \"No tests were run.\"}\n```";
+        let score = parse_score(reply, DEFAULT_THRESHOLD).unwrap();
+        assert_eq!(score.score, 0.55);
+        assert_eq!(score.rationale, "No tests were run.");
+        assert!(score.low);
+
+        let score = parse_score("{\"score\": 1.4}", 0.5).unwrap();
+        assert_eq!(score.score, 1.0);
+        assert!(!score.low);
+
+        assert!(parse_score("I am fairly confident", 0.5).is_err());
+    }
+}
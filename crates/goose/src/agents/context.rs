@@ -1,20 +1,98 @@
+use std::sync::Arc;
+
 use anyhow::Ok;
+use mcp_core::tool::Tool;
 
+use crate::config::Config;
 use crate::message::Message;
-use crate::token_counter::create_async_token_counter;
+use crate::token_counter::{create_async_token_counter, CountTokens};
 
+use crate::context_mgmt::report::ContextReport;
 use crate::context_mgmt::summarize::summarize_messages_async;
 use crate::context_mgmt::truncate::{truncate_messages, OldestFirstTruncation};
 use crate::context_mgmt::{estimate_target_context_limit, get_messages_token_counts_async};
 
 use super::super::agents::Agent;
 
+/// Fraction of the model's context limit a request may use before it is compacted up front
+const DEFAULT_CONTEXT_PREFLIGHT_THRESHOLD: f64 = 0.9;
+
 impl Agent {
+    /// Replace the token counter used to estimate prompt size before each request
+    pub async fn set_token_counter(&self, token_counter: Arc<dyn CountTokens>) {
+        *self.token_counter.lock().await = Some(token_counter);
+    }
+
+    pub(super) async fn token_counter(&self) -> Option<Arc<dyn CountTokens>> {
+        let mut token_counter = self.token_counter.lock().await;
+        if token_counter.is_none() {
+            match create_async_token_counter().await {
+                Result::Ok(counter) => *token_counter = Some(Arc::new(counter)),
+                Err(e) => tracing::debug!("Token counter unavailable: {}", e),
+            }
+        }
+        token_counter.clone()
+    }
+
+    /// Whether a request is estimated to exceed the share of the model's context window
+    /// set by `GOOSE_CONTEXT_PREFLIGHT_THRESHOLD` (0.9 by default, 0 disables the check)
+    pub(crate) async fn exceeds_context_limit(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> bool {
+        let threshold = Config::global()
+            .get_param::<f64>("GOOSE_CONTEXT_PREFLIGHT_THRESHOLD")
+            .unwrap_or(DEFAULT_CONTEXT_PREFLIGHT_THRESHOLD);
+        if threshold <= 0.0 {
+            return false;
+        }
+        let (Result::Ok(provider), Some(token_counter)) =
+            (self.provider().await, self.token_counter().await)
+        else {
+            return false;
+        };
+
+        let context_limit = provider.get_model_config().context_limit();
+        let estimated = token_counter.count_chat_tokens(system_prompt, messages, tools);
+        let exceeded = estimated as f64 > context_limit as f64 * threshold;
+        if exceeded {
+            tracing::info!(
+                "Estimated prompt size of {} tokens exceeds {:.0}% of the {} token context limit",
+                estimated,
+                threshold * 100.0,
+                context_limit
+            );
+        }
+        exceeded
+    }
+
+    /// Break down the prompt the agent would send for `messages` by source: system prompt
+    /// sections, pinned instructions, tool schemas and each message. Pass the conversation up
+    /// to any earlier turn to see what that turn's prompt was made of.
+    pub async fn context_report(&self, messages: &[Message]) -> anyhow::Result<ContextReport> {
+        // With toolshim the tool schemas are part of the system prompt, so `tools` is empty
+        let (tools, _, system_prompt) = self.prepare_tools_and_prompt().await?;
+        let token_counter = self
+            .token_counter()
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Token counter unavailable"))?;
+        Ok(ContextReport::build(
+            token_counter.as_ref(),
+            &system_prompt,
+            messages,
+            &tools,
+        ))
+    }
+
     /// Public API to truncate oldest messages so that the conversation's token count is within the allowed context limit.
     pub async fn truncate_context(
         &self,
         messages: &[Message], // last message is a user msg that led to assistant message with_context_length_exceeded
     ) -> Result<(Vec<Message>, Vec<usize>), anyhow::Error> {
+        // Earlier tool results may be dropped, so repeats can no longer refer to them
+        self.tool_output_dedup.lock().await.clear();
         let provider = self.provider().await?;
         let token_counter = create_async_token_counter()
             .await
@@ -52,6 +130,8 @@ impl Agent {
         &self,
         messages: &[Message], // last message is a user msg that led to assistant message with_context_length_exceeded
     ) -> Result<(Vec<Message>, Vec<usize>), anyhow::Error> {
+        // Earlier tool results may be dropped, so repeats can no longer refer to them
+        self.tool_output_dedup.lock().await.clear();
         let provider = self.provider().await?;
         let token_counter = create_async_token_counter()
             .await
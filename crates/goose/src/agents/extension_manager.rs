@@ -14,6 +14,7 @@ use tracing::{error, warn};
 
 use super::extension::{ExtensionConfig, ExtensionError, ExtensionInfo, ExtensionResult, ToolInfo};
 use super::tool_execution::ToolCallResult;
+use super::tool_localization::ToolLocalization;
 use crate::agents::extension::Envs;
 use crate::config::{Config, ExtensionConfigManager};
 use crate::prompt_template;
@@ -29,11 +30,14 @@ static DEFAULT_TIMESTAMP: LazyLock<DateTime<Utc>> =
 
 type McpClientBox = Arc<Mutex<Box<dyn McpClientTrait>>>;
 
-/// Manages Goose extensions / MCP clients and their interactions
+/// Manages Goose extensions / MCP clients and their interactions. Clones share the clients, so
+/// a subagent can keep using the parent's extensions without holding the parent's lock.
+#[derive(Clone)]
 pub struct ExtensionManager {
     clients: HashMap<String, McpClientBox>,
     instructions: HashMap<String, String>,
     resource_capable_extensions: HashSet<String>,
+    tool_localization: Option<Arc<ToolLocalization>>,
 }
 
 /// A flattened representation of a resource used by the agent to prepare inference
@@ -104,9 +108,15 @@ impl ExtensionManager {
             clients: HashMap::new(),
             instructions: HashMap::new(),
             resource_capable_extensions: HashSet::new(),
+            tool_localization: ToolLocalization::from_config().map(Arc::new),
         }
     }
 
+    /// Override the tool description localization loaded from `GOOSE_LOCALE`
+    pub fn set_tool_localization(&mut self, localization: Option<ToolLocalization>) {
+        self.tool_localization = localization.map(Arc::new);
+    }
+
     pub fn supports_resources(&self) -> bool {
         !self.resource_capable_extensions.is_empty()
     }
@@ -350,6 +360,13 @@ impl ExtensionManager {
         }
     }
 
+    /// Register an already connected client under an extension name
+    #[cfg(test)]
+    pub(crate) fn add_client(&mut self, name: &str, client: Box<dyn McpClientTrait>) {
+        self.clients
+            .insert(normalize(name.to_string()), Arc::new(Mutex::new(client)));
+    }
+
     pub async fn list_extensions(&self) -> ExtensionResult<Vec<String>> {
         Ok(self.clients.keys().cloned().collect())
     }
@@ -371,6 +388,7 @@ impl ExtensionManager {
         let client_futures = filtered_clients.map(|(name, client)| {
             let name = name.clone();
             let client = client.clone();
+            let localization = self.tool_localization.clone();
 
             task::spawn(async move {
                 let mut tools = Vec::new();
@@ -379,12 +397,16 @@ impl ExtensionManager {
 
                 loop {
                     for tool in client_tools.tools {
-                        tools.push(Tool::new(
+                        let mut tool = Tool::new(
                             format!("{}__{}", name, tool.name),
                             &tool.description,
                             tool.input_schema,
                             tool.annotations,
-                        ));
+                        );
+                        if let Some(localization) = &localization {
+                            localization.apply(&mut tool);
+                        }
+                        tools.push(tool);
                     }
 
                     // Exit loop when there are no more pages
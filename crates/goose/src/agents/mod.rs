@@ -1,26 +1,45 @@
 mod agent;
+pub mod citations;
+pub mod clarifications;
+pub mod confidence;
 mod context;
 pub mod extension;
 pub mod extension_manager;
 pub mod final_output_tool;
 mod large_response_handler;
+pub mod platform_tool_access;
 pub mod platform_tools;
 pub mod prompt_manager;
+pub mod provenance;
 mod recipe_tools;
 mod reply_parts;
 mod router_tool_selector;
 mod router_tools;
 mod schedule_tool;
+pub mod session_resources;
 pub mod sub_recipe_execution_tool;
 pub mod sub_recipe_manager;
 pub mod subagent;
+pub mod subagent_batch;
 pub mod subagent_handler;
 pub mod subagent_manager;
+pub mod subagent_pool;
+pub mod subagent_scheduler;
+pub mod subagent_spawn_limit;
+pub mod subagent_state;
 pub mod subagent_tools;
 pub mod subagent_types;
+pub mod table_output;
+pub mod tool_call_limit;
+pub mod tool_compression;
+pub mod tool_costs;
 mod tool_execution;
+pub mod tool_localization;
+pub mod tool_output_dedup;
+pub mod tool_output_summary;
 mod tool_router_index_manager;
 pub(crate) mod tool_vectordb;
+pub mod turn_usage;
 mod types;
 
 pub use agent::{Agent, AgentEvent};
@@ -29,5 +48,6 @@ pub use extension_manager::ExtensionManager;
 pub use prompt_manager::PromptManager;
 pub use subagent::{SubAgent, SubAgentConfig, SubAgentProgress, SubAgentStatus};
 pub use subagent_manager::SubAgentManager;
+pub use subagent_pool::WarmPoolConfig;
 pub use subagent_types::SpawnSubAgentArgs;
-pub use types::{FrontendTool, SessionConfig};
+pub use types::{FrontendTool, HostTool, PlatformToolHandler, SessionConfig, TurnUsage};
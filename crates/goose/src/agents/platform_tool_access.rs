@@ -0,0 +1,163 @@
+use crate::agents::platform_tools::{
+    PLATFORM_GET_SUBAGENT_STATUS_TOOL_NAME, PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME,
+    PLATFORM_MANAGE_SCHEDULE_TOOL_NAME,
+};
+use crate::agents::recipe_tools::sub_recipe_tools::SUB_RECIPE_TASK_TOOL_NAME_PREFIX;
+use crate::agents::sub_recipe_execution_tool::sub_recipe_execute_task_tool::SUB_RECIPE_EXECUTE_TASK_TOOL_NAME;
+use crate::agents::subagent_tools::{
+    SUBAGENT_COLLECT_RESULTS_TOOL_NAME, SUBAGENT_HANDOFF_TOOL_NAME,
+    SUBAGENT_RELAY_MESSAGE_TOOL_NAME, SUBAGENT_RUN_TASK_TOOL_NAME, SUBAGENT_SEND_MESSAGE_TOOL_NAME,
+    SUBAGENT_SPAWN_PARALLEL_TOOL_NAME,
+};
+use crate::agents::subagent_types::ToolPolicy;
+use crate::config::Config;
+
+/// How deeply agents may nest when `GOOSE_MAX_AGENT_DEPTH` is unset: a session can start
+/// subagents and sub-recipes, and those can start their own, but no further
+pub const DEFAULT_MAX_AGENT_DEPTH: usize = 2;
+
+/// Set on the goose processes started for sub-recipes to tell them how deeply they are nested
+pub const AGENT_DEPTH_ENV: &str = "GOOSE_AGENT_DEPTH";
+
+/// How many agents this process is nested under, 0 for a session a user started
+pub fn agent_depth() -> usize {
+    std::env::var(AGENT_DEPTH_ENV)
+        .ok()
+        .and_then(|depth| depth.parse().ok())
+        .unwrap_or(0)
+}
+
+/// Which platform tools a session gets to see. Tools it may not call are left out of the tool
+/// list altogether rather than refused when called, so the model never plans around them.
+#[derive(Debug, Clone)]
+pub struct PlatformToolAccess {
+    /// How many agents the session is nested under
+    pub depth: usize,
+    /// Sessions at this depth can't start agents of their own
+    pub max_depth: usize,
+    /// The session's policy over platform tool names; extension tools aren't affected
+    pub policy: ToolPolicy,
+}
+
+impl PlatformToolAccess {
+    /// The access of this process: its depth from `GOOSE_AGENT_DEPTH`, the limit from
+    /// `GOOSE_MAX_AGENT_DEPTH` and the policy from `GOOSE_PLATFORM_TOOL_POLICY`, e.g.
+    /// `{"deny": ["platform__manage_*"]}`
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        Self {
+            depth: agent_depth(),
+            max_depth: config
+                .get_param("GOOSE_MAX_AGENT_DEPTH")
+                .unwrap_or(DEFAULT_MAX_AGENT_DEPTH),
+            policy: config
+                .get_param("GOOSE_PLATFORM_TOOL_POLICY")
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Narrow the session's policy, e.g. with what a client asked for; see
+    /// [`ToolPolicy::restrict`]
+    pub fn restrict(&mut self, policy: ToolPolicy) {
+        self.policy = std::mem::take(&mut self.policy).restrict(policy);
+    }
+
+    /// Whether the session may see and call a tool. Agent tools are hidden at the maximum
+    /// depth and extension and schedule management in every nested session, which would
+    /// otherwise change what the session that started it runs with.
+    pub fn is_visible(&self, tool_name: &str) -> bool {
+        if !is_platform_tool(tool_name) {
+            return true;
+        }
+        if is_agent_tool(tool_name) && self.depth >= self.max_depth {
+            return false;
+        }
+        if self.depth > 0
+            && matches!(
+                tool_name,
+                PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME | PLATFORM_MANAGE_SCHEDULE_TOOL_NAME
+            )
+        {
+            return false;
+        }
+        self.policy.is_allowed(tool_name)
+    }
+}
+
+/// Tools that start other agents or work with the ones started
+fn is_agent_tool(tool_name: &str) -> bool {
+    matches!(
+        tool_name,
+        SUBAGENT_RUN_TASK_TOOL_NAME
+            | SUBAGENT_SPAWN_PARALLEL_TOOL_NAME
+            | SUBAGENT_COLLECT_RESULTS_TOOL_NAME
+            | SUBAGENT_SEND_MESSAGE_TOOL_NAME
+            | SUBAGENT_RELAY_MESSAGE_TOOL_NAME
+            | SUBAGENT_HANDOFF_TOOL_NAME
+            | PLATFORM_GET_SUBAGENT_STATUS_TOOL_NAME
+            | SUB_RECIPE_EXECUTE_TASK_TOOL_NAME
+    ) || tool_name.starts_with(SUB_RECIPE_TASK_TOOL_NAME_PREFIX)
+}
+
+fn is_platform_tool(tool_name: &str) -> bool {
+    tool_name.starts_with("platform__") || is_agent_tool(tool_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn access(depth: usize, policy: ToolPolicy) -> PlatformToolAccess {
+        PlatformToolAccess {
+            depth,
+            max_depth: 1,
+            policy,
+        }
+    }
+
+    #[test]
+    fn test_nested_sessions_lose_agent_and_management_tools() {
+        let top = access(0, ToolPolicy::default());
+        assert!(top.is_visible(SUBAGENT_RUN_TASK_TOOL_NAME));
+        assert!(top.is_visible(PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME));
+        assert!(top.is_visible("subrecipe__create_task_lint"));
+
+        let nested = access(1, ToolPolicy::default());
+        assert!(!nested.is_visible(SUBAGENT_RUN_TASK_TOOL_NAME));
+        assert!(!nested.is_visible(SUB_RECIPE_EXECUTE_TASK_TOOL_NAME));
+        assert!(!nested.is_visible("subrecipe__create_task_lint"));
+        assert!(!nested.is_visible(PLATFORM_MANAGE_SCHEDULE_TOOL_NAME));
+        assert!(nested.is_visible("platform__read_resource"));
+        assert!(nested.is_visible("developer__shell"));
+    }
+
+    #[test]
+    fn test_policy_only_applies_to_platform_tools() {
+        let mut restricted = access(0, ToolPolicy::default());
+        restricted.restrict(ToolPolicy {
+            deny: vec!["platform__manage_*".to_string(), "subagent__*".to_string()],
+            ..Default::default()
+        });
+        assert!(!restricted.is_visible(PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME));
+        assert!(!restricted.is_visible(SUBAGENT_HANDOFF_TOOL_NAME));
+        assert!(restricted.is_visible("platform__search_available_extensions"));
+        assert!(restricted.is_visible("developer__shell"));
+    }
+
+    #[test]
+    fn test_restricting_with_a_wider_glob_keeps_the_configured_allow_list() {
+        let mut restricted = access(
+            0,
+            ToolPolicy {
+                allow: vec!["platform__read_*".to_string()],
+                ..Default::default()
+            },
+        );
+        restricted.restrict(ToolPolicy {
+            allow: vec!["{*,platform__read_resource}".to_string()],
+            ..Default::default()
+        });
+        assert!(restricted.is_visible("platform__read_resource"));
+        assert!(!restricted.is_visible(PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME));
+    }
+}
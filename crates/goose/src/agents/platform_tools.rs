@@ -8,6 +8,8 @@ pub const PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME: &str =
     "platform__search_available_extensions";
 pub const PLATFORM_MANAGE_EXTENSIONS_TOOL_NAME: &str = "platform__manage_extensions";
 pub const PLATFORM_MANAGE_SCHEDULE_TOOL_NAME: &str = "platform__manage_schedule";
+pub const PLATFORM_DESCRIBE_TOOL_TOOL_NAME: &str = "platform__describe_tool";
+pub const PLATFORM_GET_SUBAGENT_STATUS_TOOL_NAME: &str = "platform__get_subagent_status";
 
 pub fn read_resource_tool() -> Tool {
     Tool::new(
@@ -158,3 +160,59 @@ pub fn manage_schedule_tool() -> Tool {
         }),
     )
 }
+
+pub fn describe_tool_tool() -> Tool {
+    Tool::new(
+        PLATFORM_DESCRIBE_TOOL_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Get the full description and input schema of a tool.
+
+            Tool descriptions have been shortened to save space. Use this tool when you need the
+            complete documentation, parameter details or examples of a tool before calling it.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["name"],
+            "properties": {
+                "name": {"type": "string", "description": "The full name of the tool, e.g. developer__shell"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Describe a tool".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn get_subagent_status_tool() -> Tool {
+    Tool::new(
+        PLATFORM_GET_SUBAGENT_STATUS_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Get the status of the subagents of this session and what each has used so far:
+            turns, tokens in and out, estimated cost in USD, tool calls and how long it has
+            been running.
+
+            Use it to check on subagents working in the background, or to answer what they
+            are costing. Without a subagent_id every subagent is reported. Besides the summary,
+            the result has the same progress as JSON, under goose://subagents/status.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "properties": {
+                "subagent_id": {"type": "string", "description": "Optional ID of a single subagent to report on"}
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Get subagent status".to_string()),
+            read_only_hint: true,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
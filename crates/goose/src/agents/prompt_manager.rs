@@ -1,6 +1,6 @@
 use chrono::Utc;
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use crate::agents::extension::ExtensionInfo;
 use crate::agents::router_tool_selector::RouterToolSelectionStrategy;
@@ -11,6 +11,8 @@ use crate::{config::Config, prompt_template};
 pub struct PromptManager {
     system_prompt_override: Option<String>,
     system_prompt_extras: Vec<String>,
+    /// Instructions that come and go with a setting, by key
+    keyed_system_prompt_extras: BTreeMap<String, String>,
     current_date_timestamp: String,
 }
 
@@ -25,6 +27,7 @@ impl PromptManager {
         PromptManager {
             system_prompt_override: None,
             system_prompt_extras: Vec::new(),
+            keyed_system_prompt_extras: BTreeMap::new(),
             // Use the fixed current date time so that prompt cache can be used.
             current_date_timestamp: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
         }
@@ -35,6 +38,20 @@ impl PromptManager {
         self.system_prompt_extras.push(instruction);
     }
 
+    /// Set the instruction kept under `key`, replacing the previous one, or remove it with
+    /// `None`. For instructions that depend on a setting or a session and must not pile up.
+    pub fn set_system_prompt_extra(&mut self, key: &str, instruction: Option<String>) {
+        match instruction {
+            Some(instruction) => {
+                self.keyed_system_prompt_extras
+                    .insert(key.to_string(), instruction);
+            }
+            None => {
+                self.keyed_system_prompt_extras.remove(key);
+            }
+        }
+    }
+
     /// Override the system prompt with custom text
     pub fn set_system_prompt_override(&mut self, template: String) {
         self.system_prompt_override = Some(template);
@@ -137,6 +154,7 @@ impl PromptManager {
         };
 
         let mut system_prompt_extras = self.system_prompt_extras.clone();
+        system_prompt_extras.extend(self.keyed_system_prompt_extras.values().cloned());
         let config = Config::global();
         let goose_mode = config.get_param("GOOSE_MODE").unwrap_or("auto".to_string());
         if goose_mode == "chat" {
@@ -207,6 +225,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_keyed_extras_are_replaced_and_removed() {
+        let mut manager = PromptManager::new();
+        let build = |manager: &PromptManager| {
+            manager.build_system_prompt(vec![], None, Value::Null, None, None)
+        };
+
+        manager.set_system_prompt_extra("citations", Some("Cite your sources.".to_string()));
+        manager.set_system_prompt_extra("citations", Some("Cite your sources.".to_string()));
+        assert_eq!(build(&manager).matches("Cite your sources.").count(), 1);
+
+        manager.set_system_prompt_extra("citations", None);
+        assert!(!build(&manager).contains("Cite your sources."));
+    }
+
     #[test]
     fn test_model_prompt_map_none() {
         // should return system.md for unrecognized/unsupported model names
@@ -0,0 +1,310 @@
+use std::collections::{HashSet, VecDeque};
+
+use chrono::{DateTime, Utc};
+use mcp_core::tool::ToolCall;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::message::{Message, MessageContent};
+
+/// Context records kept per agent unless `GOOSE_PROVENANCE_MAX_RECORDS` says otherwise
+const DEFAULT_MAX_RECORDS: usize = 1000;
+/// Characters of each tool result kept to match claims against
+const EXCERPT_CHARS: usize = 4000;
+
+/// Where a piece of context the model was given came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ProvenanceSource {
+    /// Memories of a category read from the memory extension
+    Memory {
+        category: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        is_global: Option<bool>,
+    },
+    /// A file read by a tool
+    File { path: String },
+    /// Results of a search, such as a memory or knowledge search
+    Retrieval {
+        query: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        sources: Vec<String>,
+    },
+    /// A resource read by URI
+    Resource { uri: String },
+    /// Any other tool output
+    Tool,
+}
+
+impl ProvenanceSource {
+    /// Tell where a tool call's result comes from by the tool and its arguments
+    pub fn classify(tool_call: &ToolCall) -> Self {
+        let arguments = &tool_call.arguments;
+        let text = |key: &str| arguments.get(key).and_then(Value::as_str).map(String::from);
+        let tool = tool_call
+            .name
+            .rsplit("__")
+            .next()
+            .unwrap_or(&tool_call.name);
+
+        if tool == "retrieve_memories" {
+            if let Some(category) = text("category") {
+                return Self::Memory {
+                    category,
+                    is_global: arguments.get("is_global").and_then(Value::as_bool),
+                };
+            }
+        }
+        if let Some(uri) = text("uri") {
+            return Self::Resource { uri };
+        }
+        if let Some(path) = text("path") {
+            return Self::File { path };
+        }
+        if let Some(query) = text("query") {
+            let sources = arguments
+                .get("sources")
+                .and_then(Value::as_array)
+                .map(|sources| {
+                    sources
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default();
+            return Self::Retrieval { query, sources };
+        }
+        Self::Tool
+    }
+}
+
+/// One tool result the model saw, with where it came from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ProvenanceRecord {
+    /// ID of the tool request whose result this is
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub tool: String,
+    pub source: ProvenanceSource,
+    /// The start of the result's text
+    pub excerpt: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A record that may back a claim, with the share of the claim's words found in it
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct ProvenanceMatch {
+    pub record: ProvenanceRecord,
+    pub score: f32,
+}
+
+/// The context injected into an agent's conversations and where each piece came from, so
+/// a claim in a reply can be traced back to the memory, file or search behind it
+#[derive(Debug)]
+pub struct ProvenanceLog {
+    max_records: usize,
+    records: VecDeque<ProvenanceRecord>,
+}
+
+impl Default for ProvenanceLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_RECORDS)
+    }
+}
+
+impl ProvenanceLog {
+    pub fn new(max_records: usize) -> Self {
+        Self {
+            max_records: max_records.max(1),
+            records: VecDeque::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(
+            crate::config::Config::global()
+                .get_param("GOOSE_PROVENANCE_MAX_RECORDS")
+                .unwrap_or(DEFAULT_MAX_RECORDS),
+        )
+    }
+
+    /// Record the results in `responses` of the tool calls requested in `requests`; failed
+    /// calls and results without text gave the model nothing to cite
+    pub fn record_turn(
+        &mut self,
+        session_id: Option<&str>,
+        requests: &Message,
+        responses: &Message,
+    ) {
+        for content in &requests.content {
+            let MessageContent::ToolRequest(request) = content else {
+                continue;
+            };
+            let Ok(tool_call) = &request.tool_call else {
+                continue;
+            };
+            let Some(Ok(result)) = responses.content.iter().find_map(|content| match content {
+                MessageContent::ToolResponse(response) if response.id == request.id => {
+                    Some(&response.tool_result)
+                }
+                _ => None,
+            }) else {
+                continue;
+            };
+            let text: Vec<&str> = result.iter().filter_map(|c| c.as_text()).collect();
+            if text.is_empty() {
+                continue;
+            }
+            let excerpt: String = text.join("\n").chars().take(EXCERPT_CHARS).collect();
+
+            if self.records.len() == self.max_records {
+                self.records.pop_front();
+            }
+            self.records.push_back(ProvenanceRecord {
+                id: request.id.clone(),
+                session_id: session_id.map(String::from),
+                tool: tool_call.name.clone(),
+                source: ProvenanceSource::classify(tool_call),
+                excerpt,
+                recorded_at: Utc::now(),
+            });
+        }
+    }
+
+    /// Records of a session, or of every session, oldest first
+    pub fn records(&self, session_id: Option<&str>) -> Vec<ProvenanceRecord> {
+        self.records
+            .iter()
+            .filter(|record| session_id.is_none() || record.session_id.as_deref() == session_id)
+            .cloned()
+            .collect()
+    }
+
+    /// The records most likely to be where a claim came from: those containing the most of
+    /// its words, best match first. Records sharing no words with the claim are left out.
+    pub fn trace(
+        &self,
+        claim: &str,
+        session_id: Option<&str>,
+        limit: usize,
+    ) -> Vec<ProvenanceMatch> {
+        let claim_words = words(claim);
+        if claim_words.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<ProvenanceMatch> = self
+            .records
+            .iter()
+            .filter(|record| session_id.is_none() || record.session_id.as_deref() == session_id)
+            .filter_map(|record| {
+                let found = words(&record.excerpt);
+                let shared = claim_words.intersection(&found).count();
+                (shared > 0).then(|| ProvenanceMatch {
+                    record: record.clone(),
+                    score: shared as f32 / claim_words.len() as f32,
+                })
+            })
+            .collect();
+        // Later records win ties, as the model most likely used the freshest context
+        matches.reverse();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// Lowercase words of three or more letters or digits, which carry a claim's meaning
+fn words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= 3)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::Content;
+    use serde_json::json;
+
+    fn turn(id: &str, name: &str, arguments: Value, output: &str) -> (Message, Message) {
+        (
+            Message::assistant().with_tool_request(
+                id,
+                Ok(ToolCall {
+                    name: name.to_string(),
+                    arguments,
+                }),
+            ),
+            Message::user().with_tool_response(id, Ok(vec![Content::text(output)])),
+        )
+    }
+
+    #[test]
+    fn test_classify_sources() {
+        let call = |name: &str, arguments: Value| ToolCall {
+            name: name.to_string(),
+            arguments,
+        };
+        assert_eq!(
+            ProvenanceSource::classify(&call(
+                "memory__retrieve_memories",
+                json!({"category": "deploys", "is_global": true})
+            )),
+            ProvenanceSource::Memory {
+                category: "deploys".to_string(),
+                is_global: Some(true)
+            }
+        );
+        assert_eq!(
+            ProvenanceSource::classify(&call(
+                "developer__text_editor",
+                json!({"command": "view", "path": "/repo/README.md"})
+            )),
+            ProvenanceSource::File {
+                path: "/repo/README.md".to_string()
+            }
+        );
+        assert_eq!(
+            ProvenanceSource::classify(&call(
+                "memory__search_knowledge",
+                json!({"query": "release process", "sources": ["handbook"]})
+            )),
+            ProvenanceSource::Retrieval {
+                query: "release process".to_string(),
+                sources: vec!["handbook".to_string()]
+            }
+        );
+        assert_eq!(
+            ProvenanceSource::classify(&call("developer__shell", json!({"command": "ls"}))),
+            ProvenanceSource::Tool
+        );
+    }
+
+    #[test]
+    fn test_trace_claim_to_its_source() {
+        let mut log = ProvenanceLog::new(10);
+        let (request, response) = turn(
+            "1",
+            "developer__text_editor",
+            json!({"command": "view", "path": "/repo/DEPLOY.md"}),
+            "Deploys run every Tuesday after the staging soak test.",
+        );
+        log.record_turn(Some("s1"), &request, &response);
+        let (request, response) = turn(
+            "2",
+            "memory__retrieve_memories",
+            json!({"category": "team"}),
+            "The on-call rotation changes on Mondays.",
+        );
+        log.record_turn(Some("s1"), &request, &response);
+
+        let matches = log.trace("We deploy on Tuesday after the soak test", Some("s1"), 5);
+        assert_eq!(matches[0].record.id, "1");
+        assert!(log.trace("Deploys on Tuesday", Some("other"), 5).is_empty());
+        assert_eq!(log.records(None).len(), 2);
+    }
+}
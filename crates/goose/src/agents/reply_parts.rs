@@ -3,10 +3,12 @@ use std::collections::HashSet;
 use std::sync::Arc;
 
 use crate::agents::router_tool_selector::RouterToolSelectionStrategy;
+use crate::agents::{clarifications, platform_tools, tool_compression};
 use crate::config::Config;
-use crate::message::{Message, MessageContent, ToolRequest};
-use crate::providers::base::{Provider, ProviderUsage};
+use crate::message::{FinishReason, Message, MessageContent, ToolRequest};
+use crate::providers::base::{CompletionOptions, Provider, ProviderUsage};
 use crate::providers::errors::ProviderError;
+use crate::providers::pricing::estimate_cost_usd;
 use crate::providers::toolshim::{
     augment_message_with_tool_calls, convert_tool_messages_to_text,
     modify_system_prompt_for_tool_json, OllamaInterpreter,
@@ -16,6 +18,17 @@ use mcp_core::tool::Tool;
 
 use super::super::agents::Agent;
 
+const CONTENT_FILTER_REWRITE_PROMPT: &str =
+    "The following request was blocked by a content filter. \
+Rewrite it so that it keeps the same intent and technical detail but avoids wording that could be \
+mistaken for harmful content. Respond with only the rewritten request.";
+
+/// Whether tool calls go through toolshim, either because it is configured or because the
+/// provider can't take tools natively
+pub(crate) fn uses_toolshim(provider: &dyn Provider) -> bool {
+    provider.get_model_config().toolshim || !provider.capabilities().supports_tools
+}
+
 impl Agent {
     /// Prepares tools and system prompt for a provider request
     pub(crate) async fn prepare_tools_and_prompt(
@@ -50,6 +63,12 @@ impl Agent {
         for frontend_tool in frontend_tools.values() {
             tools.push(frontend_tool.tool.clone());
         }
+        // Send shortened schemas, with a tool to fetch the full ones on demand
+        if tool_compression::compression_enabled() {
+            tools = tools.iter().map(tool_compression::compress_tool).collect();
+            tools.push(platform_tools::describe_tool_tool());
+        }
+        self.tool_costs.lock().await.annotate(&mut tools);
 
         // Prepare system prompt
         let extension_manager = self.extension_manager.read().await;
@@ -69,9 +88,9 @@ impl Agent {
             tool_selection_strategy,
         );
 
-        // Handle toolshim if enabled
+        // Handle toolshim if enabled or the provider can't take tools
         let mut toolshim_tools = vec![];
-        if model_config.toolshim {
+        if uses_toolshim(provider.as_ref()) {
             // If tool interpretation is enabled, modify the system prompt
             system_prompt = modify_system_prompt_for_tool_json(&system_prompt, &tools);
             // Make a copy of tools before emptying
@@ -113,26 +132,33 @@ impl Agent {
         messages: &[Message],
         tools: &[Tool],
         toolshim_tools: &[Tool],
+        options: &CompletionOptions,
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let config = provider.get_model_config();
+        let toolshim = uses_toolshim(provider.as_ref());
 
-        // Convert tool messages to text if toolshim is enabled
-        let messages_for_provider = if config.toolshim {
-            convert_tool_messages_to_text(messages)
+        // Assumptions and questions go to the provider as text, and so do tool messages
+        // if toolshim is enabled
+        let messages_for_provider = clarifications::flatten(messages);
+        let messages_for_provider = if toolshim {
+            convert_tool_messages_to_text(&messages_for_provider)
         } else {
-            messages.to_vec()
+            messages_for_provider
         };
 
         // Call the provider to get a response
-        let (mut response, usage) = provider
-            .complete(system_prompt, &messages_for_provider, tools)
+        let (mut response, mut usage) = provider
+            .complete_with_options(system_prompt, &messages_for_provider, tools, options)
             .await?;
+        if usage.usage.cost_usd.is_none() {
+            usage.usage.cost_usd =
+                estimate_cost_usd(usage.provider.as_deref(), &usage.model, &usage.usage);
+        }
 
         // Store the model information in the global store
         crate::providers::base::set_current_model(&usage.model);
 
         // Post-process / structure the response only if tool interpretation is enabled
-        if config.toolshim {
+        if toolshim {
             let interpreter = OllamaInterpreter::new().map_err(|e| {
                 ProviderError::ExecutionError(format!("Failed to create OllamaInterpreter: {}", e))
             })?;
@@ -144,9 +170,64 @@ impl Agent {
                 })?;
         }
 
+        // Gemini and several OpenAI compatible servers report "stop" even when the
+        // model asked for tools, so the message content decides in that case
+        response.finish_reason = Some(match response.finish_reason {
+            Some(FinishReason::Stop) | None if response.is_tool_call() => FinishReason::ToolCalls,
+            Some(finish_reason) => finish_reason,
+            None => FinishReason::Stop,
+        });
+
+        // A filtered turn with nothing left in it is an error, not an empty answer
+        if response.finish_reason == Some(FinishReason::ContentFilter)
+            && !response.is_tool_call()
+            && response.as_concat_text().trim().is_empty()
+        {
+            return Err(ProviderError::ContentFiltered(
+                "the provider withheld the response".to_string(),
+            ));
+        }
+
         Ok((response, usage))
     }
 
+    /// Ask the provider for a sanitized version of the most recent user prompt and
+    /// substitute it in `messages`. Returns false if there was no prompt to rewrite.
+    pub(crate) async fn rewrite_filtered_prompt(
+        provider: Arc<dyn Provider>,
+        messages: &mut [Message],
+    ) -> Result<bool, ProviderError> {
+        let Some(prompt) = messages
+            .iter_mut()
+            .rev()
+            .find(|m| m.role == mcp_core::role::Role::User && !m.is_tool_response())
+        else {
+            return Ok(false);
+        };
+        let original = prompt.as_concat_text();
+        if original.trim().is_empty() {
+            return Ok(false);
+        }
+
+        let (rewrite, _) = provider
+            .complete(
+                CONTENT_FILTER_REWRITE_PROMPT,
+                &[Message::user().with_text(original)],
+                &[],
+            )
+            .await?;
+        let rewritten = rewrite.as_concat_text();
+        if rewritten.trim().is_empty() {
+            return Ok(false);
+        }
+
+        prompt
+            .content
+            .retain(|content| !matches!(content, MessageContent::Text(_)));
+        prompt.content.insert(0, MessageContent::text(rewritten));
+        Ok(true)
+    }
+
     /// Categorize tool requests from the response into different types
     /// Returns:
     /// - frontend_requests: Tool requests that should be handled by the frontend
@@ -194,6 +275,7 @@ impl Agent {
             role: response.role.clone(),
             created: response.created,
             content: filtered_content,
+            finish_reason: response.finish_reason,
         };
 
         // Categorize tool requests
@@ -221,6 +303,7 @@ impl Agent {
         session_config: crate::agents::types::SessionConfig,
         usage: &crate::providers::base::ProviderUsage,
         messages_length: usize,
+        seed: Option<u64>,
     ) -> Result<()> {
         let session_file_path = match session::storage::get_path(session_config.id.clone()) {
             Ok(path) => path,
@@ -237,6 +320,9 @@ impl Agent {
         metadata.output_tokens = usage.usage.output_tokens;
 
         metadata.message_count = messages_length + 1;
+        if seed.is_some() {
+            metadata.seed = seed;
+        }
 
         let accumulate = |a: Option<i32>, b: Option<i32>| -> Option<i32> {
             match (a, b) {
@@ -150,6 +150,7 @@ impl Agent {
             current_session_id: None,
             process_start_time: None,
             execution_mode: Some(execution_mode.to_string()),
+            constraints: None,
         };
 
         match scheduler.add_scheduled_job(job).await {
@@ -0,0 +1,311 @@
+use std::collections::BTreeMap;
+
+use mcp_core::{Content, ResourceContents};
+use serde::{Deserialize, Serialize};
+
+use crate::message::{Message, MessageContent};
+
+/// URI prefix for resources describing the running session
+pub const SESSION_RESOURCE_PREFIX: &str = "goose://session/";
+
+/// Alias that always resolves to the session currently being served
+const CURRENT_SESSION: &str = "current";
+
+/// A file shared with the session, such as a report or a table. Binary formats such as PDF
+/// and XLSX are stored base64-encoded, which `mime_type` tells apart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredArtifact")]
+pub struct Artifact {
+    pub content: String,
+    pub mime_type: String,
+}
+
+impl Artifact {
+    pub fn new(content: impl Into<String>, mime_type: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            mime_type: mime_type.into(),
+        }
+    }
+
+    /// A text artifact, typed by the extension of its name and `text/plain` otherwise
+    pub fn text(name: &str, content: impl Into<String>) -> Self {
+        let extension = name
+            .rsplit_once('.')
+            .map(|(_, extension)| extension.to_ascii_lowercase());
+        let mime_type = match extension.as_deref() {
+            Some("md") => "text/markdown",
+            Some("html" | "htm") => "text/html",
+            Some("csv") => "text/csv",
+            Some("json") => "application/json",
+            _ => "text/plain",
+        };
+        Self::new(content, mime_type)
+    }
+
+    /// Whether the content is base64-encoded rather than text
+    pub fn is_binary(&self) -> bool {
+        !(self.mime_type.starts_with("text/") || self.mime_type == "application/json")
+    }
+}
+
+/// Artifacts used to be stored as their content alone
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredArtifact {
+    Typed { content: String, mime_type: String },
+    Untyped(String),
+}
+
+impl From<StoredArtifact> for Artifact {
+    fn from(stored: StoredArtifact) -> Self {
+        match stored {
+            StoredArtifact::Typed { content, mime_type } => Artifact::new(content, mime_type),
+            StoredArtifact::Untyped(content) => Artifact::new(content, "text/plain"),
+        }
+    }
+}
+
+/// The session's own transcript, plan and artifacts, exposed as resources under
+/// `goose://session/<id>/...` so they can be listed and read like extension resources
+#[derive(Debug, Default, Clone)]
+pub struct SessionResources {
+    session_id: Option<String>,
+    transcript: Vec<Message>,
+    plan: Option<String>,
+    artifacts: BTreeMap<String, Artifact>,
+}
+
+impl SessionResources {
+    pub fn is_session_uri(uri: &str) -> bool {
+        uri.starts_with(SESSION_RESOURCE_PREFIX)
+    }
+
+    pub fn session_id(&self) -> &str {
+        self.session_id.as_deref().unwrap_or(CURRENT_SESSION)
+    }
+
+    /// Point the resources at a session, returning whether it differs from the previous one.
+    /// Switching away from a known session drops its transcript, plan and artifacts; what was
+    /// shared before the first session was known is kept for it.
+    pub fn set_session_id(&mut self, session_id: Option<String>) -> bool {
+        let changed = self.session_id != session_id;
+        if changed && self.session_id.is_some() {
+            self.transcript.clear();
+            self.plan = None;
+            self.artifacts.clear();
+        }
+        self.session_id = session_id;
+        changed
+    }
+
+    pub fn set_transcript(&mut self, messages: &[Message]) {
+        self.transcript = messages.to_vec();
+    }
+
+    pub fn set_plan(&mut self, plan: Option<String>) {
+        self.plan = plan;
+    }
+
+    pub fn add_artifact(&mut self, name: impl Into<String>, artifact: Artifact) {
+        self.artifacts.insert(name.into(), artifact);
+    }
+
+    /// The URI an artifact is readable under
+    pub fn artifact_uri(&self, name: &str) -> String {
+        self.uri(&format!("artifacts/{}", name))
+    }
+
+    /// Whether there is anything beyond the transcript the model already has
+    pub fn has_shared_content(&self) -> bool {
+        self.plan.is_some() || !self.artifacts.is_empty()
+    }
+
+    fn uri(&self, path: &str) -> String {
+        format!("{}{}/{}", SESSION_RESOURCE_PREFIX, self.session_id(), path)
+    }
+
+    /// URIs and names of every available session resource
+    pub fn list(&self) -> Vec<(String, String)> {
+        let mut resources = vec![(self.uri("transcript"), "Session transcript".to_string())];
+        if self.plan.is_some() {
+            resources.push((self.uri("plan"), "Session plan".to_string()));
+        }
+        for (name, artifact) in &self.artifacts {
+            resources.push((
+                self.uri(&format!("artifacts/{}", name)),
+                format!("Artifact: {} ({})", name, artifact.mime_type),
+            ));
+        }
+        resources
+    }
+
+    /// The mime type of a `goose://session/...` URI, where it isn't plain text
+    pub fn mime_type(&self, uri: &str) -> Option<&str> {
+        self.resolve(uri)?
+            .strip_prefix("artifacts/")
+            .and_then(|name| self.artifacts.get(name))
+            .map(|artifact| artifact.mime_type.as_str())
+    }
+
+    /// The path of a URI of this session, which may use its id or `current`
+    fn resolve<'a>(&self, uri: &'a str) -> Option<&'a str> {
+        let rest = uri.strip_prefix(SESSION_RESOURCE_PREFIX)?;
+        let (session_id, path) = rest.split_once('/')?;
+        if session_id != self.session_id() && session_id != CURRENT_SESSION {
+            return None;
+        }
+        Some(path)
+    }
+
+    /// Read a `goose://session/...` URI. Accepts this session's id or `current`.
+    pub fn read(&self, uri: &str) -> Option<String> {
+        match self.resolve(uri)? {
+            "transcript" => Some(render_transcript(&self.transcript)),
+            "plan" => self.plan.clone(),
+            path => path
+                .strip_prefix("artifacts/")
+                .and_then(|name| self.artifacts.get(name))
+                .map(|artifact| artifact.content.clone()),
+        }
+    }
+
+    /// Read a URI as tool content: the text under its URI, or the artifact embedded as a blob
+    /// when it is binary, such as a PDF report
+    pub fn read_content(&self, uri: &str) -> Option<Content> {
+        let artifact = self
+            .resolve(uri)?
+            .strip_prefix("artifacts/")
+            .and_then(|name| self.artifacts.get(name));
+        match artifact {
+            Some(artifact) if artifact.is_binary() => {
+                Some(Content::resource(ResourceContents::BlobResourceContents {
+                    uri: uri.to_string(),
+                    mime_type: Some(artifact.mime_type.clone()),
+                    blob: artifact.content.clone(),
+                }))
+            }
+            _ => self
+                .read(uri)
+                .map(|text| Content::text(format!("{}\n\n{}", uri, text))),
+        }
+    }
+}
+
+fn render_transcript(messages: &[Message]) -> String {
+    let mut transcript = String::new();
+    for message in messages {
+        let role = match message.role {
+            mcp_core::role::Role::User => "user",
+            mcp_core::role::Role::Assistant => "assistant",
+        };
+        for content in &message.content {
+            let text = match content {
+                MessageContent::Text(text) => text.text.clone(),
+                MessageContent::ToolRequest(request) => match &request.tool_call {
+                    Ok(call) => format!("[tool call] {} {}", call.name, call.arguments),
+                    Err(e) => format!("[invalid tool call] {}", e),
+                },
+                MessageContent::ToolResponse(response) => match &response.tool_result {
+                    Ok(contents) => format!(
+                        "[tool result] {}",
+                        contents
+                            .iter()
+                            .filter_map(|c| c.as_text())
+                            .collect::<Vec<_>>()
+                            .join("\n")
+                    ),
+                    Err(e) => format!("[tool error] {}", e),
+                },
+                _ => continue,
+            };
+            transcript.push_str(&format!("{}: {}\n\n", role, text));
+        }
+    }
+    transcript
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::ToolCall;
+    use serde_json::json;
+
+    #[test]
+    fn test_list_and_read_session_resources() {
+        let mut resources = SessionResources::default();
+        resources.set_session_id(Some("20250101_1".to_string()));
+        resources.set_transcript(&[
+            Message::user().with_text("List the files"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ),
+        ]);
+        assert!(!resources.has_shared_content());
+
+        resources.set_plan(Some("1. list files".to_string()));
+        resources.add_artifact("report.md", Artifact::text("report.md", "# Report"));
+        resources.add_artifact("report.pdf", Artifact::new("JVBERi0=", "application/pdf"));
+
+        let uris: Vec<String> = resources.list().into_iter().map(|(uri, _)| uri).collect();
+        assert_eq!(
+            uris,
+            vec![
+                "goose://session/20250101_1/transcript",
+                "goose://session/20250101_1/plan",
+                "goose://session/20250101_1/artifacts/report.md",
+                "goose://session/20250101_1/artifacts/report.pdf",
+            ]
+        );
+        assert_eq!(
+            resources.mime_type("goose://session/current/artifacts/report.pdf"),
+            Some("application/pdf")
+        );
+
+        let transcript = resources
+            .read("goose://session/current/transcript")
+            .unwrap();
+        assert!(transcript.contains("user: List the files"));
+        assert!(transcript.contains("[tool call] developer__shell"));
+        assert_eq!(
+            resources.read("goose://session/20250101_1/artifacts/report.md"),
+            Some("# Report".to_string())
+        );
+        assert!(resources.read("goose://session/other/plan").is_none());
+        assert!(resources.read("goose://session/current/unknown").is_none());
+    }
+
+    #[test]
+    fn test_switching_sessions_drops_the_previous_sessions_resources() {
+        let mut resources = SessionResources::default();
+        resources.set_plan(Some("1. list files".to_string()));
+        assert!(resources.set_session_id(Some("20250101_1".to_string())));
+        assert!(resources.has_shared_content());
+
+        resources.set_transcript(&[Message::user().with_text("List the files")]);
+        resources.add_artifact("report.md", Artifact::text("report.md", "# Report"));
+        assert!(!resources.set_session_id(Some("20250101_1".to_string())));
+        assert!(resources.has_shared_content());
+
+        assert!(resources.set_session_id(Some("20250101_2".to_string())));
+        assert!(!resources.has_shared_content());
+        assert_eq!(resources.list().len(), 1);
+        assert_eq!(
+            resources.read("goose://session/current/transcript"),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn test_artifacts_stored_without_a_type_are_plain_text() {
+        let artifact: Artifact = serde_json::from_value(json!("# Report")).unwrap();
+        assert_eq!(artifact, Artifact::new("# Report", "text/plain"));
+
+        let typed = Artifact::new("JVBERi0=", "application/pdf");
+        let stored = serde_json::to_value(&typed).unwrap();
+        assert_eq!(serde_json::from_value::<Artifact>(stored).unwrap(), typed);
+        assert!(typed.is_binary());
+        assert!(!Artifact::text("table.csv", "a,b").is_binary());
+    }
+}
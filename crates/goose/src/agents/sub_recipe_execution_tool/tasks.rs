@@ -5,6 +5,7 @@ use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 use tokio::time::timeout;
 
+use crate::agents::platform_tool_access::{agent_depth, AGENT_DEPTH_ENV};
 use crate::agents::sub_recipe_execution_tool::types::{Task, TaskResult};
 
 // Process a single task based on its type
@@ -66,6 +67,9 @@ async fn execute_task(task: Task) -> Result<Value, String> {
         cmd
     };
 
+    // The task runs one level deeper than this session
+    command.env(AGENT_DEPTH_ENV, (agent_depth() + 1).to_string());
+
     // Configure to capture stdout
     command.stdout(Stdio::piped());
     command.stderr(Stdio::piped());
@@ -1,35 +1,81 @@
 use crate::{
-    agents::{extension_manager::ExtensionManager, Agent},
-    message::{Message, MessageContent, ToolRequest},
+    agents::{extension_manager::ExtensionManager, reply_parts::uses_toolshim, Agent},
+    message::{FinishReason, Message, MessageContent, ToolRequest},
+    model::ModelConfig,
     prompt_template::render_global_file,
-    providers::base::Provider,
+    providers::base::{CompletionOptions, Provider},
     providers::errors::ProviderError,
+    providers::pool::global_pool_manager,
+    providers::rate_limit::{with_priority, Priority},
+    providers::toolshim::modify_system_prompt_for_tool_json,
+    providers::usage_tracker::with_usage_session,
     recipe::Recipe,
+    token_counter::create_async_token_counter,
+    tracing::alerting_layer::SUBAGENT_EVENT_TARGET,
 };
 use anyhow::anyhow;
 use chrono::{DateTime, Utc};
+use futures::stream::BoxStream;
+use futures::StreamExt;
 use mcp_core::protocol::{JsonRpcMessage, JsonRpcNotification};
 use mcp_core::{handler::ToolError, role::Role, tool::Tool};
 use serde::{Deserialize, Serialize};
-use serde_json::{self, json};
-use std::{collections::HashMap, sync::Arc};
-use tokio::sync::{mpsc, Mutex, RwLock};
-use tracing::{debug, error, instrument};
+use serde_json::{self, json, Value};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::PathBuf,
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+use tokio::sync::{broadcast, mpsc, Mutex, RwLock};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, instrument, warn};
 use uuid::Uuid;
 
+use crate::agents::confidence::{self, ConfidenceScore};
+use crate::agents::final_output_tool::{FinalOutputTool, FINAL_OUTPUT_TOOL_NAME};
+use crate::agents::large_response_handler::process_tool_response;
 use crate::agents::platform_tools::{
     self, PLATFORM_LIST_RESOURCES_TOOL_NAME, PLATFORM_READ_RESOURCE_TOOL_NAME,
     PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME,
 };
-use crate::agents::subagent_tools::SUBAGENT_RUN_TASK_TOOL_NAME;
+use crate::agents::session_resources::Artifact;
+use crate::agents::subagent_scheduler::ProcessingSlots;
+use crate::agents::subagent_state::SubAgentSnapshot;
+use crate::agents::subagent_tools::{
+    final_output_subagent_tool, write_table_subagent_tool, SUBAGENT_COLLECT_RESULTS_TOOL_NAME,
+    SUBAGENT_FINAL_OUTPUT_TOOL_NAME, SUBAGENT_HANDOFF_TOOL_NAME, SUBAGENT_RELAY_MESSAGE_TOOL_NAME,
+    SUBAGENT_RUN_TASK_TOOL_NAME, SUBAGENT_SEND_MESSAGE_TOOL_NAME,
+    SUBAGENT_SPAWN_PARALLEL_TOOL_NAME, SUBAGENT_WRITE_TABLE_TOOL_NAME,
+};
+use crate::agents::subagent_types::{is_valid_artifact_name, SubAgentEvent, ToolPolicy};
+use crate::agents::table_output::{Column, Table, TableError, TableFormat};
+use crate::agents::tool_call_limit;
+use crate::agents::turn_usage::{PromptBreakdown, TurnUsageDelta, TurnUsageTracker};
+use crate::config::Config;
+
+/// How long a subagent may process without starting a new turn before it is reported stuck
+const DEFAULT_STUCK_AFTER_SECS: u64 = 600;
+/// How often the stall watchdog looks at a subagent
+const STALL_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Times a subagent is sent back to fix an answer that doesn't match its recipe's response
+/// schema before the parent gets the last answer as is. A reply without the final output tool
+/// and output that fails validation both count as one failed answer.
+const MAX_RESPONSE_REPAIRS: usize = 2;
+
+const RESPONSE_REPAIR_PROMPT: &str = "Your answer has to be given with the `recipe__final_output` \
+tool, as JSON that matches the response schema. Call it now with your final answer.";
 
 /// Status of a subagent
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SubAgentStatus {
     Ready,             // Ready to process messages
+    Queued,            // Waiting for a processing slot
     Processing,        // Currently working on a task
     Completed(String), // Task completed (with optional message for success/error)
     Terminated,        // Manually terminated
+    Suspended,         // Restored after a restart, waiting for a message to continue
 }
 
 /// Configuration for a subagent
@@ -40,6 +86,17 @@ pub struct SubAgentConfig {
     pub instructions: Option<String>,
     pub max_turns: Option<usize>,
     pub timeout_seconds: Option<u64>,
+    /// Provider request priority, inherited from the parent session
+    pub priority: Priority,
+    /// Slots shared by the subagents that may process at the same time; a turn waits `Queued`
+    /// for one, by priority, when all are taken
+    pub processing_slots: Option<Arc<ProcessingSlots>>,
+    /// Directory the subagent saves a snapshot to whenever its status changes
+    pub state_dir: Option<PathBuf>,
+    /// Extension tools the subagent may see and call, whatever its extensions expose
+    pub tool_policy: ToolPolicy,
+    /// Where the subagent broadcasts what it does, usually the manager's event bus
+    pub events: Option<broadcast::Sender<SubAgentEvent>>,
 }
 
 impl SubAgentConfig {
@@ -50,6 +107,11 @@ impl SubAgentConfig {
             instructions: None,
             max_turns: None,
             timeout_seconds: None,
+            priority: Priority::default(),
+            processing_slots: None,
+            state_dir: None,
+            tool_policy: ToolPolicy::from_config(),
+            events: None,
         }
     }
 
@@ -60,6 +122,11 @@ impl SubAgentConfig {
             instructions: Some(instructions),
             max_turns: None,
             timeout_seconds: None,
+            priority: Priority::default(),
+            processing_slots: None,
+            state_dir: None,
+            tool_policy: ToolPolicy::from_config(),
+            events: None,
         }
     }
 
@@ -72,6 +139,32 @@ impl SubAgentConfig {
         self.timeout_seconds = Some(timeout_seconds);
         self
     }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub fn with_processing_slots(mut self, slots: Arc<ProcessingSlots>) -> Self {
+        self.processing_slots = Some(slots);
+        self
+    }
+
+    pub fn with_state_dir(mut self, state_dir: PathBuf) -> Self {
+        self.state_dir = Some(state_dir);
+        self
+    }
+
+    pub fn with_events(mut self, events: broadcast::Sender<SubAgentEvent>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Narrow the tools the subagent may call; see [`ToolPolicy::restrict`]
+    pub fn restrict_tools(mut self, tool_policy: ToolPolicy) -> Self {
+        self.tool_policy = self.tool_policy.restrict(tool_policy);
+        self
+    }
 }
 
 /// Progress information for a subagent
@@ -83,6 +176,32 @@ pub struct SubAgentProgress {
     pub turn: usize,
     pub max_turns: Option<usize>,
     pub timestamp: DateTime<Utc>,
+    /// How the prompt grew in the latest provider call, to find what fills up the context
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage_delta: Option<TurnUsageDelta>,
+    /// Tokens sent to the provider over all turns
+    #[serde(default)]
+    pub input_tokens: i64,
+    /// Tokens the provider generated over all turns
+    #[serde(default)]
+    pub output_tokens: i64,
+    /// Estimated provider spend in USD; requests to models without a known price add nothing
+    #[serde(default)]
+    pub cost_usd: f64,
+    /// Wall-clock seconds since the subagent was created, up to when it last finished
+    #[serde(default)]
+    pub duration_secs: i64,
+    #[serde(default)]
+    pub tool_calls: u64,
+}
+
+/// What a subagent has done so far, reported with its progress. Its spend is kept by the
+/// pool's [`UsageTracker`](crate::providers::usage_tracker::UsageTracker) instead.
+#[derive(Debug, Clone, Copy, Default)]
+struct SubAgentMetrics {
+    tool_calls: u64,
+    /// When the subagent last stopped working, so a finished subagent's duration stops growing
+    finished_at: Option<DateTime<Utc>>,
 }
 
 /// A specialized agent that can handle specific tasks independently
@@ -95,43 +214,140 @@ pub struct SubAgent {
     pub created_at: DateTime<Utc>,
     pub recipe_extensions: Arc<Mutex<Vec<String>>>,
     pub missing_extensions: Arc<Mutex<Vec<String>>>, // Track extensions that weren't enabled
+    /// The recipe's extensions, started for this subagent alone so it can't reach any of the
+    /// parent's other tools, and stopped when it is terminated. Subagents without a recipe use
+    /// the parent's extensions.
+    extensions: Option<RwLock<ExtensionManager>>,
     pub mcp_notification_tx: mpsc::Sender<JsonRpcMessage>, // For MCP notifications
+    pub turn_usage: Arc<Mutex<TurnUsageTracker>>,
+    /// Named outputs registered with the final output tool, collected by the manager
+    pub artifacts: Arc<Mutex<BTreeMap<String, Artifact>>>,
+    /// How confident the model is in the final answer, when the recipe asks for a score
+    pub confidence: Arc<Mutex<Option<ConfidenceScore>>>,
+    /// Used instead of the parent's provider when the recipe's settings pick another provider,
+    /// model or temperature
+    provider_override: Option<Arc<dyn Provider>>,
+    /// Cancelled on termination, stopping the provider request and tool calls in flight
+    cancellation: CancellationToken,
+    metrics: Arc<Mutex<SubAgentMetrics>>,
+    /// How many messages of the conversation the state directory already holds; held while
+    /// persisting so snapshots of one subagent are written one at a time
+    persisted_messages: Mutex<usize>,
+}
+
+/// The provider a recipe's settings ask for, or `None` when it has no settings and the parent's
+/// provider is used as is. Settings left out are taken from the parent: its model and
+/// temperature, and without a provider setting the parent's provider is switched to the
+/// recipe's model. The model is used as is, without the session's lead model or fallbacks.
+pub(crate) fn recipe_provider(
+    recipe: &Recipe,
+    parent: &dyn Provider,
+) -> Result<Option<Arc<dyn Provider>>, anyhow::Error> {
+    let Some(settings) = &recipe.settings else {
+        return Ok(None);
+    };
+    if settings.goose_provider.is_none()
+        && settings.goose_model.is_none()
+        && settings.temperature.is_none()
+    {
+        return Ok(None);
+    }
+
+    let parent_model = parent.get_model_config();
+    let model = ModelConfig::new(
+        settings
+            .goose_model
+            .clone()
+            .unwrap_or(parent_model.model_name),
+    )
+    .with_temperature(settings.temperature.or(parent_model.temperature));
+
+    if settings.goose_provider.is_none() {
+        match parent.with_model(model.clone()) {
+            Ok(switched) => {
+                debug!("Recipe settings select model {}", model.model_name);
+                return Ok(Some(switched));
+            }
+            Err(e) => debug!("Recreating the provider to switch models: {}", e),
+        }
+    }
+    let provider_name = match &settings.goose_provider {
+        Some(name) => name.clone(),
+        None => Config::global()
+            .get_param::<String>("GOOSE_PROVIDER")
+            .map_err(|_| anyhow!("Recipe settings need a provider when GOOSE_PROVIDER is unset"))?,
+    };
+    debug!(
+        "Recipe settings select provider {} with model {}",
+        provider_name, model.model_name
+    );
+    Ok(Some(crate::providers::create_exact(&provider_name, model)?))
 }
 
 impl SubAgent {
     /// Create a new subagent with the given configuration and provider
-    #[instrument(skip(config, _provider, extension_manager, mcp_notification_tx))]
+    #[instrument(skip(config, provider, extension_manager, mcp_notification_tx))]
     pub async fn new(
         config: SubAgentConfig,
-        _provider: Arc<dyn Provider>,
-        extension_manager: Arc<tokio::sync::RwLockReadGuard<'_, ExtensionManager>>,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
         mcp_notification_tx: mpsc::Sender<JsonRpcMessage>,
     ) -> Result<(Arc<Self>, tokio::task::JoinHandle<()>), anyhow::Error> {
         debug!("Creating new subagent with id: {}", config.id);
 
         let mut missing_extensions = Vec::new();
         let mut recipe_extensions = Vec::new();
+        let mut extensions = None;
 
-        // Check if extensions from recipe exist in the extension manager
         if let Some(recipe) = &config.recipe {
-            if let Some(extensions) = &recipe.extensions {
-                for extension in extensions {
-                    let extension_name = extension.name();
-                    let existing_extensions = extension_manager.list_extensions().await?;
-
-                    if !existing_extensions.contains(&extension_name) {
+            // Start the recipe's extensions in a manager of the subagent's own, whether or not
+            // the parent has them, so only the tools the recipe declares can be called
+            let mut scoped = ExtensionManager::new();
+            for extension in recipe.extensions.iter().flatten() {
+                let extension_name = extension.name();
+                match scoped.add_extension(extension.clone()).await {
+                    Ok(()) => recipe_extensions.push(extension_name),
+                    Err(e) => {
+                        warn!(
+                            "Subagent {} could not start extension {}: {}",
+                            config.id, extension_name, e
+                        );
                         missing_extensions.push(extension_name);
-                    } else {
-                        recipe_extensions.push(extension_name);
                     }
                 }
             }
+            extensions = Some(scoped);
         } else {
             // If no recipe, inherit all extensions from the parent agent
             let existing_extensions = extension_manager.list_extensions().await?;
             recipe_extensions = existing_extensions;
         }
 
+        let provider_override = match &config.recipe {
+            Some(recipe) => recipe_provider(recipe, provider.as_ref())?,
+            None => None,
+        };
+
+        // Check the recipe's declared requirements against the provider it runs with and the
+        // extensions just started, reporting everything missing at once
+        if let (Some(recipe), Some(scoped)) = (&config.recipe, &extensions) {
+            if let Some(requires) = &recipe.requires {
+                let tools: Vec<String> = scoped
+                    .get_prefixed_tools(None)
+                    .await?
+                    .into_iter()
+                    .map(|tool| tool.name)
+                    .collect();
+                let provider = provider_override.as_deref().unwrap_or(provider.as_ref());
+                requires
+                    .check(&recipe.title, &provider.capabilities(), &tools)
+                    .map_err(|unmet| {
+                        warn!("Preflight failed: {}", unmet);
+                        anyhow::Error::new(unmet)
+                    })?;
+            }
+        }
+
         let subagent = Arc::new(SubAgent {
             id: config.id.clone(),
             conversation: Arc::new(Mutex::new(Vec::new())),
@@ -142,6 +358,14 @@ impl SubAgent {
             recipe_extensions: Arc::new(Mutex::new(recipe_extensions)),
             missing_extensions: Arc::new(Mutex::new(missing_extensions)),
             mcp_notification_tx,
+            turn_usage: Arc::new(Mutex::new(TurnUsageTracker::default())),
+            artifacts: Arc::new(Mutex::new(BTreeMap::new())),
+            confidence: Arc::new(Mutex::new(None)),
+            provider_override,
+            extensions: extensions.map(RwLock::new),
+            cancellation: CancellationToken::new(),
+            metrics: Arc::new(Mutex::new(SubAgentMetrics::default())),
+            persisted_messages: Mutex::new(0),
         });
 
         // Send initial MCP notification
@@ -149,18 +373,63 @@ impl SubAgent {
         subagent_clone
             .send_mcp_notification("subagent_created", "Subagent created and ready")
             .await;
-
-        // Create a background task handle (for future use with streaming/monitoring)
-        let subagent_clone = Arc::clone(&subagent);
-        let handle = tokio::spawn(async move {
-            // This could be used for background monitoring, cleanup, etc.
-            debug!("Subagent {} background task started", subagent_clone.id);
+        subagent.emit(SubAgentEvent::Spawned {
+            subagent_id: subagent.id.clone(),
+            recipe: subagent
+                .config
+                .recipe
+                .as_ref()
+                .map(|recipe| recipe.title.clone()),
         });
 
+        // Watch for the subagent getting stuck; aborted along with the subagent
+        let handle = tokio::spawn(Self::watch_for_stalls(Arc::downgrade(&subagent)));
+
         debug!("Subagent {} created successfully", subagent.id);
         Ok((subagent, handle))
     }
 
+    /// Report the subagent on [`SUBAGENT_EVENT_TARGET`] when it stays in `Processing` without
+    /// starting a new turn for `GOOSE_SUBAGENT_STUCK_AFTER` seconds, so alerting can pick it up.
+    /// Each stall is reported once.
+    async fn watch_for_stalls(subagent: Weak<SubAgent>) {
+        let stuck_after = Duration::from_secs(
+            Config::global()
+                .get_param("GOOSE_SUBAGENT_STUCK_AFTER")
+                .unwrap_or(DEFAULT_STUCK_AFTER_SECS),
+        );
+        let mut last_turn = 0;
+        let mut since = Instant::now();
+        let mut reported = false;
+        loop {
+            tokio::time::sleep(stuck_after.min(STALL_CHECK_INTERVAL)).await;
+            let Some(subagent) = subagent.upgrade() else {
+                return;
+            };
+            let turn = *subagent.turn_count.lock().await;
+            match subagent.get_status().await {
+                SubAgentStatus::Completed(_) | SubAgentStatus::Terminated => return,
+                SubAgentStatus::Processing if turn == last_turn => {
+                    if !reported && since.elapsed() >= stuck_after {
+                        tracing::warn!(
+                            target: SUBAGENT_EVENT_TARGET,
+                            event = "stuck",
+                            subagent_id = subagent.id.as_str(),
+                            turn,
+                            stalled_secs = since.elapsed().as_secs(),
+                        );
+                        reported = true;
+                    }
+                }
+                _ => {
+                    last_turn = turn;
+                    since = Instant::now();
+                    reported = false;
+                }
+            }
+        }
+    }
+
     /// Get the current status of the subagent
     pub async fn get_status(&self) -> SubAgentStatus {
         self.status.read().await.clone()
@@ -173,9 +442,17 @@ impl SubAgent {
             let mut current_status = self.status.write().await;
             *current_status = status.clone();
         } // Write lock is released here!
+        self.metrics.lock().await.finished_at = match &status {
+            SubAgentStatus::Completed(_) | SubAgentStatus::Terminated => Some(Utc::now()),
+            _ => None,
+        };
 
         // Send MCP notifications based on status
         match &status {
+            SubAgentStatus::Queued => {
+                self.send_mcp_notification("status_changed", "Queued for a processing slot")
+                    .await;
+            }
             SubAgentStatus::Processing => {
                 self.send_mcp_notification("status_changed", "Processing request")
                     .await;
@@ -187,28 +464,215 @@ impl SubAgent {
             SubAgentStatus::Terminated => {
                 self.send_mcp_notification("terminated", "Subagent terminated")
                     .await;
+                self.emit(SubAgentEvent::Terminated {
+                    subagent_id: self.id.clone(),
+                });
+            }
+            SubAgentStatus::Suspended => {
+                self.send_mcp_notification("status_changed", "Suspended until the next message")
+                    .await;
             }
             _ => {}
         }
+
+        self.persist().await;
+    }
+
+    /// Broadcast an event to the subscribers of the subagent's events, if it has any
+    pub(crate) fn emit(&self, event: SubAgentEvent) {
+        if let Some(events) = &self.config.events {
+            // Nobody listening is fine
+            let _ = events.send(event);
+        }
+    }
+
+    /// The subagent's state, to save and later bring it back with [`Self::restore`]
+    pub async fn snapshot(&self) -> SubAgentSnapshot {
+        SubAgentSnapshot {
+            id: self.id.clone(),
+            recipe: self.config.recipe.clone(),
+            instructions: self.config.instructions.clone(),
+            max_turns: self.config.max_turns,
+            timeout_seconds: self.config.timeout_seconds,
+            priority: self.config.priority,
+            tool_policy: self.config.tool_policy.clone(),
+            conversation: self.get_conversation().await,
+            status: self.get_status().await,
+            turn_count: *self.turn_count.lock().await,
+            created_at: self.created_at,
+            artifacts: self.get_artifacts().await,
+            saved_at: Utc::now(),
+        }
+    }
+
+    /// Save a snapshot to the state directory, if there is one, appending only the messages
+    /// added since the last one. A terminated subagent can't be resumed, so its snapshot is
+    /// removed instead. Every turn ends `Completed` and an interactive subagent may still be
+    /// sent more messages, so those snapshots are kept until [`Self::discard_snapshot`].
+    async fn persist(&self) {
+        let Some(state_dir) = &self.config.state_dir else {
+            return;
+        };
+        let mut persisted = self.persisted_messages.lock().await;
+        let result = match self.get_status().await {
+            SubAgentStatus::Terminated => SubAgentSnapshot::remove(state_dir, &self.id)
+                .await
+                .map(|_| *persisted = 0),
+            _ => self
+                .snapshot()
+                .await
+                .save_from(state_dir, *persisted)
+                .await
+                .map(|saved| *persisted = saved),
+        };
+        if let Err(e) = result {
+            error!("Failed to persist subagent {}: {}", self.id, e);
+        }
+    }
+
+    /// Remove the snapshot of a subagent whose task is done, so it isn't restored after a
+    /// restart. A later message to it saves a new one.
+    pub async fn discard_snapshot(&self) {
+        let Some(state_dir) = &self.config.state_dir else {
+            return;
+        };
+        let mut persisted = self.persisted_messages.lock().await;
+        match SubAgentSnapshot::remove(state_dir, &self.id).await {
+            Ok(()) => *persisted = 0,
+            Err(e) => error!("Failed to remove snapshot of subagent {}: {}", self.id, e),
+        }
+    }
+
+    /// Recreate a subagent from a snapshot with its conversation, status, turn count and
+    /// artifacts. `config` supplies what isn't saved, such as the processing slots and state
+    /// directory; its recipe, instructions and limits are replaced by the snapshot's.
+    pub async fn restore(
+        snapshot: SubAgentSnapshot,
+        mut config: SubAgentConfig,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+        mcp_notification_tx: mpsc::Sender<JsonRpcMessage>,
+    ) -> Result<(Arc<Self>, tokio::task::JoinHandle<()>), anyhow::Error> {
+        config.id = snapshot.id;
+        config.recipe = snapshot.recipe;
+        config.instructions = snapshot.instructions;
+        config.max_turns = snapshot.max_turns;
+        config.timeout_seconds = snapshot.timeout_seconds;
+        config.priority = snapshot.priority;
+        config = config.restrict_tools(snapshot.tool_policy);
+
+        let (subagent, handle) =
+            Self::new(config, provider, extension_manager, mcp_notification_tx).await?;
+        *subagent.conversation.lock().await = snapshot.conversation;
+        *subagent.status.write().await = snapshot.status;
+        *subagent.turn_count.lock().await = snapshot.turn_count;
+        *subagent.artifacts.lock().await = snapshot.artifacts;
+        debug!(
+            "Restored subagent {} from a snapshot saved at {}",
+            subagent.id, snapshot.saved_at
+        );
+        Ok((subagent, handle))
+    }
+
+    /// Park a restored subagent until the parent sends it a message. A subagent that was
+    /// processing when the process stopped lost that turn; the next message starts a new one.
+    pub async fn suspend(&self) {
+        self.set_status(SubAgentStatus::Suspended).await;
     }
 
     /// Send an MCP notification about the subagent's activity
     pub async fn send_mcp_notification(&self, notification_type: &str, message: &str) {
-        let notification = JsonRpcMessage::Notification(JsonRpcNotification {
+        self.send_notification_data(json!({
+            "subagent_id": self.id,
+            "type": notification_type,
+            "message": message,
+            "timestamp": Utc::now().to_rfc3339()
+        }))
+        .await;
+    }
+
+    /// Tell the parent a task run to completion is finished, with the final message and a
+    /// summary of the run, so it doesn't have to poll for the result
+    pub async fn notify_completion(
+        &self,
+        result: &Result<Message, anyhow::Error>,
+        artifacts: &[String],
+    ) {
+        let progress = self.get_progress().await;
+        let final_message = match result {
+            Ok(message) => message.as_concat_text(),
+            Err(e) => format!("Error: {}", e),
+        };
+        let mut summary = format!(
+            "{} after {} turns in {}s",
+            progress.message,
+            progress.turn,
+            (Utc::now() - self.created_at).num_seconds()
+        );
+        if !artifacts.is_empty() {
+            summary.push_str(&format!(". Artifacts: {}", artifacts.join(", ")));
+        }
+        let confidence = self.confidence.lock().await.clone();
+        if let Some(confidence) = &confidence {
+            summary.push_str(&format!(". Confidence: {:.2}", confidence.score));
+        }
+        self.send_notification_data(json!({
+            "subagent_id": self.id,
+            "type": "task_completed",
+            "message": summary,
+            "summary": summary,
+            "final_message": final_message,
+            "artifacts": artifacts,
+            "confidence": confidence,
+            "timestamp": Utc::now().to_rfc3339()
+        }))
+        .await;
+    }
+
+    /// A notification carrying a message of the subagent's conversation, to show it as it
+    /// happens
+    pub fn message_notification(&self, message: &Message) -> JsonRpcMessage {
+        let text = message.as_concat_text();
+        let summary = if text.trim().is_empty() {
+            message
+                .content
+                .iter()
+                .filter_map(|content| match content {
+                    MessageContent::ToolRequest(request) => Some(match &request.tool_call {
+                        Ok(call) => format!("Using tool: {}", call.name),
+                        Err(e) => format!("Invalid tool call: {}", e),
+                    }),
+                    MessageContent::ToolResponse(_) => Some("Tool result".to_string()),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        } else {
+            text
+        };
+        self.notification(json!({
+            "subagent_id": self.id,
+            "type": "message",
+            "message": summary,
+            "content": message,
+            "timestamp": Utc::now().to_rfc3339()
+        }))
+    }
+
+    fn notification(&self, data: Value) -> JsonRpcMessage {
+        JsonRpcMessage::Notification(JsonRpcNotification {
             jsonrpc: "2.0".to_string(),
             method: "notifications/message".to_string(),
             params: Some(json!({
                 "level": "info",
                 "logger": format!("subagent_{}", self.id),
-                "data": {
-                    "subagent_id": self.id,
-                    "type": notification_type,
-                    "message": message,
-                    "timestamp": Utc::now().to_rfc3339()
-                }
+                "data": data
             })),
-        });
+        })
+    }
 
+    async fn send_notification_data(&self, data: Value) {
+        let notification = self.notification(data);
         if let Err(e) = self.mcp_notification_tx.send(notification).await {
             error!(
                 "Failed to send MCP notification from subagent {}: {}",
@@ -221,29 +685,119 @@ impl SubAgent {
     pub async fn get_progress(&self) -> SubAgentProgress {
         let status = self.get_status().await;
         let turn_count = *self.turn_count.lock().await;
+        let metrics = *self.metrics.lock().await;
+        let spend = global_pool_manager().usage().spend(&self.id);
 
         SubAgentProgress {
             subagent_id: self.id.clone(),
             status: status.clone(),
             message: match &status {
                 SubAgentStatus::Ready => "Ready to process messages".to_string(),
+                SubAgentStatus::Queued => "Queued, waiting for a processing slot".to_string(),
                 SubAgentStatus::Processing => "Processing request...".to_string(),
                 SubAgentStatus::Completed(msg) => msg.clone(),
                 SubAgentStatus::Terminated => "Subagent terminated".to_string(),
+                SubAgentStatus::Suspended => {
+                    "Suspended after a restart, waiting for a message to continue".to_string()
+                }
             },
             turn: turn_count,
             max_turns: self.config.max_turns,
             timestamp: Utc::now(),
+            usage_delta: self.turn_usage.lock().await.latest().cloned(),
+            input_tokens: spend.input_tokens,
+            output_tokens: spend.output_tokens,
+            cost_usd: spend.cost_usd,
+            duration_secs: (metrics.finished_at.unwrap_or_else(Utc::now) - self.created_at)
+                .num_seconds(),
+            tool_calls: metrics.tool_calls,
         }
     }
 
-    /// Process a message and generate a response using the subagent's provider
-    #[instrument(skip(self, message, provider, extension_manager))]
-    pub async fn reply_subagent(
+    /// Prompt growth of every provider call made so far
+    pub async fn get_usage_deltas(&self) -> Vec<TurnUsageDelta> {
+        self.turn_usage.lock().await.history().to_vec()
+    }
+
+    /// Process a message, streaming the messages of the turn as they are produced: assistant
+    /// messages calling tools, the tool results, and last the final response
+    pub fn reply_subagent<'a>(
+        &'a self,
+        message: String,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+    ) -> BoxStream<'a, Result<Message, anyhow::Error>> {
+        let provider = self.provider_override.clone().unwrap_or(provider);
+        Box::pin(async_stream::try_stream! {
+            let turns_before = *self.turn_count.lock().await;
+            let (updates_tx, mut updates_rx) = mpsc::unbounded_channel();
+            let turn = self.run_turn(message, provider, extension_manager, updates_tx);
+            tokio::pin!(turn);
+
+            let mut result = None;
+            while result.is_none() {
+                let update = tokio::select! {
+                    Some(update) = updates_rx.recv() => Some(update),
+                    finished = &mut turn => {
+                        result = Some(finished);
+                        None
+                    }
+                };
+                if let Some(update) = update {
+                    yield update;
+                }
+            }
+            while let Ok(update) = updates_rx.try_recv() {
+                yield update;
+            }
+            if let Some(result) = result {
+                self.turn_completed(turns_before, &result).await;
+                yield result?;
+            }
+        })
+    }
+
+    /// Report the end of a turn however it ended, with an answer, an error or termination.
+    /// Messages turned away before a turn started, e.g. past the turn limit, aren't reported.
+    async fn turn_completed(&self, turns_before: usize, result: &Result<Message, anyhow::Error>) {
+        let turn = *self.turn_count.lock().await;
+        if turn == turns_before {
+            return;
+        }
+        let message = match (result, self.get_status().await) {
+            (Err(e), _) => format!("Error: {}", e),
+            (Ok(_), SubAgentStatus::Completed(message)) => message,
+            (Ok(_), _) => "Completed!".to_string(),
+        };
+        self.emit(SubAgentEvent::TurnCompleted {
+            subagent_id: self.id.clone(),
+            turn,
+            message,
+        });
+    }
+
+    /// Process a message and return only the final response
+    pub async fn reply_subagent_final(
+        &self,
+        message: String,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+    ) -> Result<Message, anyhow::Error> {
+        let mut replies = self.reply_subagent(message, provider, extension_manager);
+        let mut last = None;
+        while let Some(message) = replies.next().await {
+            last = Some(message?);
+        }
+        last.ok_or_else(|| anyhow!("Subagent {} did not reply", self.id))
+    }
+
+    #[instrument(skip(self, message, provider, extension_manager, updates))]
+    async fn run_turn(
         &self,
         message: String,
         provider: Arc<dyn Provider>,
-        extension_manager: Arc<tokio::sync::RwLockReadGuard<'_, ExtensionManager>>,
+        extension_manager: Arc<ExtensionManager>,
+        updates: mpsc::UnboundedSender<Message>,
     ) -> Result<Message, anyhow::Error> {
         debug!("Processing message for subagent {}", self.id);
         self.send_mcp_notification("message_processing", &format!("Processing: {}", message))
@@ -262,6 +816,28 @@ impl SubAgent {
                 }
             }
         }
+        if self.cancellation.is_cancelled() {
+            return Err(self.terminated_error());
+        }
+
+        // Wait for a processing slot, held until the turn ends
+        let _slot = match &self.config.processing_slots {
+            Some(slots) => Some(match slots.try_acquire() {
+                Some(slot) => slot,
+                None => {
+                    debug!(
+                        "Subagent {} queued for a processing slot at {:?} priority",
+                        self.id, self.config.priority
+                    );
+                    self.set_status(SubAgentStatus::Queued).await;
+                    tokio::select! {
+                        slot = slots.acquire(self.config.priority) => slot,
+                        _ = self.cancellation.cancelled() => return Err(self.terminated_error()),
+                    }
+                }
+            }),
+            None => None,
+        };
 
         // Set status to processing
         self.set_status(SubAgentStatus::Processing).await;
@@ -282,44 +858,31 @@ impl SubAgent {
                 &format!("Turn {}/{}", turn_count, self.config.max_turns.unwrap_or(0)),
             )
             .await;
+            self.emit(SubAgentEvent::TurnStarted {
+                subagent_id: self.id.clone(),
+                turn: *turn_count,
+            });
         }
 
         // Get the current conversation for context
         let mut messages = self.get_conversation().await;
 
-        // Get tools based on whether we're using a recipe or inheriting from parent
-        let tools: Vec<Tool> = if self.config.recipe.is_some() {
-            // Recipe mode: only get tools from the recipe's extensions
-            let recipe_extensions = self.recipe_extensions.lock().await;
-            let mut recipe_tools = Vec::new();
+        // Tools are listed from and dispatched to the subagent's own extensions when it has them
+        let scoped = match &self.extensions {
+            Some(scoped) => Some(scoped.read().await),
+            None => None,
+        };
+        let extension_manager = scoped.as_deref().unwrap_or(&extension_manager);
 
+        // Get tools based on whether we're using a recipe or inheriting from parent
+        let mut tools: Vec<Tool> = if self.config.recipe.is_some() {
+            // Recipe mode: only the recipe's extensions were started
             debug!(
                 "Subagent {} operating in recipe mode with {} extensions",
                 self.id,
-                recipe_extensions.len()
+                self.recipe_extensions.lock().await.len()
             );
-
-            for extension_name in recipe_extensions.iter() {
-                match extension_manager
-                    .get_prefixed_tools(Some(extension_name.clone()))
-                    .await
-                {
-                    Ok(mut ext_tools) => {
-                        debug!(
-                            "Added {} tools from extension {}",
-                            ext_tools.len(),
-                            extension_name
-                        );
-                        recipe_tools.append(&mut ext_tools);
-                    }
-                    Err(e) => {
-                        debug!(
-                            "Failed to get tools for extension {}: {}",
-                            extension_name, e
-                        );
-                    }
-                }
-            }
+            let recipe_tools = extension_manager.get_prefixed_tools(None).await?;
 
             debug!(
                 "Subagent {} has {} total recipe tools before filtering",
@@ -330,7 +893,7 @@ impl SubAgent {
             let mut filtered_tools = Self::filter_subagent_tools(recipe_tools);
 
             // Add platform tools (except subagent tools)
-            Self::add_platform_tools(&mut filtered_tools, &extension_manager).await;
+            Self::add_platform_tools(&mut filtered_tools, extension_manager).await;
 
             debug!(
                 "Subagent {} has {} tools after filtering and adding platform tools",
@@ -353,7 +916,7 @@ impl SubAgent {
             let mut filtered_tools = Self::filter_subagent_tools(parent_tools);
 
             // Add platform tools (except subagent tools)
-            Self::add_platform_tools(&mut filtered_tools, &extension_manager).await;
+            Self::add_platform_tools(&mut filtered_tools, extension_manager).await;
 
             debug!(
                 "Subagent {} has {} tools after filtering and adding platform tools",
@@ -363,92 +926,228 @@ impl SubAgent {
             filtered_tools
         };
 
-        let toolshim_tools: Vec<Tool> = vec![];
+        // Tools outside the subagent's policy are never offered, nor run when asked for anyway
+        tools.retain(|tool| {
+            self.is_platform_tool(&tool.name) || self.config.tool_policy.permits(tool)
+        });
+        let offered: HashSet<String> = tools.iter().map(|tool| tool.name.clone()).collect();
+
+        // A recipe with a response schema gets its answer through the final output tool, which
+        // validates it against the schema
+        let mut response_tool = self.response_tool();
+        if let Some(response_tool) = &response_tool {
+            tools.push(response_tool.tool());
+        }
 
         // Build system prompt using the template
-        let system_prompt = self.build_system_prompt(&tools).await?;
+        let mut system_prompt = self.build_system_prompt(&tools).await?;
+        if let Some(response_tool) = &response_tool {
+            system_prompt = format!("{}\n\n{}", system_prompt, response_tool.system_prompt());
+        }
 
-        // Generate response from provider
-        loop {
-            match Agent::generate_response_from_provider(
-                Arc::clone(&provider),
-                &system_prompt,
-                &messages,
-                &tools,
-                &toolshim_tools,
-            )
-            .await
-            {
-                Ok((response, _usage)) => {
-                    // Process any tool calls in the response
-                    let tool_requests: Vec<ToolRequest> = response
-                        .content
-                        .iter()
-                        .filter_map(|content| {
-                            if let MessageContent::ToolRequest(req) = content {
-                                Some(req.clone())
-                            } else {
-                                None
-                            }
-                        })
-                        .collect();
+        // Describe the tools in the prompt instead when the provider can't take them
+        let mut toolshim_tools: Vec<Tool> = vec![];
+        if uses_toolshim(provider.as_ref()) {
+            system_prompt = modify_system_prompt_for_tool_json(&system_prompt, &tools);
+            toolshim_tools = std::mem::take(&mut tools);
+        }
 
-                    // If there are no tool requests, we're done
-                    if tool_requests.is_empty() {
-                        self.add_message(response.clone()).await;
+        let completion_options =
+            CompletionOptions::from_config().with_cancellation_token(self.cancellation.clone());
+        let token_counter = create_async_token_counter().await.ok();
+        let task_start = messages.len();
+        let mut failed_responses = 0;
+        let mut response_attempt = String::new();
 
-                        // Send notification about response
-                        self.send_mcp_notification(
-                            "response_generated",
-                            &format!("Responded: {}", response.as_concat_text()),
-                        )
-                        .await;
+        // Generate response from provider
+        let run = async {
+            loop {
+                let prompt = token_counter.as_ref().map(|counter| {
+                    PromptBreakdown::measure(counter, &system_prompt, &messages, &tools)
+                });
+                match with_usage_session(
+                    self.id.clone(),
+                    with_priority(
+                        self.config.priority,
+                        Agent::generate_response_from_provider(
+                            Arc::clone(&provider),
+                            &system_prompt,
+                            &messages,
+                            &tools,
+                            &toolshim_tools,
+                            &completion_options,
+                        ),
+                    ),
+                )
+                .await
+                {
+                    Ok((response, usage)) => {
+                        if let Some(prompt) = prompt {
+                            let mut turn_usage = self.turn_usage.lock().await;
+                            let delta = turn_usage.record(prompt, usage.usage.input_tokens);
+                            if let Some(contributor) = delta.largest_contributor() {
+                                let message = format!(
+                                    "Prompt grew by {} tokens to {}, mostly {:?} (+{})",
+                                    delta.prompt_growth,
+                                    delta.prompt_tokens,
+                                    contributor.section,
+                                    contributor.delta
+                                );
+                                drop(turn_usage);
+                                self.send_mcp_notification("usage_delta", &message).await;
+                            }
+                        }
 
-                        // Add delay before completion to ensure all processing finishes
-                        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                        // Process any tool calls in the response
+                        let tool_requests: Vec<ToolRequest> = response
+                            .content
+                            .iter()
+                            .filter_map(|content| {
+                                if let MessageContent::ToolRequest(req) = content {
+                                    Some(req.clone())
+                                } else {
+                                    None
+                                }
+                            })
+                            .collect();
+
+                        // If there are no tool requests, we're done, unless the answer still has
+                        // to come through the final output tool
+                        if tool_requests.is_empty() {
+                            let response = match &response_tool {
+                                None => response,
+                                Some(response_tool) => match &response_tool.final_output {
+                                    Some(output) => Message::assistant().with_text(output),
+                                    None => {
+                                        failed_responses += 1;
+                                        if failed_responses > MAX_RESPONSE_REPAIRS {
+                                            break Ok(self
+                                                .response_schema_failed(&response.as_concat_text())
+                                                .await);
+                                        }
+                                        let repair =
+                                            Message::user().with_text(RESPONSE_REPAIR_PROMPT);
+                                        messages.push(response);
+                                        messages.push(repair);
+                                        continue;
+                                    }
+                                },
+                            };
+                            self.add_message(response.clone()).await;
 
-                        // Set status back to ready and return the final response
-                        self.set_status(SubAgentStatus::Completed("Completed!".to_string()))
+                            // Send notification about response
+                            self.send_mcp_notification(
+                                "response_generated",
+                                &format!("Responded: {}", response.as_concat_text()),
+                            )
                             .await;
-                        break Ok(response);
-                    }
 
-                    // Add the assistant message with tool calls to the conversation
-                    messages.push(response.clone());
+                            // Set status back to ready and return the final response
+                            self.set_status(SubAgentStatus::Completed("Completed!".to_string()))
+                                .await;
+                            break Ok(response);
+                        }
+
+                        // Add the assistant message with tool calls to the conversation
+                        messages.push(response.clone());
+                        let _ = updates.send(response.clone());
+
+                        // Answer every tool request in one user message, paired by request ID,
+                        // the way the parent agent builds its transcript. Requests over the
+                        // per-turn limit are answered with an error instead of being run.
+                        let requested = tool_requests.len();
+                        let max_tool_calls = tool_call_limit::max_from_config();
+                        let (tool_requests, over_limit) =
+                            tool_call_limit::split(tool_requests, max_tool_calls);
+                        let mut tool_response_message = Message::user();
+                        if let Some(max) = max_tool_calls {
+                            for request in &over_limit {
+                                tool_response_message = tool_response_message.with_tool_response(
+                                    request.id.clone(),
+                                    Err(tool_call_limit::rejection(max, requested)),
+                                );
+                            }
+                        }
+                        for request in &tool_requests {
+                            let tool_call = match &request.tool_call {
+                                Ok(tool_call) => tool_call,
+                                // A call the model got wrong still gets a response, so no
+                                // request is left unanswered
+                                Err(e) => {
+                                    tool_response_message = tool_response_message
+                                        .with_tool_response(request.id.clone(), Err(e.clone()));
+                                    continue;
+                                }
+                            };
 
-                    // Process each tool request and create user response messages
-                    for request in &tool_requests {
-                        if let Ok(tool_call) = &request.tool_call {
                             // Send notification about tool usage
                             self.send_mcp_notification(
                                 "tool_usage",
                                 &format!("Using tool: {}", tool_call.name),
                             )
                             .await;
-
-                            // Handle platform tools or dispatch to extension manager
-                            let tool_result = if self.is_platform_tool(&tool_call.name) {
-                                self.handle_platform_tool_call(
-                                    tool_call.clone(),
-                                    &extension_manager,
-                                )
-                                .await
+                            self.emit(SubAgentEvent::ToolCalled {
+                                subagent_id: self.id.clone(),
+                                tool: tool_call.name.clone(),
+                            });
+                            self.metrics.lock().await.tool_calls += 1;
+
+                            // Handle platform tools or dispatch to extension manager, unless
+                            // the policy forbids the tool even though the model asked for it
+                            let tool_result = if let Some(response_tool) = response_tool
+                                .as_mut()
+                                .filter(|_| tool_call.name == FINAL_OUTPUT_TOOL_NAME)
+                            {
+                                let result = response_tool
+                                    .execute_tool_call(tool_call.clone())
+                                    .await
+                                    .result
+                                    .await;
+                                response_attempt = tool_call.arguments.to_string();
+                                // The validation errors go back to the model to repair its output
+                                if result.is_err() {
+                                    failed_responses += 1;
+                                }
+                                result
+                            } else if self.is_platform_tool(&tool_call.name) {
+                                self.handle_platform_tool_call(tool_call.clone(), extension_manager)
+                                    .await
+                            } else if !offered.contains(&tool_call.name) {
+                                warn!(
+                                    "Subagent {} tried to call {}, which its tool policy denies",
+                                    self.id, tool_call.name
+                                );
+                                Err(ToolError::ExecutionError(format!(
+                                    "Tool {} is not permitted for this subagent",
+                                    tool_call.name
+                                )))
                             } else {
                                 match extension_manager
                                     .dispatch_tool_call(tool_call.clone())
                                     .await
                                 {
-                                    Ok(result) => result.result.await,
+                                    // Dropping the call on termination only stops waiting for
+                                    // it; see `terminate`
+                                    Ok(result) => tokio::select! {
+                                        result = result.result => result,
+                                        _ = self.cancellation.cancelled() => {
+                                            Err(ToolError::ExecutionError(
+                                                "Cancelled, the subagent was terminated"
+                                                    .to_string(),
+                                            ))
+                                        }
+                                    },
                                     Err(e) => Err(ToolError::ExecutionError(e.to_string())),
                                 }
                             };
 
+                            // Large outputs go to a file, as they do for the parent agent, so
+                            // they don't fill the conversation
+                            let tool_result = process_tool_response(tool_result);
                             match tool_result {
                                 Ok(result) => {
-                                    // Create a user message with the tool response
-                                    let tool_response_message = Message::user()
-                                        .with_tool_response(request.id.clone(), Ok(result.clone()));
-                                    messages.push(tool_response_message);
+                                    tool_response_message = tool_response_message
+                                        .with_tool_response(request.id.clone(), Ok(result));
 
                                     // Send notification about tool completion
                                     self.send_mcp_notification(
@@ -458,51 +1157,200 @@ impl SubAgent {
                                     .await;
                                 }
                                 Err(e) => {
-                                    // Create a user message with the tool error
-                                    let tool_error_message = Message::user().with_tool_response(
-                                        request.id.clone(),
-                                        Err(ToolError::ExecutionError(e.to_string())),
-                                    );
-                                    messages.push(tool_error_message);
-
                                     // Send notification about tool error
                                     self.send_mcp_notification(
                                         "tool_error",
                                         &format!("Tool {} error: {}", tool_call.name, e),
                                     )
                                     .await;
+
+                                    tool_response_message = tool_response_message
+                                        .with_tool_response(request.id.clone(), Err(e));
                                 }
                             }
                         }
-                    }
+                        let _ = updates.send(tool_response_message.clone());
 
-                    // Continue the loop to get the next response from the provider
-                }
-                Err(ProviderError::ContextLengthExceeded(_)) => {
-                    self.set_status(SubAgentStatus::Completed(
-                        "Context length exceeded".to_string(),
-                    ))
-                    .await;
-                    break Ok(Message::assistant().with_context_length_exceeded(
+                        // Keep the exchange in the subagent's conversation, request and
+                        // responses together, so later messages to it see what it did
+                        self.add_message(response.clone()).await;
+                        self.add_message(tool_response_message.clone()).await;
+                        messages.push(tool_response_message);
+
+                        if failed_responses > MAX_RESPONSE_REPAIRS {
+                            break Ok(self.response_schema_failed(&response_attempt).await);
+                        }
+
+                        // Continue the loop to get the next response from the provider
+                    }
+                    // Terminated while waiting on the provider; the status already says so
+                    Err(ProviderError::Cancelled) => break Err(self.terminated_error()),
+                    Err(ProviderError::ContextLengthExceeded(_)) => {
+                        self.set_status(SubAgentStatus::Completed(
+                            "Context length exceeded".to_string(),
+                        ))
+                        .await;
+                        break Ok(Message::assistant().with_context_length_exceeded(
                         "The context length of the model has been exceeded. Please start a new session and try again.",
                     ));
-                }
-                Err(ProviderError::RateLimitExceeded(_)) => {
-                    self.set_status(SubAgentStatus::Completed("Rate limit exceeded".to_string()))
+                    }
+                    Err(ProviderError::ContentFiltered(reason)) => {
+                        self.set_status(SubAgentStatus::Completed(
+                            "Blocked by content filter".to_string(),
+                        ))
                         .await;
-                    break Ok(Message::assistant()
-                        .with_text("Rate limit exceeded. Please try again later."));
-                }
-                Err(e) => {
-                    self.set_status(SubAgentStatus::Completed(format!("Error: {}", e)))
+                        break Ok(Message::assistant()
+                            .with_text(format!(
+                            "The model provider's content filter blocked this request ({reason})."
+                        ))
+                            .with_finish_reason(FinishReason::ContentFilter));
+                    }
+                    Err(ProviderError::CircuitOpen(reason)) => {
+                        self.set_status(SubAgentStatus::Completed(
+                            "Provider unavailable".to_string(),
+                        ))
+                        .await;
+                        break Ok(Message::assistant().with_text(format!(
+                        "The model provider is currently unavailable ({reason}). Please try again later."
+                    )));
+                    }
+                    Err(ProviderError::RateLimitExceeded(_)) => {
+                        self.set_status(SubAgentStatus::Completed(
+                            "Rate limit exceeded".to_string(),
+                        ))
                         .await;
-                    error!("Error: {}", e);
-                    break Ok(Message::assistant().with_text(format!("Ran into this error: {e}.\n\nPlease retry if you think this is a transient or recoverable error.")));
+                        break Ok(Message::assistant()
+                            .with_text("Rate limit exceeded. Please try again later."));
+                    }
+                    Err(e) => {
+                        self.set_status(SubAgentStatus::Completed(format!("Error: {}", e)))
+                            .await;
+                        error!("Error: {}", e);
+                        break Ok(Message::assistant().with_text(format!("Ran into this error: {e}.\n\nPlease retry if you think this is a transient or recoverable error.")));
+                    }
                 }
             }
+        };
+
+        // The timeout covers the whole task, provider calls and tools alike. Dropping the
+        // task on expiry cancels the request or tool call in flight.
+        let Some(timeout_seconds) = self.config.timeout_seconds else {
+            return run.await;
+        };
+        let outcome = tokio::time::timeout(Duration::from_secs(timeout_seconds), run).await;
+        match outcome {
+            Ok(result) => result,
+            Err(_) => Ok(self
+                .timed_out(timeout_seconds, &messages[task_start..])
+                .await),
         }
     }
 
+    /// The final output tool for a recipe that declares a response schema. A schema that isn't
+    /// valid JSON Schema is ignored rather than failing the task.
+    fn response_tool(&self) -> Option<FinalOutputTool> {
+        let response = self.config.recipe.as_ref()?.response.clone()?;
+        let schema = response.json_schema.as_ref()?;
+        if schema.as_object().is_some_and(|schema| schema.is_empty())
+            || jsonschema::meta::validate(schema).is_err()
+        {
+            warn!(
+                "Subagent {} ignores its recipe's response schema, which isn't valid JSON Schema",
+                self.id
+            );
+            return None;
+        }
+        Some(FinalOutputTool::new(response))
+    }
+
+    /// Stop a task whose output still didn't match the recipe's response schema after the
+    /// repair attempts, handing the parent the last answer
+    async fn response_schema_failed(&self, last_answer: &str) -> Message {
+        self.set_status(SubAgentStatus::Completed(
+            "Output did not match the response schema".to_string(),
+        ))
+        .await;
+        let mut text = format!(
+            "The subagent's output did not match the recipe's response schema after {} repair \
+             attempts.",
+            MAX_RESPONSE_REPAIRS
+        );
+        if !last_answer.trim().is_empty() {
+            text.push_str(" Its last answer:\n");
+            text.push_str(last_answer);
+        }
+        let message = Message::assistant().with_text(text);
+        self.add_message(message.clone()).await;
+        message
+    }
+
+    /// Stop a task that ran out of time, handing the parent what it got done
+    async fn timed_out(&self, timeout_seconds: u64, partial: &[Message]) -> Message {
+        debug!(
+            "Subagent {} timed out after {}s with {} messages of progress",
+            self.id,
+            timeout_seconds,
+            partial.len()
+        );
+        self.set_status(SubAgentStatus::Completed("timed out".to_string()))
+            .await;
+
+        let mut text = format!(
+            "The task timed out after {}s before it was finished.",
+            timeout_seconds
+        );
+        if partial.is_empty() {
+            text.push_str(" No progress was made.");
+        } else {
+            text.push_str(" Partial transcript:\n");
+            text.push_str(&Self::transcript(partial));
+        }
+        let message = Message::assistant().with_text(text);
+        self.add_message(message.clone()).await;
+        message
+    }
+
+    /// A plain text transcript of messages, with long entries cut short
+    fn transcript(messages: &[Message]) -> String {
+        const MAX_ENTRY_CHARS: usize = 500;
+        let mut transcript = String::new();
+        for message in messages {
+            let role = match message.role {
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+            };
+            for content in &message.content {
+                let entry = match content {
+                    MessageContent::Text(text) => text.text.clone(),
+                    MessageContent::ToolRequest(request) => match &request.tool_call {
+                        Ok(call) => format!("called {} with {}", call.name, call.arguments),
+                        Err(e) => format!("made an invalid tool call: {}", e),
+                    },
+                    MessageContent::ToolResponse(response) => match &response.tool_result {
+                        Ok(contents) => contents
+                            .iter()
+                            .filter_map(|content| content.as_text())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        Err(e) => format!("tool error: {}", e),
+                    },
+                    _ => continue,
+                };
+                let entry: String = if entry.chars().count() > MAX_ENTRY_CHARS {
+                    entry
+                        .chars()
+                        .take(MAX_ENTRY_CHARS)
+                        .chain("...".chars())
+                        .collect()
+                } else {
+                    entry
+                };
+                transcript.push_str(&format!("{}: {}\n", role, entry));
+            }
+        }
+        transcript
+    }
+
     /// Add a message to the conversation (for tracking agent responses)
     pub async fn add_message(&self, message: Message) {
         let mut conversation = self.conversation.lock().await;
@@ -522,13 +1370,29 @@ impl SubAgent {
         )
     }
 
-    /// Terminate the subagent
+    /// Terminate the subagent, stopping the extensions it started for its recipe once the
+    /// cancelled turn lets go of them. The provider request in flight is cancelled. A tool call
+    /// in flight is abandoned rather than stopped: the MCP client holds its connection for the
+    /// whole request and can't send a cancellation alongside it, so the extension runs the call
+    /// to the end and its result is dropped. Extensions of the recipe's own are stopped, which
+    /// ends their calls too.
     pub async fn terminate(&self) -> Result<(), anyhow::Error> {
         debug!("Terminating subagent {}", self.id);
         self.set_status(SubAgentStatus::Terminated).await;
+        self.cancellation.cancel();
+        if let Some(scoped) = &self.extensions {
+            let mut scoped = scoped.write().await;
+            for name in scoped.list_extensions().await? {
+                scoped.remove_extension(&name).await?;
+            }
+        }
         Ok(())
     }
 
+    fn terminated_error(&self) -> anyhow::Error {
+        anyhow!("Subagent {} was terminated", self.id)
+    }
+
     /// Get formatted conversation for display
     pub async fn get_formatted_conversation(&self) -> String {
         let conversation = self.conversation.lock().await;
@@ -585,7 +1449,12 @@ impl SubAgent {
         let filtered_tools: Vec<Tool> = tools
             .into_iter()
             .filter(|tool| {
-                let should_keep = tool.name != SUBAGENT_RUN_TASK_TOOL_NAME;
+                let should_keep = tool.name != SUBAGENT_RUN_TASK_TOOL_NAME
+                    && tool.name != SUBAGENT_SPAWN_PARALLEL_TOOL_NAME
+                    && tool.name != SUBAGENT_COLLECT_RESULTS_TOOL_NAME
+                    && tool.name != SUBAGENT_SEND_MESSAGE_TOOL_NAME
+                    && tool.name != SUBAGENT_RELAY_MESSAGE_TOOL_NAME
+                    && tool.name != SUBAGENT_HANDOFF_TOOL_NAME;
                 if !should_keep {
                     debug!("Filtering out subagent tool: {}", tool.name);
                 }
@@ -622,6 +1491,9 @@ impl SubAgent {
             debug!("Added 2 resource platform tools");
         }
 
+        tools.push(final_output_subagent_tool());
+        tools.push(write_table_subagent_tool());
+
         // Note: We explicitly do NOT add these tools for security reasons:
         // - manage_extensions (could interfere with parent agent's extensions)
         // - manage_schedule (could interfere with parent agent's scheduling)
@@ -636,6 +1508,8 @@ impl SubAgent {
             PLATFORM_SEARCH_AVAILABLE_EXTENSIONS_TOOL_NAME
                 | PLATFORM_READ_RESOURCE_TOOL_NAME
                 | PLATFORM_LIST_RESOURCES_TOOL_NAME
+                | SUBAGENT_FINAL_OUTPUT_TOOL_NAME
+                | SUBAGENT_WRITE_TABLE_TOOL_NAME
         )
     }
 
@@ -660,6 +1534,8 @@ impl SubAgent {
                 .list_resources(tool_call.arguments)
                 .await
                 .map_err(|e| ToolError::ExecutionError(e.to_string())),
+            SUBAGENT_FINAL_OUTPUT_TOOL_NAME => self.register_artifact(tool_call.arguments).await,
+            SUBAGENT_WRITE_TABLE_TOOL_NAME => self.register_table(tool_call.arguments).await,
             _ => Err(ToolError::ExecutionError(format!(
                 "Platform tool '{}' is not available to subagents for security reasons",
                 tool_call.name
@@ -667,6 +1543,147 @@ impl SubAgent {
         }
     }
 
+    async fn register_artifact(
+        &self,
+        arguments: Value,
+    ) -> Result<Vec<mcp_core::Content>, ToolError> {
+        let argument = |key: &str| {
+            arguments
+                .get(key)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| ToolError::InvalidParameters(format!("Missing {} parameter", key)))
+        };
+        let name = argument("name")?;
+        let content = argument("content")?;
+        if !is_valid_artifact_name(name) {
+            return Err(ToolError::InvalidParameters(format!(
+                "Invalid artifact name '{}': use a plain file name like 'report.md'",
+                name
+            )));
+        }
+
+        self.artifacts
+            .lock()
+            .await
+            .insert(name.to_string(), Artifact::text(name, content));
+        Ok(vec![mcp_core::Content::text(format!(
+            "Registered artifact {}",
+            name
+        ))])
+    }
+
+    /// Check the rows against the columns and register the table as a CSV or XLSX artifact
+    async fn register_table(&self, arguments: Value) -> Result<Vec<mcp_core::Content>, ToolError> {
+        let name = arguments
+            .get("name")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing name parameter".to_string()))?;
+        if !is_valid_artifact_name(name) {
+            return Err(ToolError::InvalidParameters(format!(
+                "Invalid artifact name '{}': use a plain file name like 'results.csv'",
+                name
+            )));
+        }
+        let format = TableFormat::from_name(name).ok_or_else(|| {
+            ToolError::InvalidParameters(
+                TableError::UnsupportedFormat(name.to_string()).to_string(),
+            )
+        })?;
+        let columns: Vec<Column> = arguments
+            .get("columns")
+            .cloned()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| ToolError::InvalidParameters(format!("Invalid columns: {}", e)))?
+            .ok_or_else(|| ToolError::InvalidParameters("Missing columns parameter".to_string()))?;
+        let rows = arguments
+            .get("rows")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| ToolError::InvalidParameters("Missing rows parameter".to_string()))?;
+
+        let table =
+            Table::new(columns, rows).map_err(|e| ToolError::InvalidParameters(e.to_string()))?;
+        let content = table
+            .write(format)
+            .map_err(|e| ToolError::ExecutionError(e.to_string()))?;
+        self.artifacts
+            .lock()
+            .await
+            .insert(name.to_string(), Artifact::new(content, format.mime_type()));
+        Ok(vec![mcp_core::Content::text(format!(
+            "Registered table {} with {} rows",
+            name,
+            table.row_count()
+        ))])
+    }
+
+    /// Artifacts registered so far, by name
+    pub async fn get_artifacts(&self) -> BTreeMap<String, Artifact> {
+        self.artifacts.lock().await.clone()
+    }
+
+    /// Render the recipe's report, if it has one, from the final output and register it as an
+    /// artifact. A report that fails to render is logged and left out.
+    pub async fn render_report(&self, output: &str) {
+        let Some(recipe) = &self.config.recipe else {
+            return;
+        };
+        let Some(report) = &recipe.report else {
+            return;
+        };
+        if !is_valid_artifact_name(&report.name) {
+            error!(
+                "Invalid report name '{}' in recipe {}",
+                report.name, recipe.title
+            );
+            return;
+        }
+        match report.render(&recipe.title, output) {
+            Ok(rendered) => {
+                debug!("Rendered report {} for subagent {}", rendered.name, self.id);
+                self.artifacts.lock().await.insert(
+                    rendered.name,
+                    Artifact::new(rendered.content, rendered.mime_type),
+                );
+            }
+            Err(e) => error!(
+                "Failed to render report {} for subagent {}: {}",
+                report.name, self.id, e
+            ),
+        }
+    }
+
+    /// Score the final answer against the recipe's success criteria when it asks for a
+    /// confidence check, keeping the score with the subagent's result. A failed assessment is
+    /// logged and leaves the answer unscored.
+    pub async fn assess_confidence(
+        &self,
+        task: &str,
+        output: &str,
+        provider: Arc<dyn Provider>,
+    ) -> Option<ConfidenceScore> {
+        let recipe = self.config.recipe.as_ref()?;
+        let check = recipe.confidence.as_ref()?;
+        let provider = self.provider_override.clone().unwrap_or(provider);
+        match confidence::assess(provider.as_ref(), recipe, check, task, output).await {
+            Ok(score) => {
+                debug!(
+                    "Subagent {} scored its answer {:.2} for confidence",
+                    self.id, score.score
+                );
+                *self.confidence.lock().await = Some(score.clone());
+                Some(score)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to assess confidence for subagent {}: {}",
+                    self.id, e
+                );
+                None
+            }
+        }
+    }
+
     /// Build the system prompt for the subagent using the template
     async fn build_system_prompt(&self, available_tools: &[Tool]) -> Result<String, anyhow::Error> {
         let mut context = HashMap::new();
@@ -696,14 +1713,11 @@ impl SubAgent {
 
         // Add task instructions
         let instructions = if let Some(recipe) = &self.config.recipe {
-            recipe.instructions.as_deref().unwrap_or("")
+            recipe.instructions_with_knowledge().unwrap_or_default()
         } else {
-            self.config.instructions.as_deref().unwrap_or("")
+            self.config.instructions.clone().unwrap_or_default()
         };
-        context.insert(
-            "task_instructions",
-            serde_json::Value::String(instructions.to_string()),
-        );
+        context.insert("task_instructions", serde_json::Value::String(instructions));
 
         // Add available extensions (only if we have a recipe and extensions)
         if self.config.recipe.is_some() {
@@ -755,3 +1769,452 @@ impl SubAgent {
         Ok(system_prompt)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::{ProviderMetadata, ProviderUsage};
+    use crate::providers::mock::{MockProvider, MockResponse};
+    use mcp_client::client::{ClientCapabilities, ClientInfo, Error, McpClientTrait};
+    use mcp_core::protocol::{
+        CallToolResult, GetPromptResult, InitializeResult, ListPromptsResult, ListResourcesResult,
+        ListToolsResult, ReadResourceResult,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// An extension with one tool, `secret`, counting how often it is called
+    struct CountingClient {
+        calls: Arc<AtomicUsize>,
+        /// Text the tool answers with
+        output: String,
+    }
+
+    #[async_trait::async_trait]
+    impl McpClientTrait for CountingClient {
+        async fn initialize(
+            &mut self,
+            _info: ClientInfo,
+            _capabilities: ClientCapabilities,
+        ) -> Result<InitializeResult, Error> {
+            Err(Error::NotInitialized)
+        }
+
+        async fn list_resources(
+            &self,
+            _next_cursor: Option<String>,
+        ) -> Result<ListResourcesResult, Error> {
+            Err(Error::NotInitialized)
+        }
+
+        async fn read_resource(&self, _uri: &str) -> Result<ReadResourceResult, Error> {
+            Err(Error::NotInitialized)
+        }
+
+        async fn list_tools(&self, _next_cursor: Option<String>) -> Result<ListToolsResult, Error> {
+            Ok(ListToolsResult {
+                tools: vec![Tool::new("secret", "A parent-only tool", json!({}), None)],
+                next_cursor: None,
+            })
+        }
+
+        async fn call_tool(&self, _name: &str, _arguments: Value) -> Result<CallToolResult, Error> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(CallToolResult {
+                content: vec![mcp_core::Content::text(self.output.clone())],
+                is_error: None,
+            })
+        }
+
+        async fn list_prompts(
+            &self,
+            _next_cursor: Option<String>,
+        ) -> Result<ListPromptsResult, Error> {
+            Err(Error::NotInitialized)
+        }
+
+        async fn get_prompt(
+            &self,
+            _name: &str,
+            _arguments: Value,
+        ) -> Result<GetPromptResult, Error> {
+            Err(Error::NotInitialized)
+        }
+
+        async fn subscribe(&self) -> mpsc::Receiver<JsonRpcMessage> {
+            mpsc::channel(1).1
+        }
+    }
+
+    /// A provider whose requests never finish
+    struct HangingProvider;
+
+    #[async_trait::async_trait]
+    impl Provider for HangingProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("hanging".to_string())
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            std::future::pending().await
+        }
+    }
+
+    fn recipe() -> Recipe {
+        Recipe::builder()
+            .title("Scoped")
+            .description("A recipe without extensions")
+            .instructions("Answer without tools")
+            .build()
+            .unwrap()
+    }
+
+    fn parent_extensions(calls: &Arc<AtomicUsize>) -> RwLock<ExtensionManager> {
+        let mut parent = ExtensionManager::new();
+        parent.add_client(
+            "parent",
+            Box::new(CountingClient {
+                calls: Arc::clone(calls),
+                output: String::new(),
+            }),
+        );
+        RwLock::new(parent)
+    }
+
+    #[tokio::test]
+    async fn test_recipe_subagent_cannot_reach_parent_tools() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let parent = parent_extensions(&calls);
+        let provider = Arc::new(
+            MockProvider::default()
+                .with_tool_call("parent__secret", json!({}))
+                .with_text("done"),
+        );
+        let (notifications, _) = mpsc::channel(16);
+
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_recipe(recipe()),
+            provider.clone(),
+            Arc::new(parent.read().await.clone()),
+            notifications,
+        )
+        .await
+        .unwrap();
+        let _ = subagent
+            .reply_subagent_final(
+                "Use the secret tool".to_string(),
+                provider.clone(),
+                Arc::new(parent.read().await.clone()),
+            )
+            .await;
+        handle.abort();
+
+        let requests = provider.requests();
+        assert!(!requests.is_empty());
+        assert!(requests
+            .iter()
+            .all(|request| !request.tools.iter().any(|tool| tool == "parent__secret")));
+        assert_eq!(calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn test_terminate_stops_the_recipe_extensions() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let parent = RwLock::new(ExtensionManager::new());
+        let (notifications, _) = mpsc::channel(16);
+
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_recipe(recipe()),
+            Arc::new(MockProvider::default()),
+            Arc::new(parent.read().await.clone()),
+            notifications,
+        )
+        .await
+        .unwrap();
+        subagent
+            .extensions
+            .as_ref()
+            .unwrap()
+            .write()
+            .await
+            .add_client(
+                "own",
+                Box::new(CountingClient {
+                    calls,
+                    output: String::new(),
+                }),
+            );
+
+        subagent.terminate().await.unwrap();
+        handle.abort();
+
+        let scoped = subagent.extensions.as_ref().unwrap().read().await;
+        assert!(scoped.list_extensions().await.unwrap().is_empty());
+        assert_eq!(*subagent.status.read().await, SubAgentStatus::Terminated);
+    }
+
+    #[tokio::test]
+    async fn test_terminate_stops_a_provider_call_in_flight() {
+        let parent = RwLock::new(ExtensionManager::new());
+        let provider: Arc<dyn Provider> = Arc::new(HangingProvider);
+        let extensions = Arc::new(parent.read().await.clone());
+        let (notifications, _) = mpsc::channel(16);
+
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_recipe(recipe()),
+            Arc::clone(&provider),
+            Arc::clone(&extensions),
+            notifications,
+        )
+        .await
+        .unwrap();
+        let turn = tokio::spawn({
+            let subagent = Arc::clone(&subagent);
+            async move {
+                subagent
+                    .reply_subagent_final("Hang".to_string(), provider, extensions)
+                    .await
+            }
+        });
+        while subagent.get_status().await != SubAgentStatus::Processing {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        subagent.terminate().await.unwrap();
+        let result = tokio::time::timeout(Duration::from_secs(5), turn)
+            .await
+            .expect("the turn should stop once terminated")
+            .unwrap();
+        handle.abort();
+        assert!(result.unwrap_err().to_string().contains("terminated"));
+    }
+
+    #[tokio::test]
+    async fn test_progress_reports_the_accumulated_spend() {
+        let parent = RwLock::new(ExtensionManager::new());
+        // Spend is recorded by the factory's wrapping of the provider
+        let provider = global_pool_manager().wrap(
+            "mock",
+            Arc::new(
+                MockProvider::default()
+                    .with_response(
+                        MockResponse::tool_call("missing__tool", json!({})).with_usage(10, 5),
+                    )
+                    .with_response(MockResponse::text("done").with_usage(20, 7)),
+            ),
+        );
+        let (notifications, _) = mpsc::channel(16);
+
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_recipe(recipe()),
+            provider.clone(),
+            Arc::new(parent.read().await.clone()),
+            notifications,
+        )
+        .await
+        .unwrap();
+        subagent
+            .reply_subagent_final(
+                "Go".to_string(),
+                provider,
+                Arc::new(parent.read().await.clone()),
+            )
+            .await
+            .unwrap();
+        handle.abort();
+
+        let progress = subagent.get_progress().await;
+        let spend = global_pool_manager().usage().spend(&subagent.id);
+        assert_eq!(spend.requests, 2);
+        assert_eq!((progress.input_tokens, progress.output_tokens), (30, 12));
+        assert_eq!(progress.input_tokens, spend.input_tokens);
+        assert_eq!(progress.cost_usd, spend.cost_usd);
+        assert_eq!(progress.tool_calls, 1);
+    }
+
+    #[tokio::test]
+    async fn test_large_tool_results_are_kept_out_of_the_conversation() {
+        let mut parent = ExtensionManager::new();
+        parent.add_client(
+            "parent",
+            Box::new(CountingClient {
+                calls: Arc::new(AtomicUsize::new(0)),
+                output: "x".repeat(300_000),
+            }),
+        );
+        let parent = Arc::new(parent);
+        let provider = Arc::new(
+            MockProvider::default()
+                .with_tool_call("parent__secret", json!({}))
+                .with_text("done"),
+        );
+        let (notifications, _) = mpsc::channel(16);
+
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_instructions("Use the secret tool".to_string()),
+            provider.clone(),
+            Arc::clone(&parent),
+            notifications,
+        )
+        .await
+        .unwrap();
+        subagent
+            .reply_subagent_final("Go".to_string(), provider.clone(), parent)
+            .await
+            .unwrap();
+        handle.abort();
+
+        let conversation = subagent.get_conversation().await;
+        let tool_output = conversation
+            .iter()
+            .flat_map(|message| &message.content)
+            .find_map(|content| match content {
+                MessageContent::ToolResponse(response) => response.tool_result.clone().ok(),
+                _ => None,
+            })
+            .unwrap();
+        let text = tool_output[0].as_text().unwrap();
+        assert!(text.len() < 1_000);
+        assert!(text.contains("stored in the file"));
+        // The provider saw the short reference too
+        let last_request = provider.requests().pop().unwrap();
+        assert!(last_request
+            .messages
+            .iter()
+            .all(|message| serde_json::to_string(message).unwrap().len() < 1_000));
+    }
+
+    fn schema_recipe() -> Recipe {
+        Recipe::builder()
+            .title("Structured")
+            .description("A recipe with a response schema")
+            .instructions("Answer with the final output tool")
+            .response(crate::recipe::Response {
+                json_schema: Some(json!({
+                    "type": "object",
+                    "properties": {"answer": {"type": "string"}},
+                    "required": ["answer"]
+                })),
+            })
+            .build()
+            .unwrap()
+    }
+
+    /// Run one task of a subagent with a response schema against the scripted provider
+    async fn run_structured(provider: MockProvider) -> (Arc<SubAgent>, Message, Arc<MockProvider>) {
+        let parent = RwLock::new(ExtensionManager::new());
+        let provider = Arc::new(provider);
+        let (notifications, _) = mpsc::channel(16);
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_recipe(schema_recipe()),
+            provider.clone(),
+            Arc::new(parent.read().await.clone()),
+            notifications,
+        )
+        .await
+        .unwrap();
+        let reply = subagent
+            .reply_subagent_final(
+                "What is the answer?".to_string(),
+                provider.clone(),
+                Arc::new(parent.read().await.clone()),
+            )
+            .await
+            .unwrap();
+        handle.abort();
+        (subagent, reply, provider)
+    }
+
+    #[tokio::test]
+    async fn test_invalid_output_is_sent_back_for_repair() {
+        let (_, reply, provider) = run_structured(
+            MockProvider::default()
+                .with_tool_call(FINAL_OUTPUT_TOOL_NAME, json!({"answer": 42}))
+                .with_tool_call(FINAL_OUTPUT_TOOL_NAME, json!({"answer": "42"}))
+                .with_text("Done"),
+        )
+        .await;
+        assert_eq!(reply.as_concat_text(), r#"{"answer":"42"}"#);
+        assert_eq!(provider.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_answer_without_the_output_tool_is_repaired() {
+        let (_, reply, provider) = run_structured(
+            MockProvider::default()
+                .with_text("42")
+                .with_tool_call(FINAL_OUTPUT_TOOL_NAME, json!({"answer": "42"}))
+                .with_text("Done"),
+        )
+        .await;
+        assert_eq!(reply.as_concat_text(), r#"{"answer":"42"}"#);
+        let requests = provider.requests();
+        let repair = requests[1].messages.last().unwrap().as_concat_text();
+        assert_eq!(repair, RESPONSE_REPAIR_PROMPT);
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_the_repairs_whatever_failed() {
+        // Text-only replies and failed validations share one budget
+        let (subagent, reply, provider) = run_structured(
+            MockProvider::default()
+                .with_text("No tool")
+                .with_tool_call(FINAL_OUTPUT_TOOL_NAME, json!({}))
+                .with_text("Still no tool"),
+        )
+        .await;
+        assert_eq!(provider.requests().len(), MAX_RESPONSE_REPAIRS + 1);
+        let text = reply.as_concat_text();
+        assert!(text.contains("did not match the recipe's response schema"));
+        assert!(text.contains("Still no tool"));
+        assert_eq!(
+            subagent.get_status().await,
+            SubAgentStatus::Completed("Output did not match the response schema".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_turn_completed_is_sent_for_turns_that_ran() {
+        let parent = RwLock::new(ExtensionManager::new());
+        let provider = Arc::new(MockProvider::default().with_text("done"));
+        let (notifications, _) = mpsc::channel(16);
+        let (events, mut received) = broadcast::channel(16);
+
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_recipe(recipe())
+                .with_max_turns(1)
+                .with_events(events),
+            provider.clone(),
+            Arc::new(parent.read().await.clone()),
+            notifications,
+        )
+        .await
+        .unwrap();
+        for message in ["First", "Past the turn limit"] {
+            let _ = subagent
+                .reply_subagent_final(
+                    message.to_string(),
+                    provider.clone(),
+                    Arc::new(parent.read().await.clone()),
+                )
+                .await;
+        }
+        handle.abort();
+
+        let mut completed = Vec::new();
+        while let Ok(event) = received.try_recv() {
+            if let SubAgentEvent::TurnCompleted { turn, message, .. } = event {
+                completed.push((turn, message));
+            }
+        }
+        assert_eq!(completed, vec![(1, "Completed!".to_string())]);
+    }
+}
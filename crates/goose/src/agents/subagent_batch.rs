@@ -0,0 +1,158 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::Config;
+
+/// Subagents of one batch running at once when `GOOSE_SUBAGENT_MAX_PARALLEL` is unset
+pub const DEFAULT_MAX_PARALLEL: usize = 4;
+
+/// How long the results of a finished batch are kept for collection before they are dropped
+pub const BATCH_RETENTION_MINUTES: i64 = 30;
+
+/// The most subagents of one batch allowed to run at once, from `GOOSE_SUBAGENT_MAX_PARALLEL`.
+/// A batch may ask for fewer but never more.
+pub fn max_parallel_from_config() -> usize {
+    Config::global()
+        .get_param::<usize>("GOOSE_SUBAGENT_MAX_PARALLEL")
+        .unwrap_or(DEFAULT_MAX_PARALLEL)
+        .max(1)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", content = "detail", rename_all = "snake_case")]
+pub enum BatchItemStatus {
+    Pending,
+    Running,
+    Completed(String),
+    Failed(String),
+}
+
+impl BatchItemStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, Self::Completed(_) | Self::Failed(_))
+    }
+}
+
+/// One input of a batch and what became of it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub input: String,
+    pub status: BatchItemStatus,
+}
+
+/// Subagents spawned over a list of inputs with the same recipe or instructions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubAgentBatch {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// When the last input got its result
+    #[serde(default)]
+    pub finished_at: Option<DateTime<Utc>>,
+    pub max_parallel: usize,
+    pub items: Vec<BatchItem>,
+}
+
+impl SubAgentBatch {
+    pub fn new(inputs: Vec<String>, max_parallel: usize) -> Self {
+        Self {
+            id: format!("batch_{}", Uuid::new_v4()),
+            created_at: Utc::now(),
+            finished_at: None,
+            max_parallel,
+            items: inputs
+                .into_iter()
+                .map(|input| BatchItem {
+                    input,
+                    status: BatchItemStatus::Pending,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn set_status(&mut self, index: usize, status: BatchItemStatus) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.status = status;
+        }
+        if self.finished_at.is_none() && self.is_finished() {
+            self.finished_at = Some(Utc::now());
+        }
+    }
+
+    /// Whether the batch finished longer ago than its results are kept
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.finished_at.is_some_and(|finished_at| {
+            now - finished_at > chrono::Duration::minutes(BATCH_RETENTION_MINUTES)
+        })
+    }
+
+    /// Whether every input has a result
+    pub fn is_finished(&self) -> bool {
+        self.items.iter().all(|item| item.status.is_finished())
+    }
+
+    /// Inputs with a result, out of all of them
+    pub fn finished_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| item.status.is_finished())
+            .count()
+    }
+
+    /// Every input with its subagent's final message or error, in input order
+    pub fn results_text(&self) -> String {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let result = match &item.status {
+                    BatchItemStatus::Completed(result) => result.clone(),
+                    BatchItemStatus::Failed(error) => format!("Failed: {}", error),
+                    BatchItemStatus::Pending | BatchItemStatus::Running => {
+                        "Not finished".to_string()
+                    }
+                };
+                format!(
+                    "## Result {}\nInput: {}\n\n{}",
+                    index + 1,
+                    item.input,
+                    result
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_is_finished_once_every_item_has_a_result() {
+        let mut batch = SubAgentBatch::new(vec!["a".to_string(), "b".to_string()], 2);
+        assert!(!batch.is_finished());
+
+        batch.set_status(0, BatchItemStatus::Completed("done".to_string()));
+        batch.set_status(1, BatchItemStatus::Running);
+        assert_eq!(batch.finished_count(), 1);
+        assert!(!batch.is_finished());
+
+        assert!(batch.finished_at.is_none());
+        batch.set_status(1, BatchItemStatus::Failed("boom".to_string()));
+        assert!(batch.is_finished());
+        let finished_at = batch.finished_at.unwrap();
+        assert!(!batch.is_expired(finished_at));
+        assert!(
+            batch.is_expired(finished_at + chrono::Duration::minutes(BATCH_RETENTION_MINUTES + 1))
+        );
+        // Out of range indexes are ignored
+        batch.set_status(5, BatchItemStatus::Running);
+        assert_eq!(batch.items.len(), 2);
+
+        assert_eq!(
+            batch.results_text(),
+            "## Result 1\nInput: a\n\ndone\n\n## Result 2\nInput: b\n\nFailed: boom"
+        );
+    }
+}
@@ -1,19 +1,111 @@
 use anyhow::Result;
 use mcp_core::{Content, ToolError};
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::collections::HashMap;
 use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
 
-use crate::agents::subagent_types::SpawnSubAgentArgs;
+use crate::agents::subagent::SubAgentProgress;
+use crate::agents::subagent_state::subagent_state_dir;
+use crate::agents::subagent_types::{SpawnSubAgentArgs, ToolPolicy};
+use crate::agents::tool_execution::ToolCallResult;
 use crate::agents::Agent;
+use crate::providers::rate_limit::Priority;
+
+/// URI of the JSON progress payload in subagent status results
+pub const SUBAGENT_STATUS_URI: &str = "goose://subagents/status";
+
+/// Key of the system prompt note about restored subagents
+const RESTORED_SUBAGENTS_PROMPT_KEY: &str = "restored_subagents";
 
 impl Agent {
-    /// Handle running a complete subagent task (replaces the individual spawn/send/check tools)
-    pub async fn handle_run_subagent_task(
-        &self,
-        arguments: Value,
-    ) -> Result<Vec<Content>, ToolError> {
+    /// Progress of every subagent this agent is running
+    pub async fn subagent_progress(&self) -> HashMap<String, SubAgentProgress> {
+        match self.subagent_manager.lock().await.as_ref() {
+            Some(manager) => manager.get_subagent_progress().await,
+            None => HashMap::new(),
+        }
+    }
+
+    /// Stop a subagent, e.g. one an operator found stuck
+    pub async fn terminate_subagent(&self, id: &str) -> Result<()> {
         let subagent_manager = self.subagent_manager.lock().await;
-        let manager = subagent_manager.as_ref().ok_or_else(|| {
+        let manager = subagent_manager
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Subagent manager not initialized"))?;
+        manager.terminate_subagent(id).await
+    }
+
+    /// Bring back the subagents a session had running before goose restarted, suspended until
+    /// they're sent a message. The reply that restores them tells the model which ones it can
+    /// continue with `subagent__send_message`; the note is dropped again on the next reply.
+    pub async fn restore_subagents(&self, session_id: &str) -> Result<Vec<String>> {
+        let Some(manager) = self.subagent_manager.lock().await.clone() else {
+            return Ok(Vec::new());
+        };
+        let restored = if manager.is_restored(session_id).await {
+            Vec::new()
+        } else {
+            let provider = self.provider().await?;
+            let extension_manager = Arc::new(self.extension_manager.read().await.clone());
+            manager
+                .restore_all(
+                    session_id,
+                    &subagent_state_dir(session_id)?,
+                    provider,
+                    extension_manager,
+                )
+                .await?
+        };
+
+        let note = (!restored.is_empty()).then(|| {
+            format!(
+                "These subagents were interrupted by a restart and are suspended: {}. \
+                 Continue any of them with subagent__send_message; they remember their \
+                 conversation so far.",
+                restored.join(", ")
+            )
+        });
+        self.set_system_prompt_extra(RESTORED_SUBAGENTS_PROMPT_KEY, note)
+            .await;
+        Ok(restored)
+    }
+
+    /// Run a subagent on its own in the background, e.g. for an external trigger, returning its
+    /// ID. The agent is notified with the result when the subagent finishes. Without a session
+    /// of its own in `args`, it counts against the current session's spawn rate.
+    pub async fn run_subagent_in_background(&self, args: SpawnSubAgentArgs) -> Result<String> {
+        let args = match args.session_id {
+            Some(_) => args,
+            None => {
+                let session_id = self.session_resources.lock().await.session_id().to_string();
+                args.with_session_id(session_id)
+            }
+        };
+        let manager = self
+            .subagent_manager
+            .lock()
+            .await
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("Subagent manager not initialized"))?;
+        let provider = self.provider().await?;
+        manager
+            .run_to_completion(args, provider, Arc::clone(&self.extension_manager))
+            .await
+    }
+
+    /// Handle running a complete subagent task (replaces the individual spawn/send/check tools).
+    /// The subagent's messages are streamed as notifications while the task runs.
+    pub async fn handle_run_subagent_task(&self, arguments: Value) -> ToolCallResult {
+        match self.start_subagent_task(arguments).await {
+            Ok(result) => result,
+            Err(e) => ToolCallResult::from(Err(e)),
+        }
+    }
+
+    async fn start_subagent_task(&self, arguments: Value) -> Result<ToolCallResult, ToolError> {
+        let manager = self.subagent_manager.lock().await.clone().ok_or_else(|| {
             ToolError::ExecutionError("Subagent manager not initialized".to_string())
         })?;
 
@@ -23,7 +115,362 @@ impl Agent {
             .and_then(|v| v.as_str())
             .ok_or_else(|| ToolError::ExecutionError("Missing task parameter".to_string()))?
             .to_string();
+        let mut args = self.subagent_args(&arguments, message).await?;
+
+        let deduplicate = arguments
+            .get("deduplicate")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        args = args.with_deduplication(deduplicate);
+
+        // Get the provider from the parent agent
+        let provider = self
+            .provider()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to get provider: {}", e)))?;
+
+        // Get the extension manager from the parent agent
+        let extension_manager = Arc::clone(&self.extension_manager);
+
+        // Run the complete subagent task, forwarding its messages while it runs
+        let (updates_tx, updates_rx) = mpsc::channel(32);
+        let result = async move {
+            let extension_manager = Arc::new(extension_manager.read().await.clone());
+            match manager
+                .run_complete_subagent_task(args, provider, extension_manager, Some(updates_tx))
+                .await
+            {
+                Ok(result) => Ok(vec![Content::text(result)]),
+                Err(e) => Err(ToolError::ExecutionError(format!(
+                    "Failed to run subagent task: {}",
+                    e
+                ))),
+            }
+        };
+
+        Ok(ToolCallResult {
+            result: Box::new(Box::pin(result)),
+            notification_stream: Some(Box::new(ReceiverStream::new(updates_rx))),
+        })
+    }
+
+    /// Handle spawning one subagent per input, returning the batch ID to collect results with
+    pub async fn handle_spawn_parallel_subagents(
+        &self,
+        arguments: Value,
+    ) -> Result<Vec<Content>, ToolError> {
+        let manager = self.subagent_manager.lock().await.clone().ok_or_else(|| {
+            ToolError::ExecutionError("Subagent manager not initialized".to_string())
+        })?;
+
+        let inputs: Vec<String> = arguments
+            .get("inputs")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| ToolError::ExecutionError("Missing inputs parameter".to_string()))?
+            .iter()
+            .map(|input| match input.as_str() {
+                Some(text) => text.to_string(),
+                None => input.to_string(),
+            })
+            .collect();
+        let max_parallel = arguments
+            .get("max_parallel")
+            .and_then(|v| v.as_u64())
+            .map(|n| n as usize);
+        // Inputs of a batch are background work unless asked otherwise
+        let mut template = self.subagent_args(&arguments, String::new()).await?;
+        if arguments.get("priority").is_none() {
+            template = template.with_priority(Priority::Batch);
+        }
+
+        let provider = self
+            .provider()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to get provider: {}", e)))?;
+
+        let count = inputs.len();
+        let batch_id = manager
+            .spawn_many(
+                template,
+                inputs,
+                max_parallel,
+                provider,
+                Arc::clone(&self.extension_manager),
+            )
+            .await
+            .map_err(|e| {
+                ToolError::ExecutionError(format!("Failed to spawn parallel subagents: {}", e))
+            })?;
+
+        Ok(vec![Content::text(format!(
+            "Started batch {} with {} subagents running in the background.",
+            batch_id, count
+        ))])
+    }
+
+    /// Handle sending a message to a live or suspended subagent. The reply takes a whole turn,
+    /// so it is awaited in the tool's future.
+    pub async fn handle_send_subagent_message(&self, arguments: Value) -> ToolCallResult {
+        match self.start_send_subagent_message(arguments).await {
+            Ok(result) => result,
+            Err(e) => ToolCallResult::from(Err(e)),
+        }
+    }
+
+    async fn start_send_subagent_message(
+        &self,
+        arguments: Value,
+    ) -> Result<ToolCallResult, ToolError> {
+        let manager = self.subagent_manager.lock().await.clone().ok_or_else(|| {
+            ToolError::ExecutionError("Subagent manager not initialized".to_string())
+        })?;
+        let subagent_id = arguments
+            .get("subagent_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::ExecutionError("Missing subagent_id parameter".to_string()))?
+            .to_string();
+        let message = arguments
+            .get("message")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::ExecutionError("Missing message parameter".to_string()))?
+            .to_string();
+        let provider = self
+            .provider()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to get provider: {}", e)))?;
+        let extension_manager = Arc::clone(&self.extension_manager);
+
+        let result = async move {
+            let extension_manager = Arc::new(extension_manager.read().await.clone());
+            manager
+                .send_message_to_subagent(&subagent_id, message, provider, extension_manager)
+                .await
+                .map(|reply| vec![Content::text(reply)])
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))
+        };
+        Ok(ToolCallResult {
+            result: Box::new(Box::pin(result)),
+            notification_stream: None,
+        })
+    }
+
+    /// Handle relaying one subagent's output to another. The receiver's reply takes a whole
+    /// turn, so it is awaited in the tool's future.
+    pub async fn handle_relay_subagent_message(&self, arguments: Value) -> ToolCallResult {
+        match self.start_relay_subagent_message(arguments).await {
+            Ok(result) => result,
+            Err(e) => ToolCallResult::from(Err(e)),
+        }
+    }
+
+    async fn start_relay_subagent_message(
+        &self,
+        arguments: Value,
+    ) -> Result<ToolCallResult, ToolError> {
+        let manager = self.subagent_manager.lock().await.clone().ok_or_else(|| {
+            ToolError::ExecutionError("Subagent manager not initialized".to_string())
+        })?;
+        let id = |name: &str| {
+            arguments
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(|s| s.to_string())
+                .ok_or_else(|| ToolError::ExecutionError(format!("Missing {} parameter", name)))
+        };
+        let from_id = id("from_subagent_id")?;
+        let to_id = id("to_subagent_id")?;
+        let instructions = arguments
+            .get("instructions")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let provider = self
+            .provider()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to get provider: {}", e)))?;
+        let extension_manager = Arc::clone(&self.extension_manager);
+
+        let result = async move {
+            let extension_manager = Arc::new(extension_manager.read().await.clone());
+            manager
+                .relay_message(&from_id, &to_id, instructions, provider, extension_manager)
+                .await
+                .map(|reply| vec![Content::text(reply)])
+                .map_err(|e| ToolError::ExecutionError(e.to_string()))
+        };
+        Ok(ToolCallResult {
+            result: Box::new(Box::pin(result)),
+            notification_stream: None,
+        })
+    }
+
+    /// Handle handing a subagent's work over to a new subagent from another recipe. The
+    /// transcript is summarized in the tool's future, so the caller isn't held up meanwhile.
+    pub async fn handle_subagent_handoff(&self, arguments: Value) -> ToolCallResult {
+        match self.start_subagent_handoff(arguments).await {
+            Ok(result) => result,
+            Err(e) => ToolCallResult::from(Err(e)),
+        }
+    }
+
+    async fn start_subagent_handoff(&self, arguments: Value) -> Result<ToolCallResult, ToolError> {
+        let manager = self.subagent_manager.lock().await.clone().ok_or_else(|| {
+            ToolError::ExecutionError("Subagent manager not initialized".to_string())
+        })?;
+        let from_id = arguments
+            .get("subagent_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::ExecutionError("Missing subagent_id parameter".to_string()))?
+            .to_string();
+        if arguments
+            .get("recipe_name")
+            .and_then(|v| v.as_str())
+            .is_none()
+        {
+            return Err(ToolError::ExecutionError(
+                "Missing recipe_name parameter".to_string(),
+            ));
+        }
+        let task = arguments
+            .get("task")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string();
+        let args = self.subagent_args(&arguments, task).await?;
+        let provider = self
+            .provider()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to get provider: {}", e)))?;
+        let extension_manager = Arc::clone(&self.extension_manager);
+
+        let result = async move {
+            manager
+                .handoff(&from_id, args, provider, extension_manager)
+                .await
+                .map(|to_id| {
+                    vec![Content::text(format!(
+                        "Handed subagent {} over to subagent {}, which is working in the \
+                         background. Subagent {} was terminated.",
+                        from_id, to_id, from_id
+                    ))]
+                })
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to hand off: {}", e)))
+        };
+        Ok(ToolCallResult {
+            result: Box::new(Box::pin(result)),
+            notification_stream: None,
+        })
+    }
+
+    /// Handle waiting for a batch and collecting its results, optionally reduced to one
+    /// answer. Waiting can take as long as the batch runs, so it happens in the tool's future.
+    pub async fn handle_collect_subagent_results(&self, arguments: Value) -> ToolCallResult {
+        match self.start_collect_results(arguments).await {
+            Ok(result) => result,
+            Err(e) => ToolCallResult::from(Err(e)),
+        }
+    }
+
+    async fn start_collect_results(&self, arguments: Value) -> Result<ToolCallResult, ToolError> {
+        let manager = self.subagent_manager.lock().await.clone().ok_or_else(|| {
+            ToolError::ExecutionError("Subagent manager not initialized".to_string())
+        })?;
+        let batch_id = arguments
+            .get("batch_id")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| ToolError::ExecutionError("Missing batch_id parameter".to_string()))?
+            .to_string();
+        let reducer_prompt = arguments
+            .get("reducer_prompt")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+        let provider = self
+            .provider()
+            .await
+            .map_err(|e| ToolError::ExecutionError(format!("Failed to get provider: {}", e)))?;
 
+        let result = async move {
+            manager
+                .collect_results(&batch_id, reducer_prompt.as_deref(), provider)
+                .await
+                .map(|result| vec![Content::text(result)])
+                .map_err(|e| ToolError::ExecutionError(format!("Failed to collect results: {}", e)))
+        };
+        Ok(ToolCallResult {
+            result: Box::new(Box::pin(result)),
+            notification_stream: None,
+        })
+    }
+
+    /// Handle reporting the status and resource use of one subagent or all of them, as a
+    /// summary to read and the serialized progress for code that consumes tool results
+    pub async fn handle_get_subagent_status(&self, arguments: Value) -> ToolCallResult {
+        let mut progress: Vec<SubAgentProgress> =
+            self.subagent_progress().await.into_values().collect();
+        if let Some(id) = arguments.get("subagent_id").and_then(|v| v.as_str()) {
+            progress.retain(|p| p.subagent_id == id);
+            if progress.is_empty() {
+                return ToolCallResult::from(Err(ToolError::ExecutionError(format!(
+                    "Subagent {} not found",
+                    id
+                ))));
+            }
+        }
+        progress.sort_by(|a, b| a.subagent_id.cmp(&b.subagent_id));
+        let total_cost: f64 = progress.iter().map(|p| p.cost_usd).sum();
+        let structured = match Content::json(
+            SUBAGENT_STATUS_URI,
+            &json!({ "subagents": progress, "total_cost_usd": total_cost }),
+        ) {
+            Ok(content) => content,
+            Err(e) => {
+                return ToolCallResult::from(Err(ToolError::ExecutionError(format!(
+                    "Failed to serialize subagent status: {}",
+                    e
+                ))))
+            }
+        };
+        if progress.is_empty() {
+            return ToolCallResult::from(Ok(vec![
+                Content::text("No subagents are running."),
+                structured,
+            ]));
+        }
+
+        let mut text = String::new();
+        for p in &progress {
+            let turns = match p.max_turns {
+                Some(max_turns) => format!("{}/{}", p.turn, max_turns),
+                None => p.turn.to_string(),
+            };
+            text.push_str(&format!(
+                "- {}: {:?}, {}\n  Turns: {}, Tool calls: {}, Running: {}s\n  \
+                 Tokens: {} in / {} out, Estimated cost: ${:.4}\n",
+                p.subagent_id,
+                p.status,
+                p.message,
+                turns,
+                p.tool_calls,
+                p.duration_secs,
+                p.input_tokens,
+                p.output_tokens,
+                p.cost_usd
+            ));
+        }
+        text.push_str(&format!(
+            "Total estimated cost: ${:.4} across {} subagent(s)",
+            total_cost,
+            progress.len()
+        ));
+        ToolCallResult::from(Ok(vec![Content::text(text), structured]))
+    }
+
+    /// Subagent arguments shared by the subagent tools: the recipe or instructions, turn and
+    /// time limits, and what is inherited from this session
+    async fn subagent_args(
+        &self,
+        arguments: &Value,
+        message: String,
+    ) -> Result<SpawnSubAgentArgs, ToolError> {
         // Either recipe_name or instructions must be provided
         let recipe_name = arguments
             .get("recipe_name")
@@ -35,9 +482,9 @@ impl Agent {
             .map(|s| s.to_string());
 
         let mut args = if let Some(recipe_name) = recipe_name {
-            SpawnSubAgentArgs::new_with_recipe(recipe_name, message.clone())
+            SpawnSubAgentArgs::new_with_recipe(recipe_name, message)
         } else if let Some(instructions) = instructions {
-            SpawnSubAgentArgs::new_with_instructions(instructions, message.clone())
+            SpawnSubAgentArgs::new_with_instructions(instructions, message)
         } else {
             return Err(ToolError::ExecutionError(
                 "Either recipe_name or instructions parameter must be provided".to_string(),
@@ -55,25 +502,31 @@ impl Agent {
             args = args.with_timeout(timeout);
         }
 
-        // Get the provider from the parent agent
-        let provider = self
-            .provider()
-            .await
-            .map_err(|e| ToolError::ExecutionError(format!("Failed to get provider: {}", e)))?;
+        if let Some(priority) = arguments.get("priority") {
+            let priority = serde_json::from_value(priority.clone()).map_err(|_| {
+                ToolError::InvalidParameters(
+                    "priority must be one of batch, normal or interactive".to_string(),
+                )
+            })?;
+            args = args.with_priority(priority);
+        }
 
-        // Get the extension manager from the parent agent
-        let extension_manager = Arc::new(self.extension_manager.read().await);
+        let patterns = |key: &str| -> Result<Vec<String>, ToolError> {
+            match arguments.get(key) {
+                Some(value) => serde_json::from_value(value.clone()).map_err(|_| {
+                    ToolError::InvalidParameters(format!("{} must be a list of tool names", key))
+                }),
+                None => Ok(Vec::new()),
+            }
+        };
+        args = args.with_tool_policy(ToolPolicy {
+            allow: patterns("allowed_tools")?,
+            deny: patterns("denied_tools")?,
+            ..Default::default()
+        });
 
-        // Run the complete subagent task
-        match manager
-            .run_complete_subagent_task(args, provider, extension_manager)
-            .await
-        {
-            Ok(result) => Ok(vec![Content::text(result)]),
-            Err(e) => Err(ToolError::ExecutionError(format!(
-                "Failed to run subagent task: {}",
-                e
-            ))),
-        }
+        // Subagents run at most at the priority of the session that spawned them
+        args = args.with_session_priority(*self.priority.lock().await);
+        Ok(args.with_session_id(self.session_resources.lock().await.session_id()))
     }
 }
@@ -1,22 +1,82 @@
-use std::collections::HashMap;
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use anyhow::{anyhow, Result};
+use chrono::Utc;
+use futures::StreamExt;
 use mcp_core::protocol::JsonRpcMessage;
-use tokio::sync::{mpsc, Mutex, RwLock};
+use mcp_core::role::Role;
+use tokio::sync::{broadcast, mpsc, Mutex, Notify, RwLock, Semaphore};
 use tracing::{debug, error, instrument, warn};
 
+use crate::agents::confidence;
 use crate::agents::extension_manager::ExtensionManager;
+use crate::agents::session_resources::SessionResources;
 use crate::agents::subagent::{SubAgent, SubAgentConfig, SubAgentProgress, SubAgentStatus};
-use crate::agents::subagent_types::SpawnSubAgentArgs;
+use crate::agents::subagent_batch::{max_parallel_from_config, BatchItemStatus, SubAgentBatch};
+use crate::agents::subagent_pool::{SubAgentWarmPool, WarmPoolConfig, WarmSubAgent};
+use crate::agents::subagent_scheduler::ProcessingSlots;
+use crate::agents::subagent_spawn_limit::SpawnRateLimiter;
+use crate::agents::subagent_state::{subagent_state_dir, SubAgentSnapshot};
+use crate::agents::subagent_types::{SpawnSubAgentArgs, SubAgentEvent, SubAgentLink};
+use crate::message::Message;
 use crate::providers::base::Provider;
+use crate::providers::pool::global_pool_manager;
+use crate::providers::rate_limit::Priority;
 use crate::recipe::Recipe;
 
+/// Subagent events kept for subscribers that haven't caught up yet
+pub const EVENT_CAPACITY: usize = 256;
+
+/// How many verification runs may nest, so recipes that verify each other (A to B to A) stop
+const MAX_VERIFICATION_DEPTH: usize = 2;
+
+/// Relays kept for [`SubAgentManager::get_links`]; the oldest are dropped past this
+const MAX_LINKS: usize = 500;
+
+/// System prompt for combining the results of a batch into one answer
+const BATCH_REDUCER_PROMPT: &str = "You combine the results of several subagents that worked on \
+    parts of the same job into a single answer. Follow the instructions given with the results, \
+    note results that disagree and mention inputs that failed.";
+
+/// System prompt for summarizing a subagent's transcript for the subagent taking over from it
+const HANDOFF_SUMMARY_PROMPT: &str = "You summarize a subagent's conversation for the subagent \
+    taking over its work in the next phase. Keep the task, what was found, decided and done, \
+    what is still open, and the exact file paths, commands and identifiers involved. Leave out \
+    pleasantries and dead ends that don't matter anymore.";
+
+/// Longest piece of a transcript, in characters, sent in one request when summarizing it for
+/// a handoff
+const HANDOFF_CHUNK_CHARS: usize = 100_000;
+
 /// Manages the lifecycle of subagents
 pub struct SubAgentManager {
     subagents: Arc<RwLock<HashMap<String, Arc<SubAgent>>>>,
-    handles: Arc<Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
+    /// Background tasks of each subagent, aborted when it is terminated
+    handles: Arc<Mutex<HashMap<String, Vec<tokio::task::JoinHandle<()>>>>>,
+    /// Fingerprints of deduplicated non-interactive tasks that are currently running, mapped to the subagent ID
+    running_tasks: Arc<Mutex<HashMap<String, String>>>,
+    /// Pre-initialized subagents that interactive spawns of a registered recipe can claim
+    warm_pool: Arc<Mutex<SubAgentWarmPool>>,
+    /// Spawns per session per minute
+    spawn_limiter: SpawnRateLimiter,
+    /// Batches of subagents spawned over a list of inputs, by batch ID
+    batches: Arc<Mutex<HashMap<String, SubAgentBatch>>>,
+    /// The task running each batch, aborted when the manager terminates its subagents
+    batch_tasks: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    /// Woken whenever an item of any batch changes status
+    batch_updates: Arc<Notify>,
+    /// Shared by every subagent's turns to cap how many process at once, handed out by priority
+    processing_slots: Arc<ProcessingSlots>,
+    /// Outputs relayed from one subagent to another, oldest first, at most [`MAX_LINKS`]
+    links: Arc<Mutex<VecDeque<SubAgentLink>>>,
+    /// Sessions whose snapshots were already restored into this manager
+    restored_sessions: Mutex<HashSet<String>>,
+    /// Where artifacts registered by finished subagents are stored
+    artifact_store: Option<Arc<Mutex<SessionResources>>>,
+    /// What every subagent does, as it happens; see [`Self::subscribe`]
+    events: broadcast::Sender<SubAgentEvent>,
     mcp_notification_tx: mpsc::Sender<JsonRpcMessage>,
 }
 
@@ -26,20 +86,132 @@ impl SubAgentManager {
         Self {
             subagents: Arc::new(RwLock::new(HashMap::new())),
             handles: Arc::new(Mutex::new(HashMap::new())),
+            running_tasks: Arc::new(Mutex::new(HashMap::new())),
+            warm_pool: Arc::new(Mutex::new(SubAgentWarmPool::default())),
+            spawn_limiter: SpawnRateLimiter::from_config(),
+            batches: Arc::new(Mutex::new(HashMap::new())),
+            batch_tasks: Mutex::new(HashMap::new()),
+            batch_updates: Arc::new(Notify::new()),
+            processing_slots: ProcessingSlots::from_config(),
+            links: Arc::new(Mutex::new(VecDeque::new())),
+            restored_sessions: Mutex::new(HashSet::new()),
+            artifact_store: None,
+            events: broadcast::channel(EVENT_CAPACITY).0,
             mcp_notification_tx,
         }
     }
 
-    /// Spawn a new interactive subagent
+    /// Broadcast the subagents' events on `events` instead of a channel of the manager's own,
+    /// e.g. one that outlives the manager
+    pub fn with_events(mut self, events: broadcast::Sender<SubAgentEvent>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// Follow what the subagents do as it happens: spawns, turns, tool calls, completions and
+    /// terminations. A subscriber that falls more than [`EVENT_CAPACITY`] events behind misses
+    /// the oldest ones and is told so by a `Lagged` error.
+    pub fn subscribe(&self) -> broadcast::Receiver<SubAgentEvent> {
+        self.events.subscribe()
+    }
+
+    /// Store the artifacts of finished subagents with the session's resources
+    pub fn with_artifact_store(mut self, store: Arc<Mutex<SessionResources>>) -> Self {
+        self.artifact_store = Some(store);
+        self
+    }
+
+    /// Copy a subagent's registered artifacts into the artifact store as
+    /// `<subagent id>/<name>`, returning where each can be read. Without a store only the
+    /// names are returned.
+    async fn collect_artifacts(
+        store: Option<&Mutex<SessionResources>>,
+        subagent: &SubAgent,
+    ) -> Vec<String> {
+        let artifacts = subagent.get_artifacts().await;
+        let Some(store) = store else {
+            return artifacts.into_keys().collect();
+        };
+        let mut store = store.lock().await;
+        artifacts
+            .into_iter()
+            .map(|(name, artifact)| {
+                let name = format!("{}/{}", subagent.id, name);
+                store.add_artifact(&name, artifact);
+                store.artifact_uri(&name)
+            })
+            .collect()
+    }
+
+    /// Where a session's subagents save their snapshots, so they outlive this process
+    fn state_dir(session_id: Option<&str>) -> Option<PathBuf> {
+        let session_id = session_id?;
+        subagent_state_dir(session_id)
+            .map_err(|e| warn!("No state directory for subagents of {}: {}", session_id, e))
+            .ok()
+    }
+
+    /// Count a spawn against the spawning session's per-minute limit
+    fn check_spawn_rate(&self, session_id: Option<&str>) -> Result<()> {
+        let session_id = session_id.unwrap_or_default();
+        self.spawn_limiter.check(session_id).map_err(|limited| {
+            warn!(
+                "Refused subagent spawn for session {}: {}",
+                session_id, limited
+            );
+            anyhow::Error::new(limited)
+        })
+    }
+
+    /// Spawn a new interactive subagent and return its ID without waiting for it to reply. When
+    /// `args.message` is set the first turn runs in the background; follow it with
+    /// [`Self::get_subagent_progress`].
     #[instrument(skip(self, args, provider, extension_manager))]
     pub async fn spawn_interactive_subagent(
         &self,
         args: SpawnSubAgentArgs,
         provider: Arc<dyn Provider>,
-        extension_manager: Arc<tokio::sync::RwLockReadGuard<'_, ExtensionManager>>,
+        extension_manager: Arc<RwLock<ExtensionManager>>,
     ) -> Result<String> {
         debug!("Spawning interactive subagent");
+        self.check_spawn_rate(args.session_id.as_deref())?;
 
+        let first_message = args.message.clone();
+        let subagent_id = self
+            .create_interactive_subagent(
+                args,
+                Arc::clone(&provider),
+                Arc::new(extension_manager.read().await.clone()),
+            )
+            .await?;
+        if !first_message.is_empty() {
+            self.start_first_turn(&subagent_id, first_message, provider, extension_manager)
+                .await;
+        }
+        Ok(subagent_id)
+    }
+
+    async fn create_interactive_subagent(
+        &self,
+        args: SpawnSubAgentArgs,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+    ) -> Result<String> {
+        // Warm subagents are created with the recipe defaults at interactive priority, so only
+        // claim one when the caller does not customize the turn or time limits
+        if let Some(recipe_name) = &args.recipe_name {
+            if args.max_turns.is_none()
+                && args.timeout_seconds.is_none()
+                && args.priority() == Priority::Interactive
+            {
+                if let Some(subagent_id) = self.claim_warm_subagent(recipe_name).await {
+                    debug!("Claimed warm subagent {} for {}", subagent_id, recipe_name);
+                    return Ok(subagent_id);
+                }
+            }
+        }
+
+        let priority = args.priority();
         // Create subagent config based on whether we have a recipe or instructions
         let mut config = if let Some(recipe_name) = args.recipe_name {
             debug!("Using recipe: {}", recipe_name);
@@ -61,6 +233,14 @@ impl SubAgentManager {
         if let Some(timeout) = args.timeout_seconds {
             config = config.with_timeout(timeout);
         }
+        config = config
+            .with_priority(priority)
+            .with_processing_slots(Arc::clone(&self.processing_slots))
+            .with_events(self.events.clone())
+            .restrict_tools(args.tool_policy);
+        if let Some(state_dir) = Self::state_dir(args.session_id.as_deref()) {
+            config = config.with_state_dir(state_dir);
+        }
 
         // Create the subagent with the parent agent's provider
         let (subagent, handle) = SubAgent::new(
@@ -77,15 +257,337 @@ impl SubAgentManager {
             let mut subagents = self.subagents.write().await;
             subagents.insert(subagent_id.clone(), Arc::clone(&subagent));
         }
+        self.track_handle(&subagent_id, handle).await;
+
+        Ok(subagent_id)
+    }
+
+    /// Whether a session's subagents were already restored, see [`Self::restore_all`]
+    pub async fn is_restored(&self, session_id: &str) -> bool {
+        self.restored_sessions.lock().await.contains(session_id)
+    }
+
+    /// Bring back the subagents of a session saved in `state_dir` that were still active when
+    /// the process stopped. They come back `Suspended` with their conversation, turn count and
+    /// artifacts, and continue once the parent sends them a message. Finished and terminated
+    /// subagents stay on disk only. Each session is restored once; returns the IDs restored.
+    pub async fn restore_all(
+        &self,
+        session_id: &str,
+        state_dir: &Path,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+    ) -> Result<Vec<String>> {
+        if !self
+            .restored_sessions
+            .lock()
+            .await
+            .insert(session_id.to_string())
         {
-            let mut handles = self.handles.lock().await;
-            handles.insert(subagent_id.clone(), handle);
+            return Ok(Vec::new());
+        }
+
+        let mut restored = Vec::new();
+        for snapshot in SubAgentSnapshot::list(state_dir).await? {
+            if matches!(
+                snapshot.status,
+                SubAgentStatus::Completed(_) | SubAgentStatus::Terminated
+            ) || self.has_subagent(&snapshot.id).await
+            {
+                continue;
+            }
+
+            // Everything but the slots and state directory comes from the snapshot
+            let config = SubAgentConfig::new_with_instructions(String::new())
+                .with_processing_slots(Arc::clone(&self.processing_slots))
+                .with_events(self.events.clone())
+                .with_state_dir(state_dir.to_path_buf());
+            let id = snapshot.id.clone();
+            let (subagent, handle) = match SubAgent::restore(
+                snapshot,
+                config,
+                Arc::clone(&provider),
+                Arc::clone(&extension_manager),
+                self.mcp_notification_tx.clone(),
+            )
+            .await
+            {
+                Ok(restored) => restored,
+                Err(e) => {
+                    warn!("Failed to restore subagent {}: {}", id, e);
+                    continue;
+                }
+            };
+            subagent.suspend().await;
+
+            self.subagents
+                .write()
+                .await
+                .insert(id.clone(), Arc::clone(&subagent));
+            self.track_handle(&id, handle).await;
+            restored.push(id);
+        }
+
+        if !restored.is_empty() {
+            debug!(
+                "Restored {} suspended subagents from {:?}",
+                restored.len(),
+                state_dir
+            );
+        }
+        Ok(restored)
+    }
+
+    /// Run a subagent's first turn on a background task, so spawning doesn't wait for the
+    /// reply. Failures end up in the subagent's status.
+    async fn start_first_turn(
+        &self,
+        subagent_id: &str,
+        message: String,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<RwLock<ExtensionManager>>,
+    ) {
+        let Some(subagent) = self.get_subagent(subagent_id).await else {
+            return;
+        };
+        let handle = tokio::spawn(async move {
+            let extension_manager = Arc::new(extension_manager.read().await.clone());
+            if let Err(e) = subagent
+                .reply_subagent_final(message, provider, extension_manager)
+                .await
+            {
+                debug!("First turn of subagent {} failed: {}", subagent.id, e);
+            }
+        });
+        self.track_handle(subagent_id, handle).await;
+    }
+
+    /// Spawn a subagent that works on `args.message` on its own until it is done and return its
+    /// ID right away. When it finishes the parent receives a `task_completed` notification with
+    /// the final message and a summary, instead of having to poll its status.
+    #[instrument(skip(self, args, provider, extension_manager))]
+    pub async fn run_to_completion(
+        &self,
+        args: SpawnSubAgentArgs,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<RwLock<ExtensionManager>>,
+    ) -> Result<String> {
+        debug!("Running subagent to completion in the background");
+        if args.message.is_empty() {
+            return Err(anyhow!(
+                "A task is required to run a subagent to completion"
+            ));
         }
+        self.check_spawn_rate(args.session_id.as_deref())?;
+
+        let task = args.message.clone();
+        let artifact_store = self.artifact_store.clone();
+        let events = self.events.clone();
+        let subagent_id = self
+            .create_interactive_subagent(
+                args,
+                Arc::clone(&provider),
+                Arc::new(extension_manager.read().await.clone()),
+            )
+            .await?;
+        let subagent = self
+            .get_subagent(&subagent_id)
+            .await
+            .ok_or_else(|| anyhow!("Subagent {} not found", subagent_id))?;
+
+        let handle = tokio::spawn(async move {
+            let result = {
+                let extension_manager = Arc::new(extension_manager.read().await.clone());
+                subagent
+                    .reply_subagent_final(task.clone(), Arc::clone(&provider), extension_manager)
+                    .await
+            };
+            if let Ok(message) = &result {
+                let output = message.as_concat_text();
+                subagent.render_report(&output).await;
+                subagent.assess_confidence(&task, &output, provider).await;
+            }
+            let artifacts = Self::collect_artifacts(artifact_store.as_deref(), &subagent).await;
+            subagent.notify_completion(&result, &artifacts).await;
+            subagent.discard_snapshot().await;
+            let _ = events.send(SubAgentEvent::Completed {
+                subagent_id: subagent.id.clone(),
+                final_message: match &result {
+                    Ok(message) => message.as_concat_text(),
+                    Err(e) => format!("Error: {}", e),
+                },
+            });
+        });
+        self.track_handle(&subagent_id, handle).await;
 
-        // Return immediately - no initial message processing
         Ok(subagent_id)
     }
 
+    /// Spawn one subagent per input, all with the recipe or instructions of `template`, running
+    /// at most `max_parallel` at once. The cap from `GOOSE_SUBAGENT_MAX_PARALLEL` applies when
+    /// none is given and bounds the one given. The whole batch counts as one spawn against the
+    /// session's rate limit. Returns the batch ID right away; follow the results with
+    /// [`Self::get_batch`].
+    #[instrument(skip(self, template, inputs, provider, extension_manager))]
+    pub async fn spawn_many(
+        self: &Arc<Self>,
+        mut template: SpawnSubAgentArgs,
+        inputs: Vec<String>,
+        max_parallel: Option<usize>,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<RwLock<ExtensionManager>>,
+    ) -> Result<String> {
+        if inputs.is_empty() {
+            return Err(anyhow!("At least one input is required"));
+        }
+        if template.recipe_name.is_none() && template.instructions.is_none() {
+            return Err(anyhow!(
+                "Either recipe_name or instructions must be provided"
+            ));
+        }
+
+        self.check_spawn_rate(template.session_id.as_deref())?;
+        template.spawn_counted = true;
+        self.prune_batches().await;
+
+        let cap = max_parallel_from_config();
+        let max_parallel = max_parallel.map_or(cap, |requested| requested.clamp(1, cap));
+        let batch = SubAgentBatch::new(inputs.clone(), max_parallel);
+        let batch_id = batch.id.clone();
+        self.batches.lock().await.insert(batch_id.clone(), batch);
+        debug!(
+            "Spawning batch {} of {} subagents, {} at a time",
+            batch_id,
+            inputs.len(),
+            max_parallel
+        );
+
+        let manager = Arc::clone(self);
+        let id = batch_id.clone();
+        let task = tokio::spawn(async move {
+            let permits = Arc::new(Semaphore::new(max_parallel));
+            let runs = inputs.into_iter().enumerate().map(|(index, input)| {
+                let manager = Arc::clone(&manager);
+                let permits = Arc::clone(&permits);
+                let provider = Arc::clone(&provider);
+                let extension_manager = Arc::clone(&extension_manager);
+                let id = id.clone();
+                let mut args = template.clone();
+                args.message = input;
+                async move {
+                    let Ok(_permit) = permits.acquire().await else {
+                        return;
+                    };
+                    manager
+                        .set_batch_status(&id, index, BatchItemStatus::Running)
+                        .await;
+                    let extension_manager = Arc::new(extension_manager.read().await.clone());
+                    let status = match manager
+                        .run_complete_subagent_task(args, provider, extension_manager, None)
+                        .await
+                    {
+                        Ok(result) => BatchItemStatus::Completed(result),
+                        Err(e) => BatchItemStatus::Failed(e.to_string()),
+                    };
+                    manager.set_batch_status(&id, index, status).await;
+                }
+            });
+            futures::future::join_all(runs).await;
+            debug!("Batch {} finished", id);
+        });
+        self.batch_tasks.lock().await.insert(batch_id.clone(), task);
+
+        Ok(batch_id)
+    }
+
+    /// Forget batches that finished longer ago than their results are kept without anyone
+    /// collecting them
+    async fn prune_batches(&self) {
+        let now = Utc::now();
+        let mut batches = self.batches.lock().await;
+        let mut batch_tasks = self.batch_tasks.lock().await;
+        batches.retain(|id, batch| {
+            let expired =
+                batch.is_expired(now) && batch_tasks.get(id).is_none_or(|task| task.is_finished());
+            if expired {
+                debug!("Dropping batch {}, its results were never collected", id);
+                batch_tasks.remove(id);
+            }
+            !expired
+        });
+    }
+
+    /// A batch spawned with [`Self::spawn_many`] and the results so far
+    pub async fn get_batch(&self, batch_id: &str) -> Option<SubAgentBatch> {
+        self.batches.lock().await.get(batch_id).cloned()
+    }
+
+    async fn set_batch_status(&self, batch_id: &str, index: usize, status: BatchItemStatus) {
+        if let Some(batch) = self.batches.lock().await.get_mut(batch_id) {
+            batch.set_status(index, status);
+        }
+        self.batch_updates.notify_waiters();
+    }
+
+    /// Wait for every subagent of a batch to finish and gather their final messages. With a
+    /// reducer prompt the model synthesizes the results into a single answer, otherwise the
+    /// results are returned one after the other. The batch is forgotten once collected.
+    #[instrument(skip(self, reducer_prompt, provider))]
+    pub async fn collect_results(
+        &self,
+        batch_id: &str,
+        reducer_prompt: Option<&str>,
+        provider: Arc<dyn Provider>,
+    ) -> Result<String> {
+        let batch = loop {
+            // Register for updates before checking so a status change in between isn't missed
+            let updated = self.batch_updates.notified();
+            tokio::pin!(updated);
+            updated.as_mut().enable();
+
+            let batch = self
+                .get_batch(batch_id)
+                .await
+                .ok_or_else(|| anyhow!("Batch {} not found", batch_id))?;
+            if batch.is_finished() {
+                break batch;
+            }
+            debug!(
+                "Waiting for batch {}: {} of {} finished",
+                batch_id,
+                batch.finished_count(),
+                batch.items.len()
+            );
+            updated.await;
+        };
+        self.batches.lock().await.remove(batch_id);
+        self.batch_tasks.lock().await.remove(batch_id);
+
+        let results = batch.results_text();
+        let Some(reducer_prompt) = reducer_prompt else {
+            return Ok(results);
+        };
+        let message = Message::user().with_text(format!(
+            "{}\n\nResults of the {} subagents:\n\n{}",
+            reducer_prompt,
+            batch.items.len(),
+            results
+        ));
+        let (response, _) = provider
+            .complete(BATCH_REDUCER_PROMPT, &[message], &[])
+            .await?;
+        Ok(response.as_concat_text())
+    }
+
+    async fn track_handle(&self, subagent_id: &str, handle: tokio::task::JoinHandle<()>) {
+        self.handles
+            .lock()
+            .await
+            .entry(subagent_id.to_string())
+            .or_default()
+            .push(handle);
+    }
+
     /// Get a subagent by ID
     pub async fn get_subagent(&self, id: &str) -> Option<Arc<SubAgent>> {
         let subagents = self.subagents.read().await;
@@ -129,7 +631,7 @@ impl SubAgentManager {
         subagent_id: &str,
         message: String,
         provider: Arc<dyn Provider>,
-        extension_manager: Arc<tokio::sync::RwLockReadGuard<'_, ExtensionManager>>,
+        extension_manager: Arc<ExtensionManager>,
     ) -> Result<String> {
         let subagent = self
             .get_subagent(subagent_id)
@@ -138,7 +640,7 @@ impl SubAgentManager {
 
         // Process the message and get a reply
         match subagent
-            .reply_subagent(message, provider, extension_manager)
+            .reply_subagent_final(message, provider, extension_manager)
             .await
         {
             Ok(response) => Ok(format!(
@@ -150,6 +652,151 @@ impl SubAgentManager {
         }
     }
 
+    /// Send the latest output of one subagent to another as its next message, optionally
+    /// preceded by instructions, and return the receiver's reply. The link is kept so both
+    /// subagents' transcripts show what was passed between them.
+    pub async fn relay_message(
+        &self,
+        from_id: &str,
+        to_id: &str,
+        instructions: Option<String>,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+    ) -> Result<String> {
+        if from_id == to_id {
+            return Err(anyhow!("A subagent can't relay to itself"));
+        }
+        let from = self
+            .get_subagent(from_id)
+            .await
+            .ok_or_else(|| anyhow!("Subagent {} not found", from_id))?;
+        let to = self
+            .get_subagent(to_id)
+            .await
+            .ok_or_else(|| anyhow!("Subagent {} not found", to_id))?;
+
+        // The last assistant message may be a bare tool call, so take the last one with text
+        let output = from
+            .get_conversation()
+            .await
+            .iter()
+            .rev()
+            .filter(|message| message.role == Role::Assistant)
+            .map(|message| message.as_concat_text())
+            .find(|text| !text.trim().is_empty())
+            .ok_or_else(|| anyhow!("Subagent {} has no output to relay yet", from_id))?;
+        let relayed = format!("Output of subagent {}:\n\n{}", from_id, output);
+        let message = match instructions {
+            Some(instructions) => format!("{}\n\n{}", instructions, relayed),
+            None => relayed,
+        };
+
+        let relayed_at = Utc::now();
+        self.record_link(SubAgentLink {
+            from: from_id.to_string(),
+            to: to_id.to_string(),
+            message: message.clone(),
+            reply: None,
+            relayed_at,
+        })
+        .await;
+        debug!("Relaying output of subagent {} to {}", from_id, to_id);
+
+        let reply = to
+            .reply_subagent_final(message, provider, extension_manager)
+            .await
+            .map_err(|e| anyhow!("Failed to process relayed message in subagent: {}", e))?
+            .as_concat_text();
+        // Found again rather than by index, as older links may have been dropped meanwhile
+        if let Some(link) =
+            self.links.lock().await.iter_mut().rev().find(|link| {
+                link.from == from_id && link.to == to_id && link.relayed_at == relayed_at
+            })
+        {
+            link.reply = Some(reply.clone());
+        }
+
+        Ok(format!(
+            "Relayed the output of subagent {} to subagent {}. Response:\n{}",
+            from_id, to_id, reply
+        ))
+    }
+
+    /// Hand a subagent's work over to a new subagent, typically from the recipe of the next
+    /// phase, such as triage to fix to verify. The new subagent starts from a summary of the
+    /// original's transcript followed by `args.message`, and the original is terminated once
+    /// the new one is spawned. The first turn runs in the background; returns the new ID.
+    #[instrument(skip(self, args, provider, extension_manager))]
+    pub async fn handoff(
+        &self,
+        from_id: &str,
+        mut args: SpawnSubAgentArgs,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<RwLock<ExtensionManager>>,
+    ) -> Result<String> {
+        let from = self
+            .get_subagent(from_id)
+            .await
+            .ok_or_else(|| anyhow!("Subagent {} not found", from_id))?;
+
+        let summary = summarize_for_handoff(
+            provider.as_ref(),
+            &from.get_formatted_conversation().await,
+            HANDOFF_CHUNK_CHARS,
+        )
+        .await?;
+        let seed = format!(
+            "You are taking over from subagent {}. Summary of its work so far:\n\n{}",
+            from_id, summary
+        );
+        args.message = if args.message.is_empty() {
+            seed
+        } else {
+            format!("{}\n\n{}", seed, args.message)
+        };
+        let message = args.message.clone();
+
+        let to_id = self
+            .spawn_interactive_subagent(args, provider, extension_manager)
+            .await?;
+        self.record_link(SubAgentLink {
+            from: from_id.to_string(),
+            to: to_id.clone(),
+            message,
+            reply: None,
+            relayed_at: Utc::now(),
+        })
+        .await;
+        debug!("Handed subagent {} over to {}", from_id, to_id);
+
+        if let Err(e) = self.terminate_subagent(from_id).await {
+            warn!(
+                "Failed to terminate subagent {} after handoff: {}",
+                from_id, e
+            );
+        }
+        Ok(to_id)
+    }
+
+    async fn record_link(&self, link: SubAgentLink) {
+        let mut links = self.links.lock().await;
+        if links.len() >= MAX_LINKS {
+            links.pop_front();
+        }
+        links.push_back(link);
+    }
+
+    /// Relays a subagent took part in, on either side, oldest first
+    pub async fn get_links(&self, subagent_id: &str) -> Vec<SubAgentLink> {
+        self.links
+            .lock()
+            .await
+            .iter()
+            .filter(|link| link.from == subagent_id || link.to == subagent_id)
+            .cloned()
+            .collect()
+    }
+
     /// Terminate a specific subagent
     #[instrument(skip(self))]
     pub async fn terminate_subagent(&self, id: &str) -> Result<()> {
@@ -168,15 +815,16 @@ impl SubAgentManager {
             return Err(anyhow!("Subagent {} not found", id));
         }
 
-        // Clean up the background handle
-        let handle = {
+        // Clean up the background handles
+        let handles = {
             let mut handles = self.handles.lock().await;
             handles.remove(id)
         };
 
-        if let Some(handle) = handle {
+        for handle in handles.into_iter().flatten() {
             handle.abort();
         }
+        global_pool_manager().usage().forget_session(id);
 
         debug!("Subagent {} terminated successfully", id);
         Ok(())
@@ -198,6 +846,14 @@ impl SubAgentManager {
             }
         }
 
+        let idle = self.warm_pool.lock().await.drain();
+        Self::discard_warm_subagents(idle).await;
+
+        for (_, task) in self.batch_tasks.lock().await.drain() {
+            task.abort();
+        }
+        self.batches.lock().await.clear();
+
         debug!("All subagents terminated");
         Ok(())
     }
@@ -209,7 +865,26 @@ impl SubAgentManager {
             .await
             .ok_or_else(|| anyhow!("Subagent {} not found", id))?;
 
-        Ok(subagent.get_formatted_conversation().await)
+        let mut formatted = subagent.get_formatted_conversation().await;
+        let links = self.get_links(id).await;
+        if !links.is_empty() {
+            formatted.push_str("=== Relays ===\n");
+            for link in links {
+                let time = link.relayed_at.format("%Y-%m-%d %H:%M:%S UTC");
+                if link.from == id {
+                    formatted.push_str(&format!("{} Output sent to {}\n", time, link.to));
+                } else {
+                    formatted.push_str(&format!(
+                        "{} Received from {}: {}\n",
+                        time, link.from, link.message
+                    ));
+                }
+                if let Some(reply) = &link.reply {
+                    formatted.push_str(&format!("  Reply from {}: {}\n", link.to, reply));
+                }
+            }
+        }
+        Ok(formatted)
     }
 
     /// Clean up completed or failed subagents
@@ -274,6 +949,135 @@ impl SubAgentManager {
         ))
     }
 
+    /// Change the warm pool size and idle TTL, terminating idle subagents that no longer fit
+    pub async fn configure_warm_pool(&self, config: WarmPoolConfig) {
+        let surplus = self.warm_pool.lock().await.set_config(config);
+        Self::discard_warm_subagents(surplus).await;
+    }
+
+    /// Keep a pool of pre-initialized subagents for a recipe so interactive spawns start instantly
+    #[instrument(skip(self, provider, extension_manager))]
+    pub async fn register_warm_template(
+        &self,
+        recipe_name: &str,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+    ) -> Result<usize> {
+        let recipe = self.load_recipe(recipe_name).await?;
+        self.warm_pool
+            .lock()
+            .await
+            .register_template(recipe_name, recipe);
+        let refilled = self.refill_warm_pool(provider, extension_manager).await;
+        if refilled.is_err() {
+            // E.g. the recipe's requirements aren't met, which won't change by retrying
+            self.unregister_warm_template(recipe_name).await;
+        }
+        refilled
+    }
+
+    /// Stop warming a recipe and terminate its idle subagents
+    pub async fn unregister_warm_template(&self, recipe_name: &str) {
+        let idle = self.warm_pool.lock().await.unregister_template(recipe_name);
+        Self::discard_warm_subagents(idle).await;
+    }
+
+    /// Drop expired idle subagents and top every registered template back up to the pool size.
+    /// Returns the number of subagents created.
+    pub async fn refill_warm_pool(
+        &self,
+        provider: Arc<dyn Provider>,
+        extension_manager: Arc<ExtensionManager>,
+    ) -> Result<usize> {
+        let (expired, wanted) = {
+            let mut pool = self.warm_pool.lock().await;
+            let expired = pool.evict_expired();
+            let wanted: Vec<(String, Recipe, usize)> = pool
+                .templates()
+                .map(|(name, recipe)| (name.clone(), recipe.clone(), pool.deficit(name)))
+                .filter(|(_, _, deficit)| *deficit > 0)
+                .collect();
+            (expired, wanted)
+        };
+        Self::discard_warm_subagents(expired).await;
+
+        let mut created = 0;
+        for (name, recipe, deficit) in wanted {
+            for _ in 0..deficit {
+                let (subagent, handle) = SubAgent::new(
+                    SubAgentConfig::new_with_recipe(recipe.clone())
+                        .with_priority(Priority::Interactive)
+                        .with_processing_slots(Arc::clone(&self.processing_slots))
+                        .with_events(self.events.clone()),
+                    Arc::clone(&provider),
+                    Arc::clone(&extension_manager),
+                    self.mcp_notification_tx.clone(),
+                )
+                .await?;
+                let rejected = self
+                    .warm_pool
+                    .lock()
+                    .await
+                    .push(&name, WarmSubAgent::new(subagent, handle));
+                match rejected {
+                    // The template was unregistered or filled concurrently
+                    Some(warm) => {
+                        Self::discard_warm_subagents(vec![warm]).await;
+                        break;
+                    }
+                    None => created += 1,
+                }
+            }
+        }
+
+        debug!("Warmed {} subagents", created);
+        Ok(created)
+    }
+
+    /// Number of idle warm subagents available for a recipe
+    pub async fn warm_count(&self, recipe_name: &str) -> usize {
+        self.warm_pool.lock().await.idle_count(recipe_name)
+    }
+
+    /// Move an idle warm subagent into the active set, returning its ID
+    async fn claim_warm_subagent(&self, recipe_name: &str) -> Option<String> {
+        let (claimed, expired) = self.warm_pool.lock().await.claim(recipe_name);
+        Self::discard_warm_subagents(expired).await;
+
+        let warm = claimed?;
+        let subagent_id = warm.subagent.id.clone();
+        self.subagents
+            .write()
+            .await
+            .insert(subagent_id.clone(), warm.subagent);
+        self.track_handle(&subagent_id, warm.handle).await;
+        Some(subagent_id)
+    }
+
+    async fn discard_warm_subagents(warm: Vec<WarmSubAgent>) {
+        for warm in warm {
+            if let Err(e) = warm.subagent.terminate().await {
+                debug!(
+                    "Failed to terminate warm subagent {}: {}",
+                    warm.subagent.id, e
+                );
+            }
+            warm.handle.abort();
+        }
+    }
+
+    /// Forget a deduplicated task so identical tasks can be spawned again
+    async fn release_task(&self, fingerprint: Option<&str>) {
+        if let Some(fingerprint) = fingerprint {
+            self.running_tasks.lock().await.remove(fingerprint);
+        }
+    }
+
+    /// Get the subagent ID of a running deduplicated task with the given fingerprint
+    pub async fn find_running_task(&self, fingerprint: &str) -> Option<String> {
+        self.running_tasks.lock().await.get(fingerprint).cloned()
+    }
+
     /// Get count of active subagents
     pub async fn get_active_count(&self) -> usize {
         let subagents = self.subagents.read().await;
@@ -286,16 +1090,22 @@ impl SubAgentManager {
         subagents.contains_key(id)
     }
 
-    /// Run a complete subagent task (spawn, execute, cleanup)
-    #[instrument(skip(self, args, provider, extension_manager))]
+    /// Run a complete subagent task (spawn, execute, cleanup). The messages of the task are
+    /// sent to `updates` as notifications while it runs.
+    #[instrument(skip(self, args, provider, extension_manager, updates))]
     pub async fn run_complete_subagent_task(
         &self,
         args: SpawnSubAgentArgs,
         provider: Arc<dyn Provider>,
-        extension_manager: Arc<tokio::sync::RwLockReadGuard<'_, ExtensionManager>>,
+        extension_manager: Arc<ExtensionManager>,
+        updates: Option<mpsc::Sender<JsonRpcMessage>>,
     ) -> Result<String> {
         debug!("Running complete subagent task");
 
+        let fingerprint = args.deduplicate.then(|| args.task_fingerprint());
+        let priority = args.priority();
+        let requested_recipe = args.recipe_name.clone();
+        let tool_policy = args.tool_policy.clone();
         // Create subagent config based on whether we have a recipe or instructions
         let mut config = if let Some(recipe_name) = args.recipe_name {
             debug!("Using recipe: {}", recipe_name);
@@ -318,15 +1128,54 @@ impl SubAgentManager {
         if let Some(timeout) = args.timeout_seconds {
             config = config.with_timeout(timeout);
         }
+        config = config
+            .with_priority(priority)
+            .with_processing_slots(Arc::clone(&self.processing_slots))
+            .with_events(self.events.clone())
+            .restrict_tools(args.tool_policy);
+        if let Some(state_dir) = Self::state_dir(args.session_id.as_deref()) {
+            config = config.with_state_dir(state_dir);
+        }
+
+        // Singleflight: if an identical task is already running, hand back its ID instead
+        if let Some(fingerprint) = &fingerprint {
+            let mut running_tasks = self.running_tasks.lock().await;
+            if let Some(existing_id) = running_tasks.get(fingerprint) {
+                debug!(
+                    "Identical subagent task already running as {}, not spawning a duplicate",
+                    existing_id
+                );
+                return Ok(format!(
+                    "An identical subagent task is already running as subagent {}. \
+                    Not spawning a duplicate.",
+                    existing_id
+                ));
+            }
+            running_tasks.insert(fingerprint.clone(), config.id.clone());
+        }
+
+        if !args.spawn_counted {
+            if let Err(e) = self.check_spawn_rate(args.session_id.as_deref()) {
+                self.release_task(fingerprint.as_deref()).await;
+                return Err(e);
+            }
+        }
 
         // Create the subagent with the parent agent's provider
-        let (subagent, handle) = SubAgent::new(
+        let (subagent, handle) = match SubAgent::new(
             config,
             Arc::clone(&provider),
             Arc::clone(&extension_manager),
             self.mcp_notification_tx.clone(),
         )
-        .await?;
+        .await
+        {
+            Ok(created) => created,
+            Err(e) => {
+                self.release_task(fingerprint.as_deref()).await;
+                return Err(e);
+            }
+        };
         let subagent_id = subagent.id.clone();
 
         // Store the subagent and its handle temporarily
@@ -334,10 +1183,7 @@ impl SubAgentManager {
             let mut subagents = self.subagents.write().await;
             subagents.insert(subagent_id.clone(), Arc::clone(&subagent));
         }
-        {
-            let mut handles = self.handles.lock().await;
-            handles.insert(subagent_id.clone(), handle);
-        }
+        self.track_handle(&subagent_id, handle).await;
 
         // Run the complete conversation
         let mut conversation_result = String::new();
@@ -348,14 +1194,31 @@ impl SubAgentManager {
         // for the subagent to continue autonomously without user input
         // In a future iteration, we could add logic for the subagent to continue
         // working on multi-step tasks with proper turn management
-        match subagent
-            .reply_subagent(
-                current_message,
-                Arc::clone(&provider),
-                Arc::clone(&extension_manager),
-            )
-            .await
-        {
+        let mut replies = subagent.reply_subagent(
+            current_message,
+            Arc::clone(&provider),
+            Arc::clone(&extension_manager),
+        );
+        let mut outcome = Err(anyhow!("Subagent {} did not reply", subagent_id));
+        while let Some(reply) = replies.next().await {
+            match reply {
+                Ok(message) => {
+                    // Forward each message as it happens so the parent can show the progress
+                    if let Some(updates) = &updates {
+                        let _ = updates.send(subagent.message_notification(&message)).await;
+                    }
+                    outcome = Ok(message);
+                }
+                Err(e) => {
+                    outcome = Err(e);
+                    break;
+                }
+            }
+        }
+        drop(replies);
+
+        let mut verification = None;
+        match outcome {
             Ok(response) => {
                 let response_text = response.as_concat_text();
                 conversation_result.push_str(&format!(
@@ -367,23 +1230,139 @@ impl SubAgentManager {
                     "\n[Task completed after {} turns]",
                     turn_count + 1
                 ));
+                subagent.render_report(&response_text).await;
+                if let Some(score) = subagent
+                    .assess_confidence(&args.message, &response_text, Arc::clone(&provider))
+                    .await
+                {
+                    conversation_result.push_str(&format!(
+                        "\n[Confidence: {:.2}{}: {}]",
+                        score.score,
+                        if score.low { ", low" } else { "" },
+                        score.rationale
+                    ));
+                    let verify_recipe = subagent
+                        .config
+                        .recipe
+                        .as_ref()
+                        .and_then(|recipe| recipe.confidence.as_ref())
+                        .and_then(|check| check.verify_recipe.clone())
+                        // A verification recipe doesn't verify itself
+                        .filter(|verify_recipe| Some(verify_recipe) != requested_recipe.as_ref())
+                        .filter(|_| args.verification_depth < MAX_VERIFICATION_DEPTH);
+                    if let Some(verify_recipe) = verify_recipe.filter(|_| score.low) {
+                        let task =
+                            confidence::verification_task(&args.message, &response_text, &score);
+                        // The verifier runs under the same tool policy as the task it checks
+                        let mut verify = SpawnSubAgentArgs::new_with_recipe(verify_recipe, task)
+                            .with_priority(priority)
+                            .with_tool_policy(tool_policy.clone());
+                        verify.verification_depth = args.verification_depth + 1;
+                        verification = Some(verify);
+                    }
+                }
             }
             Err(e) => {
                 conversation_result
                     .push_str(&format!("\n[Error after {} turns: {}]", turn_count, e));
             }
         }
+        let artifacts = Self::collect_artifacts(self.artifact_store.as_deref(), &subagent).await;
+        if !artifacts.is_empty() {
+            conversation_result.push_str(&format!("\n[Artifacts: {}]", artifacts.join(", ")));
+        }
+        let _ = self.events.send(SubAgentEvent::Completed {
+            subagent_id: subagent_id.clone(),
+            final_message: conversation_result.clone(),
+        });
 
         // Clean up the subagent
+        self.release_task(fingerprint.as_deref()).await;
         if let Err(e) = self.terminate_subagent(&subagent_id).await {
             debug!("Failed to cleanup subagent {}: {}", subagent_id, e);
         }
 
+        // Low confidence hands the answer to the recipe's verification recipe
+        if let Some(mut verification) = verification {
+            verification.session_id = args.session_id.clone();
+            let verify_recipe = verification.recipe_name.clone().unwrap_or_default();
+            debug!(
+                "Subagent {} answered with low confidence, verifying with recipe {}",
+                subagent_id, verify_recipe
+            );
+            let verified = Box::pin(self.run_complete_subagent_task(
+                verification,
+                Arc::clone(&provider),
+                Arc::clone(&extension_manager),
+                updates,
+            ))
+            .await;
+            match verified {
+                Ok(result) => conversation_result.push_str(&format!(
+                    "\n--- Verification by {} ---\n{}",
+                    verify_recipe, result
+                )),
+                Err(e) => conversation_result.push_str(&format!(
+                    "\n[Verification by {} failed: {}]",
+                    verify_recipe, e
+                )),
+            }
+        }
+
         // Return the complete conversation result
         Ok(format!("Subagent task completed:\n{}", conversation_result))
     }
 }
 
+/// Summarize a transcript for a handoff a chunk at a time, each request carrying the summary of
+/// the chunks before it, so a long conversation never has to fit in a single request
+async fn summarize_for_handoff(
+    provider: &dyn Provider,
+    transcript: &str,
+    max_chars: usize,
+) -> Result<String> {
+    let mut summary = String::new();
+    for chunk in text_chunks(transcript, max_chars) {
+        let text = if summary.is_empty() {
+            chunk.to_string()
+        } else {
+            format!(
+                "Summary of the conversation so far:\n\n{}\n\nThe conversation continues:\n\n{}",
+                summary, chunk
+            )
+        };
+        let (reply, _) = provider
+            .complete(
+                HANDOFF_SUMMARY_PROMPT,
+                &[Message::user().with_text(text)],
+                &[],
+            )
+            .await?;
+        summary = reply.as_concat_text();
+    }
+    Ok(summary)
+}
+
+/// Split text into pieces of at most `max_chars` characters, ending at a line break where the
+/// piece has one
+fn text_chunks(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest
+            .char_indices()
+            .nth(max_chars.max(1))
+            .map_or(rest.len(), |(index, _)| index);
+        let end = match rest[..end].rfind('\n') {
+            Some(line_end) if end < rest.len() => line_end + 1,
+            _ => end,
+        };
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    chunks
+}
+
 impl Default for SubAgentManager {
     fn default() -> Self {
         // Create a dummy channel for default implementation
@@ -402,3 +1381,99 @@ impl Drop for SubAgentManager {
         debug!("SubAgentManager dropped");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+
+    #[test]
+    fn test_text_chunks_end_at_line_breaks() {
+        assert_eq!(
+            text_chunks("one\ntwo\nthree", 9),
+            vec!["one\ntwo\n", "three"]
+        );
+        assert_eq!(text_chunks("abcdef", 4), vec!["abcd", "ef"]);
+        assert!(text_chunks("", 4).is_empty());
+        assert_eq!(text_chunks("héé", 2), vec!["hé", "é"]);
+    }
+
+    #[tokio::test]
+    async fn test_long_transcripts_are_summarized_in_chunks() {
+        let provider = MockProvider::default()
+            .with_text("first half")
+            .with_text("both halves");
+        let transcript = format!("{}\n{}", "a".repeat(10), "b".repeat(10));
+
+        let summary = summarize_for_handoff(&provider, &transcript, 12)
+            .await
+            .unwrap();
+        assert_eq!(summary, "both halves");
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        let second = requests[1].messages[0].as_concat_text();
+        assert!(second.contains("first half"));
+        assert!(second.contains(&"b".repeat(10)));
+        assert!(!second.contains(&"a".repeat(10)));
+    }
+
+    #[tokio::test]
+    async fn test_handoff_seeds_the_new_subagent_and_terminates_the_original() {
+        let manager = SubAgentManager::default();
+        let provider = Arc::new(
+            MockProvider::default()
+                .with_text("Found the bug in parser.rs")
+                .with_text("Fixed"),
+        );
+        let extensions = Arc::new(RwLock::new(ExtensionManager::new()));
+
+        let from_id = manager
+            .spawn_interactive_subagent(
+                SpawnSubAgentArgs::new_with_instructions("Triage".to_string(), String::new()),
+                provider.clone(),
+                Arc::clone(&extensions),
+            )
+            .await
+            .unwrap();
+        let from = manager.get_subagent(&from_id).await.unwrap();
+        from.conversation
+            .lock()
+            .await
+            .push(Message::user().with_text("Why does parsing fail?"));
+
+        let to_id = manager
+            .handoff(
+                &from_id,
+                SpawnSubAgentArgs::new_with_instructions(
+                    "Fix".to_string(),
+                    "Now fix it".to_string(),
+                ),
+                provider.clone(),
+                extensions,
+            )
+            .await
+            .unwrap();
+
+        // The summary request saw the original's transcript
+        let transcript = provider.requests()[0].messages[0].as_concat_text();
+        assert!(transcript.contains("Why does parsing fail?"));
+
+        assert!(manager.get_subagent(&from_id).await.is_none());
+        assert_eq!(*from.status.read().await, SubAgentStatus::Terminated);
+        assert!(manager.get_subagent(&to_id).await.is_some());
+
+        let links = manager.get_links(&to_id).await;
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].from, from_id);
+        assert_eq!(links[0].to, to_id);
+        assert_eq!(
+            links[0].message,
+            format!(
+                "You are taking over from subagent {}. Summary of its work so far:\n\n\
+                 Found the bug in parser.rs\n\nNow fix it",
+                from_id
+            )
+        );
+        manager.terminate_all_subagents().await.ok();
+    }
+}
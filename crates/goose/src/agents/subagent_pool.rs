@@ -0,0 +1,300 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::agents::subagent::SubAgent;
+use crate::recipe::Recipe;
+
+const DEFAULT_WARM_POOL_SIZE: usize = 2;
+const DEFAULT_WARM_POOL_IDLE_TTL: Duration = Duration::from_secs(300);
+
+/// Sizing for the pool of pre-initialized subagents kept per registered template
+#[derive(Debug, Clone, Copy)]
+pub struct WarmPoolConfig {
+    /// Number of idle subagents to keep ready for each template
+    pub size: usize,
+    /// How long an idle subagent may wait to be claimed before it is discarded
+    pub idle_ttl: Duration,
+}
+
+impl Default for WarmPoolConfig {
+    fn default() -> Self {
+        Self {
+            size: DEFAULT_WARM_POOL_SIZE,
+            idle_ttl: DEFAULT_WARM_POOL_IDLE_TTL,
+        }
+    }
+}
+
+impl WarmPoolConfig {
+    pub fn with_size(mut self, size: usize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_idle_ttl(mut self, idle_ttl: Duration) -> Self {
+        self.idle_ttl = idle_ttl;
+        self
+    }
+}
+
+/// A pre-initialized subagent waiting to be claimed
+pub struct WarmSubAgent {
+    pub subagent: Arc<SubAgent>,
+    pub handle: tokio::task::JoinHandle<()>,
+    warmed_at: Instant,
+}
+
+impl WarmSubAgent {
+    pub fn new(subagent: Arc<SubAgent>, handle: tokio::task::JoinHandle<()>) -> Self {
+        Self {
+            subagent,
+            handle,
+            warmed_at: Instant::now(),
+        }
+    }
+
+    fn is_expired(&self, idle_ttl: Duration) -> bool {
+        self.warmed_at.elapsed() >= idle_ttl
+    }
+}
+
+/// Idle subagents keyed by the recipe template they were created from.
+///
+/// The pool only does bookkeeping; creating and tearing down subagents is left to
+/// the `SubAgentManager`, which owns the provider and extension manager.
+#[derive(Default)]
+pub struct SubAgentWarmPool {
+    config: WarmPoolConfig,
+    templates: HashMap<String, Recipe>,
+    idle: HashMap<String, VecDeque<WarmSubAgent>>,
+}
+
+impl SubAgentWarmPool {
+    pub fn new(config: WarmPoolConfig) -> Self {
+        Self {
+            config,
+            templates: HashMap::new(),
+            idle: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> WarmPoolConfig {
+        self.config
+    }
+
+    /// Change the pool sizing. Returns idle subagents that no longer fit.
+    pub fn set_config(&mut self, config: WarmPoolConfig) -> Vec<WarmSubAgent> {
+        self.config = config;
+        let mut surplus = Vec::new();
+        for queue in self.idle.values_mut() {
+            while queue.len() > config.size {
+                surplus.extend(queue.pop_back());
+            }
+        }
+        surplus
+    }
+
+    pub fn register_template(&mut self, name: impl Into<String>, recipe: Recipe) {
+        self.templates.insert(name.into(), recipe);
+    }
+
+    /// Stop warming a template. Returns its idle subagents so they can be terminated.
+    pub fn unregister_template(&mut self, name: &str) -> Vec<WarmSubAgent> {
+        self.templates.remove(name);
+        self.idle
+            .remove(name)
+            .map(|queue| queue.into_iter().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.templates.contains_key(name)
+    }
+
+    pub fn templates(&self) -> impl Iterator<Item = (&String, &Recipe)> {
+        self.templates.iter()
+    }
+
+    /// Number of subagents needed to bring a template back up to the configured size
+    pub fn deficit(&self, name: &str) -> usize {
+        if !self.is_registered(name) {
+            return 0;
+        }
+        self.config.size.saturating_sub(self.idle_count(name))
+    }
+
+    pub fn idle_count(&self, name: &str) -> usize {
+        self.idle.get(name).map_or(0, VecDeque::len)
+    }
+
+    /// Add a freshly created subagent to a template's pool.
+    /// Returns it back if the template is unknown or already full.
+    pub fn push(&mut self, name: &str, warm: WarmSubAgent) -> Option<WarmSubAgent> {
+        if self.deficit(name) == 0 {
+            return Some(warm);
+        }
+        self.idle
+            .entry(name.to_string())
+            .or_default()
+            .push_back(warm);
+        None
+    }
+
+    /// Take the freshest idle subagent for a template, skipping any that expired
+    pub fn claim(&mut self, name: &str) -> (Option<WarmSubAgent>, Vec<WarmSubAgent>) {
+        let expired = self.evict_expired_for(name);
+        let claimed = self.idle.get_mut(name).and_then(VecDeque::pop_back);
+        (claimed, expired)
+    }
+
+    /// Remove every idle subagent that outlived the idle TTL
+    pub fn evict_expired(&mut self) -> Vec<WarmSubAgent> {
+        let names: Vec<String> = self.idle.keys().cloned().collect();
+        names
+            .iter()
+            .flat_map(|name| self.evict_expired_for(name))
+            .collect()
+    }
+
+    fn evict_expired_for(&mut self, name: &str) -> Vec<WarmSubAgent> {
+        let idle_ttl = self.config.idle_ttl;
+        let Some(queue) = self.idle.get_mut(name) else {
+            return Vec::new();
+        };
+        // Entries are pushed in creation order, so expired ones sit at the front
+        let mut expired = Vec::new();
+        while queue.front().is_some_and(|warm| warm.is_expired(idle_ttl)) {
+            expired.extend(queue.pop_front());
+        }
+        expired
+    }
+
+    /// Drain every idle subagent, e.g. when the owning manager shuts down
+    pub fn drain(&mut self) -> Vec<WarmSubAgent> {
+        self.idle
+            .drain()
+            .flat_map(|(_, queue)| queue.into_iter())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agents::extension_manager::ExtensionManager;
+    use crate::agents::subagent::SubAgentConfig;
+    use crate::message::Message;
+    use crate::model::ModelConfig;
+    use crate::providers::base::{Provider, ProviderMetadata, ProviderUsage, Usage};
+    use crate::providers::errors::ProviderError;
+    use async_trait::async_trait;
+    use mcp_core::tool::Tool;
+    use tokio::sync::{mpsc, RwLock};
+
+    struct MockProvider {
+        model_config: ModelConfig,
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model_config.clone()
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            Ok((
+                Message::assistant().with_text("ok"),
+                ProviderUsage::new("mock".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    fn recipe() -> Recipe {
+        Recipe::builder()
+            .title("Reviewer")
+            .description("Reviews code")
+            .instructions("Review the code")
+            .build()
+            .unwrap()
+    }
+
+    async fn warm_subagent(extension_manager: &RwLock<ExtensionManager>) -> WarmSubAgent {
+        let provider: Arc<dyn Provider> = Arc::new(MockProvider {
+            model_config: ModelConfig::new("mock".to_string()),
+        });
+        let (tx, _rx) = mpsc::channel(16);
+        let (subagent, handle) = SubAgent::new(
+            SubAgentConfig::new_with_recipe(recipe()),
+            provider,
+            Arc::new(extension_manager.read().await.clone()),
+            tx,
+        )
+        .await
+        .unwrap();
+        WarmSubAgent::new(subagent, handle)
+    }
+
+    #[tokio::test]
+    async fn test_push_and_claim_respects_size() {
+        let extension_manager = RwLock::new(ExtensionManager::default());
+        let mut pool = SubAgentWarmPool::new(WarmPoolConfig::default().with_size(1));
+
+        let unregistered = warm_subagent(&extension_manager).await;
+        assert!(pool.push("reviewer", unregistered).is_some());
+
+        pool.register_template("reviewer", recipe());
+        assert_eq!(pool.deficit("reviewer"), 1);
+
+        let first = warm_subagent(&extension_manager).await;
+        let first_id = first.subagent.id.clone();
+        assert!(pool.push("reviewer", first).is_none());
+        let overflow = warm_subagent(&extension_manager).await;
+        assert!(pool.push("reviewer", overflow).is_some());
+        assert_eq!(pool.deficit("reviewer"), 0);
+
+        let (claimed, expired) = pool.claim("reviewer");
+        assert!(expired.is_empty());
+        assert_eq!(claimed.unwrap().subagent.id, first_id);
+        assert_eq!(pool.deficit("reviewer"), 1);
+        assert!(pool.claim("reviewer").0.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_claim_skips_expired_subagents() {
+        let extension_manager = RwLock::new(ExtensionManager::default());
+        let mut pool =
+            SubAgentWarmPool::new(WarmPoolConfig::default().with_idle_ttl(Duration::ZERO));
+        pool.register_template("reviewer", recipe());
+
+        let warm = warm_subagent(&extension_manager).await;
+        assert!(pool.push("reviewer", warm).is_none());
+
+        let (claimed, expired) = pool.claim("reviewer");
+        assert!(claimed.is_none());
+        assert_eq!(expired.len(), 1);
+        assert_eq!(pool.idle_count("reviewer"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_unregister_returns_idle_subagents() {
+        let extension_manager = RwLock::new(ExtensionManager::default());
+        let mut pool = SubAgentWarmPool::new(WarmPoolConfig::default());
+        pool.register_template("reviewer", recipe());
+        let warm = warm_subagent(&extension_manager).await;
+        assert!(pool.push("reviewer", warm).is_none());
+
+        assert_eq!(pool.unregister_template("reviewer").len(), 1);
+        assert!(!pool.is_registered("reviewer"));
+        assert_eq!(pool.deficit("reviewer"), 0);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::oneshot;
+
+use crate::config::Config;
+use crate::providers::rate_limit::Priority;
+
+/// Subagents processing at the same time unless `GOOSE_SUBAGENT_MAX_CONCURRENT` says otherwise
+const DEFAULT_MAX_CONCURRENT: usize = 8;
+
+/// The most subagents that may process at the same time
+pub fn max_concurrent_from_config() -> usize {
+    Config::global()
+        .get_param::<usize>("GOOSE_SUBAGENT_MAX_CONCURRENT")
+        .unwrap_or(DEFAULT_MAX_CONCURRENT)
+        .max(1)
+}
+
+#[derive(Debug)]
+struct SlotState {
+    available: usize,
+    /// Turns waiting for a slot, first come first served within each priority
+    waiting: BTreeMap<Priority, VecDeque<oneshot::Sender<ProcessingSlot>>>,
+}
+
+/// Slots for the subagent turns that may talk to the provider at the same time. When all are
+/// taken, a freed slot goes to the longest waiting turn of the highest priority, so interactive
+/// subagents get ahead of queued batch work at the next turn boundary.
+#[derive(Debug)]
+pub struct ProcessingSlots {
+    state: Mutex<SlotState>,
+}
+
+/// A taken slot, handed on when dropped
+#[derive(Debug)]
+pub struct ProcessingSlot {
+    slots: Option<Arc<ProcessingSlots>>,
+}
+
+impl ProcessingSlots {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        Arc::new(Self {
+            state: Mutex::new(SlotState {
+                available: capacity.max(1),
+                waiting: BTreeMap::new(),
+            }),
+        })
+    }
+
+    pub fn from_config() -> Arc<Self> {
+        Self::new(max_concurrent_from_config())
+    }
+
+    /// A slot if one is free right now
+    pub fn try_acquire(self: &Arc<Self>) -> Option<ProcessingSlot> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.available == 0 {
+            return None;
+        }
+        state.available -= 1;
+        Some(ProcessingSlot {
+            slots: Some(Arc::clone(self)),
+        })
+    }
+
+    /// Wait for a slot behind the turns of the same or higher priority already waiting
+    pub async fn acquire(self: &Arc<Self>, priority: Priority) -> ProcessingSlot {
+        let receiver = {
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            if state.available > 0 {
+                state.available -= 1;
+                return ProcessingSlot {
+                    slots: Some(Arc::clone(self)),
+                };
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.waiting.entry(priority).or_default().push_back(sender);
+            receiver
+        };
+        // Senders are only dropped after sending, and the slots outlive their waiters
+        receiver
+            .await
+            .expect("processing slot senders are only dropped after sending")
+    }
+
+    /// Turns waiting for a slot
+    pub fn queued(&self) -> usize {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.waiting.values().map(VecDeque::len).sum()
+    }
+
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some(sender) = state
+            .waiting
+            .values_mut()
+            .rev()
+            .find_map(|queue| queue.pop_front())
+        {
+            let slot = ProcessingSlot {
+                slots: Some(Arc::clone(self)),
+            };
+            match sender.send(slot) {
+                Ok(()) => return,
+                // The waiter gave up; don't let the returned slot release again under the lock
+                Err(mut slot) => slot.slots = None,
+            }
+        }
+        state.waiting.retain(|_, queue| !queue.is_empty());
+        state.available += 1;
+    }
+}
+
+impl Drop for ProcessingSlot {
+    fn drop(&mut self) {
+        if let Some(slots) = self.slots.take() {
+            slots.release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_freed_slot_goes_to_highest_priority_waiter() {
+        let slots = ProcessingSlots::new(1);
+        let running = slots.try_acquire().unwrap();
+        assert!(slots.try_acquire().is_none());
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        for priority in [Priority::Batch, Priority::Interactive, Priority::Normal] {
+            let slots = Arc::clone(&slots);
+            let order_tx = order_tx.clone();
+            tokio::spawn(async move {
+                let _slot = slots.acquire(priority).await;
+                order_tx.send(priority).unwrap();
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            });
+        }
+        while slots.queued() < 3 {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        drop(running);
+        let mut order = Vec::new();
+        for _ in 0..3 {
+            order.push(order_rx.recv().await.unwrap());
+        }
+        assert_eq!(
+            order,
+            vec![Priority::Interactive, Priority::Normal, Priority::Batch]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_waiter_does_not_leak_slot() {
+        let slots = ProcessingSlots::new(1);
+        let running = slots.try_acquire().unwrap();
+        let waiting =
+            tokio::time::timeout(Duration::from_millis(10), slots.acquire(Priority::Normal)).await;
+        assert!(waiting.is_err());
+
+        drop(running);
+        assert!(slots.try_acquire().is_some());
+        assert_eq!(slots.queued(), 0);
+    }
+}
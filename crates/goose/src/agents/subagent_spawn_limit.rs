@@ -0,0 +1,115 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::Config;
+
+/// Subagents a session may spawn per minute when `GOOSE_SUBAGENT_SPAWNS_PER_MINUTE` is unset
+pub const DEFAULT_SPAWNS_PER_MINUTE: usize = 20;
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// A session spawned more subagents in the last minute than it is allowed to
+#[derive(Debug, Clone, thiserror::Error)]
+#[error(
+    "Subagent spawn limit reached: this session spawned {limit} subagents in the last minute. \
+    Wait {retry_in_secs}s before spawning another, or do the work without a subagent."
+)]
+pub struct SpawnRateLimited {
+    pub limit: usize,
+    pub retry_in_secs: u64,
+}
+
+/// Caps how many subagents each session may spawn in a sliding one minute window, so a
+/// runaway or prompt-injected spawn loop can't drain the provider quota other sessions share
+#[derive(Debug)]
+pub struct SpawnRateLimiter {
+    /// Spawns allowed per minute, unlimited when zero
+    limit: usize,
+    spawns: Mutex<HashMap<String, VecDeque<Instant>>>,
+}
+
+impl Default for SpawnRateLimiter {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPAWNS_PER_MINUTE)
+    }
+}
+
+impl SpawnRateLimiter {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            spawns: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The limit from `GOOSE_SUBAGENT_SPAWNS_PER_MINUTE`, where 0 turns it off
+    pub fn from_config() -> Self {
+        Self::new(
+            Config::global()
+                .get_param("GOOSE_SUBAGENT_SPAWNS_PER_MINUTE")
+                .unwrap_or(DEFAULT_SPAWNS_PER_MINUTE),
+        )
+    }
+
+    /// Count a spawn for the session, or refuse it if the session is at its limit
+    pub fn check(&self, session_id: &str) -> Result<(), SpawnRateLimited> {
+        self.check_at(session_id, Instant::now())
+    }
+
+    fn check_at(&self, session_id: &str, now: Instant) -> Result<(), SpawnRateLimited> {
+        if self.limit == 0 {
+            return Ok(());
+        }
+        let mut spawns = self.spawns.lock().unwrap_or_else(|e| e.into_inner());
+        // Sessions come and go, so forget the ones with nothing left in the window
+        spawns.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|spawned| now.duration_since(*spawned) >= WINDOW)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = spawns.entry(session_id.to_string()).or_default();
+        if times.len() >= self.limit {
+            let oldest = times.front().copied().unwrap_or(now);
+            let retry_in = WINDOW.saturating_sub(now.duration_since(oldest));
+            return Err(SpawnRateLimited {
+                limit: self.limit,
+                retry_in_secs: retry_in.as_secs().max(1),
+            });
+        }
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spawns_are_limited_per_session_within_the_window() {
+        let limiter = SpawnRateLimiter::new(2);
+        let start = Instant::now();
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter.check_at("a", start).is_ok());
+        let limited = limiter
+            .check_at("a", start + Duration::from_secs(15))
+            .unwrap_err();
+        assert_eq!(limited.retry_in_secs, 45);
+        // Other sessions have their own budget
+        assert!(limiter.check_at("b", start).is_ok());
+        // The window slides
+        assert!(limiter.check_at("a", start + WINDOW).is_ok());
+    }
+
+    #[test]
+    fn test_zero_disables_the_limit() {
+        let limiter = SpawnRateLimiter::new(0);
+        let now = Instant::now();
+        assert!((0..100).all(|_| limiter.check_at("a", now).is_ok()));
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::agents::session_resources::Artifact;
+use crate::agents::subagent::SubAgentStatus;
+use crate::agents::subagent_types::ToolPolicy;
+use crate::message::Message;
+use crate::providers::rate_limit::Priority;
+use crate::recipe::Recipe;
+use crate::session::storage::ensure_session_dir;
+
+/// Where the subagents spawned by a session keep their snapshots
+pub fn subagent_state_dir(session_id: &str) -> Result<PathBuf> {
+    Ok(ensure_session_dir()?.join("subagents").join(session_id))
+}
+
+/// Everything needed to bring a subagent back after the goose process restarts. The recipe is
+/// stored whole, so a subagent resumes with the instructions it started with even if the
+/// recipe file changed since. The conversation is kept next to it in `<id>.jsonl`, one message
+/// per line, so saving after a status change only appends the messages that are new.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubAgentSnapshot {
+    pub id: String,
+    #[serde(default)]
+    pub recipe: Option<Recipe>,
+    #[serde(default)]
+    pub instructions: Option<String>,
+    #[serde(default)]
+    pub max_turns: Option<usize>,
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+    #[serde(default)]
+    pub priority: Priority,
+    #[serde(default)]
+    pub tool_policy: ToolPolicy,
+    /// Read from the conversation file; only snapshots saved before it existed hold it here
+    #[serde(default, skip_serializing)]
+    pub conversation: Vec<Message>,
+    pub status: SubAgentStatus,
+    pub turn_count: usize,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub artifacts: BTreeMap<String, Artifact>,
+    pub saved_at: DateTime<Utc>,
+}
+
+impl SubAgentSnapshot {
+    fn path(dir: &Path, id: &str) -> PathBuf {
+        dir.join(format!("{}.json", id))
+    }
+
+    fn conversation_path(dir: &Path, id: &str) -> PathBuf {
+        dir.join(format!("{}.jsonl", id))
+    }
+
+    /// Write the snapshot to `<dir>/<id>.json` and its whole conversation to `<dir>/<id>.jsonl`
+    pub async fn save(&self, dir: &Path) -> Result<PathBuf> {
+        self.save_from(dir, 0).await?;
+        Ok(Self::path(dir, &self.id))
+    }
+
+    /// Write the snapshot when the first `persisted` messages of its conversation are already
+    /// saved, appending only the ones after them. A conversation that shrank since, or nothing
+    /// saved yet, is rewritten whole. Files are replaced in a single step, so a crash mid-write
+    /// leaves the previous snapshot intact. Returns the number of messages now saved.
+    pub async fn save_from(&self, dir: &Path, persisted: usize) -> Result<usize> {
+        tokio::fs::create_dir_all(dir).await?;
+        let conversation_path = Self::conversation_path(dir, &self.id);
+        if persisted == 0 || persisted > self.conversation.len() {
+            replace(&conversation_path, &message_lines(&self.conversation)?).await?;
+        } else if persisted < self.conversation.len() {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&conversation_path)
+                .await?;
+            file.write_all(&message_lines(&self.conversation[persisted..])?)
+                .await?;
+            file.flush().await?;
+        }
+        replace(&Self::path(dir, &self.id), &serde_json::to_vec(self)?).await?;
+        Ok(self.conversation.len())
+    }
+
+    pub async fn load(dir: &Path, id: &str) -> Result<Self> {
+        let content = tokio::fs::read(Self::path(dir, id)).await?;
+        let mut snapshot: Self = serde_json::from_slice(&content)?;
+        snapshot.load_conversation(dir).await?;
+        Ok(snapshot)
+    }
+
+    /// Read the conversation file over the inline conversation of an older snapshot. Lines that
+    /// don't parse, such as one cut short by a crash mid-append, are skipped.
+    async fn load_conversation(&mut self, dir: &Path) -> Result<()> {
+        let content = match tokio::fs::read_to_string(Self::conversation_path(dir, &self.id)).await
+        {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        self.conversation = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(message) => Some(message),
+                Err(e) => {
+                    warn!("Skipping unreadable message of subagent {}: {}", self.id, e);
+                    None
+                }
+            })
+            .collect();
+        Ok(())
+    }
+
+    /// Every snapshot in `dir`, oldest subagent first. Snapshots that can't be read are
+    /// skipped with a warning; a missing directory has none.
+    pub async fn list(dir: &Path) -> Result<Vec<Self>> {
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut snapshots = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let snapshot = match tokio::fs::read(&path).await {
+                Ok(content) => match serde_json::from_slice::<Self>(&content) {
+                    Ok(mut snapshot) => snapshot.load_conversation(dir).await.map(|_| snapshot),
+                    Err(e) => Err(e.into()),
+                },
+                Err(e) => Err(e.into()),
+            };
+            match snapshot {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => warn!("Skipping unreadable subagent snapshot {:?}: {}", path, e),
+            }
+        }
+        snapshots.sort_by_key(|snapshot| snapshot.created_at);
+        Ok(snapshots)
+    }
+
+    /// Forget a subagent's snapshot; removing one that doesn't exist is not an error
+    pub async fn remove(dir: &Path, id: &str) -> Result<()> {
+        for path in [Self::path(dir, id), Self::conversation_path(dir, id)] {
+            match tokio::fs::remove_file(path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn message_lines(messages: &[Message]) -> Result<Vec<u8>> {
+    let mut lines = Vec::new();
+    for message in messages {
+        serde_json::to_writer(&mut lines, message)?;
+        lines.push(b'\n');
+    }
+    Ok(lines)
+}
+
+/// Replace `path` with `content` through a temporary file of its own, so concurrent writers
+/// never write into the same temporary file
+async fn replace(path: &Path, content: &[u8]) -> Result<()> {
+    let temp = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    tokio::fs::write(&temp, content).await?;
+    if let Err(e) = tokio::fs::rename(&temp, path).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(id: &str, created_at: DateTime<Utc>) -> SubAgentSnapshot {
+        SubAgentSnapshot {
+            id: id.to_string(),
+            recipe: None,
+            instructions: Some("Summarize the changelog".to_string()),
+            max_turns: Some(5),
+            timeout_seconds: None,
+            priority: Priority::Batch,
+            tool_policy: ToolPolicy::default(),
+            conversation: vec![Message::user().with_text("Start with 1.0")],
+            status: SubAgentStatus::Ready,
+            turn_count: 1,
+            created_at,
+            artifacts: BTreeMap::from([(
+                "summary.md".to_string(),
+                Artifact::text("summary.md", "# 1.0"),
+            )]),
+            saved_at: Utc::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_save_list_and_remove() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().join("session");
+        assert!(SubAgentSnapshot::list(&state_dir).await.unwrap().is_empty());
+
+        let now = Utc::now();
+        let newer = snapshot("b", now);
+        let older = snapshot("a", now - chrono::Duration::minutes(5));
+        newer.save(&state_dir).await.unwrap();
+        older.save(&state_dir).await.unwrap();
+        tokio::fs::write(state_dir.join("broken.json"), b"{")
+            .await
+            .unwrap();
+
+        let listed = SubAgentSnapshot::list(&state_dir).await.unwrap();
+        let ids: Vec<&str> = listed.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b"]);
+
+        let loaded = SubAgentSnapshot::load(&state_dir, "a").await.unwrap();
+        assert_eq!(loaded.conversation, older.conversation);
+        assert_eq!(loaded.artifacts, older.artifacts);
+        assert_eq!(loaded.priority, Priority::Batch);
+        assert_eq!(loaded.status, SubAgentStatus::Ready);
+
+        SubAgentSnapshot::remove(&state_dir, "b").await.unwrap();
+        SubAgentSnapshot::remove(&state_dir, "b").await.unwrap();
+        assert_eq!(SubAgentSnapshot::list(&state_dir).await.unwrap().len(), 1);
+        assert!(!state_dir.join("b.jsonl").exists());
+    }
+
+    #[tokio::test]
+    async fn test_save_from_appends_new_messages() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut snapshot = snapshot("a", Utc::now());
+        let persisted = snapshot.save_from(dir.path(), 0).await.unwrap();
+        assert_eq!(persisted, 1);
+
+        snapshot
+            .conversation
+            .push(Message::assistant().with_text("1.0 added subagents"));
+        let persisted = snapshot.save_from(dir.path(), persisted).await.unwrap();
+        assert_eq!(persisted, 2);
+        let loaded = SubAgentSnapshot::load(dir.path(), "a").await.unwrap();
+        assert_eq!(loaded.conversation, snapshot.conversation);
+
+        // A conversation that shrank is rewritten rather than appended to
+        snapshot.conversation.truncate(1);
+        snapshot.save_from(dir.path(), persisted).await.unwrap();
+        let loaded = SubAgentSnapshot::load(dir.path(), "a").await.unwrap();
+        assert_eq!(loaded.conversation, snapshot.conversation);
+
+        let leftovers = std::fs::read_dir(dir.path())
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == "tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+}
@@ -3,6 +3,13 @@ use mcp_core::tool::{Tool, ToolAnnotations};
 use serde_json::json;
 
 pub const SUBAGENT_RUN_TASK_TOOL_NAME: &str = "subagent__run_task";
+pub const SUBAGENT_FINAL_OUTPUT_TOOL_NAME: &str = "subagent__final_output";
+pub const SUBAGENT_SPAWN_PARALLEL_TOOL_NAME: &str = "subagent__spawn_parallel";
+pub const SUBAGENT_COLLECT_RESULTS_TOOL_NAME: &str = "subagent__collect_results";
+pub const SUBAGENT_WRITE_TABLE_TOOL_NAME: &str = "subagent__write_table";
+pub const SUBAGENT_SEND_MESSAGE_TOOL_NAME: &str = "subagent__send_message";
+pub const SUBAGENT_RELAY_MESSAGE_TOOL_NAME: &str = "subagent__relay_message";
+pub const SUBAGENT_HANDOFF_TOOL_NAME: &str = "subagent__handoff";
 
 pub fn run_task_subagent_tool() -> Tool {
     Tool::new(
@@ -54,6 +61,26 @@ pub fn run_task_subagent_tool() -> Tool {
                     "type": "integer",
                     "description": "Optional timeout for the entire task in seconds",
                     "minimum": 1
+                },
+                "priority": {
+                    "type": "string",
+                    "enum": ["batch", "normal", "interactive"],
+                    "description": "Priority for provider capacity when many subagents are running. Defaults to this session's priority, which it can't exceed; use 'batch' for work nobody is waiting on."
+                },
+                "deduplicate": {
+                    "type": "boolean",
+                    "description": "If an identical task (same recipe or instructions and task) is already running, return the existing subagent's ID instead of spawning a duplicate",
+                    "default": false
+                },
+                "allowed_tools": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Only let the subagent call these tools, as names or glob patterns such as 'developer__read*'. All tools by default."
+                },
+                "denied_tools": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Never let the subagent call these tools, as names or glob patterns such as 'developer__shell'"
                 }
             }
         }),
@@ -66,3 +93,354 @@ pub fn run_task_subagent_tool() -> Tool {
         }),
     )
 }
+
+pub fn spawn_parallel_subagent_tool() -> Tool {
+    Tool::new(
+        SUBAGENT_SPAWN_PARALLEL_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Spawn one subagent per input to work on a list of independent inputs in parallel,
+            all configured the same way with a recipe or instructions.
+
+            Each subagent gets one input as its task. They run in the background, a limited
+            number at a time, and this tool returns a batch ID right away to collect the
+            results with.
+
+            Use it when the same work has to be done for many inputs, for example:
+            - Summarize each of these 12 documents
+            - Review each changed file for security issues
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["inputs"],
+            "properties": {
+                "recipe_name": {
+                    "type": "string",
+                    "description": "Name of the recipe file to configure every subagent with. Either this or 'instructions' must be provided."
+                },
+                "instructions": {
+                    "type": "string",
+                    "description": "Direct instructions for every subagent. Either this or 'recipe_name' must be provided."
+                },
+                "inputs": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "minItems": 1,
+                    "description": "One task per subagent"
+                },
+                "max_parallel": {
+                    "type": "integer",
+                    "description": "Most subagents to run at once, bounded by the configured limit",
+                    "minimum": 1
+                },
+                "max_turns": {
+                    "type": "integer",
+                    "description": "Maximum number of conversation turns for each subagent (default: 10)",
+                    "minimum": 1,
+                    "default": 10
+                },
+                "timeout_seconds": {
+                    "type": "integer",
+                    "description": "Optional timeout for each subagent's task in seconds",
+                    "minimum": 1
+                },
+                "priority": {
+                    "type": "string",
+                    "enum": ["batch", "normal", "interactive"],
+                    "description": "Priority for provider capacity, 'batch' by default so interactive subagents go first. Can't exceed this session's priority."
+                },
+                "allowed_tools": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Only let the subagent call these tools, as names or glob patterns such as 'developer__read*'. All tools by default."
+                },
+                "denied_tools": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Never let the subagent call these tools, as names or glob patterns such as 'developer__shell'"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Spawn parallel subagents".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn collect_results_subagent_tool() -> Tool {
+    Tool::new(
+        SUBAGENT_COLLECT_RESULTS_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Wait for every subagent of a batch started with subagent__spawn_parallel to finish
+            and collect their final messages.
+
+            Without a reducer prompt the results are returned one per input. With one, the
+            results are combined into a single answer following the prompt, for example
+            "Merge these document summaries into one overview" or "List the issues found,
+            most severe first". A batch can be collected once.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["batch_id"],
+            "properties": {
+                "batch_id": {
+                    "type": "string",
+                    "description": "ID of the batch returned by subagent__spawn_parallel"
+                },
+                "reducer_prompt": {
+                    "type": "string",
+                    "description": "Optional instructions for combining the results into a single answer"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Collect subagent results".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn send_message_subagent_tool() -> Tool {
+    Tool::new(
+        SUBAGENT_SEND_MESSAGE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Send a message to a subagent that is still around and wait for its reply.
+
+            Use it to continue a subagent that was suspended when goose restarted: it keeps
+            the conversation it had so far, and the message starts its next turn. It works
+            for any subagent that hasn't completed or been terminated.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["subagent_id", "message"],
+            "properties": {
+                "subagent_id": {
+                    "type": "string",
+                    "description": "ID of the subagent to send the message to"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "The message for the subagent, e.g. 'Continue where you left off'"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Message a subagent".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn relay_message_subagent_tool() -> Tool {
+    Tool::new(
+        SUBAGENT_RELAY_MESSAGE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Pipe the latest output of one subagent straight into another as its next message,
+            and wait for the receiving subagent's reply.
+
+            Use it to chain subagents without copying their output yourself, for example
+            handing a researcher's findings to a writer. Both subagents must still be around;
+            the relay shows up in both of their transcripts.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["from_subagent_id", "to_subagent_id"],
+            "properties": {
+                "from_subagent_id": {
+                    "type": "string",
+                    "description": "ID of the subagent whose latest output is relayed"
+                },
+                "to_subagent_id": {
+                    "type": "string",
+                    "description": "ID of the subagent that receives the output"
+                },
+                "instructions": {
+                    "type": "string",
+                    "description": "Optional instructions sent ahead of the output, e.g. 'Write a blog post from these findings'"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Relay subagent output".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+pub fn handoff_subagent_tool() -> Tool {
+    Tool::new(
+        SUBAGENT_HANDOFF_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Hand a subagent's work over to a new subagent started from a different recipe, for
+            workflows that move through phases such as triage, then fix, then verify.
+
+            The new subagent starts from a summary of the original subagent's conversation,
+            followed by the task you give it, and works in the background. The original
+            subagent is terminated. Follow the new subagent with its ID like any other.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["subagent_id", "recipe_name"],
+            "properties": {
+                "subagent_id": {
+                    "type": "string",
+                    "description": "ID of the subagent whose work is handed over"
+                },
+                "recipe_name": {
+                    "type": "string",
+                    "description": "Name of the recipe file for the next phase (e.g., 'fix_recipe.yaml')"
+                },
+                "task": {
+                    "type": "string",
+                    "description": "Optional task for the new subagent, e.g. 'Fix the bug identified in triage'"
+                },
+                "max_turns": {
+                    "type": "integer",
+                    "description": "Maximum number of conversation turns for the new subagent (default: 10)",
+                    "minimum": 1
+                },
+                "timeout_seconds": {
+                    "type": "integer",
+                    "description": "Optional timeout for the new subagent in seconds",
+                    "minimum": 1
+                },
+                "priority": {
+                    "type": "string",
+                    "enum": ["batch", "normal", "interactive"],
+                    "description": "Priority for provider capacity, at most this session's own (default: this session's)"
+                },
+                "allowed_tools": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Only let the subagent call these tools, as names or glob patterns such as 'developer__read*'. All tools by default."
+                },
+                "denied_tools": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Never let the subagent call these tools, as names or glob patterns such as 'developer__shell'"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Hand off to a new subagent".to_string()),
+            read_only_hint: false,
+            destructive_hint: true,
+            idempotent_hint: false,
+            open_world_hint: false,
+        }),
+    )
+}
+
+/// Offered to subagents only, to hand named output files back to the parent
+pub fn final_output_subagent_tool() -> Tool {
+    Tool::new(
+        SUBAGENT_FINAL_OUTPUT_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Register a named output artifact of your task, such as a report or a patch, so it is
+            handed back with your result. Call it once per artifact; registering the same name
+            again replaces the earlier content.
+
+            Name artifacts after what they contain, with an extension for the format:
+            - "report.md" for a written report
+            - "patch.diff" for proposed code changes
+            - "results.json" for structured data
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["name", "content"],
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "File name of the artifact, e.g. 'report.md'. Letters, digits, '.', '-' and '_' only."
+                },
+                "content": {
+                    "type": "string",
+                    "description": "The full content of the artifact"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Register output artifact".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
+
+/// Offered to subagents only, to hand structured results back as a CSV or XLSX table
+pub fn write_table_subagent_tool() -> Tool {
+    Tool::new(
+        SUBAGENT_WRITE_TABLE_TOOL_NAME.to_string(),
+        indoc! {r#"
+            Register a table of structured results as an output artifact, in CSV or XLSX format
+            depending on the name's extension. Prefer it over prose when your results are a list
+            of records, such as one row per issue triaged or per file reviewed.
+
+            Declare the columns first, then give each row as an object keyed by column name.
+            Every row is checked against the columns: unknown columns, missing required values
+            and values of the wrong type are rejected with the offending row, and nothing is
+            written until all rows pass. Registering the same name again replaces the table.
+        "#}
+        .to_string(),
+        json!({
+            "type": "object",
+            "required": ["name", "columns", "rows"],
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "File name of the table ending in .csv or .xlsx, e.g. 'triage.csv'. Letters, digits, '.', '-' and '_' only."
+                },
+                "columns": {
+                    "type": "array",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "required": ["name"],
+                        "properties": {
+                            "name": {"type": "string"},
+                            "type": {
+                                "type": "string",
+                                "enum": ["string", "integer", "number", "boolean"],
+                                "default": "string"
+                            },
+                            "required": {"type": "boolean", "default": false}
+                        }
+                    },
+                    "description": "Columns of the table, in order"
+                },
+                "rows": {
+                    "type": "array",
+                    "items": {"type": "object"},
+                    "description": "One object per row, keyed by column name"
+                }
+            }
+        }),
+        Some(ToolAnnotations {
+            title: Some("Register table artifact".to_string()),
+            read_only_hint: false,
+            destructive_hint: false,
+            idempotent_hint: true,
+            open_world_hint: false,
+        }),
+    )
+}
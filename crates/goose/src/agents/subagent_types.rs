@@ -1,5 +1,85 @@
+use chrono::{DateTime, Utc};
+use globset::Glob;
+use mcp_core::tool::Tool;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::config::Config;
+use crate::providers::rate_limit::Priority;
+
+/// Which extension tools a subagent may call, as glob patterns over prefixed tool names such
+/// as `developer__shell` or `github__*`. A tool is allowed when it matches an `allow` pattern,
+/// or there are none, matches a pattern of every list in `allow_layers`, and matches no `deny`
+/// pattern. With `read_only` it also has to be annotated as read-only.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolPolicy {
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub deny: Vec<String>,
+    /// Allow lists added by [`ToolPolicy::restrict`]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allow_layers: Vec<Vec<String>>,
+}
+
+impl ToolPolicy {
+    /// The policy every subagent starts from, `GOOSE_SUBAGENT_TOOL_POLICY`, e.g.
+    /// `{"deny": ["developer__shell", "*__write*"]}`; everything is allowed when unset
+    pub fn from_config() -> Self {
+        Config::global()
+            .get_param("GOOSE_SUBAGENT_TOOL_POLICY")
+            .unwrap_or_default()
+    }
+
+    /// Only tools that declare they change nothing, e.g. for subagents started by triggers
+    pub fn read_only() -> Self {
+        Self {
+            read_only: true,
+            ..Default::default()
+        }
+    }
+
+    /// Whether the tool may be called, by its name and, for a read-only policy, its annotations
+    pub fn permits(&self, tool: &Tool) -> bool {
+        self.is_allowed(&tool.name)
+            && (!self.read_only
+                || tool
+                    .annotations
+                    .as_ref()
+                    .is_some_and(|annotations| annotations.read_only_hint))
+    }
+
+    /// Whether the tool name is allowed; see [`ToolPolicy::permits`] for read-only policies
+    pub fn is_allowed(&self, tool_name: &str) -> bool {
+        std::iter::once(&self.allow)
+            .chain(&self.allow_layers)
+            .all(|allow| allow.is_empty() || allow.iter().any(|p| matches_pattern(p, tool_name)))
+            && !self.deny.iter().any(|p| matches_pattern(p, tool_name))
+    }
+
+    /// Narrow this policy with another, e.g. the configured one with what a spawn asked for.
+    /// Deny patterns add up and the allow lists are kept side by side, so a tool has to be
+    /// allowed by both policies and a restriction never gives access to more tools. Patterns
+    /// are never matched against each other, since a glob such as `{*,x}` can match more
+    /// tools than the pattern it looks like.
+    pub fn restrict(mut self, other: ToolPolicy) -> Self {
+        self.allow_layers.push(other.allow);
+        self.allow_layers.extend(other.allow_layers);
+        self.allow_layers.retain(|allow| !allow.is_empty());
+        self.deny.extend(other.deny);
+        self.read_only |= other.read_only;
+        self
+    }
+}
+
+fn matches_pattern(pattern: &str, tool_name: &str) -> bool {
+    Glob::new(pattern)
+        .map(|glob| glob.compile_matcher().is_match(tool_name))
+        .unwrap_or(pattern == tool_name)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpawnSubAgentArgs {
     pub recipe_name: Option<String>,
@@ -7,6 +87,25 @@ pub struct SpawnSubAgentArgs {
     pub message: String,
     pub max_turns: Option<usize>,
     pub timeout_seconds: Option<u64>,
+    /// Return the ID of an identical task that is already running instead of spawning a duplicate
+    #[serde(default)]
+    pub deduplicate: bool,
+    /// Priority for provider capacity, at most the spawning session's, which is used when unset
+    #[serde(default)]
+    pub priority: Option<Priority>,
+    /// The session spawning the subagent, whose spawn rate is limited
+    #[serde(skip)]
+    pub session_id: Option<String>,
+    /// Tools the subagent may call, on top of the configured policy
+    #[serde(default)]
+    pub tool_policy: ToolPolicy,
+    /// How many verification runs this task is nested in, 0 for a task a model asked for
+    #[serde(skip)]
+    pub verification_depth: usize,
+    /// Set on the tasks of a batch, whose spawn was counted against the session's rate limit
+    /// once for the whole batch
+    #[serde(skip)]
+    pub spawn_counted: bool,
 }
 
 impl SpawnSubAgentArgs {
@@ -17,6 +116,12 @@ impl SpawnSubAgentArgs {
             message,
             max_turns: None,
             timeout_seconds: None,
+            deduplicate: false,
+            priority: None,
+            session_id: None,
+            tool_policy: ToolPolicy::default(),
+            verification_depth: 0,
+            spawn_counted: false,
         }
     }
 
@@ -27,6 +132,12 @@ impl SpawnSubAgentArgs {
             message,
             max_turns: None,
             timeout_seconds: None,
+            deduplicate: false,
+            priority: None,
+            session_id: None,
+            tool_policy: ToolPolicy::default(),
+            verification_depth: 0,
+            spawn_counted: false,
         }
     }
 
@@ -39,4 +150,341 @@ impl SpawnSubAgentArgs {
         self.timeout_seconds = Some(timeout_seconds);
         self
     }
+
+    pub fn with_deduplication(mut self, deduplicate: bool) -> Self {
+        self.deduplicate = deduplicate;
+        self
+    }
+
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Keep the requested priority if it is no higher than the spawning session's, so a model
+    /// can only lower it, and use the session's when none was requested
+    pub fn with_session_priority(mut self, session: Priority) -> Self {
+        self.priority = Some(self.priority.map_or(session, |p| p.min(session)));
+        self
+    }
+
+    /// The priority to run at, `Normal` when unset
+    pub fn priority(&self) -> Priority {
+        self.priority.unwrap_or_default()
+    }
+
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    pub fn with_tool_policy(mut self, tool_policy: ToolPolicy) -> Self {
+        self.tool_policy = tool_policy;
+        self
+    }
+
+    /// Hash of the task definition (recipe or instructions plus the message), used to
+    /// detect identical tasks that are running concurrently
+    pub fn task_fingerprint(&self) -> String {
+        let mut hasher = blake3::Hasher::new();
+        for part in [
+            self.recipe_name.as_deref().unwrap_or_default(),
+            self.instructions.as_deref().unwrap_or_default(),
+            self.message.as_str(),
+        ] {
+            hasher.update(part.as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+}
+
+/// One subagent's output relayed by the parent as input to another, e.g. a researcher's
+/// findings handed to a writer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubAgentLink {
+    pub from: String,
+    pub to: String,
+    /// The message the receiving subagent got
+    pub message: String,
+    /// The receiving subagent's reply, once it has answered
+    pub reply: Option<String>,
+    pub relayed_at: DateTime<Utc>,
+}
+
+/// Version of the subagent event contract seen by external consumers. Adding an event type or
+/// an optional field keeps the version; renaming or removing anything, or changing what a field
+/// means, bumps it.
+pub const SUBAGENT_EVENT_SCHEMA_VERSION: u32 = 1;
+
+/// What a subagent just did, broadcast to everyone subscribed to the manager's events so
+/// activity can be shown live instead of polling progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SubAgentEvent {
+    Spawned {
+        subagent_id: String,
+        /// Title of the recipe it runs, if any
+        recipe: Option<String>,
+    },
+    TurnStarted {
+        subagent_id: String,
+        turn: usize,
+    },
+    ToolCalled {
+        subagent_id: String,
+        tool: String,
+    },
+    /// Sent at the end of every turn, including turns that failed or were terminated
+    TurnCompleted {
+        subagent_id: String,
+        turn: usize,
+        /// How the turn ended, e.g. `Completed!`, `Rate limit exceeded` or `Error: ...`
+        message: String,
+    },
+    /// A task the subagent ran to completion is done
+    Completed {
+        subagent_id: String,
+        final_message: String,
+    },
+    Terminated {
+        subagent_id: String,
+    },
+}
+
+impl SubAgentEvent {
+    pub fn subagent_id(&self) -> &str {
+        match self {
+            SubAgentEvent::Spawned { subagent_id, .. }
+            | SubAgentEvent::TurnStarted { subagent_id, .. }
+            | SubAgentEvent::ToolCalled { subagent_id, .. }
+            | SubAgentEvent::TurnCompleted { subagent_id, .. }
+            | SubAgentEvent::Completed { subagent_id, .. }
+            | SubAgentEvent::Terminated { subagent_id } => subagent_id,
+        }
+    }
+}
+
+/// A subagent event as it leaves goose, e.g. over server-sent events: the event's own fields
+/// next to the schema version and when it was emitted. Consumers should skip event types they
+/// don't know rather than fail, since new ones are added without a version bump.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SubAgentEventEnvelope {
+    pub version: u32,
+    #[schemars(with = "String")]
+    pub emitted_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: SubAgentEvent,
+}
+
+impl SubAgentEventEnvelope {
+    pub fn new(event: SubAgentEvent) -> Self {
+        Self {
+            version: SUBAGENT_EVENT_SCHEMA_VERSION,
+            emitted_at: Utc::now(),
+            event,
+        }
+    }
+
+    /// The JSON Schema of the envelope, for consumers to generate types or validate against
+    pub fn json_schema() -> serde_json::Value {
+        serde_json::to_value(schemars::schema_for!(SubAgentEventEnvelope))
+            .expect("JSON Schema always serializes")
+    }
+}
+
+/// Whether a subagent may register an artifact under this name: a plain file name such as
+/// `report.md`, which can't reach outside the subagent's own artifacts
+pub fn is_valid_artifact_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_names_are_plain_file_names() {
+        assert!(is_valid_artifact_name("report.md"));
+        assert!(is_valid_artifact_name("patch_v2.diff"));
+        assert!(!is_valid_artifact_name(""));
+        assert!(!is_valid_artifact_name(".hidden"));
+        assert!(!is_valid_artifact_name("../escape.md"));
+        assert!(!is_valid_artifact_name("dir/report.md"));
+    }
+
+    #[test]
+    fn test_tool_policy_restrictions_only_narrow() {
+        let read_only = ToolPolicy {
+            allow: vec!["developer__read*".to_string(), "memory__*".to_string()],
+            deny: vec!["memory__remove*".to_string()],
+            ..Default::default()
+        };
+        assert!(read_only.is_allowed("developer__read_file"));
+        assert!(read_only.is_allowed("memory__retrieve_memories"));
+        assert!(!read_only.is_allowed("developer__shell"));
+        assert!(!read_only.is_allowed("memory__remove_memory_category"));
+        assert!(ToolPolicy::default().is_allowed("developer__shell"));
+
+        let narrowed = read_only.clone().restrict(ToolPolicy {
+            allow: vec!["memory__*".to_string(), "developer__shell".to_string()],
+            deny: vec!["memory__retrieve*".to_string()],
+            ..Default::default()
+        });
+        assert!(!narrowed.is_allowed("developer__shell"));
+        assert!(!narrowed.is_allowed("developer__read_file"));
+        assert!(!narrowed.is_allowed("memory__retrieve_memories"));
+        assert!(narrowed.is_allowed("memory__remember_memory"));
+
+        let nothing = read_only.restrict(ToolPolicy {
+            allow: vec!["developer__shell".to_string()],
+            ..Default::default()
+        });
+        assert!(!nothing.is_allowed("developer__shell"));
+        assert!(!nothing.is_allowed("memory__remember_memory"));
+    }
+
+    #[test]
+    fn test_tool_policy_restriction_with_a_wider_glob_does_not_widen() {
+        let configured = ToolPolicy {
+            allow: vec!["*_read*".to_string()],
+            ..Default::default()
+        };
+        // `{*,x_read}` looks like it only allows `x_read`, but matches every tool
+        let widening = configured.clone().restrict(ToolPolicy {
+            allow: vec!["{*,x_read}".to_string()],
+            ..Default::default()
+        });
+        assert!(widening.is_allowed("developer__read_file"));
+        assert!(widening.is_allowed("x_read"));
+        assert!(!widening.is_allowed("developer__shell"));
+
+        // Layers stay separate when restricted again
+        let twice = widening.restrict(ToolPolicy {
+            allow: vec!["developer__*".to_string()],
+            ..Default::default()
+        });
+        assert!(twice.is_allowed("developer__read_file"));
+        assert!(!twice.is_allowed("x_read"));
+        assert!(!twice.is_allowed("developer__shell"));
+    }
+
+    #[test]
+    fn test_read_only_policy_permits_only_read_only_tools() {
+        let tool = |name: &str, read_only: bool| {
+            Tool::new(
+                name,
+                "",
+                serde_json::json!({"type": "object"}),
+                Some(mcp_core::tool::ToolAnnotations::new().with_read_only(read_only)),
+            )
+        };
+        let policy = ToolPolicy::default().restrict(ToolPolicy::read_only());
+        assert!(policy.permits(&tool("developer__read_file", true)));
+        assert!(!policy.permits(&tool("developer__shell", false)));
+        assert!(!policy.permits(&Tool::new(
+            "github__search",
+            "",
+            serde_json::json!({"type": "object"}),
+            None
+        )));
+        assert!(ToolPolicy::default().permits(&tool("developer__shell", false)));
+    }
+
+    #[test]
+    fn test_subagent_events_are_tagged_by_type() {
+        let event = SubAgentEvent::ToolCalled {
+            subagent_id: "sa_1".to_string(),
+            tool: "developer__shell".to_string(),
+        };
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "tool_called");
+        assert_eq!(json["tool"], "developer__shell");
+        assert_eq!(event.subagent_id(), "sa_1");
+        assert_eq!(
+            serde_json::from_value::<SubAgentEvent>(json).unwrap(),
+            event
+        );
+    }
+
+    #[test]
+    fn test_subagent_event_envelope_is_flat_and_versioned() {
+        let envelope = SubAgentEventEnvelope::new(SubAgentEvent::Terminated {
+            subagent_id: "sa_1".to_string(),
+        });
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["version"], SUBAGENT_EVENT_SCHEMA_VERSION);
+        assert_eq!(json["type"], "terminated");
+        assert_eq!(json["subagent_id"], "sa_1");
+        assert!(json["emitted_at"].is_string());
+        assert_eq!(
+            serde_json::from_value::<SubAgentEventEnvelope>(json).unwrap(),
+            envelope
+        );
+
+        let schema = SubAgentEventEnvelope::json_schema();
+        assert_eq!(schema["title"], "SubAgentEventEnvelope");
+        let schema = schema.to_string();
+        assert!(schema.contains("\"tool_called\""));
+        assert!(schema.contains("\"emitted_at\""));
+    }
+
+    #[test]
+    fn test_task_fingerprint_matches_identical_tasks() {
+        let a =
+            SpawnSubAgentArgs::new_with_recipe("research".to_string(), "topic: rust".to_string());
+        let b =
+            SpawnSubAgentArgs::new_with_recipe("research".to_string(), "topic: rust".to_string())
+                .with_max_turns(3);
+        assert_eq!(a.task_fingerprint(), b.task_fingerprint());
+    }
+
+    #[test]
+    fn test_task_fingerprint_differs_by_source_and_message() {
+        let recipe = SpawnSubAgentArgs::new_with_recipe("research".to_string(), "go".to_string());
+        let instructions =
+            SpawnSubAgentArgs::new_with_instructions("research".to_string(), "go".to_string());
+        let other_message =
+            SpawnSubAgentArgs::new_with_recipe("research".to_string(), "stop".to_string());
+        assert_ne!(recipe.task_fingerprint(), instructions.task_fingerprint());
+        assert_ne!(recipe.task_fingerprint(), other_message.task_fingerprint());
+    }
+
+    #[test]
+    fn test_requested_priority_is_capped_at_the_session() {
+        let args: SpawnSubAgentArgs = serde_json::from_value(serde_json::json!({
+            "instructions": "Review",
+            "message": "Go",
+            "priority": "interactive"
+        }))
+        .unwrap();
+        assert_eq!(args.priority, Some(Priority::Interactive));
+        assert_eq!(
+            args.clone()
+                .with_session_priority(Priority::Normal)
+                .priority(),
+            Priority::Normal
+        );
+
+        let batch = args.with_priority(Priority::Batch);
+        assert_eq!(
+            batch
+                .with_session_priority(Priority::Interactive)
+                .priority(),
+            Priority::Batch
+        );
+        let unset =
+            SpawnSubAgentArgs::new_with_instructions("Review".to_string(), "Go".to_string());
+        assert_eq!(unset.priority(), Priority::Normal);
+        assert_eq!(
+            unset
+                .with_session_priority(Priority::Interactive)
+                .priority(),
+            Priority::Interactive
+        );
+    }
 }
@@ -0,0 +1,338 @@
+//! Tables of structured results written as CSV or XLSX artifacts.
+//!
+//! Every row is checked against the table's columns before anything is written, so a batch of
+//! results ends up as one table that can be sorted, filtered and aggregated.
+use std::io::Cursor;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    #[default]
+    String,
+    Integer,
+    Number,
+    Boolean,
+}
+
+impl ColumnType {
+    fn accepts(self, value: &Value) -> bool {
+        match self {
+            ColumnType::String => value.is_string(),
+            ColumnType::Integer => value.is_i64() || value.is_u64(),
+            ColumnType::Number => value.is_number(),
+            ColumnType::Boolean => value.is_boolean(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Column {
+    pub name: String,
+    #[serde(rename = "type", default)]
+    pub column_type: ColumnType,
+    /// Rows must give a value; other columns are left empty when missing or null
+    #[serde(default)]
+    pub required: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    Csv,
+    /// Stored base64-encoded
+    Xlsx,
+}
+
+impl TableFormat {
+    /// The format matching an artifact name's extension
+    pub fn from_name(name: &str) -> Option<Self> {
+        let (_, extension) = name.rsplit_once('.')?;
+        match extension.to_ascii_lowercase().as_str() {
+            "csv" => Some(TableFormat::Csv),
+            "xlsx" => Some(TableFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    /// The MIME type of an artifact written in this format
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "text/csv",
+            TableFormat::Xlsx => {
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TableError {
+    #[error("A table needs at least one column")]
+    NoColumns,
+    #[error("Column '{0}' is declared more than once")]
+    DuplicateColumn(String),
+    #[error("Row {row} is not an object of column values")]
+    NotAnObject { row: usize },
+    #[error("Row {row} has a value for unknown column '{column}'")]
+    UnknownColumn { row: usize, column: String },
+    #[error("Row {row} is missing required column '{column}'")]
+    MissingValue { row: usize, column: String },
+    #[error("Row {row} has a value for column '{column}' that is not a {expected:?}")]
+    WrongType {
+        row: usize,
+        column: String,
+        expected: ColumnType,
+    },
+    #[error("Unsupported table format for '{0}': use a .csv or .xlsx name")]
+    UnsupportedFormat(String),
+    #[error("Failed to write table: {0}")]
+    Write(String),
+}
+
+/// A table whose rows all match its columns
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    columns: Vec<Column>,
+    /// Cells in column order, `Null` where an optional value is missing
+    rows: Vec<Vec<Value>>,
+}
+
+impl Table {
+    /// Check rows, given as objects keyed by column name, against the columns
+    pub fn new(columns: Vec<Column>, rows: &[Value]) -> Result<Self, TableError> {
+        if columns.is_empty() {
+            return Err(TableError::NoColumns);
+        }
+        for (i, column) in columns.iter().enumerate() {
+            if columns[..i].iter().any(|c| c.name == column.name) {
+                return Err(TableError::DuplicateColumn(column.name.clone()));
+            }
+        }
+
+        let rows = rows
+            .iter()
+            .enumerate()
+            .map(|(index, row)| Self::check_row(&columns, index + 1, row))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { columns, rows })
+    }
+
+    fn check_row(columns: &[Column], row: usize, value: &Value) -> Result<Vec<Value>, TableError> {
+        let object: &Map<String, Value> =
+            value.as_object().ok_or(TableError::NotAnObject { row })?;
+        if let Some(unknown) = object
+            .keys()
+            .find(|key| !columns.iter().any(|c| &c.name == *key))
+        {
+            return Err(TableError::UnknownColumn {
+                row,
+                column: unknown.clone(),
+            });
+        }
+
+        columns
+            .iter()
+            .map(|column| match object.get(&column.name) {
+                None | Some(Value::Null) if column.required => Err(TableError::MissingValue {
+                    row,
+                    column: column.name.clone(),
+                }),
+                None | Some(Value::Null) => Ok(Value::Null),
+                Some(value) if column.column_type.accepts(value) => Ok(value.clone()),
+                Some(_) => Err(TableError::WrongType {
+                    row,
+                    column: column.name.clone(),
+                    expected: column.column_type,
+                }),
+            })
+            .collect()
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// The table as artifact content in the given format
+    pub fn write(&self, format: TableFormat) -> Result<String, TableError> {
+        match format {
+            TableFormat::Csv => self.to_csv(),
+            TableFormat::Xlsx => {
+                Ok(base64::engine::general_purpose::STANDARD.encode(self.to_xlsx()?))
+            }
+        }
+    }
+
+    fn to_csv(&self) -> Result<String, TableError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        let write_error = |e: csv::Error| TableError::Write(e.to_string());
+        writer
+            .write_record(self.columns.iter().map(|c| csv_text(c.name.clone())))
+            .map_err(write_error)?;
+        for row in &self.rows {
+            writer
+                .write_record(row.iter().map(|value| match value {
+                    Value::String(s) => csv_text(s.clone()),
+                    other => cell_text(other),
+                }))
+                .map_err(write_error)?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| TableError::Write(e.to_string()))?;
+        String::from_utf8(bytes).map_err(|e| TableError::Write(e.to_string()))
+    }
+
+    fn to_xlsx(&self) -> Result<Vec<u8>, TableError> {
+        let mut book = umya_spreadsheet::new_file();
+        let sheet = book
+            .get_sheet_by_name_mut("Sheet1")
+            .ok_or_else(|| TableError::Write("Missing default worksheet".to_string()))?;
+
+        // Cells are addressed (column, row), counting from 1
+        for (col, column) in (1u32..).zip(&self.columns) {
+            let cell = sheet.get_cell_mut((col, 1));
+            cell.set_value(column.name.clone());
+            cell.get_style_mut().get_font_mut().set_bold(true);
+        }
+        for (row, cells) in (2u32..).zip(&self.rows) {
+            for (col, value) in (1u32..).zip(cells) {
+                match value {
+                    Value::Null => {}
+                    Value::Bool(b) => {
+                        sheet.get_cell_mut((col, row)).set_value_bool(*b);
+                    }
+                    Value::Number(n) => {
+                        sheet
+                            .get_cell_mut((col, row))
+                            .set_value_number(n.as_f64().unwrap_or_default());
+                    }
+                    other => {
+                        sheet.get_cell_mut((col, row)).set_value(cell_text(other));
+                    }
+                }
+            }
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        umya_spreadsheet::writer::xlsx::write_writer(&book, &mut buffer)
+            .map_err(|e| TableError::Write(e.to_string()))?;
+        Ok(buffer.into_inner())
+    }
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Text that spreadsheet apps would run as a formula when opening the CSV, such as
+/// `=HYPERLINK(...)` or `@SUM(...)`, is prefixed with a quote so it is shown as text instead.
+/// Only text cells are escaped; numbers like `-5` stay numbers.
+fn csv_text(text: String) -> String {
+    if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", text)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn columns() -> Vec<Column> {
+        serde_json::from_value(json!([
+            {"name": "issue", "type": "integer", "required": true},
+            {"name": "title", "required": true},
+            {"name": "severity"},
+            {"name": "duplicate", "type": "boolean"}
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_rows_are_checked_against_columns() {
+        let table = Table::new(
+            columns(),
+            &[
+                json!({"issue": 12, "title": "Crash on start, sometimes", "severity": "high"}),
+                json!({"issue": 40, "title": "Typo", "duplicate": true, "severity": null}),
+            ],
+        )
+        .unwrap();
+        assert_eq!(table.row_count(), 2);
+        assert_eq!(
+            table.write(TableFormat::Csv).unwrap(),
+            "issue,title,severity,duplicate\n12,\"Crash on start, sometimes\",high,\n40,Typo,,true\n"
+        );
+
+        assert_eq!(
+            Table::new(columns(), &[json!({"title": "No number"})]),
+            Err(TableError::MissingValue {
+                row: 1,
+                column: "issue".to_string()
+            })
+        );
+        assert_eq!(
+            Table::new(columns(), &[json!({"issue": "12", "title": "Text"})]),
+            Err(TableError::WrongType {
+                row: 1,
+                column: "issue".to_string(),
+                expected: ColumnType::Integer
+            })
+        );
+        assert_eq!(
+            Table::new(
+                columns(),
+                &[json!({"issue": 1, "title": "T", "owner": "me"})]
+            ),
+            Err(TableError::UnknownColumn {
+                row: 1,
+                column: "owner".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_csv_formulas_are_written_as_text() {
+        let columns: Vec<Column> = serde_json::from_value(json!([
+            {"name": "=title"},
+            {"name": "delta", "type": "integer"}
+        ]))
+        .unwrap();
+        let table = Table::new(
+            columns,
+            &[
+                json!({"=title": "=HYPERLINK(\"http://x\")", "delta": -5}),
+                json!({"=title": "@SUM(A1)"}),
+                json!({"=title": "+1"}),
+                json!({"=title": "-1 fixed"}),
+                json!({"=title": "a=b"}),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            table.write(TableFormat::Csv).unwrap(),
+            "'=title,delta\n\"'=HYPERLINK(\"\"http://x\"\")\",-5\n'@SUM(A1),\n'+1,\n'-1 fixed,\na=b,\n"
+        );
+    }
+
+    #[test]
+    fn test_format_from_name() {
+        assert_eq!(TableFormat::from_name("triage.csv"), Some(TableFormat::Csv));
+        assert_eq!(
+            TableFormat::from_name("triage.XLSX"),
+            Some(TableFormat::Xlsx)
+        );
+        assert_eq!(TableFormat::from_name("triage.md"), None);
+        assert_eq!(TableFormat::from_name("triage"), None);
+    }
+}
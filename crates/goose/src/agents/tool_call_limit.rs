@@ -0,0 +1,69 @@
+use mcp_core::ToolError;
+
+use crate::config::Config;
+use crate::message::ToolRequest;
+
+/// Tool calls a single assistant turn may dispatch when `GOOSE_MAX_TOOL_CALLS_PER_TURN` is unset
+pub const DEFAULT_MAX_TOOL_CALLS_PER_TURN: usize = 25;
+
+/// The per-turn limit from `GOOSE_MAX_TOOL_CALLS_PER_TURN`, where 0 turns it off
+pub fn max_from_config() -> Option<usize> {
+    let max = Config::global()
+        .get_param("GOOSE_MAX_TOOL_CALLS_PER_TURN")
+        .unwrap_or(DEFAULT_MAX_TOOL_CALLS_PER_TURN);
+    (max > 0).then_some(max)
+}
+
+/// Split a turn's tool requests into those to dispatch, the first `max` in the order the model
+/// made them, and those over the limit
+pub fn split(
+    requests: Vec<ToolRequest>,
+    max: Option<usize>,
+) -> (Vec<ToolRequest>, Vec<ToolRequest>) {
+    match max {
+        Some(max) if requests.len() > max => {
+            let mut dispatched = requests;
+            let rejected = dispatched.split_off(max);
+            (dispatched, rejected)
+        }
+        _ => (requests, Vec::new()),
+    }
+}
+
+/// The error a tool request over the limit is answered with, telling the model what to do
+pub fn rejection(max: usize, requested: usize) -> ToolError {
+    ToolError::ExecutionError(format!(
+        "Not run: this turn asked for {} tool calls and only the first {} are run per turn. \
+        Call this tool again in your next turn if you still need it.",
+        requested, max
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    fn requests(count: usize) -> Vec<ToolRequest> {
+        (0..count)
+            .map(|i| ToolRequest {
+                id: format!("call_{}", i),
+                tool_call: Ok(ToolCall::new("developer__shell", json!({}))),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_keeps_the_first_requests() {
+        let (dispatched, rejected) = split(requests(5), Some(3));
+        assert_eq!(dispatched.len(), 3);
+        assert_eq!(rejected.len(), 2);
+        assert_eq!(dispatched[2].id, "call_2");
+        assert_eq!(rejected[0].id, "call_3");
+
+        let (dispatched, rejected) = split(requests(5), None);
+        assert_eq!(dispatched.len(), 5);
+        assert!(rejected.is_empty());
+    }
+}
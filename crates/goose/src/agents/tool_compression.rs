@@ -0,0 +1,172 @@
+use mcp_core::tool::Tool;
+use serde_json::Value;
+
+use super::platform_tools::PLATFORM_DESCRIBE_TOOL_TOOL_NAME;
+
+/// Longest tool description kept when schemas are compressed
+const MAX_TOOL_DESCRIPTION_CHARS: usize = 200;
+/// Longest parameter description kept when schemas are compressed
+const MAX_PARAMETER_DESCRIPTION_CHARS: usize = 100;
+
+/// Schema keywords whose value maps names to schemas, so its keys are names, not keywords
+const SCHEMA_MAPS: &[&str] = &[
+    "properties",
+    "patternProperties",
+    "definitions",
+    "$defs",
+    "dependentSchemas",
+];
+/// Schema keywords whose value is data rather than a schema
+const DATA_KEYWORDS: &[&str] = &["default", "const", "enum"];
+
+/// Whether tool schemas are compressed before being sent to the model, set with
+/// `GOOSE_COMPRESS_TOOL_SCHEMAS`. Compressing trades a `platform__describe_tool` round trip
+/// for a much smaller prompt when many tools are loaded.
+pub fn compression_enabled() -> bool {
+    crate::config::Config::global()
+        .get_param::<bool>("GOOSE_COMPRESS_TOOL_SCHEMAS")
+        .unwrap_or(false)
+}
+
+/// Shorten a tool's description to its first paragraph, and strip examples and long
+/// parameter descriptions from its input schema. The model can fetch the full tool with
+/// `platform__describe_tool`.
+pub fn compress_tool(tool: &Tool) -> Tool {
+    let mut compressed = tool.clone();
+    if tool.name == PLATFORM_DESCRIBE_TOOL_TOOL_NAME {
+        return compressed;
+    }
+
+    let first_paragraph = tool.description.trim().split("\n\n").next().unwrap_or("");
+    let description = truncate(first_paragraph, MAX_TOOL_DESCRIPTION_CHARS);
+    if description.len() < tool.description.trim().len() {
+        compressed.description = format!(
+            "{} (see {} for details)",
+            description, PLATFORM_DESCRIBE_TOOL_TOOL_NAME
+        );
+    }
+    compress_schema(&mut compressed.input_schema);
+    compressed
+}
+
+fn compress_schema(schema: &mut Value) {
+    match schema {
+        Value::Object(object) => {
+            object.remove("examples");
+            object.remove("example");
+            if let Some(Value::String(description)) = object.get_mut("description") {
+                let first_sentence = description
+                    .split_inclusive(". ")
+                    .next()
+                    .unwrap_or("")
+                    .trim_end();
+                *description = truncate(first_sentence, MAX_PARAMETER_DESCRIPTION_CHARS);
+            }
+            for (keyword, value) in object.iter_mut() {
+                if SCHEMA_MAPS.contains(&keyword.as_str()) {
+                    if let Value::Object(schemas) = value {
+                        schemas.values_mut().for_each(compress_schema);
+                    }
+                } else if !DATA_KEYWORDS.contains(&keyword.as_str()) {
+                    compress_schema(value);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(compress_schema),
+        _ => {}
+    }
+}
+
+/// Cut `text` to at most `max_chars` characters at a word boundary, collapsing whitespace
+fn truncate(text: &str, max_chars: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= max_chars {
+        return text;
+    }
+    let cut: String = text.chars().take(max_chars).collect();
+    let cut = match cut.rfind(' ') {
+        Some(index) => &cut[..index],
+        None => &cut[..],
+    };
+    format!("{}...", cut.trim_end_matches([',', '.', ';', ':']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compress_tool_strips_examples_and_long_descriptions() {
+        let tool = Tool::new(
+            "developer__shell",
+            "Execute a command in the shell.\n\nThis will return the output and error concatenated \
+             into a single string. Examples:\n  rg --files | rg example.py",
+            json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The command to run. It runs in the current working directory and can use pipes.",
+                        "examples": ["ls -la", "rg TODO"]
+                    }
+                }
+            }),
+            None,
+        );
+
+        let compressed = compress_tool(&tool);
+        assert_eq!(
+            compressed.description,
+            "Execute a command in the shell. (see platform__describe_tool for details)"
+        );
+        assert_eq!(
+            compressed.input_schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "command": {
+                        "type": "string",
+                        "description": "The command to run."
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_compress_schema_keeps_parameters_named_like_keywords() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "example": {"type": "string", "examples": ["a"]},
+                "examples": {
+                    "type": "array",
+                    "items": {"type": "string", "example": "b"},
+                    "default": [{"description": "Kept as is. Not a schema."}]
+                }
+            }
+        });
+        compress_schema(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "example": {"type": "string"},
+                    "examples": {
+                        "type": "array",
+                        "items": {"type": "string"},
+                        "default": [{"description": "Kept as is. Not a schema."}]
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_truncate_cuts_at_word_boundary() {
+        assert_eq!(truncate("short text", 20), "short text");
+        assert_eq!(truncate("one two three four", 12), "one two...");
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+
+use mcp_core::{Content, Tool};
+use serde::{Deserialize, Serialize};
+
+/// Estimated cost and latency of a single call to a tool
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolCost {
+    /// Estimated spend per call in USD, e.g. for paid search or compute APIs
+    #[serde(default)]
+    pub cost_usd: f64,
+    /// Typical time a call takes
+    #[serde(default)]
+    pub latency_ms: Option<u64>,
+}
+
+impl ToolCost {
+    /// Relative weight used to prefer cheaper tools; cost dominates, latency breaks ties
+    fn weight(&self) -> f64 {
+        self.cost_usd * 1_000.0 + self.latency_ms.unwrap_or(0) as f64 / 1_000.0
+    }
+
+    fn describe(&self) -> String {
+        match self.latency_ms {
+            Some(latency) => format!("~${:.4} per call, ~{}ms", self.cost_usd, latency),
+            None => format!("~${:.4} per call", self.cost_usd),
+        }
+    }
+}
+
+/// Configured cost weights for tools and the session budget they are checked against.
+///
+/// `GOOSE_TOOL_COSTS` maps prefixed tool names, or whole extensions, to their cost:
+///
+/// ```yaml
+/// GOOSE_TOOL_COSTS:
+///   search__web_search: { cost_usd: 0.01, latency_ms: 2000 }
+///   computercontroller: { latency_ms: 5000 }
+/// GOOSE_TOOL_COSTS_IN_DESCRIPTIONS: true
+/// GOOSE_SESSION_BUDGET_USD: 5.0
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolCosts {
+    costs: HashMap<String, ToolCost>,
+    annotate_descriptions: bool,
+    session_budget_usd: Option<f64>,
+}
+
+impl ToolCosts {
+    pub fn new(costs: HashMap<String, ToolCost>) -> Self {
+        Self {
+            costs,
+            ..Default::default()
+        }
+    }
+
+    pub fn from_config() -> Self {
+        let config = crate::config::Config::global();
+        Self {
+            costs: config
+                .get_param::<HashMap<String, ToolCost>>("GOOSE_TOOL_COSTS")
+                .unwrap_or_default(),
+            annotate_descriptions: config
+                .get_param("GOOSE_TOOL_COSTS_IN_DESCRIPTIONS")
+                .unwrap_or(false),
+            session_budget_usd: config.get_param("GOOSE_SESSION_BUDGET_USD").ok(),
+        }
+    }
+
+    pub fn with_annotated_descriptions(mut self, annotate: bool) -> Self {
+        self.annotate_descriptions = annotate;
+        self
+    }
+
+    pub fn with_session_budget(mut self, budget_usd: Option<f64>) -> Self {
+        self.session_budget_usd = budget_usd;
+        self
+    }
+
+    pub fn session_budget_usd(&self) -> Option<f64> {
+        self.session_budget_usd
+    }
+
+    /// The cost of a prefixed tool, falling back to the entry for its extension
+    pub fn cost_for(&self, tool_name: &str) -> Option<&ToolCost> {
+        self.costs.get(tool_name).or_else(|| {
+            tool_name
+                .split_once("__")
+                .and_then(|(extension, _)| self.costs.get(extension))
+        })
+    }
+
+    /// Append the cost to each tool's description, if enabled, so the model can prefer cheap tools
+    pub fn annotate(&self, tools: &mut [Tool]) {
+        if !self.annotate_descriptions {
+            return;
+        }
+        for tool in tools {
+            if let Some(cost) = self.cost_for(&tool.name) {
+                tool.description = format!("{} (Cost: {})", tool.description, cost.describe());
+            }
+        }
+    }
+
+    /// Order tools returned by the router from cheapest to most expensive and tag each with
+    /// its cost. Entries are `Tool: <name>\n...` text blocks; tools without a cost keep
+    /// their relative order ahead of priced ones.
+    pub fn rank_selected(&self, selected: Vec<Content>) -> Vec<Content> {
+        if self.costs.is_empty() {
+            return selected;
+        }
+
+        let mut ranked: Vec<(f64, Content)> = selected
+            .into_iter()
+            .map(|content| {
+                let cost = content
+                    .as_text()
+                    .and_then(|text| text.lines().next())
+                    .and_then(|line| line.strip_prefix("Tool:"))
+                    .and_then(|name| self.cost_for(name.trim()))
+                    .copied();
+                match cost {
+                    Some(cost) => {
+                        let text = content.as_text().unwrap_or_default();
+                        (
+                            cost.weight(),
+                            Content::text(format!("{}\nCost: {}", text, cost.describe())),
+                        )
+                    }
+                    None => (0.0, content),
+                }
+            })
+            .collect();
+        ranked.sort_by(|a, b| a.0.total_cmp(&b.0));
+        ranked.into_iter().map(|(_, content)| content).collect()
+    }
+
+    /// Refuse a call whose estimated cost is more than what is left of the session budget
+    pub fn check_budget(&self, tool_name: &str, spent_usd: f64) -> Result<(), String> {
+        let (Some(budget), Some(cost)) = (self.session_budget_usd, self.cost_for(tool_name)) else {
+            return Ok(());
+        };
+        let remaining = (budget - spent_usd).max(0.0);
+        if cost.cost_usd > remaining {
+            return Err(format!(
+                "Tool '{}' is estimated to cost ${:.4}, which exceeds the remaining session budget of ${:.4}",
+                tool_name, cost.cost_usd, remaining
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn costs() -> ToolCosts {
+        ToolCosts::new(HashMap::from([
+            (
+                "search__web_search".to_string(),
+                ToolCost {
+                    cost_usd: 0.05,
+                    latency_ms: Some(2000),
+                },
+            ),
+            (
+                "developer".to_string(),
+                ToolCost {
+                    cost_usd: 0.0,
+                    latency_ms: Some(100),
+                },
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_rank_selected_prefers_cheap_tools() {
+        let ranked = costs().rank_selected(vec![
+            Content::text("Tool: search__web_search\nDescription: Search the web"),
+            Content::text("Tool: developer__shell\nDescription: Run a command"),
+            Content::text("Tool: memory__remember\nDescription: Remember a fact"),
+        ]);
+
+        let names: Vec<&str> = ranked
+            .iter()
+            .map(|c| c.as_text().unwrap().lines().next().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Tool: memory__remember",
+                "Tool: developer__shell",
+                "Tool: search__web_search"
+            ]
+        );
+        assert!(ranked[2]
+            .as_text()
+            .unwrap()
+            .ends_with("Cost: ~$0.0500 per call, ~2000ms"));
+    }
+
+    #[test]
+    fn test_annotate_and_budget() {
+        let costs = costs()
+            .with_annotated_descriptions(true)
+            .with_session_budget(Some(1.0));
+
+        let mut tools = vec![Tool::new(
+            "search__web_search",
+            "Search the web",
+            json!({"type": "object"}),
+            None,
+        )];
+        costs.annotate(&mut tools);
+        assert!(tools[0].description.contains("(Cost: ~$0.0500 per call"));
+
+        assert!(costs.check_budget("search__web_search", 0.5).is_ok());
+        assert!(costs.check_budget("search__web_search", 0.97).is_err());
+        assert!(costs.check_budget("memory__remember", 5.0).is_ok());
+    }
+}
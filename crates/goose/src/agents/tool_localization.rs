@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use etcetera::{choose_app_strategy, AppStrategy};
+use mcp_core::Tool;
+use serde::Deserialize;
+use serde_json::Value;
+
+/// Localized text for one tool, either just the description or the description
+/// plus parameter descriptions
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum ToolTranslation {
+    Description(String),
+    Full {
+        #[serde(default)]
+        description: Option<String>,
+        #[serde(default)]
+        parameters: HashMap<String, String>,
+    },
+}
+
+/// Per-locale overrides for extension tool descriptions, keyed by prefixed tool name.
+///
+/// Mapping files live at `<config dir>/locales/<locale>.yaml` (or under
+/// `GOOSE_TOOL_LOCALES_DIR`) and look like:
+///
+/// ```yaml
+/// developer__shell: "Ejecuta un comando en la terminal"
+/// developer__text_editor:
+///   description: "Ver y editar archivos"
+///   parameters:
+///     path: "Ruta absoluta del archivo"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ToolLocalization {
+    locale: String,
+    translations: HashMap<String, ToolTranslation>,
+}
+
+impl ToolLocalization {
+    pub fn new(locale: impl Into<String>, translations: HashMap<String, ToolTranslation>) -> Self {
+        Self {
+            locale: locale.into(),
+            translations,
+        }
+    }
+
+    /// Load the mapping for `GOOSE_LOCALE`, if one is configured and a file exists for it
+    pub fn from_config() -> Option<Self> {
+        let config = crate::config::Config::global();
+        let locale = config.get_param::<String>("GOOSE_LOCALE").ok()?;
+        let dir = config
+            .get_param::<String>("GOOSE_TOOL_LOCALES_DIR")
+            .map(PathBuf::from)
+            .ok()
+            .or_else(|| {
+                choose_app_strategy(crate::config::APP_STRATEGY.clone())
+                    .ok()
+                    .map(|strategy| strategy.in_config_dir("locales"))
+            })?;
+
+        match Self::load(&dir, &locale) {
+            Ok(localization) => localization,
+            Err(e) => {
+                tracing::warn!("Failed to load tool localization for {}: {}", locale, e);
+                None
+            }
+        }
+    }
+
+    /// Load `<dir>/<locale>.yaml`, falling back to the base language (`es` for `es-MX`)
+    pub fn load(dir: &Path, locale: &str) -> Result<Option<Self>> {
+        let base_language = locale.split(['-', '_']).next().unwrap_or(locale);
+        for candidate in [locale, base_language] {
+            let path = dir.join(format!("{}.yaml", candidate));
+            if path.exists() {
+                let contents = std::fs::read_to_string(&path)?;
+                let translations = serde_yaml::from_str(&contents)?;
+                return Ok(Some(Self::new(candidate, translations)));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    /// Replace the description of a prefixed tool and its parameters where a translation exists
+    pub fn apply(&self, tool: &mut Tool) {
+        let Some(translation) = self.translations.get(&tool.name) else {
+            return;
+        };
+        match translation {
+            ToolTranslation::Description(description) => {
+                tool.description = description.clone();
+            }
+            ToolTranslation::Full {
+                description,
+                parameters,
+            } => {
+                if let Some(description) = description {
+                    tool.description = description.clone();
+                }
+                if let Some(properties) = tool
+                    .input_schema
+                    .get_mut("properties")
+                    .and_then(Value::as_object_mut)
+                {
+                    for (name, text) in parameters {
+                        if let Some(Value::Object(property)) = properties.get_mut(name) {
+                            property.insert("description".to_string(), Value::String(text.clone()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn shell_tool() -> Tool {
+        Tool::new(
+            "developer__shell",
+            "Run a shell command",
+            json!({
+                "type": "object",
+                "properties": {
+                    "command": {"type": "string", "description": "The command to run"}
+                }
+            }),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_apply_full_translation() {
+        let translations: HashMap<String, ToolTranslation> = serde_yaml::from_str(
+            r#"
+developer__shell:
+  description: "Ejecuta un comando"
+  parameters:
+    command: "El comando a ejecutar"
+    missing: "ignored"
+"#,
+        )
+        .unwrap();
+        let localization = ToolLocalization::new("es", translations);
+
+        let mut tool = shell_tool();
+        localization.apply(&mut tool);
+        assert_eq!(tool.description, "Ejecuta un comando");
+        assert_eq!(
+            tool.input_schema["properties"]["command"]["description"],
+            "El comando a ejecutar"
+        );
+        assert!(tool.input_schema["properties"].get("missing").is_none());
+    }
+
+    #[test]
+    fn test_load_falls_back_to_base_language() {
+        let dir = TempDir::new().unwrap();
+        std::fs::write(
+            dir.path().join("es.yaml"),
+            "developer__shell: \"Ejecuta un comando\"\n",
+        )
+        .unwrap();
+
+        let localization = ToolLocalization::load(dir.path(), "es-MX")
+            .unwrap()
+            .unwrap();
+        assert_eq!(localization.locale(), "es");
+
+        let mut tool = shell_tool();
+        localization.apply(&mut tool);
+        assert_eq!(tool.description, "Ejecuta un comando");
+
+        assert!(ToolLocalization::load(dir.path(), "fr").unwrap().is_none());
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use mcp_core::{Content, ToolResult};
+
+/// Outputs shorter than this are cheaper to repeat than to reference
+pub const DEFAULT_MIN_DEDUP_CHARS: usize = 256;
+
+/// Where an output was first seen in the session
+#[derive(Debug, Clone, PartialEq)]
+struct FirstSeen {
+    result_number: usize,
+    tool_name: String,
+    request_id: String,
+}
+
+/// Replaces tool output identical to an earlier result in the same session with a short
+/// reference, so polling-style tools don't fill the context with the same text over and over.
+///
+/// Off unless `GOOSE_TOOL_OUTPUT_DEDUP` is true; `GOOSE_TOOL_OUTPUT_DEDUP_MIN_CHARS` sets the
+/// smallest output worth replacing. The agent forgets the recorded outputs when it moves to
+/// another session.
+#[derive(Debug)]
+pub struct ToolOutputDedup {
+    enabled: bool,
+    min_chars: usize,
+    results: usize,
+    seen: HashMap<blake3::Hash, FirstSeen>,
+}
+
+impl Default for ToolOutputDedup {
+    fn default() -> Self {
+        Self::new(false, DEFAULT_MIN_DEDUP_CHARS)
+    }
+}
+
+impl ToolOutputDedup {
+    pub fn new(enabled: bool, min_chars: usize) -> Self {
+        Self {
+            enabled,
+            min_chars,
+            results: 0,
+            seen: HashMap::new(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        let config = crate::config::Config::global();
+        Self::new(
+            config.get_param("GOOSE_TOOL_OUTPUT_DEDUP").unwrap_or(false),
+            config
+                .get_param("GOOSE_TOOL_OUTPUT_DEDUP_MIN_CHARS")
+                .unwrap_or(DEFAULT_MIN_DEDUP_CHARS),
+        )
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Forget every recorded output, e.g. after the conversation was truncated or
+    /// summarized and the earlier results may no longer be visible to the model
+    pub fn clear(&mut self) {
+        self.results = 0;
+        self.seen.clear();
+    }
+
+    /// Number the result and swap any text already returned earlier in the session
+    /// for a reference to the result it first appeared in
+    pub fn process(
+        &mut self,
+        tool_name: &str,
+        request_id: &str,
+        result: ToolResult<Vec<Content>>,
+    ) -> ToolResult<Vec<Content>> {
+        let contents = result?;
+        if !self.enabled {
+            return Ok(contents);
+        }
+
+        self.results += 1;
+        let first_seen = FirstSeen {
+            result_number: self.results,
+            tool_name: tool_name.to_string(),
+            request_id: request_id.to_string(),
+        };
+        Ok(contents
+            .into_iter()
+            .map(|content| {
+                let Some(text) = content.as_text() else {
+                    return content;
+                };
+                if text.chars().count() < self.min_chars {
+                    return content;
+                }
+                let hash = blake3::hash(text.as_bytes());
+                match self.seen.get(&hash) {
+                    Some(earlier) => Content::text(format!(
+                        "[Same as result #{} ({} call {}): the output is identical, so it is not repeated here]",
+                        earlier.result_number, earlier.tool_name, earlier.request_id
+                    )),
+                    None => {
+                        self.seen.insert(hash, first_seen.clone());
+                        content
+                    }
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_repeated_output_is_replaced_with_reference() {
+        let mut dedup = ToolOutputDedup::new(true, 10);
+        let status = "build status: running, 3 of 12 steps complete";
+
+        let first = dedup
+            .process("ci__status", "call_1", Ok(vec![Content::text(status)]))
+            .unwrap();
+        assert_eq!(first[0].as_text(), Some(status));
+
+        let second = dedup
+            .process(
+                "ci__status",
+                "call_2",
+                Ok(vec![Content::text("ok"), Content::text(status)]),
+            )
+            .unwrap();
+        assert_eq!(second[0].as_text(), Some("ok"));
+        assert!(second[1]
+            .as_text()
+            .unwrap()
+            .starts_with("[Same as result #1 (ci__status call call_1)"));
+
+        dedup.clear();
+        let third = dedup
+            .process("ci__status", "call_3", Ok(vec![Content::text(status)]))
+            .unwrap();
+        assert_eq!(third[0].as_text(), Some(status));
+    }
+
+    #[test]
+    fn test_short_or_disabled_output_is_kept() {
+        let mut dedup = ToolOutputDedup::new(true, 10);
+        for request_id in ["call_1", "call_2"] {
+            let result = dedup
+                .process("shell", request_id, Ok(vec![Content::text("done")]))
+                .unwrap();
+            assert_eq!(result[0].as_text(), Some("done"));
+        }
+
+        let mut disabled = ToolOutputDedup::new(false, 0);
+        let text = "the same long output";
+        for request_id in ["call_1", "call_2"] {
+            let result = disabled
+                .process("shell", request_id, Ok(vec![Content::text(text)]))
+                .unwrap();
+            assert_eq!(result[0].as_text(), Some(text));
+        }
+    }
+}
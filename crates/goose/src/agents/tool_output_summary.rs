@@ -0,0 +1,227 @@
+use std::sync::Arc;
+
+use mcp_core::{Content, ToolResult};
+use tokio::sync::Mutex;
+
+use super::session_resources::{Artifact, SessionResources};
+use crate::message::Message;
+use crate::model::ModelConfig;
+use crate::providers::base::Provider;
+use crate::providers::pool::global_pool_manager;
+use crate::token_counter::CountTokens;
+
+const TOOL_OUTPUT_SUMMARY_PROMPT: &str = "You condense tool output for another assistant that called the tool. \
+Summarize the output below, keeping every detail that could matter for the task: identifiers, file paths, \
+numbers, error messages and anything that looks unexpected. Leave out repetition and boilerplate. \
+Reply with the summary only.";
+
+/// Share of the summarizing model's context window the raw output may fill
+const SUMMARY_INPUT_SHARE: f64 = 0.8;
+
+/// Settings for summarizing large tool outputs before they enter the conversation.
+///
+/// Enabled by setting `GOOSE_TOOL_OUTPUT_SUMMARY_THRESHOLD` to a token count. The summary is
+/// written by `GOOSE_TOOL_OUTPUT_SUMMARY_MODEL` (on `GOOSE_TOOL_OUTPUT_SUMMARY_PROVIDER`, or
+/// `GOOSE_PROVIDER` when unset) so a fast, cheap model can be used; without a model the
+/// session's own provider is used.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolOutputSummaryConfig {
+    pub threshold_tokens: usize,
+    pub provider: Option<String>,
+    pub model: Option<String>,
+}
+
+impl ToolOutputSummaryConfig {
+    pub fn from_config() -> Option<Self> {
+        let config = crate::config::Config::global();
+        let threshold_tokens = config
+            .get_param::<usize>("GOOSE_TOOL_OUTPUT_SUMMARY_THRESHOLD")
+            .ok()
+            .filter(|threshold| *threshold > 0)?;
+        Some(Self {
+            threshold_tokens,
+            provider: config
+                .get_param("GOOSE_TOOL_OUTPUT_SUMMARY_PROVIDER")
+                .or_else(|_| config.get_param("GOOSE_PROVIDER"))
+                .ok(),
+            model: config.get_param("GOOSE_TOOL_OUTPUT_SUMMARY_MODEL").ok(),
+        })
+    }
+
+    /// The provider that writes summaries, falling back to the session's provider
+    pub fn summary_provider(&self, session_provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        let (Some(provider_name), Some(model)) = (&self.provider, &self.model) else {
+            return session_provider;
+        };
+        match global_pool_manager()
+            .create_or_get_pooled(provider_name, ModelConfig::new(model.clone()))
+        {
+            Ok(provider) => provider,
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to create tool output summary model {}/{}: {}, using the session model",
+                    provider_name,
+                    model,
+                    e
+                );
+                session_provider
+            }
+        }
+    }
+}
+
+/// Replaces tool output over the token threshold with a summary and keeps the raw
+/// output as a session artifact the model can read back on demand
+pub struct ToolOutputSummarizer {
+    provider: Arc<dyn Provider>,
+    token_counter: Arc<dyn CountTokens>,
+    threshold_tokens: usize,
+    resources: Arc<Mutex<SessionResources>>,
+}
+
+impl ToolOutputSummarizer {
+    pub fn new(
+        provider: Arc<dyn Provider>,
+        token_counter: Arc<dyn CountTokens>,
+        threshold_tokens: usize,
+        resources: Arc<Mutex<SessionResources>>,
+    ) -> Self {
+        Self {
+            provider,
+            token_counter,
+            threshold_tokens,
+            resources,
+        }
+    }
+
+    pub async fn process(
+        &self,
+        tool_name: &str,
+        request_id: &str,
+        result: ToolResult<Vec<Content>>,
+    ) -> ToolResult<Vec<Content>> {
+        let contents = result?;
+        let mut processed = Vec::with_capacity(contents.len());
+        for (index, content) in contents.into_iter().enumerate() {
+            let Some(text) = content.as_text() else {
+                processed.push(content);
+                continue;
+            };
+            let tokens = self.token_counter.count_tokens(text);
+            if tokens <= self.threshold_tokens {
+                processed.push(content);
+                continue;
+            }
+
+            match self.summarize(tool_name, text, tokens).await {
+                Ok(summary) => {
+                    let name = match index {
+                        0 => format!("tool_output_{}", request_id),
+                        _ => format!("tool_output_{}_{}", request_id, index),
+                    };
+                    let uri = {
+                        let mut resources = self.resources.lock().await;
+                        resources.add_artifact(&name, Artifact::new(text, "text/plain"));
+                        format!(
+                            "goose://session/{}/artifacts/{}",
+                            resources.session_id(),
+                            name
+                        )
+                    };
+                    processed.push(Content::text(format!(
+                        "{}\n\n[Summary of {} tokens of output from {}. The full output can be read with platform__read_resource using uri: {}]",
+                        summary, tokens, tool_name, uri
+                    )));
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to summarize output of {}: {}", tool_name, e);
+                    processed.push(content);
+                }
+            }
+        }
+        Ok(processed)
+    }
+
+    async fn summarize(
+        &self,
+        tool_name: &str,
+        text: &str,
+        tokens: usize,
+    ) -> anyhow::Result<String> {
+        // Keep the request within the summarizing model's context, cutting the tail if needed
+        let budget = (self.provider.get_model_config().context_limit() as f64 * SUMMARY_INPUT_SHARE)
+            as usize;
+        let text = if tokens > budget {
+            let keep_chars = text.chars().count() * budget / tokens;
+            text.chars().take(keep_chars).collect::<String>()
+        } else {
+            text.to_string()
+        };
+
+        let message =
+            Message::user().with_text(format!("Output of the tool {}:\n\n{}", tool_name, text));
+        let (response, _) = self
+            .provider
+            .complete(TOOL_OUTPUT_SUMMARY_PROMPT, &[message], &[])
+            .await?;
+        let summary = response.as_concat_text();
+        if summary.trim().is_empty() {
+            anyhow::bail!("summary model returned no text");
+        }
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use mcp_core::tool::Tool;
+
+    struct WordCounter;
+
+    impl CountTokens for WordCounter {
+        fn count_tokens(&self, text: &str) -> usize {
+            text.split_whitespace().count()
+        }
+
+        fn count_chat_tokens(&self, _: &str, _: &[Message], _: &[Tool]) -> usize {
+            0
+        }
+    }
+
+    #[tokio::test]
+    async fn test_large_output_is_summarized_and_stored() {
+        let provider = Arc::new(MockProvider::default().with_text("3 files, one failing test"));
+        let resources = Arc::new(Mutex::new(SessionResources::default()));
+        let summarizer = ToolOutputSummarizer::new(
+            provider.clone(),
+            Arc::new(WordCounter),
+            10,
+            resources.clone(),
+        );
+
+        let large = "line of test output ".repeat(20);
+        let result = summarizer
+            .process(
+                "developer__shell",
+                "call_1",
+                Ok(vec![Content::text("ok"), Content::text(large.clone())]),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(result[0].as_text(), Some("ok"));
+        let summarized = result[1].as_text().unwrap();
+        assert!(summarized.starts_with("3 files, one failing test"));
+        assert!(summarized.contains("goose://session/current/artifacts/tool_output_call_1_1"));
+        assert_eq!(
+            resources
+                .lock()
+                .await
+                .read("goose://session/current/artifacts/tool_output_call_1_1"),
+            Some(large)
+        );
+        assert_eq!(provider.requests().len(), 1);
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::BTreeMap;
+
+use mcp_core::{role::Role, tool::Tool};
+use serde::{Deserialize, Serialize};
+
+use crate::context_mgmt::report::{ContextReport, ContextSource};
+use crate::message::{Message, MessageContent};
+use crate::token_counter::CountTokens;
+
+/// A part of the prompt sent to the provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptSection {
+    SystemPrompt,
+    Tools,
+    UserMessages,
+    AssistantMessages,
+    ToolRequests,
+    ToolResponses,
+}
+
+/// Estimated tokens of each section of a prompt
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptBreakdown {
+    pub sections: BTreeMap<PromptSection, usize>,
+}
+
+impl PromptBreakdown {
+    /// Sum up the [`ContextReport`] of a prompt by section. A message goes to the tool
+    /// sections when it carries a tool response or request, and to its role's otherwise.
+    pub fn measure(
+        counter: &dyn CountTokens,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Self {
+        let report = ContextReport::build(counter, system_prompt, messages, tools);
+        let mut breakdown = Self::default();
+        if report.system_prompt_tokens > 0 {
+            breakdown.add(PromptSection::SystemPrompt, report.system_prompt_tokens);
+        }
+        if report.tool_tokens > 0 {
+            breakdown.add(PromptSection::Tools, report.tool_tokens);
+        }
+
+        let message_items = report
+            .items
+            .iter()
+            .filter(|item| item.source == ContextSource::Message);
+        for (item, message) in message_items.zip(messages) {
+            breakdown.add(section_of(message), item.tokens);
+        }
+        breakdown
+    }
+
+    fn add(&mut self, section: PromptSection, tokens: usize) {
+        *self.sections.entry(section).or_default() += tokens;
+    }
+
+    pub fn total(&self) -> usize {
+        self.sections.values().sum()
+    }
+
+    fn get(&self, section: PromptSection) -> usize {
+        self.sections.get(&section).copied().unwrap_or(0)
+    }
+}
+
+fn section_of(message: &Message) -> PromptSection {
+    let contents = || message.content.iter();
+    if contents().any(|content| matches!(content, MessageContent::ToolResponse(_))) {
+        PromptSection::ToolResponses
+    } else if contents().any(|content| matches!(content, MessageContent::ToolRequest(_))) {
+        PromptSection::ToolRequests
+    } else {
+        match message.role {
+            Role::User => PromptSection::UserMessages,
+            Role::Assistant => PromptSection::AssistantMessages,
+        }
+    }
+}
+
+/// How one section of the prompt changed since the previous turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionDelta {
+    pub section: PromptSection,
+    pub tokens: usize,
+    pub delta: i64,
+}
+
+/// How the prompt of a turn compares to the one before it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TurnUsageDelta {
+    /// The provider call this describes, counting from 1
+    pub turn: usize,
+    /// Estimated prompt tokens
+    pub prompt_tokens: usize,
+    /// Change in estimated prompt tokens since the previous turn
+    pub prompt_growth: i64,
+    /// Every section of the prompt, the ones that grew the most first
+    pub sections: Vec<SectionDelta>,
+    /// Input tokens the provider reported for this turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<i32>,
+    /// Change in reported input tokens since the previous turn
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens_delta: Option<i64>,
+}
+
+impl TurnUsageDelta {
+    /// The section that grew the most this turn, if any grew
+    pub fn largest_contributor(&self) -> Option<&SectionDelta> {
+        self.sections.first().filter(|section| section.delta > 0)
+    }
+}
+
+/// Turn-by-turn prompt growth of a conversation
+#[derive(Debug, Clone, Default)]
+pub struct TurnUsageTracker {
+    previous: Option<(PromptBreakdown, Option<i32>)>,
+    history: Vec<TurnUsageDelta>,
+}
+
+impl TurnUsageTracker {
+    /// Record the prompt of the next turn and the input tokens the provider reported for it
+    pub fn record(
+        &mut self,
+        breakdown: PromptBreakdown,
+        input_tokens: Option<i32>,
+    ) -> &TurnUsageDelta {
+        let empty = PromptBreakdown::default();
+        let (previous, previous_input) = match &self.previous {
+            Some((breakdown, input_tokens)) => (breakdown, *input_tokens),
+            None => (&empty, None),
+        };
+
+        let mut sections: Vec<SectionDelta> = previous
+            .sections
+            .keys()
+            .chain(breakdown.sections.keys())
+            .copied()
+            .collect::<std::collections::BTreeSet<_>>()
+            .into_iter()
+            .map(|section| SectionDelta {
+                section,
+                tokens: breakdown.get(section),
+                delta: breakdown.get(section) as i64 - previous.get(section) as i64,
+            })
+            .collect();
+        sections.sort_by(|a, b| b.delta.cmp(&a.delta).then(a.section.cmp(&b.section)));
+
+        let delta = TurnUsageDelta {
+            turn: self.history.len() + 1,
+            prompt_tokens: breakdown.total(),
+            prompt_growth: breakdown.total() as i64 - previous.total() as i64,
+            sections,
+            input_tokens,
+            input_tokens_delta: match (input_tokens, previous_input) {
+                (Some(current), Some(previous)) => Some(current as i64 - previous as i64),
+                _ => None,
+            },
+        };
+        self.previous = Some((breakdown, input_tokens));
+        self.history.push(delta);
+        self.history.last().expect("just recorded a turn")
+    }
+
+    pub fn latest(&self) -> Option<&TurnUsageDelta> {
+        self.history.last()
+    }
+
+    pub fn history(&self) -> &[TurnUsageDelta] {
+        &self.history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_counter::TokenCounter;
+    use mcp_core::{Content, ToolCall};
+    use serde_json::json;
+
+    #[test]
+    fn test_tool_responses_show_up_as_growth() {
+        let counter = TokenCounter::new();
+        let mut tracker = TurnUsageTracker::default();
+        let mut messages = vec![Message::user().with_text("Summarize the repository")];
+
+        let first = tracker
+            .record(
+                PromptBreakdown::measure(&counter, "You are goose", &messages, &[]),
+                Some(100),
+            )
+            .clone();
+        assert_eq!(first.turn, 1);
+        assert_eq!(first.prompt_growth, first.prompt_tokens as i64);
+        // The sections add up the items of the context report
+        let report = ContextReport::build(&counter, "You are goose", &messages, &[]);
+        let report_tokens: usize = report.items.iter().map(|item| item.tokens).sum();
+        assert_eq!(first.prompt_tokens, report_tokens);
+        assert_eq!(first.input_tokens_delta, None);
+
+        messages.push(
+            Message::assistant()
+                .with_tool_request("1", Ok(ToolCall::new("read", json!({"path": "README"})))),
+        );
+        messages.push(Message::user().with_tool_response(
+            "1",
+            Ok(vec![Content::text(
+                "lorem ipsum dolor sit amet ".repeat(200),
+            )]),
+        ));
+        let second = tracker.record(
+            PromptBreakdown::measure(&counter, "You are goose", &messages, &[]),
+            Some(1200),
+        );
+
+        assert_eq!(second.turn, 2);
+        assert!(second.prompt_growth > 0);
+        assert_eq!(
+            second.largest_contributor().unwrap().section,
+            PromptSection::ToolResponses
+        );
+        assert_eq!(second.input_tokens_delta, Some(1100));
+        let system = second
+            .sections
+            .iter()
+            .find(|s| s.section == PromptSection::SystemPrompt)
+            .unwrap();
+        assert_eq!(system.delta, 0);
+    }
+}
@@ -1,6 +1,11 @@
+use crate::providers::base::Usage;
+use crate::providers::rate_limit::Priority;
 use crate::session;
+use async_trait::async_trait;
 use mcp_core::{Content, Tool, ToolResult};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::future::Future;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
@@ -15,6 +20,30 @@ pub struct FrontendTool {
     pub tool: Tool,
 }
 
+/// Runs a host tool registered through `Agent::register_platform_tool` in-process
+#[async_trait]
+pub trait PlatformToolHandler: Send + Sync {
+    async fn call(&self, arguments: Value) -> ToolResult<Vec<Content>>;
+}
+
+#[async_trait]
+impl<F, Fut> PlatformToolHandler for F
+where
+    F: Fn(Value) -> Fut + Send + Sync,
+    Fut: Future<Output = ToolResult<Vec<Content>>> + Send,
+{
+    async fn call(&self, arguments: Value) -> ToolResult<Vec<Content>> {
+        self(arguments).await
+    }
+}
+
+/// A tool provided by the application embedding goose rather than by an extension
+#[derive(Clone)]
+pub struct HostTool {
+    pub tool: Tool,
+    pub handler: Arc<dyn PlatformToolHandler>,
+}
+
 /// Session configuration for an agent
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionConfig {
@@ -29,3 +58,64 @@ pub struct SessionConfig {
     /// Maximum number of turns (iterations) allowed without user input
     pub max_turns: Option<u32>,
 }
+
+impl SessionConfig {
+    /// Scheduled and background sessions run as batch work, everything else has a user waiting on it
+    pub fn priority(&self) -> Priority {
+        if self.schedule_id.is_some() || self.execution_mode.as_deref() == Some("background") {
+            Priority::Batch
+        } else {
+            Priority::Interactive
+        }
+    }
+}
+
+/// Tokens and estimated cost accumulated over one reply, emitted as
+/// [`AgentEvent::Usage`](super::AgentEvent::Usage) after every provider call so clients can
+/// show a running total
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TurnUsage {
+    pub input_tokens: i32,
+    pub output_tokens: i32,
+    pub total_tokens: i32,
+    /// Estimated cost in USD of the calls whose model has a known price
+    pub cost_usd: f64,
+    /// Provider calls made so far in this reply
+    pub requests: u32,
+}
+
+impl TurnUsage {
+    pub fn add(&mut self, usage: &Usage) {
+        self.input_tokens += usage.input_tokens.unwrap_or(0);
+        self.output_tokens += usage.output_tokens.unwrap_or(0);
+        self.total_tokens += usage.total_tokens.unwrap_or(0);
+        self.cost_usd += usage.cost_usd.unwrap_or(0.0);
+        self.requests += 1;
+    }
+
+    /// Whether the reply has spent more than `budget_usd`
+    pub fn exceeds(&self, budget_usd: Option<f64>) -> bool {
+        budget_usd.is_some_and(|budget| self.cost_usd > budget)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_turn_usage_accumulates_across_requests() {
+        let mut turn = TurnUsage::default();
+        let mut usage = Usage::new(Some(100), Some(20), Some(120));
+        usage.cost_usd = Some(0.02);
+        turn.add(&usage);
+        turn.add(&Usage::new(Some(50), None, Some(50)));
+
+        assert_eq!(turn.total_tokens, 170);
+        assert_eq!(turn.output_tokens, 20);
+        assert_eq!(turn.requests, 2);
+        assert!(turn.exceeds(Some(0.01)));
+        assert!(!turn.exceeds(Some(0.05)));
+        assert!(!turn.exceeds(None));
+    }
+}
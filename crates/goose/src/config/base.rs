@@ -9,6 +9,7 @@ use std::env;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::RwLock;
 use thiserror::Error;
 
 pub static APP_STRATEGY: Lazy<AppStrategyArgs> = Lazy::new(|| AppStrategyArgs {
@@ -106,6 +107,9 @@ impl From<keyring::Error> for ConfigError {
 pub struct Config {
     config_path: PathBuf,
     secrets: SecretStorage,
+    /// Short-lived secrets issued at runtime, e.g. by a credential refresh command.
+    /// Kept in memory only and checked before the environment and keyring.
+    runtime_secrets: RwLock<HashMap<String, Value>>,
 }
 
 enum SecretStorage {
@@ -140,6 +144,7 @@ impl Default for Config {
         Config {
             config_path,
             secrets,
+            runtime_secrets: RwLock::new(HashMap::new()),
         }
     }
 }
@@ -163,6 +168,7 @@ impl Config {
             secrets: SecretStorage::Keyring {
                 service: service.to_string(),
             },
+            runtime_secrets: RwLock::new(HashMap::new()),
         })
     }
 
@@ -179,6 +185,7 @@ impl Config {
             secrets: SecretStorage::File {
                 path: secrets_path.as_ref().to_path_buf(),
             },
+            runtime_secrets: RwLock::new(HashMap::new()),
         })
     }
 
@@ -621,7 +628,18 @@ impl Config {
     /// - The value cannot be deserialized into the requested type
     /// - There is an error accessing the keyring
     pub fn get_secret<T: for<'de> Deserialize<'de>>(&self, key: &str) -> Result<T, ConfigError> {
-        // First check environment variables (convert to uppercase)
+        // Secrets issued at runtime replace whatever was configured up front
+        if let Some(value) = self
+            .runtime_secrets
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(key)
+            .cloned()
+        {
+            return Ok(serde_json::from_value(value)?);
+        }
+
+        // Then check environment variables (convert to uppercase)
         let env_key = key.to_uppercase();
         if let Ok(val) = env::var(&env_key) {
             let value: Value = serde_json::from_str(&val).unwrap_or(Value::String(val));
@@ -636,6 +654,25 @@ impl Config {
             .and_then(|v| Ok(serde_json::from_value(v.clone())?))
     }
 
+    /// Set a secret for this process only, without writing it to the keyring or secrets file.
+    ///
+    /// Used for short-lived credentials such as STS or OAuth tokens that are refreshed while
+    /// goose runs. The value takes precedence over environment variables and stored secrets.
+    pub fn set_runtime_secret(&self, key: &str, value: Value) {
+        self.runtime_secrets
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(key.to_string(), value);
+    }
+
+    /// Remove a secret set with [`Config::set_runtime_secret`]
+    pub fn clear_runtime_secret(&self, key: &str) {
+        self.runtime_secrets
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(key);
+    }
+
     /// Set a secret value in the system keyring.
     ///
     /// This will store the value in a single JSON object in the system keyring,
@@ -875,6 +912,28 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_runtime_secret_overrides_stored_secret() -> Result<(), ConfigError> {
+        let config_file = NamedTempFile::new().unwrap();
+        let secrets_file = NamedTempFile::new().unwrap();
+        let config = Config::new_with_file_secrets(config_file.path(), secrets_file.path())?;
+        config.set_secret("token", Value::String("stored".to_string()))?;
+
+        config.set_runtime_secret("token", Value::String("issued".to_string()));
+        let value: String = config.get_secret("token")?;
+        assert_eq!(value, "issued");
+        // Never persisted
+        assert_eq!(
+            config.load_secrets()?.get("token"),
+            Some(&Value::from("stored"))
+        );
+
+        config.clear_runtime_secret("token");
+        let value: String = config.get_secret("token")?;
+        assert_eq!(value, "stored");
+        Ok(())
+    }
+
     #[test]
     #[serial]
     fn test_secret_management() -> Result<(), ConfigError> {
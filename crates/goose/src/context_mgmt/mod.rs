@@ -1,4 +1,5 @@
 mod common;
+pub mod report;
 pub mod summarize;
 pub mod truncate;
 
@@ -0,0 +1,192 @@
+use mcp_core::tool::Tool;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::message::Message;
+use crate::token_counter::CountTokens;
+
+/// Heading the prompt manager puts before instructions added to the system prompt
+const ADDITIONAL_INSTRUCTIONS_HEADING: &str = "# Additional Instructions:";
+
+/// Where a part of the prompt comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextSource {
+    /// A section of the rendered system prompt template, such as an extension's instructions
+    SystemSection,
+    /// An instruction added to the system prompt, such as recipe or memory instructions
+    PinnedInstruction,
+    /// The schema of a tool
+    ToolSchema,
+    /// A message of the conversation
+    Message,
+}
+
+/// One part of the prompt and the tokens it takes up
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContextItem {
+    pub source: ContextSource,
+    /// The section heading, tool name, or message index and role
+    pub name: String,
+    pub tokens: usize,
+}
+
+/// What a prompt is made of, to find what to slim down when it gets too large
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ContextReport {
+    pub total_tokens: usize,
+    pub system_prompt_tokens: usize,
+    pub tool_tokens: usize,
+    pub message_tokens: usize,
+    /// Every part of the prompt in the order it is sent
+    pub items: Vec<ContextItem>,
+}
+
+impl ContextReport {
+    pub fn build(
+        counter: &dyn CountTokens,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Self {
+        let mut report = Self::default();
+
+        for (source, name, text) in system_prompt_sections(system_prompt) {
+            let tokens = counter.count_tokens(text);
+            report.system_prompt_tokens += tokens;
+            report.items.push(ContextItem {
+                source,
+                name,
+                tokens,
+            });
+        }
+
+        let empty_request = counter.count_chat_tokens("", &[], &[]);
+        for tool in tools {
+            let tokens = counter
+                .count_chat_tokens("", &[], std::slice::from_ref(tool))
+                .saturating_sub(empty_request);
+            report.tool_tokens += tokens;
+            report.items.push(ContextItem {
+                source: ContextSource::ToolSchema,
+                name: tool.name.clone(),
+                tokens,
+            });
+        }
+
+        for (index, message) in messages.iter().enumerate() {
+            let tokens = counter
+                .count_chat_tokens("", std::slice::from_ref(message), &[])
+                .saturating_sub(empty_request);
+            report.message_tokens += tokens;
+            report.items.push(ContextItem {
+                source: ContextSource::Message,
+                name: format!("#{} {:?}", index, message.role).to_lowercase(),
+                tokens,
+            });
+        }
+
+        report.total_tokens = counter.count_chat_tokens(system_prompt, messages, tools);
+        report
+    }
+
+    /// The `n` largest parts of the prompt, largest first
+    pub fn largest(&self, n: usize) -> Vec<&ContextItem> {
+        let mut items: Vec<&ContextItem> = self.items.iter().collect();
+        items.sort_by_key(|item| std::cmp::Reverse(item.tokens));
+        items.truncate(n);
+        items
+    }
+}
+
+/// Split a rendered system prompt at its top-level and extension headings. Text before the
+/// first heading is the preamble, and each instruction under the additional instructions
+/// heading is its own section.
+fn system_prompt_sections(system_prompt: &str) -> Vec<(ContextSource, String, &str)> {
+    let (template, extras) = match system_prompt.split_once(ADDITIONAL_INSTRUCTIONS_HEADING) {
+        Some((template, extras)) => (template, Some(extras)),
+        None => (system_prompt, None),
+    };
+
+    let mut sections = Vec::new();
+    let mut name = "Preamble".to_string();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in template.split_inclusive('\n') {
+        let heading = line.trim_end();
+        if heading.starts_with("# ") || heading.starts_with("## ") {
+            if !template[start..offset].trim().is_empty() {
+                sections.push((ContextSource::SystemSection, name, &template[start..offset]));
+            }
+            name = heading.to_string();
+            start = offset;
+        }
+        offset += line.len();
+    }
+    if !template[start..].trim().is_empty() {
+        sections.push((ContextSource::SystemSection, name, &template[start..]));
+    }
+
+    if let Some(extras) = extras {
+        for (index, instruction) in extras
+            .split("\n\n")
+            .filter(|instruction| !instruction.trim().is_empty())
+            .enumerate()
+        {
+            sections.push((
+                ContextSource::PinnedInstruction,
+                format!("Additional instruction {}", index + 1),
+                instruction,
+            ));
+        }
+    }
+    sections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token_counter::TokenCounter;
+    use serde_json::json;
+
+    #[test]
+    fn test_report_breaks_down_prompt_by_source() {
+        let system_prompt = "You are goose.\n\n# Extensions\n\nSome text.\n\n## developer\n### Instructions\nUse the shell.\n\n## memory\nRemember things.\n\n# Additional Instructions:\n\nAlways answer in French.\n\nYou are in chat mode.";
+        let tools = vec![Tool::new(
+            "developer__shell",
+            "Run a shell command",
+            json!({"type": "object", "properties": {"command": {"type": "string"}}}),
+            None,
+        )];
+        let messages = vec![
+            Message::user().with_text("hello"),
+            Message::assistant().with_text("hi there, how can I help?"),
+        ];
+
+        let report = ContextReport::build(&TokenCounter::new(), system_prompt, &messages, &tools);
+        let names: Vec<&str> = report.items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Preamble",
+                "# Extensions",
+                "## developer",
+                "## memory",
+                "Additional instruction 1",
+                "Additional instruction 2",
+                "developer__shell",
+                "#0 user",
+                "#1 assistant",
+            ]
+        );
+        assert_eq!(report.items[4].source, ContextSource::PinnedInstruction);
+        assert!(report.tool_tokens > 0);
+        assert_eq!(
+            report.message_tokens,
+            report.items[7].tokens + report.items[8].tokens
+        );
+        let largest = report.largest(3);
+        assert_eq!(largest.len(), 3);
+        assert!(largest[0].tokens >= largest[1].tokens && largest[1].tokens >= largest[2].tokens);
+    }
+}
@@ -254,6 +254,7 @@ mod tests {
                         text: "Summarized content".to_string(),
                         annotations: None,
                     })],
+                    finish_reason: None,
                 },
                 ProviderUsage::new("mock".to_string(), Usage::default()),
             ))
@@ -281,6 +282,7 @@ mod tests {
             role,
             created: 0,
             content: vec![MessageContent::text(text.to_string())],
+            finish_reason: None,
         }
     }
 
@@ -289,6 +291,7 @@ mod tests {
             role: Role::Assistant,
             created: 0,
             content: vec![MessageContent::tool_request(id.to_string(), Ok(tool_call))],
+            finish_reason: None,
         }
     }
 
@@ -300,6 +303,7 @@ mod tests {
                 id.to_string(),
                 Ok(tool_response),
             )],
+            finish_reason: None,
         }
     }
 
@@ -455,6 +459,7 @@ mod tests {
                 text: "Summary".to_string(),
                 annotations: None,
             })],
+            finish_reason: None,
         }];
         let arguments = json!({
             "param1": "value1"
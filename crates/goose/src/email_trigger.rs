@@ -0,0 +1,259 @@
+//! Email-driven workflows: run a recipe for every incoming email that matches a rule.
+//!
+//! Emails arrive from an email provider's inbound webhook, normalized to [`InboundEmail`].
+//! Each rule in `GOOSE_EMAIL_TRIGGERS` names a recipe and the emails it handles; the email's
+//! fields are available to the rule's task template as `from`, `to`, `subject`, `body` and
+//! `message_id`.
+//!
+//! Only emails from the senders in `GOOSE_EMAIL_TRIGGER_SENDERS` run anything, and the
+//! recipes they run only get read-only tools unless a rule says otherwise. The sender is
+//! whatever the provider reports, so have it verify SPF and DKIM before forwarding.
+use std::collections::{HashSet, VecDeque};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::agents::subagent_types::ToolPolicy;
+use crate::config::Config;
+use crate::prompt_template::render_inline_once;
+
+/// Spawn rate limits apply to all email-triggered subagents together, under this key
+pub const EMAIL_TRIGGER_SESSION_ID: &str = "email-triggers";
+
+/// Message IDs remembered to ignore a provider delivering the same email twice
+const MAX_SEEN_MESSAGES: usize = 1_000;
+
+const DEFAULT_TASK_TEMPLATE: &str = "Handle this email.\n\nFrom: {{ from }}\nTo: {{ to | join(', ') }}\nSubject: {{ subject }}\n\n{{ body }}";
+
+/// An email as delivered by a provider's inbound webhook
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct InboundEmail {
+    pub from: String,
+    #[serde(default)]
+    pub to: Vec<String>,
+    #[serde(default)]
+    pub subject: String,
+    /// Plain text body
+    #[serde(default)]
+    pub body: String,
+    /// The `Message-ID` header, used to ignore duplicate deliveries
+    #[serde(default)]
+    pub message_id: Option<String>,
+}
+
+/// Run `recipe` for emails matching every condition given; a rule without conditions matches
+/// every email. Conditions match case-insensitively on substrings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmailRule {
+    pub recipe: String,
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// Task given to the recipe, rendered with the email's fields
+    #[serde(default)]
+    pub task: Option<String>,
+    /// Tools the recipe may call, read-only ones when unset
+    #[serde(default = "ToolPolicy::read_only")]
+    pub tool_policy: ToolPolicy,
+}
+
+fn contains(haystack: &str, needle: &Option<String>) -> bool {
+    needle
+        .as_ref()
+        .is_none_or(|needle| haystack.to_lowercase().contains(&needle.to_lowercase()))
+}
+
+impl EmailRule {
+    pub fn matches(&self, email: &InboundEmail) -> bool {
+        contains(&email.from, &self.from)
+            && (self.to.is_none() || email.to.iter().any(|to| contains(to, &self.to)))
+            && contains(&email.subject, &self.subject)
+    }
+
+    /// The task to run the rule's recipe with for an email
+    pub fn task(&self, email: &InboundEmail) -> Result<String> {
+        let template = self.task.as_deref().unwrap_or(DEFAULT_TASK_TEMPLATE);
+        Ok(render_inline_once(template, email)?)
+    }
+}
+
+/// The address of a `From` value such as `Alice <alice@example.com>`, lowercased
+fn sender_address(from: &str) -> String {
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    };
+    address.trim().to_lowercase()
+}
+
+/// The configured rules and the emails already handled
+pub struct EmailTriggers {
+    rules: Vec<EmailRule>,
+    /// Addresses, or domains written as `@example.com`, whose emails may run rules
+    senders: Vec<String>,
+    seen: Mutex<(HashSet<String>, VecDeque<String>)>,
+}
+
+impl EmailTriggers {
+    pub fn new(rules: Vec<EmailRule>, senders: Vec<String>) -> Self {
+        Self {
+            rules,
+            senders: senders.iter().map(|sender| sender.to_lowercase()).collect(),
+            seen: Mutex::new((HashSet::new(), VecDeque::new())),
+        }
+    }
+
+    /// Rules configured in `GOOSE_EMAIL_TRIGGERS` and the senders allowed to run them in
+    /// `GOOSE_EMAIL_TRIGGER_SENDERS`, none when unset
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        let triggers = Self::new(
+            config.get_param("GOOSE_EMAIL_TRIGGERS").unwrap_or_default(),
+            config
+                .get_param("GOOSE_EMAIL_TRIGGER_SENDERS")
+                .unwrap_or_default(),
+        );
+        if !triggers.rules.is_empty() && triggers.senders.is_empty() {
+            tracing::warn!(
+                "GOOSE_EMAIL_TRIGGERS is set without GOOSE_EMAIL_TRIGGER_SENDERS, so no email \
+                 will run a recipe"
+            );
+        }
+        triggers
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    fn is_allowed_sender(&self, email: &InboundEmail) -> bool {
+        let address = sender_address(&email.from);
+        self.senders
+            .iter()
+            .any(|sender| match sender.strip_prefix('@') {
+                Some(domain) => address
+                    .rsplit_once('@')
+                    .is_some_and(|(_, address_domain)| address_domain == domain),
+                None => *sender == address,
+            })
+    }
+
+    fn is_seen(&self, message_id: &str) -> bool {
+        let seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.0.contains(message_id)
+    }
+
+    /// The rules an email should run: none if its sender isn't allowed or it was already
+    /// handled. Call [`EmailTriggers::mark_handled`] once its runs have started.
+    pub fn matching(&self, email: &InboundEmail) -> Vec<&EmailRule> {
+        if !self.is_allowed_sender(email) {
+            tracing::warn!("Ignoring email from {}, not an allowed sender", email.from);
+            return Vec::new();
+        }
+        if email
+            .message_id
+            .as_deref()
+            .is_some_and(|message_id| self.is_seen(message_id))
+        {
+            return Vec::new();
+        }
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(email))
+            .collect()
+    }
+
+    /// Remember the email as handled, so a redelivery doesn't run its rules again. An email
+    /// whose runs failed to start isn't marked, so the provider's retry runs it.
+    pub fn mark_handled(&self, email: &InboundEmail) {
+        let Some(message_id) = &email.message_id else {
+            return;
+        };
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let (ids, order) = &mut *seen;
+        if !ids.insert(message_id.clone()) {
+            return;
+        }
+        order.push_back(message_id.clone());
+        if order.len() > MAX_SEEN_MESSAGES {
+            if let Some(oldest) = order.pop_front() {
+                ids.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email() -> InboundEmail {
+        InboundEmail {
+            from: "Alice <alice@example.com>".to_string(),
+            to: vec!["support@example.com".to_string()],
+            subject: "Invoice #42 is wrong".to_string(),
+            body: "The total is off by one.".to_string(),
+            message_id: Some("<42@example.com>".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_matching_rules_run_once_per_message() {
+        let triggers = EmailTriggers::new(
+            vec![
+                EmailRule {
+                    recipe: "billing".to_string(),
+                    from: None,
+                    to: Some("SUPPORT@".to_string()),
+                    subject: Some("invoice".to_string()),
+                    task: Some("Fix: {{ subject }} ({{ from }})".to_string()),
+                    tool_policy: ToolPolicy::read_only(),
+                },
+                EmailRule {
+                    recipe: "sales".to_string(),
+                    from: Some("@customer.com".to_string()),
+                    to: None,
+                    subject: None,
+                    task: None,
+                    tool_policy: ToolPolicy::read_only(),
+                },
+            ],
+            vec!["@Example.com".to_string()],
+        );
+
+        let matched = triggers.matching(&email());
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].recipe, "billing");
+        assert_eq!(
+            matched[0].task(&email()).unwrap(),
+            "Fix: Invoice #42 is wrong (Alice <alice@example.com>)"
+        );
+
+        // Until its runs have started, a redelivery runs it again
+        assert_eq!(triggers.matching(&email()).len(), 1);
+        triggers.mark_handled(&email());
+        assert!(triggers.matching(&email()).is_empty());
+    }
+
+    #[test]
+    fn test_only_allowed_senders_run_rules() {
+        let rule: EmailRule = serde_json::from_str(r#"{"recipe": "triage"}"#).unwrap();
+        assert_eq!(rule.tool_policy, ToolPolicy::read_only());
+
+        let triggers = EmailTriggers::new(vec![rule.clone()], Vec::new());
+        assert!(triggers.matching(&email()).is_empty());
+
+        let triggers = EmailTriggers::new(vec![rule], vec!["alice@example.com".to_string()]);
+        assert_eq!(triggers.matching(&email()).len(), 1);
+        let spoofed = InboundEmail {
+            from: "alice@example.com <mallory@evil.example>".to_string(),
+            ..email()
+        };
+        assert!(triggers.matching(&spoofed).is_empty());
+    }
+}
@@ -1,12 +1,17 @@
 pub mod agents;
 pub mod config;
 pub mod context_mgmt;
+pub mod email_trigger;
+pub mod markdown_stream;
 pub mod message;
 pub mod model;
+pub mod offline_queue;
+pub mod outbox;
 pub mod permission;
 pub mod prompt_template;
 pub mod providers;
 pub mod recipe;
+pub mod schedule_constraints;
 pub mod scheduler;
 pub mod scheduler_factory;
 pub mod scheduler_trait;
@@ -15,6 +20,7 @@ pub mod temporal_scheduler;
 pub mod token_counter;
 pub mod tool_monitor;
 pub mod tracing;
+pub mod watcher;
 
 #[cfg(test)]
 mod cron_test;
@@ -0,0 +1,270 @@
+//! Incremental markdown for text that arrives a few tokens at a time.
+//!
+//! Rendering a half-received answer as markdown goes wrong in familiar ways: an open code fence
+//! turns the rest of the answer into code, a table is laid out again with every row that
+//! arrives, and a paragraph reflows as its lines come in. [`MarkdownStream`] holds text back
+//! until the block it belongs to can be rendered for good, and hands out [`MarkdownChunk`]s
+//! that never change once emitted, so a UI can print them as they come.
+
+/// A piece of streamed markdown that is safe to render and won't change afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkdownChunk {
+    /// A complete block of ordinary markdown, such as a paragraph, a heading or a list item,
+    /// ending with a newline
+    Markdown(String),
+    /// A fenced code block opened, with the language of its info string if it has one
+    CodeStart { language: Option<String> },
+    /// A complete line of the open code block, verbatim. Code doesn't reflow, so it is
+    /// streamed line by line instead of waiting for the closing fence.
+    Code(String),
+    /// The open code block closed
+    CodeEnd,
+    /// A whole table, header, delimiter row and body, once its last row arrived
+    Table(String),
+}
+
+#[derive(Debug)]
+struct Fence {
+    marker: char,
+    len: usize,
+}
+
+/// Splits streamed markdown into render-safe chunks; see the module documentation
+#[derive(Debug, Default)]
+pub struct MarkdownStream {
+    /// Text after the last newline
+    partial: String,
+    /// Lines of the block in progress
+    block: String,
+    /// Lines of the table in progress
+    table: Vec<String>,
+    fence: Option<Fence>,
+}
+
+impl MarkdownStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add streamed text and take the chunks it completed
+    pub fn push(&mut self, text: &str) -> Vec<MarkdownChunk> {
+        self.partial.push_str(text);
+        let mut chunks = Vec::new();
+        while let Some(end) = self.partial.find('\n') {
+            let line: String = self.partial.drain(..=end).collect();
+            self.line(line, &mut chunks);
+        }
+        chunks
+    }
+
+    /// End of the stream: take everything still held back. An unterminated code block is
+    /// closed and a table without its delimiter row is given back as plain markdown.
+    pub fn finish(&mut self) -> Vec<MarkdownChunk> {
+        let mut chunks = Vec::new();
+        if !self.partial.is_empty() {
+            let mut line = std::mem::take(&mut self.partial);
+            line.push('\n');
+            self.line(line, &mut chunks);
+        }
+        self.flush_table(&mut chunks);
+        self.flush_block(&mut chunks);
+        if self.fence.take().is_some() {
+            chunks.push(MarkdownChunk::CodeEnd);
+        }
+        chunks
+    }
+
+    /// Text received but not emitted yet, e.g. to show a plain preview that the next chunks
+    /// replace
+    pub fn pending(&self) -> String {
+        let mut pending = self.table.concat();
+        pending.push_str(&self.block);
+        pending.push_str(&self.partial);
+        pending
+    }
+
+    fn line(&mut self, line: String, chunks: &mut Vec<MarkdownChunk>) {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+
+        if let Some(fence) = &self.fence {
+            if closes(trimmed, fence) {
+                self.fence = None;
+                chunks.push(MarkdownChunk::CodeEnd);
+            } else {
+                chunks.push(MarkdownChunk::Code(line));
+            }
+            return;
+        }
+
+        if let Some((fence, language)) = opens(trimmed) {
+            self.flush_table(chunks);
+            self.flush_block(chunks);
+            self.fence = Some(fence);
+            chunks.push(MarkdownChunk::CodeStart { language });
+            return;
+        }
+
+        if trimmed.trim_start().starts_with('|') {
+            if self.table.is_empty() {
+                self.flush_block(chunks);
+            }
+            self.table.push(line);
+            return;
+        }
+        self.flush_table(chunks);
+
+        let content = trimmed.trim_start();
+        if content.is_empty() {
+            self.block.push_str(&line);
+            self.flush_block(chunks);
+        } else if content.starts_with('#') {
+            self.flush_block(chunks);
+            self.block.push_str(&line);
+            self.flush_block(chunks);
+        } else {
+            if is_list_item(content) {
+                self.flush_block(chunks);
+            }
+            self.block.push_str(&line);
+        }
+    }
+
+    fn flush_block(&mut self, chunks: &mut Vec<MarkdownChunk>) {
+        if self.block.is_empty() {
+            return;
+        }
+        chunks.push(MarkdownChunk::Markdown(std::mem::take(&mut self.block)));
+    }
+
+    fn flush_table(&mut self, chunks: &mut Vec<MarkdownChunk>) {
+        if self.table.is_empty() {
+            return;
+        }
+        let rows = std::mem::take(&mut self.table);
+        if rows.len() >= 2 && is_delimiter_row(&rows[1]) {
+            chunks.push(MarkdownChunk::Table(rows.concat()));
+        } else {
+            // Not a table after all, just lines starting with a pipe
+            self.block.push_str(&rows.concat());
+        }
+    }
+}
+
+/// A line opening a code fence: up to three spaces, then three or more backticks or tildes
+fn opens(line: &str) -> Option<(Fence, Option<String>)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 {
+        return None;
+    }
+    let rest = &line[indent..];
+    let marker = rest.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = rest.chars().take_while(|c| *c == marker).count();
+    if len < 3 {
+        return None;
+    }
+    let info = rest[len..].trim();
+    if marker == '`' && info.contains('`') {
+        return None;
+    }
+    let language = info
+        .split_whitespace()
+        .next()
+        .map(|language| language.to_string());
+    Some((Fence { marker, len }, language))
+}
+
+fn closes(line: &str, fence: &Fence) -> bool {
+    let content = line.trim();
+    let len = content.chars().take_while(|c| *c == fence.marker).count();
+    len >= fence.len && content[len..].is_empty() && line.len() - line.trim_start().len() <= 3
+}
+
+fn is_list_item(line: &str) -> bool {
+    line.starts_with("- ")
+        || line.starts_with("* ")
+        || line.starts_with("+ ")
+        || line.split_once(['.', ')']).is_some_and(|(n, rest)| {
+            !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()) && rest.starts_with(' ')
+        })
+}
+
+fn is_delimiter_row(row: &str) -> bool {
+    let cells: Vec<&str> = row
+        .trim()
+        .trim_start_matches('|')
+        .trim_end_matches('|')
+        .split('|')
+        .map(str::trim)
+        .collect();
+    !cells.is_empty()
+        && cells.iter().all(|cell| {
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed text a few characters at a time, the way a provider streams it
+    fn stream(text: &str, step: usize) -> Vec<MarkdownChunk> {
+        let mut markdown = MarkdownStream::new();
+        let chars: Vec<char> = text.chars().collect();
+        let mut chunks = Vec::new();
+        for piece in chars.chunks(step) {
+            chunks.extend(markdown.push(&piece.iter().collect::<String>()));
+        }
+        chunks.extend(markdown.finish());
+        chunks
+    }
+
+    #[test]
+    fn test_blocks_are_emitted_once_complete() {
+        let text = "# Plan\nFirst we read\nthe config.\n\n- one\n- two\n\n```rust\nfn main() {}\n```\n\n| a | b |\n|---|:-:|\n| 1 | 2 |\n\nDone.";
+        let chunks = stream(text, 3);
+        assert_eq!(
+            chunks,
+            vec![
+                MarkdownChunk::Markdown("# Plan\n".to_string()),
+                MarkdownChunk::Markdown("First we read\nthe config.\n\n".to_string()),
+                MarkdownChunk::Markdown("- one\n".to_string()),
+                MarkdownChunk::Markdown("- two\n\n".to_string()),
+                MarkdownChunk::CodeStart {
+                    language: Some("rust".to_string())
+                },
+                MarkdownChunk::Code("fn main() {}\n".to_string()),
+                MarkdownChunk::CodeEnd,
+                MarkdownChunk::Markdown("\n".to_string()),
+                MarkdownChunk::Table("| a | b |\n|---|:-:|\n| 1 | 2 |\n".to_string()),
+                MarkdownChunk::Markdown("\n".to_string()),
+                MarkdownChunk::Markdown("Done.\n".to_string()),
+            ]
+        );
+
+        // However the text is split, the chunks are the same
+        assert_eq!(stream(text, 1), chunks);
+        assert_eq!(stream(text, text.len()), chunks);
+    }
+
+    #[test]
+    fn test_open_constructs_are_held_back_and_closed_at_the_end() {
+        let mut markdown = MarkdownStream::new();
+        assert_eq!(markdown.push("| a |\n| b |\nnot a table\n~~~"), vec![]);
+        assert_eq!(markdown.pending(), "| a |\n| b |\nnot a table\n~~~");
+        assert_eq!(
+            markdown.push("\nlet x"),
+            vec![
+                MarkdownChunk::Markdown("| a |\n| b |\nnot a table\n".to_string()),
+                MarkdownChunk::CodeStart { language: None },
+            ]
+        );
+        assert_eq!(
+            markdown.finish(),
+            vec![
+                MarkdownChunk::Code("let x\n".to_string()),
+                MarkdownChunk::CodeEnd
+            ]
+        );
+    }
+}
@@ -96,6 +96,22 @@ pub struct SummarizationRequested {
     pub msg: String,
 }
 
+/// Something the agent took for granted instead of asking about
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Assumption {
+    pub id: String,
+    pub text: String,
+}
+
+/// A clarifying question from the agent, with the answer once the parent or human gives one
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Question {
+    pub id: String,
+    pub question: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
 /// Content passed inside a message, which can be both simple content and tool content
 #[serde(tag = "type", rename_all = "camelCase")]
@@ -110,6 +126,8 @@ pub enum MessageContent {
     RedactedThinking(RedactedThinkingContent),
     ContextLengthExceeded(ContextLengthExceeded),
     SummarizationRequested(SummarizationRequested),
+    Assumption(Assumption),
+    Question(Question),
 }
 
 impl MessageContent {
@@ -182,6 +200,21 @@ impl MessageContent {
         MessageContent::SummarizationRequested(SummarizationRequested { msg: msg.into() })
     }
 
+    pub fn assumption<S1: Into<String>, S2: Into<String>>(id: S1, text: S2) -> Self {
+        MessageContent::Assumption(Assumption {
+            id: id.into(),
+            text: text.into(),
+        })
+    }
+
+    pub fn question<S1: Into<String>, S2: Into<String>>(id: S1, question: S2) -> Self {
+        MessageContent::Question(Question {
+            id: id.into(),
+            question: question.into(),
+            answer: None,
+        })
+    }
+
     // Add this new method to check for summarization requested content
     pub fn as_summarization_requested(&self) -> Option<&SummarizationRequested> {
         if let MessageContent::SummarizationRequested(ref summarization_requested) = self {
@@ -253,6 +286,20 @@ impl MessageContent {
             _ => None,
         }
     }
+
+    pub fn as_assumption(&self) -> Option<&Assumption> {
+        match self {
+            MessageContent::Assumption(assumption) => Some(assumption),
+            _ => None,
+        }
+    }
+
+    pub fn as_question(&self) -> Option<&Question> {
+        match self {
+            MessageContent::Question(question) => Some(question),
+            _ => None,
+        }
+    }
 }
 
 impl From<Content> for MessageContent {
@@ -299,6 +346,58 @@ impl From<PromptMessage> for Message {
     }
 }
 
+/// Why the model stopped generating, normalized across providers
+#[derive(ToSchema, Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FinishReason {
+    /// The model finished its answer or hit a stop sequence
+    Stop,
+    /// The model stopped to call one or more tools
+    ToolCalls,
+    /// Generation was cut off by the output token limit
+    Length,
+    /// The provider withheld or truncated output because of a content policy
+    ContentFilter,
+    /// Generation was interrupted by the user or the agent
+    Cancelled,
+}
+
+impl FinishReason {
+    /// Map an OpenAI-style `finish_reason` (also used by Databricks, Groq, OpenRouter, ...)
+    pub fn from_openai(reason: &str) -> Option<Self> {
+        match reason {
+            "stop" => Some(Self::Stop),
+            "tool_calls" | "function_call" => Some(Self::ToolCalls),
+            "length" => Some(Self::Length),
+            "content_filter" => Some(Self::ContentFilter),
+            _ => None,
+        }
+    }
+
+    /// Map an Anthropic `stop_reason`
+    pub fn from_anthropic(reason: &str) -> Option<Self> {
+        match reason {
+            "end_turn" | "stop_sequence" => Some(Self::Stop),
+            "tool_use" => Some(Self::ToolCalls),
+            "max_tokens" => Some(Self::Length),
+            "refusal" => Some(Self::ContentFilter),
+            _ => None,
+        }
+    }
+
+    /// Map a Google Gemini `finishReason`
+    pub fn from_google(reason: &str) -> Option<Self> {
+        match reason {
+            "STOP" => Some(Self::Stop),
+            "MAX_TOKENS" => Some(Self::Length),
+            "SAFETY" | "RECITATION" | "BLOCKLIST" | "PROHIBITED_CONTENT" | "SPII" => {
+                Some(Self::ContentFilter)
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(ToSchema, Debug, Clone, PartialEq, Serialize, Deserialize)]
 /// A message to or from an LLM
 #[serde(rename_all = "camelCase")]
@@ -306,6 +405,9 @@ pub struct Message {
     pub role: Role,
     pub created: i64,
     pub content: Vec<MessageContent>,
+    /// Why generation stopped, set on assistant messages returned by a provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finish_reason: Option<FinishReason>,
 }
 
 impl Message {
@@ -315,6 +417,7 @@ impl Message {
             role: Role::User,
             created: Utc::now().timestamp(),
             content: Vec::new(),
+            finish_reason: None,
         }
     }
 
@@ -324,6 +427,7 @@ impl Message {
             role: Role::Assistant,
             created: Utc::now().timestamp(),
             content: Vec::new(),
+            finish_reason: None,
         }
     }
 
@@ -333,6 +437,12 @@ impl Message {
         self
     }
 
+    /// Record why the model stopped generating this message
+    pub fn with_finish_reason(mut self, finish_reason: FinishReason) -> Self {
+        self.finish_reason = Some(finish_reason);
+        self
+    }
+
     /// Add text content to the message
     pub fn with_text<S: Into<String>>(self, text: S) -> Self {
         self.with_content(MessageContent::text(text))
@@ -475,6 +585,16 @@ impl Message {
     pub fn with_summarization_requested<S: Into<String>>(self, msg: S) -> Self {
         self.with_content(MessageContent::summarization_requested(msg))
     }
+
+    /// Add an assumption to the message
+    pub fn with_assumption<S1: Into<String>, S2: Into<String>>(self, id: S1, text: S2) -> Self {
+        self.with_content(MessageContent::assumption(id, text))
+    }
+
+    /// Add a clarifying question to the message
+    pub fn with_question<S1: Into<String>, S2: Into<String>>(self, id: S1, question: S2) -> Self {
+        self.with_content(MessageContent::question(id, question))
+    }
 }
 
 #[cfg(test)]
@@ -752,4 +872,36 @@ mod tests {
         assert_eq!(ids.len(), 1);
         assert!(ids.contains("req1"));
     }
+
+    #[test]
+    fn test_finish_reason_normalization() {
+        assert_eq!(
+            FinishReason::from_openai("tool_calls"),
+            Some(FinishReason::ToolCalls)
+        );
+        assert_eq!(
+            FinishReason::from_anthropic("max_tokens"),
+            Some(FinishReason::Length)
+        );
+        assert_eq!(
+            FinishReason::from_google("SAFETY"),
+            Some(FinishReason::ContentFilter)
+        );
+        assert_eq!(FinishReason::from_openai("unknown"), None);
+    }
+
+    #[test]
+    fn test_finish_reason_serialization() {
+        let message = Message::assistant()
+            .with_text("Hello")
+            .with_finish_reason(FinishReason::ToolCalls);
+        let json = serde_json::to_value(&message).unwrap();
+        assert_eq!(json["finishReason"], "tool_calls");
+
+        let without = serde_json::to_value(Message::user().with_text("Hi")).unwrap();
+        assert!(without.get("finishReason").is_none());
+
+        let parsed: Message = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.finish_reason, Some(FinishReason::ToolCalls));
+    }
 }
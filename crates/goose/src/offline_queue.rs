@@ -0,0 +1,473 @@
+//! Recipe runs held back while there is no network connection.
+//!
+//! With `GOOSE_OFFLINE_QUEUE` enabled, a non-interactive recipe run that starts while goose is
+//! completely offline is written to disk instead of failing against an unreachable provider.
+//! The queue is drained in the background once connectivity returns: each run is executed as
+//! if it had just started and removed afterwards. Runs that keep failing once online are given
+//! up after a few attempts so they don't block the queue forever.
+//!
+//! Connectivity is judged by reaching the configured provider's API, the thing a run needs,
+//! rather than a well-known public address.
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, Notify};
+use url::{Host, Url};
+
+use crate::config::Config;
+use crate::recipe::Recipe;
+
+/// Attempts a queued run gets once online before it is dropped
+pub const MAX_ATTEMPTS: u32 = 3;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// How often the drainer checks for connectivity while runs are queued, unless
+/// `GOOSE_OFFLINE_RETRY_SECS` is set
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Executes a queued run once connectivity is back
+#[async_trait]
+pub trait QueuedRunExecutor: Send + Sync {
+    /// Run the recipe, returning the id of the session it created
+    async fn execute(&self, run: &QueuedRun) -> Result<String>;
+
+    /// Whether the run may start now. Runs that may not stay queued without using up an
+    /// attempt.
+    async fn ready(&self, _run: &QueuedRun) -> bool {
+        true
+    }
+}
+
+/// A recipe run waiting for connectivity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedRun {
+    pub id: String,
+    /// Where the run came from, e.g. the recipe file of a schedule or the recipe named on the
+    /// command line
+    pub source: String,
+    /// The schedule that triggered the run, if any
+    #[serde(default)]
+    pub schedule_id: Option<String>,
+    /// The recipe as it was when the run was queued, with its parameters already rendered
+    pub recipe: Recipe,
+    #[serde(default)]
+    pub execution_mode: Option<String>,
+    pub queued_at: DateTime<Utc>,
+    #[serde(default)]
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+}
+
+/// Whether runs started while offline should be queued, from `GOOSE_OFFLINE_QUEUE`
+pub fn is_enabled() -> bool {
+    Config::global()
+        .get_param::<bool>("GOOSE_OFFLINE_QUEUE")
+        .unwrap_or(false)
+}
+
+/// The `host:port` of the configured provider's API, from its `*_HOST` or `*_ENDPOINT`
+/// setting or that setting's default. `None` when there is no provider configured, it has no
+/// such setting or it runs on this machine, in which case there is nothing to wait for.
+pub fn provider_probe() -> Option<String> {
+    let config = Config::global();
+    let provider: String = config.get_param("GOOSE_PROVIDER").ok()?;
+    let key = crate::providers::providers()
+        .into_iter()
+        .find(|metadata| metadata.name == provider)?
+        .config_keys
+        .into_iter()
+        .find(|key| key.name.ends_with("_HOST") || key.name.ends_with("_ENDPOINT"))?;
+    let endpoint = config
+        .get_param::<String>(&key.name)
+        .or_else(|_| config.get_secret::<String>(&key.name))
+        .ok()
+        .or(key.default)?;
+    probe_address(&endpoint)
+}
+
+/// The `host:port` to connect to for an endpoint URL, which may leave out the scheme
+fn probe_address(endpoint: &str) -> Option<String> {
+    let url = if endpoint.contains("://") {
+        Url::parse(endpoint)
+    } else {
+        Url::parse(&format!("https://{}", endpoint))
+    }
+    .ok()?;
+    let is_local = match url.host()? {
+        Host::Domain(domain) => domain == "localhost",
+        Host::Ipv4(ip) => ip.is_loopback(),
+        Host::Ipv6(ip) => ip.is_loopback(),
+    };
+    if is_local {
+        return None;
+    }
+    Some(format!(
+        "{}:{}",
+        url.host_str()?,
+        url.port_or_known_default()?
+    ))
+}
+
+/// Queued runs persisted as one JSON file each under a directory
+pub struct OfflineQueue {
+    dir: PathBuf,
+    /// Address checked for connectivity instead of the provider's
+    probe: Option<String>,
+    retry_interval: Duration,
+    /// Serializes draining so a run is never executed twice concurrently
+    drain_lock: Mutex<()>,
+    wake: Notify,
+    drainer_started: AtomicBool,
+}
+
+static GLOBAL_OFFLINE_QUEUE: Lazy<Arc<OfflineQueue>> = Lazy::new(|| {
+    let dir = choose_app_strategy(crate::config::APP_STRATEGY.clone())
+        .expect("goose requires a home dir")
+        .in_data_dir("offline_queue");
+    let config = Config::global();
+    let mut queue = OfflineQueue::new(dir);
+    if let Ok(probe) = config.get_param::<String>("GOOSE_OFFLINE_PROBE") {
+        queue = queue.with_probe(probe);
+    }
+    if let Ok(secs) = config.get_param::<u64>("GOOSE_OFFLINE_RETRY_SECS") {
+        queue = queue.with_retry_interval(Duration::from_secs(secs.max(1)));
+    }
+    Arc::new(queue)
+});
+
+/// The process-wide offline queue under goose's data directory
+pub fn global_offline_queue() -> &'static Arc<OfflineQueue> {
+    &GLOBAL_OFFLINE_QUEUE
+}
+
+impl OfflineQueue {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            probe: None,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            drain_lock: Mutex::new(()),
+            wake: Notify::new(),
+            drainer_started: AtomicBool::new(false),
+        }
+    }
+
+    /// Check connectivity by connecting to this `host:port` instead of the provider's API
+    pub fn with_probe(mut self, probe: impl Into<String>) -> Self {
+        self.probe = Some(probe.into());
+        self
+    }
+
+    pub fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// Whether the provider can be reached, judged by opening a TCP connection to the probe
+    /// address, which is looked up again every time so a provider configured since counts.
+    /// Name resolution failing counts as offline; having nothing to probe counts as online.
+    pub async fn is_online(&self) -> bool {
+        let Some(probe) = self.probe.clone().or_else(provider_probe) else {
+            return true;
+        };
+        matches!(
+            tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(probe)).await,
+            Ok(Ok(_))
+        )
+    }
+
+    /// Persist a run and wake the drainer. Returns the queued run. A schedule that fires again
+    /// while an earlier run of it is still queued gets that run back instead of a second one,
+    /// so coming back online doesn't run every missed firing.
+    pub async fn enqueue(
+        &self,
+        source: &str,
+        schedule_id: Option<String>,
+        recipe: Recipe,
+        execution_mode: Option<String>,
+    ) -> Result<QueuedRun> {
+        if let Some(schedule_id) = &schedule_id {
+            if let Some(queued) = self
+                .pending()
+                .await?
+                .into_iter()
+                .find(|run| run.schedule_id.as_ref() == Some(schedule_id))
+            {
+                return Ok(queued);
+            }
+        }
+        let run = QueuedRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            source: source.to_string(),
+            schedule_id,
+            recipe,
+            execution_mode,
+            queued_at: Utc::now(),
+            attempts: 0,
+            last_error: None,
+        };
+        write_run(&self.dir, &run).await?;
+        self.wake.notify_one();
+        Ok(run)
+    }
+
+    /// Runs still waiting, oldest first
+    pub async fn pending(&self) -> Result<Vec<QueuedRun>> {
+        read_runs(&self.dir).await
+    }
+
+    /// Drop a queued run without executing it; removing one that doesn't exist is not an error
+    pub async fn remove(&self, id: &str) -> Result<()> {
+        remove_run(&self.dir, id).await
+    }
+
+    /// Execute every queued run, oldest first, if the network is reachable. Stops as soon as
+    /// connectivity is lost again, leaving the rest queued. Returns the number of runs that
+    /// completed.
+    pub async fn drain(&self, executor: &dyn QueuedRunExecutor) -> Result<usize> {
+        let _guard = self.drain_lock.lock().await;
+        let mut completed = 0;
+        for mut run in self.pending().await? {
+            if !self.is_online().await {
+                break;
+            }
+            if !executor.ready(&run).await {
+                continue;
+            }
+            match executor.execute(&run).await {
+                Ok(session_id) => {
+                    tracing::info!(
+                        "Ran queued run {} of {} as session {}",
+                        run.id,
+                        run.source,
+                        session_id
+                    );
+                    remove_run(&self.dir, &run.id).await?;
+                    completed += 1;
+                }
+                Err(e) => {
+                    run.attempts += 1;
+                    run.last_error = Some(e.to_string());
+                    if run.attempts >= MAX_ATTEMPTS {
+                        tracing::error!(
+                            "Giving up on queued run {} of {} after {} attempts: {}",
+                            run.id,
+                            run.source,
+                            run.attempts,
+                            e
+                        );
+                        remove_run(&self.dir, &run.id).await?;
+                    } else {
+                        tracing::warn!(
+                            "Queued run {} of {} failed (attempt {}): {}",
+                            run.id,
+                            run.source,
+                            run.attempts,
+                            e
+                        );
+                        write_run(&self.dir, &run).await?;
+                    }
+                }
+            }
+        }
+        Ok(completed)
+    }
+
+    /// Drain the queue in the background on the current tokio runtime whenever runs are
+    /// queued, including runs left over from a previous process. Does nothing if the drainer
+    /// is already running.
+    pub fn ensure_drainer(self: &Arc<Self>, executor: Arc<dyn QueuedRunExecutor>) {
+        if tokio::runtime::Handle::try_current().is_err()
+            || self.drainer_started.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        let queue = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                match queue.pending().await {
+                    Ok(pending) if !pending.is_empty() => {
+                        if let Err(e) = queue.drain(executor.as_ref()).await {
+                            tracing::warn!("Failed to drain the offline queue: {}", e);
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => tracing::warn!("Failed to read the offline queue: {}", e),
+                }
+                tokio::select! {
+                    _ = queue.wake.notified() => {}
+                    _ = tokio::time::sleep(queue.retry_interval) => {}
+                }
+            }
+        });
+    }
+}
+
+async fn write_run(dir: &Path, run: &QueuedRun) -> Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    // Write then rename, so a crash never leaves a half-written run behind
+    let path = dir.join(format!("{}.json", run.id));
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(run)?).await?;
+    tokio::fs::rename(&tmp, &path).await?;
+    Ok(())
+}
+
+async fn remove_run(dir: &Path, id: &str) -> Result<()> {
+    match tokio::fs::remove_file(dir.join(format!("{}.json", id))).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+async fn read_runs(dir: &Path) -> Result<Vec<QueuedRun>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut runs = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let contents = tokio::fs::read_to_string(&path).await?;
+        match serde_json::from_str::<QueuedRun>(&contents) {
+            Ok(run) => runs.push(run),
+            Err(e) => tracing::warn!("Skipping unreadable queued run {:?}: {}", path, e),
+        }
+    }
+    runs.sort_by_key(|run| run.queued_at);
+    Ok(runs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::atomic::AtomicU32;
+
+    /// Fails every run whose source is `broken` and holds back those whose source is `blocked`
+    struct CountingExecutor {
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl QueuedRunExecutor for CountingExecutor {
+        async fn execute(&self, run: &QueuedRun) -> Result<String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if run.source == "broken" {
+                return Err(anyhow!("recipe failed"));
+            }
+            Ok(format!("session-{}", run.id))
+        }
+
+        async fn ready(&self, run: &QueuedRun) -> bool {
+            run.source != "blocked"
+        }
+    }
+
+    fn recipe() -> Recipe {
+        Recipe::builder()
+            .title("Nightly report")
+            .description("Summarize yesterday's changes")
+            .prompt("Summarize the commits from yesterday")
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_drain_runs_queued_recipes_when_online() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let queue = OfflineQueue::new(dir.path().to_path_buf())
+            .with_probe(listener.local_addr().unwrap().to_string());
+        let executor = CountingExecutor {
+            calls: AtomicU32::new(0),
+        };
+
+        queue
+            .enqueue("nightly.yaml", Some("nightly".into()), recipe(), None)
+            .await
+            .unwrap();
+        queue.enqueue("broken", None, recipe(), None).await.unwrap();
+        assert_eq!(queue.pending().await.unwrap().len(), 2);
+
+        assert!(queue.is_online().await);
+        assert_eq!(queue.drain(&executor).await.unwrap(), 1);
+        let pending = queue.pending().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].attempts, 1);
+        assert_eq!(pending[0].last_error.as_deref(), Some("recipe failed"));
+
+        for _ in 1..MAX_ATTEMPTS {
+            queue.drain(&executor).await.unwrap();
+        }
+        assert!(queue.pending().await.unwrap().is_empty());
+
+        // Nothing runs while the probe can't be reached
+        queue
+            .enqueue("nightly.yaml", None, recipe(), None)
+            .await
+            .unwrap();
+        drop(listener);
+        let calls = executor.calls.load(Ordering::SeqCst);
+        assert_eq!(queue.drain(&executor).await.unwrap(), 0);
+        assert_eq!(executor.calls.load(Ordering::SeqCst), calls);
+        assert_eq!(queue.pending().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_schedule_firings_are_coalesced_and_blocked_runs_wait() {
+        let dir = tempfile::tempdir().unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let queue = OfflineQueue::new(dir.path().to_path_buf())
+            .with_probe(listener.local_addr().unwrap().to_string());
+        let executor = CountingExecutor {
+            calls: AtomicU32::new(0),
+        };
+
+        let first = queue
+            .enqueue("blocked", Some("nightly".into()), recipe(), None)
+            .await
+            .unwrap();
+        let second = queue
+            .enqueue("blocked", Some("nightly".into()), recipe(), None)
+            .await
+            .unwrap();
+        assert_eq!(first.id, second.id);
+        assert_eq!(queue.pending().await.unwrap().len(), 1);
+
+        for _ in 0..MAX_ATTEMPTS {
+            assert_eq!(queue.drain(&executor).await.unwrap(), 0);
+        }
+        let pending = queue.pending().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].attempts, 0);
+        assert_eq!(executor.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_probe_address_of_provider_endpoints() {
+        assert_eq!(
+            probe_address("https://api.openai.com").as_deref(),
+            Some("api.openai.com:443")
+        );
+        assert_eq!(
+            probe_address("gateway.example.com:8443/v1").as_deref(),
+            Some("gateway.example.com:8443")
+        );
+        assert_eq!(probe_address("http://localhost:11434"), None);
+        assert_eq!(probe_address("http://127.0.0.1:8080"), None);
+    }
+}
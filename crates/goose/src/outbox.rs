@@ -0,0 +1,474 @@
+//! Durable delivery of outbound events such as completion webhooks and alerts.
+//!
+//! Events are written to disk before delivery is attempted and removed only once their sink
+//! accepts them, so a notification survives the target being briefly down or goose
+//! restarting. Failed deliveries are retried with exponential backoff; events that keep
+//! failing are moved to a dead-letter directory where they can be inspected and redriven.
+//!
+//! Sinks are registered in code by name and hold their own credentials, so nothing secret is
+//! written to the outbox. An event whose sink isn't registered (yet) stays pending, without
+//! using up attempts, until a process that registers it delivers it. Processes sharing an
+//! outbox take turns delivering under a file lock.
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use fs2::FileExt;
+use once_cell::sync::Lazy;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::{Mutex, Notify};
+
+/// Attempts before an event is dead-lettered, unless `GOOSE_OUTBOX_MAX_ATTEMPTS` is set
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 8;
+
+/// Delay before the first retry; it doubles with every attempt up to [`MAX_RETRY_DELAY`]
+const BASE_RETRY_DELAY: Duration = Duration::from_secs(5);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(600);
+
+/// How often the dispatcher looks for events that are due when it isn't woken up
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Where an event is delivered to
+#[async_trait]
+pub trait OutboxSink: Send + Sync {
+    async fn deliver(&self, event: &OutboxEvent) -> Result<()>;
+}
+
+/// An event waiting for delivery, or dead-lettered after too many failed attempts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboxEvent {
+    pub id: String,
+    /// Name of the registered sink that delivers the event
+    pub sink: String,
+    /// What happened, e.g. `schedule.completed`
+    pub kind: String,
+    pub payload: Value,
+    pub created_at: DateTime<Utc>,
+    pub attempts: u32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+}
+
+impl OutboxEvent {
+    fn retry_delay(&self) -> Duration {
+        let factor = 2u32.saturating_pow(self.attempts.saturating_sub(1));
+        BASE_RETRY_DELAY.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Events persisted under a directory: `pending/` holds one JSON file per event still to be
+/// delivered and `dead_letter/` the ones that ran out of attempts
+pub struct Outbox {
+    dir: PathBuf,
+    max_attempts: u32,
+    sinks: RwLock<HashMap<String, Arc<dyn OutboxSink>>>,
+    /// Serializes dispatch so an event is never delivered twice concurrently
+    dispatch_lock: Mutex<()>,
+    wake: Notify,
+    dispatcher_started: AtomicBool,
+}
+
+static GLOBAL_OUTBOX: Lazy<Arc<Outbox>> = Lazy::new(|| {
+    let dir = choose_app_strategy(crate::config::APP_STRATEGY.clone())
+        .expect("goose requires a home dir")
+        .in_data_dir("outbox");
+    let max_attempts = crate::config::Config::global()
+        .get_param("GOOSE_OUTBOX_MAX_ATTEMPTS")
+        .unwrap_or(DEFAULT_MAX_ATTEMPTS);
+    Arc::new(Outbox::new(dir).with_max_attempts(max_attempts))
+});
+
+/// The process-wide outbox under goose's data directory
+pub fn global_outbox() -> &'static Arc<Outbox> {
+    &GLOBAL_OUTBOX
+}
+
+impl Outbox {
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            sinks: RwLock::new(HashMap::new()),
+            dispatch_lock: Mutex::new(()),
+            wake: Notify::new(),
+            dispatcher_started: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    fn pending_dir(&self) -> PathBuf {
+        self.dir.join("pending")
+    }
+
+    fn dead_letter_dir(&self) -> PathBuf {
+        self.dir.join("dead_letter")
+    }
+
+    /// Lock the outbox for delivery across processes, or `None` when another process holds it.
+    /// The lock is released when the returned file is dropped.
+    fn try_lock_dispatch(&self) -> Result<Option<File>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(self.dir.join("dispatch.lock"))?;
+        Ok(file.try_lock_exclusive().ok().map(|()| file))
+    }
+
+    pub fn register_sink(&self, name: impl Into<String>, sink: Arc<dyn OutboxSink>) {
+        self.sinks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(name.into(), sink);
+    }
+
+    fn sink(&self, name: &str) -> Option<Arc<dyn OutboxSink>> {
+        self.sinks
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(name)
+            .cloned()
+    }
+
+    /// Persist an event for `sink` and wake the dispatcher. Returns the event id.
+    pub async fn enqueue(&self, sink: &str, kind: &str, payload: Value) -> Result<String> {
+        let now = Utc::now();
+        let event = OutboxEvent {
+            id: uuid::Uuid::new_v4().to_string(),
+            sink: sink.to_string(),
+            kind: kind.to_string(),
+            payload,
+            created_at: now,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+        };
+        write_event(&self.pending_dir(), &event).await?;
+        self.wake.notify_one();
+        Ok(event.id)
+    }
+
+    /// Events still to be delivered, oldest first
+    pub async fn pending(&self) -> Result<Vec<OutboxEvent>> {
+        read_events(&self.pending_dir()).await
+    }
+
+    /// Events that ran out of delivery attempts, oldest first
+    pub async fn dead_letters(&self) -> Result<Vec<OutboxEvent>> {
+        read_events(&self.dead_letter_dir()).await
+    }
+
+    /// Move dead-lettered events back to pending with a fresh set of attempts, e.g. once the
+    /// endpoint is fixed. Returns the number of events requeued.
+    pub async fn redrive_dead_letters(&self) -> Result<usize> {
+        let events = self.dead_letters().await?;
+        for event in &events {
+            let requeued = OutboxEvent {
+                attempts: 0,
+                next_attempt_at: Utc::now(),
+                ..event.clone()
+            };
+            write_event(&self.pending_dir(), &requeued).await?;
+            remove_event(&self.dead_letter_dir(), &event.id).await?;
+        }
+        if !events.is_empty() {
+            self.wake.notify_one();
+        }
+        Ok(events.len())
+    }
+
+    /// Attempt every event that is due and has its sink registered. Returns the number
+    /// delivered, 0 when another process is delivering from the same outbox.
+    pub async fn dispatch_due(&self) -> Result<usize> {
+        let _guard = self.dispatch_lock.lock().await;
+        let Some(_dispatch_file) = self.try_lock_dispatch()? else {
+            return Ok(0);
+        };
+        let now = Utc::now();
+        let mut delivered = 0;
+        for mut event in self.pending().await? {
+            if event.next_attempt_at > now {
+                continue;
+            }
+
+            let Some(sink) = self.sink(&event.sink) else {
+                tracing::debug!(
+                    "Leaving {} event {} pending until {} is registered",
+                    event.kind,
+                    event.id,
+                    event.sink
+                );
+                continue;
+            };
+            match sink.deliver(&event).await {
+                Ok(()) => {
+                    remove_event(&self.pending_dir(), &event.id).await?;
+                    delivered += 1;
+                }
+                Err(e) => {
+                    event.attempts += 1;
+                    event.last_error = Some(e.to_string());
+                    if event.attempts >= self.max_attempts {
+                        tracing::error!(
+                            "Giving up on {} event {} for {} after {} attempts: {}",
+                            event.kind,
+                            event.id,
+                            event.sink,
+                            event.attempts,
+                            e
+                        );
+                        write_event(&self.dead_letter_dir(), &event).await?;
+                        remove_event(&self.pending_dir(), &event.id).await?;
+                    } else {
+                        let delay = chrono::Duration::from_std(event.retry_delay())?;
+                        event.next_attempt_at = Utc::now() + delay;
+                        tracing::warn!(
+                            "Delivering {} event {} to {} failed (attempt {}), retrying at {}: {}",
+                            event.kind,
+                            event.id,
+                            event.sink,
+                            event.attempts,
+                            event.next_attempt_at,
+                            e
+                        );
+                        write_event(&self.pending_dir(), &event).await?;
+                    }
+                }
+            }
+        }
+        Ok(delivered)
+    }
+
+    /// Deliver events in the background on the current tokio runtime, including any left
+    /// over from a previous run. Does nothing if the dispatcher is already running.
+    pub fn ensure_dispatcher(self: &Arc<Self>) {
+        if tokio::runtime::Handle::try_current().is_err()
+            || self.dispatcher_started.swap(true, Ordering::SeqCst)
+        {
+            return;
+        }
+        let outbox = Arc::clone(self);
+        tokio::spawn(async move {
+            loop {
+                if let Err(e) = outbox.dispatch_due().await {
+                    tracing::warn!("Failed to dispatch outbox events: {}", e);
+                }
+                tokio::select! {
+                    _ = outbox.wake.notified() => {}
+                    _ = tokio::time::sleep(DISPATCH_INTERVAL) => {}
+                }
+            }
+        });
+    }
+}
+
+async fn write_event(dir: &Path, event: &OutboxEvent) -> Result<()> {
+    tokio::fs::create_dir_all(dir).await?;
+    // Write then rename, so a crash never leaves a half-written event behind
+    let path = dir.join(format!("{}.json", event.id));
+    let tmp = path.with_extension("json.tmp");
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(event)?).await?;
+    tokio::fs::rename(&tmp, &path).await?;
+    Ok(())
+}
+
+async fn remove_event(dir: &Path, id: &str) -> Result<()> {
+    match tokio::fs::remove_file(dir.join(format!("{}.json", id))).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+async fn read_events(dir: &Path) -> Result<Vec<OutboxEvent>> {
+    let mut entries = match tokio::fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut events = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let contents = tokio::fs::read_to_string(&path).await?;
+        match serde_json::from_str::<OutboxEvent>(&contents) {
+            Ok(event) => events.push(event),
+            Err(e) => tracing::warn!("Skipping unreadable outbox event {:?}: {}", path, e),
+        }
+    }
+    events.sort_by_key(|event| event.created_at);
+    Ok(events)
+}
+
+/// Name of the sink for `GOOSE_COMPLETION_WEBHOOK_URL`
+pub const COMPLETION_WEBHOOK_SINK: &str = "completion_webhook";
+
+/// Posts the event as JSON to a URL, `{"id", "kind", "created_at", "payload"}`. The event id
+/// is also sent as `Idempotency-Key`, since a retried delivery may arrive twice.
+pub struct WebhookSink {
+    client: Client,
+    url: String,
+}
+
+impl WebhookSink {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .expect("Failed to create HTTP client"),
+            url: url.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl OutboxSink for WebhookSink {
+    async fn deliver(&self, event: &OutboxEvent) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .header("Idempotency-Key", &event.id)
+            .json(&json!({
+                "id": event.id,
+                "kind": event.kind,
+                "created_at": event.created_at,
+                "payload": event.payload,
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Queue a completion notification for the webhook configured with
+/// `GOOSE_COMPLETION_WEBHOOK_URL`, if any
+pub async fn notify_completion(kind: &str, payload: Value) {
+    let Ok(url) =
+        crate::config::Config::global().get_param::<String>("GOOSE_COMPLETION_WEBHOOK_URL")
+    else {
+        return;
+    };
+    let outbox = global_outbox();
+    if outbox.sink(COMPLETION_WEBHOOK_SINK).is_none() {
+        outbox.register_sink(COMPLETION_WEBHOOK_SINK, Arc::new(WebhookSink::new(url)));
+    }
+    outbox.ensure_dispatcher();
+    if let Err(e) = outbox.enqueue(COMPLETION_WEBHOOK_SINK, kind, payload).await {
+        tracing::error!("Failed to queue {} notification: {}", kind, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::atomic::AtomicU32;
+
+    /// Fails the first `failures` deliveries
+    struct FlakySink {
+        failures: u32,
+        calls: AtomicU32,
+    }
+
+    #[async_trait]
+    impl OutboxSink for FlakySink {
+        async fn deliver(&self, _event: &OutboxEvent) -> Result<()> {
+            if self.calls.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(anyhow!("endpoint down"));
+            }
+            Ok(())
+        }
+    }
+
+    /// Make every pending event due now
+    async fn make_due(outbox: &Outbox) {
+        for mut event in outbox.pending().await.unwrap() {
+            event.next_attempt_at = Utc::now();
+            write_event(&outbox.pending_dir(), &event).await.unwrap();
+        }
+    }
+
+    fn flaky(failures: u32) -> Arc<FlakySink> {
+        Arc::new(FlakySink {
+            failures,
+            calls: AtomicU32::new(0),
+        })
+    }
+
+    #[tokio::test]
+    async fn test_failed_delivery_is_retried_then_dead_lettered() {
+        let dir = tempfile::tempdir().unwrap();
+        let outbox = Outbox::new(dir.path().to_path_buf()).with_max_attempts(2);
+        outbox.register_sink("flaky", flaky(1));
+        outbox.register_sink("down", flaky(u32::MAX));
+        for (sink, job) in [("flaky", "a"), ("down", "b"), ("missing", "c")] {
+            outbox
+                .enqueue(sink, "schedule.completed", json!({ "job": job }))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(outbox.dispatch_due().await.unwrap(), 0);
+        let pending = outbox.pending().await.unwrap();
+        assert_eq!(pending.len(), 3);
+        let attempts: Vec<_> = pending.iter().map(|event| event.attempts).collect();
+        assert_eq!(attempts, [1, 1, 0]);
+        // Backed off, so nothing is due yet
+        assert_eq!(outbox.dispatch_due().await.unwrap(), 0);
+
+        make_due(&outbox).await;
+        assert_eq!(outbox.dispatch_due().await.unwrap(), 1);
+        let pending = outbox.pending().await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(
+            (pending[0].sink.as_str(), pending[0].attempts),
+            ("missing", 0)
+        );
+        let dead = outbox.dead_letters().await.unwrap();
+        assert_eq!(dead.len(), 1);
+        assert_eq!(dead[0].sink, "down");
+        assert_eq!(dead[0].attempts, 2);
+
+        assert_eq!(outbox.redrive_dead_letters().await.unwrap(), 1);
+        assert!(outbox.dead_letters().await.unwrap().is_empty());
+        assert_eq!(outbox.pending().await.unwrap().len(), 2);
+
+        // Once its sink is registered the waiting event goes out
+        outbox.register_sink("missing", flaky(0));
+        assert_eq!(outbox.dispatch_due().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_waits_for_another_process() {
+        let dir = tempfile::tempdir().unwrap();
+        let outbox = Outbox::new(dir.path().to_path_buf());
+        outbox.register_sink("ok", flaky(0));
+        outbox
+            .enqueue("ok", "schedule.completed", json!({}))
+            .await
+            .unwrap();
+
+        let other = outbox.try_lock_dispatch().unwrap().unwrap();
+        assert_eq!(outbox.dispatch_due().await.unwrap(), 0);
+        assert_eq!(outbox.pending().await.unwrap().len(), 1);
+
+        drop(other);
+        assert_eq!(outbox.dispatch_due().await.unwrap(), 1);
+    }
+}
@@ -96,6 +96,7 @@ fn create_check_messages(tool_requests: Vec<&ToolRequest>) -> Vec<Message> {
             ),
             annotations: None,
         })],
+        finish_reason: None,
     });
     check_messages
 }
@@ -308,6 +309,7 @@ mod tests {
                             }),
                         }),
                     })],
+                    finish_reason: None,
                 },
                 ProviderUsage::new("mock".to_string(), Usage::default()),
             ))
@@ -366,6 +368,7 @@ mod tests {
                     }),
                 }),
             })],
+            finish_reason: None,
         };
 
         let result = extract_read_only_tools(&message);
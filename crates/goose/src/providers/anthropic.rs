@@ -3,11 +3,16 @@ use async_trait::async_trait;
 use axum::http::HeaderMap;
 use reqwest::{Client, StatusCode};
 use serde_json::Value;
+use std::sync::Arc;
 use std::time::Duration;
 
-use super::base::{ConfigKey, ModelInfo, Provider, ProviderMetadata, ProviderUsage};
+use super::base::{
+    ConfigKey, ModelInfo, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+};
+use super::egress::EgressExt;
 use super::errors::ProviderError;
 use super::formats::anthropic::{create_request, get_usage, response_to_message};
+use super::middleware::RequestHeadersExt;
 use super::utils::{emit_debug_trace, get_model};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -29,7 +34,7 @@ pub const ANTHROPIC_KNOWN_MODELS: &[&str] = &[
 pub const ANTHROPIC_DOC_URL: &str = "https://docs.anthropic.com/en/docs/about-claude/models";
 pub const ANTHROPIC_API_VERSION: &str = "2023-06-01";
 
-#[derive(serde::Serialize)]
+#[derive(Clone, serde::Serialize)]
 pub struct AnthropicProvider {
     #[serde(skip)]
     client: Client,
@@ -76,8 +81,9 @@ impl AnthropicProvider {
             .client
             .post(url)
             .headers(headers)
+            .with_request_headers()
             .json(&payload)
-            .send()
+            .send_with_egress()
             .await?;
 
         let status = response.status();
@@ -159,6 +165,17 @@ impl Provider for AnthropicProvider {
         self.model.clone()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::from_model_config(&self.model).with_vision(true)
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(Self {
+            model,
+            ..self.clone()
+        }))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -209,7 +226,7 @@ impl Provider for AnthropicProvider {
             .get(&url)
             .header("anthropic-version", ANTHROPIC_API_VERSION)
             .header("x-api-key", self.api_key.clone())
-            .send()
+            .send_with_egress()
             .await?;
         let json: serde_json::Value = response.json().await?;
         // if 'models' key missing, return None
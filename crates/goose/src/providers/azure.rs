@@ -8,8 +8,10 @@ use tokio::time::sleep;
 
 use super::azureauth::AzureAuth;
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::egress::EgressExt;
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::middleware::RequestHeadersExt;
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -141,7 +143,11 @@ impl AzureProvider {
                 }
             }
 
-            let response_result = request_builder.json(&payload).send().await;
+            let response_result = request_builder
+                .with_request_headers()
+                .json(&payload)
+                .send_with_egress()
+                .await;
 
             match response_result {
                 Ok(response) => match handle_response_openai_compat(response).await {
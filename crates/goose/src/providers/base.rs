@@ -8,7 +8,12 @@ use mcp_core::tool::Tool;
 use utoipa::ToSchema;
 
 use once_cell::sync::Lazy;
-use std::sync::Mutex;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
 
 /// A global store for the current model being used, we use this as when a provider returns, it tells us the real model, not an alias
 pub static CURRENT_MODEL: Lazy<Mutex<Option<String>>> = Lazy::new(|| Mutex::new(None));
@@ -176,11 +181,23 @@ impl ConfigKey {
 pub struct ProviderUsage {
     pub model: String,
     pub usage: Usage,
+    /// Name of the provider that served the request, set by wrappers that route between providers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
 }
 
 impl ProviderUsage {
     pub fn new(model: String, usage: Usage) -> Self {
-        Self { model, usage }
+        Self {
+            model,
+            usage,
+            provider: None,
+        }
+    }
+
+    pub fn with_provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
     }
 }
 
@@ -189,6 +206,9 @@ pub struct Usage {
     pub input_tokens: Option<i32>,
     pub output_tokens: Option<i32>,
     pub total_tokens: Option<i32>,
+    /// Cost of the request in USD, computed from the pricing table when the model is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
 }
 
 impl Usage {
@@ -201,8 +221,147 @@ impl Usage {
             input_tokens,
             output_tokens,
             total_tokens,
+            cost_usd: None,
         }
     }
+
+    pub fn with_cost_usd(mut self, cost_usd: Option<f64>) -> Self {
+        self.cost_usd = cost_usd;
+        self
+    }
+}
+
+/// Shared retry behaviour for transient HTTP failures (429 and retryable 5xx responses).
+///
+/// Delays grow exponentially from `initial_interval_ms`, are capped at `max_interval_ms`
+/// and randomized by `jitter`. A `Retry-After` header on the response takes precedence
+/// over the computed backoff.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: usize,
+    /// Delay before the first retry in milliseconds
+    pub initial_interval_ms: u64,
+    /// Multiplier applied to the delay after each retry
+    pub backoff_multiplier: f64,
+    /// Upper bound for any single delay in milliseconds, including `Retry-After`
+    pub max_interval_ms: u64,
+    /// Fraction of randomness applied to computed delays, e.g. 0.2 for +/-20%
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_interval_ms: 1000,
+            backoff_multiplier: 2.0,
+            max_interval_ms: 30_000,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Read `{PREFIX}_MAX_RETRIES`, `{PREFIX}_INITIAL_RETRY_INTERVAL_MS`, `{PREFIX}_BACKOFF_MULTIPLIER`,
+    /// `{PREFIX}_MAX_RETRY_INTERVAL_MS` and `{PREFIX}_RETRY_JITTER`, falling back to `defaults`
+    pub fn from_config(prefix: &str, defaults: RetryPolicy) -> Self {
+        fn param<T: std::str::FromStr>(key: String) -> Option<T> {
+            crate::config::Config::global()
+                .get_param::<String>(&key)
+                .ok()
+                .and_then(|v| v.parse().ok())
+        }
+
+        Self {
+            max_retries: param(format!("{}_MAX_RETRIES", prefix)).unwrap_or(defaults.max_retries),
+            initial_interval_ms: param(format!("{}_INITIAL_RETRY_INTERVAL_MS", prefix))
+                .unwrap_or(defaults.initial_interval_ms),
+            backoff_multiplier: param(format!("{}_BACKOFF_MULTIPLIER", prefix))
+                .unwrap_or(defaults.backoff_multiplier),
+            max_interval_ms: param(format!("{}_MAX_RETRY_INTERVAL_MS", prefix))
+                .unwrap_or(defaults.max_interval_ms),
+            jitter: param(format!("{}_RETRY_JITTER", prefix)).unwrap_or(defaults.jitter),
+        }
+    }
+
+    /// Calculate the backoff delay for a retry attempt (1-based), with jitter
+    pub fn delay_for_attempt(&self, attempt: usize) -> Duration {
+        if attempt == 0 {
+            return Duration::ZERO;
+        }
+
+        let exponent = (attempt - 1) as i32;
+        let base_delay_ms =
+            (self.initial_interval_ms as f64 * self.backoff_multiplier.powi(exponent)) as u64;
+        let capped_delay_ms = base_delay_ms.min(self.max_interval_ms);
+
+        // Jitter avoids a thundering herd when many clients back off together
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let jitter_factor = 1.0 - jitter + rand::random::<f64>() * 2.0 * jitter;
+        Duration::from_millis((capped_delay_ms as f64 * jitter_factor) as u64)
+    }
+
+    /// The delay before the next attempt, preferring the server's `Retry-After` hint
+    pub fn delay_for(&self, attempt: usize, retry_after: Option<Duration>) -> Duration {
+        match retry_after {
+            Some(delay) => delay.min(Duration::from_millis(self.max_interval_ms)),
+            None => self.delay_for_attempt(attempt),
+        }
+    }
+
+    pub fn is_retryable_status(status: StatusCode) -> bool {
+        matches!(
+            status,
+            StatusCode::TOO_MANY_REQUESTS
+                | StatusCode::INTERNAL_SERVER_ERROR
+                | StatusCode::BAD_GATEWAY
+                | StatusCode::SERVICE_UNAVAILABLE
+                | StatusCode::GATEWAY_TIMEOUT
+        )
+    }
+
+    /// Send a request, retrying on transient statuses until the policy is exhausted.
+    ///
+    /// `send` is called once per attempt so callers can rebuild the request (e.g. refresh
+    /// auth headers). The final response is returned as-is, so callers keep mapping
+    /// error statuses to `ProviderError` the way they already do.
+    pub async fn send<F, Fut>(&self, mut send: F) -> Result<reqwest::Response, ProviderError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<reqwest::Response, ProviderError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            let response = send().await?;
+            let status = response.status();
+            if !Self::is_retryable_status(status) || attempt >= self.max_retries {
+                return Ok(response);
+            }
+
+            attempt += 1;
+            let delay = self.delay_for(attempt, parse_retry_after(response.headers()));
+            tracing::warn!(
+                "Request failed with status {} (retry {}/{}), backing off for {:?}",
+                status,
+                attempt,
+                self.max_retries,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Parse a `Retry-After` header given either as delay seconds or as an HTTP date
+pub fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let retry_at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let delay = retry_at.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(delay.to_std().unwrap_or(Duration::ZERO))
 }
 
 use async_trait::async_trait;
@@ -216,6 +375,137 @@ pub trait LeadWorkerProviderTrait {
     fn get_active_model(&self) -> String;
 }
 
+tokio::task_local! {
+    pub(crate) static REQUEST_SEED: u64;
+}
+
+/// The sampling seed requested for the current provider call, if any. Providers whose API
+/// accepts a seed add it to the request payload.
+pub fn current_seed() -> Option<u64> {
+    REQUEST_SEED.try_with(|seed| *seed).ok()
+}
+
+/// Per-call limits and sampling settings for [`Provider::complete_with_options`]
+#[derive(Debug, Clone, Default)]
+pub struct CompletionOptions {
+    /// Give up on the request after this long
+    pub timeout: Option<Duration>,
+    /// Abort the request when this token is cancelled
+    pub cancellation_token: Option<CancellationToken>,
+    /// Ask for deterministic sampling, for providers that support it
+    pub seed: Option<u64>,
+}
+
+impl CompletionOptions {
+    /// Options with the seed configured under `GOOSE_SEED`, if any
+    pub fn from_config() -> Self {
+        Self {
+            seed: crate::config::Config::global()
+                .get_param::<u64>("GOOSE_SEED")
+                .ok(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Run a request within these limits. A request that runs out of time or is cancelled is
+    /// dropped, which closes its HTTP connection rather than leaving it running.
+    pub async fn run<T, F>(&self, request: F) -> Result<T, ProviderError>
+    where
+        F: Future<Output = Result<T, ProviderError>>,
+    {
+        if self
+            .cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(ProviderError::Cancelled);
+        }
+        let cancelled = async {
+            match &self.cancellation_token {
+                Some(token) => token.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+        let timed = async {
+            match self.timeout {
+                Some(timeout) => tokio::time::timeout(timeout, request).await.map_err(|_| {
+                    ProviderError::Timeout(format!("no response after {:?}", timeout))
+                })?,
+                None => request.await,
+            }
+        };
+        tokio::select! {
+            result = timed => result,
+            _ = cancelled => Err(ProviderError::Cancelled),
+        }
+    }
+}
+
+/// What a provider and its configured model can do, so callers can adapt a request up front
+/// instead of having it fail
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ProviderCapabilities {
+    /// Whether tools can be passed natively. When they can't, the agent falls back to toolshim.
+    pub supports_tools: bool,
+    pub supports_vision: bool,
+    pub supports_streaming: bool,
+    pub max_context_tokens: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<i32>,
+}
+
+impl ProviderCapabilities {
+    /// Capabilities of a model that accepts tools but not images
+    pub fn from_model_config(model: &ModelConfig) -> Self {
+        Self {
+            supports_tools: true,
+            supports_vision: false,
+            supports_streaming: false,
+            max_context_tokens: model.context_limit(),
+            max_output_tokens: model.max_tokens,
+        }
+    }
+
+    pub fn with_tools(mut self, supports_tools: bool) -> Self {
+        self.supports_tools = supports_tools;
+        self
+    }
+
+    pub fn with_vision(mut self, supports_vision: bool) -> Self {
+        self.supports_vision = supports_vision;
+        self
+    }
+
+    /// What both providers can do, for providers that may route a request to either one
+    pub fn intersect(&self, other: &Self) -> Self {
+        Self {
+            supports_tools: self.supports_tools && other.supports_tools,
+            supports_vision: self.supports_vision && other.supports_vision,
+            supports_streaming: self.supports_streaming && other.supports_streaming,
+            max_context_tokens: self.max_context_tokens.min(other.max_context_tokens),
+            max_output_tokens: match (self.max_output_tokens, other.max_output_tokens) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
 /// Base trait for AI providers (OpenAI, Anthropic, etc)
 #[async_trait]
 pub trait Provider: Send + Sync {
@@ -244,9 +534,32 @@ pub trait Provider: Send + Sync {
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError>;
 
+    /// Like [`Provider::complete`], but bounded by a timeout and abandoned when the
+    /// options' cancellation token fires, see [`CompletionOptions::run`]. The seed is
+    /// available to the provider through [`current_seed`].
+    async fn complete_with_options(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+        options: &CompletionOptions,
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let request = options.run(self.complete(system, messages, tools));
+        match options.seed {
+            Some(seed) => REQUEST_SEED.scope(seed, request).await,
+            None => request.await,
+        }
+    }
+
     /// Get the model config from the provider
     fn get_model_config(&self) -> ModelConfig;
 
+    /// What this provider can do with its configured model. Defaults to tools without vision
+    /// or streaming, with the limits of the model config.
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::from_model_config(&self.get_model_config())
+    }
+
     /// Optional hook to fetch supported models asynchronously.
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
         Ok(None)
@@ -264,6 +577,15 @@ pub trait Provider: Send + Sync {
         ))
     }
 
+    /// A provider for the same endpoint and credentials that uses a different model. The
+    /// HTTP client and any cached auth tokens are shared with this provider, so switching is
+    /// cheap. Providers that can't switch return an error and must be recreated instead.
+    fn with_model(&self, _model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Err(ProviderError::ExecutionError(
+            "This provider does not support switching models".to_string(),
+        ))
+    }
+
     /// Check if this provider is a LeadWorkerProvider
     /// This is used for logging model information at startup
     fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
@@ -289,6 +611,113 @@ mod tests {
 
     use serde_json::json;
 
+    #[tokio::test]
+    async fn test_completion_options_time_out_and_cancel() {
+        let slow = || async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok::<_, ProviderError>(())
+        };
+
+        let options = CompletionOptions::default().with_timeout(Duration::from_millis(10));
+        assert!(matches!(
+            options.run(slow()).await,
+            Err(ProviderError::Timeout(_))
+        ));
+
+        let token = CancellationToken::new();
+        let options = CompletionOptions::default().with_cancellation_token(token.clone());
+        let canceller = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            token.cancel();
+        });
+        assert!(matches!(
+            options.run(slow()).await,
+            Err(ProviderError::Cancelled)
+        ));
+        canceller.await.unwrap();
+        // Already cancelled, so the request never starts
+        assert!(matches!(
+            options.run(async { Ok(()) }).await,
+            Err(ProviderError::Cancelled)
+        ));
+
+        assert_eq!(
+            CompletionOptions::default()
+                .run(async { Ok(1) })
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_retry_policy_backoff_is_capped() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            initial_interval_ms: 100,
+            backoff_multiplier: 2.0,
+            max_interval_ms: 300,
+            jitter: 0.0,
+        };
+        assert_eq!(policy.delay_for_attempt(0), Duration::ZERO);
+        assert_eq!(policy.delay_for_attempt(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_for_attempt(2), Duration::from_millis(200));
+        assert_eq!(policy.delay_for_attempt(3), Duration::from_millis(300));
+        assert_eq!(
+            policy.delay_for(1, Some(Duration::from_secs(10))),
+            Duration::from_millis(300)
+        );
+        assert_eq!(
+            policy.delay_for(1, Some(Duration::from_millis(50))),
+            Duration::from_millis(50)
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(parse_retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "7".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(7)));
+
+        headers.insert(
+            RETRY_AFTER,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::ZERO));
+
+        headers.insert(RETRY_AFTER, "soon".parse().unwrap());
+        assert_eq!(parse_retry_after(&headers), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_send_retries_transient_statuses() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "0"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client = &reqwest::Client::new();
+        let uri = &server.uri();
+        let policy = RetryPolicy::default();
+        let response = policy
+            .send(|| async move { client.post(uri).send().await.map_err(ProviderError::from) })
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
     #[test]
     fn test_usage_creation() {
         let usage = Usage::new(Some(10), Some(20), Some(30));
@@ -408,4 +837,24 @@ mod tests {
         assert_eq!(info.output_token_cost, Some(0.00001));
         assert_eq!(info.currency, Some("$".to_string()));
     }
+
+    #[test]
+    fn test_capabilities_intersect() {
+        let lead = ProviderCapabilities::from_model_config(
+            &ModelConfig::new("gpt-4o".to_string())
+                .with_context_limit(Some(128_000))
+                .with_max_tokens(Some(4096)),
+        )
+        .with_vision(true);
+        let worker = ProviderCapabilities::from_model_config(
+            &ModelConfig::new("llama3".to_string()).with_context_limit(Some(8_192)),
+        )
+        .with_tools(false);
+
+        let both = lead.intersect(&worker);
+        assert!(!both.supports_tools);
+        assert!(!both.supports_vision);
+        assert_eq!(both.max_context_tokens, 8_192);
+        assert_eq!(both.max_output_tokens, Some(4096));
+    }
 }
@@ -11,6 +11,7 @@ use serde_json::Value;
 use tokio::time::sleep;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
+use super::egress::EgressInterceptor;
 use super::errors::ProviderError;
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -18,7 +19,8 @@ use crate::providers::utils::emit_debug_trace;
 
 // Import the migrated helper functions from providers/formats/bedrock.rs
 use super::formats::bedrock::{
-    from_bedrock_message, from_bedrock_usage, to_bedrock_message, to_bedrock_tool_config,
+    from_bedrock_message, from_bedrock_stop_reason, from_bedrock_usage, to_bedrock_message,
+    to_bedrock_tool_config,
 };
 
 pub const BEDROCK_DOC_LINK: &str =
@@ -64,7 +66,11 @@ impl BedrockProvider {
                 .unwrap()
                 .provide_credentials(),
         )?;
-        let client = Client::new(&sdk_config);
+        let client = Client::from_conf(
+            aws_sdk_bedrockruntime::config::Builder::from(&sdk_config)
+                .interceptor(EgressInterceptor)
+                .build(),
+        );
 
         Ok(Self { client, model })
     }
@@ -145,7 +151,8 @@ impl Provider for BedrockProvider {
                                 .map(from_bedrock_usage)
                                 .unwrap_or_default();
 
-                            let message = from_bedrock_message(&message)?;
+                            let mut message = from_bedrock_message(&message)?;
+                            message.finish_reason = from_bedrock_stop_reason(&response.stop_reason);
 
                             // Add debug trace with input context
                             let debug_payload = serde_json::json!({
@@ -5,7 +5,9 @@ use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{
+    ConfigKey, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage, Usage,
+};
 use super::errors::ProviderError;
 use super::utils::emit_debug_trace;
 use crate::message::{Message, MessageContent};
@@ -223,6 +225,7 @@ impl ClaudeCodeProvider {
             role: Role::Assistant,
             created: chrono::Utc::now().timestamp(),
             content: message_content,
+            finish_reason: None,
         };
 
         Ok((response_message, usage))
@@ -360,6 +363,7 @@ impl ClaudeCodeProvider {
                 text: description.clone(),
                 annotations: None,
             })],
+            finish_reason: None,
         };
 
         let usage = Usage::default();
@@ -395,6 +399,16 @@ impl Provider for ClaudeCodeProvider {
         ModelConfig::new("claude-3-5-sonnet-latest".to_string()).with_context_limit(Some(200_000))
     }
 
+    /// The CLI runs its own tools and ignores goose's. Routing goose's through toolshim
+    /// instead needs a local interpreter model, so it is only reported as lacking tools when
+    /// `CLAUDE_CODE_TOOLSHIM` opts into that.
+    fn capabilities(&self) -> ProviderCapabilities {
+        let toolshim = crate::config::Config::global()
+            .get_param::<bool>("CLAUDE_CODE_TOOLSHIM")
+            .unwrap_or(false);
+        ProviderCapabilities::from_model_config(&self.get_model_config()).with_tools(!toolshim)
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -0,0 +1,270 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::Mutex;
+
+/// How long before expiry a credential is refreshed when the config doesn't say
+pub const DEFAULT_REFRESH_BEFORE: Duration = Duration::from_secs(300);
+
+/// A credential handed out by a [`CredentialRefresher`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct IssuedCredential {
+    pub secret: String,
+    /// When the credential stops working; `None` for credentials that don't expire
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl IssuedCredential {
+    /// Whether the credential expires within `margin` from now
+    pub fn expires_within(&self, margin: Duration) -> bool {
+        let Some(expires_at) = self.expires_at else {
+            return false;
+        };
+        let margin = chrono::Duration::from_std(margin).unwrap_or(chrono::Duration::MAX);
+        expires_at - Utc::now() <= margin
+    }
+
+    /// Parse the output of a credential command. Either a bare secret, or a JSON object with
+    /// the secret in `token`, `access_token` or `secret` and an optional expiry as
+    /// `expires_at` (RFC 3339 or unix seconds) or `expires_in` (seconds from now).
+    pub fn parse(output: &str) -> Result<Self> {
+        let output = output.trim();
+        if output.is_empty() {
+            return Err(anyhow!("credential command printed nothing"));
+        }
+        let Ok(Value::Object(object)) = serde_json::from_str::<Value>(output) else {
+            return Ok(Self {
+                secret: output.to_string(),
+                expires_at: None,
+            });
+        };
+
+        let secret = ["token", "access_token", "secret"]
+            .iter()
+            .find_map(|field| object.get(*field).and_then(Value::as_str))
+            .ok_or_else(|| anyhow!("credential JSON has no token, access_token or secret"))?
+            .to_string();
+        let expires_at = match (object.get("expires_at"), object.get("expires_in")) {
+            (Some(Value::String(at)), _) => Some(DateTime::parse_from_rfc3339(at)?.to_utc()),
+            (Some(Value::Number(at)), _) => {
+                at.as_i64().and_then(|at| DateTime::from_timestamp(at, 0))
+            }
+            (_, Some(Value::Number(secs))) => secs
+                .as_i64()
+                .map(|secs| Utc::now() + chrono::Duration::seconds(secs)),
+            _ => None,
+        };
+        Ok(Self { secret, expires_at })
+    }
+}
+
+/// Issues fresh credentials for a provider, e.g. by exchanging a role for STS credentials
+/// or an OAuth refresh token for an access token
+#[async_trait]
+pub trait CredentialRefresher: Send + Sync {
+    async fn refresh(&self) -> Result<IssuedCredential>;
+}
+
+/// Runs a shell command and reads the credential from its output, see [`IssuedCredential::parse`]
+pub struct CommandRefresher {
+    command: String,
+}
+
+impl CommandRefresher {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl CredentialRefresher for CommandRefresher {
+    async fn refresh(&self) -> Result<IssuedCredential> {
+        #[cfg(windows)]
+        let output = tokio::process::Command::new("cmd")
+            .args(["/C", &self.command])
+            .output()
+            .await?;
+        #[cfg(not(windows))]
+        let output = tokio::process::Command::new("sh")
+            .args(["-c", &self.command])
+            .output()
+            .await?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "credential command exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        IssuedCredential::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+/// A credential command configured for a provider under `GOOSE_CREDENTIAL_REFRESH`, e.g.
+///
+/// ```yaml
+/// GOOSE_CREDENTIAL_REFRESH:
+///   databricks:
+///     secret_key: DATABRICKS_TOKEN
+///     command: my-sso-tool token --json
+///     refresh_before_secs: 600
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CredentialRefreshConfig {
+    /// The secret the provider reads its credential from, e.g. `OPENAI_API_KEY`
+    pub secret_key: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refresh_before_secs: Option<u64>,
+}
+
+impl CredentialRefreshConfig {
+    pub fn refresh_before(&self) -> Duration {
+        self.refresh_before_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_REFRESH_BEFORE)
+    }
+}
+
+/// A provider credential kept fresh by a refresher. Issued credentials are published as
+/// runtime secrets, so providers created afterwards pick them up through the config system.
+pub struct ManagedCredential {
+    secret_key: String,
+    refresher: Arc<dyn CredentialRefresher>,
+    refresh_before: Duration,
+    current: Mutex<Option<IssuedCredential>>,
+}
+
+impl ManagedCredential {
+    pub fn new(
+        secret_key: impl Into<String>,
+        refresher: Arc<dyn CredentialRefresher>,
+        refresh_before: Duration,
+    ) -> Self {
+        Self {
+            secret_key: secret_key.into(),
+            refresher,
+            refresh_before,
+            current: Mutex::new(None),
+        }
+    }
+
+    pub fn from_config(config: &CredentialRefreshConfig) -> Self {
+        Self::new(
+            config.secret_key.clone(),
+            Arc::new(CommandRefresher::new(config.command.clone())),
+            config.refresh_before(),
+        )
+    }
+
+    pub fn secret_key(&self) -> &str {
+        &self.secret_key
+    }
+
+    /// The credential currently published, if one has been issued
+    pub async fn current(&self) -> Option<IssuedCredential> {
+        self.current.lock().await.clone()
+    }
+
+    /// Issue a new credential if none has been issued yet or the current one expires within
+    /// the refresh margin. Returns whether a new credential was published. Concurrent callers
+    /// wait for a refresh in progress instead of starting their own.
+    pub async fn refresh_if_needed(&self) -> Result<bool> {
+        let mut current = self.current.lock().await;
+        if let Some(credential) = current.as_ref() {
+            if !credential.expires_within(self.refresh_before) {
+                return Ok(false);
+            }
+        }
+
+        let credential = self.refresher.refresh().await?;
+        crate::config::Config::global()
+            .set_runtime_secret(&self.secret_key, Value::String(credential.secret.clone()));
+        *current = Some(credential);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_and_json_credentials() {
+        let plain = IssuedCredential::parse("  sk-abc\n").unwrap();
+        assert_eq!(plain.secret, "sk-abc");
+        assert!(!plain.expires_within(Duration::from_secs(3600)));
+
+        let json =
+            IssuedCredential::parse(r#"{"access_token": "tok", "expires_in": 120}"#).unwrap();
+        assert_eq!(json.secret, "tok");
+        assert!(json.expires_within(DEFAULT_REFRESH_BEFORE));
+        assert!(!json.expires_within(Duration::from_secs(60)));
+
+        let at =
+            IssuedCredential::parse(r#"{"token": "tok", "expires_at": "2030-01-01T00:00:00Z"}"#)
+                .unwrap();
+        assert_eq!(
+            at.expires_at.unwrap().to_rfc3339(),
+            "2030-01-01T00:00:00+00:00"
+        );
+
+        assert!(IssuedCredential::parse(r#"{"expires_in": 60}"#).is_err());
+        assert!(IssuedCredential::parse("").is_err());
+    }
+
+    struct CountingRefresher {
+        calls: std::sync::atomic::AtomicUsize,
+        expires_in: i64,
+    }
+
+    #[async_trait]
+    impl CredentialRefresher for CountingRefresher {
+        async fn refresh(&self) -> Result<IssuedCredential> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(IssuedCredential {
+                secret: format!("token-{}", call),
+                expires_at: Some(Utc::now() + chrono::Duration::seconds(self.expires_in)),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_refreshes_only_near_expiry() {
+        let refresher = Arc::new(CountingRefresher {
+            calls: Default::default(),
+            expires_in: 3600,
+        });
+        let credential = ManagedCredential::new(
+            "GOOSE_TEST_REFRESHED_TOKEN",
+            refresher.clone(),
+            Duration::from_secs(60),
+        );
+
+        assert!(credential.refresh_if_needed().await.unwrap());
+        assert!(!credential.refresh_if_needed().await.unwrap());
+        let token: String = crate::config::Config::global()
+            .get_secret("GOOSE_TEST_REFRESHED_TOKEN")
+            .unwrap();
+        assert_eq!(token, "token-0");
+
+        // A margin longer than the lifetime means every check refreshes
+        let eager = ManagedCredential::new(
+            "GOOSE_TEST_REFRESHED_TOKEN",
+            refresher.clone(),
+            Duration::from_secs(7200),
+        );
+        assert!(eager.refresh_if_needed().await.unwrap());
+        assert!(eager.refresh_if_needed().await.unwrap());
+        assert_eq!(eager.current().await.unwrap().secret, "token-2");
+        crate::config::Config::global().clear_runtime_secret("GOOSE_TEST_REFRESHED_TOKEN");
+    }
+}
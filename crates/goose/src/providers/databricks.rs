@@ -1,7 +1,11 @@
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{
+    ConfigKey, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage, RetryPolicy, Usage,
+};
+use super::egress::EgressExt;
 use super::embedding::EmbeddingCapable;
 use super::errors::ProviderError;
 use super::formats::databricks::{create_request, get_usage, response_to_message};
+use super::middleware::RequestHeadersExt;
 use super::oauth;
 use super::utils::{get_model, ImageFormat};
 use crate::config::ConfigError;
@@ -16,8 +20,10 @@ use async_trait::async_trait;
 use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::time::Duration;
-use tokio::time::sleep;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
 
 const DEFAULT_CLIENT_ID: &str = "databricks-cli";
 const DEFAULT_REDIRECT_URL: &str = "http://localhost:8020";
@@ -35,6 +41,10 @@ const DEFAULT_MAX_RETRIES: usize = 6;
 const DEFAULT_BACKOFF_MULTIPLIER: f64 = 2.0;
 /// Default maximum interval for retry (in milliseconds)
 const DEFAULT_MAX_RETRY_INTERVAL_MS: u64 = 320_000;
+/// Refresh client-credentials tokens this long before they expire
+const TOKEN_REFRESH_MARGIN: Duration = Duration::from_secs(60);
+/// Token lifetime assumed when the token endpoint doesn't say
+const DEFAULT_TOKEN_LIFETIME_SECS: u64 = 3600;
 
 pub const DATABRICKS_DEFAULT_MODEL: &str = "databricks-claude-3-7-sonnet";
 // Databricks can passthrough to a wide range of models, we only provide the default
@@ -48,50 +58,13 @@ pub const DATABRICKS_KNOWN_MODELS: &[&str] = &[
 pub const DATABRICKS_DOC_URL: &str =
     "https://docs.databricks.com/en/generative-ai/external-models/index.html";
 
-/// Retry configuration for handling rate limit errors
-#[derive(Debug, Clone)]
-struct RetryConfig {
-    /// Maximum number of retry attempts
-    max_retries: usize,
-    /// Initial interval between retries in milliseconds
-    initial_interval_ms: u64,
-    /// Multiplier for backoff (exponential)
-    backoff_multiplier: f64,
-    /// Maximum interval between retries in milliseconds
-    max_interval_ms: u64,
-}
-
-impl Default for RetryConfig {
-    fn default() -> Self {
-        Self {
-            max_retries: DEFAULT_MAX_RETRIES,
-            initial_interval_ms: DEFAULT_INITIAL_RETRY_INTERVAL_MS,
-            backoff_multiplier: DEFAULT_BACKOFF_MULTIPLIER,
-            max_interval_ms: DEFAULT_MAX_RETRY_INTERVAL_MS,
-        }
-    }
-}
-
-impl RetryConfig {
-    /// Calculate the delay for a specific retry attempt (with jitter)
-    fn delay_for_attempt(&self, attempt: usize) -> Duration {
-        if attempt == 0 {
-            return Duration::from_millis(0);
-        }
-
-        // Calculate exponential backoff
-        let exponent = (attempt - 1) as u32;
-        let base_delay_ms = (self.initial_interval_ms as f64
-            * self.backoff_multiplier.powi(exponent as i32)) as u64;
-
-        // Apply max limit
-        let capped_delay_ms = std::cmp::min(base_delay_ms, self.max_interval_ms);
-
-        // Add jitter (+/-20% randomness) to avoid thundering herd problem
-        let jitter_factor = 0.8 + (rand::random::<f64>() * 0.4); // Between 0.8 and 1.2
-        let jittered_delay_ms = (capped_delay_ms as f64 * jitter_factor) as u64;
-
-        Duration::from_millis(jittered_delay_ms)
+fn default_retry_policy() -> RetryPolicy {
+    RetryPolicy {
+        max_retries: DEFAULT_MAX_RETRIES,
+        initial_interval_ms: DEFAULT_INITIAL_RETRY_INTERVAL_MS,
+        backoff_multiplier: DEFAULT_BACKOFF_MULTIPLIER,
+        max_interval_ms: DEFAULT_MAX_RETRY_INTERVAL_MS,
+        ..RetryPolicy::default()
     }
 }
 
@@ -104,6 +77,13 @@ pub enum DatabricksAuth {
         redirect_url: String,
         scopes: Vec<String>,
     },
+    /// OAuth machine-to-machine auth with a service principal's client credentials
+    ClientCredentials {
+        host: String,
+        client_id: String,
+        #[serde(skip_serializing)]
+        client_secret: String,
+    },
 }
 
 impl DatabricksAuth {
@@ -119,9 +99,115 @@ impl DatabricksAuth {
     pub fn token(token: String) -> Self {
         Self::Token(token)
     }
+
+    pub fn client_credentials(host: String, client_id: String, client_secret: String) -> Self {
+        Self::ClientCredentials {
+            host,
+            client_id,
+            client_secret,
+        }
+    }
+
+    /// Whether a rejected token can be replaced by fetching a new one
+    fn can_refresh(&self) -> bool {
+        matches!(self, Self::ClientCredentials { .. })
+    }
+}
+
+/// A model served behind a Databricks serving endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ServedModel {
+    /// Name of the served entity within the endpoint
+    pub name: String,
+    /// The model backing it, e.g. a Unity Catalog model or a foundation model name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_version: Option<String>,
+    /// Upstream provider for external models, e.g. "anthropic" or "openai"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_provider: Option<String>,
+}
+
+/// A serving endpoint in the Databricks workspace and the models behind it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ServingEndpoint {
+    pub name: String,
+    /// What the endpoint serves, e.g. "llm/v1/chat" or "llm/v1/embeddings"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task: Option<String>,
+    /// Whether the endpoint is ready to serve requests
+    pub ready: bool,
+    #[serde(default)]
+    pub served_models: Vec<ServedModel>,
+}
+
+impl ServingEndpoint {
+    fn from_api(endpoint: &Value) -> Option<Self> {
+        let name = endpoint.get("name")?.as_str()?.to_string();
+        let task = endpoint
+            .get("task")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        // Endpoints that don't report a state are assumed to be ready
+        let ready = matches!(
+            endpoint.pointer("/state/ready").and_then(Value::as_str),
+            None | Some("READY")
+        );
+        let served_models = endpoint
+            .pointer("/config/served_entities")
+            .or_else(|| endpoint.pointer("/config/served_models"))
+            .and_then(Value::as_array)
+            .map(|entities| {
+                entities
+                    .iter()
+                    .filter_map(|entity| {
+                        let string = |pointer: &str| {
+                            entity
+                                .pointer(pointer)
+                                .and_then(Value::as_str)
+                                .map(str::to_string)
+                        };
+                        Some(ServedModel {
+                            name: string("/name")?,
+                            entity_name: string("/entity_name")
+                                .or_else(|| string("/model_name"))
+                                .or_else(|| string("/foundation_model/name"))
+                                .or_else(|| string("/external_model/name")),
+                            entity_version: string("/entity_version")
+                                .or_else(|| string("/model_version")),
+                            external_provider: string("/external_model/provider"),
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(Self {
+            name,
+            task,
+            ready,
+            served_models,
+        })
+    }
+
+    /// Whether the endpoint can take chat completions. Endpoints that don't report a task
+    /// (e.g. custom models) are assumed to.
+    pub fn supports_chat(&self) -> bool {
+        match self.task.as_deref() {
+            Some(task) => task.ends_with("/chat") || task.ends_with("/completions"),
+            None => true,
+        }
+    }
+}
+
+/// An access token from the client-credentials flow
+#[derive(Debug, Clone)]
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
 }
 
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct DatabricksProvider {
     #[serde(skip)]
     client: Client,
@@ -130,7 +216,9 @@ pub struct DatabricksProvider {
     model: ModelConfig,
     image_format: ImageFormat,
     #[serde(skip)]
-    retry_config: RetryConfig,
+    retry_policy: RetryPolicy,
+    #[serde(skip)]
+    client_credentials_token: Arc<Mutex<Option<CachedToken>>>,
 }
 
 impl Default for DatabricksProvider {
@@ -165,7 +253,7 @@ impl DatabricksProvider {
             .build()?;
 
         // Load optional retry configuration from environment
-        let retry_config = Self::load_retry_config(config);
+        let retry_policy = RetryPolicy::from_config("DATABRICKS", default_retry_policy());
 
         // If we find a databricks token we prefer that
         if let Ok(api_key) = config.get_secret("DATABRICKS_TOKEN") {
@@ -175,7 +263,24 @@ impl DatabricksProvider {
                 auth: DatabricksAuth::token(api_key),
                 model,
                 image_format: ImageFormat::OpenAi,
-                retry_config,
+                retry_policy,
+                client_credentials_token: Arc::default(),
+            });
+        }
+
+        // A service principal's client credentials allow unattended auth that refreshes itself
+        if let (Ok(client_id), Ok(client_secret)) = (
+            config.get_param::<String>("DATABRICKS_CLIENT_ID"),
+            config.get_secret::<String>("DATABRICKS_CLIENT_SECRET"),
+        ) {
+            return Ok(Self {
+                client,
+                auth: DatabricksAuth::client_credentials(host.clone(), client_id, client_secret),
+                host,
+                model,
+                image_format: ImageFormat::OpenAi,
+                retry_policy,
+                client_credentials_token: Arc::default(),
             });
         }
 
@@ -186,44 +291,11 @@ impl DatabricksProvider {
             host,
             model,
             image_format: ImageFormat::OpenAi,
-            retry_config,
+            retry_policy,
+            client_credentials_token: Arc::default(),
         })
     }
 
-    /// Loads retry configuration from environment variables or uses defaults.
-    fn load_retry_config(config: &crate::config::Config) -> RetryConfig {
-        let max_retries = config
-            .get_param("DATABRICKS_MAX_RETRIES")
-            .ok()
-            .and_then(|v: String| v.parse::<usize>().ok())
-            .unwrap_or(DEFAULT_MAX_RETRIES);
-
-        let initial_interval_ms = config
-            .get_param("DATABRICKS_INITIAL_RETRY_INTERVAL_MS")
-            .ok()
-            .and_then(|v: String| v.parse::<u64>().ok())
-            .unwrap_or(DEFAULT_INITIAL_RETRY_INTERVAL_MS);
-
-        let backoff_multiplier = config
-            .get_param("DATABRICKS_BACKOFF_MULTIPLIER")
-            .ok()
-            .and_then(|v: String| v.parse::<f64>().ok())
-            .unwrap_or(DEFAULT_BACKOFF_MULTIPLIER);
-
-        let max_interval_ms = config
-            .get_param("DATABRICKS_MAX_RETRY_INTERVAL_MS")
-            .ok()
-            .and_then(|v: String| v.parse::<u64>().ok())
-            .unwrap_or(DEFAULT_MAX_RETRY_INTERVAL_MS);
-
-        RetryConfig {
-            max_retries,
-            initial_interval_ms,
-            backoff_multiplier,
-            max_interval_ms,
-        }
-    }
-
     /// Create a new DatabricksProvider with the specified host and token
     ///
     /// # Arguments
@@ -245,7 +317,8 @@ impl DatabricksProvider {
             auth: DatabricksAuth::token(api_key),
             model,
             image_format: ImageFormat::OpenAi,
-            retry_config: RetryConfig::default(),
+            retry_policy: default_retry_policy(),
+            client_credentials_token: Arc::default(),
         })
     }
 
@@ -262,7 +335,70 @@ impl DatabricksProvider {
                     oauth::get_oauth_token_async(host, client_id, redirect_url, scopes).await?;
                 Ok(format!("Bearer {}", token))
             }
+            DatabricksAuth::ClientCredentials {
+                host,
+                client_id,
+                client_secret,
+            } => {
+                let token = self
+                    .client_credentials_token(host, client_id, client_secret)
+                    .await?;
+                Ok(format!("Bearer {}", token))
+            }
+        }
+    }
+
+    /// A cached client-credentials token, fetching a new one shortly before it expires
+    async fn client_credentials_token(
+        &self,
+        host: &str,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<String> {
+        let mut cached = self.client_credentials_token.lock().await;
+        if let Some(token) = cached.as_ref() {
+            if token.expires_at > Instant::now() + TOKEN_REFRESH_MARGIN {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let url = Url::parse(host)?.join("oidc/v1/token")?;
+        let response = self
+            .client
+            .post(url)
+            .basic_auth(client_id, Some(client_secret))
+            .form(&[("grant_type", "client_credentials"), ("scope", "all-apis")])
+            .send_with_egress()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!(
+                "Failed to get a Databricks OAuth token for client {}: {}",
+                client_id,
+                status
+            ));
         }
+
+        let body: Value = response.json().await?;
+        let access_token = body
+            .get("access_token")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Databricks token response has no access_token"))?
+            .to_string();
+        let expires_in = body
+            .get("expires_in")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_TOKEN_LIFETIME_SECS);
+        *cached = Some(CachedToken {
+            access_token: access_token.clone(),
+            expires_at: Instant::now() + Duration::from_secs(expires_in),
+        });
+        Ok(access_token)
+    }
+
+    /// Drop the cached client-credentials token so the next request fetches a new one
+    async fn invalidate_token(&self) {
+        self.client_credentials_token.lock().await.take();
     }
 
     async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
@@ -283,151 +419,218 @@ impl DatabricksProvider {
             ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
         })?;
 
-        // Initialize retry counter
-        let mut attempts = 0;
-        let mut last_error = None;
+        let (url, payload) = (&url, &payload);
+        let send = || {
+            self.retry_policy.send(|| async move {
+                let auth_header = self.ensure_auth_header().await?;
+                Ok::<_, ProviderError>(
+                    self.client
+                        .post(url.clone())
+                        .header("Authorization", auth_header)
+                        .with_request_headers()
+                        .json(payload)
+                        .send_with_egress()
+                        .await?,
+                )
+            })
+        };
+        let mut response = send().await?;
 
-        loop {
-            // Check if we've exceeded max retries
-            if attempts > 0 && attempts > self.retry_config.max_retries {
-                let error_msg = format!(
-                    "Exceeded maximum retry attempts ({}) for rate limiting (429)",
-                    self.retry_config.max_retries
-                );
-                tracing::error!("{}", error_msg);
-                return Err(last_error.unwrap_or(ProviderError::RateLimitExceeded(error_msg)));
+        // A token can be revoked or expire early; fetch a fresh one and try once more
+        if response.status() == StatusCode::UNAUTHORIZED && self.auth.can_refresh() {
+            tracing::info!("Databricks rejected the OAuth token, refreshing it and retrying");
+            self.invalidate_token().await;
+            response = send().await?;
+        }
+
+        let status = response.status();
+        let payload: Option<Value> = response.json().await.ok();
+
+        match status {
+            StatusCode::OK => payload.ok_or_else(|| {
+                ProviderError::RequestFailed("Response body is not valid JSON".to_string())
+            }),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                Err(ProviderError::Authentication(format!(
+                    "Authentication failed. Please ensure your API keys are valid and have the required permissions. \
+                    Status: {}. Response: {:?}",
+                    status, payload
+                )))
             }
+            StatusCode::BAD_REQUEST => {
+                // Databricks provides a generic 'error' but also includes 'external_model_message' which is provider specific
+                // We try to extract the error message from the payload and check for phrases that indicate context length exceeded
+                let payload_str = serde_json::to_string(&payload)
+                    .unwrap_or_default()
+                    .to_lowercase();
+                let check_phrases = [
+                    "too long",
+                    "context length",
+                    "context_length_exceeded",
+                    "reduce the length",
+                    "token count",
+                    "exceeds",
+                    "exceed context limit",
+                    "input length",
+                    "max_tokens",
+                    "decrease input length",
+                    "context limit",
+                ];
+                if check_phrases.iter().any(|c| payload_str.contains(c)) {
+                    return Err(ProviderError::ContextLengthExceeded(payload_str));
+                }
 
-            let auth_header = self.ensure_auth_header().await?;
-            let response = self
-                .client
-                .post(url.clone())
-                .header("Authorization", auth_header)
-                .json(&payload)
-                .send()
-                .await?;
-
-            let status = response.status();
-            let payload: Option<Value> = response.json().await.ok();
-
-            match status {
-                StatusCode::OK => {
-                    return payload.ok_or_else(|| {
-                        ProviderError::RequestFailed("Response body is not valid JSON".to_string())
-                    });
+                let mut error_msg = "Unknown error".to_string();
+                if let Some(payload) = &payload {
+                    // try to convert message to string, if that fails use external_model_message
+                    error_msg = payload
+                        .get("message")
+                        .and_then(|m| m.as_str())
+                        .or_else(|| {
+                            payload
+                                .get("external_model_message")
+                                .and_then(|ext| ext.get("message"))
+                                .and_then(|m| m.as_str())
+                        })
+                        .unwrap_or("Unknown error")
+                        .to_string();
                 }
-                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
-                    return Err(ProviderError::Authentication(format!(
-                        "Authentication failed. Please ensure your API keys are valid and have the required permissions. \
-                        Status: {}. Response: {:?}",
+
+                tracing::debug!(
+                    "{}",
+                    format!(
+                        "Provider request failed with status: {}. Payload: {:?}",
                         status, payload
-                    )));
-                }
-                StatusCode::BAD_REQUEST => {
-                    // Databricks provides a generic 'error' but also includes 'external_model_message' which is provider specific
-                    // We try to extract the error message from the payload and check for phrases that indicate context length exceeded
-                    let payload_str = serde_json::to_string(&payload)
-                        .unwrap_or_default()
-                        .to_lowercase();
-                    let check_phrases = [
-                        "too long",
-                        "context length",
-                        "context_length_exceeded",
-                        "reduce the length",
-                        "token count",
-                        "exceeds",
-                        "exceed context limit",
-                        "input length",
-                        "max_tokens",
-                        "decrease input length",
-                        "context limit",
-                    ];
-                    if check_phrases.iter().any(|c| payload_str.contains(c)) {
-                        return Err(ProviderError::ContextLengthExceeded(payload_str));
-                    }
-
-                    let mut error_msg = "Unknown error".to_string();
-                    if let Some(payload) = &payload {
-                        // try to convert message to string, if that fails use external_model_message
-                        error_msg = payload
-                            .get("message")
-                            .and_then(|m| m.as_str())
-                            .or_else(|| {
-                                payload
-                                    .get("external_model_message")
-                                    .and_then(|ext| ext.get("message"))
-                                    .and_then(|m| m.as_str())
-                            })
-                            .unwrap_or("Unknown error")
-                            .to_string();
-                    }
-
-                    tracing::debug!(
-                        "{}",
-                        format!(
-                            "Provider request failed with status: {}. Payload: {:?}",
-                            status, payload
-                        )
-                    );
-                    return Err(ProviderError::RequestFailed(format!(
-                        "Request failed with status: {}. Message: {}",
-                        status, error_msg
-                    )));
-                }
-                StatusCode::TOO_MANY_REQUESTS => {
-                    attempts += 1;
-                    let error_msg = format!(
-                        "Rate limit exceeded (attempt {}/{}): {:?}",
-                        attempts, self.retry_config.max_retries, payload
-                    );
-                    tracing::warn!("{}. Retrying after backoff...", error_msg);
-
-                    // Store the error in case we need to return it after max retries
-                    last_error = Some(ProviderError::RateLimitExceeded(error_msg));
-
-                    // Calculate and apply the backoff delay
-                    let delay = self.retry_config.delay_for_attempt(attempts);
-                    tracing::info!("Backing off for {:?} before retry", delay);
-                    sleep(delay).await;
-
-                    // Continue to the next retry attempt
-                    continue;
-                }
-                StatusCode::INTERNAL_SERVER_ERROR | StatusCode::SERVICE_UNAVAILABLE => {
-                    attempts += 1;
-                    let error_msg = format!(
-                        "Server error (attempt {}/{}): {:?}",
-                        attempts, self.retry_config.max_retries, payload
-                    );
-                    tracing::warn!("{}. Retrying after backoff...", error_msg);
-
-                    // Store the error in case we need to return it after max retries
-                    last_error = Some(ProviderError::ServerError(error_msg));
-
-                    // Calculate and apply the backoff delay
-                    let delay = self.retry_config.delay_for_attempt(attempts);
-                    tracing::info!("Backing off for {:?} before retry", delay);
-                    sleep(delay).await;
-
-                    // Continue to the next retry attempt
-                    continue;
-                }
-                _ => {
-                    tracing::debug!(
-                        "{}",
-                        format!(
-                            "Provider request failed with status: {}. Payload: {:?}",
-                            status, payload
-                        )
-                    );
-                    return Err(ProviderError::RequestFailed(format!(
-                        "Request failed with status: {}",
-                        status
-                    )));
-                }
+                    )
+                );
+                Err(ProviderError::RequestFailed(format!(
+                    "Request failed with status: {}. Message: {}",
+                    status, error_msg
+                )))
             }
+            StatusCode::TOO_MANY_REQUESTS => Err(ProviderError::RateLimitExceeded(format!(
+                "Exceeded maximum retry attempts ({}) for rate limiting (429): {:?}",
+                self.retry_policy.max_retries, payload
+            ))),
+            status if status.is_server_error() => Err(ProviderError::ServerError(format!(
+                "Server error after {} retries ({}): {:?}",
+                self.retry_policy.max_retries, status, payload
+            ))),
+            _ => {
+                tracing::debug!(
+                    "{}",
+                    format!(
+                        "Provider request failed with status: {}. Payload: {:?}",
+                        status, payload
+                    )
+                );
+                Err(ProviderError::RequestFailed(format!(
+                    "Request failed with status: {}",
+                    status
+                )))
+            }
+        }
+    }
+}
+
+impl DatabricksProvider {
+    /// List the workspace's serving endpoints and the models behind them
+    pub async fn list_serving_endpoints(&self) -> Result<Vec<ServingEndpoint>, ProviderError> {
+        let base_url = Url::parse(&self.host)
+            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
+        let url = base_url.join("api/2.0/serving-endpoints").map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
+        })?;
+        let auth_header = self
+            .ensure_auth_header()
+            .await
+            .map_err(|e| ProviderError::Authentication(e.to_string()))?;
+
+        let response = self
+            .client
+            .get(url)
+            .header("Authorization", auth_header)
+            .with_request_headers()
+            .send_with_egress()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(match status {
+                StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                    ProviderError::Authentication(format!(
+                        "Failed to list Databricks serving endpoints: {} - {}",
+                        status, error_text
+                    ))
+                }
+                _ => ProviderError::RequestFailed(format!(
+                    "Failed to list Databricks serving endpoints: {} - {}",
+                    status, error_text
+                )),
+            });
         }
+
+        let json: Value = response.json().await.map_err(|e| {
+            ProviderError::RequestFailed(format!("Failed to parse Databricks API response: {e}"))
+        })?;
+        // A workspace without endpoints omits the array entirely
+        Ok(json
+            .get("endpoints")
+            .and_then(Value::as_array)
+            .map(|endpoints| {
+                endpoints
+                    .iter()
+                    .filter_map(ServingEndpoint::from_api)
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+
+    /// Check that the configured model names a serving endpoint that is ready for chat,
+    /// returning that endpoint. The error lists the chat endpoints that do exist.
+    pub async fn validate_model(&self) -> Result<ServingEndpoint, ProviderError> {
+        let endpoints = self.list_serving_endpoints().await?;
+        check_model(&self.model.model_name, endpoints)
+    }
+}
+
+fn check_model(
+    model_name: &str,
+    endpoints: Vec<ServingEndpoint>,
+) -> Result<ServingEndpoint, ProviderError> {
+    let chat_endpoints: Vec<&str> = endpoints
+        .iter()
+        .filter(|endpoint| endpoint.supports_chat())
+        .map(|endpoint| endpoint.name.as_str())
+        .collect();
+    let Some(endpoint) = endpoints
+        .iter()
+        .find(|endpoint| endpoint.name == model_name)
+    else {
+        return Err(ProviderError::RequestFailed(format!(
+            "No serving endpoint named '{}' in this Databricks workspace. Available chat endpoints: {}",
+            model_name,
+            if chat_endpoints.is_empty() {
+                "none".to_string()
+            } else {
+                chat_endpoints.join(", ")
+            }
+        )));
+    };
+    if !endpoint.supports_chat() {
+        return Err(ProviderError::RequestFailed(format!(
+            "Serving endpoint '{}' serves {} and can't be used for chat",
+            model_name,
+            endpoint.task.as_deref().unwrap_or_default()
+        )));
     }
+    if !endpoint.ready {
+        return Err(ProviderError::ServerError(format!(
+            "Serving endpoint '{}' is not ready yet",
+            model_name
+        )));
+    }
+    Ok(endpoint.clone())
 }
 
 #[async_trait]
@@ -443,6 +646,8 @@ impl Provider for DatabricksProvider {
             vec![
                 ConfigKey::new("DATABRICKS_HOST", true, false, None),
                 ConfigKey::new("DATABRICKS_TOKEN", false, true, None),
+                ConfigKey::new("DATABRICKS_CLIENT_ID", false, false, None),
+                ConfigKey::new("DATABRICKS_CLIENT_SECRET", false, true, None),
             ],
         )
     }
@@ -451,6 +656,17 @@ impl Provider for DatabricksProvider {
         self.model.clone()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::from_model_config(&self.model).with_vision(true)
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(Self {
+            model,
+            ..self.clone()
+        }))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -497,74 +713,17 @@ impl Provider for DatabricksProvider {
     }
 
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
-        let base_url = Url::parse(&self.host)
-            .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
-        let url = base_url.join("api/2.0/serving-endpoints").map_err(|e| {
-            ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
-        })?;
-
-        let auth_header = match self.ensure_auth_header().await {
-            Ok(header) => header,
-            Err(e) => {
-                tracing::warn!("Failed to authorize with Databricks: {}", e);
-                return Ok(None); // Return None to fall back to manual input
-            }
-        };
-
-        let response = match self
-            .client
-            .get(url)
-            .header("Authorization", auth_header)
-            .send()
-            .await
-        {
-            Ok(resp) => resp,
+        let endpoints = match self.list_serving_endpoints().await {
+            Ok(endpoints) => endpoints,
             Err(e) => {
                 tracing::warn!("Failed to fetch Databricks models: {}", e);
                 return Ok(None); // Return None to fall back to manual input
             }
         };
 
-        if !response.status().is_success() {
-            let status = response.status();
-            if let Ok(error_text) = response.text().await {
-                tracing::warn!(
-                    "Failed to fetch Databricks models: {} - {}",
-                    status,
-                    error_text
-                );
-            } else {
-                tracing::warn!("Failed to fetch Databricks models: {}", status);
-            }
-            return Ok(None); // Return None to fall back to manual input
-        }
-
-        let json: Value = match response.json().await {
-            Ok(json) => json,
-            Err(e) => {
-                tracing::warn!("Failed to parse Databricks API response: {}", e);
-                return Ok(None);
-            }
-        };
-
-        let endpoints = match json.get("endpoints").and_then(|v| v.as_array()) {
-            Some(endpoints) => endpoints,
-            None => {
-                tracing::warn!(
-                    "Unexpected response format from Databricks API: missing 'endpoints' array"
-                );
-                return Ok(None);
-            }
-        };
-
         let models: Vec<String> = endpoints
-            .iter()
-            .filter_map(|endpoint| {
-                endpoint
-                    .get("name")
-                    .and_then(|v| v.as_str())
-                    .map(|name| name.to_string())
-            })
+            .into_iter()
+            .map(|endpoint| endpoint.name)
             .collect();
 
         if models.is_empty() {
@@ -612,3 +771,75 @@ impl EmbeddingCapable for DatabricksProvider {
         Ok(embeddings)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_model_shares_client_and_auth() {
+        let provider = DatabricksProvider::from_params(
+            "https://example.cloud.databricks.com".to_string(),
+            "token".to_string(),
+            ModelConfig::new("databricks-meta-llama-3-3-70b-instruct".to_string()),
+        )
+        .unwrap();
+        let switched = provider
+            .with_model(ModelConfig::new("databricks-claude-3-7-sonnet".to_string()))
+            .unwrap();
+
+        assert_eq!(
+            switched.get_model_config().model_name,
+            "databricks-claude-3-7-sonnet"
+        );
+        assert_eq!(
+            provider.get_model_config().model_name,
+            "databricks-meta-llama-3-3-70b-instruct"
+        );
+    }
+
+    #[test]
+    fn test_serving_endpoints_parse_and_validate() {
+        let response = json!({
+            "endpoints": [
+                {
+                    "name": "databricks-claude-3-7-sonnet",
+                    "task": "llm/v1/chat",
+                    "state": {"ready": "READY"},
+                    "config": {"served_entities": [{
+                        "name": "claude",
+                        "external_model": {"provider": "anthropic", "name": "claude-3-7-sonnet"}
+                    }]}
+                },
+                {"name": "gte-large", "task": "llm/v1/embeddings", "state": {"ready": "READY"}},
+                {"name": "my-agent", "state": {"ready": "NOT_READY"}}
+            ]
+        });
+        let endpoints: Vec<ServingEndpoint> = response["endpoints"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(ServingEndpoint::from_api)
+            .collect();
+        assert_eq!(endpoints.len(), 3);
+        assert_eq!(
+            endpoints[0].served_models[0].external_provider.as_deref(),
+            Some("anthropic")
+        );
+        assert_eq!(
+            endpoints[0].served_models[0].entity_name.as_deref(),
+            Some("claude-3-7-sonnet")
+        );
+
+        assert!(check_model("databricks-claude-3-7-sonnet", endpoints.clone()).is_ok());
+        let missing = check_model("gpt-5", endpoints.clone())
+            .unwrap_err()
+            .to_string();
+        assert!(missing.contains("databricks-claude-3-7-sonnet, my-agent"));
+        assert!(check_model("gte-large", endpoints.clone()).is_err());
+        assert!(matches!(
+            check_model("my-agent", endpoints),
+            Err(ProviderError::ServerError(_))
+        ));
+    }
+}
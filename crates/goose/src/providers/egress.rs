@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use aws_smithy_runtime_api::box_error::BoxError;
+use aws_smithy_runtime_api::client::interceptors::context::BeforeTransmitInterceptorContextMut;
+use aws_smithy_runtime_api::client::interceptors::Intercept;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_types::config_bag::ConfigBag;
+use futures::future::BoxFuture;
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use super::regions;
+
+/// Header carrying the host a request was meant for when it is routed through a gateway
+pub const UPSTREAM_HOST_HEADER: &str = "x-forwarded-host";
+pub const SIGNATURE_HEADER: &str = "x-goose-signature";
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "x-goose-signature-timestamp";
+pub const SIGNATURE_KEY_ID_HEADER: &str = "x-goose-signature-key-id";
+
+/// A step every outgoing provider HTTP request goes through before it is sent, e.g. routing
+/// it through a corporate LLM gateway or signing it
+pub trait EgressPolicy: Send + Sync {
+    fn apply(&self, request: &mut reqwest::Request);
+}
+
+/// Sends requests to a gateway instead of the provider's host. The original path and query
+/// are kept under the gateway's base path and the original host is passed in
+/// [`UPSTREAM_HOST_HEADER`], so the gateway knows where to forward the request.
+pub struct GatewayRoute {
+    gateway: Url,
+}
+
+impl GatewayRoute {
+    pub fn new(gateway: Url) -> Self {
+        Self { gateway }
+    }
+}
+
+impl EgressPolicy for GatewayRoute {
+    fn apply(&self, request: &mut reqwest::Request) {
+        let original = request.url().clone();
+        let Some(host) = original.host_str() else {
+            return;
+        };
+        let upstream = match original.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        if let Ok(value) = HeaderValue::from_str(&upstream) {
+            request.headers_mut().insert(UPSTREAM_HOST_HEADER, value);
+        }
+
+        let mut routed = self.gateway.clone();
+        routed.set_path(&format!(
+            "{}{}",
+            self.gateway.path().trim_end_matches('/'),
+            original.path()
+        ));
+        routed.set_query(original.query());
+        *request.url_mut() = routed;
+    }
+}
+
+/// Adds fixed headers, such as a cost center or data classification the gateway enforces
+pub struct PolicyHeaders {
+    headers: HeaderMap,
+}
+
+impl PolicyHeaders {
+    pub fn new(headers: HeaderMap) -> Self {
+        Self { headers }
+    }
+}
+
+impl EgressPolicy for PolicyHeaders {
+    fn apply(&self, request: &mut reqwest::Request) {
+        for (name, value) in &self.headers {
+            request.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+}
+
+/// Signs requests with HMAC-SHA256 over the method, path and query, a unix timestamp and
+/// the SHA-256 of the body, each on its own line. The hex signature and timestamp are sent
+/// in [`SIGNATURE_HEADER`] and [`SIGNATURE_TIMESTAMP_HEADER`].
+pub struct HmacSigner {
+    key_id: Option<String>,
+    key: Vec<u8>,
+}
+
+impl HmacSigner {
+    pub fn new(key: impl Into<Vec<u8>>, key_id: Option<String>) -> Self {
+        Self {
+            key_id,
+            key: key.into(),
+        }
+    }
+
+    fn signature(&self, request: &reqwest::Request, timestamp: i64) -> String {
+        let url = request.url();
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .unwrap_or_default();
+        let to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            request.method(),
+            path,
+            timestamp,
+            to_hex(&Sha256::digest(body))
+        );
+        to_hex(&hmac_sha256(&self.key, to_sign.as_bytes()))
+    }
+}
+
+impl EgressPolicy for HmacSigner {
+    fn apply(&self, request: &mut reqwest::Request) {
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = self.signature(request, timestamp);
+        let headers = request.headers_mut();
+        headers.insert(SIGNATURE_TIMESTAMP_HEADER, HeaderValue::from(timestamp));
+        if let Ok(value) = HeaderValue::from_str(&signature) {
+            headers.insert(SIGNATURE_HEADER, value);
+        }
+        if let Some(value) = self
+            .key_id
+            .as_deref()
+            .and_then(|key_id| HeaderValue::from_str(key_id).ok())
+        {
+            headers.insert(SIGNATURE_KEY_ID_HEADER, value);
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Build the egress policies configured for this process.
+///
+/// `GOOSE_EGRESS_GATEWAY_URL` routes every provider request through a gateway,
+/// `GOOSE_EGRESS_HEADERS` adds headers given as a JSON object such as
+/// `{"x-cost-center": "1234"}`, and the `GOOSE_EGRESS_SIGNING_KEY` secret signs requests,
+/// identified by `GOOSE_EGRESS_SIGNING_KEY_ID` when set. Invalid settings are logged and
+/// skipped.
+pub fn policies_from_config() -> Vec<Arc<dyn EgressPolicy>> {
+    let config = crate::config::Config::global();
+    let mut policies: Vec<Arc<dyn EgressPolicy>> = Vec::new();
+
+    if let Ok(gateway) = config.get_param::<String>("GOOSE_EGRESS_GATEWAY_URL") {
+        match Url::parse(&gateway) {
+            Ok(gateway) => policies.push(Arc::new(GatewayRoute::new(gateway))),
+            Err(e) => tracing::warn!("Ignoring invalid GOOSE_EGRESS_GATEWAY_URL: {}", e),
+        }
+    }
+
+    let headers: HashMap<String, String> =
+        config.get_param("GOOSE_EGRESS_HEADERS").unwrap_or_default();
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        match (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            (Ok(name), Ok(value)) => {
+                header_map.insert(name, value);
+            }
+            _ => tracing::warn!("Ignoring invalid egress header {}", name),
+        }
+    }
+    if !header_map.is_empty() {
+        policies.push(Arc::new(PolicyHeaders::new(header_map)));
+    }
+
+    // Signing goes last so it covers the gateway URL and policy headers
+    if let Ok(key) = config.get_secret::<String>("GOOSE_EGRESS_SIGNING_KEY") {
+        policies.push(Arc::new(HmacSigner::new(
+            key,
+            config.get_param("GOOSE_EGRESS_SIGNING_KEY_ID").ok(),
+        )));
+    }
+    policies
+}
+
+static CONFIGURED: Lazy<Vec<Arc<dyn EgressPolicy>>> = Lazy::new(policies_from_config);
+
+static REGISTERED: Lazy<RwLock<Vec<Arc<dyn EgressPolicy>>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Add a policy applied to every provider request after the configured ones
+pub fn register_egress_policy(policy: Arc<dyn EgressPolicy>) {
+    REGISTERED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(policy);
+}
+
+/// Remove every policy added with [`register_egress_policy`]
+pub fn clear_egress_policies() {
+    REGISTERED
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
+
+fn egress_policies() -> Vec<Arc<dyn EgressPolicy>> {
+    let registered = REGISTERED.read().unwrap_or_else(|e| e.into_inner());
+    CONFIGURED
+        .iter()
+        .chain(registered.iter())
+        .cloned()
+        .collect()
+}
+
+/// Sends a provider request through the egress policies, failing over between regional
+/// endpoints when its URL belongs to a configured region group
+pub trait EgressExt {
+    fn send_with_egress(self) -> BoxFuture<'static, reqwest::Result<reqwest::Response>>;
+}
+
+impl EgressExt for reqwest::RequestBuilder {
+    fn send_with_egress(self) -> BoxFuture<'static, reqwest::Result<reqwest::Response>> {
+        let policies = egress_policies();
+        if policies.is_empty() && !regions::has_regions() {
+            return Box::pin(self.send());
+        }
+
+        let (client, request) = self.build_split();
+        Box::pin(async move {
+            let mut request = request?;
+            // Regions pick the endpoint first, so a gateway or signature covers the final URL
+            if let Some((group, from)) = regions::region_for(request.url()) {
+                return group.send(&client, request, from, &policies).await;
+            }
+            for policy in &policies {
+                policy.apply(&mut request);
+            }
+            client.execute(request).await
+        })
+    }
+}
+
+/// Applies the egress policies to the requests of the AWS SDK clients, which don't send
+/// through reqwest. It runs after the SDK signs a request, so a gateway has to forward it to
+/// the host in [`UPSTREAM_HOST_HEADER`] for the signature to hold.
+#[derive(Debug)]
+pub struct EgressInterceptor;
+
+impl Intercept for EgressInterceptor {
+    fn name(&self) -> &'static str {
+        "GooseEgress"
+    }
+
+    fn modify_before_transmit(
+        &self,
+        context: &mut BeforeTransmitInterceptorContextMut<'_>,
+        _runtime_components: &RuntimeComponents,
+        _cfg: &mut ConfigBag,
+    ) -> Result<(), BoxError> {
+        let policies = egress_policies();
+        if policies.is_empty() {
+            return Ok(());
+        }
+
+        // Mirror the request in reqwest's types for the policies, then copy back what they set
+        let request = context.request_mut();
+        let mut mirrored =
+            reqwest::Request::new(request.method().parse()?, Url::parse(request.uri())?);
+        for (name, value) in request.headers().iter() {
+            mirrored.headers_mut().append(
+                HeaderName::from_bytes(name.as_bytes())?,
+                HeaderValue::from_str(value)?,
+            );
+        }
+        if let Some(body) = request.body().bytes() {
+            *mirrored.body_mut() = Some(body.to_vec().into());
+        }
+        for policy in &policies {
+            policy.apply(&mut mirrored);
+        }
+
+        request.set_uri(mirrored.url().to_string())?;
+        for (name, value) in mirrored.headers() {
+            if let Ok(value) = value.to_str() {
+                request
+                    .headers_mut()
+                    .insert(name.as_str().to_string(), value.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hmac_sha256_matches_rfc_4231() {
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_gateway_route_and_signing() {
+        let client = reqwest::Client::new();
+        let mut request = client
+            .post("https://api.openai.com/v1/chat/completions?stream=false")
+            .body("{}")
+            .build()
+            .unwrap();
+
+        GatewayRoute::new(Url::parse("https://llm-gateway.corp.example/openai/").unwrap())
+            .apply(&mut request);
+        assert_eq!(
+            request.url().as_str(),
+            "https://llm-gateway.corp.example/openai/v1/chat/completions?stream=false"
+        );
+        assert_eq!(request.headers()[UPSTREAM_HOST_HEADER], "api.openai.com");
+
+        let signer = HmacSigner::new("secret", Some("key-1".to_string()));
+        signer.apply(&mut request);
+        let timestamp: i64 = request.headers()[SIGNATURE_TIMESTAMP_HEADER]
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(
+            request.headers()[SIGNATURE_HEADER],
+            signer.signature(&request, timestamp).as_str()
+        );
+        assert_eq!(request.headers()[SIGNATURE_KEY_ID_HEADER], "key-1");
+    }
+}
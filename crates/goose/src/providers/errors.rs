@@ -9,6 +9,9 @@ pub enum ProviderError {
     #[error("Context length exceeded: {0}")]
     ContextLengthExceeded(String),
 
+    #[error("Blocked by content filter: {0}")]
+    ContentFiltered(String),
+
     #[error("Rate limit exceeded: {0}")]
     RateLimitExceeded(String),
 
@@ -23,6 +26,15 @@ pub enum ProviderError {
 
     #[error("Usage data error: {0}")]
     UsageError(String),
+
+    #[error("Provider unavailable: {0}")]
+    CircuitOpen(String),
+
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+
+    #[error("Request cancelled")]
+    Cancelled,
 }
 
 impl From<anyhow::Error> for ProviderError {
@@ -147,6 +159,14 @@ impl OpenAIError {
             false
         }
     }
+
+    pub fn is_content_filtered(&self) -> bool {
+        if let Some(code) = &self.code {
+            code == "content_filter" || code == "content_policy_violation"
+        } else {
+            false
+        }
+    }
 }
 
 impl std::fmt::Display for OpenAIError {
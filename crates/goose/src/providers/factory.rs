@@ -7,14 +7,18 @@ use super::{
     bedrock::BedrockProvider,
     claude_code::ClaudeCodeProvider,
     databricks::DatabricksProvider,
+    fallback::{parse_fallback_chain, FallbackEntry, FallbackProvider},
     gcpvertexai::GcpVertexAIProvider,
     gemini_cli::GeminiCliProvider,
     google::GoogleProvider,
     groq::GroqProvider,
     lead_worker::LeadWorkerProvider,
+    middleware,
+    mock::MockProvider,
     ollama::OllamaProvider,
     openai::OpenAiProvider,
     openrouter::OpenRouterProvider,
+    pool::{global_pool_manager, PoolManager},
     sagemaker_tgi::SageMakerTgiProvider,
     snowflake::SnowflakeProvider,
     venice::VeniceProvider,
@@ -61,21 +65,100 @@ pub fn providers() -> Vec<ProviderMetadata> {
 }
 
 pub fn create(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
+    create_in(global_pool_manager(), name, model)
+}
+
+/// Like [`create`], with the health tracking and limits of `pool` rather than the global pool
+pub(crate) fn create_in(
+    pool: &PoolManager,
+    name: &str,
+    model: ModelConfig,
+) -> Result<Arc<dyn Provider>> {
     let config = crate::config::Config::global();
 
     // Check for lead model environment variables
-    if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
+    let primary = if let Ok(lead_model_name) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
         tracing::info!("Creating lead/worker provider from environment variables");
 
-        return create_lead_worker_from_env(name, &model, &lead_model_name);
+        create_lead_worker_from_env(pool, name, &model, &lead_model_name)?
+    } else {
+        // Default: create regular provider
+        create_provider(pool, name, model)?
+    };
+
+    // Wrap in a fallback chain if backup providers are configured
+    match config.get_param::<String>("GOOSE_FALLBACK_PROVIDERS") {
+        Ok(chain) => create_fallback_chain(pool, name, primary, &chain),
+        Err(_) => Ok(primary),
+    }
+}
+
+/// Create exactly the provider and model asked for, without the lead/worker pair or fallback
+/// chain [`create`] puts around it from `GOOSE_LEAD_MODEL` and `GOOSE_FALLBACK_PROVIDERS`, e.g.
+/// for a recipe that names its model
+pub fn create_exact(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
+    create_provider(global_pool_manager(), name, model)
+}
+
+/// Switch an existing provider to another model, reusing its HTTP client and auth where the
+/// provider supports that and creating a new `name` provider otherwise
+pub fn switch_model(
+    name: &str,
+    provider: &Arc<dyn Provider>,
+    model: ModelConfig,
+) -> Result<Arc<dyn Provider>> {
+    match provider.with_model(model.clone()) {
+        Ok(switched) => Ok(switched),
+        Err(e) => {
+            tracing::debug!("Recreating {} provider to switch models: {}", name, e);
+            create(name, model)
+        }
+    }
+}
+
+/// Check that the model exists for the provider before the first completion call, where
+/// that can be checked up front. Currently Databricks, whose model names are serving
+/// endpoints in the workspace; other providers always pass.
+pub async fn validate_model(name: &str, model: &ModelConfig) -> Result<()> {
+    match name {
+        "databricks" => {
+            DatabricksProvider::from_env(model.clone())?
+                .validate_model()
+                .await?;
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Create a fallback provider from a primary provider and a chain such as
+/// `anthropic:claude-3-5-sonnet-latest,openai:gpt-4o`
+fn create_fallback_chain(
+    pool: &PoolManager,
+    primary_name: &str,
+    primary: Arc<dyn Provider>,
+    chain: &str,
+) -> Result<Arc<dyn Provider>> {
+    let mut entries = vec![FallbackEntry::new(primary_name, primary)];
+    for (provider_name, model_name) in parse_fallback_chain(chain)? {
+        let provider = create_provider(pool, &provider_name, ModelConfig::new(model_name))?;
+        entries.push(FallbackEntry::new(provider_name, provider));
+    }
+
+    if entries.len() == 1 {
+        return Ok(entries.remove(0).provider);
     }
 
-    // Default: create regular provider
-    create_provider(name, model)
+    tracing::info!(
+        "Creating fallback provider with {} backup provider(s)",
+        entries.len() - 1
+    );
+    Ok(Arc::new(FallbackProvider::new(entries)?))
 }
 
 /// Create a lead/worker provider from environment variables
 fn create_lead_worker_from_env(
+    pool: &PoolManager,
     default_provider_name: &str,
     default_model: &ModelConfig,
     lead_model_name: &str,
@@ -134,8 +217,8 @@ fn create_lead_worker_from_env(
     };
 
     // Create the providers
-    let lead_provider = create_provider(&lead_provider_name, lead_model_config)?;
-    let worker_provider = create_provider(default_provider_name, worker_model_config)?;
+    let lead_provider = create_provider(pool, &lead_provider_name, lead_model_config)?;
+    let worker_provider = create_provider(pool, default_provider_name, worker_model_config)?;
 
     // Create the lead/worker provider with configured settings
     Ok(Arc::new(LeadWorkerProvider::new_with_settings(
@@ -147,7 +230,19 @@ fn create_lead_worker_from_env(
     )))
 }
 
-fn create_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
+/// Create a single provider, wrapped with the registered middleware and the
+/// client-side limits configured in the pool
+fn create_provider(
+    pool: &PoolManager,
+    name: &str,
+    model: ModelConfig,
+) -> Result<Arc<dyn Provider>> {
+    let provider = create_unwrapped_provider(name, model)?;
+    let provider = middleware::apply_registered(name, provider);
+    Ok(pool.wrap(name, provider))
+}
+
+fn create_unwrapped_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>> {
     // We use Arc instead of Box to be able to clone for multiple async tasks
     match name {
         "openai" => Ok(Arc::new(OpenAiProvider::from_env(model)?)),
@@ -158,6 +253,8 @@ fn create_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>>
         "databricks" => Ok(Arc::new(DatabricksProvider::from_env(model)?)),
         "gemini-cli" => Ok(Arc::new(GeminiCliProvider::from_env(model)?)),
         "groq" => Ok(Arc::new(GroqProvider::from_env(model)?)),
+        // Scripted responses for tests, deliberately not listed in `providers()`
+        "mock" => Ok(Arc::new(MockProvider::from_env(model)?)),
         "ollama" => Ok(Arc::new(OllamaProvider::from_env(model)?)),
         "openrouter" => Ok(Arc::new(OpenRouterProvider::from_env(model)?)),
         "gcp_vertex_ai" => Ok(Arc::new(GcpVertexAIProvider::from_env(model)?)),
@@ -171,7 +268,9 @@ fn create_provider(name: &str, model: ModelConfig) -> Result<Arc<dyn Provider>>
     }
 }
 
+// These tests set the GOOSE_LEAD_* variables the factory reads
 #[cfg(test)]
+#[serial_test::serial]
 mod tests {
     use super::*;
     use crate::message::{Message, MessageContent};
@@ -222,6 +321,7 @@ mod tests {
                         ),
                         annotations: None,
                     })],
+                    finish_reason: None,
                 },
                 ProviderUsage::new(self.model_config.model_name.clone(), Usage::default()),
             ))
@@ -410,16 +510,19 @@ mod tests {
             ModelConfig::new("gpt-3.5-turbo".to_string()).with_context_limit(Some(16_000));
 
         // Test case 1: No environment variables - should preserve original context_limit
-        let result = create_lead_worker_from_env("openai", &default_model, "gpt-4o");
+        let result =
+            create_lead_worker_from_env(global_pool_manager(), "openai", &default_model, "gpt-4o");
 
         // Test case 2: With GOOSE_WORKER_CONTEXT_LIMIT - should override original
         env::set_var("GOOSE_WORKER_CONTEXT_LIMIT", "32000");
-        let _result = create_lead_worker_from_env("openai", &default_model, "gpt-4o");
+        let _result =
+            create_lead_worker_from_env(global_pool_manager(), "openai", &default_model, "gpt-4o");
         env::remove_var("GOOSE_WORKER_CONTEXT_LIMIT");
 
         // Test case 3: With GOOSE_CONTEXT_LIMIT - should override original
         env::set_var("GOOSE_CONTEXT_LIMIT", "64000");
-        let _result = create_lead_worker_from_env("openai", &default_model, "gpt-4o");
+        let _result =
+            create_lead_worker_from_env(global_pool_manager(), "openai", &default_model, "gpt-4o");
         env::remove_var("GOOSE_CONTEXT_LIMIT");
 
         // Restore env vars
@@ -446,4 +549,17 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_created_providers_are_health_tracked() {
+        let model = "factory-health-tracked";
+        let provider = create("mock", ModelConfig::new(model.to_string())).unwrap();
+
+        let stats = global_pool_manager().stats();
+        let key = stats.get("mock", model).unwrap();
+        assert_eq!((key.active, key.idle), (0, 1));
+
+        drop(provider);
+        assert!(global_pool_manager().stats().get("mock", model).is_none());
+    }
 }
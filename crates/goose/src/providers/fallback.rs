@@ -0,0 +1,364 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+
+use super::base::{
+    LeadWorkerProviderTrait, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+};
+use super::errors::ProviderError;
+use crate::message::Message;
+use crate::model::ModelConfig;
+use mcp_core::tool::Tool;
+
+/// A provider in a fallback chain, identified by the name it was created with
+pub struct FallbackEntry {
+    pub provider_name: String,
+    pub provider: Arc<dyn Provider>,
+}
+
+impl FallbackEntry {
+    pub fn new(provider_name: impl Into<String>, provider: Arc<dyn Provider>) -> Self {
+        Self {
+            provider_name: provider_name.into(),
+            provider,
+        }
+    }
+}
+
+/// A provider that tries an ordered list of providers, moving on to the next entry when
+/// the current one fails with a server, rate limit or authentication error
+pub struct FallbackProvider {
+    entries: Vec<FallbackEntry>,
+    last_served_by: Mutex<Option<String>>,
+}
+
+impl FallbackProvider {
+    /// Create a new FallbackProvider. The first entry is the primary provider.
+    pub fn new(entries: Vec<FallbackEntry>) -> Result<Self> {
+        if entries.is_empty() {
+            return Err(anyhow!("A fallback chain needs at least one provider"));
+        }
+        Ok(Self {
+            entries,
+            last_served_by: Mutex::new(None),
+        })
+    }
+
+    /// Name of the provider that served the most recent successful completion
+    pub fn last_served_by(&self) -> Option<String> {
+        self.last_served_by
+            .lock()
+            .ok()
+            .and_then(|name| name.clone())
+    }
+
+    /// Whether an error should cause the next provider in the chain to be tried
+    pub fn should_fall_back(error: &ProviderError) -> bool {
+        matches!(
+            error,
+            ProviderError::ServerError(_)
+                | ProviderError::RateLimitExceeded(_)
+                | ProviderError::Authentication(_)
+                | ProviderError::CircuitOpen(_)
+        )
+    }
+
+    fn primary(&self) -> &FallbackEntry {
+        &self.entries[0]
+    }
+}
+
+/// Parse a fallback chain such as `anthropic:claude-3-5-sonnet-latest,openai:gpt-4o`
+/// into ordered (provider, model) pairs
+pub fn parse_fallback_chain(chain: &str) -> Result<Vec<(String, String)>> {
+    chain
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| match entry.split_once(':') {
+            Some((provider, model)) if !provider.trim().is_empty() && !model.trim().is_empty() => {
+                Ok((provider.trim().to_string(), model.trim().to_string()))
+            }
+            _ => Err(anyhow!(
+                "Invalid fallback entry '{}', expected provider:model",
+                entry
+            )),
+        })
+        .collect()
+}
+
+#[async_trait]
+impl Provider for FallbackProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "fallback",
+            "Fallback Provider",
+            "A provider that retries on backup providers when the primary fails",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.primary().provider.get_model_config()
+    }
+
+    /// Any entry may serve a request, so only what all of them can do is supported
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.entries
+            .iter()
+            .map(|entry| entry.provider.capabilities())
+            .reduce(|all, capabilities| all.intersect(&capabilities))
+            .unwrap_or_else(|| self.primary().provider.capabilities())
+    }
+
+    /// Switches the primary; the backups keep the models they were configured with
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        let mut entries = vec![FallbackEntry::new(
+            self.primary().provider_name.clone(),
+            self.primary().provider.with_model(model)?,
+        )];
+        entries.extend(
+            self.entries[1..]
+                .iter()
+                .map(|entry| FallbackEntry::new(&entry.provider_name, Arc::clone(&entry.provider))),
+        );
+        Ok(Arc::new(Self {
+            entries,
+            last_served_by: Mutex::new(None),
+        }))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let mut last_error = None;
+
+        for (index, entry) in self.entries.iter().enumerate() {
+            match entry.provider.complete(system, messages, tools).await {
+                Ok((message, usage)) => {
+                    if index > 0 {
+                        tracing::info!(
+                            "Fallback provider {} served the request after {} failed attempt(s)",
+                            entry.provider_name,
+                            index
+                        );
+                    }
+                    if let Ok(mut served_by) = self.last_served_by.lock() {
+                        *served_by = Some(entry.provider_name.clone());
+                    }
+                    return Ok((message, usage.with_provider(entry.provider_name.clone())));
+                }
+                Err(e) if Self::should_fall_back(&e) => {
+                    tracing::warn!(
+                        "Provider {} failed ({}), trying next provider in fallback chain",
+                        entry.provider_name,
+                        e
+                    );
+                    last_error = Some(e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| {
+            ProviderError::ExecutionError("No providers in fallback chain".to_string())
+        }))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.primary().provider.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.entries
+            .iter()
+            .any(|entry| entry.provider.supports_embeddings())
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        match self
+            .entries
+            .iter()
+            .find(|entry| entry.provider.supports_embeddings())
+        {
+            Some(entry) => entry.provider.create_embeddings(texts).await,
+            None => Err(ProviderError::ExecutionError(
+                "No provider in the fallback chain supports embeddings".to_string(),
+            )),
+        }
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.primary().provider.as_lead_worker()
+    }
+
+    fn get_active_model_name(&self) -> String {
+        self.primary().provider.get_active_model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+
+    struct MockProvider {
+        model_config: ModelConfig,
+        error: Option<fn() -> ProviderError>,
+    }
+
+    impl MockProvider {
+        fn ok(model: &str) -> Arc<dyn Provider> {
+            Arc::new(Self {
+                model_config: ModelConfig::new(model.to_string()),
+                error: None,
+            })
+        }
+
+        fn failing(model: &str, error: fn() -> ProviderError) -> Arc<dyn Provider> {
+            Arc::new(Self {
+                model_config: ModelConfig::new(model.to_string()),
+                error: Some(error),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            self.model_config.clone()
+        }
+
+        fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+            Ok(Arc::new(Self {
+                model_config: model,
+                error: self.error,
+            }))
+        }
+
+        async fn complete(
+            &self,
+            _system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            match self.error {
+                Some(error) => Err(error()),
+                None => Ok((
+                    Message::assistant().with_text("ok"),
+                    ProviderUsage::new(self.model_config.model_name.clone(), Usage::default()),
+                )),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_on_server_error() {
+        let provider = FallbackProvider::new(vec![
+            FallbackEntry::new(
+                "openai",
+                MockProvider::failing("gpt-4o", || ProviderError::ServerError("down".to_string())),
+            ),
+            FallbackEntry::new("anthropic", MockProvider::ok("claude-3-5-sonnet")),
+        ])
+        .unwrap();
+
+        let (_, usage) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.model, "claude-3-5-sonnet");
+        assert_eq!(usage.provider.as_deref(), Some("anthropic"));
+        assert_eq!(provider.last_served_by().as_deref(), Some("anthropic"));
+    }
+
+    #[tokio::test]
+    async fn test_does_not_fall_back_on_context_length() {
+        let provider = FallbackProvider::new(vec![
+            FallbackEntry::new(
+                "openai",
+                MockProvider::failing("gpt-4o", || {
+                    ProviderError::ContextLengthExceeded("too long".to_string())
+                }),
+            ),
+            FallbackEntry::new("anthropic", MockProvider::ok("claude-3-5-sonnet")),
+        ])
+        .unwrap();
+
+        let result = provider.complete("system", &[], &[]).await;
+        assert!(matches!(
+            result,
+            Err(ProviderError::ContextLengthExceeded(_))
+        ));
+        assert!(provider.last_served_by().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_returns_last_error_when_all_fail() {
+        let provider = FallbackProvider::new(vec![
+            FallbackEntry::new(
+                "openai",
+                MockProvider::failing("gpt-4o", || {
+                    ProviderError::RateLimitExceeded("slow down".to_string())
+                }),
+            ),
+            FallbackEntry::new(
+                "anthropic",
+                MockProvider::failing("claude-3-5-sonnet", || {
+                    ProviderError::Authentication("bad key".to_string())
+                }),
+            ),
+        ])
+        .unwrap();
+
+        let result = provider.complete("system", &[], &[]).await;
+        assert!(matches!(result, Err(ProviderError::Authentication(_))));
+    }
+
+    #[tokio::test]
+    async fn test_with_model_switches_the_primary_and_keeps_the_backups() {
+        let provider = FallbackProvider::new(vec![
+            FallbackEntry::new(
+                "openai",
+                MockProvider::failing("gpt-4o-mini", || {
+                    ProviderError::ServerError("down".to_string())
+                }),
+            ),
+            FallbackEntry::new("anthropic", MockProvider::ok("claude-3-5-sonnet")),
+        ])
+        .unwrap();
+
+        let switched = provider
+            .with_model(ModelConfig::new("gpt-4o".to_string()))
+            .unwrap();
+        assert_eq!(switched.get_model_config().model_name, "gpt-4o");
+        assert_eq!(switched.get_active_model_name(), "gpt-4o");
+        assert!(switched.as_lead_worker().is_none());
+
+        let (_, usage) = switched.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(usage.provider.as_deref(), Some("anthropic"));
+        assert_eq!(usage.model, "claude-3-5-sonnet");
+    }
+
+    #[test]
+    fn test_parse_fallback_chain() {
+        let chain = parse_fallback_chain("anthropic:claude-3-5-sonnet, openai:gpt-4o").unwrap();
+        assert_eq!(
+            chain,
+            vec![
+                ("anthropic".to_string(), "claude-3-5-sonnet".to_string()),
+                ("openai".to_string(), "gpt-4o".to_string()),
+            ]
+        );
+        assert!(parse_fallback_chain("openai").is_err());
+        assert!(parse_fallback_chain("openai:").is_err());
+    }
+}
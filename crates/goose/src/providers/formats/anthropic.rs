@@ -1,4 +1,4 @@
-use crate::message::{Message, MessageContent};
+use crate::message::{FinishReason, Message, MessageContent};
 use crate::model::ModelConfig;
 use crate::providers::base::Usage;
 use crate::providers::errors::ProviderError;
@@ -66,6 +66,9 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                 MessageContent::SummarizationRequested(_) => {
                     // Skip
                 }
+                MessageContent::Assumption(_) | MessageContent::Question(_) => {
+                    // Flattened to text before reaching the provider
+                }
                 MessageContent::Thinking(thinking) => {
                     content.push(json!({
                         "type": "thinking",
@@ -230,6 +233,14 @@ pub fn response_to_message(response: Value) -> Result<Message> {
         }
     }
 
+    if let Some(finish_reason) = response
+        .get("stop_reason")
+        .and_then(|r| r.as_str())
+        .and_then(FinishReason::from_anthropic)
+    {
+        message = message.with_finish_reason(finish_reason);
+    }
+
     Ok(message)
 }
 
@@ -10,7 +10,7 @@ use mcp_core::{Content, ResourceContents, Role, Tool, ToolCall, ToolError, ToolR
 use serde_json::Value;
 
 use super::super::base::Usage;
-use crate::message::{Message, MessageContent};
+use crate::message::{FinishReason, Message, MessageContent};
 use mcp_core::content::ImageContent;
 
 pub fn to_bedrock_message(message: &Message) -> Result<bedrock::Message> {
@@ -48,6 +48,9 @@ pub fn to_bedrock_message_content(content: &MessageContent) -> Result<bedrock::C
         MessageContent::SummarizationRequested(_) => {
             bail!("SummarizationRequested should not get passed to the provider")
         }
+        MessageContent::Assumption(_) | MessageContent::Question(_) => {
+            bail!("Assumptions and questions should be flattened to text before the provider")
+        }
         MessageContent::ToolRequest(tool_req) => {
             let tool_use_id = tool_req.id.to_string();
             let tool_use = if let Ok(call) = tool_req.tool_call.as_ref() {
@@ -264,9 +267,24 @@ pub fn from_bedrock_message(message: &bedrock::Message) -> Result<Message> {
         role,
         content,
         created,
+        finish_reason: None,
     })
 }
 
+pub fn from_bedrock_stop_reason(stop_reason: &bedrock::StopReason) -> Option<FinishReason> {
+    match stop_reason {
+        bedrock::StopReason::EndTurn | bedrock::StopReason::StopSequence => {
+            Some(FinishReason::Stop)
+        }
+        bedrock::StopReason::ToolUse => Some(FinishReason::ToolCalls),
+        bedrock::StopReason::MaxTokens => Some(FinishReason::Length),
+        bedrock::StopReason::ContentFiltered | bedrock::StopReason::GuardrailIntervened => {
+            Some(FinishReason::ContentFilter)
+        }
+        _ => None,
+    }
+}
+
 pub fn from_bedrock_content_block(block: &bedrock::ContentBlock) -> Result<MessageContent> {
     Ok(match block {
         bedrock::ContentBlock::Text(text) => MessageContent::text(text),
@@ -317,11 +335,11 @@ pub fn from_bedrock_role(role: &bedrock::ConversationRole) -> Result<Role> {
 }
 
 pub fn from_bedrock_usage(usage: &bedrock::TokenUsage) -> Usage {
-    Usage {
-        input_tokens: Some(usage.input_tokens),
-        output_tokens: Some(usage.output_tokens),
-        total_tokens: Some(usage.total_tokens),
-    }
+    Usage::new(
+        Some(usage.input_tokens),
+        Some(usage.output_tokens),
+        Some(usage.total_tokens),
+    )
 }
 
 pub fn from_bedrock_json(document: &Document) -> Result<Value> {
@@ -1,6 +1,6 @@
-use crate::message::{Message, MessageContent};
+use crate::message::{FinishReason, Message, MessageContent};
 use crate::model::ModelConfig;
-use crate::providers::base::Usage;
+use crate::providers::base::{current_seed, Usage};
 use crate::providers::errors::ProviderError;
 use crate::providers::utils::{
     convert_image, detect_image_path, is_valid_function_name, load_image_file,
@@ -116,6 +116,10 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 MessageContent::SummarizationRequested(_) => {
                     continue;
                 }
+                MessageContent::Assumption(_) | MessageContent::Question(_) => {
+                    // Flattened to text before reaching the provider
+                    continue;
+                }
                 MessageContent::ToolResponse(response) => {
                     match &response.tool_result {
                         Ok(contents) => {
@@ -358,10 +362,15 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
         }
     }
 
+    let finish_reason = response["choices"][0]["finish_reason"]
+        .as_str()
+        .and_then(FinishReason::from_openai);
+
     Ok(Message {
         role: Role::Assistant,
         created: chrono::Utc::now().timestamp(),
         content,
+        finish_reason,
     })
 }
 
@@ -569,6 +578,13 @@ pub fn create_request(
         }
     }
 
+    if let Some(seed) = current_seed() {
+        payload
+            .as_object_mut()
+            .unwrap()
+            .insert("seed".to_string(), json!(seed));
+    }
+
     Ok(payload)
 }
 
@@ -1,6 +1,6 @@
-use crate::message::{Message, MessageContent};
+use crate::message::{FinishReason, Message, MessageContent};
 use crate::model::ModelConfig;
-use crate::providers::base::Usage;
+use crate::providers::base::{current_seed, Usage};
 use crate::providers::errors::ProviderError;
 use crate::providers::utils::{is_valid_function_name, sanitize_function_name};
 use anyhow::Result;
@@ -209,10 +209,16 @@ pub fn response_to_message(response: Value) -> Result<Message> {
     let role = Role::Assistant;
     let created = chrono::Utc::now().timestamp();
     if candidate.is_none() {
+        // A prompt blocked by safety settings comes back without candidates
+        let finish_reason = response
+            .get("promptFeedback")
+            .and_then(|feedback| feedback.get("blockReason"))
+            .map(|_| FinishReason::ContentFilter);
         return Ok(Message {
             role,
             created,
             content,
+            finish_reason,
         });
     }
     let candidate = candidate.unwrap();
@@ -252,10 +258,16 @@ pub fn response_to_message(response: Value) -> Result<Message> {
             }
         }
     }
+    let finish_reason = candidate
+        .get("finishReason")
+        .and_then(|r| r.as_str())
+        .and_then(FinishReason::from_google);
+
     Ok(Message {
         role,
         created,
         content,
+        finish_reason,
     })
 }
 
@@ -311,6 +323,9 @@ pub fn create_request(
     if let Some(tokens) = model_config.max_tokens {
         generation_config.insert("maxOutputTokens".to_string(), json!(tokens));
     }
+    if let Some(seed) = current_seed() {
+        generation_config.insert("seed".to_string(), json!(seed));
+    }
     if !generation_config.is_empty() {
         payload.insert("generationConfig".to_string(), json!(generation_config));
     }
@@ -328,6 +343,7 @@ mod tests {
             role,
             created: 0,
             content: vec![MessageContent::text(text.to_string())],
+            finish_reason: None,
         }
     }
 
@@ -336,6 +352,7 @@ mod tests {
             role: Role::User,
             created: 0,
             content: vec![MessageContent::tool_request(id.to_string(), Ok(tool_call))],
+            finish_reason: None,
         }
     }
 
@@ -349,6 +366,7 @@ mod tests {
                 tool_call.arguments.clone(),
                 Some("Goose would like to call the above tool. Allow? (y/n):".to_string()),
             )],
+            finish_reason: None,
         }
     }
 
@@ -360,6 +378,7 @@ mod tests {
                 id.to_string(),
                 Ok(tool_response),
             )],
+            finish_reason: None,
         }
     }
 
@@ -529,6 +548,19 @@ mod tests {
         let message = response_to_message(response).unwrap();
         assert_eq!(message.role, Role::Assistant);
         assert!(message.content.is_empty());
+        assert_eq!(message.finish_reason, None);
+    }
+
+    #[test]
+    fn test_response_to_message_with_blocked_prompt() {
+        let response = json!({
+            "promptFeedback": {
+                "blockReason": "SAFETY"
+            }
+        });
+        let message = response_to_message(response).unwrap();
+        assert!(message.content.is_empty());
+        assert_eq!(message.finish_reason, Some(FinishReason::ContentFilter));
     }
 
     #[test]
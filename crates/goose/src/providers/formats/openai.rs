@@ -1,6 +1,6 @@
-use crate::message::{Message, MessageContent};
+use crate::message::{FinishReason, Message, MessageContent};
 use crate::model::ModelConfig;
-use crate::providers::base::Usage;
+use crate::providers::base::{current_seed, Usage};
 use crate::providers::errors::ProviderError;
 use crate::providers::utils::{
     convert_image, detect_image_path, is_valid_function_name, load_image_file,
@@ -58,6 +58,10 @@ pub fn format_messages(messages: &[Message], image_format: &ImageFormat) -> Vec<
                 MessageContent::SummarizationRequested(_) => {
                     continue;
                 }
+                MessageContent::Assumption(_) | MessageContent::Question(_) => {
+                    // Flattened to text before reaching the provider
+                    continue;
+                }
                 MessageContent::ToolRequest(request) => match &request.tool_call {
                     Ok(tool_call) => {
                         let sanitized_name = sanitize_function_name(&tool_call.name);
@@ -274,10 +278,15 @@ pub fn response_to_message(response: Value) -> anyhow::Result<Message> {
         }
     }
 
+    let finish_reason = response["choices"][0]["finish_reason"]
+        .as_str()
+        .and_then(FinishReason::from_openai);
+
     Ok(Message {
         role: Role::Assistant,
         created: chrono::Utc::now().timestamp(),
         content,
+        finish_reason,
     })
 }
 
@@ -323,7 +332,7 @@ pub fn validate_tool_schemas(tools: &mut [Value]) {
 }
 
 /// Ensures that the given JSON value follows the expected JSON Schema structure.
-fn ensure_valid_json_schema(schema: &mut Value) {
+pub(crate) fn ensure_valid_json_schema(schema: &mut Value) {
     if let Some(params_obj) = schema.as_object_mut() {
         // Check if this is meant to be an object type schema
         let is_object_type = params_obj
@@ -354,6 +363,119 @@ fn ensure_valid_json_schema(schema: &mut Value) {
     }
 }
 
+/// Options controlling how tool calls are requested from OpenAI compatible endpoints
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ToolCallOptions {
+    /// Allow or forbid the model to emit several tool calls in one assistant message.
+    /// When unset the endpoint default applies.
+    pub parallel_tool_calls: Option<bool>,
+    /// Mark function schemas as `strict` so generated arguments always match the schema
+    pub strict: bool,
+}
+
+/// Apply tool call options to a request created by `create_request`.
+/// Has no effect on requests without tools, since OpenAI rejects `parallel_tool_calls` there.
+pub fn apply_tool_call_options(payload: &mut Value, options: &ToolCallOptions) {
+    let Some(obj) = payload.as_object_mut() else {
+        return;
+    };
+    let Some(tools) = obj.get_mut("tools").and_then(|t| t.as_array_mut()) else {
+        return;
+    };
+
+    if options.strict {
+        for tool in tools.iter_mut() {
+            if let Some(function) = tool.get_mut("function").and_then(|f| f.as_object_mut()) {
+                if let Some(parameters) = function.get_mut("parameters") {
+                    make_schema_strict(parameters);
+                }
+                function.insert("strict".to_string(), json!(true));
+            }
+        }
+    }
+
+    if let Some(parallel) = options.parallel_tool_calls {
+        obj.insert("parallel_tool_calls".to_string(), json!(parallel));
+    }
+}
+
+/// Rewrite a JSON schema to satisfy OpenAI's strict mode: every object lists all of its
+/// properties as required and forbids additional properties. Properties that were optional
+/// become nullable so the model can still omit a value.
+fn make_schema_strict(schema: &mut Value) {
+    let Some(obj) = schema.as_object_mut() else {
+        return;
+    };
+
+    if let Some(items) = obj.get_mut("items") {
+        make_schema_strict(items);
+    }
+
+    let required: Vec<String> = obj
+        .get("required")
+        .and_then(|r| r.as_array())
+        .map(|r| {
+            r.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let property_names = match obj.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        Some(properties) => {
+            for (name, property) in properties.iter_mut() {
+                make_schema_strict(property);
+                if !required.contains(name) {
+                    allow_null(property);
+                }
+            }
+            Some(properties.keys().map(|k| json!(k)).collect::<Vec<_>>())
+        }
+        None => None,
+    };
+
+    if let Some(property_names) = property_names {
+        obj.insert("required".to_string(), Value::Array(property_names));
+    }
+    if obj.get("type").and_then(|t| t.as_str()) == Some("object") {
+        obj.insert("additionalProperties".to_string(), json!(false));
+    }
+}
+
+fn allow_null(schema: &mut Value) {
+    if let Some(obj) = schema.as_object_mut() {
+        match obj.get_mut("type") {
+            Some(Value::String(t)) if t != "null" => {
+                let t = t.clone();
+                obj.insert("type".to_string(), json!([t, "null"]));
+            }
+            Some(Value::Array(types)) if !types.iter().any(|t| t == "null") => {
+                types.push(json!("null"));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The model name to send and its reasoning effort, for O family models. The effort can be
+/// given as a suffix of the model name, e.g. `o3-high`, and is `medium` otherwise.
+pub fn split_reasoning_effort(model_name: &str) -> (String, Option<String>) {
+    if !model_name.starts_with("o") {
+        // For non-O family models, use the model name as is and no reasoning effort
+        return (model_name.to_string(), None);
+    }
+
+    let parts: Vec<&str> = model_name.split('-').collect();
+    let last_part = parts.last().unwrap();
+    match *last_part {
+        "low" | "medium" | "high" => {
+            let base_name = parts[..parts.len() - 1].join("-");
+            (base_name, Some(last_part.to_string()))
+        }
+        _ => (model_name.to_string(), Some("medium".to_string())),
+    }
+}
+
 pub fn create_request(
     model_config: &ModelConfig,
     system: &str,
@@ -368,26 +490,7 @@ pub fn create_request(
     }
 
     let is_ox_model = model_config.model_name.starts_with("o");
-
-    // Only extract reasoning effort for O1/O3 models
-    let (model_name, reasoning_effort) = if is_ox_model {
-        let parts: Vec<&str> = model_config.model_name.split('-').collect();
-        let last_part = parts.last().unwrap();
-
-        match *last_part {
-            "low" | "medium" | "high" => {
-                let base_name = parts[..parts.len() - 1].join("-");
-                (base_name, Some(last_part.to_string()))
-            }
-            _ => (
-                model_config.model_name.to_string(),
-                Some("medium".to_string()),
-            ),
-        }
-    } else {
-        // For non-O family models, use the model name as is and no reasoning effort
-        (model_config.model_name.to_string(), None)
-    };
+    let (model_name, reasoning_effort) = split_reasoning_effort(&model_config.model_name);
 
     let system_message = json!({
         "role": if is_ox_model { "developer" } else { "system" },
@@ -435,6 +538,13 @@ pub fn create_request(
         }
     }
 
+    if let Some(seed) = current_seed() {
+        payload
+            .as_object_mut()
+            .unwrap()
+            .insert("seed".to_string(), json!(seed));
+    }
+
     // o1 models use max_completion_tokens instead of max_tokens
     if let Some(tokens) = model_config.max_tokens {
         let key = if is_ox_model {
@@ -879,6 +989,119 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_response_to_message_multiple_tool_calls() -> anyhow::Result<()> {
+        let response = json!({
+            "choices": [{
+                "role": "assistant",
+                "message": {
+                    "tool_calls": [
+                        {
+                            "id": "call_1",
+                            "function": {"name": "read_file", "arguments": "{\"path\": \"a.rs\"}"}
+                        },
+                        {
+                            "id": "call_2",
+                            "function": {"name": "read_file", "arguments": "{\"path\": \"b.rs\"}"}
+                        }
+                    ]
+                }
+            }]
+        });
+
+        let message = response_to_message(response)?;
+        let ids: Vec<&str> = message
+            .content
+            .iter()
+            .filter_map(|c| match c {
+                MessageContent::ToolRequest(request) => Some(request.id.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, vec!["call_1", "call_2"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_tool_call_options() -> anyhow::Result<()> {
+        let tool = Tool::new(
+            "search",
+            "Search things",
+            json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string"},
+                    "limit": {"type": "integer"}
+                },
+                "required": ["query"]
+            }),
+            None,
+        );
+        let model_config = ModelConfig::new("gpt-4o".to_string());
+        let mut request =
+            create_request(&model_config, "system", &[], &[tool], &ImageFormat::OpenAi)?;
+
+        apply_tool_call_options(
+            &mut request,
+            &ToolCallOptions {
+                parallel_tool_calls: Some(true),
+                strict: true,
+            },
+        );
+
+        assert_eq!(request["parallel_tool_calls"], json!(true));
+        let function = &request["tools"][0]["function"];
+        assert_eq!(function["strict"], json!(true));
+        assert_eq!(function["parameters"]["additionalProperties"], json!(false));
+        assert_eq!(
+            function["parameters"]["required"],
+            json!(["limit", "query"])
+        );
+        assert_eq!(
+            function["parameters"]["properties"]["limit"]["type"],
+            json!(["integer", "null"])
+        );
+        assert_eq!(
+            function["parameters"]["properties"]["query"]["type"],
+            json!("string")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_tool_call_options_without_tools() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("gpt-4o".to_string());
+        let mut request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+
+        apply_tool_call_options(
+            &mut request,
+            &ToolCallOptions {
+                parallel_tool_calls: Some(false),
+                strict: true,
+            },
+        );
+
+        assert!(request.get("parallel_tool_calls").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_request_with_seed() -> anyhow::Result<()> {
+        let model_config = ModelConfig::new("gpt-4o".to_string());
+        let request = create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)?;
+        assert!(request.get("seed").is_none());
+
+        let request = crate::providers::base::REQUEST_SEED.sync_scope(42, || {
+            create_request(&model_config, "system", &[], &[], &ImageFormat::OpenAi)
+        })?;
+        assert_eq!(request["seed"], json!(42));
+
+        Ok(())
+    }
+
     #[test]
     fn test_create_request_o1_default() -> anyhow::Result<()> {
         // Test default medium reasoning effort for O1 model
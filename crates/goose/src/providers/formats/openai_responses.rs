@@ -0,0 +1,456 @@
+//! Request and response formats for the OpenAI Responses API (`/v1/responses`).
+//!
+//! Unlike chat completions, the conversation is a flat list of input items: messages,
+//! `function_call`s and their `function_call_output`s. The server can keep the conversation
+//! itself, so a request may continue from a `previous_response_id` and only send the items
+//! added since that response, see [`ResponseChain`].
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::message::{FinishReason, Message, MessageContent};
+use crate::model::ModelConfig;
+use crate::providers::base::Usage;
+use crate::providers::errors::ProviderError;
+use crate::providers::formats::openai::{ensure_valid_json_schema, split_reasoning_effort};
+use crate::providers::utils::{is_valid_function_name, sanitize_function_name};
+use anyhow::{anyhow, Error};
+use mcp_core::ToolError;
+use mcp_core::{Content, Role, Tool, ToolCall};
+use serde_json::{json, Value};
+
+/// How many response ids a provider remembers to continue conversations from
+const MAX_CHAINED_RESPONSES: usize = 512;
+
+fn image_url(image: &mcp_core::content::ImageContent) -> String {
+    format!("data:{};base64,{}", image.mime_type, image.data)
+}
+
+/// Convert one message to Responses API input items
+pub fn format_message(message: &Message) -> Vec<Value> {
+    let (text_type, role) = match message.role {
+        Role::User => ("input_text", "user"),
+        Role::Assistant => ("output_text", "assistant"),
+    };
+
+    let mut parts = Vec::new();
+    let mut items = Vec::new();
+    for content in &message.content {
+        match content {
+            MessageContent::Text(text) if !text.text.is_empty() => {
+                parts.push(json!({"type": text_type, "text": text.text}));
+            }
+            // Images can only be sent as input
+            MessageContent::Image(image) if message.role == Role::User => {
+                parts.push(json!({"type": "input_image", "image_url": image_url(image)}));
+            }
+            MessageContent::ToolRequest(request) => {
+                items.push(function_call(&request.id, &request.tool_call));
+            }
+            MessageContent::FrontendToolRequest(request) => {
+                items.push(function_call(&request.id, &request.tool_call));
+            }
+            MessageContent::ToolResponse(response) => {
+                let (output, images) = match &response.tool_result {
+                    Ok(contents) => tool_output(contents),
+                    // Shown as output so the model can interpret the error message
+                    Err(e) => (
+                        format!("The tool call returned the following error:\n{}", e),
+                        Vec::new(),
+                    ),
+                };
+                items.push(json!({
+                    "type": "function_call_output",
+                    "call_id": response.id,
+                    "output": output,
+                }));
+                // Tool outputs are text only, so images follow as a user message
+                if !images.is_empty() {
+                    items.push(json!({"role": "user", "content": images}));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !parts.is_empty() {
+        items.insert(0, json!({"role": role, "content": parts}));
+    }
+    items
+}
+
+fn function_call(id: &str, tool_call: &Result<ToolCall, ToolError>) -> Value {
+    match tool_call {
+        Ok(tool_call) => json!({
+            "type": "function_call",
+            "call_id": id,
+            "name": sanitize_function_name(&tool_call.name),
+            "arguments": tool_call.arguments.to_string(),
+        }),
+        // The call never ran; keep the pairing with its output intact
+        Err(e) => json!({
+            "type": "function_call",
+            "call_id": id,
+            "name": "invalid_tool_call",
+            "arguments": json!({"error": e.to_string()}).to_string(),
+        }),
+    }
+}
+
+/// The text of a tool result, and its images as `input_image` parts
+fn tool_output(contents: &[Content]) -> (String, Vec<Value>) {
+    let mut text = Vec::new();
+    let mut images = Vec::new();
+    for content in contents.iter().filter(|content| {
+        content
+            .audience()
+            .is_none_or(|audience| audience.contains(&Role::Assistant))
+    }) {
+        match content {
+            Content::Text(t) => text.push(t.text.clone()),
+            Content::Resource(resource) => text.push(resource.get_text()),
+            Content::Image(image) => {
+                text.push(
+                    "This tool result included an image that is uploaded in the next message."
+                        .to_string(),
+                );
+                images.push(json!({"type": "input_image", "image_url": image_url(image)}));
+            }
+        }
+    }
+    (text.join(" "), images)
+}
+
+/// Convert tools to Responses API function tools
+pub fn format_tools(tools: &[Tool]) -> anyhow::Result<Vec<Value>> {
+    let mut tool_names = std::collections::HashSet::new();
+    let mut result = Vec::new();
+    for tool in tools {
+        if !tool_names.insert(&tool.name) {
+            return Err(anyhow!("Duplicate tool name: {}", tool.name));
+        }
+        result.push(json!({
+            "type": "function",
+            "name": tool.name,
+            "description": tool.description,
+            "parameters": tool.input_schema,
+        }));
+    }
+    Ok(result)
+}
+
+/// A tool hosted by OpenAI, e.g. `web_search_preview`, `code_interpreter` or `image_generation`
+pub fn builtin_tool(name: &str) -> Value {
+    match name {
+        "code_interpreter" => json!({"type": name, "container": {"type": "auto"}}),
+        _ => json!({"type": name}),
+    }
+}
+
+/// Build a Responses API request. `input` is the conversation, or only the items added
+/// since `previous_response_id` when continuing a stored response.
+pub fn create_request(
+    model_config: &ModelConfig,
+    system: &str,
+    input: Vec<Value>,
+    tools: &[Tool],
+    builtin_tools: &[String],
+    previous_response_id: Option<&str>,
+) -> anyhow::Result<Value, Error> {
+    let (model_name, reasoning_effort) = split_reasoning_effort(&model_config.model_name);
+
+    let mut tools_spec = format_tools(tools)?;
+    for tool in tools_spec.iter_mut() {
+        if let Some(parameters) = tool.get_mut("parameters").filter(|p| p.is_object()) {
+            ensure_valid_json_schema(parameters);
+        }
+    }
+    tools_spec.extend(builtin_tools.iter().map(|name| builtin_tool(name)));
+
+    let mut payload = json!({
+        "model": model_name,
+        "instructions": system,
+        "input": input,
+        "store": true,
+    });
+    let object = payload.as_object_mut().unwrap();
+
+    if let Some(previous_response_id) = previous_response_id {
+        object.insert(
+            "previous_response_id".to_string(),
+            json!(previous_response_id),
+        );
+    }
+    if !tools_spec.is_empty() {
+        object.insert("tools".to_string(), json!(tools_spec));
+    }
+    match reasoning_effort {
+        Some(effort) => {
+            object.insert("reasoning".to_string(), json!({"effort": effort}));
+        }
+        // Reasoning models don't support temperature
+        None => {
+            if let Some(temp) = model_config.temperature {
+                object.insert("temperature".to_string(), json!(temp));
+            }
+        }
+    }
+    if let Some(tokens) = model_config.max_tokens {
+        object.insert("max_output_tokens".to_string(), json!(tokens));
+    }
+    Ok(payload)
+}
+
+/// Convert a Responses API response to a message. Output from built-in tools such as web
+/// search is already reflected in the output text and is not returned separately.
+pub fn response_to_message(response: &Value) -> anyhow::Result<Message> {
+    let mut content = Vec::new();
+    let output = response["output"].as_array().cloned().unwrap_or_default();
+    for item in &output {
+        match item["type"].as_str() {
+            Some("message") => {
+                for part in item["content"].as_array().into_iter().flatten() {
+                    match part["type"].as_str() {
+                        Some("output_text") => {
+                            content.push(MessageContent::text(
+                                part["text"].as_str().unwrap_or_default(),
+                            ));
+                        }
+                        Some("refusal") => {
+                            content.push(MessageContent::text(
+                                part["refusal"].as_str().unwrap_or_default(),
+                            ));
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Some("function_call") => {
+                let id = item["call_id"].as_str().unwrap_or_default().to_string();
+                let name = item["name"].as_str().unwrap_or_default();
+                let arguments = match item["arguments"].as_str().unwrap_or_default() {
+                    "" => "{}",
+                    arguments => arguments,
+                };
+
+                let tool_call = if !is_valid_function_name(name) {
+                    Err(ToolError::NotFound(format!(
+                        "The provided function name '{}' had invalid characters, it must match this regex [a-zA-Z0-9_-]+",
+                        name
+                    )))
+                } else {
+                    serde_json::from_str::<Value>(arguments)
+                        .map(|params| ToolCall::new(name, params))
+                        .map_err(|e| {
+                            ToolError::InvalidParameters(format!(
+                                "Could not interpret tool use parameters for id {}: {}",
+                                id, e
+                            ))
+                        })
+                };
+                content.push(MessageContent::tool_request(id, tool_call));
+            }
+            _ => {}
+        }
+    }
+
+    let has_tool_calls = content
+        .iter()
+        .any(|c| matches!(c, MessageContent::ToolRequest(_)));
+    let finish_reason = match response["status"].as_str() {
+        Some("incomplete") => match response["incomplete_details"]["reason"].as_str() {
+            Some("max_output_tokens") => Some(FinishReason::Length),
+            Some("content_filter") => Some(FinishReason::ContentFilter),
+            _ => None,
+        },
+        Some("completed") if has_tool_calls => Some(FinishReason::ToolCalls),
+        Some("completed") => Some(FinishReason::Stop),
+        _ => None,
+    };
+
+    Ok(Message {
+        role: Role::Assistant,
+        created: chrono::Utc::now().timestamp(),
+        content,
+        finish_reason,
+    })
+}
+
+pub fn get_usage(data: &Value) -> Result<Usage, ProviderError> {
+    let usage = data
+        .get("usage")
+        .ok_or_else(|| ProviderError::UsageError("No usage data in response".to_string()))?;
+    let tokens = |key: &str| usage.get(key).and_then(Value::as_i64).map(|v| v as i32);
+
+    let input_tokens = tokens("input_tokens");
+    let output_tokens = tokens("output_tokens");
+    let total_tokens = tokens("total_tokens").or_else(|| match (input_tokens, output_tokens) {
+        (Some(input), Some(output)) => Some(input + output),
+        _ => None,
+    });
+    Ok(Usage::new(input_tokens, output_tokens, total_tokens))
+}
+
+/// Remembers which stored response each conversation ended with, so the next request can
+/// continue from it with `previous_response_id` instead of resending the whole conversation.
+///
+/// Conversations are identified by a hash of their input items, which makes this safe to
+/// share between sessions: a request only continues a response whose conversation is an
+/// exact prefix of its own.
+#[derive(Debug, Default)]
+pub struct ResponseChain {
+    inner: Mutex<ChainInner>,
+}
+
+#[derive(Debug, Default)]
+struct ChainInner {
+    responses: HashMap<String, String>,
+    order: VecDeque<String>,
+}
+
+impl ResponseChain {
+    /// Hashes of every prefix of the conversation; `hashes[i]` covers `messages[..=i]`
+    pub fn prefix_hashes(model: &str, system: &str, messages: &[Message]) -> Vec<String> {
+        let mut hasher = blake3::Hasher::new();
+        for part in [model, system] {
+            hasher.update(part.as_bytes());
+            hasher.update(&[0]);
+        }
+        messages
+            .iter()
+            .map(|message| {
+                hasher.update(json!(format_message(message)).to_string().as_bytes());
+                hasher.update(&[0]);
+                hasher.finalize().to_hex().to_string()
+            })
+            .collect()
+    }
+
+    /// The stored response to continue from and how many messages it already covers
+    pub fn find(&self, prefix_hashes: &[String]) -> Option<(String, usize)> {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        prefix_hashes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(i, hash)| inner.responses.get(hash).map(|id| (id.clone(), i + 1)))
+    }
+
+    /// Record that the conversation with this hash ended with `response_id`
+    pub fn record(&self, conversation_hash: String, response_id: String) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if inner
+            .responses
+            .insert(conversation_hash.clone(), response_id)
+            .is_none()
+        {
+            inner.order.push_back(conversation_hash);
+        }
+        while inner.order.len() > MAX_CHAINED_RESPONSES {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.responses.remove(&oldest);
+            }
+        }
+    }
+
+    /// Forget a response, e.g. after the server no longer has it
+    pub fn forget(&self, response_id: &str) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.responses.retain(|_, id| id != response_id);
+        let ChainInner { responses, order } = &mut *inner;
+        order.retain(|hash| responses.contains_key(hash));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_response_to_message_with_text_and_function_call() {
+        let response = json!({
+            "id": "resp_1",
+            "status": "completed",
+            "output": [
+                {"type": "web_search_call", "id": "ws_1", "status": "completed"},
+                {
+                    "type": "message",
+                    "role": "assistant",
+                    "content": [{"type": "output_text", "text": "Checking the weather"}]
+                },
+                {
+                    "type": "function_call",
+                    "call_id": "call_1",
+                    "name": "get_weather",
+                    "arguments": "{\"city\":\"Paris\"}"
+                }
+            ],
+            "usage": {"input_tokens": 10, "output_tokens": 5, "total_tokens": 15}
+        });
+
+        let message = response_to_message(&response).unwrap();
+        assert_eq!(message.content.len(), 2);
+        assert_eq!(message.content[0].as_text(), Some("Checking the weather"));
+        let request = message.content[1].as_tool_request().unwrap();
+        assert_eq!(request.id, "call_1");
+        let tool_call = request.tool_call.as_ref().unwrap();
+        assert_eq!(tool_call.name, "get_weather");
+        assert_eq!(tool_call.arguments, json!({"city": "Paris"}));
+        assert_eq!(message.finish_reason, Some(FinishReason::ToolCalls));
+
+        let usage = get_usage(&response).unwrap();
+        assert_eq!(usage.total_tokens, Some(15));
+    }
+
+    #[test]
+    fn test_format_message_pairs_function_calls_with_outputs() {
+        let messages = [
+            Message::assistant().with_tool_request(
+                "call_1",
+                Ok(ToolCall::new("get_weather", json!({"city": "Paris"}))),
+            ),
+            Message::user().with_tool_response("call_1", Ok(vec![Content::text("Sunny")])),
+        ];
+
+        let items: Vec<Value> = messages.iter().flat_map(format_message).collect();
+        assert_eq!(
+            items,
+            vec![
+                json!({
+                    "type": "function_call",
+                    "call_id": "call_1",
+                    "name": "get_weather",
+                    "arguments": "{\"city\":\"Paris\"}"
+                }),
+                json!({
+                    "type": "function_call_output",
+                    "call_id": "call_1",
+                    "output": "Sunny"
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_response_chain_continues_from_longest_prefix() {
+        let chain = ResponseChain::default();
+        let first = vec![Message::user().with_text("Hello")];
+        let hashes = ResponseChain::prefix_hashes("gpt-4o", "system", &first);
+        assert_eq!(chain.find(&hashes), None);
+
+        let mut conversation = first.clone();
+        conversation.push(Message::assistant().with_text("Hi!"));
+        let hashes = ResponseChain::prefix_hashes("gpt-4o", "system", &conversation);
+        chain.record(hashes.last().unwrap().clone(), "resp_1".to_string());
+
+        conversation.push(Message::user().with_text("How are you?"));
+        let hashes = ResponseChain::prefix_hashes("gpt-4o", "system", &conversation);
+        assert_eq!(chain.find(&hashes), Some(("resp_1".to_string(), 2)));
+
+        // A different system prompt is a different conversation
+        let other = ResponseChain::prefix_hashes("gpt-4o", "other", &conversation);
+        assert_eq!(chain.find(&other), None);
+
+        chain.forget("resp_1");
+        assert_eq!(chain.find(&hashes), None);
+    }
+}
@@ -63,6 +63,9 @@ pub fn format_messages(messages: &[Message]) -> Vec<Value> {
                 MessageContent::SummarizationRequested(_) => {
                     // Skip
                 }
+                MessageContent::Assumption(_) | MessageContent::Question(_) => {
+                    // Flattened to text before reaching the provider
+                }
                 MessageContent::Thinking(_thinking) => {
                     // Skip thinking for now
                 }
@@ -7,6 +7,8 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{env, fmt, io};
 use tokio::sync::RwLock;
 
+use super::egress::EgressExt;
+
 /// Represents errors that can occur during GCP authentication.
 ///
 /// This enum encompasses various error conditions that might arise during
@@ -243,6 +245,7 @@ impl AdcCredentials {
         let client = reqwest::Client::new();
         let metadata_path = "/computeMetadata/v1/instance/service-accounts/default/token";
 
+        // The metadata server is local to the instance, so this one never goes through a gateway
         let response = client
             .get(format!("{}{}", base_url, metadata_path))
             .header("Metadata-Flavor", "Google")
@@ -471,7 +474,7 @@ impl GcpAuth {
             .client
             .post(token_uri)
             .form(params)
-            .send()
+            .send_with_egress()
             .await
             .map_err(|e| AuthError::TokenExchange(e.to_string()))?;
 
@@ -11,11 +11,13 @@ use crate::message::Message;
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
 
+use crate::providers::egress::EgressExt;
 use crate::providers::errors::ProviderError;
 use crate::providers::formats::gcpvertexai::{
     create_request, get_usage, response_to_message, ClaudeVersion, GcpVertexAIModel, GeminiVersion,
     ModelProvider, RequestContext,
 };
+use crate::providers::middleware::RequestHeadersExt;
 
 use crate::providers::formats::gcpvertexai::GcpLocation::Iowa;
 use crate::providers::gcpauth::GcpAuth;
@@ -312,7 +314,8 @@ impl GcpVertexAIProvider {
                 .post(url.clone())
                 .json(payload)
                 .header("Authorization", auth_header)
-                .send()
+                .with_request_headers()
+                .send_with_egress()
                 .await
                 .map_err(|e| ProviderError::RequestFailed(e.to_string()))?;
 
@@ -5,7 +5,7 @@ use std::process::Stdio;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
 
-use super::base::{Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage, Usage};
 use super::errors::ProviderError;
 use super::utils::emit_debug_trace;
 use crate::message::{Message, MessageContent};
@@ -176,6 +176,7 @@ impl GeminiCliProvider {
                 text: response_text,
                 annotations: None,
             })],
+            finish_reason: None,
         };
 
         let usage = Usage::default(); // No usage info available for gemini CLI
@@ -221,6 +222,7 @@ impl GeminiCliProvider {
                 text: description.clone(),
                 annotations: None,
             })],
+            finish_reason: None,
         };
 
         let usage = Usage::default();
@@ -251,6 +253,16 @@ impl Provider for GeminiCliProvider {
         ModelConfig::new("gemini-1.5-pro".to_string()).with_context_limit(Some(1_000_000))
     }
 
+    /// The CLI runs its own tools and ignores goose's. Routing goose's through toolshim
+    /// instead needs a local interpreter model, so it is only reported as lacking tools when
+    /// `GEMINI_CLI_TOOLSHIM` opts into that.
+    fn capabilities(&self) -> ProviderCapabilities {
+        let toolshim = crate::config::Config::global()
+            .get_param::<bool>("GEMINI_CLI_TOOLSHIM")
+            .unwrap_or(false);
+        ProviderCapabilities::from_model_config(&self.get_model_config()).with_tools(!toolshim)
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -12,8 +12,10 @@ use std::path::PathBuf;
 use std::time::Duration;
 
 use super::base::{Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::egress::EgressExt;
 use super::errors::ProviderError;
 use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::middleware::RequestHeadersExt;
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 
 use crate::config::{Config, ConfigError};
@@ -159,8 +161,9 @@ impl GithubCopilotProvider {
             .post(url)
             .headers(self.get_github_headers())
             .header("Authorization", format!("Bearer {}", token))
+            .with_request_headers()
             .json(&payload)
-            .send()
+            .send_with_egress()
             .await?;
         if stream_only_model {
             let mut collector = OAIStreamCollector::new();
@@ -250,7 +253,7 @@ impl GithubCopilotProvider {
             .get(GITHUB_COPILOT_API_KEY_URL)
             .headers(self.get_github_headers())
             .header(http::header::AUTHORIZATION, format!("bearer {}", &token))
-            .send()
+            .send_with_egress()
             .await?
             .error_for_status()?
             .text()
@@ -296,7 +299,7 @@ impl GithubCopilotProvider {
                 client_id: GITHUB_COPILOT_CLIENT_ID.to_string(),
                 scope: "read:user".to_string(),
             })
-            .send()
+            .send_with_egress()
             .await
             .context("failed to send request to get device code")?
             .error_for_status()
@@ -332,7 +335,7 @@ impl GithubCopilotProvider {
                     device_code: device_code.to_string(),
                     grant_type: "urn:ietf:params:oauth:grant-type:device_code".to_string(),
                 })
-                .send()
+                .send_with_egress()
                 .await
                 .context("failed to make request while polling for access token")?
                 .error_for_status()
@@ -1,7 +1,11 @@
+use super::egress::EgressExt;
 use super::errors::ProviderError;
+use super::middleware::RequestHeadersExt;
 use crate::message::Message;
 use crate::model::ModelConfig;
-use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
+use crate::providers::base::{
+    ConfigKey, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+};
 use crate::providers::formats::google::{create_request, get_usage, response_to_message};
 use crate::providers::utils::{
     emit_debug_trace, handle_response_google_compat, unescape_json_values,
@@ -107,8 +111,9 @@ impl GoogleProvider {
             let response = self
                 .client
                 .post(url.clone()) // Clone the URL for each retry
+                .with_request_headers()
                 .json(&payload)
-                .send()
+                .send_with_egress()
                 .await;
 
             match response {
@@ -165,6 +170,10 @@ impl Provider for GoogleProvider {
         self.model.clone()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::from_model_config(&self.model).with_vision(true)
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -196,7 +205,7 @@ impl Provider for GoogleProvider {
     async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
         // List models via the v1beta/models endpoint
         let url = format!("{}/v1beta/models", self.host);
-        let response = self.client.get(&url).send().await?;
+        let response = self.client.get(&url).send_with_egress().await?;
         let json: serde_json::Value = response.json().await?;
         // If 'models' field missing, return None
         let arr = match json.get("models").and_then(|v| v.as_array()) {
@@ -1,4 +1,6 @@
+use super::egress::EgressExt;
 use super::errors::ProviderError;
+use super::middleware::RequestHeadersExt;
 use crate::message::Message;
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
@@ -65,8 +67,9 @@ impl GroqProvider {
             .client
             .post(url)
             .header("Authorization", format!("Bearer {}", self.api_key))
+            .with_request_headers()
             .json(&payload)
-            .send()
+            .send_with_egress()
             .await?;
 
         let status = response.status();
@@ -169,7 +172,7 @@ impl Provider for GroqProvider {
             .header("Content-Type", "application/json");
 
         // Send request
-        let response = request.send().await?;
+        let response = request.send_with_egress().await?;
         let status = response.status();
         let payload: serde_json::Value = response.json().await.map_err(|_| {
             ProviderError::RequestFailed("Response body is not valid JSON".to_string())
@@ -3,7 +3,9 @@ use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::base::{LeadWorkerProviderTrait, Provider, ProviderMetadata, ProviderUsage};
+use super::base::{
+    LeadWorkerProviderTrait, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+};
 use super::errors::ProviderError;
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
@@ -324,6 +326,13 @@ impl Provider for LeadWorkerProvider {
         self.lead_provider.get_model_config()
     }
 
+    /// Either model may serve a turn, so only what both can do is supported
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.lead_provider
+            .capabilities()
+            .intersect(&self.worker_provider.capabilities())
+    }
+
     async fn complete(
         &self,
         system: &str,
@@ -487,6 +496,7 @@ mod tests {
                         text: format!("Response from {}", self.name),
                         annotations: None,
                     })],
+                    finish_reason: None,
                 },
                 ProviderUsage::new(self.name.clone(), Usage::default()),
             ))
@@ -650,6 +660,7 @@ mod tests {
                             text: format!("Response from {}", self.name),
                             annotations: None,
                         })],
+                        finish_reason: None,
                     },
                     ProviderUsage::new(self.name.clone(), Usage::default()),
                 ))
@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use mcp_core::tool::Tool;
+use once_cell::sync::Lazy;
+
+use super::base::{
+    LeadWorkerProviderTrait, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+};
+use super::errors::ProviderError;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// A completion request as seen by middleware before it reaches the provider
+#[derive(Debug, Clone)]
+pub struct ProviderRequest {
+    /// Name the provider was created with in the factory, e.g. "openai"
+    pub provider: String,
+    pub model: String,
+    pub system: String,
+    pub messages: Vec<Message>,
+    pub tools: Vec<Tool>,
+    /// Extra HTTP headers sent with the request by providers that call an HTTP API
+    pub headers: HashMap<String, String>,
+}
+
+/// Hooks run around every completion of a provider created by the factory.
+///
+/// Middleware can rewrite the request (e.g. scrub payloads or add headers), inspect or
+/// rewrite the response, and observe errors. Hooks run in registration order on the way
+/// in and in reverse order on the way out.
+#[async_trait]
+pub trait ProviderMiddleware: Send + Sync {
+    async fn on_request(&self, _request: &mut ProviderRequest) -> Result<(), ProviderError> {
+        Ok(())
+    }
+
+    async fn on_response(
+        &self,
+        _request: &ProviderRequest,
+        _message: &mut Message,
+        _usage: &mut ProviderUsage,
+    ) -> Result<(), ProviderError> {
+        Ok(())
+    }
+
+    async fn on_error(&self, _request: &ProviderRequest, _error: &ProviderError) {}
+}
+
+static MIDDLEWARE: Lazy<RwLock<Vec<Arc<dyn ProviderMiddleware>>>> =
+    Lazy::new(|| RwLock::new(Vec::new()));
+
+/// Add middleware to every provider the factory creates from now on. Providers that
+/// are already pooled keep the middleware they were created with.
+pub fn register_provider_middleware(middleware: Arc<dyn ProviderMiddleware>) {
+    MIDDLEWARE
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(middleware);
+}
+
+/// Remove all registered middleware
+pub fn clear_provider_middleware() {
+    MIDDLEWARE
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .clear();
+}
+
+/// Wrap a provider with the registered middleware, if there is any
+pub(crate) fn apply_registered(name: &str, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+    let middleware = MIDDLEWARE.read().unwrap_or_else(|e| e.into_inner()).clone();
+    if middleware.is_empty() {
+        return provider;
+    }
+    Arc::new(MiddlewareProvider::new(name, provider, middleware))
+}
+
+tokio::task_local! {
+    static REQUEST_HEADERS: HashMap<String, String>;
+}
+
+/// Adds the headers middleware attached to the current request
+pub trait RequestHeadersExt {
+    fn with_request_headers(self) -> Self;
+}
+
+impl RequestHeadersExt for reqwest::RequestBuilder {
+    fn with_request_headers(self) -> Self {
+        let headers = REQUEST_HEADERS
+            .try_with(|headers| headers.clone())
+            .unwrap_or_default();
+        headers
+            .iter()
+            .fold(self, |builder, (name, value)| builder.header(name, value))
+    }
+}
+
+/// A provider with middleware stacked around its completions
+pub struct MiddlewareProvider {
+    name: String,
+    inner: Arc<dyn Provider>,
+    middleware: Vec<Arc<dyn ProviderMiddleware>>,
+}
+
+impl MiddlewareProvider {
+    pub fn new(
+        name: impl Into<String>,
+        inner: Arc<dyn Provider>,
+        middleware: Vec<Arc<dyn ProviderMiddleware>>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            inner,
+            middleware,
+        }
+    }
+
+    async fn run(
+        &self,
+        request: &ProviderRequest,
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let complete = self
+            .inner
+            .complete(&request.system, &request.messages, &request.tools);
+        if request.headers.is_empty() {
+            complete.await
+        } else {
+            REQUEST_HEADERS
+                .scope(request.headers.clone(), complete)
+                .await
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for MiddlewareProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "middleware",
+            "Middleware Provider",
+            "A provider with request and response middleware",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(Self::new(
+            self.name.clone(),
+            self.inner.with_model(model)?,
+            self.middleware.clone(),
+        )))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let mut request = ProviderRequest {
+            provider: self.name.clone(),
+            model: self.inner.get_active_model_name(),
+            system: system.to_string(),
+            messages: messages.to_vec(),
+            tools: tools.to_vec(),
+            headers: HashMap::new(),
+        };
+
+        let result = async {
+            for middleware in &self.middleware {
+                middleware.on_request(&mut request).await?;
+            }
+            let (mut message, mut usage) = self.run(&request).await?;
+            for middleware in self.middleware.iter().rev() {
+                middleware
+                    .on_response(&request, &mut message, &mut usage)
+                    .await?;
+            }
+            Ok((message, usage))
+        }
+        .await;
+
+        if let Err(error) = &result {
+            for middleware in self.middleware.iter().rev() {
+                middleware.on_error(&request, error).await;
+            }
+        }
+        result
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn get_active_model_name(&self) -> String {
+        self.inner.get_active_model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+    use std::sync::Mutex;
+
+    struct Recorder {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ProviderMiddleware for Recorder {
+        async fn on_request(&self, request: &mut ProviderRequest) -> Result<(), ProviderError> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} request", self.name));
+            request.system = format!("{} [{}]", request.system, self.name);
+            request
+                .headers
+                .insert("x-middleware".to_string(), self.name.to_string());
+            Ok(())
+        }
+
+        async fn on_response(
+            &self,
+            _request: &ProviderRequest,
+            _message: &mut Message,
+            _usage: &mut ProviderUsage,
+        ) -> Result<(), ProviderError> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} response", self.name));
+            Ok(())
+        }
+
+        async fn on_error(&self, _request: &ProviderRequest, _error: &ProviderError) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} error", self.name));
+        }
+    }
+
+    fn stack(calls: &Arc<Mutex<Vec<String>>>) -> Vec<Arc<dyn ProviderMiddleware>> {
+        ["outer", "inner"]
+            .into_iter()
+            .map(|name| {
+                Arc::new(Recorder {
+                    name,
+                    calls: Arc::clone(calls),
+                }) as Arc<dyn ProviderMiddleware>
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_middleware_runs_in_order_around_completion() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mock = Arc::new(MockProvider::default().with_text("hello"));
+        let provider = MiddlewareProvider::new("mock", mock.clone(), stack(&calls));
+
+        let (message, _) = provider.complete("system", &[], &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "hello");
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "outer request",
+                "inner request",
+                "inner response",
+                "outer response"
+            ]
+        );
+        assert_eq!(mock.requests()[0].system, "system [outer] [inner]");
+    }
+
+    #[tokio::test]
+    async fn test_middleware_observes_errors() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        // An unscripted mock provider fails every request
+        let provider =
+            MiddlewareProvider::new("mock", Arc::new(MockProvider::default()), stack(&calls));
+
+        assert!(provider.complete("system", &[], &[]).await.is_err());
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                "outer request",
+                "inner request",
+                "inner error",
+                "outer error"
+            ]
+        );
+    }
+}
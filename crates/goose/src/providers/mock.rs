@@ -0,0 +1,381 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use mcp_core::tool::{Tool, ToolCall};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::errors::ProviderError;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+pub const MOCK_DEFAULT_MODEL: &str = "mock";
+
+/// A tool call the mock provider should ask for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockToolCall {
+    /// Request id, generated when omitted
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+/// Error kinds the mock provider can simulate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MockErrorKind {
+    Authentication,
+    ContextLengthExceeded,
+    ContentFiltered,
+    RateLimitExceeded,
+    ServerError,
+    RequestFailed,
+}
+
+/// A simulated provider failure
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MockError {
+    pub kind: MockErrorKind,
+    #[serde(default)]
+    pub message: String,
+}
+
+impl MockError {
+    fn to_provider_error(&self) -> ProviderError {
+        let message = self.message.clone();
+        match self.kind {
+            MockErrorKind::Authentication => ProviderError::Authentication(message),
+            MockErrorKind::ContextLengthExceeded => ProviderError::ContextLengthExceeded(message),
+            MockErrorKind::ContentFiltered => ProviderError::ContentFiltered(message),
+            MockErrorKind::RateLimitExceeded => ProviderError::RateLimitExceeded(message),
+            MockErrorKind::ServerError => ProviderError::ServerError(message),
+            MockErrorKind::RequestFailed => ProviderError::RequestFailed(message),
+        }
+    }
+}
+
+/// One canned reply. An `error` takes precedence over `text` and `tool_calls`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockResponse {
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub tool_calls: Vec<MockToolCall>,
+    #[serde(default)]
+    pub input_tokens: Option<i32>,
+    #[serde(default)]
+    pub output_tokens: Option<i32>,
+    #[serde(default)]
+    pub error: Option<MockError>,
+}
+
+impl MockResponse {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Default::default()
+        }
+    }
+
+    pub fn tool_call(name: impl Into<String>, arguments: Value) -> Self {
+        Self::default().with_tool_call(name, arguments)
+    }
+
+    pub fn error(kind: MockErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            error: Some(MockError {
+                kind,
+                message: message.into(),
+            }),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_tool_call(mut self, name: impl Into<String>, arguments: Value) -> Self {
+        self.tool_calls.push(MockToolCall {
+            id: None,
+            name: name.into(),
+            arguments,
+        });
+        self
+    }
+
+    pub fn with_usage(mut self, input_tokens: i32, output_tokens: i32) -> Self {
+        self.input_tokens = Some(input_tokens);
+        self.output_tokens = Some(output_tokens);
+        self
+    }
+}
+
+/// A script of canned replies, loaded from YAML or JSON
+///
+/// ```yaml
+/// responses:
+///   - text: "Let me look at the files"
+///     tool_calls:
+///       - name: developer__shell
+///         arguments: { command: "ls" }
+///   - text: "There are three files"
+/// repeat_last: false
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MockScript {
+    pub responses: Vec<MockResponse>,
+    /// Keep replaying the last response once the script runs out instead of failing
+    #[serde(default)]
+    pub repeat_last: bool,
+}
+
+impl MockScript {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let contents = std::fs::read_to_string(path.as_ref())?;
+        Ok(serde_yaml::from_str(&contents)?)
+    }
+}
+
+/// A request received by the mock provider, kept for assertions
+#[derive(Debug, Clone)]
+pub struct MockRequest {
+    pub system: String,
+    pub messages: Vec<Message>,
+    pub tools: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    responses: VecDeque<MockResponse>,
+    last: Option<MockResponse>,
+    requests: Vec<MockRequest>,
+    next_call_id: usize,
+}
+
+/// A provider that replays scripted responses, for tests that should not need API keys.
+///
+/// Select it with `GOOSE_PROVIDER=mock` and point `MOCK_SCRIPT` at a script file, or
+/// build one in code with [`MockProvider::new`] and the `with_*` methods.
+#[derive(Debug)]
+pub struct MockProvider {
+    model: ModelConfig,
+    repeat_last: bool,
+    state: Mutex<MockState>,
+}
+
+impl Default for MockProvider {
+    fn default() -> Self {
+        Self::new(ModelConfig::new(MOCK_DEFAULT_MODEL.to_string()))
+    }
+}
+
+impl MockProvider {
+    pub fn new(model: ModelConfig) -> Self {
+        Self {
+            model,
+            repeat_last: false,
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    pub fn from_script(model: ModelConfig, script: MockScript) -> Self {
+        let provider = Self::new(model).with_repeat_last(script.repeat_last);
+        script
+            .responses
+            .into_iter()
+            .fold(provider, MockProvider::with_response)
+    }
+
+    pub fn from_env(model: ModelConfig) -> Result<Self> {
+        let config = crate::config::Config::global();
+        match config.get_param::<String>("MOCK_SCRIPT") {
+            Ok(path) => Ok(Self::from_script(model, MockScript::from_file(path)?)),
+            Err(_) => Ok(Self::new(model)),
+        }
+    }
+
+    pub fn with_response(self, response: MockResponse) -> Self {
+        self.lock().responses.push_back(response);
+        self
+    }
+
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        self.with_response(MockResponse::text(text))
+    }
+
+    pub fn with_tool_call(self, name: impl Into<String>, arguments: Value) -> Self {
+        self.with_response(MockResponse::tool_call(name, arguments))
+    }
+
+    pub fn with_error(self, kind: MockErrorKind, message: impl Into<String>) -> Self {
+        self.with_response(MockResponse::error(kind, message))
+    }
+
+    pub fn with_repeat_last(mut self, repeat_last: bool) -> Self {
+        self.repeat_last = repeat_last;
+        self
+    }
+
+    /// Queue another response after construction, e.g. between agent turns
+    pub fn push_response(&self, response: MockResponse) {
+        self.lock().responses.push_back(response);
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.lock().requests.clone()
+    }
+
+    /// Number of scripted responses not yet served
+    pub fn remaining(&self) -> usize {
+        self.lock().responses.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn to_message(&self, response: &MockResponse, state: &mut MockState) -> Message {
+        let mut message = Message::assistant();
+        if let Some(text) = &response.text {
+            message = message.with_text(text);
+        }
+        for tool_call in &response.tool_calls {
+            let id = tool_call.id.clone().unwrap_or_else(|| {
+                state.next_call_id += 1;
+                format!("mock_call_{}", state.next_call_id)
+            });
+            message = message.with_tool_request(
+                id,
+                Ok(ToolCall::new(&tool_call.name, tool_call.arguments.clone())),
+            );
+        }
+        message
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    fn metadata() -> ProviderMetadata {
+        ProviderMetadata::new(
+            "mock",
+            "Mock",
+            "Replays scripted responses for tests",
+            MOCK_DEFAULT_MODEL,
+            vec![MOCK_DEFAULT_MODEL],
+            "",
+            vec![ConfigKey::new("MOCK_SCRIPT", false, false, None)],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.model.clone()
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let mut state = self.lock();
+        state.requests.push(MockRequest {
+            system: system.to_string(),
+            messages: messages.to_vec(),
+            tools: tools.iter().map(|tool| tool.name.clone()).collect(),
+        });
+
+        let response = match state.responses.pop_front() {
+            Some(response) => response,
+            None => match (&state.last, self.repeat_last) {
+                (Some(last), true) => last.clone(),
+                _ => {
+                    return Err(ProviderError::ExecutionError(
+                        "Mock provider has no scripted responses left".to_string(),
+                    ))
+                }
+            },
+        };
+        state.last = Some(response.clone());
+
+        if let Some(error) = &response.error {
+            return Err(error.to_provider_error());
+        }
+
+        let message = self.to_message(&response, &mut state);
+        let usage = Usage::new(
+            response.input_tokens,
+            response.output_tokens,
+            match (response.input_tokens, response.output_tokens) {
+                (None, None) => None,
+                (input, output) => Some(input.unwrap_or(0) + output.unwrap_or(0)),
+            },
+        );
+        Ok((
+            message,
+            ProviderUsage::new(self.model.model_name.clone(), usage),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_replays_responses_in_order() {
+        let provider = MockProvider::default()
+            .with_tool_call("developer__shell", json!({"command": "ls"}))
+            .with_text("done");
+
+        let (first, _) = provider.complete("system", &[], &[]).await.unwrap();
+        assert!(first.is_tool_call());
+        assert!(first.get_tool_ids().contains("mock_call_1"));
+
+        let (second, _) = provider
+            .complete("system", &[Message::user().with_text("hi")], &[])
+            .await
+            .unwrap();
+        assert_eq!(second.as_concat_text(), "done");
+
+        assert!(matches!(
+            provider.complete("system", &[], &[]).await,
+            Err(ProviderError::ExecutionError(_))
+        ));
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(requests[1].messages.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_script_errors_and_repeat_last() {
+        let script: MockScript = serde_yaml::from_str(
+            r#"
+responses:
+  - error:
+      kind: context_length_exceeded
+      message: too long
+  - text: "ok"
+    input_tokens: 10
+    output_tokens: 2
+repeat_last: true
+"#,
+        )
+        .unwrap();
+        let provider = MockProvider::from_script(ModelConfig::new("mock".to_string()), script);
+
+        assert!(matches!(
+            provider.complete("", &[], &[]).await,
+            Err(ProviderError::ContextLengthExceeded(_))
+        ));
+        for _ in 0..2 {
+            let (message, usage) = provider.complete("", &[], &[]).await.unwrap();
+            assert_eq!(message.as_concat_text(), "ok");
+            assert_eq!(usage.usage.total_tokens, Some(12));
+        }
+    }
+}
@@ -4,10 +4,13 @@ pub mod azureauth;
 pub mod base;
 pub mod bedrock;
 pub mod claude_code;
+pub mod credentials;
 pub mod databricks;
+pub mod egress;
 pub mod embedding;
 pub mod errors;
 mod factory;
+pub mod fallback;
 pub mod formats;
 mod gcpauth;
 pub mod gcpvertexai;
@@ -16,17 +19,25 @@ pub mod githubcopilot;
 pub mod google;
 pub mod groq;
 pub mod lead_worker;
+pub mod middleware;
+pub mod mock;
 pub mod oauth;
 pub mod ollama;
 pub mod openai;
 pub mod openrouter;
+pub mod pool;
+pub mod pool_stats;
 pub mod pricing;
+pub mod rate_limit;
+pub mod regions;
 pub mod sagemaker_tgi;
 pub mod snowflake;
 pub mod toolshim;
+pub mod usage_telemetry;
+pub mod usage_tracker;
 pub mod utils;
 pub mod utils_universal_openai_stream;
 pub mod venice;
 pub mod xai;
 
-pub use factory::{create, providers};
+pub use factory::{create, create_exact, providers, switch_model, validate_model};
@@ -11,6 +11,8 @@ use std::{collections::HashMap, fs, net::SocketAddr, path::PathBuf, sync::Arc};
 use tokio::sync::{oneshot, Mutex as TokioMutex};
 use url::Url;
 
+use super::egress::EgressExt;
+
 static OAUTH_MUTEX: Lazy<TokioMutex<()>> = Lazy::new(|| TokioMutex::new(()));
 
 #[derive(Debug, Clone)]
@@ -99,7 +101,7 @@ async fn get_workspace_endpoints(host: &str) -> Result<OidcEndpoints> {
         .expect("Invalid OIDC URL");
 
     let client = reqwest::Client::new();
-    let resp = client.get(oidc_url.clone()).send().await?;
+    let resp = client.get(oidc_url.clone()).send_with_egress().await?;
 
     if !resp.status().is_success() {
         return Err(anyhow::anyhow!(
@@ -247,7 +249,7 @@ impl OAuthFlow {
             .post(&self.endpoints.token_endpoint)
             .header("Content-Type", "application/x-www-form-urlencoded")
             .form(&params)
-            .send()
+            .send_with_egress()
             .await?;
 
         if !resp.status().is_success() {
@@ -276,7 +278,7 @@ impl OAuthFlow {
             .post(&self.endpoints.token_endpoint)
             .header("Content-Type", "application/x-www-form-urlencoded")
             .form(&params)
-            .send()
+            .send_with_egress()
             .await?;
 
         if !resp.status().is_success() {
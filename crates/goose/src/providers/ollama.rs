@@ -1,5 +1,7 @@
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::egress::EgressExt;
 use super::errors::ProviderError;
+use super::middleware::RequestHeadersExt;
 use super::utils::{get_model, handle_response_openai_compat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -88,7 +90,13 @@ impl OllamaProvider {
             ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
         })?;
 
-        let response = self.client.post(url).json(&payload).send().await?;
+        let response = self
+            .client
+            .post(url)
+            .with_request_headers()
+            .json(&payload)
+            .send_with_egress()
+            .await?;
 
         handle_response_openai_compat(response).await
     }
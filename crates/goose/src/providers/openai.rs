@@ -3,12 +3,21 @@ use async_trait::async_trait;
 use reqwest::Client;
 use serde_json::Value;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
-use super::base::{ConfigKey, ModelInfo, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{
+    ConfigKey, ModelInfo, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+    RetryPolicy, Usage,
+};
+use super::egress::EgressExt;
 use super::embedding::{EmbeddingCapable, EmbeddingRequest, EmbeddingResponse};
 use super::errors::ProviderError;
-use super::formats::openai::{create_request, get_usage, response_to_message};
+use super::formats::openai::{
+    apply_tool_call_options, create_request, get_usage, response_to_message, ToolCallOptions,
+};
+use super::formats::openai_responses::{self, ResponseChain};
+use super::middleware::RequestHeadersExt;
 use super::utils::{emit_debug_trace, get_model, handle_response_openai_compat, ImageFormat};
 use crate::message::Message;
 use crate::model::ModelConfig;
@@ -27,7 +36,18 @@ pub const OPEN_AI_KNOWN_MODELS: &[&str] = &[
 
 pub const OPEN_AI_DOC_URL: &str = "https://platform.openai.com/docs/models";
 
-#[derive(Debug, serde::Serialize)]
+/// Which OpenAI API requests go to, selected with `OPENAI_API_MODE`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpenAiApi {
+    /// `/v1/chat/completions`, also offered by most OpenAI compatible servers
+    #[default]
+    ChatCompletions,
+    /// `/v1/responses`, with server-side conversation state and OpenAI's built-in tools
+    Responses,
+}
+
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct OpenAiProvider {
     #[serde(skip)]
     client: Client,
@@ -38,6 +58,16 @@ pub struct OpenAiProvider {
     project: Option<String>,
     model: ModelConfig,
     custom_headers: Option<HashMap<String, String>>,
+    parallel_tool_calls: Option<bool>,
+    strict_tools: bool,
+    api: OpenAiApi,
+    responses_path: String,
+    /// Built-in tools offered with the Responses API, e.g. `web_search_preview`
+    builtin_tools: Vec<String>,
+    #[serde(skip)]
+    response_chain: Arc<ResponseChain>,
+    #[serde(skip)]
+    retry_policy: RetryPolicy,
 }
 
 impl Default for OpenAiProvider {
@@ -65,6 +95,24 @@ impl OpenAiProvider {
             .ok()
             .map(parse_custom_headers);
         let timeout_secs: u64 = config.get_param("OPENAI_TIMEOUT").unwrap_or(600);
+        let parallel_tool_calls: Option<bool> = config.get_param("OPENAI_PARALLEL_TOOL_CALLS").ok();
+        let strict_tools: bool = config.get_param("OPENAI_STRICT_TOOLS").unwrap_or(false);
+        let api: OpenAiApi = config.get_param("OPENAI_API_MODE").unwrap_or_default();
+        let responses_path: String = config
+            .get_param("OPENAI_RESPONSES_PATH")
+            .unwrap_or_else(|_| "v1/responses".to_string());
+        let builtin_tools: Vec<String> = config
+            .get_param::<String>("OPENAI_BUILTIN_TOOLS")
+            .map(|tools| {
+                tools
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tool| !tool.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let retry_policy = RetryPolicy::from_config("OPENAI", RetryPolicy::default());
         let client = Client::builder()
             .timeout(Duration::from_secs(timeout_secs))
             .build()?;
@@ -78,6 +126,13 @@ impl OpenAiProvider {
             project,
             model,
             custom_headers,
+            parallel_tool_calls,
+            strict_tools,
+            api,
+            responses_path,
+            builtin_tools,
+            response_chain: Arc::new(ResponseChain::default()),
+            retry_policy,
         })
     }
 
@@ -103,24 +158,99 @@ impl OpenAiProvider {
         request
     }
 
-    async fn post(&self, payload: Value) -> Result<Value, ProviderError> {
+    async fn post(&self, path: &str, payload: Value) -> Result<Value, ProviderError> {
         let base_url = url::Url::parse(&self.host)
             .map_err(|e| ProviderError::RequestFailed(format!("Invalid base URL: {e}")))?;
-        let url = base_url.join(&self.base_path).map_err(|e| {
+        let url = base_url.join(path).map_err(|e| {
             ProviderError::RequestFailed(format!("Failed to construct endpoint URL: {e}"))
         })?;
 
-        let request = self
-            .client
-            .post(url)
-            .header("Authorization", format!("Bearer {}", self.api_key));
+        let (url, payload) = (&url, &payload);
+        let response = self
+            .retry_policy
+            .send(|| async move {
+                let request = self
+                    .client
+                    .post(url.clone())
+                    .header("Authorization", format!("Bearer {}", self.api_key));
 
-        let request = self.add_headers(request);
+                let request = self.add_headers(request).with_request_headers();
 
-        let response = request.json(&payload).send().await?;
+                Ok::<_, ProviderError>(request.json(payload).send_with_egress().await?)
+            })
+            .await?;
 
         handle_response_openai_compat(response).await
     }
+
+    /// Complete through the Responses API, continuing from the stored response for the
+    /// conversation so far when there is one
+    async fn complete_responses(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let model_name = &self.model.model_name;
+        let hashes = ResponseChain::prefix_hashes(model_name, system, messages);
+        let previous = self.response_chain.find(&hashes);
+        let request = |previous: Option<&(String, usize)>| {
+            let (previous_response_id, covered) = match previous {
+                Some((id, covered)) => (Some(id.as_str()), *covered),
+                None => (None, 0),
+            };
+            let input = messages[covered..]
+                .iter()
+                .flat_map(openai_responses::format_message)
+                .collect();
+            openai_responses::create_request(
+                &self.model,
+                system,
+                input,
+                tools,
+                &self.builtin_tools,
+                previous_response_id,
+            )
+        };
+
+        let mut payload = request(previous.as_ref())?;
+        let response = match self.post(&self.responses_path, payload.clone()).await {
+            // Stored responses expire; start over with the whole conversation
+            Err(ProviderError::RequestFailed(e))
+                if previous.is_some() && is_missing_previous_response(&e) =>
+            {
+                let (id, _) = previous.as_ref().unwrap();
+                tracing::debug!("Stored response {} is gone, resending conversation", id);
+                self.response_chain.forget(id);
+                payload = request(None)?;
+                self.post(&self.responses_path, payload.clone()).await?
+            }
+            response => response?,
+        };
+
+        let message = openai_responses::response_to_message(&response)?;
+        if let Some(id) = response["id"].as_str() {
+            let mut conversation = messages.to_vec();
+            conversation.push(message.clone());
+            if let Some(hash) =
+                ResponseChain::prefix_hashes(model_name, system, &conversation).pop()
+            {
+                self.response_chain.record(hash, id.to_string());
+            }
+        }
+
+        let usage = match openai_responses::get_usage(&response) {
+            Ok(usage) => usage,
+            Err(ProviderError::UsageError(e)) => {
+                tracing::debug!("Failed to get usage data: {}", e);
+                Usage::default()
+            }
+            Err(e) => return Err(e),
+        };
+        let model = get_model(&response);
+        emit_debug_trace(&self.model, &payload, &response, &usage);
+        Ok((message, ProviderUsage::new(model, usage)))
+    }
 }
 
 #[async_trait]
@@ -149,6 +279,12 @@ impl Provider for OpenAiProvider {
                 ConfigKey::new("OPENAI_PROJECT", false, false, None),
                 ConfigKey::new("OPENAI_CUSTOM_HEADERS", false, true, None),
                 ConfigKey::new("OPENAI_TIMEOUT", false, false, Some("600")),
+                ConfigKey::new("OPENAI_PARALLEL_TOOL_CALLS", false, false, None),
+                ConfigKey::new("OPENAI_STRICT_TOOLS", false, false, Some("false")),
+                ConfigKey::new("OPENAI_MAX_RETRIES", false, false, Some("3")),
+                ConfigKey::new("OPENAI_API_MODE", false, false, Some("chat_completions")),
+                ConfigKey::new("OPENAI_RESPONSES_PATH", false, false, Some("v1/responses")),
+                ConfigKey::new("OPENAI_BUILTIN_TOOLS", false, false, None),
             ],
         )
     }
@@ -157,6 +293,17 @@ impl Provider for OpenAiProvider {
         self.model.clone()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::from_model_config(&self.model).with_vision(true)
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(Self {
+            model,
+            ..self.clone()
+        }))
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -167,10 +314,22 @@ impl Provider for OpenAiProvider {
         messages: &[Message],
         tools: &[Tool],
     ) -> Result<(Message, ProviderUsage), ProviderError> {
-        let payload = create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        if self.api == OpenAiApi::Responses {
+            return self.complete_responses(system, messages, tools).await;
+        }
+
+        let mut payload =
+            create_request(&self.model, system, messages, tools, &ImageFormat::OpenAi)?;
+        apply_tool_call_options(
+            &mut payload,
+            &ToolCallOptions {
+                parallel_tool_calls: self.parallel_tool_calls,
+                strict: self.strict_tools,
+            },
+        );
 
         // Make request
-        let response = self.post(payload.clone()).await?;
+        let response = self.post(&self.base_path, payload.clone()).await?;
 
         // Parse response
         let message = response_to_message(response.clone())?;
@@ -207,7 +366,7 @@ impl Provider for OpenAiProvider {
                 request = request.header(key, value);
             }
         }
-        let response = request.send().await?;
+        let response = request.send_with_egress().await?;
         let json: serde_json::Value = response.json().await?;
         if let Some(err_obj) = json.get("error") {
             let msg = err_obj
@@ -238,6 +397,12 @@ impl Provider for OpenAiProvider {
     }
 }
 
+/// Whether a failed request referenced a stored response the server no longer has
+fn is_missing_previous_response(error: &str) -> bool {
+    let error = error.to_lowercase();
+    error.contains("previous response") || error.contains("previous_response_id")
+}
+
 fn parse_custom_headers(s: String) -> HashMap<String, String> {
     s.split(',')
         .filter_map(|header| {
@@ -281,7 +446,7 @@ impl EmbeddingCapable for OpenAiProvider {
         let req = self.add_headers(req);
 
         let response = req
-            .send()
+            .send_with_egress()
             .await
             .map_err(|e| anyhow::anyhow!("Failed to send embedding request: {e}"))?;
 
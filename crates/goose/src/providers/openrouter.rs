@@ -5,7 +5,9 @@ use serde_json::{json, Value};
 use std::time::Duration;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::egress::EgressExt;
 use super::errors::ProviderError;
+use super::middleware::RequestHeadersExt;
 use super::utils::{
     emit_debug_trace, get_model, handle_response_google_compat, handle_response_openai_compat,
     is_google_model,
@@ -79,8 +81,9 @@ impl OpenRouterProvider {
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("HTTP-Referer", "https://block.github.io/goose")
             .header("X-Title", "Goose")
+            .with_request_headers()
             .json(&payload)
-            .send()
+            .send_with_egress()
             .await?;
 
         // Handle Google-compatible model responses differently
@@ -0,0 +1,2571 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use mcp_core::tool::Tool;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+
+use super::base::{
+    LeadWorkerProviderTrait, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+};
+use super::credentials::{CredentialRefreshConfig, CredentialRefresher, ManagedCredential};
+use super::errors::ProviderError;
+use super::pool_stats::{CircuitStats, KeyCounters, PoolKeyStats, PoolStats, POOL_EVENT_TARGET};
+use super::rate_limit::{RateLimitConfig, RateLimitedProvider, RateLimiter};
+use super::usage_tracker::{UsageTrackedProvider, UsageTracker};
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// Consecutive failures after which a pooled provider is replaced, unless configured
+pub const DEFAULT_MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// How long `PoolManager::acquire` waits for a free provider, unless configured
+pub const DEFAULT_ACQUIRE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How often refreshed credentials are checked for upcoming expiry
+const CREDENTIAL_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long an open circuit rejects requests, unless configured
+pub const DEFAULT_CIRCUIT_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Size limits for the providers pooled in one partition, see [`PoolKey`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolLimits {
+    /// Maximum providers handed out at once; unlimited and shared when unset
+    #[serde(default)]
+    pub max_size: Option<usize>,
+    /// Maximum unused providers kept around for reuse
+    #[serde(default)]
+    pub max_idle: Option<usize>,
+    /// How long `acquire` waits for a provider before failing with `PoolExhausted`
+    #[serde(default)]
+    pub acquire_timeout_secs: Option<u64>,
+    /// Maximum requests running at once through each provider; further requests wait
+    /// their turn in the order they arrived
+    #[serde(default)]
+    pub max_in_flight: Option<usize>,
+}
+
+impl PoolLimits {
+    pub fn acquire_timeout(&self) -> Duration {
+        self.acquire_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_ACQUIRE_TIMEOUT)
+    }
+}
+
+/// Every provider allowed for a pool partition is in use
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Provider pool for '{provider}/{model}' is exhausted: all {max_size} providers are in use (waited {waited:?})")]
+pub struct PoolExhausted {
+    pub provider: String,
+    pub model: String,
+    pub max_size: usize,
+    pub waited: Duration,
+}
+
+/// Settings shared by every provider handed out through the pool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolConfig {
+    /// Rate limit applied to providers without a specific entry in `rate_limits`
+    #[serde(default)]
+    pub default_rate_limit: RateLimitConfig,
+    /// Rate limits keyed by provider name, e.g. "openai" or "databricks"
+    #[serde(default)]
+    pub rate_limits: HashMap<String, RateLimitConfig>,
+    /// Drop pooled providers that have not been used for this many seconds
+    #[serde(default)]
+    pub idle_ttl_secs: Option<u64>,
+    /// Run health checks and idle eviction this often; disabled when unset
+    #[serde(default)]
+    pub health_check_interval_secs: Option<u64>,
+    /// Replace a pooled provider after this many consecutive failures
+    #[serde(default)]
+    pub max_consecutive_failures: Option<u32>,
+    /// Size limits applied to providers without a specific entry in `limits`
+    #[serde(default)]
+    pub default_limits: PoolLimits,
+    /// Size limits keyed by provider name, or by "provider/model" for a single model
+    #[serde(default)]
+    pub limits: HashMap<String, PoolLimits>,
+    /// Fail fast while a provider endpoint keeps failing; disabled when unset
+    #[serde(default)]
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    /// Circuit breaker settings keyed by provider name, used instead of `circuit_breaker`
+    #[serde(default)]
+    pub circuit_breakers: HashMap<String, CircuitBreakerConfig>,
+    /// Commands that issue short-lived credentials, keyed by provider name
+    #[serde(default)]
+    pub credential_refresh: HashMap<String, CredentialRefreshConfig>,
+}
+
+impl PoolConfig {
+    /// Load pool settings from the goose config.
+    ///
+    /// `GOOSE_PROVIDER_REQUESTS_PER_MINUTE` and `GOOSE_PROVIDER_TOKENS_PER_MINUTE` set the default,
+    /// `GOOSE_PROVIDER_RATE_LIMITS` holds per-provider overrides as a JSON object such as
+    /// `{"openai": {"requests_per_minute": 500, "tokens_per_minute": 200000}}`.
+    /// `GOOSE_PROVIDER_POOL_IDLE_TTL`, `GOOSE_PROVIDER_POOL_HEALTH_CHECK_INTERVAL` (both in
+    /// seconds) and `GOOSE_PROVIDER_POOL_MAX_FAILURES` control health checks and eviction.
+    /// `GOOSE_PROVIDER_POOL_MAX_SIZE`, `GOOSE_PROVIDER_POOL_MAX_IDLE` and
+    /// `GOOSE_PROVIDER_POOL_ACQUIRE_TIMEOUT` set default size limits and
+    /// `GOOSE_PROVIDER_POOL_MAX_IN_FLIGHT` caps concurrent requests per provider, with per-provider
+    /// overrides in `GOOSE_PROVIDER_POOL_LIMITS` such as `{"databricks": {"max_size": 4}}` or
+    /// `{"openai/gpt-4o": {"max_size": 8}}`.
+    /// `GOOSE_PROVIDER_CIRCUIT_BREAKER_THRESHOLD` enables the circuit breaker, which stays open
+    /// for `GOOSE_PROVIDER_CIRCUIT_BREAKER_COOLDOWN` seconds, with per-provider settings in
+    /// `GOOSE_PROVIDER_CIRCUIT_BREAKERS` such as `{"openai": {"failure_threshold": 3}}`.
+    /// `GOOSE_CREDENTIAL_REFRESH` maps provider names to a [`CredentialRefreshConfig`].
+    pub fn from_config() -> Self {
+        let config = crate::config::Config::global();
+        let default_rate_limit = RateLimitConfig {
+            requests_per_minute: config.get_param("GOOSE_PROVIDER_REQUESTS_PER_MINUTE").ok(),
+            tokens_per_minute: config.get_param("GOOSE_PROVIDER_TOKENS_PER_MINUTE").ok(),
+        };
+        let rate_limits = config
+            .get_param::<HashMap<String, RateLimitConfig>>("GOOSE_PROVIDER_RATE_LIMITS")
+            .unwrap_or_default();
+
+        Self {
+            default_rate_limit,
+            rate_limits,
+            idle_ttl_secs: config.get_param("GOOSE_PROVIDER_POOL_IDLE_TTL").ok(),
+            health_check_interval_secs: config
+                .get_param("GOOSE_PROVIDER_POOL_HEALTH_CHECK_INTERVAL")
+                .ok(),
+            max_consecutive_failures: config.get_param("GOOSE_PROVIDER_POOL_MAX_FAILURES").ok(),
+            default_limits: PoolLimits {
+                max_size: config.get_param("GOOSE_PROVIDER_POOL_MAX_SIZE").ok(),
+                max_idle: config.get_param("GOOSE_PROVIDER_POOL_MAX_IDLE").ok(),
+                acquire_timeout_secs: config.get_param("GOOSE_PROVIDER_POOL_ACQUIRE_TIMEOUT").ok(),
+                max_in_flight: config.get_param("GOOSE_PROVIDER_POOL_MAX_IN_FLIGHT").ok(),
+            },
+            limits: config
+                .get_param::<HashMap<String, PoolLimits>>("GOOSE_PROVIDER_POOL_LIMITS")
+                .unwrap_or_default(),
+            circuit_breaker: config
+                .get_param::<u32>("GOOSE_PROVIDER_CIRCUIT_BREAKER_THRESHOLD")
+                .ok()
+                .filter(|threshold| *threshold > 0)
+                .map(|failure_threshold| CircuitBreakerConfig {
+                    failure_threshold,
+                    cooldown_secs: config
+                        .get_param("GOOSE_PROVIDER_CIRCUIT_BREAKER_COOLDOWN")
+                        .unwrap_or_else(|_| default_circuit_cooldown_secs()),
+                }),
+            circuit_breakers: config
+                .get_param::<HashMap<String, CircuitBreakerConfig>>(
+                    "GOOSE_PROVIDER_CIRCUIT_BREAKERS",
+                )
+                .unwrap_or_default(),
+            credential_refresh: config
+                .get_param::<HashMap<String, CredentialRefreshConfig>>("GOOSE_CREDENTIAL_REFRESH")
+                .unwrap_or_default(),
+        }
+    }
+
+    pub fn with_rate_limit(
+        mut self,
+        provider_name: impl Into<String>,
+        limit: RateLimitConfig,
+    ) -> Self {
+        self.rate_limits.insert(provider_name.into(), limit);
+        self
+    }
+
+    pub fn rate_limit_for(&self, provider_name: &str) -> RateLimitConfig {
+        self.rate_limits
+            .get(provider_name)
+            .copied()
+            .unwrap_or(self.default_rate_limit)
+    }
+
+    pub fn with_limits(mut self, provider_name: impl Into<String>, limits: PoolLimits) -> Self {
+        self.limits.insert(provider_name.into(), limits);
+        self
+    }
+
+    pub fn limits_for(&self, provider_name: &str) -> PoolLimits {
+        self.limits
+            .get(provider_name)
+            .copied()
+            .unwrap_or(self.default_limits)
+    }
+
+    /// Limits for one model of a provider, preferring a "provider/model" entry
+    pub fn limits_for_model(&self, provider_name: &str, model_name: &str) -> PoolLimits {
+        self.limits
+            .get(&format!("{}/{}", provider_name, model_name))
+            .copied()
+            .unwrap_or_else(|| self.limits_for(provider_name))
+    }
+
+    /// Circuit breaker settings for a provider, preferring its own entry
+    pub fn circuit_breaker_for(&self, provider_name: &str) -> Option<CircuitBreakerConfig> {
+        self.circuit_breakers
+            .get(provider_name)
+            .copied()
+            .or(self.circuit_breaker)
+            .filter(|config| config.failure_threshold > 0)
+    }
+
+    pub fn idle_ttl(&self) -> Option<Duration> {
+        self.idle_ttl_secs.map(Duration::from_secs)
+    }
+
+    pub fn health_check_interval(&self) -> Option<Duration> {
+        self.health_check_interval_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    pub fn max_consecutive_failures(&self) -> u32 {
+        self.max_consecutive_failures
+            .unwrap_or(DEFAULT_MAX_CONSECUTIVE_FAILURES)
+            .max(1)
+    }
+}
+
+/// Liveness of one pooled provider, updated by every request made through it
+#[derive(Debug)]
+pub struct ProviderHealth {
+    consecutive_failures: AtomicU32,
+    in_flight: AtomicUsize,
+    last_used: Mutex<Instant>,
+}
+
+impl Default for ProviderHealth {
+    fn default() -> Self {
+        Self {
+            consecutive_failures: AtomicU32::new(0),
+            in_flight: AtomicUsize::new(0),
+            last_used: Mutex::new(Instant::now()),
+        }
+    }
+}
+
+/// Counts a request as in flight until dropped
+struct InFlightGuard<'a>(&'a AtomicUsize);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl ProviderHealth {
+    fn start_request(&self) -> InFlightGuard<'_> {
+        self.touch();
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(&self.in_flight)
+    }
+
+    /// Requests currently running through the provider
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub fn touch(&self) {
+        *self.last_used.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    pub fn idle_for(&self) -> Duration {
+        self.last_used
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed()
+    }
+
+    pub fn record_success(&self) {
+        self.consecutive_failures.store(0, Ordering::SeqCst);
+    }
+
+    pub fn record_failure(&self) {
+        self.consecutive_failures.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::SeqCst)
+    }
+
+    /// Record the outcome of a request. Only errors that point at a broken client or
+    /// endpoint count; bad prompts and rate limits say nothing about the connection.
+    fn record<T>(&self, result: &Result<T, ProviderError>) {
+        match result {
+            Ok(_) => self.record_success(),
+            Err(ProviderError::Authentication(_)) => self.record_failure(),
+            Err(e) if is_endpoint_failure(e) => self.record_failure(),
+            Err(_) => {}
+        }
+    }
+}
+
+/// A provider that reports request outcomes to its [`ProviderHealth`], and optionally caps
+/// how many requests run through it at once
+pub struct HealthTrackedProvider {
+    inner: Arc<dyn Provider>,
+    health: Arc<ProviderHealth>,
+    /// Name the provider was created under, for the events it reports. Without one only
+    /// health is tracked, e.g. for the pool's own bookkeeping around a factory-built provider
+    /// that reports them already.
+    provider_name: String,
+    /// Tokio's semaphore is fair, so waiting requests are served first come, first served
+    in_flight_limit: Option<Arc<Semaphore>>,
+}
+
+impl HealthTrackedProvider {
+    pub fn new(inner: Arc<dyn Provider>, health: Arc<ProviderHealth>) -> Self {
+        Self {
+            inner,
+            health,
+            provider_name: String::new(),
+            in_flight_limit: None,
+        }
+    }
+
+    pub fn with_provider_name(mut self, provider_name: impl Into<String>) -> Self {
+        self.provider_name = provider_name.into();
+        self
+    }
+
+    pub fn with_max_in_flight(mut self, max_in_flight: Option<usize>) -> Self {
+        self.in_flight_limit = max_in_flight.map(|max| Arc::new(Semaphore::new(max.max(1))));
+        self
+    }
+
+    /// Wait for a free request slot, if requests are capped
+    async fn wait_for_slot(&self) -> Option<OwnedSemaphorePermit> {
+        let limit = self.in_flight_limit.as_ref()?;
+        // The semaphore is never closed, so acquiring only fails if it were
+        Arc::clone(limit).acquire_owned().await.ok()
+    }
+
+    /// Record the outcome of a request, and report the failures operators get alerted on
+    fn record<T>(&self, result: &Result<T, ProviderError>) {
+        self.health.record(result);
+        if self.provider_name.is_empty() {
+            return;
+        }
+        match result {
+            Err(ProviderError::Authentication(_)) => tracing::warn!(
+                target: POOL_EVENT_TARGET,
+                event = "auth_failure",
+                provider = self.provider_name.as_str(),
+                model = self.inner.get_model_config().model_name.as_str(),
+                consecutive_failures = self.health.consecutive_failures(),
+            ),
+            Err(ProviderError::RateLimitExceeded(message))
+                if message.to_lowercase().contains("quota") =>
+            {
+                tracing::warn!(
+                    target: POOL_EVENT_TARGET,
+                    event = "quota_exceeded",
+                    provider = self.provider_name.as_str(),
+                    model = self.inner.get_model_config().model_name.as_str(),
+                    message = message.as_str(),
+                )
+            }
+            _ => {}
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for HealthTrackedProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "health_tracked",
+            "Health Tracked Provider",
+            "A pooled provider that tracks request failures",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    /// The switched provider shares the HTTP client and endpoint, so it shares their health
+    /// and in-flight cap too
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(Self {
+            inner: self.inner.with_model(model)?,
+            health: Arc::clone(&self.health),
+            provider_name: self.provider_name.clone(),
+            in_flight_limit: self.in_flight_limit.clone(),
+        }))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let _slot = self.wait_for_slot().await;
+        let _in_flight = self.health.start_request();
+        let result = self.inner.complete(system, messages, tools).await;
+        self.record(&result);
+        result
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        let _slot = self.wait_for_slot().await;
+        let _in_flight = self.health.start_request();
+        let result = self.inner.create_embeddings(texts).await;
+        self.record(&result);
+        result
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn get_active_model_name(&self) -> String {
+        self.inner.get_active_model_name()
+    }
+}
+
+/// The slots of a partition with a `max_size`, one per provider that may be handed out.
+/// Tokio's semaphore is fair, so callers waiting in `acquire` are served in order.
+struct PartitionSlots {
+    size: usize,
+    semaphore: Arc<Semaphore>,
+    /// Slots to take out of circulation when they are released, because `size` shrank while
+    /// they were held
+    owed: Arc<AtomicUsize>,
+}
+
+impl PartitionSlots {
+    fn new(size: usize) -> Self {
+        Self {
+            size,
+            semaphore: Arc::new(Semaphore::new(size)),
+            owed: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Change the number of slots in place, so leases already out keep counting against it
+    fn resize(&mut self, size: usize) {
+        if size > self.size {
+            let added = size - self.size;
+            let repaid = self
+                .owed
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |owed| {
+                    Some(owed.saturating_sub(added))
+                })
+                .map_or(0, |owed| owed.min(added));
+            self.semaphore.add_permits(added - repaid);
+        } else {
+            let removed = self.size - size;
+            let forgotten = self.semaphore.forget_permits(removed);
+            self.owed.fetch_add(removed - forgotten, Ordering::SeqCst);
+        }
+        self.size = size;
+    }
+}
+
+/// One held slot of a partition
+struct Slot {
+    permit: Option<OwnedSemaphorePermit>,
+    owed: Arc<AtomicUsize>,
+}
+
+impl Slot {
+    fn new(permit: OwnedSemaphorePermit, owed: Arc<AtomicUsize>) -> Self {
+        Self {
+            permit: Some(permit),
+            owed,
+        }
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let shrank = self
+            .owed
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |owed| {
+                owed.checked_sub(1)
+            })
+            .is_ok();
+        // The partition shrank while this was held, so the slot goes instead of coming back
+        if shrank {
+            if let Some(permit) = self.permit.take() {
+                permit.forget();
+            }
+        }
+    }
+}
+
+/// A provider handed out from a partition with a `max_size`. It holds one of the partition's
+/// slots, which is released to the next waiting caller when the last handle is dropped.
+pub struct PooledLease {
+    inner: Arc<dyn Provider>,
+    _slot: Slot,
+}
+
+#[async_trait]
+impl Provider for PooledLease {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "pooled_lease",
+            "Pooled Lease",
+            "A pooled provider held by one caller at a time",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        self.inner.with_model(model)
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.inner.complete(system, messages, tools).await
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn get_active_model_name(&self) -> String {
+        self.inner.get_active_model_name()
+    }
+}
+
+/// Hand a pooled provider to one caller, holding its partition slot if it has one
+fn lease(provider: Arc<dyn Provider>, slot: Option<Slot>) -> Arc<dyn Provider> {
+    match slot {
+        Some(slot) => Arc::new(PooledLease {
+            inner: provider,
+            _slot: slot,
+        }),
+        None => provider,
+    }
+}
+
+/// Whether an error points at a broken client or endpoint rather than at the request
+fn is_endpoint_failure(error: &ProviderError) -> bool {
+    matches!(
+        error,
+        ProviderError::ServerError(_)
+            | ProviderError::RequestFailed(_)
+            | ProviderError::ExecutionError(_)
+    )
+}
+
+/// Settings for the circuit breaker kept per provider endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive endpoint failures that open the circuit
+    pub failure_threshold: u32,
+    /// How long an open circuit rejects requests before letting a probe through
+    #[serde(default = "default_circuit_cooldown_secs")]
+    pub cooldown_secs: u64,
+}
+
+fn default_circuit_cooldown_secs() -> u64 {
+    DEFAULT_CIRCUIT_COOLDOWN.as_secs()
+}
+
+impl CircuitBreakerConfig {
+    pub fn cooldown(&self) -> Duration {
+        Duration::from_secs(self.cooldown_secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Requests flow normally
+    Closed,
+    /// Requests fail immediately until the cool-down has passed
+    Open,
+    /// The cool-down has passed and a single probe request decides what happens next
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct CircuitInner {
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    probe_started: Option<Instant>,
+}
+
+/// Stops sending requests to a provider endpoint that keeps failing, so callers
+/// fail fast instead of each spending their full retry budget against it
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    inner: Mutex<CircuitInner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(CircuitInner {
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_started: None,
+            }),
+        }
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.inner
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .consecutive_failures
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        match inner.opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.config.cooldown() => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Check whether a request may go ahead. When the circuit is open this returns how long
+    /// until a probe is let through; once half-open only one probe runs at a time.
+    pub fn allow(&self) -> Result<(), Duration> {
+        let cooldown = self.config.cooldown();
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(opened_at) = inner.opened_at else {
+            return Ok(());
+        };
+        let open_for = opened_at.elapsed();
+        if open_for < cooldown {
+            return Err(cooldown - open_for);
+        }
+        // A probe that never reported back (e.g. it was cancelled) must not wedge the
+        // circuit, so another one is let through after a further cool-down
+        match inner.probe_started {
+            Some(started) if started.elapsed() < cooldown => Err(cooldown - started.elapsed()),
+            _ => {
+                inner.probe_started = Some(Instant::now());
+                Ok(())
+            }
+        }
+    }
+
+    /// Close the circuit. Returns true if it was open.
+    pub fn record_success(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures = 0;
+        inner.probe_started = None;
+        inner.opened_at.take().is_some()
+    }
+
+    /// Count a failure. Returns true if this opened a closed circuit.
+    pub fn record_failure(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        // A failed probe re-opens the circuit for another full cool-down
+        if inner.probe_started.is_some()
+            || inner.consecutive_failures >= self.config.failure_threshold.max(1)
+        {
+            let was_closed = inner.opened_at.is_none();
+            inner.opened_at = Some(Instant::now());
+            inner.probe_started = None;
+            return was_closed;
+        }
+        false
+    }
+}
+
+/// A provider guarded by the circuit breaker shared by every instance calling its endpoint
+pub struct CircuitBreakerProvider {
+    inner: Arc<dyn Provider>,
+    breaker: Arc<CircuitBreaker>,
+    provider_name: String,
+    /// The configured endpoint the breaker covers, empty for the provider's default
+    endpoint: String,
+}
+
+impl CircuitBreakerProvider {
+    pub fn new(
+        inner: Arc<dyn Provider>,
+        breaker: Arc<CircuitBreaker>,
+        provider_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner,
+            breaker,
+            provider_name: provider_name.into(),
+            endpoint: String::new(),
+        }
+    }
+
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    fn check(&self) -> Result<(), ProviderError> {
+        self.breaker.allow().map_err(|retry_in| {
+            tracing::debug!(
+                target: POOL_EVENT_TARGET,
+                event = "circuit_open",
+                provider = self.provider_name.as_str(),
+                endpoint = self.endpoint.as_str(),
+                retry_in_ms = retry_in.as_millis() as u64,
+            );
+            ProviderError::CircuitOpen(format!(
+                "{} has failed repeatedly, requests are paused for another {}s",
+                self.provider_name,
+                retry_in.as_secs().max(1)
+            ))
+        })
+    }
+
+    /// Record the outcome of a request, reporting when the circuit opens or closes
+    fn record<T>(&self, result: &Result<T, ProviderError>) {
+        match result {
+            Err(e) if is_endpoint_failure(e) => {
+                if self.breaker.record_failure() {
+                    tracing::warn!(
+                        target: POOL_EVENT_TARGET,
+                        event = "circuit_opened",
+                        provider = self.provider_name.as_str(),
+                        endpoint = self.endpoint.as_str(),
+                        consecutive_failures = self.breaker.consecutive_failures(),
+                        error = %e,
+                    );
+                }
+            }
+            // Anything else still proves the endpoint answered
+            _ => {
+                if self.breaker.record_success() {
+                    tracing::info!(
+                        target: POOL_EVENT_TARGET,
+                        event = "circuit_closed",
+                        provider = self.provider_name.as_str(),
+                        endpoint = self.endpoint.as_str(),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for CircuitBreakerProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "circuit_breaker",
+            "Circuit Breaker Provider",
+            "A provider that fails fast while its endpoint is down",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(
+            Self::new(
+                self.inner.with_model(model)?,
+                Arc::clone(&self.breaker),
+                self.provider_name.clone(),
+            )
+            .with_endpoint(self.endpoint.clone()),
+        ))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.check()?;
+        let result = self.inner.complete(system, messages, tools).await;
+        self.record(&result);
+        result
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.check()?;
+        let result = self.inner.create_embeddings(texts).await;
+        self.record(&result);
+        result
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn get_active_model_name(&self) -> String {
+        self.inner.get_active_model_name()
+    }
+}
+
+/// The partition a pooled provider belongs to. Each partition has its own providers and
+/// size limits, so a burst of calls for one model or endpoint can't take clients from another.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct PoolKey {
+    pub provider: String,
+    pub model: String,
+    /// The host the provider is configured to call, empty for its default endpoint
+    pub endpoint: String,
+    /// Hash of the provider's configuration when its providers were created, see
+    /// [`config_fingerprint`]. Not shown in `Display`, since it is derived from secrets.
+    #[serde(default)]
+    pub fingerprint: String,
+}
+
+impl PoolKey {
+    pub fn new(
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        endpoint: impl Into<String>,
+    ) -> Self {
+        Self {
+            provider: provider.into(),
+            model: model.into(),
+            endpoint: endpoint.into(),
+            fingerprint: String::new(),
+        }
+    }
+
+    pub fn with_fingerprint(mut self, fingerprint: impl Into<String>) -> Self {
+        self.fingerprint = fingerprint.into();
+        self
+    }
+
+    /// The partition for a provider and model, using the endpoint and configuration
+    /// currently set for the provider (e.g. `OPENAI_HOST` or `AZURE_OPENAI_ENDPOINT`)
+    pub fn for_model(provider_name: &str, model: &ModelConfig) -> Self {
+        Self::new(
+            provider_name,
+            &model.model_name,
+            configured_endpoint(provider_name),
+        )
+        .with_fingerprint(config_fingerprint(provider_name))
+    }
+
+    /// Whether both keys are for the same provider, model and endpoint, whatever the
+    /// configuration they were created with
+    fn same_partition(&self, other: &PoolKey) -> bool {
+        self.provider == other.provider
+            && self.model == other.model
+            && self.endpoint == other.endpoint
+    }
+
+    /// This key without its fingerprint, which is what stats are kept per
+    fn partition(&self) -> PoolKey {
+        PoolKey::new(&self.provider, &self.model, &self.endpoint)
+    }
+}
+
+impl std::fmt::Display for PoolKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.provider, self.model)?;
+        if !self.endpoint.is_empty() {
+            write!(f, "@{}", self.endpoint)?;
+        }
+        Ok(())
+    }
+}
+
+/// The host configured for a provider under its `<NAME>_HOST` or `<NAME>_ENDPOINT` key
+fn configured_endpoint(provider_name: &str) -> String {
+    let config = crate::config::Config::global();
+    let prefix = provider_name.to_uppercase().replace('-', "_");
+    config
+        .get_param::<String>(&format!("{}_HOST", prefix))
+        .or_else(|_| config.get_param::<String>(&format!("{}_ENDPOINT", prefix)))
+        .map(|endpoint| endpoint.trim_end_matches('/').to_string())
+        .unwrap_or_default()
+}
+
+/// Global model defaults that are baked into a provider when it is created
+const MODEL_DEFAULT_KEYS: &[&str] = &[
+    "GOOSE_TEMPERATURE",
+    "GOOSE_CONTEXT_LIMIT",
+    "GOOSE_MAX_TOKENS",
+    "GOOSE_TOOLSHIM",
+    "GOOSE_TOOLSHIM_OLLAMA_MODEL",
+];
+
+/// A hash of the configuration a provider is created from: the config keys in its metadata
+/// (API key, base URL and the like) and the global model defaults. Providers without
+/// metadata are fingerprinted by their `<NAME>_API_KEY`. When the fingerprint changes, the
+/// pool creates new providers instead of handing out ones built with the old configuration.
+pub fn config_fingerprint(provider_name: &str) -> String {
+    let config = crate::config::Config::global();
+    let keys: Vec<(String, bool)> = match super::providers()
+        .into_iter()
+        .find(|metadata| metadata.name == provider_name)
+    {
+        Some(metadata) => metadata
+            .config_keys
+            .into_iter()
+            .map(|key| (key.name, key.secret))
+            .collect(),
+        None => vec![(
+            format!("{}_API_KEY", provider_name.to_uppercase().replace('-', "_")),
+            true,
+        )],
+    };
+
+    let mut hasher = blake3::Hasher::new();
+    let model_defaults = MODEL_DEFAULT_KEYS
+        .iter()
+        .map(|key| (key.to_string(), false));
+    for (key, secret) in keys.into_iter().chain(model_defaults) {
+        let value = config
+            .get(&key, secret)
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        hasher.update(key.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(value.as_bytes());
+        hasher.update(&[0]);
+    }
+    hasher.finalize().to_hex()[..16].to_string()
+}
+
+/// A provider held by the pool along with what is needed to recreate it
+struct PoolEntry {
+    provider: Arc<dyn Provider>,
+    health: Arc<ProviderHealth>,
+    model: ModelConfig,
+}
+
+impl PoolEntry {
+    /// Callers currently holding this provider
+    fn leases(&self) -> usize {
+        Arc::strong_count(&self.provider) - 1
+    }
+
+    fn is_idle(&self) -> bool {
+        self.leases() == 0 && self.health.in_flight() == 0
+    }
+}
+
+/// A provider and model to connect to before the first request needs it
+#[derive(Debug, Clone)]
+pub struct PrewarmTarget {
+    pub provider: String,
+    pub model: ModelConfig,
+}
+
+impl PrewarmTarget {
+    pub fn new(provider: impl Into<String>, model: ModelConfig) -> Self {
+        Self {
+            provider: provider.into(),
+            model,
+        }
+    }
+
+    /// The configured `GOOSE_PROVIDER`/`GOOSE_MODEL`, plus the lead model when `GOOSE_LEAD_MODEL` is set
+    pub fn from_config() -> Vec<Self> {
+        let config = crate::config::Config::global();
+        let Ok(provider) = config.get_param::<String>("GOOSE_PROVIDER") else {
+            return Vec::new();
+        };
+
+        let mut targets = Vec::new();
+        if let Ok(model) = config.get_param::<String>("GOOSE_MODEL") {
+            targets.push(Self::new(provider.clone(), ModelConfig::new(model)));
+        }
+        if let Ok(lead_model) = config.get_param::<String>("GOOSE_LEAD_MODEL") {
+            let lead_provider = config
+                .get_param::<String>("GOOSE_LEAD_PROVIDER")
+                .unwrap_or(provider);
+            targets.push(Self::new(lead_provider, ModelConfig::new(lead_model)));
+        }
+        targets
+    }
+}
+
+/// Outcome of pre-warming one provider
+#[derive(Debug, Clone)]
+pub struct PrewarmResult {
+    pub provider: String,
+    pub model: String,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+impl PrewarmResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+
+    fn log(&self) {
+        tracing::debug!(
+            target: POOL_EVENT_TARGET,
+            event = "prewarm",
+            provider = self.provider.as_str(),
+            model = self.model.as_str(),
+            elapsed_ms = self.elapsed.as_millis() as u64,
+            ok = self.is_ok(),
+        );
+        match &self.error {
+            None => tracing::info!(
+                "Pre-warmed provider {}/{} in {:?}",
+                self.provider,
+                self.model,
+                self.elapsed
+            ),
+            Some(e) => tracing::warn!(
+                "Failed to pre-warm provider {}/{}: {}",
+                self.provider,
+                self.model,
+                e
+            ),
+        }
+    }
+}
+
+/// Shares provider instances and their client-side limits across sessions and subagents
+pub struct PoolManager {
+    config: RwLock<PoolConfig>,
+    providers: Mutex<HashMap<PoolKey, Vec<PoolEntry>>>,
+    limiters: Mutex<HashMap<String, Arc<RateLimiter>>>,
+    /// Circuit breakers keyed by provider name and configured endpoint
+    breakers: Mutex<HashMap<(String, String), Arc<CircuitBreaker>>>,
+    /// Credentials kept fresh through refreshers registered in code, by provider name
+    registered_credentials: Mutex<HashMap<String, Arc<ManagedCredential>>>,
+    /// Credentials kept fresh through `credential_refresh` commands, built on first use
+    configured_credentials: Mutex<HashMap<String, Arc<ManagedCredential>>>,
+    usage: Arc<UsageTracker>,
+    counters: Mutex<HashMap<PoolKey, KeyCounters>>,
+    /// Slots of the partitions with a `max_size`
+    slots: Mutex<HashMap<PoolKey, PartitionSlots>>,
+    /// Health of every provider created through [`PoolManager::wrap`], pooled or not, by
+    /// partition, for as long as the provider is alive
+    tracked: Mutex<HashMap<PoolKey, Vec<Weak<ProviderHealth>>>>,
+}
+
+static GLOBAL_POOL_MANAGER: Lazy<PoolManager> =
+    Lazy::new(|| PoolManager::new(PoolConfig::from_config()));
+
+/// The process-wide pool used by the provider factory
+pub fn global_pool_manager() -> &'static PoolManager {
+    &GLOBAL_POOL_MANAGER
+}
+
+/// Pick up changed provider credentials, endpoints and pool limits without a restart,
+/// e.g. after rotating an API key. Returns the number of pooled providers retired.
+pub fn reload_provider_config() -> usize {
+    global_pool_manager().reload_provider_config()
+}
+
+/// Drop the pooled providers of one provider from the global pool, see [`PoolManager::invalidate`]
+pub fn invalidate_provider(provider_name: &str) -> usize {
+    global_pool_manager().invalidate(provider_name)
+}
+
+impl PoolManager {
+    pub fn new(config: PoolConfig) -> Self {
+        Self {
+            config: RwLock::new(config),
+            providers: Mutex::new(HashMap::new()),
+            limiters: Mutex::new(HashMap::new()),
+            breakers: Mutex::new(HashMap::new()),
+            registered_credentials: Mutex::new(HashMap::new()),
+            configured_credentials: Mutex::new(HashMap::new()),
+            usage: Arc::new(UsageTracker::default()),
+            counters: Mutex::new(HashMap::new()),
+            slots: Mutex::new(HashMap::new()),
+            tracked: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Token usage and spend of every provider created through the factory, per provider,
+    /// model and session or subagent
+    pub fn usage(&self) -> &UsageTracker {
+        &self.usage
+    }
+
+    pub fn config(&self) -> PoolConfig {
+        self.config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Replace the pool configuration. Pooled providers are dropped so that new
+    /// limits apply to everything created afterwards.
+    pub fn set_config(&self, config: PoolConfig) {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config;
+        self.limiters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.breakers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.configured_credentials
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Re-read the pool configuration and provider credentials from the config system.
+    ///
+    /// Every pooled provider is retired so the next caller builds one with the current API
+    /// keys and endpoints. Retired providers are not torn down: callers holding one finish
+    /// their in-flight requests on it, and it is dropped with the last handle. Rate limiters
+    /// whose limits are unchanged keep their budget; circuit breakers start closed.
+    /// Returns the number of providers retired.
+    pub fn reload_provider_config(&self) -> usize {
+        self.reload(PoolConfig::from_config())
+    }
+
+    /// Like [`PoolManager::reload_provider_config`], with an explicit pool configuration
+    pub fn reload(&self, config: PoolConfig) -> usize {
+        *self.config.write().unwrap_or_else(|e| e.into_inner()) = config.clone();
+        self.limiters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|name, limiter| limiter.config() == config.rate_limit_for(name));
+        self.breakers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.configured_credentials
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+
+        let retired: Vec<(PoolKey, usize)> = self
+            .providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .drain()
+            .map(|(key, entries)| (key, entries.len()))
+            .collect();
+
+        let mut total = 0;
+        for (key, count) in retired {
+            for _ in 0..count {
+                self.record_evicted(&key, "reload");
+            }
+            total += count;
+        }
+        tracing::info!(
+            "Reloaded provider configuration, retired {} pooled providers",
+            total
+        );
+        total
+    }
+
+    /// The limiter shared by all instances of a provider, if any limit is configured
+    pub fn rate_limiter(&self, provider_name: &str) -> Option<Arc<RateLimiter>> {
+        let limit = self.config().rate_limit_for(provider_name);
+        if limit.is_unlimited() {
+            return None;
+        }
+
+        let mut limiters = self.limiters.lock().unwrap_or_else(|e| e.into_inner());
+        let limiter = limiters
+            .entry(provider_name.to_string())
+            .or_insert_with(|| Arc::new(RateLimiter::new(limit)));
+        Some(Arc::clone(limiter))
+    }
+
+    /// The circuit breaker shared by all instances of a provider calling its configured
+    /// endpoint, if one is configured. Every model, session and subagent on the endpoint
+    /// shares it, so an outage seen by one caller fails the rest fast.
+    pub fn circuit_breaker(&self, provider_name: &str) -> Option<Arc<CircuitBreaker>> {
+        self.circuit_breaker_at(provider_name, &configured_endpoint(provider_name))
+    }
+
+    fn circuit_breaker_at(
+        &self,
+        provider_name: &str,
+        endpoint: &str,
+    ) -> Option<Arc<CircuitBreaker>> {
+        let config = self.config().circuit_breaker_for(provider_name)?;
+        let mut breakers = self.breakers.lock().unwrap_or_else(|e| e.into_inner());
+        let breaker = breakers
+            .entry((provider_name.to_string(), endpoint.to_string()))
+            .or_insert_with(|| Arc::new(CircuitBreaker::new(config)));
+        Some(Arc::clone(breaker))
+    }
+
+    /// State of every circuit breaker in use, sorted by provider and endpoint
+    pub fn circuit_stats(&self) -> Vec<CircuitStats> {
+        let mut stats: Vec<CircuitStats> = self
+            .breakers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .map(|((provider, endpoint), breaker)| CircuitStats {
+                provider: provider.clone(),
+                endpoint: endpoint.clone(),
+                state: breaker.state(),
+                consecutive_failures: breaker.consecutive_failures(),
+            })
+            .collect();
+        stats.sort_by(|a, b| (&a.provider, &a.endpoint).cmp(&(&b.provider, &b.endpoint)));
+        stats
+    }
+
+    /// Wrap a freshly created provider with health tracking and the limits configured for its
+    /// name. Every provider the factory creates goes through here, pooled or not, so goose-cli
+    /// and goose-server sessions get the same in-flight cap, alerts and stats as pooled ones.
+    pub fn wrap(&self, provider_name: &str, provider: Arc<dyn Provider>) -> Arc<dyn Provider> {
+        let model = provider.get_model_config();
+        let health = self.track(PoolKey::new(
+            provider_name,
+            &model.model_name,
+            configured_endpoint(provider_name),
+        ));
+        let max_in_flight = self
+            .config()
+            .limits_for_model(provider_name, &model.model_name)
+            .max_in_flight;
+        let provider: Arc<dyn Provider> = Arc::new(
+            HealthTrackedProvider::new(provider, health)
+                .with_provider_name(provider_name)
+                .with_max_in_flight(max_in_flight),
+        );
+        let provider: Arc<dyn Provider> = Arc::new(UsageTrackedProvider::new(
+            provider,
+            Arc::clone(&self.usage),
+            provider_name,
+        ));
+        let provider: Arc<dyn Provider> = match self.rate_limiter(provider_name) {
+            Some(limiter) => Arc::new(RateLimitedProvider::new(provider, limiter)),
+            None => provider,
+        };
+        // Outermost, so an open circuit rejects requests before they wait on the rate limiter
+        let endpoint = configured_endpoint(provider_name);
+        match self.circuit_breaker_at(provider_name, &endpoint) {
+            Some(breaker) => Arc::new(
+                CircuitBreakerProvider::new(provider, breaker, provider_name)
+                    .with_endpoint(endpoint),
+            ),
+            None => provider,
+        }
+    }
+
+    /// Keep the health of a new provider in a partition for stats
+    fn track(&self, key: PoolKey) -> Arc<ProviderHealth> {
+        let health = Arc::new(ProviderHealth::default());
+        let mut tracked = self.tracked.lock().unwrap_or_else(|e| e.into_inner());
+        let entries = tracked.entry(key).or_default();
+        entries.retain(|health| health.strong_count() > 0);
+        entries.push(Arc::downgrade(&health));
+        health
+    }
+
+    /// Return a pooled provider for this provider name and model, creating it if needed.
+    ///
+    /// Providers are partitioned by provider, model and endpoint (see [`PoolKey`]). Without a
+    /// `max_size` for the partition, one provider is shared by every caller. With one, each
+    /// caller gets a provider no one else holds, and this fails with [`PoolExhausted`] when all
+    /// of them are taken; use [`PoolManager::acquire`] to wait for one.
+    /// A provider that has failed too many times in a row is replaced with a fresh one.
+    pub fn create_or_get_pooled(
+        &self,
+        provider_name: &str,
+        model: ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        let started = Instant::now();
+        let key = PoolKey::for_model(provider_name, &model);
+        let slot = match self.slots(&key) {
+            Some((semaphore, owed)) => match semaphore.try_acquire_owned() {
+                Ok(permit) => Some(Slot::new(permit, owed)),
+                Err(_) => return Err(self.exhausted(&key, started.elapsed()).into()),
+            },
+            None => None,
+        };
+        self.take(&key, &model, started, slot)
+    }
+
+    /// Like [`PoolManager::create_or_get_pooled`], but waits up to the provider's acquire
+    /// timeout for a provider to be released before failing with [`PoolExhausted`]
+    pub async fn acquire(
+        &self,
+        provider_name: &str,
+        model: ModelConfig,
+    ) -> Result<Arc<dyn Provider>> {
+        let started = Instant::now();
+        self.refresh_credential(provider_name).await;
+        let key = PoolKey::for_model(provider_name, &model);
+        let timeout = self
+            .config()
+            .limits_for_model(provider_name, &model.model_name)
+            .acquire_timeout();
+        let slot = match self.slots(&key) {
+            Some((semaphore, owed)) => {
+                match tokio::time::timeout(timeout, semaphore.acquire_owned()).await {
+                    // The semaphore is never closed, so acquiring only fails by timing out
+                    Ok(Ok(permit)) => Some(Slot::new(permit, owed)),
+                    _ => return Err(self.exhausted(&key, started.elapsed()).into()),
+                }
+            }
+            None => None,
+        };
+        self.take(&key, &model, started, slot)
+    }
+
+    /// The semaphore handing out the slots of a partition with a `max_size`, resized to the
+    /// configured size, and the count of slots its held permits owe after shrinking
+    fn slots(&self, key: &PoolKey) -> Option<(Arc<Semaphore>, Arc<AtomicUsize>)> {
+        let max_size = self
+            .config()
+            .limits_for_model(&key.provider, &key.model)
+            .max_size?;
+        let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+        let slots = slots
+            .entry(key.clone())
+            .or_insert_with(|| PartitionSlots::new(max_size));
+        if slots.size != max_size {
+            slots.resize(max_size);
+        }
+        Some((Arc::clone(&slots.semaphore), Arc::clone(&slots.owed)))
+    }
+
+    fn exhausted(&self, key: &PoolKey, waited: Duration) -> PoolExhausted {
+        let max_size = self
+            .config()
+            .limits_for_model(&key.provider, &key.model)
+            .max_size
+            .unwrap_or_default();
+        tracing::debug!(
+            target: POOL_EVENT_TARGET,
+            event = "exhausted",
+            provider = %key.provider,
+            model = %key.model,
+            endpoint = %key.endpoint,
+            waited_ms = waited.as_millis() as u64,
+        );
+        PoolExhausted {
+            provider: key.provider.clone(),
+            model: key.model.clone(),
+            max_size,
+            waited,
+        }
+    }
+
+    /// Hand out an existing provider or create one. With a slot of a partition with a
+    /// `max_size`, an unleased provider is handed out, or a new one when all are leased;
+    /// holding the slot guarantees the partition has room for it.
+    fn take(
+        &self,
+        key: &PoolKey,
+        model: &ModelConfig,
+        started: Instant,
+        slot: Option<Slot>,
+    ) -> Result<Arc<dyn Provider>> {
+        let max_failures = self.config().max_consecutive_failures();
+
+        let mut providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+        let mut replaced = 0;
+        if let Some(entries) = providers.get_mut(key) {
+            let before = entries.len();
+            entries.retain(|entry| entry.health.consecutive_failures() < max_failures);
+            replaced = before - entries.len();
+        }
+
+        let available = providers.get(key).and_then(|entries| match &slot {
+            None => entries.first(),
+            Some(_) => entries.iter().find(|entry| entry.leases() == 0),
+        });
+        if let Some(entry) = available {
+            entry.health.touch();
+            let provider = Arc::clone(&entry.provider);
+            drop(providers);
+            self.record_acquire(key, started.elapsed(), false);
+            return Ok(lease(provider, slot));
+        }
+
+        let first_in_partition = !providers.contains_key(key);
+        drop(providers);
+
+        // A new fingerprint means the provider's configuration changed since the partition's
+        // providers were built, so those would keep using e.g. a rotated-out API key
+        if first_in_partition {
+            let stale = self.retire_where(
+                |other| other.same_partition(key) && other.fingerprint != key.fingerprint,
+                "config_changed",
+            );
+            if stale > 0 {
+                tracing::info!(
+                    "Configuration of {} changed, retired {} pooled providers",
+                    key,
+                    stale
+                );
+            }
+        }
+
+        for _ in 0..replaced {
+            tracing::warn!(
+                "Replacing pooled provider {} after {} consecutive failures",
+                key,
+                max_failures
+            );
+            self.record_evicted(key, "unhealthy");
+        }
+
+        // Create outside the lock; provider construction can be slow (e.g. OAuth discovery)
+        let entry = self.new_entry(&key.provider, model.clone())?;
+        let provider = Arc::clone(&entry.provider);
+        self.providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .push(entry);
+        self.record_acquire(key, started.elapsed(), true);
+        Ok(lease(provider, slot))
+    }
+
+    fn record_acquire(&self, key: &PoolKey, wait: Duration, created: bool) {
+        let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = counters.entry(key.partition()).or_default();
+        counters.wait_time.record(wait);
+        if created {
+            counters.created += 1;
+        }
+        let event = if created { "created" } else { "acquired" };
+        tracing::debug!(
+            target: POOL_EVENT_TARGET,
+            event,
+            provider = %key.provider,
+            model = %key.model,
+            endpoint = %key.endpoint,
+            wait_ms = wait.as_millis() as u64,
+        );
+    }
+
+    fn record_evicted(&self, key: &PoolKey, reason: &str) {
+        self.counters
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.partition())
+            .or_default()
+            .evicted += 1;
+        tracing::debug!(
+            target: POOL_EVENT_TARGET,
+            event = "evicted",
+            provider = %key.provider,
+            model = %key.model,
+            endpoint = %key.endpoint,
+            reason,
+        );
+    }
+
+    /// A snapshot of per-partition pool usage, sorted by provider, model and endpoint
+    pub fn stats(&self) -> PoolStats {
+        let providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+        let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+        let mut tracked = self.tracked.lock().unwrap_or_else(|e| e.into_inner());
+        tracked.retain(|_, entries| {
+            entries.retain(|health| health.strong_count() > 0);
+            !entries.is_empty()
+        });
+
+        let mut keys: Vec<PoolKey> = providers
+            .keys()
+            .map(PoolKey::partition)
+            .chain(counters.keys().cloned())
+            .chain(tracked.keys().cloned())
+            .collect();
+        keys.sort();
+        keys.dedup();
+
+        PoolStats {
+            keys: keys
+                .into_iter()
+                .map(|key| {
+                    // Every live provider, pooled or created directly through the factory
+                    let health: Vec<Arc<ProviderHealth>> = tracked
+                        .get(&key)
+                        .into_iter()
+                        .flatten()
+                        .filter_map(Weak::upgrade)
+                        .collect();
+                    let counters = counters.get(&key).cloned().unwrap_or_default();
+                    PoolKeyStats {
+                        provider: key.provider.clone(),
+                        model: key.model.clone(),
+                        endpoint: key.endpoint.clone(),
+                        active: health.iter().map(|health| health.in_flight()).sum(),
+                        idle: health
+                            .iter()
+                            .filter(|health| health.in_flight() == 0)
+                            .count(),
+                        created: counters.created,
+                        evicted: counters.evicted,
+                        wait_time: counters.wait_time,
+                    }
+                })
+                .collect(),
+            circuits: self.circuit_stats(),
+        }
+    }
+
+    fn new_entry(&self, provider_name: &str, model: ModelConfig) -> Result<PoolEntry> {
+        // The factory applies the in-flight cap and alerts through `wrap`; the pool only
+        // needs its own view of the provider's health to decide when to replace it
+        let health = Arc::new(ProviderHealth::default());
+        let provider = super::factory::create_in(self, provider_name, model.clone())?;
+        Ok(PoolEntry {
+            provider: Arc::new(HealthTrackedProvider::new(provider, Arc::clone(&health))),
+            health,
+            model,
+        })
+    }
+
+    /// Drop providers that have been idle for longer than the configured TTL, and idle
+    /// providers beyond each partition's `max_idle`. Returns the number evicted.
+    pub fn evict_idle(&self) -> usize {
+        let config = self.config();
+        let ttl = config.idle_ttl();
+        let mut evicted = Vec::new();
+        {
+            let mut providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(ttl) = ttl {
+                for (key, entries) in providers.iter_mut() {
+                    entries.retain(|entry| {
+                        let keep = !entry.is_idle() || entry.health.idle_for() < ttl;
+                        if !keep {
+                            evicted.push((key.clone(), "idle"));
+                        }
+                        keep
+                    });
+                }
+            }
+
+            for (key, entries) in providers.iter_mut() {
+                let Some(max_idle) = config.limits_for_model(&key.provider, &key.model).max_idle
+                else {
+                    continue;
+                };
+                // Keep the most recently used idle providers of this partition, drop the rest
+                let mut idle: Vec<(usize, Duration)> = entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| entry.is_idle())
+                    .map(|(position, entry)| (position, entry.health.idle_for()))
+                    .collect();
+                idle.sort_by_key(|(_, idle_for)| *idle_for);
+                let mut stale: Vec<usize> = idle
+                    .into_iter()
+                    .skip(max_idle)
+                    .map(|(position, _)| position)
+                    .collect();
+                // Remove from the back so earlier positions stay valid
+                stale.sort_unstable_by(|a, b| b.cmp(a));
+                for position in stale {
+                    entries.remove(position);
+                    evicted.push((key.clone(), "max_idle"));
+                }
+            }
+            providers.retain(|_, entries| !entries.is_empty());
+        }
+
+        for (key, reason) in &evicted {
+            self.record_evicted(key, reason);
+        }
+        evicted.len()
+    }
+
+    /// Ping every pooled provider with a model-list call and replace the ones that have
+    /// now failed too many times in a row. Returns the number of providers replaced.
+    pub async fn check_health(&self) -> usize {
+        let entries: Vec<_> = self
+            .providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .flat_map(|(key, entries)| {
+                entries.iter().map(move |entry| {
+                    (
+                        key.clone(),
+                        Arc::clone(&entry.provider),
+                        Arc::clone(&entry.health),
+                        entry.model.clone(),
+                    )
+                })
+            })
+            .collect();
+
+        let max_failures = self.config().max_consecutive_failures();
+        let mut replaced = 0;
+        for (key, provider, health, model) in entries {
+            match provider.fetch_supported_models_async().await {
+                Ok(Some(_)) => health.record_success(),
+                // The provider has no model listing to ping, so this says nothing either way
+                Ok(None) => {}
+                Err(e) => health.record::<()>(&Err(e)),
+            }
+            // Release our handle so it doesn't count as a lease
+            drop(provider);
+            if health.consecutive_failures() < max_failures {
+                continue;
+            }
+
+            tracing::warn!(
+                "Pooled provider {} failed its health check, replacing it",
+                key
+            );
+            // Drop the dead provider even if a replacement can't be built right now,
+            // so the next caller retries creation instead of getting the broken one
+            let replacement = self.new_entry(&key.provider, model);
+            let created = replacement.is_ok();
+            {
+                let mut providers = self.providers.lock().unwrap_or_else(|e| e.into_inner());
+                let Some(entries) = providers.get_mut(&key) else {
+                    continue;
+                };
+                entries.retain(|entry| !Arc::ptr_eq(&entry.health, &health));
+                match replacement {
+                    Ok(entry) => entries.push(entry),
+                    Err(e) => {
+                        tracing::warn!("Failed to recreate provider {}: {}", key, e)
+                    }
+                }
+                providers.retain(|_, entries| !entries.is_empty());
+            }
+            self.record_evicted(&key, "unhealthy");
+            if created {
+                self.counters
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .entry(key)
+                    .or_default()
+                    .created += 1;
+            }
+            replaced += 1;
+        }
+        replaced
+    }
+
+    /// Keep a provider's credential fresh with `refresher`. Each credential it issues is
+    /// published under `secret_key` in the config, `refresh_before` ahead of its expiry,
+    /// and pooled providers of that name are retired so new ones pick it up.
+    pub fn register_credential_refresher(
+        &self,
+        provider_name: impl Into<String>,
+        secret_key: impl Into<String>,
+        refresher: Arc<dyn CredentialRefresher>,
+        refresh_before: Duration,
+    ) {
+        self.registered_credentials
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(
+                provider_name.into(),
+                Arc::new(ManagedCredential::new(
+                    secret_key,
+                    refresher,
+                    refresh_before,
+                )),
+            );
+    }
+
+    /// Every managed credential by provider name; registered refreshers win over the config
+    fn managed_credentials(&self) -> Vec<(String, Arc<ManagedCredential>)> {
+        let mut credentials: HashMap<String, Arc<ManagedCredential>> = {
+            let mut configured = self
+                .configured_credentials
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            for (name, refresh) in self.config().credential_refresh {
+                configured
+                    .entry(name)
+                    .or_insert_with(|| Arc::new(ManagedCredential::from_config(&refresh)));
+            }
+            configured.clone()
+        };
+        credentials.extend(
+            self.registered_credentials
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .map(|(name, credential)| (name.clone(), Arc::clone(credential))),
+        );
+        let mut credentials: Vec<_> = credentials.into_iter().collect();
+        credentials.sort_by(|(a, _), (b, _)| a.cmp(b));
+        credentials
+    }
+
+    /// Refresh the provider's credential if it is missing or about to expire, retiring
+    /// pooled providers that still hold the old one. Returns whether it was refreshed.
+    async fn refresh_credential(&self, provider_name: &str) -> bool {
+        let Some(credential) = self
+            .managed_credentials()
+            .into_iter()
+            .find(|(name, _)| name == provider_name)
+            .map(|(_, credential)| credential)
+        else {
+            return false;
+        };
+        self.apply_refresh(provider_name, &credential).await
+    }
+
+    async fn apply_refresh(&self, provider_name: &str, credential: &ManagedCredential) -> bool {
+        match credential.refresh_if_needed().await {
+            Ok(false) => false,
+            Ok(true) => {
+                let retired = self.retire_provider(provider_name, "credential_refresh");
+                tracing::info!(
+                    "Refreshed {} for {}, retired {} pooled providers",
+                    credential.secret_key(),
+                    provider_name,
+                    retired
+                );
+                true
+            }
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to refresh {} for {}: {}",
+                    credential.secret_key(),
+                    provider_name,
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    /// Refresh every managed credential that is missing or close to expiry.
+    /// Returns the number refreshed.
+    pub async fn refresh_credentials(&self) -> usize {
+        let mut refreshed = 0;
+        for (provider_name, credential) in self.managed_credentials() {
+            if self.apply_refresh(&provider_name, &credential).await {
+                refreshed += 1;
+            }
+        }
+        refreshed
+    }
+
+    /// Drop every pooled provider of this name, e.g. after rotating its credentials, so the
+    /// next caller builds one from the current configuration. Holders keep using theirs until
+    /// released. The provider's circuit breaker is reset as well, since it may have opened on
+    /// the old credentials. Returns the number of providers retired.
+    pub fn invalidate(&self, provider_name: &str) -> usize {
+        self.breakers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|(provider, _), _| provider != provider_name);
+        let retired = self.retire_provider(provider_name, "invalidated");
+        tracing::info!(
+            "Invalidated provider {}, retired {} pooled providers",
+            provider_name,
+            retired
+        );
+        retired
+    }
+
+    /// Remove every pooled provider of this name; holders keep using theirs until released
+    fn retire_provider(&self, provider_name: &str, reason: &str) -> usize {
+        self.retire_where(|key| key.provider == provider_name, reason)
+    }
+
+    /// Remove the pooled providers of every partition matching `retire`
+    fn retire_where(&self, retire: impl Fn(&PoolKey) -> bool, reason: &str) -> usize {
+        let mut retired = Vec::new();
+        self.providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|key, entries| {
+                let keep = !retire(key);
+                if !keep {
+                    retired.push((key.clone(), entries.len()));
+                }
+                keep
+            });
+
+        let mut total = 0;
+        for (key, count) in retired {
+            for _ in 0..count {
+                self.record_evicted(&key, reason);
+            }
+            total += count;
+        }
+        total
+    }
+
+    /// Issue credentials now and refresh them ahead of expiry from then on, if any provider
+    /// has a credential refresher
+    pub fn spawn_credential_refresh(&'static self) -> Option<JoinHandle<()>> {
+        if self.managed_credentials().is_empty() {
+            return None;
+        }
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(CREDENTIAL_CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                self.refresh_credentials().await;
+            }
+        }))
+    }
+
+    /// Periodically evict idle providers and health check the rest, if an interval is configured
+    pub fn spawn_health_checks(&'static self) -> Option<JoinHandle<()>> {
+        let interval = self.config().health_check_interval()?;
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let evicted = self.evict_idle();
+                let replaced = self.check_health().await;
+                if evicted > 0 || replaced > 0 {
+                    tracing::info!(
+                        "Provider pool maintenance: evicted {} idle, replaced {} unhealthy",
+                        evicted,
+                        replaced
+                    );
+                }
+            }
+        }))
+    }
+
+    /// Create the pooled providers for these targets and make one authenticated call through
+    /// each, so TLS connections are open and bad credentials are reported at startup rather
+    /// than on the first user turn. Targets are warmed concurrently.
+    pub async fn prewarm(&self, configs: &[PrewarmTarget]) -> Vec<PrewarmResult> {
+        futures::future::join_all(configs.iter().map(|target| async move {
+            let started = Instant::now();
+            self.refresh_credential(&target.provider).await;
+            let error = match self.create_or_get_pooled(&target.provider, target.model.clone()) {
+                Ok(provider) => provider
+                    .fetch_supported_models_async()
+                    .await
+                    .err()
+                    .map(|e| e.to_string()),
+                Err(e) => Some(e.to_string()),
+            };
+            let result = PrewarmResult {
+                provider: target.provider.clone(),
+                model: target.model.model_name.clone(),
+                elapsed: started.elapsed(),
+                error,
+            };
+            result.log();
+            result
+        }))
+        .await
+    }
+
+    /// Create up to `n` providers in the partition for this provider and model ahead of time
+    /// and make one authenticated call through each, so the first requests after startup find
+    /// open connections and fresh auth tokens instead of paying for the handshakes. The
+    /// partition's configured limits apply: without a `max_size` it shares a single provider,
+    /// so only one is warmed, and `max_idle` should be at least `n` for the rest to survive
+    /// idle eviction. Returns one result per warmed provider.
+    pub async fn warm(
+        &self,
+        provider_name: &str,
+        model: ModelConfig,
+        n: usize,
+    ) -> Vec<PrewarmResult> {
+        let limits = self
+            .config
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .limits_for_model(provider_name, &model.model_name);
+        let n = n.min(limits.max_size.unwrap_or(1));
+        self.refresh_credential(provider_name).await;
+
+        let key = PoolKey::for_model(provider_name, &model);
+        let existing = self
+            .providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&key)
+            .map_or(0, Vec::len);
+
+        let mut results = Vec::new();
+        let mut warming = Vec::new();
+        // Create outside the lock; provider construction can be slow (e.g. OAuth discovery)
+        for _ in existing..n {
+            let started = Instant::now();
+            match self.new_entry(provider_name, model.clone()) {
+                Ok(entry) => {
+                    warming.push((Arc::clone(&entry.provider), started));
+                    self.providers
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .entry(key.clone())
+                        .or_default()
+                        .push(entry);
+                    self.record_acquire(&key, started.elapsed(), true);
+                }
+                Err(e) => results.push(PrewarmResult {
+                    provider: provider_name.to_string(),
+                    model: model.model_name.clone(),
+                    elapsed: started.elapsed(),
+                    error: Some(e.to_string()),
+                }),
+            }
+        }
+
+        let model_name = model.model_name.as_str();
+        let handshakes =
+            futures::future::join_all(warming.into_iter().map(|(provider, started)| async move {
+                let error = provider
+                    .fetch_supported_models_async()
+                    .await
+                    .err()
+                    .map(|e| e.to_string());
+                PrewarmResult {
+                    provider: provider_name.to_string(),
+                    model: model_name.to_string(),
+                    elapsed: started.elapsed(),
+                    error,
+                }
+            }))
+            .await;
+        results.extend(handshakes);
+        results.iter().for_each(PrewarmResult::log);
+        results
+    }
+
+    /// Number of pooled provider instances
+    pub fn len(&self) -> usize {
+        self.providers
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .map(Vec::len)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Pooled providers are built by the factory, so these can't run alongside the factory's
+// tests of the GOOSE_LEAD_* variables
+#[cfg(test)]
+#[serial_test::serial]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+
+    #[test]
+    fn test_rate_limit_for_prefers_provider_override() {
+        let config = PoolConfig {
+            default_rate_limit: RateLimitConfig {
+                requests_per_minute: Some(60),
+                tokens_per_minute: None,
+            },
+            ..Default::default()
+        }
+        .with_rate_limit(
+            "openai",
+            RateLimitConfig {
+                requests_per_minute: Some(10),
+                tokens_per_minute: Some(1000),
+            },
+        );
+
+        assert_eq!(
+            config.rate_limit_for("openai").requests_per_minute,
+            Some(10)
+        );
+        assert_eq!(
+            config.rate_limit_for("databricks").requests_per_minute,
+            Some(60)
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter_is_shared_per_provider_name() {
+        let pool = PoolManager::new(PoolConfig::default().with_rate_limit(
+            "openai",
+            RateLimitConfig {
+                requests_per_minute: Some(10),
+                tokens_per_minute: None,
+            },
+        ));
+
+        let first = pool.rate_limiter("openai").unwrap();
+        let second = pool.rate_limiter("openai").unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(pool.rate_limiter("databricks").is_none());
+
+        pool.set_config(PoolConfig::default());
+        assert!(pool.rate_limiter("openai").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stats_track_created_evicted_and_active() {
+        let pool = PoolManager::new(PoolConfig {
+            idle_ttl_secs: Some(0),
+            ..Default::default()
+        });
+        let model = ModelConfig::new("mock".to_string());
+        pool.create_or_get_pooled("mock", model.clone()).unwrap();
+        pool.create_or_get_pooled("mock", model.clone()).unwrap();
+
+        let stats = pool.stats();
+        let key = stats.get("mock", "mock").unwrap();
+        assert_eq!((key.created, key.evicted), (1, 0));
+        assert_eq!((key.active, key.idle), (0, 1));
+        assert_eq!(key.wait_time.count, 2);
+
+        assert_eq!(pool.evict_idle(), 1);
+        let stats = pool.stats();
+        let key = stats.get("mock", "mock").unwrap();
+        assert_eq!((key.created, key.evicted), (1, 1));
+        assert_eq!(key.idle, 0);
+        assert_eq!(stats.total_active(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_max_size_limits_leases_per_provider() {
+        let pool = PoolManager::new(PoolConfig::default().with_limits(
+            "mock",
+            PoolLimits {
+                max_size: Some(1),
+                max_idle: Some(0),
+                acquire_timeout_secs: Some(0),
+                ..Default::default()
+            },
+        ));
+        let model = ModelConfig::new("mock".to_string());
+
+        let leased = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+        let Err(err) = pool.acquire("mock", model.clone()).await else {
+            panic!("acquire should time out while the only slot is leased");
+        };
+        let exhausted = err.downcast_ref::<PoolExhausted>().unwrap();
+        assert_eq!(exhausted.provider, "mock");
+        assert_eq!(exhausted.max_size, 1);
+
+        // Other models of the provider are partitioned with their own limit
+        let other_model = pool
+            .create_or_get_pooled("mock", ModelConfig::new("other".to_string()))
+            .unwrap();
+        drop(other_model);
+
+        drop(leased);
+        let again = pool.acquire("mock", model).await.unwrap();
+        drop(again);
+        assert_eq!(pool.evict_idle(), 2);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_resizing_keeps_counting_held_leases() {
+        let sized = |max_size| {
+            PoolConfig::default().with_limits(
+                "mock",
+                PoolLimits {
+                    max_size: Some(max_size),
+                    ..Default::default()
+                },
+            )
+        };
+        let pool = PoolManager::new(sized(2));
+        let model = ModelConfig::new("mock".to_string());
+        let first = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+        let second = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+
+        // Shrinking while both are held leaves no room until enough of them come back
+        pool.set_config(sized(1));
+        assert!(pool.create_or_get_pooled("mock", model.clone()).is_err());
+        drop(first);
+        assert!(pool.create_or_get_pooled("mock", model.clone()).is_err());
+        drop(second);
+        let only = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+        assert!(pool.create_or_get_pooled("mock", model.clone()).is_err());
+
+        // Growing adds room next to the lease still held
+        pool.set_config(sized(3));
+        let more: Vec<_> = (0..2)
+            .map(|_| pool.create_or_get_pooled("mock", model.clone()).unwrap())
+            .collect();
+        assert!(pool.create_or_get_pooled("mock", model).is_err());
+        drop((only, more));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_a_released_provider_without_exceeding_max_size() {
+        let pool = Arc::new(PoolManager::new(PoolConfig::default().with_limits(
+            "mock",
+            PoolLimits {
+                max_size: Some(2),
+                acquire_timeout_secs: Some(30),
+                ..Default::default()
+            },
+        )));
+        let model = ModelConfig::new("mock".to_string());
+
+        let waiters: Vec<_> = (0..8)
+            .map(|_| {
+                let pool = Arc::clone(&pool);
+                let model = model.clone();
+                tokio::spawn(async move {
+                    let provider = pool.acquire("mock", model).await.unwrap();
+                    tokio::time::sleep(Duration::from_millis(5)).await;
+                    drop(provider);
+                })
+            })
+            .collect();
+        for waiter in waiters {
+            waiter.await.unwrap();
+        }
+
+        // Every caller got a provider, and the partition never grew past its max_size
+        assert_eq!(pool.len(), 2);
+        let stats = pool.stats();
+        let key = stats.get("mock", "mock").unwrap();
+        assert_eq!(key.created, 2);
+        assert_eq!(key.wait_time.count, 8);
+    }
+
+    #[tokio::test]
+    async fn test_reload_retires_pooled_providers() {
+        let limit = RateLimitConfig {
+            requests_per_minute: Some(60),
+            ..Default::default()
+        };
+        let pool = PoolManager::new(PoolConfig::default().with_rate_limit("mock", limit));
+        let model = ModelConfig::new("mock".to_string());
+        let held = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+        let limiter = pool.rate_limiter("mock").unwrap();
+
+        assert_eq!(
+            pool.reload(PoolConfig::default().with_rate_limit("mock", limit)),
+            1
+        );
+        assert!(pool.is_empty());
+        // The unchanged limiter is kept, and the retired provider still serves its holder
+        assert!(Arc::ptr_eq(&limiter, &pool.rate_limiter("mock").unwrap()));
+        assert_eq!(held.get_model_config().model_name, "mock");
+
+        let fresh = pool.create_or_get_pooled("mock", model).unwrap();
+        assert!(!Arc::ptr_eq(&held, &fresh));
+        let stats = pool.stats();
+        let key = stats.get("mock", "mock").unwrap();
+        assert_eq!((key.created, key.evicted), (2, 1));
+    }
+
+    #[test]
+    fn test_config_change_retires_stale_providers() {
+        let pool = PoolManager::new(PoolConfig::default());
+        let model = ModelConfig::new("mock".to_string());
+        let old_key = PoolKey::new("mock", "mock", "").with_fingerprint("old");
+        let new_key = PoolKey::new("mock", "mock", "").with_fingerprint("new");
+
+        let old = pool.take(&old_key, &model, Instant::now(), None).unwrap();
+        let new = pool.take(&new_key, &model, Instant::now(), None).unwrap();
+        assert!(!Arc::ptr_eq(&old, &new));
+        assert_eq!(pool.len(), 1);
+
+        // Stats are per partition, across configurations
+        let stats = pool.stats();
+        assert_eq!(stats.keys.len(), 1);
+        let key = stats.get("mock", "mock").unwrap();
+        assert_eq!((key.created, key.evicted), (2, 1));
+    }
+
+    #[test]
+    fn test_invalidate_retires_only_that_provider() {
+        let pool = PoolManager::new(PoolConfig::default());
+        let key = |model: &str| PoolKey::new("mock", model, "");
+        for model in ["a", "b"] {
+            let model = ModelConfig::new(model.to_string());
+            pool.take(&key(&model.model_name), &model, Instant::now(), None)
+                .unwrap();
+        }
+        let other = PoolKey::new("other", "a", "");
+        pool.providers
+            .lock()
+            .unwrap()
+            .insert(other.clone(), Vec::new());
+
+        assert_eq!(pool.invalidate("mock"), 2);
+        assert!(pool.providers.lock().unwrap().contains_key(&other));
+        assert_eq!(pool.invalidate("mock"), 0);
+    }
+
+    use crate::providers::credentials::IssuedCredential;
+
+    struct ExpiringToken;
+
+    #[async_trait]
+    impl CredentialRefresher for ExpiringToken {
+        async fn refresh(&self) -> Result<IssuedCredential> {
+            Ok(IssuedCredential {
+                secret: "short-lived".to_string(),
+                expires_at: Some(chrono::Utc::now() + chrono::Duration::seconds(60)),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_credential_refresh_retires_pooled_providers() {
+        let pool = PoolManager::new(PoolConfig::default());
+        let model = ModelConfig::new("mock".to_string());
+        pool.register_credential_refresher(
+            "mock",
+            "GOOSE_TEST_POOL_TOKEN",
+            Arc::new(ExpiringToken),
+            Duration::from_secs(300),
+        );
+
+        // The first acquire issues the credential before creating the provider
+        let first = pool.acquire("mock", model.clone()).await.unwrap();
+        assert_eq!(pool.len(), 1);
+
+        // The token is within the refresh margin, so it is replaced and the provider retired
+        assert_eq!(pool.refresh_credentials().await, 1);
+        assert!(pool.is_empty());
+        let second = pool.acquire("mock", model).await.unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        crate::config::Config::global().clear_runtime_secret("GOOSE_TEST_POOL_TOKEN");
+    }
+
+    #[test]
+    fn test_evict_idle_respects_ttl() {
+        let pool = PoolManager::new(PoolConfig::default());
+        pool.create_or_get_pooled("mock", ModelConfig::new("mock".to_string()))
+            .unwrap();
+        assert_eq!(pool.evict_idle(), 0);
+
+        pool.set_config(PoolConfig {
+            idle_ttl_secs: Some(0),
+            ..Default::default()
+        });
+        pool.create_or_get_pooled("mock", ModelConfig::new("mock".to_string()))
+            .unwrap();
+        assert_eq!(pool.evict_idle(), 1);
+        assert!(pool.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_failing_provider_is_replaced() {
+        let pool = PoolManager::new(PoolConfig {
+            max_consecutive_failures: Some(2),
+            ..Default::default()
+        });
+        let model = ModelConfig::new("mock".to_string());
+        let first = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+
+        // An unscripted mock provider fails every request
+        for _ in 0..2 {
+            assert!(first.complete("", &[], &[]).await.is_err());
+        }
+
+        let second = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        let third = pool.create_or_get_pooled("mock", model).unwrap();
+        assert!(Arc::ptr_eq(&second, &third));
+    }
+
+    #[test]
+    fn test_circuit_breaker_opens_and_recovers_through_probe() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            cooldown_secs: 0,
+        });
+        assert!(!breaker.record_failure());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.record_failure());
+        // With no cool-down the open circuit is immediately ready for a probe
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.allow().is_ok());
+        // A failed probe re-opens a circuit that was never closed
+        assert!(!breaker.record_failure());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.allow().is_ok());
+        assert!(breaker.record_success());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_circuit_breakers_are_per_endpoint() {
+        let pool = PoolManager::new(PoolConfig {
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 1,
+                cooldown_secs: 60,
+            }),
+            ..Default::default()
+        });
+        let primary = pool.circuit_breaker_at("openai", "").unwrap();
+        let proxy = pool
+            .circuit_breaker_at("openai", "https://proxy.example.com")
+            .unwrap();
+        primary.record_failure();
+
+        assert_eq!(primary.state(), CircuitState::Open);
+        assert_eq!(proxy.state(), CircuitState::Closed);
+        assert!(Arc::ptr_eq(
+            &primary,
+            &pool.circuit_breaker_at("openai", "").unwrap()
+        ));
+        let circuits = pool.stats().circuits;
+        assert_eq!(circuits.len(), 2);
+        assert_eq!(circuits[0].state, CircuitState::Open);
+
+        // A provider can turn its breaker off
+        let pool = PoolManager::new(PoolConfig {
+            circuit_breaker: Some(CircuitBreakerConfig {
+                failure_threshold: 1,
+                cooldown_secs: 60,
+            }),
+            circuit_breakers: HashMap::from([(
+                "ollama".to_string(),
+                CircuitBreakerConfig {
+                    failure_threshold: 0,
+                    cooldown_secs: 60,
+                },
+            )]),
+            ..Default::default()
+        });
+        assert!(pool.circuit_breaker_at("ollama", "").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_falls_back_without_calling_the_endpoint() {
+        use crate::providers::fallback::{FallbackEntry, FallbackProvider};
+        use crate::providers::mock::MockProvider;
+
+        let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown_secs: 60,
+        }));
+        breaker.record_failure();
+        let primary = Arc::new(MockProvider::default());
+        let chain = FallbackProvider::new(vec![
+            FallbackEntry::new(
+                "primary",
+                Arc::new(CircuitBreakerProvider::new(
+                    Arc::clone(&primary) as Arc<dyn Provider>,
+                    breaker,
+                    "primary",
+                )),
+            ),
+            FallbackEntry::new(
+                "backup",
+                Arc::new(MockProvider::default().with_text("from backup")),
+            ),
+        ])
+        .unwrap();
+
+        let (message, usage) = chain.complete("", &[], &[]).await.unwrap();
+        assert_eq!(message.as_concat_text(), "from backup");
+        assert_eq!(usage.provider.as_deref(), Some("backup"));
+        assert!(primary.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_open_circuit_fails_fast() {
+        let breaker = Arc::new(CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 1,
+            cooldown_secs: 60,
+        }));
+        let provider = CircuitBreakerProvider::new(
+            Arc::new(crate::providers::mock::MockProvider::default()),
+            Arc::clone(&breaker),
+            "mock",
+        );
+
+        // An unscripted mock provider fails every request
+        assert!(matches!(
+            provider.complete("", &[], &[]).await,
+            Err(ProviderError::ExecutionError(_))
+        ));
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert!(matches!(
+            provider.complete("", &[], &[]).await,
+            Err(ProviderError::CircuitOpen(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prewarm_reports_each_target() {
+        let pool = PoolManager::new(PoolConfig::default());
+        let results = pool
+            .prewarm(&[
+                PrewarmTarget::new("mock", ModelConfig::new("mock".to_string())),
+                PrewarmTarget::new("no-such-provider", ModelConfig::new("model".to_string())),
+            ])
+            .await;
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(!results[1].is_ok());
+        // The warmed provider stays pooled for the first real request
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_warm_creates_n_providers_up_to_max_size() {
+        let limits = PoolLimits {
+            max_size: Some(3),
+            max_idle: Some(3),
+            ..Default::default()
+        };
+        let pool = PoolManager::new(PoolConfig::default().with_limits("mock", limits));
+        let model = ModelConfig::new("mock".to_string());
+
+        let results = pool.warm("mock", model.clone(), 5).await;
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(PrewarmResult::is_ok));
+        assert_eq!(pool.len(), 3);
+
+        // Already warm, so nothing more is created
+        assert!(pool.warm("mock", model.clone(), 3).await.is_empty());
+        // Warming leaves the shared config alone
+        assert!(!pool.config().limits.contains_key("mock/mock"));
+
+        // The warmed providers are handed out to callers one each
+        let first = pool.create_or_get_pooled("mock", model.clone()).unwrap();
+        let second = pool.create_or_get_pooled("mock", model).unwrap();
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(pool.len(), 3);
+    }
+
+    /// Records how many requests run at once and the order they started in
+    struct SlowProvider {
+        running: AtomicUsize,
+        peak: AtomicUsize,
+        started: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Provider for SlowProvider {
+        fn metadata() -> ProviderMetadata {
+            ProviderMetadata::empty()
+        }
+
+        fn get_model_config(&self) -> ModelConfig {
+            ModelConfig::new("slow".to_string())
+        }
+
+        async fn complete(
+            &self,
+            system: &str,
+            _messages: &[Message],
+            _tools: &[Tool],
+        ) -> Result<(Message, ProviderUsage), ProviderError> {
+            self.started.lock().unwrap().push(system.to_string());
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            Ok((
+                Message::assistant().with_text("done"),
+                ProviderUsage::new("slow".to_string(), Usage::default()),
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_max_in_flight_queues_requests_in_order() {
+        let inner = Arc::new(SlowProvider {
+            running: AtomicUsize::new(0),
+            peak: AtomicUsize::new(0),
+            started: Mutex::new(Vec::new()),
+        });
+        let provider = Arc::new(
+            HealthTrackedProvider::new(inner.clone(), Arc::new(ProviderHealth::default()))
+                .with_max_in_flight(Some(2)),
+        );
+
+        let mut requests = Vec::new();
+        for i in 0..6 {
+            let provider = Arc::clone(&provider);
+            requests.push(tokio::spawn(async move {
+                provider.complete(&i.to_string(), &[], &[]).await
+            }));
+            // Give each request time to join the queue before the next one
+            tokio::task::yield_now().await;
+        }
+        for request in requests {
+            request.await.unwrap().unwrap();
+        }
+
+        assert_eq!(inner.peak.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            *inner.started.lock().unwrap(),
+            vec!["0", "1", "2", "3", "4", "5"]
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::pool::CircuitState;
+
+/// Tracing target for pool events. Enable with e.g. `RUST_LOG=goose::provider_pool=debug`
+/// to get a stream of acquire, create and evict events with provider, model and wait time.
+pub const POOL_EVENT_TARGET: &str = "goose::provider_pool";
+
+/// Upper bounds of the wait time histogram buckets, in milliseconds
+pub const WAIT_TIME_BUCKETS_MS: [u64; 7] = [1, 5, 25, 100, 500, 2_500, 10_000];
+
+/// Distribution of the time callers spent acquiring a provider from the pool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaitTimeHistogram {
+    /// Counts per bucket of [`WAIT_TIME_BUCKETS_MS`], followed by one bucket for slower waits
+    pub buckets: Vec<u64>,
+    pub count: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+impl WaitTimeHistogram {
+    pub fn record(&mut self, wait: Duration) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; WAIT_TIME_BUCKETS_MS.len() + 1];
+        }
+        let ms = wait.as_millis() as u64;
+        let bucket = WAIT_TIME_BUCKETS_MS
+            .iter()
+            .position(|bound| ms <= *bound)
+            .unwrap_or(WAIT_TIME_BUCKETS_MS.len());
+        self.buckets[bucket] += 1;
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+    }
+
+    pub fn mean_ms(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.total_ms as f64 / self.count as f64)
+    }
+
+    /// Upper bound of the bucket holding the given percentile (0.0 to 1.0). Waits beyond
+    /// the last bucket report the slowest wait observed.
+    pub fn percentile_ms(&self, percentile: f64) -> Option<u64> {
+        if self.count == 0 {
+            return None;
+        }
+        let target = ((self.count as f64) * percentile.clamp(0.0, 1.0))
+            .ceil()
+            .max(1.0) as u64;
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= target {
+                return Some(
+                    WAIT_TIME_BUCKETS_MS
+                        .get(index)
+                        .copied()
+                        .unwrap_or(self.max_ms),
+                );
+            }
+        }
+        Some(self.max_ms)
+    }
+}
+
+/// Lifetime counters for one pool key, kept across evictions
+#[derive(Debug, Clone, Default)]
+pub(crate) struct KeyCounters {
+    pub created: u64,
+    pub evicted: u64,
+    pub wait_time: WaitTimeHistogram,
+}
+
+/// Pool usage for one partition of the pool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolKeyStats {
+    pub provider: String,
+    pub model: String,
+    /// Configured endpoint of the provider, empty for its default
+    #[serde(default)]
+    pub endpoint: String,
+    /// Requests currently in flight through the pooled provider
+    pub active: usize,
+    /// Pooled providers with no request in flight
+    pub idle: usize,
+    /// Providers created for this key, including replacements
+    pub created: u64,
+    /// Providers dropped for being idle or unhealthy
+    pub evicted: u64,
+    pub wait_time: WaitTimeHistogram,
+}
+
+/// State of the circuit breaker for one provider endpoint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CircuitStats {
+    pub provider: String,
+    /// Configured endpoint of the provider, empty for its default
+    pub endpoint: String,
+    pub state: CircuitState,
+    pub consecutive_failures: u32,
+}
+
+/// A snapshot of the provider pool, see `PoolManager::stats`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub keys: Vec<PoolKeyStats>,
+    /// Circuit breakers in use, see `PoolManager::circuit_stats`
+    #[serde(default)]
+    pub circuits: Vec<CircuitStats>,
+}
+
+impl PoolStats {
+    /// Usage for a provider and model, on the first endpoint it was pooled for
+    pub fn get(&self, provider: &str, model: &str) -> Option<&PoolKeyStats> {
+        self.keys
+            .iter()
+            .find(|stats| stats.provider == provider && stats.model == model)
+    }
+
+    pub fn total_active(&self) -> usize {
+        self.keys.iter().map(|stats| stats.active).sum()
+    }
+
+    pub fn total_idle(&self) -> usize {
+        self.keys.iter().map(|stats| stats.idle).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets_and_percentiles() {
+        let mut histogram = WaitTimeHistogram::default();
+        assert_eq!(histogram.percentile_ms(0.5), None);
+
+        for ms in [0, 3, 3, 80, 20_000] {
+            histogram.record(Duration::from_millis(ms));
+        }
+
+        assert_eq!(histogram.count, 5);
+        assert_eq!(histogram.buckets[0], 1);
+        assert_eq!(histogram.buckets[1], 2);
+        assert_eq!(histogram.buckets[WAIT_TIME_BUCKETS_MS.len()], 1);
+        assert_eq!(histogram.percentile_ms(0.5), Some(5));
+        assert_eq!(histogram.percentile_ms(1.0), Some(20_000));
+        assert_eq!(histogram.mean_ms(), Some(20_086.0 / 5.0));
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use once_cell::sync::Lazy;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -7,6 +8,8 @@ use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::RwLock;
 
+use super::base::Usage;
+
 /// Disk cache configuration
 const CACHE_FILE_NAME: &str = "pricing_cache.json";
 const CACHE_TTL_DAYS: u64 = 7; // Cache for 7 days
@@ -38,6 +41,114 @@ pub struct PricingInfo {
     pub input_cost: f64,  // Cost per token
     pub output_cost: f64, // Cost per token
     pub context_length: Option<u32>,
+    /// Cost per cached input token, when the provider discounts prompt cache hits
+    #[serde(default)]
+    pub cached_input_cost: Option<f64>,
+}
+
+impl PricingInfo {
+    /// Build pricing from USD per million tokens, the unit providers publish prices in
+    pub fn per_million(input: f64, output: f64, cached_input: Option<f64>) -> Self {
+        Self {
+            input_cost: input / 1_000_000.0,
+            output_cost: output / 1_000_000.0,
+            context_length: None,
+            cached_input_cost: cached_input.map(|price| price / 1_000_000.0),
+        }
+    }
+
+    /// Cost in USD of a request, or None when the usage has no token counts
+    pub fn cost_usd(&self, usage: &Usage) -> Option<f64> {
+        self.cost_usd_with_cached(usage, 0)
+    }
+
+    /// Cost in USD where `cached_input_tokens` of the input were served from the prompt cache
+    pub fn cost_usd_with_cached(&self, usage: &Usage, cached_input_tokens: i32) -> Option<f64> {
+        if usage.input_tokens.is_none() && usage.output_tokens.is_none() {
+            return None;
+        }
+        let input = usage.input_tokens.unwrap_or(0).max(0);
+        let cached = cached_input_tokens.clamp(0, input);
+        let output = usage.output_tokens.unwrap_or(0).max(0);
+        let cached_rate = self.cached_input_cost.unwrap_or(self.input_cost);
+
+        Some(
+            (input - cached) as f64 * self.input_cost
+                + cached as f64 * cached_rate
+                + output as f64 * self.output_cost,
+        )
+    }
+}
+
+/// Built-in prices (USD per million tokens: input, output, cached input) for common models,
+/// used when the OpenRouter cache has no entry. Overrides take precedence over both.
+const BUILTIN_PRICING: &[(&str, f64, f64, Option<f64>)] = &[
+    ("gpt-4o", 2.5, 10.0, Some(1.25)),
+    ("gpt-4o-mini", 0.15, 0.6, Some(0.075)),
+    ("gpt-4.1", 2.0, 8.0, Some(0.5)),
+    ("gpt-4.1-mini", 0.4, 1.6, Some(0.1)),
+    ("o3", 2.0, 8.0, Some(0.5)),
+    ("o4-mini", 1.1, 4.4, Some(0.275)),
+    ("claude-3-5-sonnet-latest", 3.0, 15.0, Some(0.3)),
+    ("claude-3-7-sonnet-latest", 3.0, 15.0, Some(0.3)),
+    ("claude-sonnet-4-0", 3.0, 15.0, Some(0.3)),
+    ("claude-opus-4-0", 15.0, 75.0, Some(1.5)),
+    ("claude-3-5-haiku-latest", 0.8, 4.0, Some(0.08)),
+    ("gemini-2.5-pro", 1.25, 10.0, Some(0.31)),
+    ("gemini-2.5-flash", 0.3, 2.5, Some(0.075)),
+];
+
+/// Prices set at runtime, keyed by model name
+static PRICING_OVERRIDES: Lazy<std::sync::RwLock<HashMap<String, PricingInfo>>> =
+    Lazy::new(|| std::sync::RwLock::new(HashMap::new()));
+
+/// Set or replace the price used for a model, e.g. for negotiated or self-hosted rates
+pub fn set_model_price(model: impl Into<String>, pricing: PricingInfo) {
+    PRICING_OVERRIDES
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(model.into(), pricing);
+}
+
+/// Look up the price for a model without waiting on the OpenRouter cache.
+///
+/// Checks runtime overrides, then the OpenRouter cache (when it is loaded and not being
+/// refreshed), then the built-in table. Databricks style prefixes such as
+/// `databricks-claude-3-7-sonnet` are not resolved and yield None.
+pub fn lookup_model_price(provider: Option<&str>, model: &str) -> Option<PricingInfo> {
+    if let Some(pricing) = PRICING_OVERRIDES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(model)
+    {
+        return Some(pricing.clone());
+    }
+
+    if let Ok(cache) = PRICING_CACHE.memory_cache.try_read() {
+        if let Some(cached) = &*cache {
+            let from_provider = provider.and_then(|provider| {
+                cached
+                    .pricing
+                    .get(&provider.to_lowercase())
+                    .and_then(|models| models.get(model))
+            });
+            let found = from_provider
+                .or_else(|| cached.pricing.values().find_map(|models| models.get(model)));
+            if let Some(pricing) = found {
+                return Some(pricing.clone());
+            }
+        }
+    }
+
+    BUILTIN_PRICING
+        .iter()
+        .find(|(name, ..)| *name == model)
+        .map(|(_, input, output, cached)| PricingInfo::per_million(*input, *output, *cached))
+}
+
+/// Cost in USD of a request to `model`, when its price is known
+pub fn estimate_cost_usd(provider: Option<&str>, model: &str, usage: &Usage) -> Option<f64> {
+    lookup_model_price(provider, model)?.cost_usd(usage)
 }
 
 /// Cache for OpenRouter pricing data with disk persistence
@@ -160,6 +271,11 @@ impl PricingCache {
                             input_cost,
                             output_cost,
                             context_length: model.context_length,
+                            cached_input_cost: model
+                                .pricing
+                                .input_cache_read
+                                .as_deref()
+                                .and_then(convert_pricing),
                         },
                     );
                 }
@@ -253,6 +369,8 @@ pub struct OpenRouterModel {
 pub struct OpenRouterPricing {
     pub prompt: String,     // Cost per token for input (in USD)
     pub completion: String, // Cost per token for output (in USD)
+    #[serde(default)]
+    pub input_cache_read: Option<String>, // Cost per cached input token (in USD)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -365,6 +483,31 @@ pub fn convert_pricing(price_str: &str) -> Option<f64> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cost_usd_uses_cached_rate() {
+        let pricing = PricingInfo::per_million(2.0, 8.0, Some(0.5));
+        let usage = Usage::new(Some(1_000_000), Some(500_000), None);
+
+        let cost = pricing.cost_usd(&usage).unwrap();
+        assert!((cost - 6.0).abs() < 1e-9);
+
+        let cached = pricing.cost_usd_with_cached(&usage, 1_000_000).unwrap();
+        assert!((cached - 4.5).abs() < 1e-9);
+
+        assert_eq!(pricing.cost_usd(&Usage::default()), None);
+    }
+
+    #[test]
+    fn test_lookup_model_price_prefers_overrides() {
+        assert!(lookup_model_price(None, "gpt-4o").is_some());
+        assert!(lookup_model_price(None, "not-a-real-model").is_none());
+
+        set_model_price("my-finetune", PricingInfo::per_million(1.0, 1.0, None));
+        let usage = Usage::new(Some(1_000_000), Some(0), None);
+        let cost = estimate_cost_usd(Some("openai"), "my-finetune", &usage).unwrap();
+        assert!((cost - 1.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_parse_model_id() {
         assert_eq!(
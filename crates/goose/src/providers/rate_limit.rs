@@ -0,0 +1,372 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use mcp_core::tool::Tool;
+use serde::{Deserialize, Serialize};
+
+use super::base::{
+    LeadWorkerProviderTrait, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage,
+};
+use super::errors::ProviderError;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// Quality-of-service class of a provider request. Higher classes are served first
+/// when a rate limiter is saturated.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Scheduled or background work that can wait
+    Batch,
+    #[default]
+    Normal,
+    /// Work a user is actively waiting on
+    Interactive,
+}
+
+impl Priority {
+    const ALL: [Priority; 3] = [Priority::Batch, Priority::Normal, Priority::Interactive];
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+tokio::task_local! {
+    static REQUEST_PRIORITY: Priority;
+}
+
+/// Run `future` with `priority` attached to every provider request it makes
+pub async fn with_priority<F: Future>(priority: Priority, future: F) -> F::Output {
+    REQUEST_PRIORITY.scope(priority, future).await
+}
+
+/// The priority of the current task, `Normal` when none was set
+pub fn current_priority() -> Priority {
+    REQUEST_PRIORITY.try_with(|p| *p).unwrap_or_default()
+}
+
+/// Client-side request and token budgets for a provider
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    /// Maximum requests started per minute
+    pub requests_per_minute: Option<u32>,
+    /// Maximum tokens (input + output) consumed per minute
+    pub tokens_per_minute: Option<u32>,
+}
+
+impl RateLimitConfig {
+    pub fn is_unlimited(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_minute.is_none()
+    }
+}
+
+/// A token bucket that refills continuously up to its per-minute capacity.
+///
+/// The balance may go negative when actual usage is only known after a request,
+/// in which case later callers wait until the debt is paid back.
+#[derive(Debug)]
+pub struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    pub fn per_minute(limit: u32) -> Self {
+        let capacity = limit.max(1) as f64;
+        Self {
+            capacity,
+            available: capacity,
+            refill_per_sec: capacity / 60.0,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+
+    /// Take `amount` if it is available, otherwise return how long until it will be
+    pub fn try_take(&mut self, amount: f64) -> Result<(), Duration> {
+        self.refill();
+        if self.available >= amount {
+            self.available -= amount;
+            Ok(())
+        } else {
+            let missing = amount - self.available;
+            Err(Duration::from_secs_f64(missing / self.refill_per_sec))
+        }
+    }
+
+    /// Subtract usage that was only known after the fact
+    pub fn debit(&mut self, amount: f64) {
+        self.refill();
+        self.available -= amount;
+    }
+
+    pub fn available(&mut self) -> f64 {
+        self.refill();
+        self.available
+    }
+}
+
+/// Shared limiter for every provider instance created under the same provider name
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    requests: Option<Mutex<TokenBucket>>,
+    tokens: Option<Mutex<TokenBucket>>,
+    /// Number of callers waiting in `acquire`, per priority
+    waiting: [AtomicUsize; 3],
+}
+
+/// Deregisters a waiter even if `acquire` is cancelled
+struct WaitingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for WaitingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// How long a lower priority caller yields to higher priority waiters before checking again
+const PRIORITY_YIELD: Duration = Duration::from_millis(50);
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            requests: config
+                .requests_per_minute
+                .map(|limit| Mutex::new(TokenBucket::per_minute(limit))),
+            tokens: config
+                .tokens_per_minute
+                .map(|limit| Mutex::new(TokenBucket::per_minute(limit))),
+            waiting: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> RateLimitConfig {
+        self.config
+    }
+
+    /// Wait until a request may be sent.
+    ///
+    /// Token usage is unknown up front, so the token budget only has to be out of debt;
+    /// the actual usage is charged afterwards through [`RateLimiter::record_usage`].
+    /// Callers of a lower [`Priority`] hold back while higher priority callers are waiting.
+    pub async fn acquire(&self) {
+        let priority = current_priority();
+        self.waiting[priority.index()].fetch_add(1, Ordering::SeqCst);
+        let _guard = WaitingGuard(&self.waiting[priority.index()]);
+
+        loop {
+            if self.higher_priority_waiting(priority) {
+                tokio::time::sleep(PRIORITY_YIELD).await;
+                continue;
+            }
+            match self.try_acquire() {
+                Ok(()) => return,
+                Err(wait) => {
+                    tracing::debug!(
+                        "Client-side rate limit reached, waiting {:?} ({:?} priority)",
+                        wait,
+                        priority
+                    );
+                    // Wake up early enough to notice a higher priority caller taking the slot
+                    tokio::time::sleep(wait.min(PRIORITY_YIELD.max(wait / 2))).await;
+                }
+            }
+        }
+    }
+
+    fn higher_priority_waiting(&self, priority: Priority) -> bool {
+        Priority::ALL
+            .iter()
+            .filter(|p| **p > priority)
+            .any(|p| self.waiting[p.index()].load(Ordering::SeqCst) > 0)
+    }
+
+    fn try_acquire(&self) -> Result<(), Duration> {
+        if let Some(tokens) = &self.tokens {
+            let mut bucket = tokens.lock().unwrap_or_else(|e| e.into_inner());
+            let available = bucket.available();
+            if available < 1.0 {
+                return Err(Duration::from_secs_f64(
+                    (1.0 - available) / bucket.refill_per_sec,
+                ));
+            }
+        }
+        if let Some(requests) = &self.requests {
+            let mut bucket = requests.lock().unwrap_or_else(|e| e.into_inner());
+            bucket.try_take(1.0)?;
+        }
+        Ok(())
+    }
+
+    pub fn record_usage(&self, usage: &ProviderUsage) {
+        let Some(tokens) = &self.tokens else {
+            return;
+        };
+        let used = usage.usage.total_tokens.or_else(|| {
+            match (usage.usage.input_tokens, usage.usage.output_tokens) {
+                (None, None) => None,
+                (input, output) => Some(input.unwrap_or(0) + output.unwrap_or(0)),
+            }
+        });
+        if let Some(used) = used {
+            let mut bucket = tokens.lock().unwrap_or_else(|e| e.into_inner());
+            bucket.debit(used.max(0) as f64);
+        }
+    }
+}
+
+/// A provider wrapper that waits for the shared rate limiter before each request
+pub struct RateLimitedProvider {
+    inner: Arc<dyn Provider>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitedProvider {
+    pub fn new(inner: Arc<dyn Provider>, limiter: Arc<RateLimiter>) -> Self {
+        Self { inner, limiter }
+    }
+
+    pub fn limiter(&self) -> &Arc<RateLimiter> {
+        &self.limiter
+    }
+}
+
+#[async_trait]
+impl Provider for RateLimitedProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "rate_limited",
+            "Rate Limited Provider",
+            "A provider that applies client-side request and token limits",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(Self::new(
+            self.inner.with_model(model)?,
+            Arc::clone(&self.limiter),
+        )))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        self.limiter.acquire().await;
+        let (message, usage) = self.inner.complete(system, messages, tools).await?;
+        self.limiter.record_usage(&usage);
+        Ok((message, usage))
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.limiter.acquire().await;
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn get_active_model_name(&self) -> String {
+        self.inner.get_active_model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+
+    #[test]
+    fn test_token_bucket_reports_wait_time() {
+        let mut bucket = TokenBucket::per_minute(60);
+        assert!(bucket.try_take(60.0).is_ok());
+        let wait = bucket.try_take(1.0).unwrap_err();
+        assert!(wait > Duration::from_millis(900) && wait <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_request_limit_blocks_after_budget() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_minute: Some(2),
+            tokens_per_minute: None,
+        });
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_ok());
+        assert!(limiter.try_acquire().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_priority_is_scoped_to_task() {
+        assert_eq!(current_priority(), Priority::Normal);
+        let inner = with_priority(Priority::Interactive, async { current_priority() }).await;
+        assert_eq!(inner, Priority::Interactive);
+        assert_eq!(current_priority(), Priority::Normal);
+    }
+
+    #[test]
+    fn test_lower_priority_yields_to_waiting_callers() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_minute: Some(1),
+            tokens_per_minute: None,
+        });
+        limiter.waiting[Priority::Interactive.index()].fetch_add(1, Ordering::SeqCst);
+        assert!(limiter.higher_priority_waiting(Priority::Batch));
+        assert!(!limiter.higher_priority_waiting(Priority::Interactive));
+    }
+
+    #[test]
+    fn test_token_debt_blocks_until_repaid() {
+        let limiter = RateLimiter::new(RateLimitConfig {
+            requests_per_minute: None,
+            tokens_per_minute: Some(600),
+        });
+        assert!(limiter.try_acquire().is_ok());
+
+        let usage = ProviderUsage::new("mock".to_string(), Usage::new(Some(500), Some(200), None));
+        limiter.record_usage(&usage);
+
+        // 100 tokens of debt at 10 tokens/sec is roughly ten seconds of waiting
+        let wait = limiter.try_acquire().unwrap_err();
+        assert!(wait > Duration::from_secs(9));
+    }
+}
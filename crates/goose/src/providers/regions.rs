@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use url::Url;
+
+use super::egress::EgressPolicy;
+
+/// How long a failed endpoint sits out unless `GOOSE_PROVIDER_REGION_COOLDOWN` says otherwise
+const DEFAULT_COOLDOWN_SECS: u64 = 30;
+/// Weight of the newest latency sample in an endpoint's running average
+const LATENCY_WEIGHT: f64 = 0.3;
+
+#[derive(Debug, Default, Clone)]
+struct EndpointHealth {
+    latency: Option<Duration>,
+    down_until: Option<Instant>,
+}
+
+/// Regional endpoints serving the same API. A request meant for any of them goes to the
+/// healthy endpoint with the lowest latency seen so far, and moves on to the next one when an
+/// endpoint can't be reached or answers with a server error, so a regional outage costs a
+/// retry instead of the session. Failed endpoints sit out a cooldown and are tried last.
+pub struct RegionGroup {
+    name: String,
+    endpoints: Vec<Url>,
+    cooldown: Duration,
+    health: Mutex<Vec<EndpointHealth>>,
+}
+
+impl RegionGroup {
+    pub fn new(name: impl Into<String>, endpoints: Vec<Url>, cooldown: Duration) -> Self {
+        Self {
+            name: name.into(),
+            health: Mutex::new(vec![EndpointHealth::default(); endpoints.len()]),
+            endpoints,
+            cooldown,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The endpoint a URL is under, if it belongs to this group
+    fn endpoint_of(&self, url: &Url) -> Option<usize> {
+        self.endpoints.iter().position(|endpoint| {
+            endpoint.origin() == url.origin()
+                && url
+                    .path()
+                    .starts_with(endpoint.path().trim_end_matches('/'))
+        })
+    }
+
+    /// Endpoints in the order to try them: healthy ones first, fastest first, then the ones
+    /// sitting out, soonest back first. Endpoints without a measured latency yet count as
+    /// fastest so each gets measured, and ties keep the configured order.
+    fn order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let mut order: Vec<usize> = (0..self.endpoints.len()).collect();
+        order.sort_by_key(|&index| {
+            let health = &health[index];
+            let down_until = health.down_until.filter(|until| *until > now);
+            (down_until, health.latency.unwrap_or_default())
+        });
+        order
+    }
+
+    fn record_success(&self, index: usize, latency: Duration) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        let endpoint = &mut health[index];
+        endpoint.latency = Some(match endpoint.latency {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_WEIGHT) + latency.mul_f64(LATENCY_WEIGHT)
+            }
+            None => latency,
+        });
+        endpoint.down_until = None;
+    }
+
+    fn record_failure(&self, index: usize, reason: &str) {
+        tracing::warn!(
+            "Provider endpoint {} of {} failed, trying other regions for {:?}: {}",
+            self.endpoints[index],
+            self.name,
+            self.cooldown,
+            reason
+        );
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        health[index].down_until = Some(Instant::now() + self.cooldown);
+    }
+
+    /// Send a request meant for endpoint `from` to the group's endpoints in order of
+    /// preference, applying the egress policies to each attempt. Requests whose body can't be
+    /// cloned, such as streamed uploads, get a single attempt.
+    pub(crate) async fn send(
+        &self,
+        client: &reqwest::Client,
+        request: reqwest::Request,
+        from: usize,
+        policies: &[Arc<dyn EgressPolicy>],
+    ) -> reqwest::Result<reqwest::Response> {
+        let order = self.order();
+        let mut pending = Some(request);
+        for (attempt, &index) in order.iter().enumerate() {
+            let Some(request) = pending.take() else {
+                break;
+            };
+            let last = attempt + 1 == order.len();
+            let mut current = match request.try_clone() {
+                Some(copy) if !last => {
+                    pending = Some(request);
+                    copy
+                }
+                _ => request,
+            };
+            *current.url_mut() =
+                rebase(current.url(), &self.endpoints[from], &self.endpoints[index]);
+            for policy in policies {
+                policy.apply(&mut current);
+            }
+
+            let started = Instant::now();
+            match client.execute(current).await {
+                Ok(response) if response.status().is_server_error() => {
+                    self.record_failure(index, &response.status().to_string());
+                    if pending.is_none() {
+                        return Ok(response);
+                    }
+                }
+                Ok(response) => {
+                    self.record_success(index, started.elapsed());
+                    return Ok(response);
+                }
+                Err(e) => {
+                    self.record_failure(index, &e.to_string());
+                    if pending.is_none() {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        unreachable!("the last attempt always returns")
+    }
+}
+
+/// Move a URL under one endpoint to the same place under another
+fn rebase(url: &Url, from: &Url, to: &Url) -> Url {
+    let rest = &url.path()[from.path().trim_end_matches('/').len()..];
+    let mut rebased = to.clone();
+    rebased.set_path(&format!("{}{}", to.path().trim_end_matches('/'), rest));
+    rebased.set_query(url.query());
+    rebased
+}
+
+/// Build the region groups configured for this process.
+///
+/// `GOOSE_PROVIDER_REGIONS` maps a name to the base URLs of endpoints that serve the same
+/// API, such as `{"azure": ["https://acme-eastus.openai.azure.com",
+/// "https://acme-westeurope.openai.azure.com"]}`. Point the provider at any one of them.
+/// `GOOSE_PROVIDER_REGION_COOLDOWN` sets how many seconds a failed endpoint sits out.
+/// Invalid URLs are logged and skipped.
+pub fn regions_from_config() -> Vec<Arc<RegionGroup>> {
+    let config = crate::config::Config::global();
+    let groups: HashMap<String, Vec<String>> = config
+        .get_param("GOOSE_PROVIDER_REGIONS")
+        .unwrap_or_default();
+    let cooldown = Duration::from_secs(
+        config
+            .get_param("GOOSE_PROVIDER_REGION_COOLDOWN")
+            .unwrap_or(DEFAULT_COOLDOWN_SECS),
+    );
+
+    groups
+        .into_iter()
+        .filter_map(|(name, endpoints)| {
+            let endpoints: Vec<Url> = endpoints
+                .iter()
+                .filter_map(|endpoint| match Url::parse(endpoint) {
+                    Ok(url) => Some(url),
+                    Err(e) => {
+                        tracing::warn!("Ignoring invalid endpoint {} of {}: {}", endpoint, name, e);
+                        None
+                    }
+                })
+                .collect();
+            (endpoints.len() > 1).then(|| Arc::new(RegionGroup::new(name, endpoints, cooldown)))
+        })
+        .collect()
+}
+
+static REGIONS: Lazy<Vec<Arc<RegionGroup>>> = Lazy::new(regions_from_config);
+
+pub(crate) fn has_regions() -> bool {
+    !REGIONS.is_empty()
+}
+
+/// The configured group a URL belongs to, with the endpoint it is under
+pub(crate) fn region_for(url: &Url) -> Option<(Arc<RegionGroup>, usize)> {
+    REGIONS.iter().find_map(|group| {
+        group
+            .endpoint_of(url)
+            .map(|index| (Arc::clone(group), index))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_rebase_keeps_path_under_endpoint() {
+        let from = Url::parse("https://east.example.com/openai").unwrap();
+        let to = Url::parse("https://west.example.com/v2/openai/").unwrap();
+        let url =
+            Url::parse("https://east.example.com/openai/chat/completions?api-version=1").unwrap();
+        assert_eq!(
+            rebase(&url, &from, &to).as_str(),
+            "https://west.example.com/v2/openai/chat/completions?api-version=1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_and_prefers_healthy_endpoint() {
+        let east = MockServer::start().await;
+        let west = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&east)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&west)
+            .await;
+
+        let group = RegionGroup::new(
+            "test",
+            vec![
+                Url::parse(&east.uri()).unwrap(),
+                Url::parse(&west.uri()).unwrap(),
+            ],
+            Duration::from_secs(60),
+        );
+        let client = reqwest::Client::new();
+        let request = client
+            .post(format!("{}/v1/chat", east.uri()))
+            .body("{}")
+            .build()
+            .unwrap();
+        let from = group.endpoint_of(request.url()).unwrap();
+        assert_eq!(from, 0);
+
+        let response = group.send(&client, request, from, &[]).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(group.order(), vec![1, 0]);
+    }
+}
@@ -10,7 +10,10 @@ use mcp_core::Tool;
 use serde_json::{json, Value};
 use tokio::time::sleep;
 
-use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::base::{
+    ConfigKey, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage, Usage,
+};
+use super::egress::EgressInterceptor;
 use super::errors::ProviderError;
 use super::utils::emit_debug_trace;
 use crate::message::{Message, MessageContent};
@@ -74,7 +77,11 @@ impl SageMakerTgiProvider {
             .timeout_config(timeout_config)
             .build();
 
-        let sagemaker_client = SageMakerClient::new(&config_with_timeout);
+        let sagemaker_client = SageMakerClient::from_conf(
+            aws_sdk_sagemakerruntime::config::Builder::from(&config_with_timeout)
+                .interceptor(EgressInterceptor)
+                .build(),
+        );
 
         Ok(Self {
             sagemaker_client,
@@ -210,6 +217,7 @@ impl SageMakerTgiProvider {
                 text: clean_text,
                 annotations: None,
             })],
+            finish_reason: None,
         })
     }
 
@@ -287,6 +295,10 @@ impl Provider for SageMakerTgiProvider {
         self.model.clone()
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::from_model_config(&self.model).with_tools(false)
+    }
+
     #[tracing::instrument(
         skip(self, system, messages, tools),
         fields(model_config, input, output, input_tokens, output_tokens, total_tokens)
@@ -323,6 +335,7 @@ impl Provider for SageMakerTgiProvider {
                         input_tokens: Some(0),  // Would need to tokenize input to get accurate count
                         output_tokens: Some(0), // Would need to tokenize output to get accurate count
                         total_tokens: Some(0),
+                        cost_usd: None,
                     };
 
                     // Add debug trace
@@ -6,8 +6,10 @@ use serde_json::{json, Value};
 use std::time::Duration;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage};
+use super::egress::EgressExt;
 use super::errors::ProviderError;
 use super::formats::snowflake::{create_request, get_usage, response_to_message};
+use super::middleware::RequestHeadersExt;
 use super::utils::{get_model, ImageFormat};
 use crate::config::ConfigError;
 use crate::message::Message;
@@ -128,8 +130,9 @@ impl SnowflakeProvider {
             .post(url)
             .header("Authorization", auth_header)
             .header("User-Agent", "Goose")
+            .with_request_headers()
             .json(&payload)
-            .send()
+            .send_with_egress()
             .await?;
 
         let status = response.status();
@@ -30,6 +30,7 @@
 //! - `augment_message_with_tool_calls`: A utility function that takes any message, extracts text content, sends it to an interpreter, and adds any detected tool calls back to the message.
 //!
 
+use super::egress::EgressExt;
 use super::errors::ProviderError;
 use super::ollama::OLLAMA_DEFAULT_PORT;
 use super::ollama::OLLAMA_HOST;
@@ -170,7 +171,12 @@ impl OllamaInterpreter {
             serde_json::to_string_pretty(&payload).unwrap_or_default()
         );
 
-        let response = self.client.post(&url).json(&payload).send().await?;
+        let response = self
+            .client
+            .post(&url)
+            .json(&payload)
+            .send_with_egress()
+            .await?;
 
         if !response.status().is_success() {
             let status = response.status();
@@ -363,6 +369,7 @@ pub fn convert_tool_messages_to_text(messages: &[Message]) -> Vec<Message> {
                     role: message.role.clone(),
                     content: new_content,
                     created: message.created,
+                    finish_reason: message.finish_reason,
                 }
             } else {
                 message.clone()
@@ -0,0 +1,352 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+use super::pool::global_pool_manager;
+use super::usage_tracker::{UsageFilter, UsageKey, UsageTotals, UsageTracker};
+use crate::config::Config;
+use crate::outbox::{global_outbox, Outbox, WebhookSink};
+
+/// Name of the outbox sink usage reports are delivered through
+pub const USAGE_TELEMETRY_SINK: &str = "usage_telemetry";
+
+/// How often usage is exported when `GOOSE_USAGE_TELEMETRY_INTERVAL_SECS` is unset
+const DEFAULT_EXPORT_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How usage is coarsened before it leaves the process
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum UsagePrivacy {
+    /// Totals as recorded, per provider, model and session
+    #[default]
+    Exact,
+    /// Totals per provider and model only, rounded to multiples of `bucket`. With `epsilon`,
+    /// Laplace noise of scale `bucket / epsilon` is added before rounding, so a smaller epsilon
+    /// hides individual sessions better at the cost of accuracy.
+    Coarse {
+        bucket: u64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        epsilon: Option<f64>,
+    },
+}
+
+impl UsagePrivacy {
+    /// Coarse when `GOOSE_USAGE_TELEMETRY_BUCKET` is set, with noise when
+    /// `GOOSE_USAGE_TELEMETRY_EPSILON` is set too; exact otherwise
+    pub fn from_config() -> Self {
+        let config = Config::global();
+        match config.get_param::<u64>("GOOSE_USAGE_TELEMETRY_BUCKET") {
+            Ok(bucket) => Self::Coarse {
+                bucket: bucket.max(1),
+                epsilon: config
+                    .get_param::<f64>("GOOSE_USAGE_TELEMETRY_EPSILON")
+                    .ok()
+                    .filter(|epsilon| *epsilon > 0.0),
+            },
+            Err(_) => Self::Exact,
+        }
+    }
+}
+
+/// Usage of one provider and model, and of one session unless coarsened
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRow {
+    pub provider: String,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session: Option<String>,
+    #[serde(flatten)]
+    pub totals: UsageTotals,
+}
+
+/// What is handed to a telemetry sink: counts only, never prompts or responses
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub generated_at: DateTime<Utc>,
+    pub privacy: UsagePrivacy,
+    pub rows: Vec<UsageRow>,
+}
+
+/// Where usage analytics are exported to, e.g. a team's central metrics store
+#[async_trait]
+pub trait UsageTelemetry: Send + Sync {
+    /// How usage is coarsened before it reaches [`Self::export`]
+    fn privacy(&self) -> UsagePrivacy {
+        UsagePrivacy::from_config()
+    }
+
+    async fn export(&self, report: UsageReport) -> Result<()>;
+}
+
+/// Queues reports in an outbox, which delivers them to its [`USAGE_TELEMETRY_SINK`] and
+/// retries until they are accepted
+pub struct OutboxUsageTelemetry {
+    outbox: Arc<Outbox>,
+}
+
+impl OutboxUsageTelemetry {
+    pub fn new(outbox: Arc<Outbox>) -> Self {
+        Self { outbox }
+    }
+}
+
+#[async_trait]
+impl UsageTelemetry for OutboxUsageTelemetry {
+    async fn export(&self, report: UsageReport) -> Result<()> {
+        self.outbox
+            .enqueue(
+                USAGE_TELEMETRY_SINK,
+                "usage.report",
+                serde_json::to_value(report)?,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Export what the tracker recorded since the totals in `exported`, coarsened as the sink
+/// asks, and move `exported` up to the current totals. Nothing is exported when nothing new
+/// was recorded.
+pub async fn export_usage(
+    tracker: &UsageTracker,
+    telemetry: &dyn UsageTelemetry,
+    exported: &mut HashMap<UsageKey, UsageTotals>,
+) -> Result<()> {
+    let current = tracker.breakdown(&UsageFilter::default());
+    let delta: Vec<_> = current
+        .iter()
+        .filter_map(|(key, totals)| {
+            let since = match exported.get(key) {
+                // The tracker was reset since, so everything it holds is new
+                Some(previous) if previous.requests <= totals.requests => *previous,
+                _ => UsageTotals::default(),
+            };
+            let delta = UsageTotals {
+                requests: totals.requests - since.requests,
+                input_tokens: totals.input_tokens.saturating_sub(since.input_tokens),
+                output_tokens: totals.output_tokens.saturating_sub(since.output_tokens),
+                total_tokens: totals.total_tokens.saturating_sub(since.total_tokens),
+            };
+            (delta.requests > 0).then(|| (key.clone(), delta))
+        })
+        .collect();
+    if delta.is_empty() {
+        return Ok(());
+    }
+
+    let report = prepare_report(delta, telemetry.privacy(), &mut rand::thread_rng());
+    telemetry.export(report).await?;
+    *exported = current.into_iter().collect();
+    Ok(())
+}
+
+/// Export the usage of [`global_pool_manager`] to `GOOSE_USAGE_TELEMETRY_URL` every
+/// `GOOSE_USAGE_TELEMETRY_INTERVAL_SECS`, hourly by default, if the URL is set. Each report
+/// covers the usage since the one before and goes through the outbox, so it isn't lost when
+/// the endpoint is briefly down.
+pub fn spawn_usage_export() -> Option<JoinHandle<()>> {
+    let config = Config::global();
+    let url = config
+        .get_param::<String>("GOOSE_USAGE_TELEMETRY_URL")
+        .ok()?;
+    let interval = config
+        .get_param::<u64>("GOOSE_USAGE_TELEMETRY_INTERVAL_SECS")
+        .map(|secs| Duration::from_secs(secs.max(1)))
+        .unwrap_or(DEFAULT_EXPORT_INTERVAL);
+
+    let outbox = Arc::clone(global_outbox());
+    outbox.register_sink(USAGE_TELEMETRY_SINK, Arc::new(WebhookSink::new(url)));
+    outbox.ensure_dispatcher();
+    let telemetry = OutboxUsageTelemetry::new(outbox);
+    Some(tokio::spawn(async move {
+        let mut exported = HashMap::new();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) =
+                export_usage(global_pool_manager().usage(), &telemetry, &mut exported).await
+            {
+                tracing::warn!("Failed to export usage telemetry: {}", e);
+            }
+        }
+    }))
+}
+
+/// Turn per provider, model and session totals into a report. Coarse reports merge sessions,
+/// add noise, round to buckets and leave out providers and models that round to nothing.
+pub fn prepare_report(
+    breakdown: Vec<(UsageKey, UsageTotals)>,
+    privacy: UsagePrivacy,
+    rng: &mut impl Rng,
+) -> UsageReport {
+    let rows = match &privacy {
+        UsagePrivacy::Exact => breakdown
+            .into_iter()
+            .map(|(key, totals)| UsageRow {
+                provider: key.provider,
+                model: key.model,
+                session: key.session,
+                totals,
+            })
+            .collect(),
+        UsagePrivacy::Coarse { bucket, epsilon } => {
+            let mut merged: BTreeMap<(String, String), UsageTotals> = BTreeMap::new();
+            for (key, totals) in breakdown {
+                let sum = merged.entry((key.provider, key.model)).or_default();
+                sum.requests += totals.requests;
+                sum.input_tokens += totals.input_tokens;
+                sum.output_tokens += totals.output_tokens;
+                sum.total_tokens += totals.total_tokens;
+            }
+            let bucket = (*bucket).max(1);
+            let mut coarsen = |value: u64| {
+                let noise = epsilon.map_or(0.0, |epsilon| laplace(rng, bucket as f64 / epsilon));
+                let buckets = ((value as f64 + noise) / bucket as f64).round().max(0.0);
+                buckets as u64 * bucket
+            };
+            merged
+                .into_iter()
+                .map(|((provider, model), totals)| UsageRow {
+                    provider,
+                    model,
+                    session: None,
+                    totals: UsageTotals {
+                        requests: coarsen(totals.requests),
+                        input_tokens: coarsen(totals.input_tokens),
+                        output_tokens: coarsen(totals.output_tokens),
+                        total_tokens: coarsen(totals.total_tokens),
+                    },
+                })
+                .filter(|row| row.totals.requests > 0)
+                .collect()
+        }
+    };
+    UsageReport {
+        generated_at: Utc::now(),
+        privacy,
+        rows,
+    }
+}
+
+/// A sample of the Laplace distribution centered on zero
+fn laplace(rng: &mut impl Rng, scale: f64) -> f64 {
+    let u: f64 = rng.gen_range(-0.5..0.5);
+    -scale * u.signum() * (1.0 - 2.0 * u.abs()).max(f64::MIN_POSITIVE).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::base::Usage;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use std::sync::Mutex;
+
+    /// Keeps what it is handed
+    #[derive(Default)]
+    struct RecordingTelemetry {
+        reports: Mutex<Vec<UsageReport>>,
+    }
+
+    #[async_trait]
+    impl UsageTelemetry for RecordingTelemetry {
+        fn privacy(&self) -> UsagePrivacy {
+            UsagePrivacy::Exact
+        }
+
+        async fn export(&self, report: UsageReport) -> Result<()> {
+            self.reports.lock().unwrap().push(report);
+            Ok(())
+        }
+    }
+
+    fn row(provider: &str, session: &str, requests: u64, tokens: u64) -> (UsageKey, UsageTotals) {
+        (
+            UsageKey {
+                provider: provider.to_string(),
+                model: "model".to_string(),
+                session: Some(session.to_string()),
+            },
+            UsageTotals {
+                requests,
+                input_tokens: tokens,
+                output_tokens: 0,
+                total_tokens: tokens,
+            },
+        )
+    }
+
+    #[test]
+    fn test_coarse_report_merges_sessions_and_buckets() {
+        let breakdown = vec![
+            row("openai", "s1", 7, 1_240),
+            row("openai", "s2", 8, 2_130),
+            row("anthropic", "s1", 3, 400),
+        ];
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let exact = prepare_report(breakdown.clone(), UsagePrivacy::Exact, &mut rng);
+        assert_eq!(exact.rows.len(), 3);
+
+        let privacy = UsagePrivacy::Coarse {
+            bucket: 10,
+            epsilon: None,
+        };
+        let coarse = prepare_report(breakdown.clone(), privacy, &mut rng);
+        assert_eq!(coarse.rows.len(), 1);
+        let openai = &coarse.rows[0];
+        assert_eq!(
+            (openai.provider.as_str(), &openai.session),
+            ("openai", &None)
+        );
+        assert_eq!(openai.totals.requests, 20);
+        assert_eq!(openai.totals.input_tokens, 3_370);
+
+        let privacy = UsagePrivacy::Coarse {
+            bucket: 100,
+            epsilon: Some(1.0),
+        };
+        let noisy = prepare_report(breakdown, privacy, &mut rng);
+        for row in noisy.rows {
+            assert!(row.session.is_none());
+            assert_eq!(row.totals.total_tokens % 100, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_covers_the_usage_since_the_last_export() {
+        let tracker = UsageTracker::default();
+        let telemetry = RecordingTelemetry::default();
+        let mut exported = HashMap::new();
+        let key = row("openai", "s1", 0, 0).0;
+        let usage = Usage::new(Some(100), Some(20), Some(120));
+
+        tracker.record(key.clone(), &usage);
+        tracker.record(key.clone(), &usage);
+        export_usage(&tracker, &telemetry, &mut exported)
+            .await
+            .unwrap();
+        // Nothing new, nothing sent
+        export_usage(&tracker, &telemetry, &mut exported)
+            .await
+            .unwrap();
+        tracker.record(key.clone(), &usage);
+        export_usage(&tracker, &telemetry, &mut exported)
+            .await
+            .unwrap();
+
+        let reports = telemetry.reports.lock().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].rows[0].totals.requests, 2);
+        assert_eq!(reports[0].rows[0].totals.total_tokens, 240);
+        assert_eq!(reports[1].rows[0].totals.requests, 1);
+        assert_eq!(reports[1].rows[0].totals.input_tokens, 100);
+    }
+}
@@ -0,0 +1,568 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use mcp_core::tool::Tool;
+use serde::{Deserialize, Serialize};
+
+use super::base::{
+    LeadWorkerProviderTrait, Provider, ProviderCapabilities, ProviderMetadata, ProviderUsage, Usage,
+};
+use super::errors::ProviderError;
+use super::pricing::estimate_cost_usd;
+use crate::message::Message;
+use crate::model::ModelConfig;
+
+/// How long individual requests are kept for rolling window queries
+pub const DEFAULT_USAGE_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+tokio::task_local! {
+    static USAGE_SESSION: String;
+}
+
+/// Run `future` with every provider request it makes counted towards `session_id`
+pub async fn with_usage_session<F: Future>(session_id: impl Into<String>, future: F) -> F::Output {
+    USAGE_SESSION.scope(session_id.into(), future).await
+}
+
+/// The session provider requests of the current task are counted towards, if any
+pub fn current_usage_session() -> Option<String> {
+    USAGE_SESSION.try_with(|session| session.clone()).ok()
+}
+
+/// What usage is grouped by
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct UsageKey {
+    pub provider: String,
+    pub model: String,
+    /// The session or subagent the requests were made for, if known
+    pub session: Option<String>,
+}
+
+/// Token counts summed over a set of requests
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub total_tokens: u64,
+}
+
+impl UsageTotals {
+    fn from_usage(usage: &Usage) -> Self {
+        let input_tokens = usage.input_tokens.unwrap_or(0).max(0) as u64;
+        let output_tokens = usage.output_tokens.unwrap_or(0).max(0) as u64;
+        Self {
+            requests: 1,
+            input_tokens,
+            output_tokens,
+            total_tokens: usage
+                .total_tokens
+                .map(|total| total.max(0) as u64)
+                .unwrap_or(input_tokens + output_tokens),
+        }
+    }
+
+    fn merge(&mut self, other: &UsageTotals) {
+        self.requests += other.requests;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+/// Selects the usage a query covers; unset fields match everything
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UsageFilter {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub session: Option<String>,
+}
+
+impl UsageFilter {
+    pub fn provider(mut self, provider: impl Into<String>) -> Self {
+        self.provider = Some(provider.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn session(mut self, session: impl Into<String>) -> Self {
+        self.session = Some(session.into());
+        self
+    }
+
+    fn matches(&self, key: &UsageKey) -> bool {
+        field_matches(&self.provider, &key.provider)
+            && field_matches(&self.model, &key.model)
+            && match &self.session {
+                None => true,
+                Some(session) => key.session.as_ref() == Some(session),
+            }
+    }
+}
+
+fn field_matches(expected: &Option<String>, value: &str) -> bool {
+    match expected {
+        None => true,
+        Some(expected) => expected == value,
+    }
+}
+
+/// Usage and spend of one session or subagent, including what its tool calls cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpendSummary {
+    pub cost_usd: f64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub requests: u64,
+    /// Requests whose model had no known price and are missing from `cost_usd`
+    pub unpriced_requests: u64,
+    /// Estimated spend on tool calls with a configured cost, see `GOOSE_TOOL_COSTS`
+    #[serde(default)]
+    pub tool_cost_usd: f64,
+}
+
+impl SpendSummary {
+    fn add(&mut self, usage: &Usage) {
+        self.input_tokens += usage.input_tokens.unwrap_or(0) as i64;
+        self.output_tokens += usage.output_tokens.unwrap_or(0) as i64;
+        self.requests += 1;
+        match usage.cost_usd {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+
+    /// Model and tool spend combined
+    pub fn total_cost_usd(&self) -> f64 {
+        self.cost_usd + self.tool_cost_usd
+    }
+}
+
+struct UsageEvent {
+    at: Instant,
+    key: UsageKey,
+    totals: UsageTotals,
+}
+
+/// Token usage of every provider created through the factory, per provider, model and
+/// session, so budgets can be enforced across all agents in the process. Spend is kept per
+/// session and subagent too, in the same place. What a session or subagent recorded is
+/// dropped once it has recorded nothing for the retention period.
+pub struct UsageTracker {
+    retention: Duration,
+    totals: Mutex<HashMap<UsageKey, UsageTotals>>,
+    /// Spend per session or subagent, see [`with_usage_session`]
+    spend: Mutex<HashMap<String, SpendSummary>>,
+    /// When each session or subagent last recorded anything
+    last_seen: Mutex<HashMap<String, Instant>>,
+    /// Individual requests within the retention period, oldest first
+    events: Mutex<VecDeque<UsageEvent>>,
+}
+
+impl Default for UsageTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_USAGE_RETENTION)
+    }
+}
+
+impl UsageTracker {
+    /// A tracker that answers rolling window queries up to `retention` long
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            retention,
+            totals: Mutex::new(HashMap::new()),
+            spend: Mutex::new(HashMap::new()),
+            last_seen: Mutex::new(HashMap::new()),
+            events: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn record(&self, key: UsageKey, usage: &Usage) {
+        self.record_at(key, usage, Instant::now());
+    }
+
+    fn record_at(&self, key: UsageKey, usage: &Usage, at: Instant) {
+        let totals = UsageTotals::from_usage(usage);
+        if let Some(session) = &key.session {
+            self.touch(session, at);
+            self.spend
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry(session.clone())
+                .or_default()
+                .add(usage);
+        }
+        self.totals
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.clone())
+            .or_default()
+            .merge(&totals);
+
+        let mut events = self.events.lock().unwrap_or_else(|e| e.into_inner());
+        events.push_back(UsageEvent { at, key, totals });
+        while events
+            .front()
+            .is_some_and(|event| at.saturating_duration_since(event.at) > self.retention)
+        {
+            events.pop_front();
+        }
+    }
+
+    /// Note that a session was active at `at`, and drop the sessions that have been idle for
+    /// longer than the retention period
+    fn touch(&self, session: &str, at: Instant) {
+        let mut last_seen = self.last_seen.lock().unwrap_or_else(|e| e.into_inner());
+        last_seen.insert(session.to_string(), at);
+        let expired: Vec<String> = last_seen
+            .iter()
+            .filter(|(_, seen)| at.saturating_duration_since(**seen) > self.retention)
+            .map(|(session, _)| session.clone())
+            .collect();
+        if expired.is_empty() {
+            return;
+        }
+        for session in &expired {
+            last_seen.remove(session);
+        }
+        drop(last_seen);
+
+        let mut spend = self.spend.lock().unwrap_or_else(|e| e.into_inner());
+        for session in &expired {
+            spend.remove(session);
+        }
+        drop(spend);
+        self.totals
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|key, _| !key.session.as_ref().is_some_and(|s| expired.contains(s)));
+    }
+
+    /// Drop the spend of a session or subagent that has ended. Its token totals stay until
+    /// they age out, so usage exports still see them.
+    pub fn forget_session(&self, session: &str) {
+        self.spend
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(session);
+    }
+
+    /// Everything recorded by sessions active within the retention period, and by requests
+    /// made outside of any session, that matches the filter
+    pub fn totals(&self, filter: &UsageFilter) -> UsageTotals {
+        let mut sum = UsageTotals::default();
+        for (key, totals) in self.totals.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            if filter.matches(key) {
+                sum.merge(totals);
+            }
+        }
+        sum
+    }
+
+    /// Totals per provider, model and session for everything matching the filter, sorted
+    pub fn breakdown(&self, filter: &UsageFilter) -> Vec<(UsageKey, UsageTotals)> {
+        let mut breakdown: Vec<_> = self
+            .totals
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(key, _)| filter.matches(key))
+            .map(|(key, totals)| (key.clone(), *totals))
+            .collect();
+        breakdown.sort_by(|(a, _), (b, _)| a.cmp(b));
+        breakdown
+    }
+
+    /// Usage matching the filter over the last `window`, which is capped at the retention
+    pub fn in_window(&self, filter: &UsageFilter, window: Duration) -> UsageTotals {
+        self.in_window_at(filter, window, Instant::now())
+    }
+
+    fn in_window_at(&self, filter: &UsageFilter, window: Duration, now: Instant) -> UsageTotals {
+        let window = window.min(self.retention);
+        let mut sum = UsageTotals::default();
+        for event in self
+            .events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .rev()
+        {
+            if now.saturating_duration_since(event.at) > window {
+                break;
+            }
+            if filter.matches(&event.key) {
+                sum.merge(&event.totals);
+            }
+        }
+        sum
+    }
+
+    /// Usage and spend of a session or subagent
+    pub fn spend(&self, session: &str) -> SpendSummary {
+        self.spend
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(session)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Add the estimated cost of a tool call to a session's spend
+    pub fn record_tool_cost(&self, session: &str, cost_usd: f64) {
+        self.touch(session, Instant::now());
+        self.spend
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(session.to_string())
+            .or_default()
+            .tool_cost_usd += cost_usd;
+    }
+
+    pub fn reset(&self) {
+        self.totals
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.spend.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.last_seen
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+        self.events
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+/// Records the usage of every completion in a [`UsageTracker`], priced from the pricing table
+/// where the provider didn't report a cost
+pub struct UsageTrackedProvider {
+    inner: Arc<dyn Provider>,
+    tracker: Arc<UsageTracker>,
+    provider_name: String,
+}
+
+impl UsageTrackedProvider {
+    pub fn new(
+        inner: Arc<dyn Provider>,
+        tracker: Arc<UsageTracker>,
+        provider_name: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner,
+            tracker,
+            provider_name: provider_name.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for UsageTrackedProvider {
+    fn metadata() -> ProviderMetadata {
+        // This is a wrapper provider, so we return minimal metadata
+        ProviderMetadata::new(
+            "usage_tracked",
+            "Usage Tracked Provider",
+            "A provider whose token usage is tracked by the pool",
+            "",
+            vec![],
+            "",
+            vec![],
+        )
+    }
+
+    fn get_model_config(&self) -> ModelConfig {
+        self.inner.get_model_config()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn with_model(&self, model: ModelConfig) -> Result<Arc<dyn Provider>, ProviderError> {
+        Ok(Arc::new(Self::new(
+            self.inner.with_model(model)?,
+            Arc::clone(&self.tracker),
+            self.provider_name.clone(),
+        )))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> Result<(Message, ProviderUsage), ProviderError> {
+        let mut result = self.inner.complete(system, messages, tools).await;
+        if let Ok((_, usage)) = &mut result {
+            if usage.usage.cost_usd.is_none() {
+                usage.usage.cost_usd = estimate_cost_usd(
+                    Some(self.provider_name.as_str()),
+                    &usage.model,
+                    &usage.usage,
+                );
+            }
+            self.tracker.record(
+                UsageKey {
+                    provider: self.provider_name.clone(),
+                    model: usage.model.clone(),
+                    session: current_usage_session(),
+                },
+                &usage.usage,
+            );
+        }
+        result
+    }
+
+    async fn fetch_supported_models_async(&self) -> Result<Option<Vec<String>>, ProviderError> {
+        self.inner.fetch_supported_models_async().await
+    }
+
+    fn supports_embeddings(&self) -> bool {
+        self.inner.supports_embeddings()
+    }
+
+    async fn create_embeddings(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>, ProviderError> {
+        self.inner.create_embeddings(texts).await
+    }
+
+    fn as_lead_worker(&self) -> Option<&dyn LeadWorkerProviderTrait> {
+        self.inner.as_lead_worker()
+    }
+
+    fn get_active_model_name(&self) -> String {
+        self.inner.get_active_model_name()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::providers::mock::MockProvider;
+
+    fn usage(input: i32, output: i32) -> Usage {
+        Usage::new(Some(input), Some(output), Some(input + output))
+    }
+
+    fn key(provider: &str, model: &str, session: Option<&str>) -> UsageKey {
+        UsageKey {
+            provider: provider.to_string(),
+            model: model.to_string(),
+            session: session.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_totals_and_rolling_window() {
+        let tracker = UsageTracker::new(Duration::from_secs(3600));
+        let start = Instant::now();
+        tracker.record_at(key("openai", "gpt-4o", Some("s1")), &usage(100, 10), start);
+        tracker.record_at(
+            key("openai", "gpt-4o-mini", Some("s2")),
+            &usage(50, 5),
+            start + Duration::from_secs(600),
+        );
+        tracker.record_at(
+            key("anthropic", "claude", Some("s1")),
+            &usage(20, 2),
+            start + Duration::from_secs(1200),
+        );
+
+        let all = tracker.totals(&UsageFilter::default());
+        assert_eq!((all.requests, all.total_tokens), (3, 187));
+        let openai = tracker.totals(&UsageFilter::default().provider("openai"));
+        assert_eq!(openai.input_tokens, 150);
+        let session = tracker.totals(&UsageFilter::default().session("s1"));
+        assert_eq!(session.output_tokens, 12);
+        assert_eq!(tracker.breakdown(&UsageFilter::default()).len(), 3);
+
+        let now = start + Duration::from_secs(1500);
+        let recent = tracker.in_window_at(&UsageFilter::default(), Duration::from_secs(1000), now);
+        assert_eq!(recent.requests, 2);
+        let recent_openai = tracker.in_window_at(
+            &UsageFilter::default().provider("openai"),
+            Duration::from_secs(1000),
+            now,
+        );
+        assert_eq!(recent_openai.total_tokens, 55);
+    }
+
+    #[tokio::test]
+    async fn test_tracked_provider_records_session() {
+        let tracker = Arc::new(UsageTracker::default());
+        let provider = UsageTrackedProvider::new(
+            Arc::new(MockProvider::default().with_text("hi")),
+            tracker.clone(),
+            "mock",
+        );
+        with_usage_session("session-1", provider.complete("", &[], &[]))
+            .await
+            .unwrap();
+
+        let totals = tracker.totals(&UsageFilter::default().provider("mock").session("session-1"));
+        assert_eq!(totals.requests, 1);
+    }
+
+    #[test]
+    fn test_spend_per_session_and_subagent() {
+        let tracker = UsageTracker::default();
+        let priced = usage(100, 50).with_cost_usd(Some(0.25));
+
+        tracker.record(key("openai", "gpt-4o", Some("session-1")), &priced);
+        tracker.record(key("mock", "mock", Some("session-1")), &usage(10, 5));
+        tracker.record(key("openai", "gpt-4o", Some("subagent-1")), &priced);
+        tracker.record(key("openai", "gpt-4o", None), &priced);
+        tracker.record_tool_cost("session-1", 0.5);
+
+        let session = tracker.spend("session-1");
+        assert_eq!(session.requests, 2);
+        assert_eq!(session.unpriced_requests, 1);
+        assert_eq!(session.input_tokens, 110);
+        assert!((session.cost_usd - 0.25).abs() < 1e-9);
+        assert!((session.total_cost_usd() - 0.75).abs() < 1e-9);
+
+        assert_eq!(tracker.spend("subagent-1").requests, 1);
+        assert_eq!(tracker.spend("missing"), SpendSummary::default());
+        // Tokens without a session still count towards the totals
+        assert_eq!(tracker.totals(&UsageFilter::default()).requests, 4);
+    }
+
+    #[test]
+    fn test_ended_sessions_are_dropped() {
+        let tracker = UsageTracker::new(Duration::from_secs(3600));
+        let start = Instant::now();
+        tracker.record_at(key("openai", "gpt-4o", Some("s1")), &usage(100, 10), start);
+        tracker.record_at(
+            key("openai", "gpt-4o", Some("subagent-1")),
+            &usage(5, 5),
+            start,
+        );
+
+        // A subagent's spend goes when it is terminated
+        tracker.forget_session("subagent-1");
+        assert_eq!(tracker.spend("subagent-1"), SpendSummary::default());
+
+        // A session that stopped recording ages out with the next request after retention
+        tracker.record_at(
+            key("openai", "gpt-4o", Some("s2")),
+            &usage(1, 1),
+            start + Duration::from_secs(3601),
+        );
+        assert_eq!(tracker.spend("s1"), SpendSummary::default());
+        assert_eq!(
+            tracker.totals(&UsageFilter::default().session("s1")),
+            UsageTotals::default()
+        );
+        assert_eq!(tracker.spend("s2").requests, 1);
+        assert_eq!(tracker.breakdown(&UsageFilter::default()).len(), 1);
+    }
+}
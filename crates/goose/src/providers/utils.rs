@@ -70,6 +70,9 @@ pub async fn handle_response_openai_compat(response: Response) -> Result<Value,
                 if err.is_context_length_exceeded() {
                     return Err(ProviderError::ContextLengthExceeded(err.message.unwrap_or("Unknown error".to_string())));
                 }
+                if err.is_content_filtered() {
+                    return Err(ProviderError::ContentFiltered(err.message.unwrap_or("Unknown error".to_string())));
+                }
                 return Err(ProviderError::RequestFailed(format!("{} (status {})", err, status.as_u16())));
             }
             Err(ProviderError::RequestFailed(format!("Unknown error (status {})", status)))
@@ -7,7 +7,9 @@ use serde_json::{json, Value};
 use std::time::Duration;
 
 use super::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
+use super::egress::EgressExt;
 use super::errors::ProviderError;
+use super::middleware::RequestHeadersExt;
 use crate::message::{Message, MessageContent};
 use crate::model::ModelConfig;
 use mcp_core::{tool::Tool, Role, ToolCall, ToolResult};
@@ -141,8 +143,9 @@ impl VeniceProvider {
         let response = method
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
+            .with_request_headers()
             .body(body.to_string())
-            .send()
+            .send_with_egress()
             .await?;
 
         let status = response.status();
@@ -261,7 +264,7 @@ impl Provider for VeniceProvider {
             .client
             .get(models_url)
             .header("Authorization", format!("Bearer {}", self.api_key))
-            .send()
+            .send_with_egress()
             .await?;
         if !response.status().is_success() {
             return Err(ProviderError::RequestFailed(format!(
@@ -554,6 +557,7 @@ impl Provider for VeniceProvider {
             input_tokens: usage_data["prompt_tokens"].as_i64().map(|v| v as i32),
             output_tokens: usage_data["completion_tokens"].as_i64().map(|v| v as i32),
             total_tokens: usage_data["total_tokens"].as_i64().map(|v| v as i32),
+            cost_usd: None,
         };
 
         Ok((
@@ -561,6 +565,7 @@ impl Provider for VeniceProvider {
                 role: Role::Assistant,
                 created: Utc::now().timestamp(),
                 content,
+                finish_reason: None,
             },
             ProviderUsage::new(strip_flags(&self.model.model_name).to_string(), usage),
         ))
@@ -1,4 +1,6 @@
+use super::egress::EgressExt;
 use super::errors::ProviderError;
+use super::middleware::RequestHeadersExt;
 use crate::message::Message;
 use crate::model::ModelConfig;
 use crate::providers::base::{ConfigKey, Provider, ProviderMetadata, ProviderUsage, Usage};
@@ -92,8 +94,9 @@ impl XaiProvider {
             .client
             .post(url)
             .header("Authorization", format!("Bearer {}", self.api_key))
+            .with_request_headers()
             .json(&payload)
-            .send()
+            .send_with_egress()
             .await?;
 
         let status = response.status();
@@ -0,0 +1,152 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use etcetera::{choose_app_strategy, AppStrategy};
+use serde::{Deserialize, Serialize};
+
+use super::Recipe;
+
+/// A structured recipe result stored on disk for reuse by identical runs
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CachedRecipeResult {
+    pub recipe_title: String,
+    pub recipe_version: String,
+    pub created_at: DateTime<Utc>,
+    pub output: String,
+}
+
+/// Opt-in cache of recipe results keyed by recipe version and parameters.
+///
+/// Entries are plain JSON files so a stale or corrupt entry is simply treated as a miss.
+#[derive(Debug, Clone)]
+pub struct RecipeResultCache {
+    cache_dir: PathBuf,
+}
+
+impl RecipeResultCache {
+    pub fn new() -> Self {
+        let cache_dir = choose_app_strategy(crate::config::APP_STRATEGY.clone())
+            .expect("goose requires a home dir")
+            .in_data_dir("recipe_cache");
+        Self { cache_dir }
+    }
+
+    pub fn with_dir(cache_dir: PathBuf) -> Self {
+        Self { cache_dir }
+    }
+
+    /// Build the cache key for a recipe run. Parameter order does not matter.
+    pub fn cache_key(recipe: &Recipe, params: &[(String, String)]) -> String {
+        let mut params = params.to_vec();
+        params.sort();
+
+        let mut hasher = blake3::Hasher::new();
+        hasher.update(recipe.title.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(recipe.version.as_bytes());
+        hasher.update(&[0]);
+        for (key, value) in &params {
+            hasher.update(key.as_bytes());
+            hasher.update(&[0]);
+            hasher.update(value.as_bytes());
+            hasher.update(&[0]);
+        }
+        hasher.finalize().to_hex().to_string()
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.cache_dir.join(format!("{}.json", key))
+    }
+
+    /// Return the stored result for `key` if it was written less than `ttl` ago
+    pub async fn get(&self, key: &str, ttl: Duration) -> Option<CachedRecipeResult> {
+        let contents = tokio::fs::read_to_string(self.entry_path(key)).await.ok()?;
+        let entry: CachedRecipeResult = serde_json::from_str(&contents).ok()?;
+        let age = Utc::now().signed_duration_since(entry.created_at);
+        let ttl = chrono::Duration::from_std(ttl).ok()?;
+        if age < chrono::Duration::zero() || age >= ttl {
+            return None;
+        }
+        Some(entry)
+    }
+
+    pub async fn put(&self, key: &str, recipe: &Recipe, output: String) -> Result<()> {
+        tokio::fs::create_dir_all(&self.cache_dir).await?;
+        let entry = CachedRecipeResult {
+            recipe_title: recipe.title.clone(),
+            recipe_version: recipe.version.clone(),
+            created_at: Utc::now(),
+            output,
+        };
+        tokio::fs::write(self.entry_path(key), serde_json::to_string(&entry)?).await?;
+        Ok(())
+    }
+}
+
+impl Default for RecipeResultCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn recipe(version: &str) -> Recipe {
+        Recipe::builder()
+            .title("Research")
+            .description("Expensive research recipe")
+            .instructions("Do research")
+            .version(version)
+            .build()
+            .unwrap()
+    }
+
+    fn params(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_cache_key_ignores_param_order() {
+        let recipe = recipe("1.0.0");
+        let a = RecipeResultCache::cache_key(&recipe, &params(&[("a", "1"), ("b", "2")]));
+        let b = RecipeResultCache::cache_key(&recipe, &params(&[("b", "2"), ("a", "1")]));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_cache_key_changes_with_version_and_params() {
+        let base = RecipeResultCache::cache_key(&recipe("1.0.0"), &params(&[("a", "1")]));
+        let bumped = RecipeResultCache::cache_key(&recipe("1.1.0"), &params(&[("a", "1")]));
+        let other = RecipeResultCache::cache_key(&recipe("1.0.0"), &params(&[("a", "2")]));
+        assert_ne!(base, bumped);
+        assert_ne!(base, other);
+    }
+
+    #[tokio::test]
+    async fn test_get_respects_ttl() {
+        let dir = TempDir::new().unwrap();
+        let cache = RecipeResultCache::with_dir(dir.path().to_path_buf());
+        let recipe = recipe("1.0.0");
+        let key = RecipeResultCache::cache_key(&recipe, &[]);
+
+        assert!(cache.get(&key, Duration::from_secs(60)).await.is_none());
+
+        cache
+            .put(&key, &recipe, r#"{"answer":42}"#.to_string())
+            .await
+            .unwrap();
+        let hit = cache.get(&key, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(hit.output, r#"{"answer":42}"#);
+        assert_eq!(hit.recipe_version, "1.0.0");
+
+        assert!(cache.get(&key, Duration::ZERO).await.is_none());
+    }
+}
@@ -1,9 +1,15 @@
+pub mod cache;
+pub mod report;
+pub mod requirements;
+
 use anyhow::Result;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::fmt;
 
 use crate::agents::extension::ExtensionConfig;
+use crate::recipe::report::ReportTemplate;
+use crate::recipe::requirements::Requirements;
 use serde::de::Deserializer;
 use serde::{Deserialize, Serialize};
 
@@ -30,6 +36,13 @@ fn default_version() -> String {
 /// * `author` - Information about the Recipe's creator and metadata
 /// * `parameters` - Additional parameters for the Recipe
 /// * `response` - Response configuration including JSON schema validation
+/// * `requires` - Capabilities the provider and extensions must offer, checked before spawning
+/// * `report` - Template a subagent's final output is rendered into as a report artifact
+/// * `knowledge` - Knowledge sources the Recipe searches, by name; all of them when unset
+/// * `require_citations` - Whether claims in the final answer must cite the tool results they use
+/// * `clarifications` - Whether the agent must state its assumptions and may ask clarifying questions;
+///   applies to sessions started with the recipe, not to subagents running it
+/// * `confidence` - Success criteria the final answer is scored against, and what to do when it scores low
 ///
 /// # Example
 ///
@@ -59,6 +72,12 @@ fn default_version() -> String {
 ///     parameters: None,
 ///     response: None,
 ///     sub_recipes: None,
+///     requires: None,
+///     report: None,
+///     knowledge: None,
+///     require_citations: None,
+///     clarifications: None,
+///     confidence: None,
 /// };
 ///
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -102,6 +121,24 @@ pub struct Recipe {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub sub_recipes: Option<Vec<SubRecipe>>, // sub-recipes for the recipe
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires: Option<Requirements>, // capabilities the recipe needs to run
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<ReportTemplate>, // report rendered from the final output
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub knowledge: Option<Vec<String>>, // knowledge sources to search
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub require_citations: Option<bool>, // whether the final answer must cite its sources
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clarifications: Option<Clarifications>, // assumption and question channels, top-level sessions only
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<ConfidenceCheck>, // self-assessment of the final answer
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -115,10 +152,10 @@ pub struct Author {
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Settings {
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "provider", skip_serializing_if = "Option::is_none")]
     pub goose_provider: Option<String>,
 
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "model", skip_serializing_if = "Option::is_none")]
     pub goose_model: Option<String>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -131,6 +168,35 @@ pub struct Response {
     pub json_schema: Option<serde_json::Value>,
 }
 
+/// How the agent surfaces what it is unsure about. Assumptions and questions come back as
+/// their own message content instead of free text, and questions are answered through the
+/// agent rather than a new prompt.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Clarifications {
+    /// The final answer must list the assumptions it was built on
+    #[serde(default)]
+    pub require_assumptions: bool,
+    /// The agent may stop to ask clarifying questions
+    #[serde(default)]
+    pub allow_questions: bool,
+}
+
+/// A self-assessment run once the final answer is in: the model scores its confidence that
+/// the answer meets the success criteria, and a score under the threshold can hand the answer
+/// to a verification recipe.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ConfidenceCheck {
+    /// What a successful answer achieves; the recipe's instructions are used when empty
+    #[serde(default)]
+    pub criteria: Vec<String>,
+    /// Scores below this, between 0 and 1, count as low confidence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threshold: Option<f64>,
+    /// Recipe that checks an answer scored with low confidence
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verify_recipe: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct SubRecipe {
     pub name: String,
@@ -230,6 +296,12 @@ pub struct RecipeBuilder {
     parameters: Option<Vec<RecipeParameter>>,
     response: Option<Response>,
     sub_recipes: Option<Vec<SubRecipe>>,
+    requires: Option<Requirements>,
+    report: Option<ReportTemplate>,
+    knowledge: Option<Vec<String>>,
+    require_citations: Option<bool>,
+    clarifications: Option<Clarifications>,
+    confidence: Option<ConfidenceCheck>,
 }
 
 impl Recipe {
@@ -262,6 +334,12 @@ impl Recipe {
             parameters: None,
             response: None,
             sub_recipes: None,
+            requires: None,
+            report: None,
+            knowledge: None,
+            require_citations: None,
+            clarifications: None,
+            confidence: None,
         }
     }
     pub fn from_content(content: &str) -> Result<Self> {
@@ -283,6 +361,22 @@ impl Recipe {
             ))
         }
     }
+
+    /// The instructions, followed by which knowledge sources to search when the Recipe
+    /// names any
+    pub fn instructions_with_knowledge(&self) -> Option<String> {
+        let Some(sources) = self.knowledge.as_ref().filter(|s| !s.is_empty()) else {
+            return self.instructions.clone();
+        };
+        let knowledge = format!(
+            "When searching knowledge with search_knowledge, only search these sources: {}.",
+            sources.join(", ")
+        );
+        Some(match &self.instructions {
+            Some(instructions) => format!("{}\n\n{}", instructions, knowledge),
+            None => knowledge,
+        })
+    }
 }
 
 impl RecipeBuilder {
@@ -360,6 +454,42 @@ impl RecipeBuilder {
         self
     }
 
+    /// Sets the capabilities the Recipe needs to run
+    pub fn requires(mut self, requires: Requirements) -> Self {
+        self.requires = Some(requires);
+        self
+    }
+
+    /// Sets the report rendered from a subagent's final output
+    pub fn report(mut self, report: ReportTemplate) -> Self {
+        self.report = Some(report);
+        self
+    }
+
+    /// Sets the knowledge sources the Recipe searches
+    pub fn knowledge(mut self, knowledge: Vec<String>) -> Self {
+        self.knowledge = Some(knowledge);
+        self
+    }
+
+    /// Sets whether the final answer must cite the tool results it relies on
+    pub fn require_citations(mut self, require_citations: bool) -> Self {
+        self.require_citations = Some(require_citations);
+        self
+    }
+
+    /// Sets whether the agent states its assumptions and may ask clarifying questions
+    pub fn clarifications(mut self, clarifications: Clarifications) -> Self {
+        self.clarifications = Some(clarifications);
+        self
+    }
+
+    /// Sets the self-assessment run on the final answer
+    pub fn confidence(mut self, confidence: ConfidenceCheck) -> Self {
+        self.confidence = Some(confidence);
+        self
+    }
+
     /// Builds the Recipe instance
     ///
     /// Returns an error if any required fields are missing
@@ -385,6 +515,12 @@ impl RecipeBuilder {
             parameters: self.parameters,
             response: self.response,
             sub_recipes: self.sub_recipes,
+            requires: self.requires,
+            report: self.report,
+            knowledge: self.knowledge,
+            require_citations: self.require_citations,
+            clarifications: self.clarifications,
+            confidence: self.confidence,
         })
     }
 }
@@ -630,6 +766,25 @@ sub_recipes:
         assert_eq!(activities, vec!["activity1", "activity2"]);
     }
 
+    #[test]
+    fn test_from_content_with_short_settings_names() {
+        let content = r#"version: 1.0.0
+title: Test Recipe
+description: A test recipe
+instructions: Test instructions
+settings:
+  provider: openai
+  model: gpt-4o-mini
+  temperature: 0.2"#;
+
+        let recipe = Recipe::from_content(content).unwrap();
+
+        let settings = recipe.settings.unwrap();
+        assert_eq!(settings.goose_provider, Some("openai".to_string()));
+        assert_eq!(settings.goose_model, Some("gpt-4o-mini".to_string()));
+        assert_eq!(settings.temperature, Some(0.2));
+    }
+
     #[test]
     fn test_from_content_with_nested_recipe_yaml() {
         let content = r#"name: test_recipe
@@ -659,4 +814,25 @@ isGlobal: true"#;
         let extensions = recipe.extensions.unwrap();
         assert_eq!(extensions.len(), 0);
     }
+
+    #[test]
+    fn test_from_content_with_knowledge_sources() {
+        let content = r#"title: Support Recipe
+description: Answers support questions
+instructions: Answer the question
+knowledge:
+  - handbook
+  - wiki"#;
+
+        let recipe = Recipe::from_content(content).unwrap();
+        assert_eq!(
+            recipe.knowledge,
+            Some(vec!["handbook".to_string(), "wiki".to_string()])
+        );
+        assert_eq!(
+            recipe.instructions_with_knowledge().unwrap(),
+            "Answer the question\n\nWhen searching knowledge with search_knowledge, only search \
+             these sources: handbook, wiki."
+        );
+    }
 }
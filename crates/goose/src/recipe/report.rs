@@ -0,0 +1,326 @@
+use anyhow::Result;
+use base64::Engine;
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Document, Object, Stream};
+use pulldown_cmark::{html, CowStr, Event, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::prompt_template::render_inline_once;
+
+/// Characters per line and lines per page of PDF reports, set in 10pt Courier on A4
+const PDF_LINE_CHARS: usize = 90;
+const PDF_PAGE_LINES: usize = 64;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+    /// Plain text pages of the rendered Markdown, stored base64-encoded
+    Pdf,
+}
+
+/// A report a subagent's final output is rendered into when it finishes, stored as an artifact
+/// for people to read
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ReportTemplate {
+    /// Artifact name of the report, e.g. `audit.html`
+    pub name: String,
+
+    /// Markdown template. The final output is available as `output`, parsed when it is JSON,
+    /// along with the raw `text` and the recipe's `title`.
+    pub template: String,
+
+    #[serde(default)]
+    pub format: ReportFormat,
+}
+
+/// A rendered report ready to be stored as an artifact
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedReport {
+    pub name: String,
+    pub mime_type: &'static str,
+    pub content: String,
+}
+
+impl ReportTemplate {
+    pub fn render(&self, title: &str, output: &str) -> Result<RenderedReport> {
+        let context = json!({
+            "title": title,
+            "output": structured_output(output),
+            "text": output,
+        });
+        let markdown = render_inline_once(&self.template, &context)?;
+
+        let (mime_type, content) = match self.format {
+            ReportFormat::Markdown => ("text/markdown", markdown),
+            ReportFormat::Html => ("text/html", markdown_to_html(title, &markdown)),
+            ReportFormat::Pdf => (
+                "application/pdf",
+                base64::engine::general_purpose::STANDARD.encode(text_to_pdf(&markdown)?),
+            ),
+        };
+        Ok(RenderedReport {
+            name: self.name.clone(),
+            mime_type,
+            content,
+        })
+    }
+}
+
+/// The output as JSON when it is JSON, on its own or in a fenced code block, otherwise as text
+fn structured_output(output: &str) -> Value {
+    let trimmed = output.trim();
+    let fenced = trimmed
+        .split_once("```json")
+        .and_then(|(_, rest)| rest.split_once("```"))
+        .map(|(json, _)| json);
+    [Some(trimmed), fenced]
+        .into_iter()
+        .flatten()
+        .find_map(|candidate| serde_json::from_str(candidate).ok())
+        .unwrap_or_else(|| Value::String(output.to_string()))
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Whether following a link can't run code in the page, e.g. through `javascript:`
+fn is_safe_url(url: &str) -> bool {
+    // Browsers skip whitespace and control characters in the scheme
+    let normalized: String = url
+        .chars()
+        .filter(|c| !c.is_ascii_whitespace() && !c.is_control())
+        .take(16)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    !["javascript:", "vbscript:", "data:"]
+        .iter()
+        .any(|scheme| normalized.starts_with(scheme))
+}
+
+fn safe_url(url: CowStr<'_>) -> CowStr<'_> {
+    if is_safe_url(&url) {
+        url
+    } else {
+        CowStr::Borrowed("")
+    }
+}
+
+/// Render the Markdown as HTML. The Markdown comes from model output, so raw HTML in it is
+/// shown as text and links that run code lose their destination.
+fn markdown_to_html(title: &str, markdown: &str) -> String {
+    let events = Parser::new_ext(markdown, Options::all()).map(|event| match event {
+        Event::Html(raw) => Event::Text(raw),
+        Event::Start(Tag::Link(kind, url, title)) => {
+            Event::Start(Tag::Link(kind, safe_url(url), title))
+        }
+        Event::End(Tag::Link(kind, url, title)) => {
+            Event::End(Tag::Link(kind, safe_url(url), title))
+        }
+        Event::Start(Tag::Image(kind, url, title)) => {
+            Event::Start(Tag::Image(kind, safe_url(url), title))
+        }
+        Event::End(Tag::Image(kind, url, title)) => {
+            Event::End(Tag::Image(kind, safe_url(url), title))
+        }
+        other => other,
+    });
+    let mut body = String::new();
+    html::push_html(&mut body, events);
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body {{ font-family: sans-serif; max-width: 48em; margin: 2em auto; line-height: 1.5; }} \
+         table {{ border-collapse: collapse; }} th, td {{ border: 1px solid #ccc; padding: 0.3em 0.6em; }} \
+         pre {{ background: #f5f5f5; padding: 1em; overflow-x: auto; }}</style>\n\
+         </head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        body
+    )
+}
+
+/// Wrap text to the PDF line width, breaking at spaces where possible
+fn wrap_line(line: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in line.split(' ') {
+        let word_chars = word.chars().count();
+        let current_chars = current.chars().count();
+        if current_chars > 0 && current_chars + 1 + word_chars > PDF_LINE_CHARS {
+            lines.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push(' ');
+        }
+        current.push_str(word);
+        while current.chars().count() > PDF_LINE_CHARS {
+            let split = current
+                .char_indices()
+                .nth(PDF_LINE_CHARS)
+                .map_or(current.len(), |(index, _)| index);
+            let rest = current.split_off(split);
+            lines.push(std::mem::replace(&mut current, rest));
+        }
+    }
+    lines.push(current);
+    lines
+}
+
+/// Characters the standard PDF fonts can't show become `?`
+fn latin1(line: &str) -> Vec<u8> {
+    line.chars()
+        .map(|c| u8::try_from(u32::from(c)).unwrap_or(b'?'))
+        .collect()
+}
+
+fn text_to_pdf(text: &str) -> Result<Vec<u8>> {
+    let lines: Vec<String> = text.lines().flat_map(wrap_line).collect();
+
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let font_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+    let resources_id = doc.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    // An empty report is still a one-page document
+    let pages: Vec<&[String]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(PDF_PAGE_LINES).collect()
+    };
+
+    let mut kids = Vec::new();
+    for page in pages {
+        let mut operations = vec![
+            Operation::new("BT", vec![]),
+            Operation::new("Tf", vec!["F1".into(), 10.into()]),
+            Operation::new("TL", vec![12.into()]),
+            Operation::new("Td", vec![40.into(), 800.into()]),
+        ];
+        for line in page {
+            operations.push(Operation::new(
+                "Tj",
+                vec![Object::string_literal(latin1(line))],
+            ));
+            operations.push(Operation::new("T*", vec![]));
+        }
+        operations.push(Operation::new("ET", vec![]));
+
+        let content = Content { operations };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode()?));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        });
+        kids.push(Object::from(page_id));
+    }
+
+    let count = kids.len() as i64;
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    doc.trailer.set("Root", catalog_id);
+    doc.compress();
+
+    let mut pdf = Vec::new();
+    doc.save_to(&mut pdf)?;
+    Ok(pdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(format: ReportFormat) -> ReportTemplate {
+        ReportTemplate {
+            name: "audit".to_string(),
+            template: "# {{ title }}\n\n{% for finding in output.findings %}- **{{ finding.severity }}**: {{ finding.summary }}\n{% endfor %}".to_string(),
+            format,
+        }
+    }
+
+    #[test]
+    fn test_render_structured_output_in_each_format() {
+        let output = "Done.\n```json\n{\"findings\": [{\"severity\": \"high\", \"summary\": \"SQL injection in login\"}]}\n```";
+
+        let markdown = template(ReportFormat::Markdown)
+            .render("Audit <Q3>", output)
+            .unwrap();
+        assert_eq!(markdown.mime_type, "text/markdown");
+        assert_eq!(
+            markdown.content,
+            "# Audit <Q3>\n\n- **high**: SQL injection in login"
+        );
+
+        let html = template(ReportFormat::Html)
+            .render("Audit <Q3>", output)
+            .unwrap();
+        assert!(html.content.contains("<title>Audit &lt;Q3&gt;</title>"));
+        assert!(html
+            .content
+            .contains("<li><strong>high</strong>: SQL injection in login</li>"));
+
+        let pdf = template(ReportFormat::Pdf).render("Audit", output).unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(pdf.content)
+            .unwrap();
+        assert!(bytes.starts_with(b"%PDF-1.5"));
+    }
+
+    #[test]
+    fn test_html_reports_escape_raw_html_and_script_links() {
+        let report = ReportTemplate {
+            name: "report.html".to_string(),
+            template: "{{ text }}".to_string(),
+            format: ReportFormat::Html,
+        };
+        let html = report
+            .render(
+                "Report",
+                "<script>alert(1)</script>
+
+See <img src=x onerror=alert(1)> and                  [this](javascript:alert(1)) or [docs](https://example.com/docs)",
+            )
+            .unwrap()
+            .content;
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;alert(1)&lt;/script&gt;"));
+        assert!(!html.contains("<img"));
+        assert!(!html.contains("javascript:"));
+        assert!(html.contains("<a href=\"https://example.com/docs\">docs</a>"));
+    }
+
+    #[test]
+    fn test_wrap_line_breaks_long_lines() {
+        let long = "word ".repeat(40);
+        let lines = wrap_line(long.trim_end());
+        assert!(lines.len() > 1);
+        assert!(lines
+            .iter()
+            .all(|line| line.chars().count() <= PDF_LINE_CHARS));
+        assert_eq!(wrap_line(""), vec![String::new()]);
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::providers::base::ProviderCapabilities;
+
+/// Capabilities a recipe needs from the provider and extensions it runs with, checked before
+/// a subagent is spawned from it
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct Requirements {
+    /// The model must accept images
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub vision: bool,
+
+    /// Smallest context window, in tokens, the recipe can work with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_context_tokens: Option<usize>,
+
+    /// Tools that must be available, either prefixed with their extension
+    /// (`developer__shell`) or bare (`shell`) to accept the tool from any extension
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+}
+
+/// Every requirement of a recipe that the provider or extensions at hand don't meet
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("Recipe '{recipe}' can't run with the current setup: {}", .unmet.join("; "))]
+pub struct UnmetRequirements {
+    pub recipe: String,
+    pub unmet: Vec<String>,
+}
+
+impl Requirements {
+    /// Check the requirements against the provider's capabilities and the names of the
+    /// available tools, reporting all of the unmet ones at once
+    pub fn check(
+        &self,
+        recipe: &str,
+        capabilities: &ProviderCapabilities,
+        tools: &[String],
+    ) -> Result<(), UnmetRequirements> {
+        let mut unmet = Vec::new();
+
+        if self.vision && !capabilities.supports_vision {
+            unmet.push("the model does not support vision".to_string());
+        }
+        if let Some(min_context_tokens) = self.min_context_tokens {
+            if capabilities.max_context_tokens < min_context_tokens {
+                unmet.push(format!(
+                    "needs a context window of at least {} tokens, the model has {}",
+                    min_context_tokens, capabilities.max_context_tokens
+                ));
+            }
+        }
+        for required in &self.tools {
+            if !tools.iter().any(|tool| tool_matches(tool, required)) {
+                unmet.push(format!("tool '{}' is not available", required));
+            }
+        }
+
+        if unmet.is_empty() {
+            Ok(())
+        } else {
+            Err(UnmetRequirements {
+                recipe: recipe.to_string(),
+                unmet,
+            })
+        }
+    }
+}
+
+fn tool_matches(tool: &str, required: &str) -> bool {
+    tool == required
+        || (!required.contains("__")
+            && tool
+                .split_once("__")
+                .is_some_and(|(_, name)| name == required))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capabilities() -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_tools: true,
+            supports_vision: false,
+            supports_streaming: false,
+            max_context_tokens: 32_000,
+            max_output_tokens: None,
+        }
+    }
+
+    #[test]
+    fn test_check_reports_every_unmet_requirement() {
+        let tools = vec![
+            "developer__shell".to_string(),
+            "memory__remember".to_string(),
+        ];
+        let requirements = Requirements {
+            vision: true,
+            min_context_tokens: Some(128_000),
+            tools: vec![
+                "shell".to_string(),
+                "developer__text_editor".to_string(),
+                "memory__remember".to_string(),
+            ],
+        };
+
+        let err = requirements
+            .check("review", &capabilities(), &tools)
+            .unwrap_err();
+        assert_eq!(err.recipe, "review");
+        assert_eq!(err.unmet.len(), 3);
+        assert!(err.unmet[0].contains("vision"));
+        assert!(err.unmet[1].contains("128000"));
+        assert!(err.unmet[2].contains("developer__text_editor"));
+
+        let met = Requirements {
+            min_context_tokens: Some(16_000),
+            tools: vec!["shell".to_string()],
+            ..Default::default()
+        };
+        assert!(met.check("review", &capabilities(), &tools).is_ok());
+    }
+}
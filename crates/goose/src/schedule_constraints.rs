@@ -0,0 +1,384 @@
+//! Calendar-aware constraints on when scheduled recipes may run, so automated runs skip deploy
+//! freezes and the hours outside the working week.
+//!
+//! A job's own constraints and the global ones from `GOOSE_SCHEDULE_CONSTRAINTS` both apply. A
+//! cron tick that falls inside a blackout, outside business hours or on an excluded cron time
+//! is skipped rather than postponed; running a job by hand is never constrained.
+use std::path::PathBuf;
+
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{Config, ConfigError};
+
+fn working_week() -> Vec<Weekday> {
+    vec![
+        Weekday::Mon,
+        Weekday::Tue,
+        Weekday::Wed,
+        Weekday::Thu,
+        Weekday::Fri,
+    ]
+}
+
+/// Days and hours, in the constraints' time zone, jobs may run in. Hours that end at or before
+/// they start run overnight into the next day, e.g. 22:00 to 06:00; the days are the ones the
+/// hours start on.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct BusinessHours {
+    /// Monday to Friday when unset
+    #[serde(default = "working_week")]
+    #[schema(value_type = Vec<String>, example = json!(["Mon", "Tue", "Wed", "Thu", "Fri"]))]
+    pub days: Vec<Weekday>,
+    #[schema(value_type = String, example = "09:00:00")]
+    pub start: NaiveTime,
+    #[schema(value_type = String, example = "17:00:00")]
+    pub end: NaiveTime,
+}
+
+impl BusinessHours {
+    fn contains(&self, day: Weekday, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            return self.days.contains(&day) && self.start <= time && time < self.end;
+        }
+        // Overnight: the early hours belong to the hours that started the day before
+        if time >= self.start {
+            self.days.contains(&day)
+        } else {
+            time < self.end && self.days.contains(&day.pred())
+        }
+    }
+}
+
+/// A period no job may run in, such as a deploy freeze
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct BlackoutWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl BlackoutWindow {
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.start <= at && at < self.end
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, utoipa::ToSchema)]
+pub struct ScheduleConstraints {
+    /// IANA time zone of the business hours, cron exclusions and calendar times without one,
+    /// e.g. `Europe/Berlin`. UTC when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub business_hours: Option<BusinessHours>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blackouts: Vec<BlackoutWindow>,
+    /// Cron expressions for times not to run at, matched to the minute, e.g. `* * 24,31 12 *`
+    /// to skip Christmas Eve and New Year's Eve
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude_cron: Vec<String>,
+    /// An iCalendar file whose events are blackouts, e.g. an exported release-freeze calendar.
+    /// It is read at every run so edits apply without rescheduling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<String>)]
+    pub blackout_calendar: Option<PathBuf>,
+}
+
+impl ScheduleConstraints {
+    /// Constraints applying to every scheduled job, from `GOOSE_SCHEDULE_CONSTRAINTS`, or an
+    /// error when they are set but can't be parsed or are invalid
+    pub fn global() -> Result<Option<Self>, ConfigError> {
+        let constraints: Self = match Config::global().get_param("GOOSE_SCHEDULE_CONSTRAINTS") {
+            Ok(constraints) => constraints,
+            Err(ConfigError::NotFound(_)) => return Ok(None),
+            Err(e) => {
+                return Err(ConfigError::DeserializeError(format!(
+                    "Invalid GOOSE_SCHEDULE_CONSTRAINTS: {}",
+                    e
+                )))
+            }
+        };
+        constraints.validate().map_err(|e| {
+            ConfigError::DeserializeError(format!("Invalid GOOSE_SCHEDULE_CONSTRAINTS: {}", e))
+        })?;
+        Ok(Some(constraints))
+    }
+
+    fn tz(&self) -> Result<Tz, String> {
+        match &self.timezone {
+            Some(name) => name
+                .parse::<Tz>()
+                .map_err(|_| format!("Unknown time zone '{}'", name)),
+            None => Ok(Tz::UTC),
+        }
+    }
+
+    fn exclusions(&self) -> Result<Vec<Cron>, String> {
+        self.exclude_cron
+            .iter()
+            .map(|expression| {
+                Cron::new(expression)
+                    .with_seconds_optional()
+                    .parse()
+                    .map_err(|e| format!("Invalid cron exclusion '{}': {}", expression, e))
+            })
+            .collect()
+    }
+
+    /// Check the time zone and cron exclusions, to reject bad constraints when a job is added
+    pub fn validate(&self) -> Result<(), String> {
+        self.tz()?;
+        self.exclusions()?;
+        Ok(())
+    }
+
+    /// Why a job must not run at `at`, or `None` if it may
+    pub fn blocked_reason(&self, at: DateTime<Utc>) -> Option<String> {
+        let tz = match self.tz() {
+            Ok(tz) => tz,
+            Err(e) => return Some(e),
+        };
+        let local = at.with_timezone(&tz);
+
+        if let Some(hours) = &self.business_hours {
+            if !hours.contains(local.weekday(), local.time()) {
+                return Some(format!("{} is outside business hours", local));
+            }
+        }
+
+        if let Some(window) = self.blackouts.iter().find(|window| window.contains(at)) {
+            return Some(blackout_reason(window));
+        }
+
+        if let Some(path) = &self.blackout_calendar {
+            match std::fs::read_to_string(path) {
+                Ok(calendar) => {
+                    if let Some(window) = parse_ical_blackouts(&calendar, tz)
+                        .into_iter()
+                        .find(|window| window.contains(at))
+                    {
+                        return Some(blackout_reason(&window));
+                    }
+                }
+                // Running through a freeze is worse than skipping a run
+                Err(e) => {
+                    return Some(format!(
+                        "blackout calendar {} can't be read: {}",
+                        path.display(),
+                        e
+                    ))
+                }
+            }
+        }
+
+        let minute = local
+            .with_second(0)
+            .and_then(|time| time.with_nanosecond(0))
+            .unwrap_or(local);
+        match self.exclusions() {
+            Ok(exclusions) => exclusions
+                .iter()
+                .zip(&self.exclude_cron)
+                .find(|(cron, _)| cron.is_time_matching(&minute).unwrap_or(false))
+                .map(|(_, expression)| format!("{} is excluded by '{}'", local, expression)),
+            Err(e) => Some(e),
+        }
+    }
+}
+
+fn blackout_reason(window: &BlackoutWindow) -> String {
+    match &window.reason {
+        Some(reason) => format!("blackout until {}: {}", window.end, reason),
+        None => format!("blackout until {}", window.end),
+    }
+}
+
+/// Parse a `DTSTART` or `DTEND` line's parameters and value. Returns the time and whether it
+/// was a whole date (`VALUE=DATE`).
+fn parse_ical_time(params: &str, value: &str, default_tz: Tz) -> Option<(DateTime<Utc>, bool)> {
+    let tz = params
+        .split(';')
+        .find_map(|param| param.strip_prefix("TZID="))
+        .and_then(|name| name.parse::<Tz>().ok())
+        .unwrap_or(default_tz);
+
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        let start = tz
+            .from_local_datetime(&date.and_time(NaiveTime::MIN))
+            .earliest()?;
+        return Some((start.with_timezone(&Utc), true));
+    }
+    if let Some(utc) = value.strip_suffix('Z') {
+        let time = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&time), false));
+    }
+    let time = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()?;
+    let local = tz.from_local_datetime(&time).earliest()?;
+    Some((local.with_timezone(&Utc), false))
+}
+
+#[derive(Default)]
+struct IcalEvent {
+    start: Option<(DateTime<Utc>, bool)>,
+    end: Option<DateTime<Utc>>,
+    summary: Option<String>,
+}
+
+impl IcalEvent {
+    fn into_blackout(self) -> Option<BlackoutWindow> {
+        let (start, whole_day) = self.start?;
+        // An all-day event without an end lasts the day
+        let end = self
+            .end
+            .or(whole_day.then(|| start + Duration::days(1)))
+            .filter(|end| *end > start)?;
+        Some(BlackoutWindow {
+            start,
+            end,
+            reason: self.summary,
+        })
+    }
+}
+
+/// The events of an iCalendar file as blackout windows. Recurrence rules are not expanded.
+fn parse_ical_blackouts(calendar: &str, default_tz: Tz) -> Vec<BlackoutWindow> {
+    // Long lines are folded onto following lines that start with a space or tab
+    let mut lines: Vec<String> = Vec::new();
+    for line in calendar.lines() {
+        match line.strip_prefix([' ', '\t']) {
+            Some(continuation) if !lines.is_empty() => {
+                lines.last_mut().expect("not empty").push_str(continuation)
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    let mut windows = Vec::new();
+    let mut event: Option<IcalEvent> = None;
+    for line in lines {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        match (name.to_ascii_uppercase().as_str(), event.as_mut()) {
+            ("BEGIN", _) if value.eq_ignore_ascii_case("VEVENT") => {
+                event = Some(IcalEvent::default())
+            }
+            ("DTSTART", Some(event)) => event.start = parse_ical_time(params, value, default_tz),
+            ("DTEND", Some(event)) => {
+                event.end = parse_ical_time(params, value, default_tz).map(|(end, _)| end)
+            }
+            ("SUMMARY", Some(event)) => event.summary = Some(value.to_string()),
+            ("END", Some(_)) if value.eq_ignore_ascii_case("VEVENT") => {
+                if let Some(window) = event.take().and_then(IcalEvent::into_blackout) {
+                    windows.push(window);
+                }
+            }
+            _ => {}
+        }
+    }
+    windows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn test_business_hours_blackouts_and_exclusions() {
+        let constraints = ScheduleConstraints {
+            timezone: Some("Europe/Berlin".to_string()),
+            business_hours: Some(BusinessHours {
+                days: working_week(),
+                start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+            }),
+            blackouts: vec![BlackoutWindow {
+                start: at("2025-06-04T00:00:00Z"),
+                end: at("2025-06-05T00:00:00Z"),
+                reason: Some("release freeze".to_string()),
+            }],
+            exclude_cron: vec!["* 12 * * *".to_string()],
+            blackout_calendar: None,
+        };
+        assert!(constraints.validate().is_ok());
+
+        // Tuesday 10:30 in Berlin
+        assert_eq!(constraints.blocked_reason(at("2025-06-03T08:30:00Z")), None);
+        // 07:30 in Berlin
+        assert!(constraints
+            .blocked_reason(at("2025-06-03T05:30:00Z"))
+            .is_some());
+        // Saturday
+        assert!(constraints
+            .blocked_reason(at("2025-06-07T08:30:00Z"))
+            .is_some());
+        let reason = constraints
+            .blocked_reason(at("2025-06-04T08:30:00Z"))
+            .unwrap();
+        assert!(reason.contains("release freeze"));
+        // 12:15 in Berlin is excluded
+        assert!(constraints
+            .blocked_reason(at("2025-06-03T10:15:00Z"))
+            .is_some());
+
+        let overnight = ScheduleConstraints {
+            business_hours: Some(BusinessHours {
+                days: vec![Weekday::Fri],
+                start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            }),
+            ..Default::default()
+        };
+        assert!(overnight.validate().is_ok());
+        // Friday 23:00 and the early hours of Saturday run; Friday morning doesn't
+        assert_eq!(overnight.blocked_reason(at("2025-06-06T23:00:00Z")), None);
+        assert_eq!(overnight.blocked_reason(at("2025-06-07T05:59:00Z")), None);
+        assert!(overnight
+            .blocked_reason(at("2025-06-06T05:00:00Z"))
+            .is_some());
+        assert!(overnight
+            .blocked_reason(at("2025-06-07T23:00:00Z"))
+            .is_some());
+
+        let invalid = ScheduleConstraints {
+            timezone: Some("Mars/Olympus".to_string()),
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_ical_events_become_blackouts() {
+        let calendar = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Deploy\r\n  freeze\r\n\
+            DTSTART:20251222T000000Z\r\n\
+            DTEND:20260105T000000Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Offsite\r\n\
+            DTSTART;VALUE=DATE:20250910\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let windows = parse_ical_blackouts(calendar, Tz::UTC);
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].reason.as_deref(), Some("Deploy freeze"));
+        assert!(windows[0].contains(at("2025-12-31T12:00:00Z")));
+        assert!(windows[1].contains(at("2025-09-10T23:59:00Z")));
+        assert!(!windows[1].contains(at("2025-09-11T00:00:00Z")));
+    }
+}
@@ -16,9 +16,11 @@ use crate::agents::AgentEvent;
 use crate::agents::{Agent, SessionConfig};
 use crate::config::{self, Config};
 use crate::message::Message;
+use crate::offline_queue::{global_offline_queue, QueuedRun, QueuedRunExecutor};
 use crate::providers::base::Provider as GooseProvider; // Alias to avoid conflict in test section
 use crate::providers::create;
 use crate::recipe::Recipe;
+use crate::schedule_constraints::ScheduleConstraints;
 use crate::scheduler_trait::SchedulerTrait;
 use crate::session;
 use crate::session::storage::SessionMetadata;
@@ -93,6 +95,7 @@ pub enum SchedulerError {
     AgentSetupError(String),
     PersistError(String),
     CronParseError(String),
+    ConfigError(String),
     SchedulerInternalError(String),
     AnyhowError(anyhow::Error),
 }
@@ -107,6 +110,7 @@ impl std::fmt::Display for SchedulerError {
             SchedulerError::AgentSetupError(e) => write!(f, "Agent setup error: {}", e),
             SchedulerError::PersistError(e) => write!(f, "Failed to persist schedules: {}", e),
             SchedulerError::CronParseError(e) => write!(f, "Invalid cron string: {}", e),
+            SchedulerError::ConfigError(e) => write!(f, "Invalid configuration: {}", e),
             SchedulerError::SchedulerInternalError(e) => {
                 write!(f, "Scheduler internal error: {}", e)
             }
@@ -159,6 +163,41 @@ pub struct ScheduledJob {
     pub process_start_time: Option<DateTime<Utc>>,
     #[serde(default)]
     pub execution_mode: Option<String>, // "foreground" or "background"
+    /// Blackouts and business hours this job's cron runs must respect
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constraints: Option<ScheduleConstraints>,
+}
+
+/// Why a cron run must be skipped now, if the job's own or the global constraints forbid it.
+/// Global constraints that can't be loaded block every run, as running through a freeze is
+/// worse than skipping a run.
+fn constraints_block(constraints: Option<&ScheduleConstraints>) -> Option<String> {
+    let now = Utc::now();
+    let global = match ScheduleConstraints::global() {
+        Ok(global) => global,
+        Err(e) => return Some(e.to_string()),
+    };
+    constraints
+        .into_iter()
+        .chain(global.as_ref())
+        .find_map(|constraints| constraints.blocked_reason(now))
+}
+
+/// [`constraints_block`] off the async runtime, since a blackout calendar is read from disk
+async fn check_constraints(constraints: Option<ScheduleConstraints>) -> Option<String> {
+    tokio::task::spawn_blocking(move || constraints_block(constraints.as_ref()))
+        .await
+        .unwrap_or_else(|e| Some(format!("constraints check failed: {}", e)))
+}
+
+/// Why a cron run of the job must be skipped now, checked without holding the jobs lock
+async fn job_blocked(jobs: &Arc<Mutex<JobsMap>>, job_id: &str) -> Option<String> {
+    let constraints = {
+        let jobs_guard = jobs.lock().await;
+        let (_, job) = jobs_guard.get(job_id)?;
+        job.constraints.clone()
+    };
+    check_constraints(constraints).await
 }
 
 async fn persist_jobs_from_arc(
@@ -199,11 +238,23 @@ impl Scheduler {
         });
 
         arc_self.load_jobs_from_storage().await?;
+        if let Err(e) = ScheduleConstraints::global() {
+            tracing::error!(
+                "Scheduled runs are skipped until the global constraints are fixed: {}",
+                e
+            );
+        }
         arc_self
             .internal_scheduler
             .start()
             .await
             .map_err(|e| SchedulerError::SchedulerInternalError(e.to_string()))?;
+        if crate::offline_queue::is_enabled() {
+            global_offline_queue().ensure_drainer(Arc::new(QueuedJobExecutor::for_scheduler(
+                Arc::clone(&arc_self.jobs),
+                arc_self.storage_path.clone(),
+            )));
+        }
 
         Ok(arc_self)
     }
@@ -216,6 +267,12 @@ impl Scheduler {
         if jobs_guard.contains_key(&original_job_spec.id) {
             return Err(SchedulerError::JobIdExists(original_job_spec.id.clone()));
         }
+        if let Some(constraints) = &original_job_spec.constraints {
+            constraints
+                .validate()
+                .map_err(SchedulerError::CronParseError)?;
+        }
+        ScheduleConstraints::global().map_err(|e| SchedulerError::ConfigError(e.to_string()))?;
 
         let original_recipe_path = Path::new(&original_job_spec.source);
         if !original_recipe_path.exists() {
@@ -309,6 +366,12 @@ impl Scheduler {
                     return;
                 }
 
+                let blocked = job_blocked(&current_jobs_arc, &task_job_id).await;
+                if let Some(reason) = blocked {
+                    tracing::info!("Skipping execution of job '{}': {}", &task_job_id, reason);
+                    return;
+                }
+
                 let current_time = Utc::now();
                 let mut needs_persist = false;
                 {
@@ -487,6 +550,12 @@ impl Scheduler {
                         return;
                     }
 
+                    let blocked = job_blocked(&current_jobs_arc, &task_job_id).await;
+                    if let Some(reason) = blocked {
+                        tracing::info!("Skipping execution of job '{}': {}", &task_job_id, reason);
+                        return;
+                    }
+
                     let current_time = Utc::now();
                     let mut needs_persist = false;
                     {
@@ -868,6 +937,16 @@ impl Scheduler {
                             return;
                         }
 
+                        let blocked = job_blocked(&current_jobs_arc, &task_job_id).await;
+                        if let Some(reason) = blocked {
+                            tracing::info!(
+                                "Skipping execution of job '{}': {}",
+                                &task_job_id,
+                                reason
+                            );
+                            return;
+                        }
+
                         let current_time = Utc::now();
                         let mut needs_persist = false;
                         {
@@ -1068,9 +1147,225 @@ async fn run_scheduled_job_internal(
     provider_override: Option<Arc<dyn GooseProvider>>, // New optional parameter
     jobs_arc: Option<Arc<Mutex<JobsMap>>>,
     job_id: Option<String>,
+) -> std::result::Result<String, JobExecutionError> {
+    if provider_override.is_none() {
+        if let Some(run) = queue_if_offline(&job).await {
+            crate::outbox::notify_completion(
+                "schedule.queued",
+                serde_json::json!({
+                    "job_id": job.id,
+                    "source": job.source,
+                    "run_id": run.id,
+                }),
+            )
+            .await;
+            return Err(JobExecutionError {
+                job_id: job.id.clone(),
+                error: format!(
+                    "No network connection, queued as run {} until connectivity returns",
+                    run.id
+                ),
+            });
+        }
+    }
+    let (scheduled_job_id, source) = (job.id.clone(), job.source.clone());
+    let result = execute_scheduled_job(job, provider_override, jobs_arc, job_id).await;
+    notify_job_result(&scheduled_job_id, &source, &result).await;
+    result
+}
+
+/// Queue the job's recipe instead of running it if the offline queue is enabled and the
+/// network is unreachable. A recipe that can't be loaded isn't queued, so the run fails as
+/// usual.
+async fn queue_if_offline(job: &ScheduledJob) -> Option<QueuedRun> {
+    let queue = global_offline_queue();
+    if !crate::offline_queue::is_enabled() || queue.is_online().await {
+        return None;
+    }
+    let recipe = load_job_recipe(job).ok()?;
+    match queue
+        .enqueue(
+            &job.source,
+            Some(job.id.clone()),
+            recipe,
+            job.execution_mode.clone(),
+        )
+        .await
+    {
+        Ok(run) => {
+            tracing::info!("Offline, queued job '{}' as run {}", job.id, run.id);
+            queue.ensure_drainer(Arc::new(QueuedJobExecutor::default()));
+            Some(run)
+        }
+        Err(e) => {
+            tracing::error!("Failed to queue job '{}' while offline: {}", job.id, e);
+            None
+        }
+    }
+}
+
+/// Runs queued while offline the way the scheduler runs its jobs: a run of a schedule waits
+/// while the schedule is paused, already running or blocked by its constraints, and shows as
+/// the schedule's current run while it executes
+#[derive(Default)]
+pub struct QueuedJobExecutor {
+    /// The scheduler's jobs; without them schedules are read from storage and runs aren't
+    /// tracked
+    jobs: Option<Arc<Mutex<JobsMap>>>,
+    storage_path: Option<PathBuf>,
+}
+
+impl QueuedJobExecutor {
+    fn for_scheduler(jobs: Arc<Mutex<JobsMap>>, storage_path: PathBuf) -> Self {
+        Self {
+            jobs: Some(jobs),
+            storage_path: Some(storage_path),
+        }
+    }
+
+    async fn scheduled_job(&self, schedule_id: &str) -> Option<ScheduledJob> {
+        if let Some(jobs) = &self.jobs {
+            return jobs
+                .lock()
+                .await
+                .get(schedule_id)
+                .map(|(_, job)| job.clone());
+        }
+        let storage_path = match &self.storage_path {
+            Some(path) => path.clone(),
+            None => get_default_scheduler_storage_path().ok()?,
+        };
+        serde_json::from_str::<Vec<ScheduledJob>>(&fs::read_to_string(storage_path).ok()?)
+            .ok()?
+            .into_iter()
+            .find(|job| job.id == schedule_id)
+    }
+
+    /// Mark a schedule of the scheduler as running the queued run, or as done with it
+    async fn set_running(&self, schedule_id: &str, running: bool) {
+        let Some(jobs) = &self.jobs else {
+            return;
+        };
+        {
+            let mut jobs_guard = jobs.lock().await;
+            let Some((_, job)) = jobs_guard.get_mut(schedule_id) else {
+                return;
+            };
+            let now = Utc::now();
+            job.currently_running = running;
+            job.process_start_time = running.then_some(now);
+            if running {
+                job.last_run = Some(now);
+            } else {
+                job.current_session_id = None;
+            }
+        }
+        if let Some(storage_path) = &self.storage_path {
+            if let Err(e) = persist_jobs_from_arc(storage_path, jobs).await {
+                tracing::error!(
+                    "Failed to persist running status update for job {}: {}",
+                    schedule_id,
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl QueuedRunExecutor for QueuedJobExecutor {
+    async fn ready(&self, run: &QueuedRun) -> bool {
+        let Some(schedule_id) = &run.schedule_id else {
+            return true;
+        };
+        let Some(job) = self.scheduled_job(schedule_id).await else {
+            return true;
+        };
+        if job.paused || job.currently_running {
+            return false;
+        }
+        match check_constraints(job.constraints.clone()).await {
+            Some(reason) => {
+                tracing::debug!("Holding queued run {} of '{}': {}", run.id, job.id, reason);
+                false
+            }
+            None => true,
+        }
+    }
+
+    async fn execute(&self, run: &QueuedRun) -> Result<String> {
+        let job = ScheduledJob {
+            id: run.schedule_id.clone().unwrap_or_else(|| run.id.clone()),
+            source: run.source.clone(),
+            cron: String::new(),
+            last_run: None,
+            currently_running: false,
+            paused: false,
+            current_session_id: None,
+            process_start_time: None,
+            execution_mode: run.execution_mode.clone(),
+            constraints: None,
+        };
+        let job_id = job.id.clone();
+        let tracked = run.schedule_id.clone().filter(|_| self.jobs.is_some());
+        if let Some(schedule_id) = &tracked {
+            self.set_running(schedule_id, true).await;
+        }
+        let result = execute_recipe_job(
+            job,
+            run.recipe.clone(),
+            None,
+            tracked.as_ref().and(self.jobs.clone()),
+            tracked.clone(),
+        )
+        .await;
+        if let Some(schedule_id) = &tracked {
+            self.set_running(schedule_id, false).await;
+        }
+        notify_job_result(&job_id, &run.source, &result).await;
+        result.map_err(|e| anyhow!(e.error))
+    }
+}
+
+async fn notify_job_result(
+    scheduled_job_id: &str,
+    source: &str,
+    result: &std::result::Result<String, JobExecutionError>,
+) {
+    // Queued in the outbox, so the notification isn't lost if the webhook is briefly down
+    let (kind, payload) = match result {
+        Ok(session_id) => (
+            "schedule.completed",
+            serde_json::json!({
+                "job_id": scheduled_job_id,
+                "source": source,
+                "session_id": session_id,
+            }),
+        ),
+        Err(e) => (
+            "schedule.failed",
+            serde_json::json!({
+                "job_id": scheduled_job_id,
+                "source": source,
+                "error": e.error,
+            }),
+        ),
+    };
+    crate::outbox::notify_completion(kind, payload).await;
+}
+
+async fn execute_scheduled_job(
+    job: ScheduledJob,
+    provider_override: Option<Arc<dyn GooseProvider>>,
+    jobs_arc: Option<Arc<Mutex<JobsMap>>>,
+    job_id: Option<String>,
 ) -> std::result::Result<String, JobExecutionError> {
     tracing::info!("Executing job: {} (Source: {})", job.id, job.source);
+    let recipe = load_job_recipe(&job)?;
+    execute_recipe_job(job, recipe, provider_override, jobs_arc, job_id).await
+}
 
+fn load_job_recipe(job: &ScheduledJob) -> std::result::Result<Recipe, JobExecutionError> {
     let recipe_path = Path::new(&job.source);
 
     let recipe_content = match fs::read_to_string(recipe_path) {
@@ -1083,36 +1378,42 @@ async fn run_scheduled_job_internal(
         }
     };
 
-    let recipe: Recipe = {
-        let extension = recipe_path
-            .extension()
-            .and_then(|os_str| os_str.to_str())
-            .unwrap_or("yaml")
-            .to_lowercase();
+    let extension = recipe_path
+        .extension()
+        .and_then(|os_str| os_str.to_str())
+        .unwrap_or("yaml")
+        .to_lowercase();
 
-        match extension.as_str() {
-            "json" | "jsonl" => {
-                serde_json::from_str::<Recipe>(&recipe_content).map_err(|e| JobExecutionError {
-                    job_id: job.id.clone(),
-                    error: format!("Failed to parse JSON recipe '{}': {}", job.source, e),
-                })
-            }
-            "yaml" | "yml" => {
-                serde_yaml::from_str::<Recipe>(&recipe_content).map_err(|e| JobExecutionError {
-                    job_id: job.id.clone(),
-                    error: format!("Failed to parse YAML recipe '{}': {}", job.source, e),
-                })
-            }
-            _ => Err(JobExecutionError {
+    match extension.as_str() {
+        "json" | "jsonl" => {
+            serde_json::from_str::<Recipe>(&recipe_content).map_err(|e| JobExecutionError {
                 job_id: job.id.clone(),
-                error: format!(
-                    "Unsupported recipe file extension '{}' for: {}",
-                    extension, job.source
-                ),
-            }),
+                error: format!("Failed to parse JSON recipe '{}': {}", job.source, e),
+            })
         }
-    }?;
+        "yaml" | "yml" => {
+            serde_yaml::from_str::<Recipe>(&recipe_content).map_err(|e| JobExecutionError {
+                job_id: job.id.clone(),
+                error: format!("Failed to parse YAML recipe '{}': {}", job.source, e),
+            })
+        }
+        _ => Err(JobExecutionError {
+            job_id: job.id.clone(),
+            error: format!(
+                "Unsupported recipe file extension '{}' for: {}",
+                extension, job.source
+            ),
+        }),
+    }
+}
 
+async fn execute_recipe_job(
+    job: ScheduledJob,
+    recipe: Recipe,
+    provider_override: Option<Arc<dyn GooseProvider>>,
+    jobs_arc: Option<Arc<Mutex<JobsMap>>>,
+    job_id: Option<String>,
+) -> std::result::Result<String, JobExecutionError> {
     let agent: Agent = Agent::new();
 
     let agent_provider: Arc<dyn GooseProvider>; // Use the aliased GooseProvider
@@ -1227,8 +1528,8 @@ async fn run_scheduled_job_internal(
                         Ok(AgentEvent::McpNotification(_)) => {
                             // Handle notifications if needed
                         }
-                        Ok(AgentEvent::ModelChange { .. }) => {
-                            // Model change events are informational, just continue
+                        Ok(AgentEvent::ModelChange { .. }) | Ok(AgentEvent::Usage(_)) => {
+                            // Model change and usage events are informational, just continue
                         }
 
                         Err(e) => {
@@ -1274,6 +1575,7 @@ async fn run_scheduled_job_internal(
                             accumulated_total_tokens: None,
                             accumulated_input_tokens: None,
                             accumulated_output_tokens: None,
+                            seed: None,
                         };
                         if let Err(e_fb) = crate::session::storage::save_messages_with_metadata(
                             &session_file_path,
@@ -1377,6 +1679,7 @@ mod tests {
                         text: "Mocked scheduled response".to_string(),
                         annotations: None,
                     })],
+                    finish_reason: None,
                 },
                 ProviderUsage::new("mock-scheduler-test".to_string(), Usage::default()),
             ))
@@ -1420,6 +1723,12 @@ mod tests {
             settings: None,
             response: None,
             sub_recipes: None,
+            requires: None,
+            report: None,
+            knowledge: None,
+            require_citations: None,
+            clarifications: None,
+            confidence: None,
         };
         let mut recipe_file = File::create(&recipe_filename)?;
         writeln!(
@@ -1440,6 +1749,7 @@ mod tests {
             current_session_id: None,
             process_start_time: None,
             execution_mode: Some("background".to_string()), // Default for test
+            constraints: None,
         };
 
         // Create the mock provider instance for the test
@@ -1493,6 +1803,46 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_job_blocked_by_calendar() {
+        let temp_dir = tempdir().unwrap();
+        let calendar = temp_dir.path().join("freeze.ics");
+        let now = Utc::now();
+        fs::write(
+            &calendar,
+            format!(
+                "BEGIN:VCALENDAR\nBEGIN:VEVENT\nSUMMARY:Freeze\nDTSTART:{}\nDTEND:{}\nEND:VEVENT\nEND:VCALENDAR\n",
+                (now - chrono::Duration::hours(1)).format("%Y%m%dT%H%M%SZ"),
+                (now + chrono::Duration::hours(1)).format("%Y%m%dT%H%M%SZ"),
+            ),
+        )
+        .unwrap();
+
+        let job = ScheduledJob {
+            id: "frozen".to_string(),
+            source: String::new(),
+            cron: "* * * * *".to_string(),
+            last_run: None,
+            currently_running: false,
+            paused: false,
+            current_session_id: None,
+            process_start_time: None,
+            execution_mode: None,
+            constraints: Some(ScheduleConstraints {
+                blackout_calendar: Some(calendar),
+                ..Default::default()
+            }),
+        };
+        let jobs = Arc::new(Mutex::new(HashMap::from([(
+            job.id.clone(),
+            (JobId::new_v4(), job),
+        )])));
+
+        let reason = job_blocked(&jobs, "frozen").await.unwrap();
+        assert!(reason.contains("Freeze"));
+        assert_eq!(job_blocked(&jobs, "missing").await, None);
+    }
 }
 
 #[async_trait]
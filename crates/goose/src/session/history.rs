@@ -0,0 +1,217 @@
+//! Paginated reads of session history for listing UIs.
+//!
+//! Pages are addressed with opaque cursors rather than offsets, so a session list stays
+//! consistent while sessions are being written. When a read replica of the session directory
+//! is configured (see [`read_replica_dir`]) history is served from it, falling back to the
+//! session directory for sessions the replica doesn't have yet, in listings too.
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Serialize;
+
+use super::info::{session_info, SessionInfo};
+use super::storage::{
+    ensure_session_dir, get_path, list_sessions_in, read_history_metadata, read_messages_range,
+    read_replica_dir, Identifier, SessionMetadata,
+};
+use crate::message::Message;
+
+/// Largest page a caller can ask for
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// A cursor that wasn't returned by a previous page
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid cursor")]
+pub struct InvalidCursor;
+
+/// One page of results and the cursor for the next one, `None` on the last page
+#[derive(Debug, Clone, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_cursor: Option<String>,
+}
+
+fn encode_cursor(position: &str) -> String {
+    URL_SAFE_NO_PAD.encode(position)
+}
+
+fn decode_cursor(cursor: &str) -> Result<String, InvalidCursor> {
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| InvalidCursor)?;
+    String::from_utf8(bytes).map_err(|_| InvalidCursor)
+}
+
+/// The page size to read: at most [`MAX_PAGE_SIZE`] when asked for, everything otherwise
+fn page_size(limit: Option<usize>) -> usize {
+    match limit {
+        Some(limit) => limit.clamp(1, MAX_PAGE_SIZE),
+        None => usize::MAX,
+    }
+}
+
+/// The session files history is read from: those in the read replica when configured, and
+/// those in the session directory the replica doesn't have yet
+pub fn history_sessions() -> Result<Vec<(String, PathBuf)>> {
+    let mut sessions = match read_replica_dir() {
+        Some(replica_dir) => list_sessions_in(&replica_dir)?,
+        None => Vec::new(),
+    };
+    let replicated: HashSet<String> = sessions.iter().map(|(id, _)| id.clone()).collect();
+    sessions.extend(
+        list_sessions_in(&ensure_session_dir()?)?
+            .into_iter()
+            .filter(|(id, _)| !replicated.contains(id)),
+    );
+    Ok(sessions)
+}
+
+/// The file to read a session's history from, preferring the read replica
+pub fn history_path(session_id: &str) -> Result<PathBuf> {
+    let primary = get_path(Identifier::Name(session_id.to_string()))?;
+    if let Some(replica_dir) = read_replica_dir() {
+        let replica = replica_dir.join(format!("{}.jsonl", session_id));
+        if replica.exists() {
+            return Ok(replica);
+        }
+    }
+    Ok(primary)
+}
+
+/// Sessions from most to least recently modified. The cursor holds the modification time and
+/// id of the last session returned, so sessions modified in the meantime move to the front
+/// instead of shifting later pages.
+pub fn list_sessions_page(cursor: Option<&str>, limit: Option<usize>) -> Result<Page<SessionInfo>> {
+    list_sessions_page_in(history_sessions()?, cursor, limit)
+}
+
+fn list_sessions_page_in(
+    sessions: Vec<(String, PathBuf)>,
+    cursor: Option<&str>,
+    limit: Option<usize>,
+) -> Result<Page<SessionInfo>> {
+    let after = cursor
+        .map(|cursor| {
+            let position = decode_cursor(cursor)?;
+            let (modified, id) = position.split_once(':').ok_or(InvalidCursor)?;
+            let modified: u128 = modified.parse().map_err(|_| InvalidCursor)?;
+            Ok::<_, InvalidCursor>((modified, id.to_string()))
+        })
+        .transpose()?;
+
+    // Only modification times are needed to order the sessions; metadata is read for the
+    // page alone
+    let mut sessions: Vec<(u128, String, PathBuf)> = sessions
+        .into_iter()
+        .filter_map(|(id, path)| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+            let modified = modified
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()?
+                .as_nanos();
+            Some((modified, id, path))
+        })
+        .collect();
+    sessions.sort_by(|a, b| (b.0, &b.1).cmp(&(a.0, &a.1)));
+
+    let limit = page_size(limit);
+    let mut remaining = sessions
+        .into_iter()
+        .filter(|(modified, id, _)| match &after {
+            Some(after) => (*modified, id) < (after.0, &after.1),
+            None => true,
+        })
+        .peekable();
+
+    let mut items = Vec::new();
+    let mut last = None;
+    while items.len() < limit {
+        let Some((modified, id, path)) = remaining.next() else {
+            break;
+        };
+        last = Some(format!("{}:{}", modified, id));
+        if let Some(info) = session_info(id, &path) {
+            items.push(info);
+        }
+    }
+
+    let next_cursor = match remaining.peek() {
+        Some(_) => last.as_deref().map(encode_cursor),
+        None => None,
+    };
+    Ok(Page { items, next_cursor })
+}
+
+/// A session's metadata and a page of its messages, oldest first. The cursor is the position
+/// of the next message; a session rewritten by context management since may return fewer.
+pub fn read_messages_page(
+    session_id: &str,
+    cursor: Option<&str>,
+    limit: Option<usize>,
+) -> Result<(SessionMetadata, Page<Message>)> {
+    read_messages_page_at(&history_path(session_id)?, cursor, limit)
+}
+
+fn read_messages_page_at(
+    path: &Path,
+    cursor: Option<&str>,
+    limit: Option<usize>,
+) -> Result<(SessionMetadata, Page<Message>)> {
+    let offset = match cursor {
+        Some(cursor) => decode_cursor(cursor)?
+            .parse::<usize>()
+            .map_err(|_| InvalidCursor)?,
+        None => 0,
+    };
+    if !path.exists() {
+        return Err(anyhow!("Session not found"));
+    }
+
+    let metadata = read_history_metadata(path)?;
+    let limit = page_size(limit);
+    let (items, next) = read_messages_range(path, offset, limit)?;
+    let next_cursor = next.map(|next| encode_cursor(&next.to_string()));
+    Ok((metadata, Page { items, next_cursor }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::storage::save_messages_with_metadata;
+    use std::time::Duration;
+
+    #[test]
+    fn test_sessions_and_messages_page_through_cursors() {
+        let dir = tempfile::tempdir().unwrap();
+        for (i, id) in ["a", "b", "c"].iter().enumerate() {
+            let path = dir.path().join(format!("{}.jsonl", id));
+            let messages: Vec<Message> = (0..=i)
+                .map(|n| Message::user().with_text(format!("{} {}", id, n)))
+                .collect();
+            save_messages_with_metadata(&path, &SessionMetadata::default(), &messages).unwrap();
+            // Distinct modification times, "c" most recent
+            std::thread::sleep(Duration::from_millis(20));
+        }
+
+        let sessions = || list_sessions_in(dir.path()).unwrap();
+        let first = list_sessions_page_in(sessions(), None, Some(2)).unwrap();
+        let ids: Vec<_> = first.items.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["c", "b"]);
+        let second =
+            list_sessions_page_in(sessions(), first.next_cursor.as_deref(), Some(2)).unwrap();
+        let ids: Vec<_> = second.items.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["a"]);
+        assert!(second.next_cursor.is_none());
+
+        let path = dir.path().join("c.jsonl");
+        let (_, page) = read_messages_page_at(&path, None, Some(2)).unwrap();
+        assert_eq!(page.items.len(), 2);
+        let (_, rest) = read_messages_page_at(&path, page.next_cursor.as_deref(), Some(2)).unwrap();
+        assert_eq!(rest.items[0].as_concat_text(), "c 2");
+        assert!(rest.next_cursor.is_none());
+
+        assert!(read_messages_page_at(&path, Some("not a cursor"), None).is_err());
+    }
+}
@@ -2,6 +2,7 @@ use crate::session::{self, SessionMetadata};
 use anyhow::Result;
 use serde::Serialize;
 use std::cmp::Ordering;
+use std::path::Path;
 use utoipa::ToSchema;
 
 #[derive(Clone, Serialize, ToSchema)]
@@ -18,6 +19,28 @@ pub enum SortOrder {
     Descending,
 }
 
+/// The listing entry for a session file, or `None` if it can't be read
+pub(crate) fn session_info(id: String, path: &Path) -> Option<SessionInfo> {
+    let modified = path
+        .metadata()
+        .and_then(|m| m.modified())
+        .map(|time| {
+            chrono::DateTime::<chrono::Utc>::from(time)
+                .format("%Y-%m-%d %H:%M:%S UTC")
+                .to_string()
+        })
+        .ok()?;
+
+    let metadata = session::storage::read_history_metadata(path).ok()?;
+
+    Some(SessionInfo {
+        id,
+        path: path.to_string_lossy().to_string(),
+        modified,
+        metadata,
+    })
+}
+
 pub fn get_valid_sorted_sessions(sort_order: SortOrder) -> Result<Vec<SessionInfo>> {
     let sessions = match session::list_sessions() {
         Ok(sessions) => sessions,
@@ -28,26 +51,7 @@ pub fn get_valid_sorted_sessions(sort_order: SortOrder) -> Result<Vec<SessionInf
     };
     let mut session_infos: Vec<SessionInfo> = sessions
         .into_iter()
-        .filter_map(|(id, path)| {
-            let modified = path
-                .metadata()
-                .and_then(|m| m.modified())
-                .map(|time| {
-                    chrono::DateTime::<chrono::Utc>::from(time)
-                        .format("%Y-%m-%d %H:%M:%S UTC")
-                        .to_string()
-                })
-                .ok()?;
-
-            let metadata = session::read_metadata(&path).ok()?;
-
-            Some(SessionInfo {
-                id,
-                path: path.to_string_lossy().to_string(),
-                modified,
-                metadata,
-            })
-        })
+        .filter_map(|(id, path)| session_info(id, &path))
         .collect();
 
     // Sort sessions by modified date
@@ -13,7 +13,7 @@ use etcetera::{choose_app_strategy, AppStrategy, AppStrategyArgs};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::io::{self, BufRead, Write};
+use std::io::{self, BufRead, Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use utoipa::ToSchema;
@@ -22,6 +22,13 @@ use utoipa::ToSchema;
 const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB
 const MAX_MESSAGE_COUNT: usize = 5000;
 const MAX_LINE_LENGTH: usize = 1024 * 1024; // 1MB per line
+const MAX_DECOMPRESSED_SIZE: u64 = 20 * MAX_FILE_SIZE; // limit for compressed transcripts
+
+/// Magic number that starts every zstd frame
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+/// Messages per zstd frame in a compressed transcript, so a damaged frame loses only its chunk
+const TRANSCRIPT_CHUNK_MESSAGES: usize = 64;
+const DEFAULT_COMPRESSION_LEVEL: i32 = 3;
 
 fn get_home_dir() -> PathBuf {
     choose_app_strategy(crate::config::APP_STRATEGY.clone())
@@ -54,6 +61,9 @@ pub struct SessionMetadata {
     pub accumulated_input_tokens: Option<i32>,
     /// The number of output tokens used in the session. Accumulated across all messages.
     pub accumulated_output_tokens: Option<i32>,
+    /// The sampling seed provider requests were made with, so the session can be replayed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<u64>,
 }
 
 // Custom deserializer to handle old sessions without working_dir
@@ -74,6 +84,7 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: Option<i32>,
             accumulated_output_tokens: Option<i32>,
             working_dir: Option<PathBuf>,
+            seed: Option<u64>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -95,6 +106,7 @@ impl<'de> Deserialize<'de> for SessionMetadata {
             accumulated_input_tokens: helper.accumulated_input_tokens,
             accumulated_output_tokens: helper.accumulated_output_tokens,
             working_dir,
+            seed: helper.seed,
         })
     }
 }
@@ -119,6 +131,7 @@ impl SessionMetadata {
             accumulated_total_tokens: None,
             accumulated_input_tokens: None,
             accumulated_output_tokens: None,
+            seed: None,
         }
     }
 }
@@ -324,6 +337,31 @@ pub fn ensure_session_dir() -> Result<PathBuf> {
     Ok(data_dir)
 }
 
+/// A read-only copy of the session directory to serve history reads from, set with
+/// `GOOSE_SESSION_READ_REPLICA_DIR`, so listing and paging through history doesn't contend
+/// with sessions being written. It may lag behind the session directory.
+pub fn read_replica_dir() -> Option<PathBuf> {
+    crate::config::Config::global()
+        .get_param::<String>("GOOSE_SESSION_READ_REPLICA_DIR")
+        .ok()
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_dir())
+}
+
+/// Validate a session file that history is read from, which like [`get_path`] must be in the
+/// session directory, or else in the [`read_replica_dir`]. Only for reads that never write.
+pub fn get_history_path(path: PathBuf) -> Result<PathBuf> {
+    if let Some(replica_dir) = read_replica_dir() {
+        if validate_path_within_session_dir(&path, &replica_dir)? {
+            if path.extension().is_none_or(|ext| ext != "jsonl") {
+                return Err(anyhow::anyhow!("Invalid file extension"));
+            }
+            return Ok(path);
+        }
+    }
+    get_path(Identifier::Path(path))
+}
+
 /// Get the path to the most recently modified session file
 pub fn get_most_recent_session() -> Result<PathBuf> {
     let session_dir = ensure_session_dir()?;
@@ -353,8 +391,12 @@ pub fn get_most_recent_session() -> Result<PathBuf> {
 
 /// List all available session files
 pub fn list_sessions() -> Result<Vec<(String, PathBuf)>> {
-    let session_dir = ensure_session_dir()?;
-    let entries = fs::read_dir(&session_dir)?
+    list_sessions_in(&ensure_session_dir()?)
+}
+
+/// List the session files in a session directory or a replica of one
+pub fn list_sessions_in(session_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let entries = fs::read_dir(session_dir)?
         .filter_map(|entry| {
             let entry = entry.ok()?;
             let path = entry.path();
@@ -401,6 +443,50 @@ pub fn read_messages(session_file: &Path) -> Result<Vec<Message>> {
     result
 }
 
+/// Read up to `limit` messages starting at message `offset`, without deserializing the ones
+/// before it. Unlike [`read_messages`] this never creates or repairs the file, so it can read
+/// from a read-only replica; lines that fail to parse are skipped but still count towards
+/// `offset`. Returns the messages and the offset to continue from, if more follow.
+pub fn read_messages_range(
+    session_file: &Path,
+    offset: usize,
+    limit: usize,
+) -> Result<(Vec<Message>, Option<usize>)> {
+    let secure_path = get_history_path(session_file.to_path_buf())?;
+    if fs::metadata(&secure_path)?.len() > MAX_FILE_SIZE {
+        tracing::warn!("Session file exceeds size limit during ranged read");
+        return Err(anyhow::anyhow!("Session file too large"));
+    }
+
+    let reader = open_session_reader(fs::File::open(&secure_path)?)?;
+    let mut lines = reader.lines().peekable();
+    if let Some(Ok(first)) = lines.peek() {
+        if serde_json::from_str::<SessionMetadata>(first).is_ok() {
+            lines.next();
+        }
+    }
+
+    let mut messages = Vec::new();
+    for (line_number, line) in lines.enumerate().skip(offset) {
+        let line = line?;
+        if messages.len() == limit {
+            return Ok((messages, Some(line_number)));
+        }
+        if line.len() > MAX_LINE_LENGTH {
+            tracing::warn!(
+                "Skipping message {} over the line length limit",
+                line_number
+            );
+            continue;
+        }
+        match parse_message_with_truncation(&line, Some(50000)) {
+            Ok(message) => messages.push(message),
+            Err(e) => tracing::warn!("Skipping unreadable message {}: {}", line_number, e),
+        }
+    }
+    Ok((messages, None))
+}
+
 /// Read messages from a session file with optional content truncation and corruption recovery
 ///
 /// Creates the file if it doesn't exist, reads and deserializes all messages if it does.
@@ -450,7 +536,7 @@ pub fn read_messages_with_truncation(
         .truncate(false)
         .open(session_file)?;
 
-    let reader = io::BufReader::new(file);
+    let reader = open_session_reader(file)?;
     let mut lines = reader.lines();
     let mut messages = Vec::new();
     let mut corrupted_lines = Vec::new();
@@ -980,6 +1066,131 @@ fn truncate_json_string(json_str: &str, max_content_size: usize) -> String {
     result
 }
 
+/// Wrap a session file in a line reader, decompressing it first if it was written compressed
+fn open_session_reader(file: fs::File) -> Result<Box<dyn BufRead>> {
+    let mut reader = io::BufReader::new(file);
+    if !reader.fill_buf()?.starts_with(&ZSTD_MAGIC) {
+        return Ok(Box::new(reader));
+    }
+
+    let mut compressed = Vec::new();
+    reader.read_to_end(&mut compressed)?;
+    Ok(Box::new(io::Cursor::new(decompress_frames(&compressed))))
+}
+
+/// Decompress a transcript frame by frame. A damaged frame ends the transcript there
+/// instead of failing the whole read, so the chunks before it are still recovered.
+fn decompress_frames(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut position = 0;
+    while position < data.len() {
+        let frame_size = match zstd::zstd_safe::find_frame_compressed_size(&data[position..]) {
+            Ok(size) => size,
+            Err(code) => {
+                println!(
+                    "[SESSION] Compressed transcript is damaged after {} bytes",
+                    position
+                );
+                tracing::warn!(
+                    "Failed to find zstd frame at byte {}: {}",
+                    position,
+                    zstd::zstd_safe::get_error_name(code)
+                );
+                break;
+            }
+        };
+        let frame = &data[position..position + frame_size];
+        let remaining = MAX_DECOMPRESSED_SIZE.saturating_sub(output.len() as u64);
+        let decoded = zstd::stream::read::Decoder::new(frame)
+            .and_then(|decoder| decoder.take(remaining).read_to_end(&mut output));
+        if let Err(e) = decoded {
+            println!("[SESSION] Failed to decompress transcript chunk: {}", e);
+            tracing::warn!(
+                "Failed to decompress zstd frame at byte {}: {}",
+                position,
+                e
+            );
+            break;
+        }
+        if output.len() as u64 >= MAX_DECOMPRESSED_SIZE {
+            tracing::warn!("Decompressed session transcript exceeds size limit");
+            break;
+        }
+        position += frame_size;
+    }
+    output
+}
+
+/// Compression level for new transcripts, if `GOOSE_SESSION_COMPRESSION` is set to `zstd`
+fn transcript_compression_level() -> Option<i32> {
+    let config = crate::config::Config::global();
+    match config.get_param::<String>("GOOSE_SESSION_COMPRESSION") {
+        Ok(compression) if compression.eq_ignore_ascii_case("zstd") => Some(
+            config
+                .get_param("GOOSE_SESSION_COMPRESSION_LEVEL")
+                .unwrap_or(DEFAULT_COMPRESSION_LEVEL),
+        ),
+        _ => None,
+    }
+}
+
+/// Write the metadata line and messages as JSONL, optionally as zstd frames of
+/// `TRANSCRIPT_CHUNK_MESSAGES` lines each
+fn write_session_lines<W: Write>(
+    writer: &mut W,
+    metadata: &SessionMetadata,
+    messages: &[Message],
+    compression_level: Option<i32>,
+) -> Result<()> {
+    let mut chunk = Vec::new();
+    let mut chunk_lines = 0;
+
+    // Write metadata as the first line
+    serde_json::to_writer(&mut chunk, &metadata).map_err(|e| {
+        tracing::error!("Failed to serialize metadata: {}", e);
+        anyhow::anyhow!("Failed to write session metadata")
+    })?;
+    writeln!(chunk)?;
+
+    // Write all messages with progress tracking
+    for (i, message) in messages.iter().enumerate() {
+        serde_json::to_writer(&mut chunk, &message).map_err(|e| {
+            tracing::error!("Failed to serialize message {}: {}", i, e);
+            anyhow::anyhow!("Failed to write session message")
+        })?;
+        writeln!(chunk)?;
+        chunk_lines += 1;
+
+        if chunk_lines == TRANSCRIPT_CHUNK_MESSAGES || compression_level.is_none() {
+            write_chunk(writer, &chunk, compression_level)?;
+            chunk.clear();
+            chunk_lines = 0;
+        }
+    }
+    if !chunk.is_empty() {
+        write_chunk(writer, &chunk, compression_level)?;
+    }
+    Ok(())
+}
+
+fn write_chunk<W: Write>(
+    writer: &mut W,
+    chunk: &[u8],
+    compression_level: Option<i32>,
+) -> Result<()> {
+    match compression_level {
+        Some(level) => {
+            let frame = zstd::stream::encode_all(chunk, level).map_err(|e| {
+                tracing::error!("Failed to compress session data: {}", e);
+                anyhow::anyhow!("Failed to compress session data")
+            })?;
+            writer.write_all(&frame)?;
+        }
+        None => writer.write_all(chunk)?,
+    }
+    Ok(())
+}
+
 /// Read session metadata from a session file with security validation
 ///
 /// Returns default empty metadata if the file doesn't exist or has no metadata.
@@ -987,23 +1198,32 @@ fn truncate_json_string(json_str: &str, max_content_size: usize) -> String {
 pub fn read_metadata(session_file: &Path) -> Result<SessionMetadata> {
     // Validate the path for security
     let secure_path = get_path(Identifier::Path(session_file.to_path_buf()))?;
+    read_metadata_at(&secure_path)
+}
 
+/// Like [`read_metadata`], for history that may be read from the [`read_replica_dir`]
+pub fn read_history_metadata(session_file: &Path) -> Result<SessionMetadata> {
+    let secure_path = get_history_path(session_file.to_path_buf())?;
+    read_metadata_at(&secure_path)
+}
+
+fn read_metadata_at(secure_path: &Path) -> Result<SessionMetadata> {
     if !secure_path.exists() {
         return Ok(SessionMetadata::default());
     }
 
     // Security check: file size
-    let file_metadata = fs::metadata(&secure_path)?;
+    let file_metadata = fs::metadata(secure_path)?;
     if file_metadata.len() > MAX_FILE_SIZE {
         tracing::warn!("Session file exceeds size limit during metadata read");
         return Err(anyhow::anyhow!("Session file too large"));
     }
 
-    let file = fs::File::open(&secure_path).map_err(|e| {
+    let file = fs::File::open(secure_path).map_err(|e| {
         tracing::error!("Failed to open session file for metadata read: {}", e);
         anyhow::anyhow!("Failed to access session file")
     })?;
-    let mut reader = io::BufReader::new(file);
+    let mut reader = open_session_reader(file)?;
     let mut first_line = String::new();
 
     // Read just the first line
@@ -1108,10 +1328,29 @@ pub async fn persist_messages_with_schedule_id(
 /// - Path validation to prevent directory traversal
 /// - File size and message count limits
 /// - Sanitized error messages to prevent information leakage
+///
+/// When `GOOSE_SESSION_COMPRESSION` is set to `zstd` the transcript is stored compressed;
+/// every reader in this module decompresses it transparently.
 pub fn save_messages_with_metadata(
     session_file: &Path,
     metadata: &SessionMetadata,
     messages: &[Message],
+) -> Result<()> {
+    save_messages_with_compression(
+        session_file,
+        metadata,
+        messages,
+        transcript_compression_level(),
+    )
+}
+
+/// Write messages like [`save_messages_with_metadata`], compressing the transcript as zstd
+/// frames at the given level, or as plain JSONL when no level is given
+fn save_messages_with_compression(
+    session_file: &Path,
+    metadata: &SessionMetadata,
+    messages: &[Message],
+    compression_level: Option<i32>,
 ) -> Result<()> {
     use fs2::FileExt;
 
@@ -1170,22 +1409,7 @@ pub fn save_messages_with_metadata(
     // Write to temporary file
     {
         let mut writer = io::BufWriter::new(&file);
-
-        // Write metadata as the first line
-        serde_json::to_writer(&mut writer, &metadata).map_err(|e| {
-            tracing::error!("Failed to serialize metadata: {}", e);
-            anyhow::anyhow!("Failed to write session metadata")
-        })?;
-        writeln!(writer)?;
-
-        // Write all messages with progress tracking
-        for (i, message) in messages.iter().enumerate() {
-            serde_json::to_writer(&mut writer, &message).map_err(|e| {
-                tracing::error!("Failed to serialize message {}: {}", i, e);
-                anyhow::anyhow!("Failed to write session message")
-            })?;
-            writeln!(writer)?;
-        }
+        write_session_lines(&mut writer, metadata, messages, compression_level)?;
 
         // Ensure all data is written to disk
         writer.flush().map_err(|e| {
@@ -1464,6 +1688,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_compressed_transcript_round_trip() -> Result<()> {
+        let dir = tempdir()?;
+        let file_path = dir.path().join("compressed.jsonl");
+
+        let messages: Vec<Message> = (0..TRANSCRIPT_CHUNK_MESSAGES * 2 + 5)
+            .map(|i| Message::user().with_text(format!("message number {}", i)))
+            .collect();
+        let metadata = SessionMetadata {
+            description: "compressed session".to_string(),
+            ..SessionMetadata::default()
+        };
+        save_messages_with_compression(&file_path, &metadata, &messages, Some(3))?;
+
+        let raw = fs::read(&file_path)?;
+        assert!(raw.starts_with(&ZSTD_MAGIC));
+
+        let read_back = read_messages(&file_path)?;
+        assert_eq!(read_back.len(), messages.len());
+        assert_eq!(
+            read_back.last().unwrap().as_concat_text(),
+            messages.last().unwrap().as_concat_text()
+        );
+        assert_eq!(read_metadata(&file_path)?.description, "compressed session");
+
+        // A damaged trailing frame keeps the chunks before it readable
+        let mut damaged = raw.clone();
+        damaged.truncate(raw.len() - 10);
+        fs::write(&file_path, damaged)?;
+        let recovered = read_messages(&file_path)?;
+        assert_eq!(recovered.len(), TRANSCRIPT_CHUNK_MESSAGES * 2);
+        Ok(())
+    }
+
     #[test]
     fn test_generate_session_id() {
         let id = generate_session_id();
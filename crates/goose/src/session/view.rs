@@ -0,0 +1,281 @@
+//! A session transcript at increasing levels of detail, so a UI can render a long session as
+//! one line per turn and expand turns on demand.
+//!
+//! - [`ZoomLevel::Turns`]: a summary of each turn, from a user prompt up to the next one
+//! - [`ZoomLevel::Messages`]: every message, with tool arguments and output cut short
+//! - [`ZoomLevel::Raw`]: every message exactly as stored
+//!
+//! Each level is computed the first time it is asked for, and the views of recently read
+//! sessions are cached until the session file changes.
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use mcp_core::role::Role;
+use mcp_core::Content;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use super::history::history_path;
+use super::storage::read_messages_range;
+use crate::message::{Message, MessageContent, ToolRequest, ToolResponse};
+
+/// Longest text shown for a prompt, reply, tool argument or tool output before it is cut short
+const PREVIEW_CHARS: usize = 200;
+
+/// Sessions whose views are kept in memory
+const MAX_CACHED_SESSIONS: usize = 16;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ZoomLevel {
+    #[default]
+    Turns,
+    Messages,
+    Raw,
+}
+
+/// One turn of the conversation: a user prompt and everything up to the next prompt
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TurnSummary {
+    pub index: usize,
+    /// Position of the turn's first message in the transcript
+    pub first_message: usize,
+    pub message_count: usize,
+    pub created: i64,
+    pub prompt: String,
+    /// The last thing the assistant said in the turn
+    pub reply: String,
+    /// Tools called during the turn, in order
+    pub tools: Vec<String>,
+}
+
+/// Every zoom level of one transcript, each computed on first use
+#[derive(Debug)]
+pub struct ConversationView {
+    messages: Vec<Message>,
+    turns: OnceLock<Vec<TurnSummary>>,
+    condensed: OnceLock<Vec<Message>>,
+}
+
+impl ConversationView {
+    pub fn new(messages: Vec<Message>) -> Self {
+        Self {
+            messages,
+            turns: OnceLock::new(),
+            condensed: OnceLock::new(),
+        }
+    }
+
+    pub fn turns(&self) -> &[TurnSummary] {
+        self.turns.get_or_init(|| summarize_turns(&self.messages))
+    }
+
+    /// Messages at the given level; [`ZoomLevel::Turns`] has none and returns an empty slice
+    pub fn messages(&self, zoom: ZoomLevel) -> &[Message] {
+        match zoom {
+            ZoomLevel::Turns => &[],
+            ZoomLevel::Messages => self
+                .condensed
+                .get_or_init(|| self.messages.iter().map(condense).collect()),
+            ZoomLevel::Raw => &self.messages,
+        }
+    }
+
+    /// The messages of a single turn at the given level, to expand it
+    pub fn turn_messages(&self, turn: usize, zoom: ZoomLevel) -> Option<&[Message]> {
+        let summary = self.turns().get(turn)?;
+        let range = summary.first_message..summary.first_message + summary.message_count;
+        match zoom {
+            ZoomLevel::Turns => Some(&[]),
+            _ => self.messages(zoom).get(range),
+        }
+    }
+}
+
+/// A user message that starts a turn rather than returning tool results
+fn is_prompt(message: &Message) -> bool {
+    message.role == Role::User && !message.is_tool_response()
+}
+
+fn summarize_turns(messages: &[Message]) -> Vec<TurnSummary> {
+    let mut turns: Vec<TurnSummary> = Vec::new();
+    for (position, message) in messages.iter().enumerate() {
+        if is_prompt(message) || turns.is_empty() {
+            turns.push(TurnSummary {
+                index: turns.len(),
+                first_message: position,
+                message_count: 0,
+                created: message.created,
+                prompt: if is_prompt(message) {
+                    preview(&message.as_concat_text())
+                } else {
+                    String::new()
+                },
+                reply: String::new(),
+                tools: Vec::new(),
+            });
+        }
+        let turn = turns.last_mut().expect("a turn was just started");
+        turn.message_count += 1;
+        if message.role == Role::Assistant {
+            let text = message.as_concat_text();
+            if !text.trim().is_empty() {
+                turn.reply = preview(&text);
+            }
+            turn.tools.extend(
+                message
+                    .content
+                    .iter()
+                    .filter_map(MessageContent::as_tool_request)
+                    .filter_map(|request| request.tool_call.as_ref().ok())
+                    .map(|call| call.name.clone()),
+            );
+        }
+    }
+    turns
+}
+
+fn preview(text: &str) -> String {
+    match text.char_indices().nth(PREVIEW_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+fn condense_value(value: &Value) -> Value {
+    match value {
+        Value::String(text) => Value::String(preview(text)),
+        Value::Array(items) => Value::Array(items.iter().map(condense_value).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, value)| (key.clone(), condense_value(value)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+fn condense_content(content: &Content) -> Content {
+    match content {
+        Content::Text(text) => Content::text(preview(&text.text)),
+        Content::Image(image) => Content::text(format!("[{} image]", image.mime_type)),
+        Content::Resource(_) => Content::text("[embedded resource]"),
+    }
+}
+
+/// A message with its tool payloads cut short
+fn condense(message: &Message) -> Message {
+    let mut condensed = message.clone();
+    for content in &mut condensed.content {
+        match content {
+            MessageContent::ToolRequest(ToolRequest {
+                tool_call: Ok(call),
+                ..
+            }) => {
+                call.arguments = condense_value(&call.arguments);
+            }
+            MessageContent::ToolResponse(ToolResponse {
+                tool_result: Ok(output),
+                ..
+            }) => {
+                *output = output.iter().map(condense_content).collect();
+            }
+            _ => {}
+        }
+    }
+    condensed
+}
+
+/// A session's view, with the modification time and size of the file it was read from
+type CachedView = (SystemTime, u64, Arc<ConversationView>);
+
+/// Views of recently read sessions
+static VIEW_CACHE: Lazy<Mutex<HashMap<PathBuf, CachedView>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The view of a session's transcript, read from its history file
+pub fn session_view(session_id: &str) -> Result<Arc<ConversationView>> {
+    view_at(&history_path(session_id)?)
+}
+
+fn view_at(path: &Path) -> Result<Arc<ConversationView>> {
+    if !path.exists() {
+        return Err(anyhow!("Session not found"));
+    }
+    let metadata = path.metadata()?;
+    let modified = metadata.modified()?;
+    let len = metadata.len();
+
+    let mut cache = VIEW_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((cached_modified, cached_len, view)) = cache.get(path) {
+        if *cached_modified == modified && *cached_len == len {
+            return Ok(Arc::clone(view));
+        }
+    }
+
+    let (messages, _) = read_messages_range(path, 0, usize::MAX)?;
+    let view = Arc::new(ConversationView::new(messages));
+    if cache.len() >= MAX_CACHED_SESSIONS && !cache.contains_key(path) {
+        // Make room by dropping the least recently modified session
+        if let Some(oldest) = cache
+            .iter()
+            .min_by_key(|(_, (modified, _, _))| *modified)
+            .map(|(path, _)| path.clone())
+        {
+            cache.remove(&oldest);
+        }
+    }
+    cache.insert(path.to_path_buf(), (modified, len, Arc::clone(&view)));
+    Ok(view)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_core::tool::ToolCall;
+    use serde_json::json;
+
+    fn transcript() -> Vec<Message> {
+        vec![
+            Message::user().with_text("List the files"),
+            Message::assistant().with_tool_request(
+                "1",
+                Ok(ToolCall::new("developer__shell", json!({"command": "ls"}))),
+            ),
+            Message::user().with_tool_response("1", Ok(vec![Content::text("x".repeat(1000))])),
+            Message::assistant().with_text("There is one file"),
+            Message::user().with_text("Thanks"),
+            Message::assistant().with_text("You're welcome"),
+        ]
+    }
+
+    #[test]
+    fn test_turns_group_tool_calls_under_their_prompt() {
+        let view = ConversationView::new(transcript());
+
+        let turns = view.turns();
+        assert_eq!(turns.len(), 2);
+        assert_eq!(turns[0].prompt, "List the files");
+        assert_eq!(turns[0].reply, "There is one file");
+        assert_eq!(turns[0].tools, ["developer__shell"]);
+        assert_eq!(turns[0].message_count, 4);
+        assert_eq!(turns[1].first_message, 4);
+
+        let expanded = view.turn_messages(0, ZoomLevel::Messages).unwrap();
+        assert_eq!(expanded.len(), 4);
+        let output = expanded[2].content[0].as_tool_response_text().unwrap();
+        assert!(output.chars().count() <= PREVIEW_CHARS + 1);
+
+        let raw = view.turn_messages(0, ZoomLevel::Raw).unwrap();
+        let output = raw[2].content[0].as_tool_response_text().unwrap();
+        assert_eq!(output.len(), 1000);
+        assert!(view.turn_messages(2, ZoomLevel::Raw).is_none());
+    }
+}
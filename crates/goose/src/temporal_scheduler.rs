@@ -546,6 +546,13 @@ impl TemporalScheduler {
             job.id
         );
 
+        // Runs are triggered by the Temporal service, which doesn't know about constraints
+        if job.constraints.is_some() {
+            return Err(SchedulerError::SchedulerInternalError(
+                "Schedule constraints are not supported by the Temporal scheduler".to_string(),
+            ));
+        }
+
         // Normalize the cron expression to ensure it's 6-field format
         let normalized_cron = normalize_cron_expression(&job.cron);
         if normalized_cron != job.cron {
@@ -601,6 +608,7 @@ impl TemporalScheduler {
                         current_session_id: None, // Not provided by Temporal service
                         process_start_time: None, // Not provided by Temporal service
                         execution_mode: tj.execution_mode,
+                        constraints: None,
                     }
                 })
                 .collect();
@@ -14,6 +14,17 @@ static TOKENIZER: OnceCell<Arc<CoreBPE>> = OnceCell::const_new();
 // Cache size limits to prevent unbounded growth
 const MAX_TOKEN_CACHE_SIZE: usize = 10_000;
 
+/// Local prompt size estimation, used to check requests against the model's
+/// context window before they are sent to the provider
+pub trait CountTokens: Send + Sync {
+    /// Count tokens for a piece of text
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Count tokens for a full request: system prompt, messages and tool definitions
+    fn count_chat_tokens(&self, system_prompt: &str, messages: &[Message], tools: &[Tool])
+        -> usize;
+}
+
 /// Async token counter with caching capabilities
 pub struct AsyncTokenCounter {
     tokenizer: Arc<CoreBPE>,
@@ -329,6 +340,36 @@ impl TokenCounter {
     }
 }
 
+impl CountTokens for AsyncTokenCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        AsyncTokenCounter::count_tokens(self, text)
+    }
+
+    fn count_chat_tokens(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> usize {
+        AsyncTokenCounter::count_chat_tokens(self, system_prompt, messages, tools)
+    }
+}
+
+impl CountTokens for TokenCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        TokenCounter::count_tokens(self, text)
+    }
+
+    fn count_chat_tokens(
+        &self,
+        system_prompt: &str,
+        messages: &[Message],
+        tools: &[Tool],
+    ) -> usize {
+        TokenCounter::count_chat_tokens(self, system_prompt, messages, tools)
+    }
+}
+
 /// Get the global tokenizer instance (async version)
 /// Fixed encoding for all tokenization - using o200k_base for GPT-4o and o1 models
 async fn get_tokenizer() -> Result<Arc<CoreBPE>, String> {
@@ -413,6 +454,7 @@ mod tests {
                 content: vec![MessageContent::text(
                     "What's the weather like in San Francisco?",
                 )],
+                finish_reason: None,
             },
             Message {
                 role: Role::Assistant,
@@ -420,11 +462,13 @@ mod tests {
                 content: vec![MessageContent::text(
                     "Looks like it's 60 degrees Fahrenheit in San Francisco.",
                 )],
+                finish_reason: None,
             },
             Message {
                 role: Role::User,
                 created: 2,
                 content: vec![MessageContent::text("How about New York?")],
+                finish_reason: None,
             },
         ];
 
@@ -511,6 +555,7 @@ mod tests {
                 content: vec![MessageContent::text(
                     "What's the weather like in San Francisco?",
                 )],
+                finish_reason: None,
             },
             Message {
                 role: Role::Assistant,
@@ -518,11 +563,13 @@ mod tests {
                 content: vec![MessageContent::text(
                     "Looks like it's 60 degrees Fahrenheit in San Francisco.",
                 )],
+                finish_reason: None,
             },
             Message {
                 role: Role::User,
                 created: 2,
                 content: vec![MessageContent::text("How about New York?")],
+                finish_reason: None,
             },
         ];
 
@@ -686,4 +733,19 @@ mod tests {
             "Longer text should have more tokens"
         );
     }
+
+    #[tokio::test]
+    async fn test_count_tokens_trait_matches_inherent_counts() {
+        let async_counter = AsyncTokenCounter::new().await.unwrap();
+        let messages = vec![Message::user().with_text("How many tokens is this?")];
+
+        let counters: Vec<Box<dyn CountTokens>> =
+            vec![Box::new(TokenCounter::new()), Box::new(async_counter)];
+        for counter in &counters {
+            assert_eq!(
+                counter.count_chat_tokens("system", &messages, &[]),
+                TokenCounter::new().count_chat_tokens("system", &messages, &[])
+            );
+        }
+    }
 }
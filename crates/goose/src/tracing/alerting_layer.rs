@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::mpsc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::Context;
+use tracing_subscriber::Layer;
+
+use crate::outbox::{global_outbox, OutboxEvent, OutboxSink};
+use crate::providers::pool_stats::POOL_EVENT_TARGET;
+
+/// Tracing target for subagent lifecycle events such as `stuck`
+pub const SUBAGENT_EVENT_TARGET: &str = "goose::subagent";
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+const OPSGENIE_ALERTS_URL: &str = "https://api.opsgenie.com/v2/alerts";
+const DEFAULT_DEDUP_WINDOW: Duration = Duration::from_secs(600);
+const DEFAULT_AUTH_FAILURE_THRESHOLD: u64 = 3;
+
+/// The kinds of fleet problems that can be forwarded to an alerting provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertClass {
+    /// A provider keeps rejecting its credentials
+    ProviderAuthFailures,
+    /// A subagent has been processing without making progress
+    SubagentStuck,
+    /// A provider reported that the account is out of quota
+    QuotaExceeded,
+    /// A provider endpoint kept failing and its circuit breaker opened
+    ProviderOutage,
+}
+
+impl AlertClass {
+    fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(json!(name.trim())).ok()
+    }
+}
+
+/// One alert, deduplicated by `dedup_key` on our side and by the alerting provider
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub class: AlertClass,
+    pub dedup_key: String,
+    pub summary: String,
+    pub details: HashMap<String, String>,
+}
+
+/// Where alerts go and which ones are sent.
+///
+/// `PAGERDUTY_ROUTING_KEY` and `OPSGENIE_API_KEY` enable the providers. `GOOSE_ALERT_CLASSES`
+/// is a comma separated list of [`AlertClass`] names to forward (all when unset),
+/// `GOOSE_ALERT_DEDUP_WINDOW` is how many seconds an alert with the same dedup key is held
+/// back for, and `GOOSE_ALERT_AUTH_FAILURE_THRESHOLD` is how many authentication failures in a
+/// row a provider has before it alerts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlertingConfig {
+    pub pagerduty_routing_key: Option<String>,
+    pub opsgenie_api_key: Option<String>,
+    /// Classes to forward, every class when empty
+    pub classes: Vec<AlertClass>,
+    pub dedup_window: Duration,
+    pub auth_failure_threshold: u64,
+}
+
+impl Default for AlertingConfig {
+    fn default() -> Self {
+        Self {
+            pagerduty_routing_key: None,
+            opsgenie_api_key: None,
+            classes: Vec::new(),
+            dedup_window: DEFAULT_DEDUP_WINDOW,
+            auth_failure_threshold: DEFAULT_AUTH_FAILURE_THRESHOLD,
+        }
+    }
+}
+
+impl AlertingConfig {
+    pub fn from_config() -> Self {
+        let config = crate::config::Config::global();
+        Self {
+            pagerduty_routing_key: config.get_secret::<String>("PAGERDUTY_ROUTING_KEY").ok(),
+            opsgenie_api_key: config.get_secret::<String>("OPSGENIE_API_KEY").ok(),
+            classes: config
+                .get_param::<String>("GOOSE_ALERT_CLASSES")
+                .map(|classes| classes.split(',').filter_map(AlertClass::parse).collect())
+                .unwrap_or_default(),
+            dedup_window: config
+                .get_param("GOOSE_ALERT_DEDUP_WINDOW")
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_DEDUP_WINDOW),
+            auth_failure_threshold: config
+                .get_param("GOOSE_ALERT_AUTH_FAILURE_THRESHOLD")
+                .unwrap_or(DEFAULT_AUTH_FAILURE_THRESHOLD),
+        }
+    }
+
+    fn has_destination(&self) -> bool {
+        self.pagerduty_routing_key.is_some() || self.opsgenie_api_key.is_some()
+    }
+
+    fn forwards(&self, class: AlertClass) -> bool {
+        self.classes.is_empty() || self.classes.contains(&class)
+    }
+}
+
+/// Name of the outbox sink alerts are delivered through
+pub const ALERT_SINK: &str = "alerts";
+
+/// Bridges pool and subagent events to PagerDuty and OpsGenie. Events are turned into
+/// [`Alert`]s here and handed to a background task that queues them in the outbox, so logging
+/// never waits on the network and alerts are retried while a provider is unreachable.
+pub struct AlertingLayer {
+    config: AlertingConfig,
+    last_sent: Mutex<HashMap<String, Instant>>,
+    alerts: mpsc::UnboundedSender<Alert>,
+}
+
+impl AlertingLayer {
+    pub fn new(config: AlertingConfig, alerts: mpsc::UnboundedSender<Alert>) -> Self {
+        Self {
+            config,
+            last_sent: Mutex::new(HashMap::new()),
+            alerts,
+        }
+    }
+
+    /// The alert an event raises, if it is one of the alerting classes
+    fn classify(&self, target: &str, fields: &HashMap<String, String>) -> Option<Alert> {
+        let field = |name: &str| fields.get(name).cloned().unwrap_or_default();
+        let alert = match (target, fields.get("event")?.as_str()) {
+            (POOL_EVENT_TARGET, "auth_failure") => {
+                let failures: u64 = field("consecutive_failures").parse().ok()?;
+                if failures < self.config.auth_failure_threshold {
+                    return None;
+                }
+                Alert {
+                    class: AlertClass::ProviderAuthFailures,
+                    dedup_key: format!("goose/provider_auth_failures/{}", field("provider")),
+                    summary: format!(
+                        "Provider {} failed authentication {} times in a row",
+                        field("provider"),
+                        failures
+                    ),
+                    details: fields.clone(),
+                }
+            }
+            (POOL_EVENT_TARGET, "quota_exceeded") => Alert {
+                class: AlertClass::QuotaExceeded,
+                dedup_key: format!("goose/quota_exceeded/{}", field("provider")),
+                summary: format!("Provider {} is out of quota", field("provider")),
+                details: fields.clone(),
+            },
+            (POOL_EVENT_TARGET, "circuit_opened") => Alert {
+                class: AlertClass::ProviderOutage,
+                dedup_key: format!(
+                    "goose/provider_outage/{}/{}",
+                    field("provider"),
+                    field("endpoint")
+                ),
+                summary: format!(
+                    "Provider {} failed {} times in a row, requests are failing over",
+                    field("provider"),
+                    field("consecutive_failures")
+                ),
+                details: fields.clone(),
+            },
+            (SUBAGENT_EVENT_TARGET, "stuck") => Alert {
+                class: AlertClass::SubagentStuck,
+                dedup_key: format!("goose/subagent_stuck/{}", field("subagent_id")),
+                summary: format!(
+                    "Subagent {} has made no progress for {}s",
+                    field("subagent_id"),
+                    field("stalled_secs")
+                ),
+                details: fields.clone(),
+            },
+            _ => return None,
+        };
+        self.config.forwards(alert.class).then_some(alert)
+    }
+
+    /// Whether an alert with this key was already sent within the dedup window
+    fn is_duplicate(&self, dedup_key: &str) -> bool {
+        let mut last_sent = self.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match last_sent.get(dedup_key) {
+            Some(sent) if now.duration_since(*sent) < self.config.dedup_window => true,
+            _ => {
+                last_sent.insert(dedup_key.to_string(), now);
+                false
+            }
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for AlertingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let target = event.metadata().target();
+        if target != POOL_EVENT_TARGET && target != SUBAGENT_EVENT_TARGET {
+            return;
+        }
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        if let Some(alert) = self.classify(target, &visitor.fields) {
+            if !self.is_duplicate(&alert.dedup_key) {
+                let _ = self.alerts.send(alert);
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+struct FieldVisitor {
+    fields: HashMap<String, String>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields
+            .insert(field.name().to_string(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.fields
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+}
+
+/// Posts alerts to the configured alerting providers
+pub struct AlertSender {
+    client: Client,
+    config: AlertingConfig,
+}
+
+impl AlertSender {
+    pub fn new(config: AlertingConfig) -> Self {
+        Self {
+            client: Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .expect("Failed to create HTTP client"),
+            config,
+        }
+    }
+
+    pub async fn send(&self, alert: &Alert) -> Result<(), reqwest::Error> {
+        if let Some(routing_key) = &self.config.pagerduty_routing_key {
+            self.client
+                .post(PAGERDUTY_EVENTS_URL)
+                .json(&json!({
+                    "routing_key": routing_key,
+                    "event_action": "trigger",
+                    "dedup_key": alert.dedup_key,
+                    "payload": {
+                        "summary": alert.summary,
+                        "source": "goose",
+                        "severity": "error",
+                        "class": alert.class,
+                        "custom_details": alert.details,
+                    }
+                }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        if let Some(api_key) = &self.config.opsgenie_api_key {
+            self.client
+                .post(OPSGENIE_ALERTS_URL)
+                .header("Authorization", format!("GenieKey {}", api_key))
+                .json(&json!({
+                    "message": alert.summary,
+                    "alias": alert.dedup_key,
+                    "source": "goose",
+                    "tags": [alert.class],
+                    "details": alert.details,
+                }))
+                .send()
+                .await?
+                .error_for_status()?;
+        }
+        Ok(())
+    }
+
+    fn spawn(self, runtime: &tokio::runtime::Handle, mut alerts: mpsc::UnboundedReceiver<Alert>) {
+        let outbox = global_outbox();
+        outbox.register_sink(ALERT_SINK, Arc::new(self));
+        runtime.spawn(async move {
+            outbox.ensure_dispatcher();
+            while let Some(alert) = alerts.recv().await {
+                let payload = match serde_json::to_value(&alert) {
+                    Ok(payload) => payload,
+                    Err(e) => {
+                        tracing::warn!("Failed to serialize alert {}: {}", alert.dedup_key, e);
+                        continue;
+                    }
+                };
+                if let Err(e) = outbox.enqueue(ALERT_SINK, "alert", payload).await {
+                    // Not on an alerting target, so this doesn't loop back into the layer
+                    tracing::warn!("Failed to queue alert {}: {}", alert.dedup_key, e);
+                }
+            }
+        });
+    }
+}
+
+#[async_trait::async_trait]
+impl OutboxSink for AlertSender {
+    async fn deliver(&self, event: &OutboxEvent) -> anyhow::Result<()> {
+        let alert: Alert = serde_json::from_value(event.payload.clone())?;
+        // Both providers deduplicate on the alert's key, so a retry that resends to the
+        // provider that already accepted it doesn't page twice
+        self.send(&alert).await?;
+        Ok(())
+    }
+}
+
+/// An alerting layer for the configured providers, or `None` when none is configured.
+/// Alerts are sent from a task on the current tokio runtime.
+pub fn create_alerting_layer() -> Option<AlertingLayer> {
+    let config = AlertingConfig::from_config();
+    if !config.has_destination() {
+        return None;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        eprintln!("Alerting is configured but there is no async runtime to send alerts from");
+        return None;
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    AlertSender::new(config.clone()).spawn(&runtime, rx);
+    Some(AlertingLayer::new(config, tx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_events_become_deduplicated_alerts() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let layer = AlertingLayer::new(
+            AlertingConfig {
+                classes: vec![AlertClass::ProviderAuthFailures, AlertClass::SubagentStuck],
+                auth_failure_threshold: 2,
+                ..Default::default()
+            },
+            tx,
+        );
+        let subscriber = tracing_subscriber::registry().with(layer);
+
+        tracing::subscriber::with_default(subscriber, || {
+            for consecutive_failures in 1..=3u32 {
+                tracing::warn!(
+                    target: POOL_EVENT_TARGET,
+                    event = "auth_failure",
+                    provider = "openai",
+                    consecutive_failures,
+                );
+            }
+            // Not a forwarded class
+            tracing::warn!(target: POOL_EVENT_TARGET, event = "quota_exceeded", provider = "openai");
+            tracing::warn!(
+                target: SUBAGENT_EVENT_TARGET,
+                event = "stuck",
+                subagent_id = "sub-1",
+                stalled_secs = 900u64,
+            );
+        });
+
+        let auth = rx.try_recv().unwrap();
+        assert_eq!(auth.class, AlertClass::ProviderAuthFailures);
+        assert_eq!(auth.dedup_key, "goose/provider_auth_failures/openai");
+        assert_eq!(auth.details["consecutive_failures"], "2");
+
+        let stuck = rx.try_recv().unwrap();
+        assert_eq!(stuck.class, AlertClass::SubagentStuck);
+        assert_eq!(
+            stuck.summary,
+            "Subagent sub-1 has made no progress for 900s"
+        );
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod alerting_layer;
 pub mod langfuse_layer;
 mod observation_layer;
 
@@ -0,0 +1,310 @@
+//! Watch mode: run a scheduled recipe again when files it depends on change, e.g. rerun an
+//! "update docs" recipe whenever `src/**/*.rs` changes.
+//!
+//! Watches are polled rather than relying on OS file events, so they behave the same on every
+//! platform and on network filesystems. Scans run on the blocking thread pool and skip hidden
+//! directories and build output such as `target/` and `node_modules/`, which change constantly
+//! and are never sources. Changes are debounced, and runs go through the
+//! scheduler's `run_now`, so a job that is already running is not started twice; changes made
+//! during a run trigger one more run once it finishes.
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::Result;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::config::Config;
+use crate::scheduler_trait::SchedulerTrait;
+
+/// How often watched paths are scanned for changes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Files tracked per watch at most, so a watch on a huge tree can't stall the scanner
+const MAX_WATCHED_FILES: usize = 10_000;
+
+/// Directories never descended into, besides hidden ones such as `.git`
+const SKIPPED_DIRS: &[&str] = &["target", "node_modules"];
+
+fn default_debounce_ms() -> u64 {
+    2_000
+}
+
+/// A scheduled recipe to run when files matching `patterns` under `root` change
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct WatchConfig {
+    /// The scheduled job to run
+    pub schedule_id: String,
+    pub root: PathBuf,
+    /// Globs relative to `root`, e.g. `src/**/*.rs`
+    pub patterns: Vec<String>,
+    /// Quiet period after the last change before the recipe runs
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+}
+
+impl WatchConfig {
+    /// Watches configured in `GOOSE_RECIPE_WATCHES`, none when unset
+    pub fn from_config() -> Vec<Self> {
+        Config::global()
+            .get_param("GOOSE_RECIPE_WATCHES")
+            .unwrap_or_default()
+    }
+}
+
+/// Modification times of the files a watch matches
+type Snapshot = HashMap<PathBuf, SystemTime>;
+
+/// The files a watch tracks: those under its root matching its patterns
+struct Scanner {
+    schedule_id: String,
+    root: PathBuf,
+    matcher: GlobSet,
+}
+
+struct Watch {
+    config: WatchConfig,
+    /// Shared with the blocking task scanning it
+    scanner: Arc<Scanner>,
+    snapshot: Snapshot,
+    /// When the latest change not yet handed to the scheduler was seen
+    changed_at: Option<Instant>,
+}
+
+impl Watch {
+    fn new(config: WatchConfig) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &config.patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        let scanner = Arc::new(Scanner {
+            schedule_id: config.schedule_id.clone(),
+            root: config.root.clone(),
+            matcher: builder.build()?,
+        });
+        let snapshot = scanner.scan();
+        Ok(Self {
+            config,
+            scanner,
+            snapshot,
+            changed_at: None,
+        })
+    }
+
+    #[cfg(test)]
+    fn scan(&self) -> Snapshot {
+        self.scanner.scan()
+    }
+
+    /// Record a new scan, noting when matching files were added, removed or modified
+    fn observe(&mut self, snapshot: Snapshot, now: Instant) {
+        if snapshot != self.snapshot {
+            self.snapshot = snapshot;
+            self.changed_at = Some(now);
+        }
+    }
+
+    /// Whether changes have settled for the debounce period
+    fn is_due(&self, now: Instant) -> bool {
+        self.changed_at.is_some_and(|changed_at| {
+            now.duration_since(changed_at) >= Duration::from_millis(self.config.debounce_ms)
+        })
+    }
+}
+
+impl Scanner {
+    fn scan(&self) -> Snapshot {
+        let mut snapshot = Snapshot::new();
+        let mut dirs = vec![self.root.clone()];
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                let Ok(file_type) = entry.file_type() else {
+                    continue;
+                };
+                if file_type.is_dir() {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if !name.starts_with('.') && !SKIPPED_DIRS.contains(&name.as_str()) {
+                        dirs.push(path);
+                    }
+                    continue;
+                }
+                if !self.matches(&path) {
+                    continue;
+                }
+                if snapshot.len() >= MAX_WATCHED_FILES {
+                    tracing::warn!(
+                        "Watch for {} matches more than {} files, ignoring the rest",
+                        self.schedule_id,
+                        MAX_WATCHED_FILES
+                    );
+                    return snapshot;
+                }
+                if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                    snapshot.insert(path, modified);
+                }
+            }
+        }
+        snapshot
+    }
+
+    fn matches(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.root)
+            .is_ok_and(|relative| self.matcher.is_match(relative))
+    }
+}
+
+/// Polls the configured watches and runs their recipes through the scheduler
+pub struct RecipeWatcher {
+    watches: Vec<Watch>,
+    scheduler: Arc<dyn SchedulerTrait>,
+}
+
+impl RecipeWatcher {
+    pub fn new(configs: Vec<WatchConfig>, scheduler: Arc<dyn SchedulerTrait>) -> Result<Self> {
+        let watches = configs
+            .into_iter()
+            .map(Watch::new)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { watches, scheduler })
+    }
+
+    /// Poll the watches in the background until the returned handle is aborted
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(POLL_INTERVAL);
+            loop {
+                interval.tick().await;
+                self.poll().await;
+            }
+        })
+    }
+
+    async fn poll(&mut self) {
+        let scanners: Vec<Arc<Scanner>> = self
+            .watches
+            .iter()
+            .map(|watch| Arc::clone(&watch.scanner))
+            .collect();
+        let snapshots = match tokio::task::spawn_blocking(move || {
+            scanners
+                .iter()
+                .map(|scanner| scanner.scan())
+                .collect::<Vec<_>>()
+        })
+        .await
+        {
+            Ok(snapshots) => snapshots,
+            Err(e) => {
+                tracing::warn!("Recipe watcher scan failed: {}", e);
+                return;
+            }
+        };
+
+        let now = Instant::now();
+        let mut due = Vec::new();
+        for (index, (watch, snapshot)) in self.watches.iter_mut().zip(snapshots).enumerate() {
+            watch.observe(snapshot, now);
+            if watch.is_due(now) {
+                due.push(index);
+            }
+        }
+        if due.is_empty() {
+            return;
+        }
+
+        let jobs = match self.scheduler.list_scheduled_jobs().await {
+            Ok(jobs) => jobs,
+            Err(e) => {
+                tracing::warn!("Recipe watcher could not list scheduled jobs: {}", e);
+                return;
+            }
+        };
+        for index in due {
+            let watch = &mut self.watches[index];
+            let schedule_id = watch.config.schedule_id.clone();
+            let Some(job) = jobs.iter().find(|job| job.id == schedule_id) else {
+                tracing::warn!("Watched schedule {} does not exist", schedule_id);
+                watch.changed_at = None;
+                continue;
+            };
+            if job.paused {
+                watch.changed_at = None;
+                continue;
+            }
+            // Already running: keep the change pending and run again once the run finishes
+            if job.currently_running {
+                continue;
+            }
+
+            watch.changed_at = None;
+            tracing::info!("Watched files changed, running schedule {}", schedule_id);
+            let scheduler = Arc::clone(&self.scheduler);
+            tokio::spawn(async move {
+                if let Err(e) = scheduler.run_now(&schedule_id).await {
+                    tracing::warn!("Watch run of schedule {} failed: {}", schedule_id, e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(root: &Path) -> WatchConfig {
+        WatchConfig {
+            schedule_id: "update_docs".to_string(),
+            root: root.to_path_buf(),
+            patterns: vec!["src/**/*.rs".to_string()],
+            debounce_ms: 100,
+        }
+    }
+
+    #[test]
+    fn test_changes_to_matching_files_are_debounced() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/agents")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+
+        let mut watch = Watch::new(config(dir.path())).unwrap();
+        assert_eq!(watch.snapshot.len(), 1);
+
+        let start = Instant::now();
+        fs::write(dir.path().join("README.md"), "not watched").unwrap();
+        watch.observe(watch.scan(), start);
+        assert!(watch.changed_at.is_none());
+
+        fs::write(dir.path().join("src/agents/mod.rs"), "").unwrap();
+        watch.observe(watch.scan(), start);
+        assert!(!watch.is_due(start));
+        assert!(watch.is_due(start + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_build_output_is_not_scanned() {
+        let dir = tempfile::tempdir().unwrap();
+        for path in ["src", "target/debug/build", "node_modules/pkg", ".git"] {
+            fs::create_dir_all(dir.path().join(path)).unwrap();
+            fs::write(dir.path().join(path).join("mod.rs"), "").unwrap();
+        }
+
+        let watch = Watch::new(WatchConfig {
+            patterns: vec!["**/*.rs".to_string()],
+            ..config(dir.path())
+        })
+        .unwrap();
+        assert_eq!(
+            watch.snapshot.keys().collect::<Vec<_>>(),
+            vec![&dir.path().join("src/mod.rs")]
+        );
+    }
+}
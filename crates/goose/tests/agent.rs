@@ -139,8 +139,8 @@ async fn run_truncate_test(
             Ok(AgentEvent::McpNotification(n)) => {
                 println!("MCP Notification: {n:?}");
             }
-            Ok(AgentEvent::ModelChange { .. }) => {
-                // Model change events are informational, just continue
+            Ok(AgentEvent::ModelChange { .. }) | Ok(AgentEvent::Usage(_)) => {
+                // Model change and usage events are informational, just continue
             }
 
             Err(e) => {
@@ -733,7 +733,7 @@ mod max_turns_tests {
                     responses.push(response);
                 }
                 Ok(AgentEvent::McpNotification(_)) => {}
-                Ok(AgentEvent::ModelChange { .. }) => {}
+                Ok(AgentEvent::ModelChange { .. }) | Ok(AgentEvent::Usage(_)) => {}
                 Err(e) => {
                     return Err(e);
                 }
@@ -759,3 +759,135 @@ mod max_turns_tests {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod mock_provider_tests {
+    use super::*;
+    use goose::message::MessageContent;
+    use goose::providers::mock::{MockErrorKind, MockProvider};
+    use serde_json::json;
+
+    async fn collect_messages(agent: &Agent, messages: &[Message]) -> Result<Vec<Message>> {
+        let reply_stream = agent.reply(messages, None).await?;
+        tokio::pin!(reply_stream);
+
+        let mut responses = Vec::new();
+        while let Some(event) = reply_stream.next().await {
+            if let AgentEvent::Message(message) = event? {
+                responses.push(message);
+            }
+        }
+        Ok(responses)
+    }
+
+    #[tokio::test]
+    async fn test_agent_runs_scripted_tool_loop() -> Result<()> {
+        let provider = Arc::new(
+            MockProvider::default()
+                .with_tool_call("missing__tool", json!({"path": "README.md"}))
+                .with_text("All done"),
+        );
+        let agent = Agent::new();
+        agent.update_provider(provider.clone()).await?;
+
+        let responses =
+            collect_messages(&agent, &[Message::user().with_text("Read the readme")]).await?;
+
+        assert!(responses[0].is_tool_call());
+        assert!(responses.iter().any(|m| m.is_tool_response()));
+        assert_eq!(responses.last().unwrap().as_concat_text(), "All done");
+
+        // The second request carries the tool call and its (failed) result back to the model
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].messages.iter().any(|m| m.is_tool_response()));
+        assert_eq!(provider.remaining(), 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_agent_surfaces_scripted_context_error() -> Result<()> {
+        let provider = Arc::new(
+            MockProvider::default().with_error(MockErrorKind::ContextLengthExceeded, "too long"),
+        );
+        let agent = Agent::new();
+        agent.update_provider(provider).await?;
+
+        let responses = collect_messages(&agent, &[Message::user().with_text("Hello")]).await?;
+
+        assert!(matches!(
+            responses.last().unwrap().content.first(),
+            Some(MessageContent::ContextLengthExceeded(_))
+        ));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod platform_tool_tests {
+    use super::*;
+    use mcp_core::tool::{Tool, ToolCall};
+    use mcp_core::{Content, ToolError};
+    use serde_json::{json, Value};
+
+    fn ticket_tool(name: &str) -> Tool {
+        Tool::new(
+            name,
+            "Create a ticket in the tracker",
+            json!({
+                "type": "object",
+                "properties": {"title": {"type": "string"}},
+                "required": ["title"]
+            }),
+            None,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_registered_tool_is_listed_and_dispatched() -> Result<()> {
+        let agent = Agent::new();
+        agent
+            .register_platform_tool(
+                ticket_tool("create_ticket"),
+                |arguments: Value| async move {
+                    match arguments.get("title").and_then(|v| v.as_str()) {
+                        Some(title) => {
+                            Ok(vec![Content::text(format!("Created ticket: {}", title))])
+                        }
+                        None => Err(ToolError::InvalidParameters("Missing title".to_string())),
+                    }
+                },
+            )
+            .await?;
+
+        let tools = agent.list_tools(Some("platform".to_string())).await;
+        assert!(tools.iter().any(|tool| tool.name == "create_ticket"));
+
+        let (_, result) = agent
+            .dispatch_tool_call(
+                ToolCall::new("create_ticket", json!({"title": "Fix login"})),
+                "request_id".to_string(),
+            )
+            .await;
+        let content = result.unwrap().result.await.unwrap();
+        assert_eq!(
+            content.first().unwrap().as_text(),
+            Some("Created ticket: Fix login")
+        );
+
+        assert!(agent.unregister_platform_tool("create_ticket").await);
+        assert!(!agent.is_host_tool("create_ticket").await);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejects_extension_style_names() {
+        let agent = Agent::new();
+        let result = agent
+            .register_platform_tool(ticket_tool("jira__create_ticket"), |_: Value| async {
+                Ok(vec![])
+            })
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -362,6 +362,7 @@ impl ScheduleToolTestBuilder {
             current_session_id: None,
             process_start_time: None,
             execution_mode: Some("background".to_string()),
+            constraints: None,
         };
         {
             let mut jobs = self.scheduler.jobs.lock().await;
@@ -410,5 +411,6 @@ pub fn create_test_session_metadata(message_count: usize, working_dir: &str) ->
         accumulated_total_tokens: Some(100),
         accumulated_input_tokens: Some(50),
         accumulated_output_tokens: Some(50),
+        seed: None,
     }
 }
@@ -112,6 +112,15 @@ impl Content {
         })
     }
 
+    /// Embed a value as a JSON resource, for results that are read by code as well as by models
+    pub fn json<S: Into<String>, T: Serialize>(uri: S, value: &T) -> serde_json::Result<Self> {
+        Ok(Content::resource(ResourceContents::TextResourceContents {
+            uri: uri.into(),
+            mime_type: Some("application/json".to_string()),
+            text: serde_json::to_string_pretty(value)?,
+        }))
+    }
+
     /// Get the text content if this is a TextContent variant
     pub fn as_text(&self) -> Option<&str> {
         match self {
@@ -223,6 +232,28 @@ mod tests {
         assert_eq!(content.as_image(), Some(("data", "image/png")));
     }
 
+    #[test]
+    fn test_content_json() {
+        let content = Content::json("goose://test", &serde_json::json!({"a": 1})).unwrap();
+        match content {
+            Content::Resource(EmbeddedResource {
+                resource:
+                    ResourceContents::TextResourceContents {
+                        uri,
+                        mime_type,
+                        text,
+                    },
+                ..
+            }) => {
+                assert_eq!(uri, "goose://test");
+                assert_eq!(mime_type.as_deref(), Some("application/json"));
+                let value: serde_json::Value = serde_json::from_str(&text).unwrap();
+                assert_eq!(value["a"], 1);
+            }
+            _ => panic!("Expected Resource content"),
+        }
+    }
+
     #[test]
     fn test_content_annotations_basic() {
         let content = Content::text("hello")